@@ -31,8 +31,8 @@
 //!     let pod = orchestrator.ensure_ready_pod().await?;
 //!     println!("Pod ready: {} at {}", pod.name, pod.public_ip);
 //!
-//!     if let Some((host, port)) = pod.ssh_endpoint() {
-//!         println!("SSH: ssh -p {} user@{}", port, host);
+//!     if let Some(ssh) = pod.ssh_command("root", None, &[]) {
+//!         println!("SSH: {ssh}");
 //!     }
 //!
 //!     Ok(())
@@ -99,22 +99,241 @@ pub mod runpod_starter;
 /// Use this module to persist pod state and compute idempotent action plans.
 pub mod runpod_state;
 
+/// State store backends outside the local filesystem.
+///
+/// Use this module (features `sqlite`, `redis`, `s3`) to share pod state between hosts.
+#[cfg(any(feature = "sqlite", feature = "redis", feature = "s3"))]
+pub mod runpod_store;
+
 /// GraphQL client for advanced RunPod API operations.
 ///
 /// Use this module for operations not available via REST API.
 pub mod runpod_client;
 
+/// Shared HTTP helpers (dry-run request descriptions).
+///
+/// Use this module to inspect requests captured in dry-run mode.
+pub mod runpod_http;
+
+/// Pod environment templating.
+///
+/// Use this module to expand `${VAR}` references in pod env values.
+pub mod runpod_env;
+
+/// Jupyter Server client for leased pods.
+///
+/// Use this module (feature `jupyter`) to upload notebooks and execute code on a pod's Jupyter
+/// server.
+#[cfg(feature = "jupyter")]
+pub mod runpod_jupyter;
+
+/// Distributed locking between orchestrator processes.
+///
+/// Use this module to stop concurrent orchestrators from creating the same pod twice.
+pub mod runpod_lock;
+
+/// Private networking between managed pods.
+///
+/// Use this module to address pods over the private network and check reachability.
+pub mod runpod_network;
+
+/// Container registry pre-checks.
+///
+/// Use this module to verify an image exists before creating a pod with it.
+pub mod runpod_registry;
+
+/// Serverless endpoint management.
+///
+/// Use this module to read and converge serverless endpoint scaling.
+pub mod runpod_serverless;
+
+/// Serverless job-completion webhook receiver.
+///
+/// Use this module (feature `webhook`) to receive finished jobs as a channel.
+#[cfg(feature = "webhook")]
+pub mod runpod_webhook;
+
+/// Offline, deterministic stand-in for the RunPod API.
+///
+/// Use this module (via `RUNPOD_SIMULATE=true`) to develop without an account.
+pub mod runpod_simulator;
+
+/// Fixture builders for tests.
+///
+/// Use this module (feature `test-util`) to build pods, leases and states in downstream tests.
+#[cfg(feature = "test-util")]
+pub mod runpod_fixtures;
+
+/// Cost tracking and spend reports.
+///
+/// Use this module to estimate per-pod and total spend over a time window.
+pub mod runpod_cost;
+
+/// Pluggable notifications (budget alerts, enforcement).
+///
+/// Use this module to route crate notifications to chat webhooks or your own handler.
+pub mod runpod_notify;
+
+/// Pluggable metrics sink (counters, gauges, histograms).
+///
+/// Use this module to bridge crate metrics to statsd, `CloudWatch`, etc.
+pub mod runpod_metrics;
+
+/// SSH host key management (trust on first use).
+///
+/// Use this module (feature `ssh`) to keep SSH host checking on for pods whose keys change on
+/// every creation.
+#[cfg(feature = "ssh")]
+pub mod runpod_ssh;
+
+/// Incremental directory synchronization to pods over SSH.
+///
+/// Use this module (feature `ssh`) to upload only the files of a local directory that changed.
+#[cfg(feature = "ssh")]
+pub mod runpod_sync;
+
+/// VS Code Remote-SSH / devcontainer export.
+///
+/// Use this module (feature `ssh`) to open a leased pod in VS Code.
+#[cfg(feature = "ssh")]
+pub mod runpod_vscode;
+
+/// Internal diagnostics facade (features `log` / `tracing`).
+mod runpod_log;
+
 /// High-level pod orchestration.
 ///
 /// Use this module for simplified pod management with automatic reconciliation.
 pub mod runpod_orchestrator;
 
+/// Readiness probes.
+///
+/// Use this module to wait until a pod's services really answer, not just their port mappings.
+pub mod runpod_probe;
+
+/// Shared pod pools.
+///
+/// Use this module to let several workers check pods out of a pool and back in.
+pub mod runpod_pool;
+
+/// Batch jobs over a pod pool.
+///
+/// Use this module (feature `ssh`) to run a batch of commands on pool pods over SSH.
+#[cfg(feature = "ssh")]
+pub mod runpod_jobs;
+
+/// Liveness pings of leased pods.
+///
+/// Use this module to notice when a leased pod stops answering, and to re-lease it.
+pub mod runpod_liveness;
+
+/// Scoped pod leases.
+///
+/// Use this module to stop or terminate a pod when its lease handle is dropped.
+pub mod runpod_guard;
+
+/// Lease expiry.
+///
+/// Use this module to terminate pods whose client stopped renewing its lease.
+pub mod runpod_ttl;
+
+/// Provisioning availability history.
+///
+/// Use this module to try chronically unavailable GPU types and data centers last.
+pub mod runpod_availability;
+
+/// Background reconciliation.
+///
+/// Use this module to keep a pod running, stopped or terminated from a supervised loop.
+pub mod runpod_reconciler;
+
+/// Idle-GPU auto-stop.
+///
+/// Use this module to stop a pod whose GPUs have been idle for too long.
+pub mod runpod_idle;
+
+/// Scheduled start/stop windows.
+///
+/// Use this module to run a pod only during cron-style time windows.
+pub mod runpod_schedule;
+
+/// Pod labels and label selectors.
+///
+/// Use this module to label pods and find them by label instead of by name.
+pub mod runpod_labels;
+
 // ============================================================================
 // Re-exports for convenience
 // ============================================================================
 
-pub use runpod_client::{RunpodClient, RunpodClientConfig};
-pub use runpod_orchestrator::{PodLease, RunpodOrchestrator, RunpodOrchestratorConfig};
-pub use runpod_provisioner::{RunpodProvisionConfig, RunpodProvisioner};
+pub use runpod_availability::{
+    AvailabilityError, AvailabilityHistory, AvailabilityTracker, ProvisionOutcome,
+};
+pub use runpod_client::{GpuLowestPrice, RunpodClient, RunpodClientConfig};
+pub use runpod_cost::{
+    BudgetConfig, BudgetMonitor, BudgetStatus, CostLedger, GpuPrice, PriceChange, PriceDiff,
+    PriceSnapshot, SpendCap, SpendCapAction, SpendCapScope, SpendCapStatus, SpendGuard,
+    SpendGuardTask, SpendReport,
+};
+pub use runpod_guard::{LeaseGuard, LeaseReleasePolicy};
+pub use runpod_http::{
+    ApiKey, ApiResource, ApiVersion, BackoffStrategy, DryRunRequest, HttpClientError, HttpOptions,
+    HttpVersion, InvalidHttpEnv, RetryPolicy, TlsPinMismatch,
+};
+pub use runpod_idle::{IdleAction, IdleError, IdleMonitor, IdlePolicy};
+#[cfg(feature = "ssh")]
+pub use runpod_jobs::{Job, JobError, JobFile, JobOutput, JobQueue, JobQueueConfig, JobRun};
+#[cfg(feature = "jupyter")]
+pub use runpod_jupyter::{
+    CellError, CellResult, CellStatus, JupyterClient, JupyterError, JupyterKernel,
+};
+pub use runpod_labels::{LabelError, LabelSelector};
+pub use runpod_liveness::{
+    LeaseSwapHook, LivenessConfig, LivenessError, LivenessPinger, LivenessProbe, ManagedLease,
+};
+pub use runpod_lock::{DistributedLock, FileLock, LockError, MemoryLock};
+pub use runpod_metrics::{MetricsSink, NoopMetrics};
+pub use runpod_network::{ConnectivityCheck, PrivateEndpoint};
+pub use runpod_notify::{Notification, Notifier, Severity, WebhookNotifier};
+pub use runpod_orchestrator::{
+    BenchmarkResult, ClusterLease, ClusterNode, ClusterSpec, CompatibilityCheck,
+    CompatibilityMismatch, CompatibilityReport, ConsideredPod, CostEstimate, DataCenterFailover,
+    ExposureMode, FailoverReason, FleetActionResult, FleetApplyResult, HostBenchmark,
+    ImageUpdateOptions, LeaseAction, LeaseProvenance, OrchestrationPlan, OrchestrationReport,
+    PlannedStep, PodFilter, PodLease, PodOverrides, PodSnapshot, PodStatus, PodTransition,
+    PodUpdate, PortConflict, PreemptionPolicy, Progress, ProgressPhase, ProgressReporter,
+    ReportStep, RunpodOrchestrator, RunpodOrchestratorConfig, StepKind,
+};
+pub use runpod_pool::{PodPool, PodPoolConfig, PoolError, PoolLease};
+pub use runpod_probe::{ReadinessCheck, ReadinessProbe};
+pub use runpod_provisioner::{
+    BidStrategy, GpuFallback, PodDeadline, RunpodProvisionConfig, RunpodProvisioner,
+};
+pub use runpod_reconciler::{ReconcileAction, ReconcilePass, ReconcilerHandle};
+pub use runpod_schedule::{CronExpr, Schedule, ScheduleError};
+pub use runpod_serverless::{
+    CancelResult, EndpointHealth, JobResult, JobStatus, PurgeResult, ScalingSpec, ServerlessClient,
+    ServerlessConfig,
+};
+#[cfg(feature = "ssh")]
+pub use runpod_ssh::{
+    HostKey, HostKeyPolicy, HostKeyStatus, KnownHosts, SshError, SshLogin, SshOutput,
+};
 pub use runpod_starter::{RunpodStarter, RunpodStarterConfig};
-pub use runpod_state::{JsonFileStateStore, PlannedAction, RunPodState, StateStore};
+pub use runpod_state::{
+    ActionOutcome, DeployMode, FleetState, JsonFileStateStore, MemoryStateStore, Plan, PlanReason,
+    PlannedAction, PodUsage, RunPodState, ScaleDownAction, StateStore, StateTransaction,
+    TerminateOutcome, state_store_from_env,
+};
+#[cfg(feature = "redis")]
+pub use runpod_store::RedisStateStore;
+#[cfg(feature = "sqlite")]
+pub use runpod_store::SqliteStateStore;
+#[cfg(feature = "s3")]
+pub use runpod_store::{S3Credentials, S3StateStore};
+#[cfg(feature = "ssh")]
+pub use runpod_sync::{SyncCompare, SyncError, SyncOptions, SyncReport};
+pub use runpod_ttl::{LeaseBook, LeaseHeartbeat, LeaseRecord, LeaseRegistry, LeaseTtlError};
+
+/// Token accepted by `RunpodOrchestrator::ensure_ready_pod_cancellable()`.
+pub use tokio_util::sync::CancellationToken;
@@ -34,9 +34,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  Status: {}", pod.desired_status);
 
     // Show connection info
-    if let Some((host, port)) = pod.ssh_endpoint() {
+    if let Some(ssh) = pod.ssh_command("root", None, &[]) {
         println!("\nSSH connection:");
-        println!("  ssh -p {} user@{}", port, host);
+        println!("  {ssh}");
     }
 
     if let Some(url) = pod.jupyter_endpoint() {
@@ -0,0 +1,516 @@
+//! Provisioning availability history.
+//!
+//! Unique responsibility: remember how provisioning went per GPU type and
+//! data center (created, out of capacity, preempted) and rank later choices
+//! so chronically unavailable combinations are tried last.
+//!
+//! Outcomes fade with a half-life: a combination that ran out of capacity
+//! last week is not penalized forever, while one that failed the last ten
+//! times in a row goes to the back of the list. Combinations without history
+//! keep the order they were configured in.
+//!
+//! The history lives next to the pod state file, or in memory with
+//! `RUNPOD_STATE_BACKEND=memory`. The `sqlite`, `redis` and `s3` backends
+//! only hold the pod state: the history then stays in the file.
+//!
+//! ```ignore
+//! let tracker = Arc::new(AvailabilityTracker::from_env()?);
+//! let orchestrator = RunpodOrchestrator::new(cfg)?.with_availability(tracker.clone());
+//!
+//! // Creations and capacity errors are recorded by the orchestrator;
+//! // preemptions are reported by whoever notices them.
+//! tracker.record("NVIDIA A40", Some("EU-RO-1"), ProvisionOutcome::Preempted, now_unix_ms())?;
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_log::log_warn;
+use crate::runpod_state::{self, JsonFileStateStore, StateStoreError};
+
+/// History file format version.
+const AVAILABILITY_FORMAT_VERSION: u32 = 1;
+
+/// Default half-life of recorded outcomes (one day).
+pub const DEFAULT_HALF_LIFE_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Outcome of a provisioning attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvisionOutcome {
+    /// The pod was created.
+    Success,
+    /// `RunPod` had no capacity for the request.
+    NoCapacity,
+    /// The pod was reclaimed by `RunPod` (spot preemption, host failure).
+    Preempted,
+}
+
+impl ProvisionOutcome {
+    /// Stable lowercase name.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::NoCapacity => "no_capacity",
+            Self::Preempted => "preempted",
+        }
+    }
+
+    /// Whether the outcome counts against the combination.
+    #[must_use]
+    pub const fn is_failure(self) -> bool {
+        !matches!(self, Self::Success)
+    }
+}
+
+/// Recorded outcomes of one GPU type in one data center.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AvailabilityRecord {
+    /// GPU type ID (e.g. "NVIDIA A40").
+    pub gpu_type_id: String,
+    /// Data center ID; `None` when the request was not pinned to one.
+    pub data_center_id: Option<String>,
+    /// Total successful creations.
+    pub successes: u64,
+    /// Total capacity errors.
+    pub no_capacity: u64,
+    /// Total preemptions.
+    pub preemptions: u64,
+    /// Decayed weight of successes as of `updated_ms`.
+    pub success_weight: f64,
+    /// Decayed weight of failures as of `updated_ms`.
+    pub failure_weight: f64,
+    /// Most recent outcome.
+    pub last_outcome: ProvisionOutcome,
+    /// Time of the most recent outcome (ms since epoch).
+    pub updated_ms: u64,
+}
+
+impl AvailabilityRecord {
+    /// Success and failure weights decayed to `now_ms`.
+    fn weights(&self, half_life_ms: u64, now_ms: u64) -> (f64, f64) {
+        let factor = decay(half_life_ms, now_ms.saturating_sub(self.updated_ms));
+        (self.success_weight * factor, self.failure_weight * factor)
+    }
+}
+
+/// Multiplier applied to a weight after `elapsed_ms` (1 when `half_life_ms` is 0).
+fn decay(half_life_ms: u64, elapsed_ms: u64) -> f64 {
+    if half_life_ms == 0 {
+        return 1.0;
+    }
+    #[allow(clippy::cast_precision_loss)] // Millisecond spans stay far below 2^52.
+    let half_lives = elapsed_ms as f64 / half_life_ms as f64;
+    0.5_f64.powf(half_lives)
+}
+
+/// Provisioning outcomes per GPU type / data center.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AvailabilityHistory {
+    /// Format version.
+    pub format_version: u32,
+    /// Time after which a recorded outcome weighs half as much.
+    #[serde(default = "default_half_life_ms")]
+    pub half_life_ms: u64,
+    /// One record per GPU type / data center seen.
+    #[serde(default)]
+    pub records: Vec<AvailabilityRecord>,
+}
+
+const fn default_half_life_ms() -> u64 {
+    DEFAULT_HALF_LIFE_MS
+}
+
+impl Default for AvailabilityHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HALF_LIFE_MS)
+    }
+}
+
+impl AvailabilityHistory {
+    /// Create an empty history.
+    #[must_use]
+    pub const fn new(half_life_ms: u64) -> Self {
+        Self {
+            format_version: AVAILABILITY_FORMAT_VERSION,
+            half_life_ms,
+            records: Vec::new(),
+        }
+    }
+
+    /// Record the outcome of a provisioning attempt.
+    pub fn record(
+        &mut self,
+        gpu_type_id: &str,
+        data_center_id: Option<&str>,
+        outcome: ProvisionOutcome,
+        now_ms: u64,
+    ) {
+        let half_life_ms = self.half_life_ms;
+        let idx = self
+            .records
+            .iter()
+            .position(|r| {
+                r.gpu_type_id == gpu_type_id && r.data_center_id.as_deref() == data_center_id
+            })
+            .unwrap_or_else(|| {
+                self.records.push(AvailabilityRecord {
+                    gpu_type_id: gpu_type_id.to_string(),
+                    data_center_id: data_center_id.map(ToString::to_string),
+                    successes: 0,
+                    no_capacity: 0,
+                    preemptions: 0,
+                    success_weight: 0.0,
+                    failure_weight: 0.0,
+                    last_outcome: outcome,
+                    updated_ms: now_ms,
+                });
+                self.records.len() - 1
+            });
+
+        let record = &mut self.records[idx];
+        let (successes, failures) = record.weights(half_life_ms, now_ms);
+        record.success_weight = successes;
+        record.failure_weight = failures;
+        match outcome {
+            ProvisionOutcome::Success => {
+                record.successes += 1;
+                record.success_weight += 1.0;
+            }
+            ProvisionOutcome::NoCapacity => {
+                record.no_capacity += 1;
+                record.failure_weight += 1.0;
+            }
+            ProvisionOutcome::Preempted => {
+                record.preemptions += 1;
+                record.failure_weight += 1.0;
+            }
+        }
+        record.last_outcome = outcome;
+        record.updated_ms = record.updated_ms.max(now_ms);
+    }
+
+    /// Penalty of a combination, from 0 (no failures) towards 1 (only failures).
+    ///
+    /// Aggregates the records of `gpu_type_ids` (any GPU type when empty) in
+    /// `data_center_id` (any data center when `None`). A single failure
+    /// weighs less than a long streak: the penalty is
+    /// `failures / (failures + successes + 1)` on decayed weights.
+    #[must_use]
+    pub fn penalty(
+        &self,
+        gpu_type_ids: &[String],
+        data_center_id: Option<&str>,
+        now_ms: u64,
+    ) -> f64 {
+        let (successes, failures) = self
+            .records
+            .iter()
+            .filter(|r| gpu_type_ids.is_empty() || gpu_type_ids.contains(&r.gpu_type_id))
+            .filter(|r| data_center_id.is_none() || r.data_center_id.as_deref() == data_center_id)
+            .map(|r| r.weights(self.half_life_ms, now_ms))
+            .fold((0.0, 0.0), |(s, f), (rs, rf)| (s + rs, f + rf));
+        failures / (failures + successes + 1.0)
+    }
+
+    /// Order `gpu_type_ids` by penalty in `data_center_id`, lowest first.
+    ///
+    /// The sort is stable: GPU types with equal penalties (e.g. no history)
+    /// keep their configured order.
+    #[must_use]
+    pub fn rank_gpu_types(
+        &self,
+        gpu_type_ids: &[String],
+        data_center_id: Option<&str>,
+        now_ms: u64,
+    ) -> Vec<String> {
+        rank(gpu_type_ids, |gpu| {
+            self.penalty(std::slice::from_ref(gpu), data_center_id, now_ms)
+        })
+    }
+
+    /// Order `data_center_ids` by penalty for `gpu_type_ids`, lowest first.
+    ///
+    /// The sort is stable: data centers with equal penalties keep their
+    /// configured order.
+    #[must_use]
+    pub fn rank_data_centers(
+        &self,
+        gpu_type_ids: &[String],
+        data_center_ids: &[String],
+        now_ms: u64,
+    ) -> Vec<String> {
+        rank(data_center_ids, |dc| {
+            self.penalty(gpu_type_ids, Some(dc), now_ms)
+        })
+    }
+
+    /// Load a history file (`None` if it does not exist).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, parsed, or has an
+    /// unsupported format version.
+    pub fn load(path: &Path) -> Result<Option<Self>, StateStoreError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let history: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if history.format_version != AVAILABILITY_FORMAT_VERSION {
+            return Err(StateStoreError::InvalidState(
+                "unsupported availability format version",
+            ));
+        }
+        Ok(Some(history))
+    }
+
+    /// Save the history to `path` atomically (readers never see a missing or
+    /// partial file).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<(), StateStoreError> {
+        runpod_state::write_atomic(path, &serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Stable sort of `items` by ascending `penalty`.
+fn rank(items: &[String], penalty: impl Fn(&String) -> f64) -> Vec<String> {
+    let mut scored: Vec<(f64, &String)> = items.iter().map(|i| (penalty(i), i)).collect();
+    scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+    scored.into_iter().map(|(_, i)| i.clone()).collect()
+}
+
+// ============================================================================
+// Tracker
+// ============================================================================
+
+/// Shared, persisted availability history.
+///
+/// Every `record()` reloads the file, applies the outcome and saves it back
+/// holding the file's lock, so several processes sharing the state directory
+/// build one history. `record()` blocks on that file: from async code, call
+/// it through `tokio::task::spawn_blocking`.
+#[derive(Debug)]
+pub struct AvailabilityTracker {
+    history: Mutex<AvailabilityHistory>,
+    path: Option<PathBuf>,
+}
+
+impl AvailabilityTracker {
+    /// Create a tracker that keeps its history in memory only.
+    #[must_use]
+    pub const fn in_memory(half_life_ms: u64) -> Self {
+        Self {
+            history: Mutex::new(AvailabilityHistory::new(half_life_ms)),
+            path: None,
+        }
+    }
+
+    /// Open (or start) the history file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an existing file cannot be loaded.
+    pub fn open(path: impl Into<PathBuf>, half_life_ms: u64) -> Result<Self, StateStoreError> {
+        let path = path.into();
+        let mut history = AvailabilityHistory::load(&path)?.unwrap_or_default();
+        history.half_life_ms = half_life_ms;
+        Ok(Self {
+            history: Mutex::new(history),
+            path: Some(path),
+        })
+    }
+
+    /// Create a tracker on the state backend selected by configuration.
+    ///
+    /// Env: `RUNPOD_STATE_BACKEND` (default: "json"): "json" stores the
+    /// history at `default_path()`, "memory" keeps it in memory. "sqlite",
+    /// "redis" and "s3" also store it at `default_path()`, with a warning.
+    /// Env: `RUNPOD_AVAILABILITY_HALF_LIFE_MS` (default: 86400000)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the env-file or a variable is invalid, the
+    /// backend is unsupported, or the history file cannot be loaded.
+    pub fn from_env() -> Result<Self, AvailabilityError> {
+        runpod_env::load_dotenv().map_err(AvailabilityError::EnvFile)?;
+
+        let key = "RUNPOD_AVAILABILITY_HALF_LIFE_MS";
+        let half_life_ms = runpod_env::u64_var(key, DEFAULT_HALF_LIFE_MS)
+            .map_err(|reason| AvailabilityError::InvalidEnv { key, reason })?;
+
+        let backend = env::var("RUNPOD_STATE_BACKEND").unwrap_or_else(|_| "json".to_string());
+        match backend.trim().to_lowercase().as_str() {
+            "" | "json" => Ok(Self::open(Self::default_path(), half_life_ms)?),
+            "memory" => Ok(Self::in_memory(half_life_ms)),
+            other @ ("sqlite" | "redis" | "s3") => {
+                let path = Self::default_path();
+                log_warn!(
+                    "state backend {other} does not hold the availability history: using {}",
+                    path.display()
+                );
+                Ok(Self::open(path, half_life_ms)?)
+            }
+            other => Err(StateStoreError::UnsupportedBackend(other.to_string()).into()),
+        }
+    }
+
+    /// History file next to the pod state file.
+    ///
+    /// `.runpod_state.json` gives `.runpod_state.availability.json`; the
+    /// state profile (`RUNPOD_STATE_PROFILE`) is kept in the name.
+    #[must_use]
+    pub fn default_path() -> PathBuf {
+        JsonFileStateStore::profile_path(&JsonFileStateStore::default_path(), "availability")
+    }
+
+    /// Path of the history file (`None` in memory).
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Record a provisioning outcome and persist it.
+    ///
+    /// Blocks on the history file (and its lock) when there is one; the
+    /// in-memory history is only locked to read and replace it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the history file cannot be locked, reloaded or
+    /// saved (the outcome is still recorded in memory).
+    pub fn record(
+        &self,
+        gpu_type_id: &str,
+        data_center_id: Option<&str>,
+        outcome: ProvisionOutcome,
+        now_ms: u64,
+    ) -> Result<(), StateStoreError> {
+        let Some(path) = &self.path else {
+            self.lock()
+                .record(gpu_type_id, data_center_id, outcome, now_ms);
+            return Ok(());
+        };
+
+        let mut history = self.snapshot();
+        let mut recorded = false;
+        let saved = runpod_state::with_file_lock(path, || {
+            if let Some(mut stored) = AvailabilityHistory::load(path)? {
+                stored.half_life_ms = history.half_life_ms;
+                history = stored;
+            }
+            history.record(gpu_type_id, data_center_id, outcome, now_ms);
+            recorded = true;
+            history.save(path)
+        });
+        if !recorded {
+            history.record(gpu_type_id, data_center_id, outcome, now_ms);
+        }
+        *self.lock() = history;
+        saved
+    }
+
+    /// Copy of the current history.
+    #[must_use]
+    pub fn snapshot(&self) -> AvailabilityHistory {
+        self.lock().clone()
+    }
+
+    /// See `AvailabilityHistory::rank_gpu_types()`.
+    #[must_use]
+    pub fn rank_gpu_types(
+        &self,
+        gpu_type_ids: &[String],
+        data_center_id: Option<&str>,
+        now_ms: u64,
+    ) -> Vec<String> {
+        self.lock()
+            .rank_gpu_types(gpu_type_ids, data_center_id, now_ms)
+    }
+
+    /// See `AvailabilityHistory::rank_data_centers()`.
+    #[must_use]
+    pub fn rank_data_centers(
+        &self,
+        gpu_type_ids: &[String],
+        data_center_ids: &[String],
+        now_ms: u64,
+    ) -> Vec<String> {
+        self.lock()
+            .rank_data_centers(gpu_type_ids, data_center_ids, now_ms)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, AvailabilityHistory> {
+        self.history.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Run a (blocking) tracker call on Tokio's blocking thread pool.
+///
+/// # Errors
+///
+/// Returns the call's error, or `StateStoreError::Io` if the call panicked.
+pub(crate) async fn run_blocking<T: Send + 'static>(
+    tracker: &Arc<AvailabilityTracker>,
+    call: impl FnOnce(&AvailabilityTracker) -> Result<T, StateStoreError> + Send + 'static,
+) -> Result<T, StateStoreError> {
+    let tracker = Arc::clone(tracker);
+    tokio::task::spawn_blocking(move || call(&tracker))
+        .await
+        .map_err(|e| StateStoreError::Io(io::Error::other(format!("tracker call panicked: {e}"))))?
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// Errors for availability tracking.
+#[derive(Debug)]
+pub enum AvailabilityError {
+    /// Invalid environment variable value.
+    InvalidEnv {
+        /// Variable name.
+        key: &'static str,
+        /// Reason it is invalid.
+        reason: &'static str,
+    },
+    /// The env-file could not be loaded.
+    EnvFile(EnvFileError),
+    /// The history could not be loaded or saved.
+    State(StateStoreError),
+}
+
+impl fmt::Display for AvailabilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
+            Self::EnvFile(e) => write!(f, "{e}"),
+            Self::State(_) => write!(f, "availability history error"),
+        }
+    }
+}
+
+impl std::error::Error for AvailabilityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::EnvFile(e) => std::error::Error::source(e),
+            Self::State(e) => Some(e),
+            Self::InvalidEnv { .. } => None,
+        }
+    }
+}
+
+impl From<StateStoreError> for AvailabilityError {
+    fn from(value: StateStoreError) -> Self {
+        Self::State(value)
+    }
+}
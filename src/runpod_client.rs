@@ -10,36 +10,44 @@
 //! - Pod deployment (on-demand and spot)
 //! - Pod lifecycle (stop, terminate, resume)
 //! - Pod queries (list, get by ID)
-//! - GPU type queries
+//! - GPU type queries (including current spot / on-demand prices)
+//! - Instant Clusters (multi-node create / delete)
 //!
 //! All configuration is loaded from environment variables.
 
-use std::{env, fmt, time::Duration};
+use std::{env, fmt, sync::Arc};
 
 use serde::{Deserialize, Serialize};
 
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_http::{self, ApiKey, HttpOptions, RetryPolicy};
+use crate::runpod_log::{ErrorChain, log_debug};
+use crate::runpod_metrics::{self, MetricsSink};
+use crate::runpod_state::{NOT_FOUND_CODE, TerminateOutcome, is_pod_gone_error};
+
 /// Configuration for the `RunPod` GraphQL client.
 #[derive(Clone, Debug)]
 pub struct RunpodClientConfig {
     /// `RunPod` API key for authentication.
     /// Env: `RUNPOD_API_KEY` (required)
-    pub api_key: String,
+    pub api_key: ApiKey,
 
     /// GraphQL API URL for `RunPod`.
     /// Env: `RUNPOD_GRAPHQL_URL` (default: "<https://api.runpod.io/graphql>")
     pub graphql_url: String,
 
-    /// HTTP request timeout in milliseconds.
+    /// Total HTTP request timeout in milliseconds (0 = none; see also
+    /// `HttpOptions::connect_timeout_ms` and `read_timeout_ms`).
     /// Env: `RUNPOD_HTTP_TIMEOUT_MS` (default: 30000)
     pub timeout_ms: u64,
 
-    /// Maximum number of retry attempts.
-    /// Env: `RUNPOD_HTTP_RETRY_MAX` (default: 3)
-    pub retry_max: u32,
+    /// Retry policy for transient failures.
+    /// Env: `RUNPOD_HTTP_RETRY_*` (see `RetryPolicy`)
+    pub retry: RetryPolicy,
 
-    /// Backoff time between retries in milliseconds.
-    /// Env: `RUNPOD_HTTP_RETRY_BACKOFF_MS` (default: 500)
-    pub retry_backoff_ms: u64,
+    /// Connection pool / keepalive / HTTP version tuning.
+    /// Env: `RUNPOD_HTTP_*` (see `HttpOptions`)
+    pub http: HttpOptions,
 }
 
 impl RunpodClientConfig {
@@ -49,15 +57,21 @@ impl RunpodClientConfig {
     ///
     /// Returns an error if required environment variables are missing or invalid.
     pub fn from_env() -> Result<Self, RunpodClientError> {
-        let _ = dotenvy::dotenv();
+        runpod_env::load_dotenv().map_err(RunpodClientError::EnvFile)?;
 
         Ok(Self {
-            api_key: must_env("RUNPOD_API_KEY")?,
+            api_key: must_env("RUNPOD_API_KEY")?.into(),
             graphql_url: env::var("RUNPOD_GRAPHQL_URL")
                 .unwrap_or_else(|_| "https://api.runpod.io/graphql".to_string()),
             timeout_ms: parse_u64_env("RUNPOD_HTTP_TIMEOUT_MS", 30_000)?,
-            retry_max: parse_u32_env("RUNPOD_HTTP_RETRY_MAX", 3)?,
-            retry_backoff_ms: parse_u64_env("RUNPOD_HTTP_RETRY_BACKOFF_MS", 500)?,
+            retry: RetryPolicy::from_env().map_err(|e| RunpodClientError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?,
+            http: HttpOptions::from_env().map_err(|e| RunpodClientError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?,
         })
     }
 }
@@ -66,6 +80,7 @@ impl RunpodClientConfig {
 pub struct RunpodClient {
     cfg: RunpodClientConfig,
     http: reqwest::Client,
+    metrics: Arc<dyn MetricsSink>,
 }
 
 impl RunpodClient {
@@ -73,14 +88,34 @@ impl RunpodClient {
     ///
     /// # Errors
     ///
-    /// Returns an error if the HTTP client cannot be built.
+    /// Returns an error if the HTTP options are invalid or the HTTP client
+    /// cannot be built.
     pub fn new(cfg: RunpodClientConfig) -> Result<Self, RunpodClientError> {
-        let http = reqwest::Client::builder()
-            .timeout(Duration::from_millis(cfg.timeout_ms))
+        let http = cfg
+            .http
+            .client_builder(cfg.timeout_ms)
+            .map_err(|e| RunpodClientError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?
             .build()
             .map_err(RunpodClientError::Http)?;
 
-        Ok(Self { cfg, http })
+        Ok(Self::new_with_http(cfg, http))
+    }
+
+    /// Create a client sending its requests through `http` (e.g. a client
+    /// shared with other components, see `HttpOptions::shared_client()`).
+    ///
+    /// `cfg.http` options are those of `http`; `cfg.timeout_ms` still applies
+    /// per request.
+    #[must_use]
+    pub fn new_with_http(cfg: RunpodClientConfig, http: reqwest::Client) -> Self {
+        Self {
+            cfg,
+            http,
+            metrics: runpod_metrics::noop(),
+        }
     }
 
     /// Get a reference to the current configuration.
@@ -89,6 +124,13 @@ impl RunpodClient {
         &self.cfg
     }
 
+    /// Report HTTP request and retry metrics to `sink`.
+    #[must_use]
+    pub fn with_metrics(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = sink;
+        self
+    }
+
     /// Deploy an on-demand pod.
     ///
     /// Uses the `podFindAndDeployOnDemand` mutation.
@@ -212,12 +254,13 @@ impl RunpodClient {
 
     /// Terminate a pod (delete it).
     ///
-    /// Uses the `podTerminate` mutation.
+    /// Uses the `podTerminate` mutation. Idempotent: a pod that does not
+    /// exist (anymore) yields `TerminateOutcome::AlreadyGone`.
     ///
     /// # Errors
     ///
     /// Returns an error if the request fails or the server returns an error.
-    pub async fn terminate_pod(&self, pod_id: &str) -> Result<(), RunpodClientError> {
+    pub async fn terminate_pod(&self, pod_id: &str) -> Result<TerminateOutcome, RunpodClientError> {
         let query = r"
             mutation podTerminate($input: PodTerminateInput!) {
                 podTerminate(input: $input)
@@ -227,9 +270,16 @@ impl RunpodClient {
         let variables = serde_json::json!({
             "input": { "podId": pod_id }
         });
-        let _resp: GraphQLResponse<PodTerminateData> = self.execute(query, variables).await?;
-
-        Ok(())
+        match self.execute::<PodTerminateData>(query, variables).await {
+            Ok(_) => Ok(TerminateOutcome::Terminated),
+            Err(RunpodClientError::NotFound(_)) => Ok(TerminateOutcome::AlreadyGone),
+            Err(RunpodClientError::Api { status, body })
+                if is_pod_gone_error(status.as_u16(), &body) =>
+            {
+                Ok(TerminateOutcome::AlreadyGone)
+            }
+            Err(e) => Err(e),
+        }
     }
 
     /// Get a pod by ID.
@@ -325,6 +375,8 @@ impl RunpodClient {
                     memoryInGb
                     secureCloud
                     communityCloud
+                    securePrice
+                    communityPrice
                 }
             }
         ";
@@ -334,14 +386,118 @@ impl RunpodClient {
         Ok(resp.data.map(|d| d.gpuTypes).unwrap_or_default())
     }
 
+    /// Get the current lowest prices of a GPU type (spot minimum bid and
+    /// on-demand price, per GPU per hour).
+    ///
+    /// Uses the `gpuTypes` query with `lowestPrice`. Returns `None` if the
+    /// GPU type is unknown.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the server returns an error.
+    pub async fn gpu_lowest_price(
+        &self,
+        gpu_type_id: &str,
+        gpu_count: u32,
+        secure_cloud: bool,
+    ) -> Result<Option<GpuLowestPrice>, RunpodClientError> {
+        let query = r"
+            query gpuLowestPrice($filter: GpuTypeFilter, $input: GpuLowestPriceInput) {
+                gpuTypes(input: $filter) {
+                    id
+                    lowestPrice(input: $input) {
+                        minimumBidPrice
+                        uninterruptablePrice
+                    }
+                }
+            }
+        ";
+
+        let variables = serde_json::json!({
+            "filter": { "id": gpu_type_id },
+            "input": { "gpuCount": gpu_count, "secureCloud": secure_cloud },
+        });
+
+        let resp: GraphQLResponse<GpuLowestPriceData> = self.execute(query, variables).await?;
+
+        Ok(resp
+            .data
+            .and_then(|d| d.gpuTypes.into_iter().find(|g| g.id == gpu_type_id))
+            .and_then(|g| g.lowestPrice))
+    }
+
+    /// Create an Instant Cluster (several multi-GPU pods on one private network).
+    ///
+    /// Uses the `createCluster` mutation. The member pods are returned in node
+    /// order; node 0 is the primary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the server returns an error.
+    pub async fn create_cluster(
+        &self,
+        input: CreateClusterInput,
+    ) -> Result<ClusterInfo, RunpodClientError> {
+        let query = r"
+            mutation createCluster($input: CreateClusterInput!) {
+                createCluster(input: $input) {
+                    id
+                    name
+                    pods {
+                        id
+                        clusterIp
+                        clusterIdx
+                    }
+                }
+            }
+        ";
+
+        let variables = serde_json::json!({ "input": input });
+        let resp: GraphQLResponse<CreateClusterData> = self.execute(query, variables).await?;
+
+        let mut cluster = resp
+            .data
+            .and_then(|d| d.createCluster)
+            .ok_or(RunpodClientError::EmptyResponse)?;
+        cluster
+            .pods
+            .sort_by_key(|p| p.clusterIdx.unwrap_or(u32::MAX));
+        Ok(cluster)
+    }
+
+    /// Delete an Instant Cluster and all of its pods.
+    ///
+    /// Uses the `deleteCluster` mutation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the server returns an error.
+    pub async fn delete_cluster(&self, cluster_id: &str) -> Result<(), RunpodClientError> {
+        let query = r"
+            mutation deleteCluster($input: DeleteClusterInput!) {
+                deleteCluster(input: $input)
+            }
+        ";
+
+        let variables = serde_json::json!({
+            "input": {
+                "id": cluster_id
+            }
+        });
+
+        let _: GraphQLResponse<serde_json::Value> = self.execute(query, variables).await?;
+        Ok(())
+    }
+
     /// Execute a GraphQL query/mutation with retry logic.
     async fn execute<T: for<'de> Deserialize<'de>>(
         &self,
         query: &str,
         variables: serde_json::Value,
     ) -> Result<GraphQLResponse<T>, RunpodClientError> {
+        // A mutation may have taken effect even if its response was lost.
+        let idempotent = !query.trim_start().starts_with("mutation");
         let mut attempt: u32 = 0;
-        let mut backoff = Duration::from_millis(self.cfg.retry_backoff_ms);
 
         loop {
             attempt = attempt.saturating_add(1);
@@ -351,13 +507,17 @@ impl RunpodClient {
                 "variables": variables
             });
 
-            let send_res = self
-                .http
-                .post(&self.cfg.graphql_url)
-                .bearer_auth(&self.cfg.api_key)
+            let sent_at = std::time::Instant::now();
+            let req = self.http.post(&self.cfg.graphql_url);
+            let send_res = runpod_http::with_timeout(req, self.cfg.timeout_ms)
+                .bearer_auth(self.cfg.api_key.expose())
                 .json(&body)
                 .send()
                 .await;
+            self.record_request(
+                sent_at,
+                send_res.as_ref().ok().map(reqwest::Response::status),
+            );
 
             match send_res {
                 Ok(resp) => {
@@ -366,9 +526,12 @@ impl RunpodClient {
                     if !status.is_success() {
                         let body_text = resp.text().await.unwrap_or_default();
 
-                        if attempt <= self.cfg.retry_max && is_retryable_status(status) {
-                            tokio::time::sleep(backoff).await;
-                            backoff = next_backoff(backoff);
+                        if self.cfg.retry.retries_status(attempt, status, idempotent) {
+                            log_debug!(
+                                "graphql request returned {status}, retrying (attempt {attempt})"
+                            );
+                            runpod_http::record_retry(self.metrics.as_ref(), "client");
+                            tokio::time::sleep(self.cfg.retry.delay(attempt)).await;
                             continue;
                         }
 
@@ -378,10 +541,9 @@ impl RunpodClient {
                         });
                     }
 
-                    let gql_resp: GraphQLResponse<T> = resp
-                        .json()
-                        .await
-                        .map_err(|e| RunpodClientError::Json(e.to_string()))?;
+                    let body_text = resp.text().await.map_err(RunpodClientError::Http)?;
+                    let gql_resp: GraphQLResponse<T> =
+                        serde_json::from_str(&body_text).map_err(RunpodClientError::Json)?;
 
                     // Check for GraphQL errors
                     if let Some(errors) = &gql_resp.errors
@@ -392,15 +554,26 @@ impl RunpodClient {
                             .map(|e| e.message.as_str())
                             .collect::<Vec<_>>()
                             .join("; ");
+                        let not_found = errors.iter().all(|e| {
+                            e.extensions.as_ref().and_then(|x| x.code.as_deref())
+                                == Some(NOT_FOUND_CODE)
+                        });
+                        if not_found {
+                            return Err(RunpodClientError::NotFound(msg));
+                        }
                         return Err(RunpodClientError::GraphQL(msg));
                     }
 
                     return Ok(gql_resp);
                 }
                 Err(e) => {
-                    if attempt <= self.cfg.retry_max && is_retryable_reqwest(&e) {
-                        tokio::time::sleep(backoff).await;
-                        backoff = next_backoff(backoff);
+                    if self.cfg.retry.retries_error(attempt, &e, idempotent) {
+                        log_debug!(
+                            "graphql request failed: {e}, retrying (attempt {attempt})",
+                            e = ErrorChain(&e)
+                        );
+                        runpod_http::record_retry(self.metrics.as_ref(), "client");
+                        tokio::time::sleep(self.cfg.retry.delay(attempt)).await;
                         continue;
                     }
 
@@ -409,6 +582,20 @@ impl RunpodClient {
             }
         }
     }
+
+    fn record_request(&self, sent_at: std::time::Instant, status: Option<reqwest::StatusCode>) {
+        let status = status.map_or_else(|| "error".to_string(), |s| s.as_u16().to_string());
+        self.metrics.counter(
+            runpod_metrics::HTTP_REQUESTS,
+            1,
+            &[("component", "client"), ("status", &status)],
+        );
+        self.metrics.histogram(
+            runpod_metrics::HTTP_REQUEST_MS,
+            runpod_metrics::elapsed_ms(sent_at),
+            &[("component", "client")],
+        );
+    }
 }
 
 // ============================================================================
@@ -462,8 +649,8 @@ pub struct DeployPodInput {
     pub startJupyter: Option<bool>,
 }
 
-/// Environment variable for pod.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Environment variable for pod (`Debug` hides the value).
+#[derive(Clone, Serialize, Deserialize)]
 pub struct EnvVar {
     /// Variable key.
     pub key: String,
@@ -471,6 +658,15 @@ pub struct EnvVar {
     pub value: String,
 }
 
+impl fmt::Debug for EnvVar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnvVar")
+            .field("key", &self.key)
+            .field("value", &runpod_http::REDACTED)
+            .finish()
+    }
+}
+
 /// Result from pod deployment.
 #[derive(Debug, Clone, Deserialize)]
 #[allow(non_snake_case)]
@@ -574,6 +770,64 @@ pub struct GpuInfo {
     pub memoryUtilPercent: Option<f32>,
 }
 
+/// Input for creating an Instant Cluster.
+#[derive(Debug, Clone, Serialize)]
+#[allow(non_snake_case)]
+pub struct CreateClusterInput {
+    /// Cluster name.
+    pub clusterName: String,
+    /// GPU type ID (e.g., "NVIDIA H100 80GB HBM3").
+    pub gpuTypeId: String,
+    /// Number of nodes (pods).
+    pub podCount: u32,
+    /// GPUs per node.
+    pub gpuCountPerPod: u32,
+    /// Cluster type ("TRAINING" | "INFERENCE").
+    #[serde(rename = "type")]
+    pub cluster_type: String,
+    /// Container image name.
+    pub imageName: String,
+    /// Container disk size in GB (per node).
+    pub containerDiskInGb: u32,
+    /// Volume size in GB (per node).
+    pub volumeInGb: u32,
+    /// Volume mount path.
+    pub volumeMountPath: String,
+    /// Exposed ports (format: "22/tcp,8888/http").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ports: Option<String>,
+    /// Environment variables (same on every node).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<Vec<EnvVar>>,
+    /// Network volume ID (mounted on every node).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub networkVolumeId: Option<String>,
+}
+
+/// A created Instant Cluster.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClusterInfo {
+    /// Cluster ID.
+    pub id: String,
+    /// Cluster name.
+    pub name: Option<String>,
+    /// Member pods, in node order.
+    #[serde(default)]
+    pub pods: Vec<ClusterPod>,
+}
+
+/// A member pod of an Instant Cluster.
+#[derive(Debug, Clone, Deserialize)]
+#[allow(non_snake_case)]
+pub struct ClusterPod {
+    /// Pod ID.
+    pub id: String,
+    /// Address on the cluster's private network.
+    pub clusterIp: Option<String>,
+    /// Node index (0 = primary).
+    pub clusterIdx: Option<u32>,
+}
+
 /// GPU type information.
 #[derive(Debug, Clone, Deserialize)]
 #[allow(non_snake_case)]
@@ -588,6 +842,36 @@ pub struct GpuType {
     pub secureCloud: Option<bool>,
     /// Available in community cloud.
     pub communityCloud: Option<bool>,
+    /// On-demand price per GPU per hour in secure cloud (USD).
+    #[serde(default)]
+    pub securePrice: Option<f64>,
+    /// On-demand price per GPU per hour in community cloud (USD).
+    #[serde(default)]
+    pub communityPrice: Option<f64>,
+}
+
+impl GpuType {
+    /// Price per GPU per hour for the given cloud type ("SECURE" | "COMMUNITY").
+    #[must_use]
+    pub const fn price_for(&self, cloud_type: &str) -> Option<f64> {
+        if cloud_type.eq_ignore_ascii_case("COMMUNITY") {
+            self.communityPrice
+        } else {
+            self.securePrice
+        }
+    }
+}
+
+/// Current lowest prices of a GPU type (see `gpu_lowest_price()`).
+#[derive(Debug, Clone, Deserialize)]
+#[allow(non_snake_case)]
+pub struct GpuLowestPrice {
+    /// Minimum spot bid per GPU per hour (USD).
+    #[serde(default)]
+    pub minimumBidPrice: Option<f64>,
+    /// On-demand price per GPU per hour (USD).
+    #[serde(default)]
+    pub uninterruptablePrice: Option<f64>,
 }
 
 // ============================================================================
@@ -603,6 +887,12 @@ struct GraphQLResponse<T> {
 #[derive(Debug, Deserialize)]
 struct GraphQLError {
     message: String,
+    extensions: Option<GraphQLErrorExtensions>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLErrorExtensions {
+    code: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -651,12 +941,32 @@ struct MyselfInfo {
     pods: Vec<PodSummary>,
 }
 
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct CreateClusterData {
+    createCluster: Option<ClusterInfo>,
+}
+
 #[derive(Debug, Deserialize)]
 #[allow(non_snake_case)]
 struct GpuTypesData {
     gpuTypes: Vec<GpuType>,
 }
 
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct GpuLowestPriceData {
+    gpuTypes: Vec<GpuLowestPriceType>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct GpuLowestPriceType {
+    id: String,
+    #[serde(default)]
+    lowestPrice: Option<GpuLowestPrice>,
+}
+
 // ============================================================================
 // Error type
 // ============================================================================
@@ -676,9 +986,11 @@ pub enum RunpodClientError {
     /// HTTP client error.
     Http(reqwest::Error),
     /// JSON parsing error.
-    Json(String),
+    Json(serde_json::Error),
     /// GraphQL error from server.
     GraphQL(String),
+    /// GraphQL errors all carrying the `NOT_FOUND` error code.
+    NotFound(String),
     /// API error response.
     Api {
         /// HTTP status code.
@@ -688,6 +1000,8 @@ pub enum RunpodClientError {
     },
     /// Empty response from server.
     EmptyResponse,
+    /// The env file (`RUNPOD_ENV_FILE` / `RUNPOD_POD_ENV_FILE`) could not be loaded.
+    EnvFile(EnvFileError),
 }
 
 impl fmt::Display for RunpodClientError {
@@ -695,18 +1009,29 @@ impl fmt::Display for RunpodClientError {
         match self {
             Self::MissingEnv(k) => write!(f, "missing required env var: {k}"),
             Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
-            Self::Http(e) => write!(f, "http error: {e}"),
-            Self::Json(e) => write!(f, "json error: {e}"),
+            Self::Http(e) => crate::runpod_http::fmt_http_error(f, e),
+            Self::Json(_) => write!(f, "invalid json response"),
             Self::GraphQL(e) => write!(f, "graphql error: {e}"),
+            Self::NotFound(e) => write!(f, "not found: {e}"),
             Self::Api { status, body } => {
                 write!(f, "api error: status={status}, body={body}")
             }
             Self::EmptyResponse => write!(f, "empty response from server"),
+            Self::EnvFile(e) => write!(f, "{e}"),
         }
     }
 }
 
-impl std::error::Error for RunpodClientError {}
+impl std::error::Error for RunpodClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(e) => Some(e),
+            Self::Json(e) => Some(e),
+            Self::EnvFile(e) => std::error::Error::source(e),
+            _ => None,
+        }
+    }
+}
 
 // ============================================================================
 // Helper functions
@@ -716,45 +1041,7 @@ fn must_env(key: &'static str) -> Result<String, RunpodClientError> {
     env::var(key).map_err(|_| RunpodClientError::MissingEnv(key))
 }
 
-fn parse_u32_env(key: &'static str, default: u32) -> Result<u32, RunpodClientError> {
-    env::var(key).map_or_else(
-        |_| Ok(default),
-        |v| {
-            v.parse::<u32>().map_err(|_| RunpodClientError::InvalidEnv {
-                key,
-                reason: "expected an unsigned integer",
-            })
-        },
-    )
-}
-
 fn parse_u64_env(key: &'static str, default: u64) -> Result<u64, RunpodClientError> {
-    env::var(key).map_or_else(
-        |_| Ok(default),
-        |v| {
-            v.parse::<u64>().map_err(|_| RunpodClientError::InvalidEnv {
-                key,
-                reason: "expected an unsigned integer",
-            })
-        },
-    )
-}
-
-#[inline]
-const fn is_retryable_status(status: reqwest::StatusCode) -> bool {
-    matches!(
-        status.as_u16(),
-        408 | 409 | 425 | 429 | 500 | 502 | 503 | 504
-    )
-}
-
-#[inline]
-fn is_retryable_reqwest(e: &reqwest::Error) -> bool {
-    e.is_timeout() || e.is_connect() || e.is_request()
-}
-
-#[inline]
-fn next_backoff(current: Duration) -> Duration {
-    let next = current.saturating_mul(2);
-    next.min(Duration::from_secs(10))
+    runpod_env::u64_var(key, default)
+        .map_err(|reason| RunpodClientError::InvalidEnv { key, reason })
 }
@@ -0,0 +1,1474 @@
+//! Cost tracking.
+//!
+//! Unique responsibility: turn pod observations into uptime intervals and
+//! price them into spend reports over a time window.
+//!
+//! `RunPod` bills running pods by the second; this module reconstructs that
+//! from what the crate already observes (state snapshots, fleet states),
+//! which is enough for teams without access to the billing console. Figures
+//! are estimates: uptime between two observations is attributed to the
+//! status seen at the first one.
+//!
+//! The ledger is serializable, so it can be persisted next to the state
+//! file and reloaded across runs.
+//!
+//! `BudgetMonitor` watches the ledger against a daily or monthly budget
+//! (`RUNPOD_BUDGET_*`), notifies at 50/80/100% and, in enforcement mode,
+//! stops every non-exempt running pod once the budget is spent.
+//!
+//! `SpendGuard` is the hard cap (`RUNPOD_MAX_SPEND_USD`, per day or per
+//! pod): installed on an orchestrator, it refuses pod creations once the cap
+//! is reached and stops (or terminates) the pods running over it.
+//!
+//! `PriceSnapshot` captures the `gpuTypes` prices and availability as
+//! timestamped JSON/CSV; `diff()` lists what changed between two captures.
+//!
+//! ```ignore
+//! let mut ledger = CostLedger::new();
+//! // After each reconciliation:
+//! ledger.observe_fleet(&fleet);
+//!
+//! let now = now_unix_ms();
+//! let report = ledger.report(now - 24 * 3_600_000, now);
+//! println!("last 24h: ${:.2}", report.total_usd);
+//! std::fs::write("spend.json", report.to_json()?)?;
+//!
+//! let mut budget = BudgetMonitor::new(BudgetConfig::from_env()?, notifier);
+//! let status = budget.check_and_enforce(&ledger, &orchestrator, now).await;
+//!
+//! let snapshot = PriceSnapshot::capture(&client).await?;
+//! std::fs::write(snapshot.file_name("json"), snapshot.to_json()?)?;
+//! for change in &previous.diff(&snapshot).changes {
+//!     println!("{change}");
+//! }
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::runpod_client::{GpuType, RunpodClient, RunpodClientError};
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_log::{ErrorChain, log_warn};
+use crate::runpod_notify::{self, Notification, Notifier, Severity};
+use crate::runpod_orchestrator::{PodInfo, PodStatus, RunpodOrchestrator};
+use crate::runpod_provisioner::format_rfc3339;
+use crate::runpod_state::{
+    FleetState, PodDesiredStatus, RemotePodSnapshot, RunPodState, now_unix_ms,
+};
+
+/// Milliseconds per hour.
+const HOUR_MS: f64 = 3_600_000.0;
+
+/// Milliseconds per day.
+const DAY_MS: u64 = 86_400_000;
+
+/// Hourly price of a pod: GPU price for `cloud_type` times `gpu_count`.
+///
+/// Returns `None` if the GPU type is unknown or has no price in that cloud.
+#[must_use]
+pub fn hourly_price(
+    gpu_types: &[GpuType],
+    gpu_type_id: &str,
+    cloud_type: &str,
+    gpu_count: u32,
+) -> Option<f64> {
+    gpu_types
+        .iter()
+        .find(|g| g.id == gpu_type_id)
+        .and_then(|g| g.price_for(cloud_type))
+        .map(|price| price * f64::from(gpu_count))
+}
+
+/// A closed period during which a pod was running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UptimeInterval {
+    /// Start (ms since UNIX epoch).
+    pub start_ms: u64,
+    /// End (ms since UNIX epoch, exclusive).
+    pub end_ms: u64,
+}
+
+impl UptimeInterval {
+    /// Milliseconds of this interval that fall inside `[from_ms, to_ms)`.
+    #[must_use]
+    pub fn overlap_ms(&self, from_ms: u64, to_ms: u64) -> u64 {
+        self.end_ms
+            .min(to_ms)
+            .saturating_sub(self.start_ms.max(from_ms))
+    }
+}
+
+/// Uptime history of one logical pod.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PodUsage {
+    /// Last known pod ID.
+    pub pod_id: Option<String>,
+    /// Hourly price in USD (last known).
+    pub hourly_usd: Option<f64>,
+    /// Closed running intervals, oldest first.
+    pub intervals: Vec<UptimeInterval>,
+    /// Start of the current running interval, if the pod was last seen running.
+    pub running_since_ms: Option<u64>,
+    /// Timestamp of the last observation.
+    pub last_observed_ms: u64,
+}
+
+impl PodUsage {
+    /// Running milliseconds inside `[from_ms, to_ms)`.
+    ///
+    /// A pod still running counts as running until `to_ms`.
+    #[must_use]
+    pub fn running_ms(&self, from_ms: u64, to_ms: u64) -> u64 {
+        let closed: u64 = self
+            .intervals
+            .iter()
+            .map(|i| i.overlap_ms(from_ms, to_ms))
+            .sum();
+        let open = self.running_since_ms.map_or(0, |start| {
+            UptimeInterval {
+                start_ms: start,
+                end_ms: to_ms,
+            }
+            .overlap_ms(from_ms, to_ms)
+        });
+        closed.saturating_add(open)
+    }
+
+    fn observe(&mut self, pod_id: &str, running: bool, at_ms: u64, hourly_usd: Option<f64>) {
+        // Out-of-order observations would corrupt the intervals.
+        if at_ms < self.last_observed_ms {
+            return;
+        }
+        self.last_observed_ms = at_ms;
+        self.pod_id = Some(pod_id.to_string());
+        if hourly_usd.is_some() {
+            self.hourly_usd = hourly_usd;
+        }
+
+        match (self.running_since_ms, running) {
+            (None, true) => self.running_since_ms = Some(at_ms),
+            (Some(start_ms), false) => {
+                self.intervals.push(UptimeInterval {
+                    start_ms,
+                    end_ms: at_ms,
+                });
+                self.running_since_ms = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Uptime ledger for a set of pods, keyed by logical pod name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostLedger {
+    /// Usage by logical pod name.
+    pub pods: BTreeMap<String, PodUsage>,
+}
+
+impl CostLedger {
+    /// Create an empty ledger.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            pods: BTreeMap::new(),
+        }
+    }
+
+    /// Record one observation of a pod.
+    ///
+    /// `hourly_usd` updates the pod's price when set; observations older
+    /// than the last one recorded for the pod are ignored.
+    pub fn observe(&mut self, snapshot: &RemotePodSnapshot, hourly_usd: Option<f64>) {
+        self.pods.entry(snapshot.name.clone()).or_default().observe(
+            snapshot.id.as_str(),
+            snapshot.desired_status == PodDesiredStatus::Running,
+            snapshot.observed_at_ms,
+            hourly_usd,
+        );
+    }
+
+    /// Record a pod as gone (terminated or not found) at `at_ms`.
+    pub fn observe_gone(&mut self, pod_name: &str, at_ms: u64) {
+        if let Some(usage) = self.pods.get_mut(pod_name) {
+            let pod_id = usage.pod_id.clone().unwrap_or_default();
+            usage.observe(&pod_id, false, at_ms, None);
+        }
+    }
+
+    /// Record the last observation of a pod state, priced with its
+    /// `policy.hourly_cost_usd`.
+    pub fn observe_state(&mut self, state: &RunPodState) {
+        match &state.last_remote {
+            Some(snapshot) => self.observe(snapshot, state.policy.hourly_cost_usd),
+            // No snapshot: the pod was not found (or never observed).
+            None => self.observe_gone(
+                &state.pod_name,
+                state.last_reconciled_ms.unwrap_or(state.last_updated_ms),
+            ),
+        }
+    }
+
+    /// Record the last observation of every pod of a fleet.
+    pub fn observe_fleet(&mut self, fleet: &FleetState) {
+        for pod in fleet.groups.values().flat_map(|g| &g.pods) {
+            self.observe_state(pod);
+        }
+    }
+
+    /// Drop intervals that ended before `before_ms` (bounds the ledger size).
+    pub fn prune_before(&mut self, before_ms: u64) {
+        for usage in self.pods.values_mut() {
+            usage.intervals.retain(|i| i.end_ms >= before_ms);
+        }
+    }
+
+    /// Spend per pod and in total over `[from_ms, to_ms)`.
+    #[must_use]
+    pub fn report(&self, from_ms: u64, to_ms: u64) -> SpendReport {
+        let pods: Vec<PodSpend> = self
+            .pods
+            .iter()
+            .map(|(name, usage)| {
+                #[allow(clippy::cast_precision_loss)]
+                let running_hours = usage.running_ms(from_ms, to_ms) as f64 / HOUR_MS;
+                PodSpend {
+                    pod_name: name.clone(),
+                    pod_id: usage.pod_id.clone(),
+                    running_hours,
+                    hourly_usd: usage.hourly_usd,
+                    spend_usd: usage.hourly_usd.map(|rate| rate * running_hours),
+                }
+            })
+            .filter(|p| p.running_hours > 0.0)
+            .collect();
+
+        SpendReport {
+            from_ms,
+            to_ms,
+            total_usd: pods.iter().filter_map(|p| p.spend_usd).sum(),
+            total_running_hours: pods.iter().map(|p| p.running_hours).sum(),
+            unpriced_pods: pods
+                .iter()
+                .filter(|p| p.spend_usd.is_none())
+                .map(|p| p.pod_name.clone())
+                .collect(),
+            pods,
+        }
+    }
+}
+
+/// Spend of one pod over a report window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PodSpend {
+    /// Logical pod name.
+    pub pod_name: String,
+    /// Last known pod ID.
+    pub pod_id: Option<String>,
+    /// Hours spent running inside the window.
+    pub running_hours: f64,
+    /// Hourly price in USD, if known.
+    pub hourly_usd: Option<f64>,
+    /// Estimated spend in USD (`None` without a price).
+    pub spend_usd: Option<f64>,
+}
+
+/// Per-pod and aggregate spend over a time window.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpendReport {
+    /// Window start (ms since UNIX epoch).
+    pub from_ms: u64,
+    /// Window end (ms since UNIX epoch, exclusive).
+    pub to_ms: u64,
+    /// Pods that ran inside the window.
+    pub pods: Vec<PodSpend>,
+    /// Total estimated spend in USD (priced pods only).
+    pub total_usd: f64,
+    /// Total running hours across pods.
+    pub total_running_hours: f64,
+    /// Pods that ran without a known price (not counted in `total_usd`).
+    pub unpriced_pods: Vec<String>,
+}
+
+impl SpendReport {
+    /// Serialize the report as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+// ============================================================================
+// Budget alerts
+// ============================================================================
+
+/// Period a budget applies to (UTC calendar boundaries).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    /// From 00:00 UTC today.
+    #[default]
+    Daily,
+    /// From the 1st of the current month, 00:00 UTC.
+    Monthly,
+}
+
+impl BudgetPeriod {
+    /// Parse "daily" or "monthly" (case-insensitive).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "daily" | "day" => Some(Self::Daily),
+            "monthly" | "month" => Some(Self::Monthly),
+            _ => None,
+        }
+    }
+
+    /// Start (ms since UNIX epoch) of the period containing `now_ms`.
+    #[must_use]
+    pub const fn start_ms(self, now_ms: u64) -> u64 {
+        let days = now_ms / DAY_MS;
+        match self {
+            Self::Daily => days * DAY_MS,
+            Self::Monthly => (days + 1 - day_of_month(days)) * DAY_MS,
+        }
+    }
+}
+
+/// Day of the month (1-31) of a day count since 1970-01-01.
+const fn day_of_month(days: u64) -> u64 {
+    // Civil date from days (H. Hinnant's algorithm), day part only.
+    let doe = (days + 719_468) % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    doy - (153 * mp + 2) / 5 + 1
+}
+
+/// Budget configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetConfig {
+    /// Budget in USD for one period.
+    /// Env: `RUNPOD_BUDGET_USD` (required)
+    pub budget_usd: f64,
+
+    /// Period the budget applies to.
+    /// Env: `RUNPOD_BUDGET_PERIOD` (default: "daily")
+    /// Options: "daily", "monthly"
+    pub period: BudgetPeriod,
+
+    /// Percentages of the budget that trigger an alert, ascending.
+    /// Env: `RUNPOD_BUDGET_ALERT_PERCENTS` (default: "50,80,100")
+    pub alert_percents: Vec<u32>,
+
+    /// Stop every non-exempt running pod once 100% is reached.
+    /// Env: `RUNPOD_BUDGET_ENFORCE` (default: false)
+    pub enforce: bool,
+
+    /// Logical pod names never stopped by enforcement.
+    /// Env: `RUNPOD_BUDGET_EXEMPT_PODS` (optional, comma-separated)
+    pub exempt_pods: Vec<String>,
+}
+
+impl BudgetConfig {
+    /// Load configuration from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if required environment variables are missing or invalid.
+    pub fn from_env() -> Result<Self, CostError> {
+        runpod_env::load_dotenv().map_err(CostError::EnvFile)?;
+
+        let budget_usd = env::var("RUNPOD_BUDGET_USD")
+            .map_err(|_| CostError::MissingEnv("RUNPOD_BUDGET_USD"))?
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|b| b.is_finite() && *b > 0.0)
+            .ok_or(CostError::InvalidEnv {
+                key: "RUNPOD_BUDGET_USD",
+                reason: "expected a positive number",
+            })?;
+
+        let period = env::var("RUNPOD_BUDGET_PERIOD").map_or(Ok(BudgetPeriod::Daily), |v| {
+            BudgetPeriod::parse(&v).ok_or(CostError::InvalidEnv {
+                key: "RUNPOD_BUDGET_PERIOD",
+                reason: "expected daily or monthly",
+            })
+        })?;
+
+        let mut alert_percents = split_csv_env("RUNPOD_BUDGET_ALERT_PERCENTS", "50,80,100")
+            .iter()
+            .map(|p| p.parse::<u32>().ok().filter(|p| *p > 0))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(CostError::InvalidEnv {
+                key: "RUNPOD_BUDGET_ALERT_PERCENTS",
+                reason: "expected comma-separated positive integers",
+            })?;
+        alert_percents.sort_unstable();
+        alert_percents.dedup();
+
+        Ok(Self {
+            budget_usd,
+            period,
+            alert_percents,
+            enforce: parse_bool_env("RUNPOD_BUDGET_ENFORCE", false)?,
+            exempt_pods: split_csv_env("RUNPOD_BUDGET_EXEMPT_PODS", ""),
+        })
+    }
+}
+
+/// A budget threshold crossed during the current period.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BudgetAlert {
+    /// Threshold crossed, in percent of the budget.
+    pub percent: u32,
+    /// Spend so far in the period (USD).
+    pub spend_usd: f64,
+    /// Budget for the period (USD).
+    pub budget_usd: f64,
+    /// Budget period.
+    pub period: BudgetPeriod,
+}
+
+/// Result of one budget check.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BudgetStatus {
+    /// Start of the current period (ms since UNIX epoch).
+    pub period_start_ms: u64,
+    /// Spend so far in the period (USD).
+    pub spend_usd: f64,
+    /// Budget for the period (USD).
+    pub budget_usd: f64,
+    /// Alert raised by this check, if a new threshold was crossed.
+    pub alert: Option<BudgetAlert>,
+    /// Pods (logical names) enforcement should stop: running, not exempt,
+    /// budget spent. Always empty when enforcement is off.
+    pub to_stop: Vec<String>,
+}
+
+impl BudgetStatus {
+    /// Spend as a percentage of the budget.
+    #[must_use]
+    pub fn percent_used(&self) -> f64 {
+        self.spend_usd / self.budget_usd * 100.0
+    }
+}
+
+/// Watches a `CostLedger` against a budget and notifies on thresholds.
+///
+/// Each threshold fires once per period; when several are crossed between
+/// two checks only the highest is notified.
+pub struct BudgetMonitor {
+    cfg: BudgetConfig,
+    notifier: Arc<dyn Notifier>,
+    period_start_ms: u64,
+    fired: BTreeSet<u32>,
+}
+
+impl BudgetMonitor {
+    /// Create a monitor reporting to `notifier`.
+    #[must_use]
+    pub fn new(cfg: BudgetConfig, notifier: Arc<dyn Notifier>) -> Self {
+        Self {
+            cfg,
+            notifier,
+            period_start_ms: 0,
+            fired: BTreeSet::new(),
+        }
+    }
+
+    /// Create a monitor that only reports through the returned statuses.
+    #[must_use]
+    pub fn without_notifier(cfg: BudgetConfig) -> Self {
+        Self::new(cfg, runpod_notify::noop())
+    }
+
+    /// Get a reference to the current configuration.
+    #[must_use]
+    pub const fn config(&self) -> &BudgetConfig {
+        &self.cfg
+    }
+
+    /// Compare the period's spend with the budget, notifying new thresholds.
+    pub fn check(&mut self, ledger: &CostLedger, now_ms: u64) -> BudgetStatus {
+        let period_start_ms = self.cfg.period.start_ms(now_ms);
+        if period_start_ms != self.period_start_ms {
+            self.period_start_ms = period_start_ms;
+            self.fired.clear();
+        }
+
+        let spend_usd = ledger.report(period_start_ms, now_ms).total_usd;
+        let percent_used = spend_usd / self.cfg.budget_usd * 100.0;
+
+        let crossed: Vec<u32> = self
+            .cfg
+            .alert_percents
+            .iter()
+            .copied()
+            .filter(|p| f64::from(*p) <= percent_used && !self.fired.contains(p))
+            .collect();
+        self.fired.extend(&crossed);
+        let alert = crossed.last().map(|&percent| BudgetAlert {
+            percent,
+            spend_usd,
+            budget_usd: self.cfg.budget_usd,
+            period: self.cfg.period,
+        });
+
+        if let Some(alert) = &alert {
+            self.notifier.notify(&Notification {
+                severity: match alert.percent {
+                    p if p >= 100 => Severity::Critical,
+                    p if p >= 80 => Severity::Warning,
+                    _ => Severity::Info,
+                },
+                kind: "budget_threshold",
+                message: format!(
+                    "{:?} budget {}% reached: ${spend_usd:.2} of ${:.2}",
+                    self.cfg.period, alert.percent, self.cfg.budget_usd
+                ),
+                at_ms: now_ms,
+            });
+        }
+
+        let to_stop = if self.cfg.enforce && percent_used >= 100.0 {
+            ledger
+                .pods
+                .iter()
+                .filter(|(name, usage)| {
+                    usage.running_since_ms.is_some() && !self.cfg.exempt_pods.contains(name)
+                })
+                .map(|(name, _)| name.clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        BudgetStatus {
+            period_start_ms,
+            spend_usd,
+            budget_usd: self.cfg.budget_usd,
+            alert,
+            to_stop,
+        }
+    }
+
+    /// Run `check()`, then stop the pods it selected for enforcement.
+    ///
+    /// Each pod's status is read first: pods the ledger still counts as
+    /// running but that are already stopped (or gone) are left alone.
+    /// Stopped pods are notified as one `Critical` notification; failures
+    /// are notified too and retried on the next check.
+    pub async fn check_and_enforce(
+        &mut self,
+        ledger: &CostLedger,
+        orchestrator: &RunpodOrchestrator,
+        now_ms: u64,
+    ) -> BudgetStatus {
+        let status = self.check(ledger, now_ms);
+
+        let mut stopped = Vec::new();
+        for name in &status.to_stop {
+            let Some(pod_id) = ledger.pods.get(name).and_then(|u| u.pod_id.as_deref()) else {
+                continue;
+            };
+            let stop = match orchestrator.pod_status(pod_id).await {
+                Ok(PodStatus::Running | PodStatus::Starting) => orchestrator.stop_pod(pod_id).await,
+                Ok(PodStatus::Created | PodStatus::Exited | PodStatus::Terminated) => continue,
+                Err(e) => Err(e),
+            };
+            match stop {
+                Ok(()) => stopped.push(name.as_str()),
+                Err(e) => self.notifier.notify(&Notification {
+                    severity: Severity::Critical,
+                    kind: "budget_enforcement_failed",
+                    message: format!("budget spent but pod {name} could not be stopped: {e}"),
+                    at_ms: now_ms,
+                }),
+            }
+        }
+
+        if !stopped.is_empty() {
+            self.notifier.notify(&Notification {
+                severity: Severity::Critical,
+                kind: "budget_enforced",
+                message: format!(
+                    "{:?} budget of ${:.2} spent: stopped {}",
+                    self.cfg.period,
+                    self.cfg.budget_usd,
+                    stopped.join(", ")
+                ),
+                at_ms: now_ms,
+            });
+        }
+
+        status
+    }
+}
+
+impl fmt::Debug for BudgetMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BudgetMonitor")
+            .field("cfg", &self.cfg)
+            .field("period_start_ms", &self.period_start_ms)
+            .field("fired", &self.fired)
+            .finish_non_exhaustive()
+    }
+}
+
+// ============================================================================
+// Spend caps
+// ============================================================================
+
+/// What a `SpendCap` is measured against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpendCapScope {
+    /// Spend of all pods since 00:00 UTC.
+    #[default]
+    Daily,
+    /// Lifetime spend of each pod name (across re-creations).
+    Pod,
+}
+
+impl SpendCapScope {
+    /// Parse "daily" or "pod" (case-insensitive).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "daily" | "day" => Some(Self::Daily),
+            "pod" | "per-pod" => Some(Self::Pod),
+            _ => None,
+        }
+    }
+
+    /// Scope name (as parsed).
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Pod => "pod",
+        }
+    }
+}
+
+/// What happens to running pods once a `SpendCap` is reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpendCapAction {
+    /// Stop the pods (they can be restarted, their volumes are kept).
+    #[default]
+    Stop,
+    /// Terminate the pods.
+    Terminate,
+}
+
+impl SpendCapAction {
+    /// Parse "stop" or "terminate" (case-insensitive).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "stop" => Some(Self::Stop),
+            "terminate" => Some(Self::Terminate),
+            _ => None,
+        }
+    }
+
+    /// Action name (as parsed).
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Stop => "stop",
+            Self::Terminate => "terminate",
+        }
+    }
+}
+
+/// Hard spending cap enforced by a `SpendGuard`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpendCap {
+    /// Maximum spend in USD.
+    /// Env: `RUNPOD_MAX_SPEND_USD` (required)
+    pub max_spend_usd: f64,
+
+    /// What the cap is measured against.
+    /// Env: `RUNPOD_MAX_SPEND_SCOPE` (default: "daily")
+    /// Options: "daily", "pod"
+    pub scope: SpendCapScope,
+
+    /// What happens to running pods once the cap is reached.
+    /// Env: `RUNPOD_MAX_SPEND_ACTION` (default: "stop")
+    pub action: SpendCapAction,
+}
+
+impl SpendCap {
+    /// Load the cap from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `RUNPOD_MAX_SPEND_USD` is missing or a variable is invalid.
+    pub fn from_env() -> Result<Self, CostError> {
+        runpod_env::load_dotenv().map_err(CostError::EnvFile)?;
+
+        let max_spend_usd = env::var("RUNPOD_MAX_SPEND_USD")
+            .map_err(|_| CostError::MissingEnv("RUNPOD_MAX_SPEND_USD"))?
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|m| m.is_finite() && *m > 0.0)
+            .ok_or(CostError::InvalidEnv {
+                key: "RUNPOD_MAX_SPEND_USD",
+                reason: "expected a positive number",
+            })?;
+        let scope = env::var("RUNPOD_MAX_SPEND_SCOPE").map_or(Ok(SpendCapScope::Daily), |v| {
+            SpendCapScope::parse(&v).ok_or(CostError::InvalidEnv {
+                key: "RUNPOD_MAX_SPEND_SCOPE",
+                reason: "expected daily or pod",
+            })
+        })?;
+        let action = env::var("RUNPOD_MAX_SPEND_ACTION").map_or(Ok(SpendCapAction::Stop), |v| {
+            SpendCapAction::parse(&v).ok_or(CostError::InvalidEnv {
+                key: "RUNPOD_MAX_SPEND_ACTION",
+                reason: "expected stop or terminate",
+            })
+        })?;
+
+        Ok(Self {
+            max_spend_usd,
+            scope,
+            action,
+        })
+    }
+}
+
+/// Result of one `SpendGuard` check.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpendCapStatus {
+    /// What the cap is measured against.
+    pub scope: SpendCapScope,
+    /// The cap (USD).
+    pub max_spend_usd: f64,
+    /// Spend counted against the cap: today's total (daily scope) or the
+    /// highest lifetime spend of a pod (pod scope), in USD.
+    pub spend_usd: f64,
+    /// Running pods (logical names) over the cap.
+    pub over_cap: Vec<String>,
+    /// Pods of `over_cap` stopped or terminated by this check.
+    pub enforced: Vec<String>,
+}
+
+impl SpendCapStatus {
+    /// Whether the cap is reached.
+    #[must_use]
+    pub fn exceeded(&self) -> bool {
+        self.spend_usd >= self.max_spend_usd
+    }
+}
+
+/// Hard spending cap: estimates spend from pod uptime and hourly prices,
+/// refuses pod creations and starts once the cap is reached, and stops (or
+/// terminates) the pods running over it.
+///
+/// Install it with `RunpodOrchestrator::with_spend_guard()`; the
+/// orchestrator's pods are observed (and the cap enforced) by
+/// `RunpodOrchestrator::enforce_spend_cap()`. Spend between two observations
+/// is attributed to the status seen at the first one; a pod first seen
+/// running counts from its `lastStartedAt`.
+///
+/// A guard built by `from_env()` or `persisted()` saves its ledger after
+/// every check, so a restart does not reset the day's spend.
+#[derive(Debug)]
+pub struct SpendGuard {
+    cap: SpendCap,
+    ledger: Mutex<CostLedger>,
+    ledger_path: Option<PathBuf>,
+}
+
+impl SpendGuard {
+    /// Create a guard with an empty, in-memory ledger.
+    #[must_use]
+    pub const fn new(cap: SpendCap) -> Self {
+        Self::with_ledger(cap, CostLedger::new())
+    }
+
+    /// Create a guard resuming `ledger`, kept in memory.
+    #[must_use]
+    pub const fn with_ledger(cap: SpendCap, ledger: CostLedger) -> Self {
+        Self {
+            cap,
+            ledger: Mutex::new(ledger),
+            ledger_path: None,
+        }
+    }
+
+    /// Create a guard saving its ledger to `path`, resuming the ledger
+    /// already there (if any).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn persisted(cap: SpendCap, path: impl Into<PathBuf>) -> Result<Self, CostError> {
+        let path = path.into();
+        let ledger = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(CostError::LedgerFormat)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => CostLedger::new(),
+            Err(e) => return Err(CostError::LedgerIo(e)),
+        };
+        Ok(Self {
+            ledger_path: Some(path),
+            ..Self::with_ledger(cap, ledger)
+        })
+    }
+
+    /// Load the cap (`SpendCap::from_env()`) and the ledger persisted at
+    /// `RUNPOD_MAX_SPEND_LEDGER_PATH`.
+    ///
+    /// Env: `RUNPOD_MAX_SPEND_LEDGER_PATH` (default: `.runpod_spend_ledger.json`;
+    /// empty keeps the ledger in memory)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is invalid or the ledger cannot be read.
+    pub fn from_env() -> Result<Self, CostError> {
+        let cap = SpendCap::from_env()?;
+        match env::var("RUNPOD_MAX_SPEND_LEDGER_PATH") {
+            Ok(path) if path.trim().is_empty() => Ok(Self::new(cap)),
+            Ok(path) => Self::persisted(cap, path.trim()),
+            Err(_) => Self::persisted(cap, ".runpod_spend_ledger.json"),
+        }
+    }
+
+    /// File the ledger is saved to (`None` if it is kept in memory).
+    #[must_use]
+    pub fn ledger_path(&self) -> Option<&Path> {
+        self.ledger_path.as_deref()
+    }
+
+    /// Save the ledger to `ledger_path()` (no-op without one).
+    ///
+    /// Blocking: call it from `spawn_blocking` in async code.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self) -> Result<(), CostError> {
+        let Some(path) = &self.ledger_path else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec_pretty(&self.ledger()).map_err(CostError::LedgerFormat)?;
+        crate::runpod_state::write_atomic(path, &json).map_err(CostError::LedgerIo)
+    }
+
+    /// Whether the ledger holds a pod named `pod_name` (e.g. one recorded
+    /// with `track()`).
+    #[must_use]
+    pub fn tracks(&self, pod_name: &str) -> bool {
+        self.lock().pods.contains_key(pod_name)
+    }
+
+    /// Record pod `pod_name` (`pod_id`) created and billed from `at_ms`, so
+    /// its spend counts even if its name is not one the orchestrator
+    /// recognizes.
+    pub fn track(&self, pod_name: &str, pod_id: &str, at_ms: u64) {
+        self.lock()
+            .pods
+            .entry(pod_name.to_string())
+            .or_default()
+            .observe(pod_id, true, at_ms, None);
+    }
+
+    /// Get the cap.
+    #[must_use]
+    pub const fn cap(&self) -> SpendCap {
+        self.cap
+    }
+
+    /// Copy of the ledger (to persist it).
+    #[must_use]
+    pub fn ledger(&self) -> CostLedger {
+        self.lock().clone()
+    }
+
+    /// Record a pod listing taken at `at_ms`, priced with each pod's
+    /// `costPerHr`. Pods of the ledger missing from the listing are gone.
+    ///
+    /// Pass only the pods the cap applies to. A running pod the ledger did
+    /// not see start (new to the guard, or started while it was down) is
+    /// counted from its `lastStartedAt`.
+    pub fn observe_pods(&self, pods: &[PodInfo], at_ms: u64) {
+        let mut ledger = self.lock();
+        for pod in pods {
+            let name = pod.name.clone().unwrap_or_else(|| pod.id.clone());
+            let running = pod.desiredStatus.as_deref() == Some("RUNNING");
+            let usage = ledger.pods.entry(name).or_default();
+            if running
+                && usage.running_since_ms.is_none()
+                && let Some(started_ms) = pod.lastStartedAt.as_deref().and_then(parse_utc_ms)
+                && started_ms > usage.last_observed_ms
+                && started_ms < at_ms
+            {
+                usage.observe(&pod.id, true, started_ms, pod.costPerHr);
+            }
+            usage.observe(&pod.id, running, at_ms, pod.costPerHr);
+        }
+
+        let gone: Vec<String> = ledger
+            .pods
+            .iter()
+            .filter(|(_, usage)| {
+                usage
+                    .pod_id
+                    .as_deref()
+                    .is_some_and(|id| pods.iter().all(|p| p.id != id))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in gone {
+            ledger.observe_gone(&name, at_ms);
+        }
+    }
+
+    /// Spend counted against the cap for pod `pod_name`: today's total
+    /// (daily scope) or the pod's lifetime spend (pod scope), in USD.
+    #[must_use]
+    pub fn spend_for(&self, pod_name: &str, now_ms: u64) -> f64 {
+        let ledger = self.lock();
+        match self.cap.scope {
+            SpendCapScope::Daily => {
+                ledger
+                    .report(BudgetPeriod::Daily.start_ms(now_ms), now_ms)
+                    .total_usd
+            }
+            SpendCapScope::Pod => ledger
+                .pods
+                .get(pod_name)
+                .map_or(0.0, |u| lifetime_usd(u, now_ms)),
+        }
+    }
+
+    /// Whether pod `pod_name` may be created (or keep running).
+    #[must_use]
+    pub fn allows(&self, pod_name: &str, now_ms: u64) -> bool {
+        self.spend_for(pod_name, now_ms) < self.cap.max_spend_usd
+    }
+
+    /// Compare the spend with the cap and list the running pods over it.
+    #[must_use]
+    pub fn check(&self, now_ms: u64) -> SpendCapStatus {
+        let ledger = self.lock();
+        let running = ledger
+            .pods
+            .iter()
+            .filter(|(_, u)| u.running_since_ms.is_some());
+        let (spend_usd, over_cap) = match self.cap.scope {
+            SpendCapScope::Daily => {
+                let spend = ledger
+                    .report(BudgetPeriod::Daily.start_ms(now_ms), now_ms)
+                    .total_usd;
+                let over = if spend >= self.cap.max_spend_usd {
+                    running.map(|(name, _)| name.clone()).collect()
+                } else {
+                    Vec::new()
+                };
+                (spend, over)
+            }
+            SpendCapScope::Pod => {
+                let spend = ledger
+                    .pods
+                    .values()
+                    .map(|u| lifetime_usd(u, now_ms))
+                    .fold(0.0, f64::max);
+                let over = running
+                    .filter(|(_, u)| lifetime_usd(u, now_ms) >= self.cap.max_spend_usd)
+                    .map(|(name, _)| name.clone())
+                    .collect();
+                (spend, over)
+            }
+        };
+        drop(ledger);
+
+        SpendCapStatus {
+            scope: self.cap.scope,
+            max_spend_usd: self.cap.max_spend_usd,
+            spend_usd,
+            over_cap,
+            enforced: Vec::new(),
+        }
+    }
+
+    /// Last known pod ID of pod `pod_name`.
+    #[must_use]
+    pub fn pod_id(&self, pod_name: &str) -> Option<String> {
+        self.lock()
+            .pods
+            .get(pod_name)
+            .and_then(|u| u.pod_id.clone())
+    }
+
+    /// Logical name of pod `pod_id` in the ledger, if it was observed.
+    #[must_use]
+    pub fn pod_name(&self, pod_id: &str) -> Option<String> {
+        self.lock()
+            .pods
+            .iter()
+            .find(|(_, u)| u.pod_id.as_deref() == Some(pod_id))
+            .map(|(name, _)| name.clone())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CostLedger> {
+        self.ledger.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Background enforcement of a spend cap
+/// (see `RunpodOrchestrator::run_spend_guard()`).
+///
+/// The task stops when the handle is dropped (or `stop()` is called).
+pub struct SpendGuardTask {
+    last: Arc<Mutex<Option<SpendCapStatus>>>,
+    task: JoinHandle<()>,
+}
+
+impl SpendGuardTask {
+    /// Run `orchestrator.enforce_spend_cap()` every `interval`, starting now.
+    ///
+    /// Must be called inside a Tokio runtime.
+    #[must_use]
+    pub fn start(orchestrator: Arc<RunpodOrchestrator>, interval: Duration) -> Self {
+        let last = Arc::new(Mutex::new(None));
+        let shared = Arc::clone(&last);
+        let task = tokio::spawn(async move {
+            loop {
+                match orchestrator.enforce_spend_cap().await {
+                    Ok(status) => {
+                        *shared.lock().unwrap_or_else(PoisonError::into_inner) = status;
+                    }
+                    Err(e) => log_warn!("spend cap check failed: {e}", e = ErrorChain(&e)),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        });
+        Self { last, task }
+    }
+
+    /// Outcome of the last successful check (`None` before the first one,
+    /// or without a spend guard).
+    #[must_use]
+    pub fn last_status(&self) -> Option<SpendCapStatus> {
+        self.last
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Stop enforcing.
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for SpendGuardTask {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for SpendGuardTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpendGuardTask")
+            .field("last_status", &self.last_status())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Lifetime spend of a pod in USD (0 without a price).
+/// Parse a UTC timestamp as `RunPod` reports it (`2024-07-12T19:14:40.144Z`
+/// or `2024-07-12 19:14:40.144 +0000 UTC`) into ms since the UNIX epoch.
+///
+/// Fractions of a second are dropped.
+fn parse_utc_ms(timestamp: &str) -> Option<u64> {
+    let field = |range: std::ops::Range<usize>| -> Option<u64> {
+        let digits = timestamp.get(range)?;
+        digits
+            .bytes()
+            .all(|b| b.is_ascii_digit())
+            .then(|| digits.parse().ok())?
+    };
+    let (year, month, day) = (field(0..4)?, field(5..7)?, field(8..10)?);
+    let (hour, minute, second) = (field(11..13)?, field(14..16)?, field(17..19)?);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year < 1970 {
+        return None;
+    }
+
+    // Days since 1970-01-01 (Howard Hinnant's `days_from_civil`).
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = (era * 146_097 + doe).checked_sub(719_468)?;
+
+    Some(((days * 24 + hour) * 60 + minute) * 60_000 + second * 1000)
+}
+
+fn lifetime_usd(usage: &PodUsage, now_ms: u64) -> f64 {
+    #[allow(clippy::cast_precision_loss)]
+    let running_hours = usage.running_ms(0, now_ms) as f64 / HOUR_MS;
+    usage.hourly_usd.map_or(0.0, |rate| rate * running_hours)
+}
+
+// ============================================================================
+// Price snapshots
+// ============================================================================
+
+/// Price and availability of one GPU type in a `PriceSnapshot`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuPrice {
+    /// GPU type ID.
+    pub gpu_type_id: String,
+    /// Display name.
+    pub display_name: Option<String>,
+    /// Memory in GB.
+    pub memory_gb: Option<u32>,
+    /// Offered in secure cloud.
+    pub secure_cloud: bool,
+    /// Offered in community cloud.
+    pub community_cloud: bool,
+    /// On-demand price per GPU per hour in secure cloud (USD).
+    pub secure_price: Option<f64>,
+    /// On-demand price per GPU per hour in community cloud (USD).
+    pub community_price: Option<f64>,
+}
+
+impl GpuPrice {
+    /// Availability and price in `cloud_type` ("SECURE" | "COMMUNITY").
+    const fn offer(&self, cloud_type: &str) -> (bool, Option<f64>) {
+        if cloud_type.eq_ignore_ascii_case("COMMUNITY") {
+            (self.community_cloud, self.community_price)
+        } else {
+            (self.secure_cloud, self.secure_price)
+        }
+    }
+}
+
+/// GPU prices and availability at one point in time.
+///
+/// Snapshots are plain JSON (`to_json()` / `from_json()`), so they can be
+/// stored by date and compared later with `diff()`; `to_csv()` is meant for
+/// spreadsheets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceSnapshot {
+    /// When the prices were read (ms since UNIX epoch).
+    pub taken_ms: u64,
+    /// One entry per GPU type, sorted by ID.
+    pub gpu_types: Vec<GpuPrice>,
+}
+
+/// CSV header of `PriceSnapshot::to_csv()`.
+const PRICE_CSV_HEADER: &str =
+    "gpu_type_id,display_name,memory_gb,secure_cloud,community_cloud,secure_price,community_price";
+
+impl PriceSnapshot {
+    /// Build a snapshot from a `gpuTypes` listing.
+    #[must_use]
+    pub fn from_gpu_types(gpu_types: &[GpuType], taken_ms: u64) -> Self {
+        let mut gpu_types: Vec<GpuPrice> = gpu_types
+            .iter()
+            .map(|g| GpuPrice {
+                gpu_type_id: g.id.clone(),
+                display_name: g.displayName.clone(),
+                memory_gb: g.memoryInGb,
+                secure_cloud: g.secureCloud.unwrap_or(false),
+                community_cloud: g.communityCloud.unwrap_or(false),
+                secure_price: g.securePrice,
+                community_price: g.communityPrice,
+            })
+            .collect();
+        gpu_types.sort_by(|a, b| a.gpu_type_id.cmp(&b.gpu_type_id));
+        Self {
+            taken_ms,
+            gpu_types,
+        }
+    }
+
+    /// Read the current prices from `RunPod`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `gpuTypes` query fails.
+    pub async fn capture(client: &RunpodClient) -> Result<Self, RunpodClientError> {
+        let gpu_types = client.list_gpu_types().await?;
+        Ok(Self::from_gpu_types(&gpu_types, now_unix_ms()))
+    }
+
+    /// Timestamped file name, e.g. `gpu-prices-20250131T180000Z.json`.
+    #[must_use]
+    pub fn file_name(&self, extension: &str) -> String {
+        let stamp: String = format_rfc3339(self.taken_ms / 1000)
+            .chars()
+            .filter(|c| *c != '-' && *c != ':')
+            .collect();
+        format!("gpu-prices-{stamp}.{}", extension.trim_start_matches('.'))
+    }
+
+    /// Serialize the snapshot as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Parse a snapshot written by `to_json()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `json` is not a snapshot.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Render the snapshot as CSV (one row per GPU type, empty = unknown).
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let opt = |v: Option<String>| v.unwrap_or_default();
+        let mut out = format!("{PRICE_CSV_HEADER}\n");
+        for g in &self.gpu_types {
+            let row = [
+                csv_field(&g.gpu_type_id),
+                csv_field(g.display_name.as_deref().unwrap_or_default()),
+                opt(g.memory_gb.map(|m| m.to_string())),
+                g.secure_cloud.to_string(),
+                g.community_cloud.to_string(),
+                opt(g.secure_price.map(|p| p.to_string())),
+                opt(g.community_price.map(|p| p.to_string())),
+            ];
+            out.push_str(&row.join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Compare this snapshot with a `newer` one.
+    ///
+    /// Reports GPU types that appeared or disappeared, and every cloud offer
+    /// (secure / community) whose price or availability changed.
+    #[must_use]
+    pub fn diff(&self, newer: &Self) -> PriceDiff {
+        let old: BTreeMap<&str, &GpuPrice> = self
+            .gpu_types
+            .iter()
+            .map(|g| (g.gpu_type_id.as_str(), g))
+            .collect();
+        let new: BTreeMap<&str, &GpuPrice> = newer
+            .gpu_types
+            .iter()
+            .map(|g| (g.gpu_type_id.as_str(), g))
+            .collect();
+
+        let mut changes = Vec::new();
+        for (id, before) in &old {
+            let Some(after) = new.get(id) else {
+                continue;
+            };
+            for cloud_type in ["SECURE", "COMMUNITY"] {
+                let (was_available, old_price) = before.offer(cloud_type);
+                let (available, new_price) = after.offer(cloud_type);
+                if was_available != available || old_price != new_price {
+                    changes.push(PriceChange {
+                        gpu_type_id: (*id).to_string(),
+                        cloud_type: cloud_type.to_string(),
+                        old_price,
+                        new_price,
+                        was_available,
+                        available,
+                    });
+                }
+            }
+        }
+
+        PriceDiff {
+            from_ms: self.taken_ms,
+            to_ms: newer.taken_ms,
+            added: only_in(&new, &old),
+            removed: only_in(&old, &new),
+            changes,
+        }
+    }
+}
+
+/// GPU type IDs of `a` missing from `b`.
+fn only_in(a: &BTreeMap<&str, &GpuPrice>, b: &BTreeMap<&str, &GpuPrice>) -> Vec<String> {
+    a.keys()
+        .filter(|id| !b.contains_key(*id))
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Change of one GPU type's offer in one cloud between two snapshots.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PriceChange {
+    /// GPU type ID.
+    pub gpu_type_id: String,
+    /// Cloud type ("SECURE" | "COMMUNITY").
+    pub cloud_type: String,
+    /// Price per GPU per hour in the older snapshot (USD).
+    pub old_price: Option<f64>,
+    /// Price per GPU per hour in the newer snapshot (USD).
+    pub new_price: Option<f64>,
+    /// Offered in the older snapshot.
+    pub was_available: bool,
+    /// Offered in the newer snapshot.
+    pub available: bool,
+}
+
+impl PriceChange {
+    /// Relative price change in percent (`None` unless both prices are known).
+    #[must_use]
+    pub fn percent_change(&self) -> Option<f64> {
+        match (self.old_price, self.new_price) {
+            (Some(old), Some(new)) if old > 0.0 => Some((new - old) / old * 100.0),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PriceChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let price = |p: Option<f64>| p.map_or_else(|| "-".to_string(), |p| format!("${p:.2}"));
+        write!(
+            f,
+            "{} ({}): {} -> {}",
+            self.gpu_type_id,
+            self.cloud_type.to_lowercase(),
+            price(self.old_price),
+            price(self.new_price)
+        )?;
+        if let Some(percent) = self.percent_change() {
+            write!(f, " ({percent:+.1}%)")?;
+        }
+        match (self.was_available, self.available) {
+            (true, false) => write!(f, ", no longer offered"),
+            (false, true) => write!(f, ", now offered"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Differences between two `PriceSnapshot`s.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PriceDiff {
+    /// Time of the older snapshot (ms since UNIX epoch).
+    pub from_ms: u64,
+    /// Time of the newer snapshot (ms since UNIX epoch).
+    pub to_ms: u64,
+    /// GPU types only in the newer snapshot.
+    pub added: Vec<String>,
+    /// GPU types only in the older snapshot.
+    pub removed: Vec<String>,
+    /// Price or availability changes of GPU types in both.
+    pub changes: Vec<PriceChange>,
+}
+
+impl PriceDiff {
+    /// Whether nothing changed.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changes.is_empty()
+    }
+
+    /// Serialize the diff as pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+// ============================================================================
+// Error type
+// ============================================================================
+
+/// Error type for cost and budget configuration.
+#[derive(Debug)]
+pub enum CostError {
+    /// Missing required environment variable.
+    MissingEnv(&'static str),
+    /// Invalid environment variable value.
+    InvalidEnv {
+        /// The environment variable key.
+        key: &'static str,
+        /// The reason for invalidity.
+        reason: &'static str,
+    },
+    /// The env file (`RUNPOD_ENV_FILE`) could not be loaded.
+    EnvFile(EnvFileError),
+    /// The spend ledger file could not be read or written.
+    LedgerIo(io::Error),
+    /// The spend ledger file is not a valid ledger.
+    LedgerFormat(serde_json::Error),
+}
+
+impl fmt::Display for CostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingEnv(k) => write!(f, "missing required env var: {k}"),
+            Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
+            Self::EnvFile(e) => write!(f, "{e}"),
+            Self::LedgerIo(_) => write!(f, "spend ledger file could not be read or written"),
+            Self::LedgerFormat(_) => write!(f, "spend ledger file is not a valid ledger"),
+        }
+    }
+}
+
+impl std::error::Error for CostError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::EnvFile(e) => std::error::Error::source(e),
+            Self::LedgerIo(e) => Some(e),
+            Self::LedgerFormat(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+fn parse_bool_env(key: &'static str, default: bool) -> Result<bool, CostError> {
+    runpod_env::bool_var(key, default).map_err(|reason| CostError::InvalidEnv { key, reason })
+}
+
+fn split_csv_env(key: &'static str, default: &str) -> Vec<String> {
+    let raw = env::var(key).unwrap_or_else(|_| default.to_string());
+    raw.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
@@ -0,0 +1,329 @@
+//! Environment loading and pod environment helpers.
+//!
+//! Unique responsibility: load the crate's own dotenv file, and turn the
+//! configured pod environment into the final key/value map sent to `RunPod`.
+//!
+//! Files:
+//! - `RUNPOD_ENV_FILE`: dotenv file loaded by every `from_env()` instead of
+//!   `./.env` (must exist when set)
+//! - `RUNPOD_POD_ENV_FILE`: standard env-file (`KEY=value` lines) holding the
+//!   *pod's* variables; `RUNPOD_POD_ENV` entries override it
+//!
+//! Templating (`RUNPOD_POD_ENV_TEMPLATE`):
+//! - `${VAR}` is replaced by the orchestrator's own `VAR` (process env or `.env`)
+//! - `$$` is a literal `$` (so `$${VAR}` yields `${VAR}` verbatim)
+//! - Any other `$` is kept as-is
+//!
+//! ```text
+//! RUNPOD_POD_ENV={"HF_TOKEN": "${HF_TOKEN}", "PS1": "$$ "}
+//! RUNPOD_POD_ENV_TEMPLATE=strict
+//! ```
+
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    hash::BuildHasher,
+    path::{Path, PathBuf},
+};
+
+/// Load the crate's dotenv file into the process environment.
+///
+/// Loads `RUNPOD_ENV_FILE` if set, `./.env` otherwise. Variables already set
+/// in the process environment win over the file, as with `dotenvy::dotenv()`.
+///
+/// # Errors
+///
+/// Returns an error if `RUNPOD_ENV_FILE` is set but cannot be loaded.
+/// A missing `./.env` is not an error.
+pub fn load_dotenv() -> Result<(), EnvFileError> {
+    env::var_os("RUNPOD_ENV_FILE")
+        .filter(|p| !p.is_empty())
+        .map_or_else(
+            || {
+                let _ = dotenvy::dotenv();
+                Ok(())
+            },
+            |path| {
+                let path = PathBuf::from(path);
+                dotenvy::from_path(&path).map_err(|e| EnvFileError::new(&path, None, e.to_string()))
+            },
+        )
+}
+
+/// Read the pod env-file named by `RUNPOD_POD_ENV_FILE`, if set.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or parsed.
+pub fn pod_env_file_from_env() -> Result<HashMap<String, String>, EnvFileError> {
+    env::var_os("RUNPOD_POD_ENV_FILE")
+        .filter(|p| !p.is_empty())
+        .map_or_else(
+            || Ok(HashMap::new()),
+            |path| read_env_file(Path::new(&path)),
+        )
+}
+
+/// Read a boolean variable: "1", "true", "yes", "on" or "0", "false", "no",
+/// "off", "" (case-insensitive), `default` when unset.
+///
+/// Modules wrap it to report the returned reason in their own `InvalidEnv`
+/// error.
+pub(crate) fn bool_var(key: &str, default: bool) -> Result<bool, &'static str> {
+    env::var(key).map_or(Ok(default), |v| match v.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" | "" => Ok(false),
+        _ => Err("expected a boolean (true/false)"),
+    })
+}
+
+/// Read an unsigned integer variable, `default` when unset.
+///
+/// Wrapped like `bool_var()`.
+pub(crate) fn u64_var(key: &str, default: u64) -> Result<u64, &'static str> {
+    env::var(key).map_or(Ok(default), |v| {
+        v.trim()
+            .parse::<u64>()
+            .map_err(|_| "expected an unsigned integer")
+    })
+}
+
+/// Read an unsigned 32-bit integer variable, `default` when unset.
+///
+/// Wrapped like `bool_var()`.
+pub(crate) fn u32_var(key: &str, default: u32) -> Result<u32, &'static str> {
+    env::var(key).map_or(Ok(default), |v| {
+        v.trim()
+            .parse::<u32>()
+            .map_err(|_| "expected an unsigned integer")
+    })
+}
+
+/// Read a standard env-file.
+///
+/// Format: one `KEY=value` per line; blank lines and `#` comments are
+/// skipped, a leading `export ` is allowed, and values may be wrapped in
+/// single or double quotes (unquoted values end at ` #`). Values are not
+/// expanded here (see `expand_pod_env()`).
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or a line is malformed.
+pub fn read_env_file(path: &Path) -> Result<HashMap<String, String>, EnvFileError> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| EnvFileError::new(path, None, e.to_string()))?;
+    parse_env_file(&contents).map_err(|(line, reason)| EnvFileError::new(path, Some(line), reason))
+}
+
+fn parse_env_file(contents: &str) -> Result<HashMap<String, String>, (usize, String)> {
+    let mut vars = HashMap::new();
+    for (idx, raw) in contents.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").map_or(line, str::trim_start);
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err((idx + 1, "expected KEY=value".to_string()));
+        };
+        let key = key.trim();
+        if !is_var_name(key) {
+            return Err((idx + 1, format!("invalid variable name {key:?}")));
+        }
+
+        let value = value.trim();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..]
+                .strip_suffix(quote)
+                .ok_or_else(|| (idx + 1, "unterminated quoted value".to_string()))?,
+            _ => value.split_once(" #").map_or(value, |(v, _)| v).trim_end(),
+        };
+        vars.insert(key.to_string(), value.to_string());
+    }
+    Ok(vars)
+}
+
+/// How `${VAR}` references in pod env values are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EnvTemplateMode {
+    /// Values are passed verbatim.
+    #[default]
+    Off,
+    /// References are expanded; unset variables expand to an empty string.
+    Lenient,
+    /// References are expanded; an unset variable is an error.
+    Strict,
+}
+
+impl EnvTemplateMode {
+    /// Parse "off", "lenient" or "strict" (case-insensitive; empty = off).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "off" | "false" | "none" => Some(Self::Off),
+            "lenient" | "on" | "true" => Some(Self::Lenient),
+            "strict" => Some(Self::Strict),
+            _ => None,
+        }
+    }
+}
+
+/// Expand every value of a pod env map against the process environment.
+///
+/// # Errors
+///
+/// Returns an error if a value is malformed, or (in strict mode) references
+/// an unset variable.
+pub fn expand_pod_env<S: BuildHasher + Default>(
+    pod_env: HashMap<String, String, S>,
+    mode: EnvTemplateMode,
+) -> Result<HashMap<String, String, S>, EnvTemplateError> {
+    if mode == EnvTemplateMode::Off {
+        return Ok(pod_env);
+    }
+
+    pod_env
+        .into_iter()
+        .map(|(key, value)| {
+            let expanded = expand(&value, mode, |var| env::var(var).ok()).map_err(|reason| {
+                EnvTemplateError {
+                    key: key.clone(),
+                    reason,
+                }
+            })?;
+            Ok((key, expanded))
+        })
+        .collect()
+}
+
+/// Expand `${VAR}` references in `value`, resolving names with `lookup`.
+///
+/// # Errors
+///
+/// Returns the reason if the template is malformed or a variable is missing
+/// in strict mode.
+pub fn expand(
+    value: &str,
+    mode: EnvTemplateMode,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String, TemplateErrorReason> {
+    if mode == EnvTemplateMode::Off {
+        return Ok(value.to_string());
+    }
+
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+
+        if let Some(tail) = after.strip_prefix('$') {
+            out.push('$');
+            rest = tail;
+        } else if let Some(tail) = after.strip_prefix('{') {
+            let end = tail.find('}').ok_or(TemplateErrorReason::Unterminated)?;
+            let name = &tail[..end];
+            if !is_var_name(name) {
+                return Err(TemplateErrorReason::InvalidName(name.to_string()));
+            }
+            match lookup(name) {
+                Some(v) => out.push_str(&v),
+                None if mode == EnvTemplateMode::Strict => {
+                    return Err(TemplateErrorReason::Missing(name.to_string()));
+                }
+                None => {}
+            }
+            rest = &tail[end + 1..];
+        } else {
+            out.push('$');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+fn is_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Why a template could not be expanded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateErrorReason {
+    /// `${VAR}` references an unset variable (strict mode).
+    Missing(String),
+    /// `${` without a closing `}`.
+    Unterminated,
+    /// `${...}` does not contain a valid variable name.
+    InvalidName(String),
+}
+
+/// A pod env value could not be expanded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvTemplateError {
+    /// Pod env key whose value failed.
+    pub key: String,
+    /// What went wrong.
+    pub reason: TemplateErrorReason,
+}
+
+impl fmt::Display for EnvTemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            TemplateErrorReason::Missing(var) => {
+                write!(f, "pod env {}: ${{{var}}} is not set", self.key)
+            }
+            TemplateErrorReason::Unterminated => {
+                write!(f, "pod env {}: unterminated ${{", self.key)
+            }
+            TemplateErrorReason::InvalidName(name) => {
+                write!(f, "pod env {}: invalid variable name {name:?}", self.key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EnvTemplateError {}
+
+/// An env file could not be loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvFileError {
+    /// File path.
+    pub path: PathBuf,
+    /// 1-based line number, for parse errors.
+    pub line: Option<usize>,
+    /// What went wrong.
+    pub reason: String,
+}
+
+impl EnvFileError {
+    fn new(path: &Path, line: Option<usize>, reason: String) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            line,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for EnvFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(
+                f,
+                "env file {}:{line}: {}",
+                self.path.display(),
+                self.reason
+            ),
+            None => write!(f, "env file {}: {}", self.path.display(), self.reason),
+        }
+    }
+}
+
+impl std::error::Error for EnvFileError {}
@@ -0,0 +1,549 @@
+//! Test fixtures (feature `test-util`).
+//!
+//! Unique responsibility: build the crate's data types with sensible defaults
+//! for downstream tests, so each scenario only states what it cares about.
+//!
+//! Every builder starts from a ready, running pod (SSH on 22, Jupyter on
+//! 8888, documentation-range public IP) and exposes fluent overrides:
+//!
+//! ```ignore
+//! use halldyll_starter_runpod::runpod_fixtures;
+//!
+//! let details = runpod_fixtures::pod_details("pod-1").status("EXITED").no_ports().build();
+//! let lease = runpod_fixtures::pod_lease("pod-1").port(8000, 18000).build();
+//! let state = runpod_fixtures::state("trainer").running("pod-1").build();
+//! ```
+
+use std::collections::HashMap;
+
+use crate::runpod_labels::{LABELS_ENV, encode_labels};
+use crate::runpod_orchestrator::{LeaseAction, LeaseProvenance, PodDetails, PodInfo, PodLease};
+use crate::runpod_state::{
+    DeployMode, PodDesiredStatus, PodId, RemotePodSnapshot, RunPodState, StatePolicy, TargetStatus,
+};
+
+/// Default image of fixture pods.
+pub const IMAGE: &str = "runpod/pytorch:2.4.0-py3.11-cuda12.4.1-devel-ubuntu22.04";
+
+/// Default public IP of fixture pods (TEST-NET-3, never routable).
+pub const PUBLIC_IP: &str = "203.0.113.10";
+
+/// Default port mappings of fixture pods (container port, public port).
+pub const PORTS: [(u16, u16); 2] = [(22, 10_022), (8888, 18_888)];
+
+fn default_port_mappings() -> HashMap<u16, u16> {
+    PORTS.into_iter().collect()
+}
+
+// ============================================================================
+// PodDetails
+// ============================================================================
+
+/// Start a `PodDetails` for a running, ready pod named `test-pod`.
+#[must_use]
+pub fn pod_details(id: impl Into<String>) -> PodDetailsBuilder {
+    PodDetailsBuilder {
+        id: id.into(),
+        name: Some("test-pod".to_string()),
+        desired_status: Some("RUNNING".to_string()),
+        image_name: Some(IMAGE.to_string()),
+        public_ip: Some(PUBLIC_IP.to_string()),
+        port_mappings: Some(default_port_mappings()),
+        network_volume_id: None,
+        volume_mount_path: Some("/workspace".to_string()),
+    }
+}
+
+/// Builder for `PodDetails` (see `pod_details()`).
+#[derive(Debug, Clone)]
+pub struct PodDetailsBuilder {
+    id: String,
+    name: Option<String>,
+    desired_status: Option<String>,
+    image_name: Option<String>,
+    public_ip: Option<String>,
+    port_mappings: Option<HashMap<u16, u16>>,
+    network_volume_id: Option<String>,
+    volume_mount_path: Option<String>,
+}
+
+impl PodDetailsBuilder {
+    /// Set the pod name.
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the desired status (e.g. `EXITED`).
+    #[must_use]
+    pub fn status(mut self, status: impl Into<String>) -> Self {
+        self.desired_status = Some(status.into());
+        self
+    }
+
+    /// Set the image name.
+    #[must_use]
+    pub fn image(mut self, image: impl Into<String>) -> Self {
+        self.image_name = Some(image.into());
+        self
+    }
+
+    /// Set the public IP.
+    #[must_use]
+    pub fn public_ip(mut self, ip: impl Into<String>) -> Self {
+        self.public_ip = Some(ip.into());
+        self
+    }
+
+    /// Map `container` to `public` (adding to the default mappings).
+    #[must_use]
+    pub fn port(mut self, container: u16, public: u16) -> Self {
+        self.port_mappings
+            .get_or_insert_with(HashMap::new)
+            .insert(container, public);
+        self
+    }
+
+    /// Remove the public IP and every port mapping (a pod still starting).
+    #[must_use]
+    pub fn no_ports(mut self) -> Self {
+        self.public_ip = None;
+        self.port_mappings = None;
+        self
+    }
+
+    /// Attach a network volume.
+    #[must_use]
+    pub fn network_volume(mut self, volume_id: impl Into<String>) -> Self {
+        self.network_volume_id = Some(volume_id.into());
+        self
+    }
+
+    /// Set the volume mount path.
+    #[must_use]
+    pub fn volume_mount_path(mut self, path: impl Into<String>) -> Self {
+        self.volume_mount_path = Some(path.into());
+        self
+    }
+
+    /// Build the `PodDetails` (`ports` lists every mapped container port).
+    #[must_use]
+    pub fn build(self) -> PodDetails {
+        let mut container_ports: Vec<u16> = self
+            .port_mappings
+            .iter()
+            .flat_map(HashMap::keys)
+            .copied()
+            .collect();
+        container_ports.sort_unstable();
+
+        PodDetails {
+            id: self.id,
+            name: self.name,
+            desiredStatus: self.desired_status,
+            imageName: self.image_name,
+            publicIp: self.public_ip,
+            ports: Some(
+                container_ports
+                    .iter()
+                    .map(|port| format!("{port}/{}", if *port == 22 { "tcp" } else { "http" }))
+                    .collect(),
+            ),
+            portMappings: self.port_mappings.map(|mappings| {
+                mappings
+                    .into_iter()
+                    .map(|(container, public)| (container.to_string(), public))
+                    .collect()
+            }),
+            networkVolumeId: self.network_volume_id,
+            volumeMountPath: self.volume_mount_path,
+            env: None,
+            gpu: None,
+            containerDiskInGb: None,
+            volumeInGb: None,
+            dockerStartCmd: None,
+            interruptible: None,
+            machine: None,
+            machineId: None,
+            costPerHr: None,
+            lastStartedAt: None,
+        }
+    }
+}
+
+// ============================================================================
+// PodInfo
+// ============================================================================
+
+/// Start a `PodInfo` for a running pod named `test-pod`.
+#[must_use]
+pub fn pod_info(id: impl Into<String>) -> PodInfoBuilder {
+    PodInfoBuilder {
+        id: id.into(),
+        name: Some("test-pod".to_string()),
+        desired_status: Some("RUNNING".to_string()),
+        image_name: Some(IMAGE.to_string()),
+        machine_id: Some("test-machine".to_string()),
+        cost_per_hr: None,
+        labels: Vec::new(),
+    }
+}
+
+/// Builder for `PodInfo` (see `pod_info()`).
+#[derive(Debug, Clone)]
+pub struct PodInfoBuilder {
+    id: String,
+    name: Option<String>,
+    desired_status: Option<String>,
+    image_name: Option<String>,
+    machine_id: Option<String>,
+    cost_per_hr: Option<f64>,
+    labels: Vec<(String, String)>,
+}
+
+impl PodInfoBuilder {
+    /// Set the pod name.
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Set the desired status (e.g. `EXITED`).
+    #[must_use]
+    pub fn status(mut self, status: impl Into<String>) -> Self {
+        self.desired_status = Some(status.into());
+        self
+    }
+
+    /// Set the image name.
+    #[must_use]
+    pub fn image(mut self, image: impl Into<String>) -> Self {
+        self.image_name = Some(image.into());
+        self
+    }
+
+    /// Set the machine ID.
+    #[must_use]
+    pub fn machine(mut self, machine_id: impl Into<String>) -> Self {
+        self.machine_id = Some(machine_id.into());
+        self
+    }
+
+    /// Set the cost per hour (USD).
+    #[must_use]
+    pub const fn cost_per_hr(mut self, usd: f64) -> Self {
+        self.cost_per_hr = Some(usd);
+        self
+    }
+
+    /// Add a label (see `runpod_labels`).
+    #[must_use]
+    pub fn label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.push((key.into(), value.into()));
+        self
+    }
+
+    /// Build the `PodInfo`.
+    #[must_use]
+    pub fn build(self) -> PodInfo {
+        let env = (!self.labels.is_empty()).then(|| {
+            let labels = self.labels.into_iter().collect();
+            HashMap::from([(LABELS_ENV.to_string(), encode_labels(&labels))])
+        });
+        PodInfo {
+            id: self.id,
+            name: self.name,
+            desiredStatus: self.desired_status,
+            imageName: self.image_name,
+            machineId: self.machine_id,
+            costPerHr: self.cost_per_hr,
+            env,
+            lastStartedAt: None,
+        }
+    }
+}
+
+// ============================================================================
+// PodLease
+// ============================================================================
+
+/// Start a `PodLease` on a reused, running pod named `test-pod`.
+#[must_use]
+pub fn pod_lease(id: impl Into<String>) -> PodLeaseBuilder {
+    PodLeaseBuilder {
+        lease: PodLease {
+            id: id.into(),
+            name: "test-pod".to_string(),
+            public_ip: PUBLIC_IP.to_string(),
+            port_mappings: default_port_mappings(),
+            desired_status: "RUNNING".to_string(),
+            data_center_id: None,
+            provenance: LeaseProvenance::default(),
+            port_remaps: HashMap::new(),
+            proxy_endpoints: HashMap::new(),
+            benchmark: None,
+            expires_ms: None,
+            interruptible: false,
+        },
+    }
+}
+
+/// Builder for `PodLease` (see `pod_lease()`).
+#[derive(Debug, Clone)]
+pub struct PodLeaseBuilder {
+    lease: PodLease,
+}
+
+impl PodLeaseBuilder {
+    /// Set the pod name.
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.lease.name = name.into();
+        self
+    }
+
+    /// Set the public IP.
+    #[must_use]
+    pub fn public_ip(mut self, ip: impl Into<String>) -> Self {
+        self.lease.public_ip = ip.into();
+        self
+    }
+
+    /// Map `container` to `public` (adding to the default mappings).
+    #[must_use]
+    pub fn port(mut self, container: u16, public: u16) -> Self {
+        self.lease.port_mappings.insert(container, public);
+        self
+    }
+
+    /// Record that the port spec `requested` ("8888/tcp") was remapped to container port `exposed`.
+    #[must_use]
+    pub fn remap(mut self, requested: impl Into<String>, exposed: u16) -> Self {
+        self.lease.port_remaps.insert(requested.into(), exposed);
+        self
+    }
+
+    /// Make the lease proxy-only: no public IP or port mappings, and a
+    /// proxy URL for each of `http_ports`.
+    #[must_use]
+    pub fn proxy_only(mut self, http_ports: &[u16]) -> Self {
+        self.lease.public_ip.clear();
+        self.lease.port_mappings.clear();
+        self.lease.proxy_endpoints = http_ports
+            .iter()
+            .map(|port| {
+                (
+                    *port,
+                    format!("https://{}-{port}.proxy.runpod.net", self.lease.id),
+                )
+            })
+            .collect();
+        self
+    }
+
+    /// Remove every port mapping.
+    #[must_use]
+    pub fn no_ports(mut self) -> Self {
+        self.lease.port_mappings.clear();
+        self
+    }
+
+    /// Set the desired status.
+    #[must_use]
+    pub fn status(mut self, status: impl Into<String>) -> Self {
+        self.lease.desired_status = status.into();
+        self
+    }
+
+    /// Set how the pod was obtained.
+    #[must_use]
+    pub const fn action(mut self, action: LeaseAction) -> Self {
+        self.lease.provenance.action = action;
+        self
+    }
+
+    /// Mark the pod as created in `cloud_type` after `skipped` had no capacity.
+    #[must_use]
+    pub fn created_in(mut self, cloud_type: impl Into<String>, skipped: &[&str]) -> Self {
+        self.lease.provenance = LeaseProvenance {
+            action: LeaseAction::Created,
+            cloud_type: Some(cloud_type.into()),
+            skipped_cloud_types: skipped.iter().map(ToString::to_string).collect(),
+            ..LeaseProvenance::default()
+        };
+        self
+    }
+
+    /// Set the data center the pod runs in.
+    #[must_use]
+    pub fn data_center(mut self, data_center_id: impl Into<String>) -> Self {
+        self.lease.data_center_id = Some(data_center_id.into());
+        self
+    }
+
+    /// Mark the pod as a spot (interruptible) pod.
+    #[must_use]
+    pub const fn interruptible(mut self) -> Self {
+        self.lease.interruptible = true;
+        self
+    }
+
+    /// Build the `PodLease`.
+    #[must_use]
+    pub fn build(self) -> PodLease {
+        self.lease
+    }
+}
+
+// ============================================================================
+// RemotePodSnapshot
+// ============================================================================
+
+/// Start a `RemotePodSnapshot` of a running pod named `test-pod`, observed at 0.
+#[must_use]
+pub fn snapshot(id: impl Into<String>) -> SnapshotBuilder {
+    SnapshotBuilder {
+        snapshot: RemotePodSnapshot {
+            id: PodId::new(id),
+            name: "test-pod".to_string(),
+            desired_status: PodDesiredStatus::Running,
+            observed_at_ms: 0,
+        },
+    }
+}
+
+/// Builder for `RemotePodSnapshot` (see `snapshot()`).
+#[derive(Debug, Clone)]
+pub struct SnapshotBuilder {
+    snapshot: RemotePodSnapshot,
+}
+
+impl SnapshotBuilder {
+    /// Set the pod name.
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.snapshot.name = name.into();
+        self
+    }
+
+    /// Set the desired status.
+    #[must_use]
+    pub const fn status(mut self, status: PodDesiredStatus) -> Self {
+        self.snapshot.desired_status = status;
+        self
+    }
+
+    /// Set the observation timestamp (ms).
+    #[must_use]
+    pub const fn observed_at(mut self, at_ms: u64) -> Self {
+        self.snapshot.observed_at_ms = at_ms;
+        self
+    }
+
+    /// Build the `RemotePodSnapshot`.
+    #[must_use]
+    pub fn build(self) -> RemotePodSnapshot {
+        self.snapshot
+    }
+}
+
+// ============================================================================
+// RunPodState
+// ============================================================================
+
+/// Start a fresh `RunPodState` for `pod_name` (no pod yet), updated at 0.
+#[must_use]
+pub fn state(pod_name: impl Into<String>) -> StateBuilder {
+    StateBuilder {
+        state: RunPodState::new(pod_name, 0),
+    }
+}
+
+/// Builder for `RunPodState` (see `state()`).
+#[derive(Debug, Clone)]
+pub struct StateBuilder {
+    state: RunPodState,
+}
+
+impl StateBuilder {
+    /// Track pod `id`, last observed running.
+    #[must_use]
+    pub fn running(self, id: impl Into<String>) -> Self {
+        self.observed(PodDesiredStatus::Running, id)
+    }
+
+    /// Track pod `id`, last observed exited.
+    #[must_use]
+    pub fn exited(self, id: impl Into<String>) -> Self {
+        self.observed(PodDesiredStatus::Exited, id)
+    }
+
+    fn observed(mut self, status: PodDesiredStatus, id: impl Into<String>) -> Self {
+        let snap = snapshot(id)
+            .name(self.state.pod_name.clone())
+            .status(status)
+            .observed_at(self.state.last_updated_ms)
+            .build();
+        self.state.pod_id = Some(snap.id.clone());
+        self.state.last_remote = Some(snap);
+        self
+    }
+
+    /// Set the last remote snapshot (and the tracked pod ID).
+    #[must_use]
+    pub fn last_remote(mut self, snapshot: RemotePodSnapshot) -> Self {
+        self.state.pod_id = Some(snapshot.id.clone());
+        self.state.last_remote = Some(snapshot);
+        self
+    }
+
+    /// Set the target status.
+    #[must_use]
+    pub const fn target(mut self, target: TargetStatus) -> Self {
+        self.state.target = target;
+        self
+    }
+
+    /// Set the local policy.
+    #[must_use]
+    pub fn policy(mut self, policy: StatePolicy) -> Self {
+        self.state.policy = policy;
+        self
+    }
+
+    /// Set the deploy mode.
+    #[must_use]
+    pub const fn deploy_mode(mut self, mode: DeployMode) -> Self {
+        self.state.deploy_mode = mode;
+        self
+    }
+
+    /// Set the last update timestamp (ms).
+    #[must_use]
+    pub const fn updated_at(mut self, at_ms: u64) -> Self {
+        self.state.last_updated_ms = at_ms;
+        self
+    }
+
+    /// Set the store revision.
+    #[must_use]
+    pub const fn revision(mut self, revision: u64) -> Self {
+        self.state.revision = revision;
+        self
+    }
+
+    /// Set the accumulated spend (USD).
+    #[must_use]
+    pub const fn spend(mut self, usd: f64) -> Self {
+        self.state.accumulated_spend_usd = usd;
+        self
+    }
+
+    /// Build the `RunPodState`.
+    #[must_use]
+    pub fn build(self) -> RunPodState {
+        self.state
+    }
+}
@@ -0,0 +1,189 @@
+//! Scoped pod leases.
+//!
+//! Unique responsibility: release a leased pod (stop or terminate it) when
+//! the handle that owns it goes away, so a process that exits without
+//! explicit cleanup does not leave pods billing.
+//!
+//! `release().await` is the reliable way out. Dropping an armed guard is a
+//! best-effort fallback: the release is spawned on the current Tokio runtime
+//! (and lost if that runtime shuts down first); outside a runtime the pod is
+//! left as it is, with a warning.
+//!
+//! ```ignore
+//! let orchestrator = Arc::new(orchestrator);
+//! let pod = orchestrator.ensure_guarded_pod().await?;
+//! println!("working on {}", pod.id); // derefs to PodLease
+//!
+//! pod.release().await?; // or let it drop
+//! ```
+
+use std::{fmt, ops::Deref, sync::Arc};
+
+use tokio::runtime::Handle;
+
+use crate::runpod_log::{ErrorChain, log_info, log_warn};
+use crate::runpod_orchestrator::{OrchestratorError, PodLease, RunpodOrchestrator};
+
+/// What happens to a guarded pod when its guard is released or dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LeaseReleasePolicy {
+    /// Leave the pod as it is.
+    Keep,
+    /// Stop the pod: billing of the GPU stops, the disk is kept.
+    #[default]
+    Stop,
+    /// Terminate the pod (cannot be undone).
+    Terminate,
+}
+
+impl LeaseReleasePolicy {
+    /// Parse "keep", "stop" or "terminate" (case-insensitive).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "keep" => Some(Self::Keep),
+            "stop" => Some(Self::Stop),
+            "terminate" => Some(Self::Terminate),
+            _ => None,
+        }
+    }
+
+    /// Policy name ("keep", "stop" or "terminate").
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Keep => "keep",
+            Self::Stop => "stop",
+            Self::Terminate => "terminate",
+        }
+    }
+}
+
+/// A pod lease that stops or terminates its pod when released or dropped.
+///
+/// Derefs to the `PodLease`. `disarm()` hands the lease back and keeps the
+/// pod.
+pub struct LeaseGuard {
+    lease: PodLease,
+    /// Cleared once the lease is released or handed back.
+    armed: bool,
+    orchestrator: Arc<RunpodOrchestrator>,
+    policy: LeaseReleasePolicy,
+}
+
+impl LeaseGuard {
+    /// Guard `lease`: `policy` is applied through `orchestrator` on release.
+    #[must_use]
+    pub const fn new(
+        lease: PodLease,
+        orchestrator: Arc<RunpodOrchestrator>,
+        policy: LeaseReleasePolicy,
+    ) -> Self {
+        Self {
+            lease,
+            armed: true,
+            orchestrator,
+            policy,
+        }
+    }
+
+    /// The policy applied on release.
+    #[must_use]
+    pub const fn policy(&self) -> LeaseReleasePolicy {
+        self.policy
+    }
+
+    /// Change the policy applied on release.
+    pub const fn set_policy(&mut self, policy: LeaseReleasePolicy) {
+        self.policy = policy;
+    }
+
+    /// Apply the policy now.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if stopping or terminating the pod fails. The guard
+    /// is disarmed either way: a failed release is not retried on drop.
+    pub async fn release(mut self) -> Result<(), OrchestratorError> {
+        self.armed = false;
+        release(&self.orchestrator, &self.lease, self.policy).await
+    }
+
+    /// Give the lease back without touching the pod.
+    #[must_use]
+    pub fn disarm(mut self) -> PodLease {
+        self.armed = false;
+        self.lease.clone()
+    }
+}
+
+impl Deref for LeaseGuard {
+    type Target = PodLease;
+
+    fn deref(&self) -> &PodLease {
+        &self.lease
+    }
+}
+
+impl Drop for LeaseGuard {
+    fn drop(&mut self) {
+        if !self.armed || self.policy == LeaseReleasePolicy::Keep {
+            return;
+        }
+        let name = &self.lease.name;
+
+        // Drop cannot await: hand the release to the caller's runtime.
+        let Ok(handle) = Handle::try_current() else {
+            log_warn!(
+                "lease of pod {name} dropped outside a Tokio runtime: not released ({}); \
+                 call release().await instead",
+                self.policy.as_str()
+            );
+            return;
+        };
+        let orchestrator = Arc::clone(&self.orchestrator);
+        let lease = self.lease.clone();
+        let policy = self.policy;
+        handle.spawn(async move {
+            if let Err(e) = release(&orchestrator, &lease, policy).await {
+                log_warn!(
+                    "releasing dropped lease of pod {} failed: {e}",
+                    lease.name,
+                    e = ErrorChain(&e)
+                );
+            }
+        });
+    }
+}
+
+impl fmt::Debug for LeaseGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeaseGuard")
+            .field("lease", &self.lease)
+            .field("armed", &self.armed)
+            .field("policy", &self.policy)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Apply `policy` to the pod of `lease`.
+async fn release(
+    orchestrator: &RunpodOrchestrator,
+    lease: &PodLease,
+    policy: LeaseReleasePolicy,
+) -> Result<(), OrchestratorError> {
+    match policy {
+        LeaseReleasePolicy::Keep => return Ok(()),
+        LeaseReleasePolicy::Stop => orchestrator.stop_pod(&lease.id).await?,
+        LeaseReleasePolicy::Terminate => {
+            orchestrator.terminate(&lease.id).await?;
+        }
+    }
+    log_info!(
+        "released lease of pod {} ({}): {}",
+        lease.name,
+        lease.id,
+        policy.as_str()
+    );
+    Ok(())
+}
@@ -0,0 +1,1311 @@
+//! Shared HTTP helpers.
+//!
+//! Unique responsibility: types and helpers shared by every HTTP-speaking module
+//! (provisioner, starter, client, orchestrator).
+//!
+//! Currently provides:
+//! - `DryRunRequest`: description of a request that was *not* sent, with secrets redacted
+//! - `HttpOptions`: connection pool / keepalive / HTTP version tuning for every client
+//! - `RetryPolicy`: retry count, backoff and retryable statuses for every client,
+//!   and the accounting of retries (`HTTP_RETRIES` metric, per-call count)
+//! - `ApiKey`: API key wiped from memory on drop and redacted in `Debug`
+//! - `ApiVersion`: REST API version (`RUNPOD_API_VERSION`), its default URL and
+//!   the mapping of request/response bodies to and from its wire format
+//! - Response parsing straight from bytes, element by element for arrays
+//!   (SIMD-accelerated with the `simd-json` feature)
+//! - TLS public key pinning (`RUNPOD_TLS_PINS`), enforced on top of the normal
+//!   certificate validation of every client
+
+use std::{
+    cell::Cell, collections::HashMap, env, fmt, marker::PhantomData, sync::Arc, time::Duration,
+};
+
+use base64::Engine as _;
+use rustls::{
+    CertificateError, DigitallySignedStruct, OtherError, SignatureScheme,
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::CryptoProvider,
+    pki_types::{CertificateDer, ServerName, UnixTime},
+};
+use serde::{
+    Serialize,
+    de::{DeserializeOwned, SeqAccess, Visitor},
+};
+use zeroize::Zeroizing;
+
+use crate::runpod_metrics::{self, MetricsSink};
+
+/// Placeholder used in place of secret values.
+pub const REDACTED: &str = "***";
+
+/// `Debug` view of pod environment variables: the keys, every value `REDACTED`.
+pub(crate) struct RedactedEnv<'a>(pub &'a HashMap<String, String>);
+
+impl fmt::Debug for RedactedEnv<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut keys: Vec<&String> = self.0.keys().collect();
+        keys.sort();
+        f.debug_map()
+            .entries(keys.into_iter().map(|k| (k, REDACTED)))
+            .finish()
+    }
+}
+
+/// `RunPod` API key.
+///
+/// The key bytes are zeroized when each copy is dropped, and `Debug`
+/// never prints the key, so configs holding keys for several accounts can be
+/// logged and discarded safely. Copies made by the HTTP stack (request
+/// headers) are outside this guarantee.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ApiKey(Zeroizing<String>);
+
+impl ApiKey {
+    /// Wrap a key.
+    #[must_use]
+    pub fn new(key: String) -> Self {
+        Self(Zeroizing::new(key))
+    }
+
+    /// Borrow the key, e.g. for an `Authorization` header.
+    #[must_use]
+    pub fn expose(&self) -> &str {
+        self.0.as_str()
+    }
+
+    /// Check whether the key is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for ApiKey {
+    fn from(key: String) -> Self {
+        Self::new(key)
+    }
+}
+
+impl From<&str> for ApiKey {
+    fn from(key: &str) -> Self {
+        Self::new(key.to_string())
+    }
+}
+
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ApiKey({REDACTED})")
+    }
+}
+
+/// A request that would have been sent, captured instead of performed (dry run).
+///
+/// The bearer token and pod environment values are redacted, so the
+/// description can safely be printed in CI logs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DryRunRequest {
+    /// HTTP method ("GET", "POST", ...).
+    pub method: String,
+    /// Full request URL.
+    pub url: String,
+    /// Request headers (secrets redacted).
+    pub headers: Vec<(String, String)>,
+    /// JSON body (secrets redacted), if any.
+    pub body: Option<serde_json::Value>,
+}
+
+impl DryRunRequest {
+    /// Describe an authenticated JSON request without sending it.
+    #[must_use]
+    pub fn new(method: &reqwest::Method, url: &str, body: Option<&serde_json::Value>) -> Self {
+        let mut headers = vec![("Authorization".to_string(), format!("Bearer {REDACTED}"))];
+        if body.is_some() {
+            headers.push(("Content-Type".to_string(), "application/json".to_string()));
+        }
+
+        Self {
+            method: method.as_str().to_string(),
+            url: url.to_string(),
+            headers,
+            body: body.map(|b| {
+                let mut b = b.clone();
+                redact_env_values(&mut b);
+                b
+            }),
+        }
+    }
+}
+
+impl fmt::Display for DryRunRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.method, self.url)?;
+        for (name, value) in &self.headers {
+            write!(f, "\n{name}: {value}")?;
+        }
+        if let Some(body) = &self.body {
+            let pretty = serde_json::to_string_pretty(body).map_err(|_| fmt::Error)?;
+            write!(f, "\n\n{pretty}")?;
+        }
+        Ok(())
+    }
+}
+
+/// `RunPod` REST API version.
+///
+/// The crate's request and response types follow v1. Every version maps
+/// bodies between those types and its own wire format, so supporting a new
+/// version does not change the public types.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ApiVersion {
+    /// REST v1 (`https://rest.runpod.io/v1`).
+    #[default]
+    V1,
+}
+
+/// REST resource a body belongs to, for per-version mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ApiResource {
+    /// A pod (`/pods/{podId}`), a pod update or a pod creation request.
+    Pod,
+    /// A pod list (`GET /pods`).
+    PodList,
+    /// A network volume (`/networkvolumes/{networkVolumeId}`).
+    NetworkVolume,
+    /// A serverless endpoint (`/endpoints/{endpointId}`).
+    Endpoint,
+}
+
+impl ApiVersion {
+    /// Every supported version, oldest first.
+    pub const ALL: [Self; 1] = [Self::V1];
+
+    /// Version name as used in URLs and `RUNPOD_API_VERSION` ("v1").
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+        }
+    }
+
+    /// REST base URL of this version.
+    #[must_use]
+    pub const fn default_rest_url(self) -> &'static str {
+        match self {
+            Self::V1 => "https://rest.runpod.io/v1",
+        }
+    }
+
+    /// Parse a version name ("v1" or "1", case-insensitive).
+    #[must_use]
+    pub fn parse(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|v| name == v.as_str() || name == v.as_str()[1..])
+    }
+
+    /// Load the version from `RUNPOD_API_VERSION` (default: v1).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the version is not supported.
+    pub fn from_env() -> Result<Self, InvalidHttpEnv> {
+        match env::var("RUNPOD_API_VERSION") {
+            Ok(raw) if !raw.trim().is_empty() => Self::parse(&raw).ok_or(InvalidHttpEnv {
+                key: "RUNPOD_API_VERSION",
+                value: raw,
+                reason: "unsupported API version (expected v1)",
+            }),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// `RUNPOD_REST_URL`, or this version's base URL when unset.
+    #[must_use]
+    pub fn rest_url_from_env(self) -> String {
+        env::var("RUNPOD_REST_URL").unwrap_or_else(|_| self.default_rest_url().to_string())
+    }
+
+    /// Serialize a request body for `resource` in this version's wire format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `body` cannot be serialized.
+    pub fn encode<T: Serialize>(
+        self,
+        resource: ApiResource,
+        body: &T,
+    ) -> serde_json::Result<serde_json::Value> {
+        serde_json::to_value(body).map(|value| self.map_request(resource, value))
+    }
+
+    /// Parse a response body for `resource` from this version's wire format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `body` is not valid JSON of the expected shape.
+    pub fn decode<T: DeserializeOwned>(
+        self,
+        resource: ApiResource,
+        body: &str,
+    ) -> serde_json::Result<T> {
+        self.decode_slice(resource, body.as_bytes())
+    }
+
+    /// Parse a response body for `resource` straight from bytes.
+    ///
+    /// Bodies this version does not remap are deserialized directly, without
+    /// an intermediate `String` or `serde_json::Value`. Pass an owned body
+    /// (`Vec<u8>`, `bytes::Bytes`) to let the `simd-json` feature parse it in
+    /// place instead of copying it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `body` is not valid JSON of the expected shape.
+    pub fn decode_slice<T: DeserializeOwned>(
+        self,
+        resource: ApiResource,
+        body: impl AsRef<[u8]> + Into<Vec<u8>>,
+    ) -> serde_json::Result<T> {
+        if !self.remaps_responses(resource) {
+            return from_slice(body);
+        }
+        let value = from_slice(body)?;
+        serde_json::from_value(self.map_response(resource, value))
+    }
+
+    /// Parse a JSON array response for `resource`, handing each element to
+    /// `f` as soon as it is deserialized.
+    ///
+    /// Only one element is held at a time, so callers that keep a few
+    /// elements (e.g. one pod out of hundreds) never build the whole list.
+    /// As with `decode_slice()`, an owned body is parsed in place.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `body` is not a JSON array of the expected elements.
+    pub fn decode_each<T: DeserializeOwned>(
+        self,
+        resource: ApiResource,
+        body: impl AsRef<[u8]> + Into<Vec<u8>>,
+        mut f: impl FnMut(T),
+    ) -> serde_json::Result<()> {
+        if !self.remaps_responses(resource) {
+            return for_each_in_array(body, f);
+        }
+        let items: Vec<serde_json::Value> =
+            serde_json::from_value(self.map_response(resource, from_slice(body)?))?;
+        for item in items {
+            f(serde_json::from_value(item)?);
+        }
+        Ok(())
+    }
+
+    /// Whether this version's responses for `resource` differ from v1.
+    const fn remaps_responses(self, _resource: ApiResource) -> bool {
+        match self {
+            Self::V1 => false,
+        }
+    }
+
+    /// Map a v1-shaped request body to this version's wire format.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // Later versions rewrite the body.
+    pub fn map_request(self, resource: ApiResource, body: serde_json::Value) -> serde_json::Value {
+        match (self, resource) {
+            (Self::V1, _) => body,
+        }
+    }
+
+    /// Map a response body in this version's wire format to the v1 shape.
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)] // Later versions rewrite the body.
+    pub fn map_response(self, resource: ApiResource, body: serde_json::Value) -> serde_json::Value {
+        match (self, resource) {
+            (Self::V1, _) => body,
+        }
+    }
+}
+
+/// Parse a JSON body from bytes.
+///
+/// With the `simd-json` feature the body is parsed in place with SIMD
+/// instructions (borrowed bodies are copied into a scratch buffer first).
+#[cfg_attr(not(feature = "simd-json"), allow(clippy::needless_pass_by_value))]
+fn from_slice<T: DeserializeOwned>(
+    body: impl AsRef<[u8]> + Into<Vec<u8>>,
+) -> serde_json::Result<T> {
+    #[cfg(feature = "simd-json")]
+    {
+        let mut scratch: Vec<u8> = body.into();
+        simd_json::serde::from_slice(&mut scratch).map_err(serde::de::Error::custom)
+    }
+    #[cfg(not(feature = "simd-json"))]
+    {
+        serde_json::from_slice(body.as_ref())
+    }
+}
+
+/// Deserialize the elements of a JSON array body one by one into `f`.
+#[cfg_attr(not(feature = "simd-json"), allow(clippy::needless_pass_by_value))]
+fn for_each_in_array<T: DeserializeOwned>(
+    body: impl AsRef<[u8]> + Into<Vec<u8>>,
+    f: impl FnMut(T),
+) -> serde_json::Result<()> {
+    let visitor = EachElement {
+        f,
+        element: PhantomData,
+    };
+    #[cfg(feature = "simd-json")]
+    {
+        let mut scratch: Vec<u8> = body.into();
+        let mut de =
+            simd_json::Deserializer::from_slice(&mut scratch).map_err(serde::de::Error::custom)?;
+        serde::Deserializer::deserialize_seq(&mut de, visitor).map_err(serde::de::Error::custom)
+    }
+    #[cfg(not(feature = "simd-json"))]
+    {
+        let mut de = serde_json::Deserializer::from_slice(body.as_ref());
+        serde::Deserializer::deserialize_seq(&mut de, visitor)?;
+        de.end()
+    }
+}
+
+/// Sequence visitor feeding each element to a callback instead of a `Vec`.
+struct EachElement<F, T> {
+    f: F,
+    element: PhantomData<T>,
+}
+
+impl<'de, F: FnMut(T), T: DeserializeOwned> Visitor<'de> for EachElement<F, T> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a JSON array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(element) = seq.next_element::<T>()? {
+            (self.f)(element);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Preferred HTTP protocol version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HttpVersion {
+    /// Negotiate (HTTP/2 via ALPN when the server supports it).
+    #[default]
+    Auto,
+    /// HTTP/1.1 only.
+    Http1,
+    /// HTTP/2 only (prior knowledge, no negotiation).
+    Http2,
+}
+
+/// Connection tuning shared by every HTTP client of this crate.
+///
+/// Unset fields keep reqwest's defaults. Long-lived orchestrators usually
+/// want idle connections kept long enough to survive between polls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpOptions {
+    /// Maximum idle connections kept per host.
+    /// Env: `RUNPOD_HTTP_POOL_MAX_IDLE` (optional)
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// How long an idle pooled connection is kept, in milliseconds.
+    /// Env: `RUNPOD_HTTP_POOL_IDLE_TIMEOUT_MS` (optional)
+    pub pool_idle_timeout_ms: Option<u64>,
+
+    /// TCP keepalive interval in milliseconds.
+    /// Env: `RUNPOD_HTTP_TCP_KEEPALIVE_MS` (optional)
+    pub tcp_keepalive_ms: Option<u64>,
+
+    /// Maximum time to establish a connection, in milliseconds, so dead
+    /// hosts fail fast instead of waiting for the total request timeout.
+    /// Env: `RUNPOD_HTTP_CONNECT_TIMEOUT_MS` (optional)
+    pub connect_timeout_ms: Option<u64>,
+
+    /// Maximum time between two reads of a response, in milliseconds.
+    /// Unlike the total timeout, a slow but progressing download never
+    /// trips it.
+    /// Env: `RUNPOD_HTTP_READ_TIMEOUT_MS` (optional)
+    pub read_timeout_ms: Option<u64>,
+
+    /// Preferred HTTP version.
+    /// Env: `RUNPOD_HTTP_VERSION` (default: "auto")
+    /// Options: "auto", "http1", "http2"
+    pub version: HttpVersion,
+
+    /// Pinned public keys: `sha256/<base64>` hashes of a certificate's
+    /// `SubjectPublicKeyInfo` (the format of `curl --pinnedpubkey`).
+    /// A connection is accepted only if its chain validates normally *and*
+    /// one of its certificates (leaf or intermediate) matches a pin.
+    /// Env: `RUNPOD_TLS_PINS` (optional, comma-separated)
+    pub tls_pins: Vec<String>,
+
+    /// Hosts the pins apply to (each also covers its subdomains).
+    /// Other hosts (e.g. container registries) are validated normally.
+    /// Env: `RUNPOD_TLS_PIN_HOSTS` (default: "runpod.io,runpod.ai", the
+    /// serverless API being on `api.runpod.ai`)
+    pub tls_pin_hosts: Vec<String>,
+}
+
+/// An HTTP tuning environment variable has an invalid value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidHttpEnv {
+    /// The environment variable key.
+    pub key: &'static str,
+    /// The environment variable value.
+    pub value: String,
+    /// The reason for invalidity.
+    pub reason: &'static str,
+}
+
+impl fmt::Display for InvalidHttpEnv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid env var {}: {}", self.key, self.reason)
+    }
+}
+
+impl std::error::Error for InvalidHttpEnv {}
+
+/// The shared HTTP client could not be built.
+#[derive(Debug)]
+pub enum HttpClientError {
+    /// The options are invalid (e.g. a TLS pin configuration rustls rejects).
+    InvalidEnv(InvalidHttpEnv),
+    /// HTTP client error.
+    Http(reqwest::Error),
+}
+
+impl fmt::Display for HttpClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEnv(e) => write!(f, "{e}"),
+            Self::Http(e) => fmt_http_error(f, e),
+        }
+    }
+}
+
+impl std::error::Error for HttpClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidEnv(e) => Some(e),
+            Self::Http(e) => Some(e),
+        }
+    }
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: None,
+            pool_idle_timeout_ms: None,
+            tcp_keepalive_ms: None,
+            connect_timeout_ms: None,
+            read_timeout_ms: None,
+            version: HttpVersion::Auto,
+            tls_pins: Vec::new(),
+            tls_pin_hosts: DEFAULT_PIN_HOSTS.map(str::to_string).to_vec(),
+        }
+    }
+}
+
+/// Hosts (and subdomains) TLS pins apply to by default.
+const DEFAULT_PIN_HOSTS: [&str; 2] = ["runpod.io", "runpod.ai"];
+
+impl HttpOptions {
+    /// Load HTTP tuning options from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is set to an invalid value.
+    pub fn from_env() -> Result<Self, InvalidHttpEnv> {
+        let raw_version = env::var("RUNPOD_HTTP_VERSION").unwrap_or_default();
+        let version = match raw_version.trim().to_lowercase().as_str() {
+            "" | "auto" => HttpVersion::Auto,
+            "http1" | "1" | "http/1.1" => HttpVersion::Http1,
+            "http2" | "2" | "h2" => HttpVersion::Http2,
+            _ => {
+                return Err(InvalidHttpEnv {
+                    key: "RUNPOD_HTTP_VERSION",
+                    value: raw_version,
+                    reason: "expected auto, http1 or http2",
+                });
+            }
+        };
+
+        let tls_pins = split_csv_env("RUNPOD_TLS_PINS");
+        if let Some(bad) = tls_pins.iter().find(|pin| decode_pin(pin).is_none()) {
+            return Err(invalid_pin(bad));
+        }
+
+        let mut tls_pin_hosts = split_csv_env("RUNPOD_TLS_PIN_HOSTS");
+        if tls_pin_hosts.is_empty() {
+            tls_pin_hosts = DEFAULT_PIN_HOSTS.map(str::to_string).to_vec();
+        }
+
+        Ok(Self {
+            pool_max_idle_per_host: parse_opt_env("RUNPOD_HTTP_POOL_MAX_IDLE")?,
+            pool_idle_timeout_ms: parse_opt_env("RUNPOD_HTTP_POOL_IDLE_TIMEOUT_MS")?,
+            tcp_keepalive_ms: parse_opt_env("RUNPOD_HTTP_TCP_KEEPALIVE_MS")?,
+            connect_timeout_ms: parse_opt_env("RUNPOD_HTTP_CONNECT_TIMEOUT_MS")?,
+            read_timeout_ms: parse_opt_env("RUNPOD_HTTP_READ_TIMEOUT_MS")?,
+            version,
+            tls_pins,
+            tls_pin_hosts,
+        })
+    }
+
+    /// Apply the options to a client builder.
+    ///
+    /// With TLS pins configured, the client uses a rustls configuration whose
+    /// verifier enforces them: pinning never silently falls back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error (key `RUNPOD_TLS_PINS`) if a pin is malformed or the
+    /// pinned TLS configuration cannot be built.
+    pub fn apply(
+        &self,
+        mut builder: reqwest::ClientBuilder,
+    ) -> Result<reqwest::ClientBuilder, InvalidHttpEnv> {
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(ms) = self.pool_idle_timeout_ms {
+            builder = builder.pool_idle_timeout(Duration::from_millis(ms));
+        }
+        if let Some(ms) = self.tcp_keepalive_ms {
+            builder = builder.tcp_keepalive(Duration::from_millis(ms));
+        }
+        if let Some(ms) = self.connect_timeout_ms {
+            builder = builder.connect_timeout(Duration::from_millis(ms));
+        }
+        if let Some(ms) = self.read_timeout_ms {
+            builder = builder.read_timeout(Duration::from_millis(ms));
+        }
+        if !self.tls_pins.is_empty() {
+            builder = builder.tls_backend_preconfigured(self.pinned_tls_config()?);
+        }
+        Ok(match self.version {
+            HttpVersion::Auto => builder,
+            HttpVersion::Http1 => builder.http1_only(),
+            HttpVersion::Http2 => builder.http2_prior_knowledge(),
+        })
+    }
+
+    /// Client builder with these options and a total request timeout of
+    /// `timeout_ms` (0 = no total timeout: connect/read timeouts only).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the options cannot be applied (see `apply()`).
+    pub fn client_builder(
+        &self,
+        timeout_ms: u64,
+    ) -> Result<reqwest::ClientBuilder, InvalidHttpEnv> {
+        let builder = self.apply(reqwest::Client::builder())?;
+        Ok(match total_timeout(timeout_ms) {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        })
+    }
+
+    /// Client with these options and no total timeout, to share between the
+    /// orchestrator, provisioner, starter and GraphQL client (their
+    /// `new_with_http()`): connections and TLS sessions are reused, and each
+    /// component applies its own `timeout_ms` per request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the options cannot be applied or the client
+    /// cannot be built.
+    pub fn shared_client(&self) -> Result<reqwest::Client, HttpClientError> {
+        self.client_builder(0)
+            .map_err(HttpClientError::InvalidEnv)?
+            .build()
+            .map_err(HttpClientError::Http)
+    }
+
+    fn pinned_tls_config(&self) -> Result<rustls::ClientConfig, InvalidHttpEnv> {
+        let provider = tls_provider();
+        let inner = rustls_platform_verifier::Verifier::new(provider.clone());
+        self.pinned_tls_config_with(provider, inner)
+    }
+
+    fn pinned_tls_config_with(
+        &self,
+        provider: Arc<CryptoProvider>,
+        inner: Result<rustls_platform_verifier::Verifier, rustls::Error>,
+    ) -> Result<rustls::ClientConfig, InvalidHttpEnv> {
+        let pins = self
+            .tls_pins
+            .iter()
+            .map(|pin| decode_pin(pin).ok_or_else(|| invalid_pin(pin)))
+            .collect::<Result<_, _>>()?;
+        let verifier = PinnedVerifier {
+            inner,
+            provider: provider.clone(),
+            pins,
+            hosts: self
+                .tls_pin_hosts
+                .iter()
+                .map(|h| h.trim().trim_start_matches("*.").to_lowercase())
+                .collect(),
+        };
+
+        let mut config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|_| InvalidHttpEnv {
+                key: "RUNPOD_TLS_PINS",
+                value: self.tls_pins.join(","),
+                reason: "the crypto provider supports no TLS version for the pinned configuration",
+            })?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth();
+
+        // reqwest does not negotiate ALPN for preconfigured backends.
+        config.alpn_protocols = match self.version {
+            HttpVersion::Auto => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+            HttpVersion::Http1 => vec![b"http/1.1".to_vec()],
+            HttpVersion::Http2 => vec![b"h2".to_vec()],
+        };
+        Ok(config)
+    }
+}
+
+/// Same provider selection as reqwest's own rustls backend.
+fn tls_provider() -> Arc<CryptoProvider> {
+    CryptoProvider::get_default()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(rustls::crypto::aws_lc_rs::default_provider()))
+}
+
+/// How the delay between retries grows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackoffStrategy {
+    /// Double the delay after each retry.
+    #[default]
+    Exponential,
+    /// Keep the same delay for every retry.
+    Constant,
+}
+
+/// Retry policy shared by every HTTP client of this crate.
+///
+/// Load it once (`from_env()`) and inject the same value into the client,
+/// starter, provisioner and orchestrator configs. Only transient failures
+/// are retried: timeouts, connection errors and `retryable_statuses`.
+///
+/// Non-idempotent requests (`POST`/`PATCH`, `GraphQL` mutations) may already
+/// have taken effect when they time out or get a 5xx or 409 back, so they are
+/// only retried when the server provably did not act on them: connection
+/// failures and `425`/`429` responses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt (0 disables retries).
+    /// Env: `RUNPOD_HTTP_RETRY_MAX` (default: 3)
+    pub max_retries: u32,
+
+    /// Delay before the first retry, in milliseconds.
+    /// Env: `RUNPOD_HTTP_RETRY_BACKOFF_MS` (default: 500)
+    pub base_delay_ms: u64,
+
+    /// How the delay grows between retries.
+    /// Env: `RUNPOD_HTTP_RETRY_STRATEGY` (default: "exponential")
+    /// Options: "exponential", "constant"
+    pub strategy: BackoffStrategy,
+
+    /// Upper bound of a single delay, in milliseconds.
+    /// Env: `RUNPOD_HTTP_RETRY_MAX_BACKOFF_MS` (default: 10000)
+    pub max_delay_ms: u64,
+
+    /// Fraction of each delay randomly taken off (0.0 = none, 1.0 = full jitter),
+    /// so that many clients failing together do not retry in lockstep.
+    /// Env: `RUNPOD_HTTP_RETRY_JITTER` (default: 0.0)
+    pub jitter: f64,
+
+    /// HTTP statuses worth retrying.
+    /// Env: `RUNPOD_HTTP_RETRY_STATUSES` (default: "408,409,425,429,500,502,503,504")
+    pub retryable_statuses: Vec<u16>,
+}
+
+/// Statuses retried by default.
+const DEFAULT_RETRYABLE_STATUSES: [u16; 8] = [408, 409, 425, 429, 500, 502, 503, 504];
+
+/// Statuses meaning the server did not process the request at all.
+const UNPROCESSED_STATUSES: [u16; 2] = [425, 429];
+
+/// Whether sending a `method` request twice has the same effect as sending it once.
+#[must_use]
+pub fn is_idempotent(method: &reqwest::Method) -> bool {
+    use reqwest::Method;
+
+    [
+        Method::GET,
+        Method::HEAD,
+        Method::OPTIONS,
+        Method::PUT,
+        Method::DELETE,
+    ]
+    .contains(method)
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay_ms: 500,
+            strategy: BackoffStrategy::Exponential,
+            max_delay_ms: 10_000,
+            jitter: 0.0,
+            retryable_statuses: DEFAULT_RETRYABLE_STATUSES.to_vec(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    #[must_use]
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Load the retry policy from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is set to an invalid value.
+    pub fn from_env() -> Result<Self, InvalidHttpEnv> {
+        let defaults = Self::default();
+
+        let raw_strategy = env::var("RUNPOD_HTTP_RETRY_STRATEGY").unwrap_or_default();
+        let strategy = match raw_strategy.trim().to_lowercase().as_str() {
+            "" | "exponential" => BackoffStrategy::Exponential,
+            "constant" => BackoffStrategy::Constant,
+            _ => {
+                return Err(InvalidHttpEnv {
+                    key: "RUNPOD_HTTP_RETRY_STRATEGY",
+                    value: raw_strategy,
+                    reason: "expected exponential or constant",
+                });
+            }
+        };
+
+        let jitter = match env::var("RUNPOD_HTTP_RETRY_JITTER") {
+            Ok(v) if !v.trim().is_empty() => match v.trim().parse::<f64>() {
+                Ok(j) if (0.0..=1.0).contains(&j) => j,
+                _ => {
+                    return Err(InvalidHttpEnv {
+                        key: "RUNPOD_HTTP_RETRY_JITTER",
+                        value: v,
+                        reason: "expected a number between 0.0 and 1.0",
+                    });
+                }
+            },
+            _ => defaults.jitter,
+        };
+
+        let mut retryable_statuses = Vec::new();
+        for status in split_csv_env("RUNPOD_HTTP_RETRY_STATUSES") {
+            match status.parse::<u16>() {
+                Ok(code) if (100..600).contains(&code) => retryable_statuses.push(code),
+                _ => {
+                    return Err(InvalidHttpEnv {
+                        key: "RUNPOD_HTTP_RETRY_STATUSES",
+                        value: status,
+                        reason: "expected comma-separated HTTP status codes",
+                    });
+                }
+            }
+        }
+        if retryable_statuses.is_empty() {
+            retryable_statuses = defaults.retryable_statuses;
+        }
+
+        Ok(Self {
+            max_retries: parse_opt_env("RUNPOD_HTTP_RETRY_MAX")?.unwrap_or(defaults.max_retries),
+            base_delay_ms: parse_opt_env("RUNPOD_HTTP_RETRY_BACKOFF_MS")?
+                .unwrap_or(defaults.base_delay_ms),
+            strategy,
+            max_delay_ms: parse_opt_env("RUNPOD_HTTP_RETRY_MAX_BACKOFF_MS")?
+                .unwrap_or(defaults.max_delay_ms),
+            jitter,
+            retryable_statuses,
+        })
+    }
+
+    /// Check whether a response with `status` to attempt number `attempt`
+    /// (1 for the first request) should be retried.
+    ///
+    /// A non-`idempotent` request is only retried on `425` and `429`.
+    #[must_use]
+    pub fn retries_status(
+        &self,
+        attempt: u32,
+        status: reqwest::StatusCode,
+        idempotent: bool,
+    ) -> bool {
+        attempt <= self.max_retries
+            && self.retryable_statuses.contains(&status.as_u16())
+            && (idempotent || UNPROCESSED_STATUSES.contains(&status.as_u16()))
+    }
+
+    /// Check whether a transport error on attempt number `attempt` should be retried.
+    ///
+    /// A non-`idempotent` request is only retried if the connection failed,
+    /// i.e. the request never reached the server.
+    #[must_use]
+    pub fn retries_error(&self, attempt: u32, err: &reqwest::Error, idempotent: bool) -> bool {
+        attempt <= self.max_retries
+            && (err.is_connect() || (idempotent && (err.is_timeout() || err.is_request())))
+    }
+
+    /// Delay to wait after failed attempt number `attempt` (1 for the first request).
+    #[must_use]
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let base = Duration::from_millis(self.base_delay_ms);
+        let grown = match self.strategy {
+            BackoffStrategy::Constant => base,
+            BackoffStrategy::Exponential => {
+                let doublings = attempt.saturating_sub(1).min(31);
+                base.saturating_mul(1 << doublings)
+            }
+        };
+        let capped = grown.min(Duration::from_millis(self.max_delay_ms));
+
+        if self.jitter <= 0.0 {
+            return capped;
+        }
+        capped.mul_f64(self.jitter.min(1.0).mul_add(-random_unit(), 1.0))
+    }
+}
+
+/// Pseudo-random number in `[0, 1)`, good enough to spread retries.
+fn random_unit() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+
+    let bits = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish()
+        >> 11;
+    #[allow(clippy::cast_precision_loss)]
+    let unit = bits as f64 / (1_u64 << 53) as f64;
+    unit
+}
+
+/// The server's certificate chain validated, but matched none of the configured pins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsPinMismatch {
+    /// Host that presented the chain.
+    pub host: String,
+    /// `sha256/<base64>` pins of every certificate in the presented chain.
+    pub presented: Vec<String>,
+}
+
+impl fmt::Display for TlsPinMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tls pin mismatch for {}: presented chain [{}] matches none of RUNPOD_TLS_PINS",
+            self.host,
+            self.presented.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for TlsPinMismatch {}
+
+/// Find a `TlsPinMismatch` in the source chain of an HTTP error.
+///
+/// reqwest's own message for a rejected handshake is generic; error types of
+/// this crate use this to report the pin mismatch instead.
+#[must_use]
+pub fn tls_pin_mismatch(err: &reqwest::Error) -> Option<TlsPinMismatch> {
+    let mut source = std::error::Error::source(err);
+    while let Some(e) = source {
+        if let Some(mismatch) = e.downcast_ref::<rustls::Error>().and_then(pin_mismatch_of) {
+            return Some(mismatch);
+        }
+        // The TLS stream reports a failed handshake as (possibly nested)
+        // `io::Error`s wrapping the `rustls::Error`, and `io::Error::source()`
+        // skips the error it wraps.
+        source = e
+            .downcast_ref::<std::io::Error>()
+            .and_then(std::io::Error::get_ref)
+            .map_or_else(|| e.source(), |inner| Some(inner));
+    }
+    None
+}
+
+fn pin_mismatch_of(err: &rustls::Error) -> Option<TlsPinMismatch> {
+    match err {
+        rustls::Error::InvalidCertificate(CertificateError::Other(OtherError(other))) => {
+            other.downcast_ref::<TlsPinMismatch>().cloned()
+        }
+        _ => None,
+    }
+}
+
+tokio::task_local! {
+    /// HTTP retries made by the current task, while counted (see `count_retries()`).
+    static RETRIES: Cell<u32>;
+}
+
+/// Account for one HTTP retry by `component` ("orchestrator", "client", ...):
+/// the `HTTP_RETRIES` metric, and the count of the current task if it is
+/// being counted.
+pub(crate) fn record_retry(metrics: &dyn MetricsSink, component: &str) {
+    metrics.counter(runpod_metrics::HTTP_RETRIES, 1, &[("component", component)]);
+    let _ = RETRIES.try_with(|count| count.set(count.get().saturating_add(1)));
+}
+
+/// Run `future`, counting the HTTP retries it makes on the current task.
+pub(crate) async fn count_retries<T>(future: impl Future<Output = T>) -> (T, u32) {
+    RETRIES
+        .scope(Cell::new(0), async {
+            let output = future.await;
+            (output, RETRIES.with(Cell::get))
+        })
+        .await
+}
+
+/// Total request timeout of a component (`timeout_ms`, 0 = none).
+pub(crate) const fn total_timeout(timeout_ms: u64) -> Option<Duration> {
+    if timeout_ms == 0 {
+        None
+    } else {
+        Some(Duration::from_millis(timeout_ms))
+    }
+}
+
+/// Apply a component's total timeout to a request, so it holds on a shared
+/// client too.
+pub(crate) fn with_timeout(
+    req: reqwest::RequestBuilder,
+    timeout_ms: u64,
+) -> reqwest::RequestBuilder {
+    match total_timeout(timeout_ms) {
+        Some(timeout) => req.timeout(timeout),
+        None => req,
+    }
+}
+
+/// Format an HTTP error, surfacing a TLS pin mismatch if that is the cause.
+pub(crate) fn fmt_http_error(f: &mut fmt::Formatter<'_>, err: &reqwest::Error) -> fmt::Result {
+    match tls_pin_mismatch(err) {
+        Some(mismatch) => write!(f, "http error: {mismatch}"),
+        None => write!(f, "http request failed"),
+    }
+}
+
+/// Certificate verifier enforcing SPKI pins after normal validation.
+#[derive(Debug)]
+struct PinnedVerifier {
+    inner: Result<rustls_platform_verifier::Verifier, rustls::Error>,
+    provider: Arc<CryptoProvider>,
+    pins: Vec<[u8; 32]>,
+    hosts: Vec<String>,
+}
+
+impl PinnedVerifier {
+    fn applies_to(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        self.hosts
+            .iter()
+            .any(|h| host == *h || host.ends_with(&format!(".{h}")))
+    }
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let verified = self
+            .inner
+            .as_ref()
+            .map_err(Clone::clone)?
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)?;
+
+        let host = server_name.to_str();
+        if !self.applies_to(&host) {
+            return Ok(verified);
+        }
+
+        let hashes: Vec<[u8; 32]> = std::iter::once(end_entity)
+            .chain(intermediates)
+            .filter_map(spki_sha256)
+            .collect();
+        if hashes.iter().any(|h| self.pins.contains(h)) {
+            return Ok(verified);
+        }
+
+        let mismatch = TlsPinMismatch {
+            host: host.into_owned(),
+            presented: hashes.iter().map(encode_pin).collect(),
+        };
+        Err(CertificateError::Other(OtherError(Arc::new(mismatch))).into())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// SHA-256 of a certificate's DER `SubjectPublicKeyInfo`.
+fn spki_sha256(cert: &CertificateDer<'_>) -> Option<[u8; 32]> {
+    let parsed = webpki::EndEntityCert::try_from(cert).ok()?;
+    let digest = aws_lc_rs::digest::digest(
+        &aws_lc_rs::digest::SHA256,
+        parsed.subject_public_key_info().as_ref(),
+    );
+    digest.as_ref().try_into().ok()
+}
+
+fn invalid_pin(pin: &str) -> InvalidHttpEnv {
+    InvalidHttpEnv {
+        key: "RUNPOD_TLS_PINS",
+        value: pin.to_string(),
+        reason: "expected sha256/<base64 of the SPKI SHA-256 hash>",
+    }
+}
+
+/// Decode a `sha256/<base64>` pin (the prefix is optional).
+fn decode_pin(pin: &str) -> Option<[u8; 32]> {
+    let b64 = pin
+        .trim()
+        .strip_prefix("sha256/")
+        .unwrap_or_else(|| pin.trim());
+    base64::engine::general_purpose::STANDARD
+        .decode(b64)
+        .ok()?
+        .try_into()
+        .ok()
+}
+
+fn encode_pin(hash: &[u8; 32]) -> String {
+    format!(
+        "sha256/{}",
+        base64::engine::general_purpose::STANDARD.encode(hash)
+    )
+}
+
+fn split_csv_env(key: &str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse_opt_env<T: std::str::FromStr>(key: &'static str) -> Result<Option<T>, InvalidHttpEnv> {
+    env::var(key)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map_or(Ok(None), |v| {
+            v.trim().parse::<T>().map(Some).map_err(|_| InvalidHttpEnv {
+                key,
+                value: v,
+                reason: "expected an unsigned integer",
+            })
+        })
+}
+
+/// Replace the values of every `env` object/array in a JSON body with `REDACTED`.
+///
+/// Handles both the REST shape (`{"env": {"KEY": "value"}}`) and the GraphQL
+/// shape (`{"env": [{"key": "KEY", "value": "value"}]}`).
+pub fn redact_env_values(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if key == "env" {
+                    redact_env(child);
+                } else {
+                    redact_env_values(child);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_env_values),
+        _ => {}
+    }
+}
+
+fn redact_env(env: &mut serde_json::Value) {
+    match env {
+        serde_json::Value::Object(map) => {
+            for v in map.values_mut() {
+                *v = serde_json::Value::String(REDACTED.to_string());
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                if let Some(v) = item.get_mut("value") {
+                    *v = serde_json::Value::String(REDACTED.to_string());
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// The extra trust root of the test CA goes through the platform verifier,
+// whose Apple and Android backends add their own policy checks.
+#[cfg(all(test, target_os = "linux"))]
+#[allow(clippy::unwrap_used, clippy::expect_used, clippy::panic)]
+mod tests {
+    use std::net::{SocketAddr, TcpListener};
+
+    use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+
+    use super::*;
+    use crate::runpod_serverless::{ServerlessClient, ServerlessConfig, ServerlessError};
+
+    const CA: &[u8] = include_bytes!("../tests/fixtures/tls/ca.der");
+    const LEAF: &[u8] = include_bytes!("../tests/fixtures/tls/leaf.der");
+    const LEAF_KEY: &[u8] = include_bytes!("../tests/fixtures/tls/leaf.key.der");
+
+    /// Accept one connection on a local port and run the server side of a
+    /// TLS handshake presenting the `localhost` fixture chain.
+    fn serve_one_handshake() -> SocketAddr {
+        let config = rustls::ServerConfig::builder_with_provider(tls_provider())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![CertificateDer::from(LEAF), CertificateDer::from(CA)],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(LEAF_KEY)),
+            )
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut tcp, _) = listener.accept().unwrap();
+            let mut conn = rustls::ServerConnection::new(Arc::new(config)).unwrap();
+            while conn.is_handshaking() && conn.complete_io(&mut tcp).is_ok() {}
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn pin_mismatch_is_reported_from_a_failed_handshake() {
+        let options = HttpOptions {
+            tls_pins: vec![encode_pin(&[0; 32])],
+            tls_pin_hosts: vec!["localhost".to_string()],
+            ..HttpOptions::default()
+        };
+        let provider = tls_provider();
+        let inner = rustls_platform_verifier::Verifier::new_with_extra_roots(
+            [CertificateDer::from(CA).into_owned()],
+            provider.clone(),
+        );
+        let tls = options.pinned_tls_config_with(provider, inner).unwrap();
+
+        let addr = serve_one_handshake();
+        let client = reqwest::Client::builder()
+            .tls_backend_preconfigured(tls)
+            .resolve("localhost", addr)
+            .build()
+            .unwrap();
+        let err = client
+            .get(format!("https://localhost:{}/", addr.port()))
+            .send()
+            .await
+            .unwrap_err();
+
+        let mismatch = tls_pin_mismatch(&err).expect("pin mismatch in the error chain");
+        assert_eq!(mismatch.host, "localhost");
+        let leaf_pin = encode_pin(&spki_sha256(&CertificateDer::from(LEAF)).unwrap());
+        assert_eq!(mismatch.presented.first(), Some(&leaf_pin));
+        assert_eq!(mismatch.presented.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn serverless_client_refuses_an_unpinned_chain() {
+        let options = HttpOptions {
+            tls_pins: vec![encode_pin(&[0; 32])],
+            ..HttpOptions::default()
+        };
+        let provider = tls_provider();
+        let inner = rustls_platform_verifier::Verifier::new_with_extra_roots(
+            [CertificateDer::from(CA).into_owned()],
+            provider.clone(),
+        );
+        let tls = options.pinned_tls_config_with(provider, inner).unwrap();
+
+        let addr = serve_one_handshake();
+        let http = reqwest::Client::builder()
+            .tls_backend_preconfigured(tls)
+            .resolve("api.runpod.ai", addr)
+            .build()
+            .unwrap();
+        let cfg = ServerlessConfig {
+            api_key: "test-key".into(),
+            api_version: ApiVersion::V1,
+            rest_url: ApiVersion::V1.default_rest_url().to_string(),
+            api_url: format!("https://api.runpod.ai:{}/v2", addr.port()),
+            timeout_ms: 10_000,
+            retry: RetryPolicy::none(),
+            http: options,
+        };
+        let client = ServerlessClient::new_with_http(cfg, http);
+
+        let Err(ServerlessError::Http(err)) = client.health("endpoint").await else {
+            panic!("expected an HTTP error");
+        };
+        let mismatch = tls_pin_mismatch(&err).expect("pin mismatch in the error chain");
+        assert_eq!(mismatch.host, "api.runpod.ai");
+    }
+}
@@ -0,0 +1,319 @@
+//! Idle-GPU auto-stop.
+//!
+//! Unique responsibility: watch the GPU utilization of the configured pod in
+//! the background and stop (or terminate) it once it has stayed below a
+//! threshold for long enough, so a forgotten pod stops billing.
+//!
+//! Utilization is the highest `gpuUtilPercent` of the pod's GPUs, read with
+//! the GraphQL client every `poll_interval_ms`. The idle timer restarts
+//! whenever a GPU goes over `threshold_percent`, and while the pod is not
+//! running or reports no GPU metrics (e.g. still booting). The pod is looked
+//! up by name on every poll, so a replaced pod keeps being watched, with a
+//! fresh idle timer.
+//!
+//! ```ignore
+//! let orchestrator = Arc::new(RunpodOrchestrator::new(cfg)?);
+//! let monitor = orchestrator.enable_idle_stop(IdlePolicy::from_env()?);
+//!
+//! if let Some(since) = monitor.idle_since_ms() {
+//!     println!("idle since {since}, last at {:?}%", monitor.last_utilization());
+//! }
+//! println!("{} pods stopped for idling", monitor.stops());
+//! ```
+
+use std::{
+    env, fmt,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicU32, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::task::JoinHandle;
+
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_log::{ErrorChain, log_debug, log_info, log_warn};
+use crate::runpod_orchestrator::RunpodOrchestrator;
+use crate::runpod_state::now_unix_ms;
+
+/// What to do with a pod that stayed idle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdleAction {
+    /// Stop the pod (it can be restarted, its volume is kept).
+    #[default]
+    Stop,
+    /// Terminate the pod.
+    Terminate,
+}
+
+impl IdleAction {
+    /// Parse "stop" or "terminate" (case-insensitive).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "stop" => Some(Self::Stop),
+            "terminate" => Some(Self::Terminate),
+            _ => None,
+        }
+    }
+
+    /// Action name (as parsed).
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Stop => "stop",
+            Self::Terminate => "terminate",
+        }
+    }
+}
+
+/// When an idle pod is stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdlePolicy {
+    /// How long the GPUs must stay below the threshold, in milliseconds.
+    /// Env: `RUNPOD_IDLE_STOP_AFTER_MS` (default: 1800000)
+    pub stop_after_ms: u64,
+
+    /// Utilization (in %) under which a GPU counts as idle.
+    /// Env: `RUNPOD_IDLE_THRESHOLD_PERCENT` (default: 5)
+    pub threshold_percent: f32,
+
+    /// Delay between two utilization reads in milliseconds.
+    /// Env: `RUNPOD_IDLE_POLL_INTERVAL_MS` (default: 60000)
+    pub poll_interval_ms: u64,
+
+    /// What to do once the pod stayed idle.
+    /// Env: `RUNPOD_IDLE_ACTION` (default: "stop")
+    pub action: IdleAction,
+}
+
+impl Default for IdlePolicy {
+    fn default() -> Self {
+        Self {
+            stop_after_ms: 1_800_000,
+            threshold_percent: 5.0,
+            poll_interval_ms: 60_000,
+            action: IdleAction::Stop,
+        }
+    }
+}
+
+impl IdlePolicy {
+    /// Load the policy from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the env-file or a variable is invalid.
+    pub fn from_env() -> Result<Self, IdleError> {
+        runpod_env::load_dotenv().map_err(IdleError::EnvFile)?;
+
+        let threshold_percent = env::var("RUNPOD_IDLE_THRESHOLD_PERCENT").map_or(Ok(5.0), |v| {
+            v.trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|p| (0.0..=100.0).contains(p))
+                .ok_or(IdleError::InvalidEnv {
+                    key: "RUNPOD_IDLE_THRESHOLD_PERCENT",
+                    reason: "expected a percentage between 0 and 100",
+                })
+        })?;
+        let action = env::var("RUNPOD_IDLE_ACTION")
+            .map_or(Some(IdleAction::Stop), |v| IdleAction::parse(&v))
+            .ok_or(IdleError::InvalidEnv {
+                key: "RUNPOD_IDLE_ACTION",
+                reason: "expected stop or terminate",
+            })?;
+
+        Ok(Self {
+            stop_after_ms: parse_u64_env("RUNPOD_IDLE_STOP_AFTER_MS", 1_800_000)?,
+            threshold_percent,
+            poll_interval_ms: parse_u64_env("RUNPOD_IDLE_POLL_INTERVAL_MS", 60_000)?,
+            action,
+        })
+    }
+}
+
+/// State shared between a monitor and its background task.
+#[derive(Default)]
+struct Shared {
+    /// When the GPUs went idle (ms since UNIX epoch, 0 = busy).
+    idle_since_ms: AtomicU64,
+    last_utilization: Mutex<Option<f32>>,
+    stops: AtomicU32,
+}
+
+/// Background idle-GPU auto-stop of the configured pod
+/// (see `RunpodOrchestrator::enable_idle_stop()`).
+///
+/// The task stops when the monitor is dropped (or `stop()` is called).
+pub struct IdleMonitor {
+    shared: Arc<Shared>,
+    task: JoinHandle<()>,
+}
+
+impl IdleMonitor {
+    /// Start watching `orchestrator`'s pod with `policy`.
+    ///
+    /// Must be called inside a Tokio runtime.
+    #[must_use]
+    pub fn start(orchestrator: Arc<RunpodOrchestrator>, policy: IdlePolicy) -> Self {
+        let shared = Arc::new(Shared::default());
+        let task = tokio::spawn(run(orchestrator, policy, Arc::clone(&shared)));
+        Self { shared, task }
+    }
+
+    /// When the GPUs went idle (ms since UNIX epoch; `None` while busy or
+    /// unknown).
+    #[must_use]
+    pub fn idle_since_ms(&self) -> Option<u64> {
+        Some(self.shared.idle_since_ms.load(Ordering::SeqCst)).filter(|ms| *ms > 0)
+    }
+
+    /// Highest GPU utilization (in %) at the last read.
+    #[must_use]
+    pub fn last_utilization(&self) -> Option<f32> {
+        *self
+            .shared
+            .last_utilization
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Pods stopped (or terminated) for idling so far.
+    #[must_use]
+    pub fn stops(&self) -> u32 {
+        self.shared.stops.load(Ordering::SeqCst)
+    }
+
+    /// Stop watching.
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for IdleMonitor {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for IdleMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdleMonitor")
+            .field("idle_since_ms", &self.idle_since_ms())
+            .field("last_utilization", &self.last_utilization())
+            .field("stops", &self.stops())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Poll loop of a monitor.
+async fn run(orchestrator: Arc<RunpodOrchestrator>, policy: IdlePolicy, shared: Arc<Shared>) {
+    let interval = Duration::from_millis(policy.poll_interval_ms.max(1));
+    // Pod the idle timer runs for: a replacement starts its own.
+    let mut idle_pod: Option<String> = None;
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let utilization = match orchestrator.running_gpu_utilization().await {
+            Ok(utilization) => utilization,
+            Err(e) => {
+                log_warn!("could not read GPU utilization: {e}", e = ErrorChain(&e));
+                continue;
+            }
+        };
+        let last = utilization.as_ref().map(|(_, percent)| *percent);
+        *shared
+            .last_utilization
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = last;
+
+        let Some((pod_id, percent)) = utilization.filter(|(_, p)| *p < policy.threshold_percent)
+        else {
+            shared.idle_since_ms.store(0, Ordering::SeqCst);
+            continue;
+        };
+
+        let now = now_unix_ms();
+        let since = match shared.idle_since_ms.load(Ordering::SeqCst) {
+            since if since > 0 && idle_pod.as_deref() == Some(pod_id.as_str()) => since,
+            _ => {
+                shared.idle_since_ms.store(now, Ordering::SeqCst);
+                idle_pod = Some(pod_id.clone());
+                now
+            }
+        };
+        let idle_ms = now.saturating_sub(since);
+        log_debug!("pod {pod_id} idle for {idle_ms} ms ({percent}% GPU)");
+        if idle_ms < policy.stop_after_ms {
+            continue;
+        }
+
+        log_info!(
+            "pod {pod_id} idle for {}s (under {}% GPU): {}",
+            idle_ms / 1000,
+            policy.threshold_percent,
+            policy.action.as_str()
+        );
+        let result = match policy.action {
+            IdleAction::Stop => orchestrator.stop_pod(&pod_id).await,
+            IdleAction::Terminate => orchestrator.terminate(&pod_id).await.map(|_| ()),
+        };
+        match result {
+            Ok(()) => {
+                shared.stops.fetch_add(1, Ordering::SeqCst);
+                shared.idle_since_ms.store(0, Ordering::SeqCst);
+            }
+            Err(e) => log_warn!(
+                "could not {} idle pod {pod_id}: {e}",
+                policy.action.as_str(),
+                e = ErrorChain(&e)
+            ),
+        }
+    }
+}
+
+// ============================================================================
+// Error type
+// ============================================================================
+
+/// Error type for idle policy configuration.
+#[derive(Debug)]
+pub enum IdleError {
+    /// Invalid environment variable value.
+    InvalidEnv {
+        /// The environment variable key.
+        key: &'static str,
+        /// The reason for invalidity.
+        reason: &'static str,
+    },
+    /// The env file (`RUNPOD_ENV_FILE`) could not be loaded.
+    EnvFile(EnvFileError),
+}
+
+impl fmt::Display for IdleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
+            Self::EnvFile(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for IdleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::EnvFile(e) => std::error::Error::source(e),
+            Self::InvalidEnv { .. } => None,
+        }
+    }
+}
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+fn parse_u64_env(key: &'static str, default: u64) -> Result<u64, IdleError> {
+    runpod_env::u64_var(key, default).map_err(|reason| IdleError::InvalidEnv { key, reason })
+}
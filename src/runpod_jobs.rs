@@ -0,0 +1,574 @@
+//! Batch jobs over a pod pool.
+//!
+//! Unique responsibility: run queued jobs (a shell command plus the files it
+//! reads and writes) on the pods of a `PodPool` over SSH, and collect their
+//! results.
+//!
+//! Each job goes through:
+//! 1. Check out a pod from the pool (one job per pod at a time)
+//! 2. Upload its input files
+//! 3. Run its command in the work directory, killed after the job timeout
+//! 4. Download its output files (only if the command succeeded)
+//! 5. Check the pod back in
+//!
+//! A job whose pod fails (the pod never comes up, has no SSH endpoint, or
+//! the SSH connection drops) is retried on a fresh checkout of another pool
+//! pod, up to `max_attempts`. A command that exits non-zero is a job result,
+//! not a pod failure: it is not retried. Files are streamed, never held in
+//! memory whole.
+//!
+//! ```ignore
+//! let cfg = JobQueueConfig::from_env()?;
+//! let mut queue = JobQueue::new(Arc::new(pool), SshLogin::from_env()?, cfg);
+//! queue.submit(
+//!     Job::new("train-1", "python train.py --config cfg.yaml")
+//!         .input("./cfg.yaml", "cfg.yaml")
+//!         .output("model.bin", "./out/model-1.bin"),
+//! );
+//! for result in queue.run().await {
+//!     println!("{}: {:?}", result.job_id, result.outcome.map(|o| o.status));
+//! }
+//! ```
+
+use std::{
+    env, fmt, io,
+    path::{Path, PathBuf},
+    process::{Output, Stdio},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures_util::stream::{self, StreamExt};
+use tokio::io::AsyncWriteExt;
+
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_log::{ErrorChain, log_info, log_warn};
+use crate::runpod_pool::{PodPool, PoolError, PoolLease};
+use crate::runpod_ssh::{SshError, SshLogin, shell_quote};
+
+/// Exit status of `ssh` when the connection fails (or the command exits 255).
+const SSH_CONNECTION_FAILED: i32 = 255;
+
+/// Timeout of the login probe telling a failed connection from a command
+/// exiting with 255.
+const CONNECT_PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Job queue configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobQueueConfig {
+    /// Attempts per job; a job is retried on a fresh checkout when its pod
+    /// fails.
+    /// Env: `RUNPOD_JOB_MAX_ATTEMPTS` (default: 3)
+    pub max_attempts: u32,
+
+    /// Maximum duration of a job's command in milliseconds (it is killed
+    /// after that).
+    /// Env: `RUNPOD_JOB_TIMEOUT_MS` (default: 3600000 = 1 hour)
+    pub timeout_ms: u64,
+
+    /// Pod directory commands run in; relative remote paths resolve there.
+    /// Env: `RUNPOD_JOB_WORK_DIR` (default: "/workspace")
+    pub work_dir: String,
+}
+
+impl JobQueueConfig {
+    /// Load configuration from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is invalid.
+    pub fn from_env() -> Result<Self, JobError> {
+        runpod_env::load_dotenv().map_err(JobError::EnvFile)?;
+
+        let max_attempts = parse_u64_env("RUNPOD_JOB_MAX_ATTEMPTS", 3)?;
+        let max_attempts =
+            u32::try_from(max_attempts)
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or(JobError::InvalidEnv {
+                    key: "RUNPOD_JOB_MAX_ATTEMPTS",
+                    reason: "expected a positive integer",
+                })?;
+
+        Ok(Self {
+            max_attempts,
+            timeout_ms: parse_u64_env("RUNPOD_JOB_TIMEOUT_MS", 3_600_000)?,
+            work_dir: env::var("RUNPOD_JOB_WORK_DIR")
+                .ok()
+                .filter(|d| !d.trim().is_empty())
+                .unwrap_or_else(|| "/workspace".to_string()),
+        })
+    }
+}
+
+/// A file copied between the local machine and the pod.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobFile {
+    /// Local path.
+    pub local: PathBuf,
+    /// Pod path (relative paths resolve in the work directory).
+    pub remote: String,
+}
+
+/// A unit of work: a shell command plus the files it reads and writes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Job {
+    /// Job ID, reported back in its `JobRun`.
+    pub id: String,
+    /// Shell command run on the pod.
+    pub command: String,
+    /// Files uploaded before the command runs.
+    pub inputs: Vec<JobFile>,
+    /// Files downloaded after the command succeeds.
+    pub outputs: Vec<JobFile>,
+}
+
+impl Job {
+    /// Create a job without files.
+    #[must_use]
+    pub fn new(id: impl Into<String>, command: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            command: command.into(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Upload `local` to `remote` before the command runs.
+    #[must_use]
+    pub fn input(mut self, local: impl Into<PathBuf>, remote: impl Into<String>) -> Self {
+        self.inputs.push(JobFile {
+            local: local.into(),
+            remote: remote.into(),
+        });
+        self
+    }
+
+    /// Download `remote` to `local` after the command succeeds.
+    #[must_use]
+    pub fn output(mut self, remote: impl Into<String>, local: impl Into<PathBuf>) -> Self {
+        self.outputs.push(JobFile {
+            local: local.into(),
+            remote: remote.into(),
+        });
+        self
+    }
+}
+
+/// What a job's command produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobOutput {
+    /// Exit status of the command (`None` if killed by a signal).
+    pub status: Option<i32>,
+    /// Standard output.
+    pub stdout: String,
+    /// Standard error.
+    pub stderr: String,
+    /// Local paths of the downloaded output files.
+    pub downloaded: Vec<PathBuf>,
+}
+
+impl JobOutput {
+    /// Check whether the command exited with status 0.
+    #[must_use]
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
+/// Result of one job.
+#[derive(Debug)]
+pub struct JobRun {
+    /// Job ID.
+    pub job_id: String,
+    /// Pool pod of the last attempt (`None` if no pod could be checked out).
+    pub pod_name: Option<String>,
+    /// Attempts made (more than 1 after pod failures).
+    pub attempts: u32,
+    /// Total duration in milliseconds, retries included.
+    pub duration_ms: u64,
+    /// Command output, or why the job could not run.
+    pub outcome: Result<JobOutput, JobError>,
+}
+
+impl JobRun {
+    /// Check whether the job ran and its command exited with status 0.
+    #[must_use]
+    pub fn success(&self) -> bool {
+        self.outcome.as_ref().is_ok_and(JobOutput::success)
+    }
+}
+
+/// Queue of jobs run on the pods of a pool.
+#[derive(Debug)]
+pub struct JobQueue {
+    pool: Arc<PodPool>,
+    login: SshLogin,
+    cfg: JobQueueConfig,
+    pending: Vec<Job>,
+}
+
+impl JobQueue {
+    /// Create an empty queue over `pool`, logging in to pods with `login`.
+    #[must_use]
+    pub const fn new(pool: Arc<PodPool>, login: SshLogin, cfg: JobQueueConfig) -> Self {
+        Self {
+            pool,
+            login,
+            cfg,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Add a job to the queue.
+    pub fn submit(&mut self, job: Job) {
+        self.pending.push(job);
+    }
+
+    /// Jobs waiting for `run()`, in submission order.
+    #[must_use]
+    pub fn pending(&self) -> &[Job] {
+        &self.pending
+    }
+
+    /// Run every pending job and empty the queue.
+    ///
+    /// As many jobs run at once as the pool has pods. Results come back in
+    /// submission order; a failing job does not stop the others.
+    pub async fn run(&mut self) -> Vec<JobRun> {
+        let jobs = std::mem::take(&mut self.pending);
+        let limit = self.pool.config().size.max(1);
+        stream::iter(&jobs)
+            .map(|job| self.run_job(job))
+            .buffered(limit)
+            .collect()
+            .await
+    }
+
+    /// Run one job, retrying on a fresh checkout of another pod when its pod
+    /// fails (once every pool pod failed, the last failure is the outcome).
+    pub async fn run_job(&self, job: &Job) -> JobRun {
+        let started = Instant::now();
+        let mut attempts = 0;
+        let mut pod_name = None;
+        let mut failed_pods = Vec::new();
+        let outcome = loop {
+            attempts += 1;
+            let result = match self.pool.checkout_excluding(&failed_pods).await {
+                Ok(lease) => {
+                    pod_name = Some(lease.pod_name.clone());
+                    let result = self.pool.hold(&lease, self.execute(job, &lease)).await;
+                    if let Err(e) = self.pool.checkin(&lease).await {
+                        log_warn!(
+                            "job {}: checkin of {} failed: {e}",
+                            job.id,
+                            lease.pod_name,
+                            e = ErrorChain(&e)
+                        );
+                    }
+                    result
+                }
+                Err(e) => Err(JobError::Pool(e)),
+            };
+            match result {
+                Err(e) if e.is_pod_failure() && attempts < self.cfg.max_attempts => {
+                    failed_pods.extend(pod_name.clone());
+                    if failed_pods.len() >= self.pool.config().size {
+                        break Err(e);
+                    }
+                    log_warn!(
+                        "job {} attempt {attempts} failed, retrying: {e}",
+                        job.id,
+                        e = ErrorChain(&e)
+                    );
+                }
+                other => break other,
+            }
+        };
+        log_info!("job {} finished after {attempts} attempt(s)", job.id);
+
+        JobRun {
+            job_id: job.id.clone(),
+            pod_name,
+            attempts,
+            duration_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+            outcome,
+        }
+    }
+
+    /// Upload, run, download on one checked-out pod.
+    async fn execute(&self, job: &Job, lease: &PoolLease) -> Result<JobOutput, JobError> {
+        let (host, port) = lease
+            .pod
+            .ssh_endpoint()
+            .ok_or_else(|| JobError::Ssh(SshError::NoSshEndpoint(lease.pod.id.clone())))?;
+        let target = Target {
+            login: &self.login,
+            host,
+            port,
+            work_dir: &self.cfg.work_dir,
+            timeout: Duration::from_millis(self.cfg.timeout_ms),
+        };
+
+        for file in &job.inputs {
+            target.upload(file).await?;
+        }
+
+        let output = target.run(&job.command, None, None).await?;
+        let status = output.status.code();
+        if target.connection_failed(&output).await {
+            return Err(JobError::Connection {
+                host: host.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+
+        let mut downloaded = Vec::new();
+        if status == Some(0) {
+            for file in &job.outputs {
+                target.download(file).await?;
+                downloaded.push(file.local.clone());
+            }
+        }
+
+        Ok(JobOutput {
+            status,
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            downloaded,
+        })
+    }
+}
+
+// ============================================================================
+// Remote execution
+// ============================================================================
+
+struct Target<'a> {
+    login: &'a SshLogin,
+    host: &'a str,
+    port: u16,
+    work_dir: &'a str,
+    timeout: Duration,
+}
+
+impl Target<'_> {
+    /// Run `command` in the work directory, streaming the local file
+    /// `stdin_from` into it and its standard output into the local file
+    /// `stdout_to` when given (the returned stdout is then empty).
+    async fn run(
+        &self,
+        command: &str,
+        stdin_from: Option<&Path>,
+        stdout_to: Option<&Path>,
+    ) -> Result<Output, JobError> {
+        let dir = shell_quote(self.work_dir);
+        let remote = format!("mkdir -p {dir} && cd {dir} && {command}");
+        let mut cmd = self.login.command(self.host, self.port, &remote);
+        let input = match stdin_from {
+            Some(path) => {
+                cmd.stdin(Stdio::piped());
+                Some(tokio::fs::File::open(path).await.map_err(io_error(path))?)
+            }
+            None => None,
+        };
+        let spawn_error = |e| {
+            JobError::Ssh(SshError::Spawn {
+                program: self.login.program.clone(),
+                source: e,
+            })
+        };
+
+        let run = async {
+            let mut child = cmd.spawn().map_err(spawn_error)?;
+            let stdin = child.stdin.take();
+            let stdout = stdout_to.and_then(|_| child.stdout.take());
+            let feed = async {
+                if let (Some(mut file), Some(mut pipe)) = (input, stdin) {
+                    // A write error means the remote side went away: the
+                    // exit status tells why.
+                    let _ = tokio::io::copy(&mut file, &mut pipe).await;
+                    let _ = pipe.shutdown().await;
+                }
+            };
+            let drain = async {
+                let (Some(path), Some(mut pipe)) = (stdout_to, stdout) else {
+                    return Ok(());
+                };
+                let mut file = tokio::fs::File::create(path)
+                    .await
+                    .map_err(io_error(path))?;
+                tokio::io::copy(&mut pipe, &mut file)
+                    .await
+                    .map_err(io_error(path))?;
+                file.flush().await.map_err(io_error(path))
+            };
+            let ((), drained, output) = tokio::join!(feed, drain, child.wait_with_output());
+            drained?;
+            output.map_err(spawn_error)
+        };
+        tokio::time::timeout(self.timeout, run).await.map_err(|_| {
+            JobError::Ssh(SshError::CommandTimeout {
+                command: command.to_string(),
+                timeout: self.timeout,
+            })
+        })?
+    }
+
+    async fn upload(&self, file: &JobFile) -> Result<(), JobError> {
+        let remote = shell_quote(&file.remote);
+        let command = match file.remote.rsplit_once('/') {
+            Some((dir, _)) if !dir.is_empty() => {
+                format!("mkdir -p {} && cat > {remote}", shell_quote(dir))
+            }
+            _ => format!("cat > {remote}"),
+        };
+        let output = self.run(&command, Some(&file.local), None).await?;
+        self.check_transfer(&output, &file.remote).await
+    }
+
+    async fn download(&self, file: &JobFile) -> Result<(), JobError> {
+        if let Some(parent) = file.local.parent().filter(|p| !p.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(io_error(parent))?;
+        }
+        let command = format!("cat {}", shell_quote(&file.remote));
+        let output = self.run(&command, None, Some(&file.local)).await?;
+        let checked = self.check_transfer(&output, &file.remote).await;
+        if checked.is_err() {
+            // Do not leave a truncated copy behind.
+            let _ = tokio::fs::remove_file(&file.local).await;
+        }
+        checked
+    }
+
+    async fn check_transfer(&self, output: &Output, remote: &str) -> Result<(), JobError> {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if output.status.success() {
+            Ok(())
+        } else if self.connection_failed(output).await {
+            Err(JobError::Connection {
+                host: self.host.to_string(),
+                stderr,
+            })
+        } else {
+            Err(JobError::Transfer {
+                remote: remote.to_string(),
+                stderr,
+            })
+        }
+    }
+
+    /// Whether `output` comes from a failed connection rather than from the
+    /// remote command (which may exit with 255 too): the pod is probed.
+    async fn connection_failed(&self, output: &Output) -> bool {
+        output.status.code() == Some(SSH_CONNECTION_FAILED)
+            && !self
+                .login
+                .can_connect(self.host, self.port, CONNECT_PROBE_TIMEOUT)
+                .await
+    }
+}
+
+/// Map an I/O error on the local file `path` to `JobError::Io`.
+fn io_error(path: &Path) -> impl Fn(io::Error) -> JobError + '_ {
+    move |source| JobError::Io {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+// ============================================================================
+// Error type
+// ============================================================================
+
+/// Error type for batch jobs.
+#[derive(Debug)]
+pub enum JobError {
+    /// Invalid environment variable value.
+    InvalidEnv {
+        /// The environment variable key.
+        key: &'static str,
+        /// The reason for invalidity.
+        reason: &'static str,
+    },
+    /// The env file (`RUNPOD_ENV_FILE`) could not be loaded.
+    EnvFile(EnvFileError),
+    /// No pod could be checked out of the pool.
+    Pool(PoolError),
+    /// SSH could not run (no endpoint, `ssh` missing, command timeout).
+    Ssh(SshError),
+    /// The SSH connection to the pod failed.
+    Connection {
+        /// The pod host.
+        host: String,
+        /// `ssh` standard error.
+        stderr: String,
+    },
+    /// A file could not be copied to or from the pod.
+    Transfer {
+        /// The pod path.
+        remote: String,
+        /// Standard error of the remote command.
+        stderr: String,
+    },
+    /// A local file could not be read or written.
+    Io {
+        /// The local path.
+        path: PathBuf,
+        /// The underlying error.
+        source: io::Error,
+    },
+}
+
+impl JobError {
+    /// Whether the failure comes from the pod rather than the job, so that
+    /// running the job on another checkout may succeed.
+    #[must_use]
+    pub const fn is_pod_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::Pool(PoolError::Orchestrator(_))
+                | Self::Ssh(SshError::NoSshEndpoint(_))
+                | Self::Connection { .. }
+        )
+    }
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
+            Self::EnvFile(e) => write!(f, "{e}"),
+            Self::Pool(e) => write!(f, "{e}"),
+            Self::Ssh(e) => write!(f, "{e}"),
+            Self::Connection { host, stderr } => {
+                write!(f, "ssh connection to {host} failed: {stderr}")
+            }
+            Self::Transfer { remote, stderr } => write!(f, "cannot copy {remote}: {stderr}"),
+            Self::Io { path, .. } => write!(f, "cannot access {}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for JobError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::EnvFile(e) => std::error::Error::source(e),
+            Self::Pool(e) => std::error::Error::source(e),
+            Self::Ssh(e) => std::error::Error::source(e),
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+fn parse_u64_env(key: &'static str, default: u64) -> Result<u64, JobError> {
+    runpod_env::u64_var(key, default).map_err(|reason| JobError::InvalidEnv { key, reason })
+}
@@ -0,0 +1,737 @@
+//! Jupyter Server client for leased pods.
+//!
+//! Unique responsibility: drive the Jupyter Server running on a pod (port
+//! 8888): upload files and notebooks, start kernels and execute code in them.
+//!
+//! Files go through the REST contents API (`/api/contents`); code runs over
+//! the kernel's websocket channel (`/api/kernels/{id}/channels`, plain
+//! `ws://`, as the pod's mapped port is plain HTTP). An execution that
+//! outlives the execution timeout is interrupted, so the kernel is free for
+//! the next one.
+//!
+//! ```ignore
+//! let pod = orchestrator.ensure_ready_pod().await?;
+//! let jupyter = pod.jupyter()?; // token from RUNPOD_JUPYTER_TOKEN
+//! jupyter.upload_notebook("train.ipynb", &notebook).await?;
+//!
+//! let mut kernel = jupyter.start_kernel(None).await?;
+//! for cell in kernel.execute_notebook(&notebook).await? {
+//!     print!("{}", cell.stdout);
+//! }
+//! kernel.shutdown().await?;
+//! ```
+
+use std::{env, fmt, time::Duration};
+
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::net::TcpStream;
+use tokio_tungstenite::{
+    MaybeTlsStream, WebSocketStream,
+    tungstenite::{
+        self, Message,
+        client::IntoClientRequest,
+        http::{HeaderValue, header::AUTHORIZATION},
+        protocol::WebSocketConfig,
+    },
+};
+
+use crate::runpod_http::ApiKey;
+use crate::runpod_log::{ErrorChain, log_warn};
+use crate::runpod_orchestrator::PodLease;
+
+/// Channel connection of a kernel.
+type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Largest websocket message accepted, in bytes.
+const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Maximum duration of one REST request to the server.
+const REQUEST_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// How long an interrupted execution may take to wind down before the
+/// channel connection is reopened.
+const INTERRUPT_GRACE: Duration = Duration::from_secs(10);
+
+/// Jupyter messaging protocol version spoken.
+const PROTOCOL_VERSION: &str = "5.3";
+
+// ============================================================================
+// Client
+// ============================================================================
+
+/// Client of a Jupyter Server.
+#[derive(Debug, Clone)]
+pub struct JupyterClient {
+    base_url: String,
+    host: String,
+    port: u16,
+    token: Option<ApiKey>,
+    exec_timeout: Duration,
+    http: reqwest::Client,
+}
+
+impl JupyterClient {
+    /// Create a client for the Jupyter Server at `base_url` (`http://host:port`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is not a plain `http://host:port` URL or
+    /// the HTTP client cannot be created.
+    pub fn new(base_url: &str) -> Result<Self, JupyterError> {
+        let http = reqwest::Client::builder()
+            .build()
+            .map_err(JupyterError::Http)?;
+        Self::new_with_http(base_url, http)
+    }
+
+    /// Create a client sending its REST requests through `http` (e.g. an
+    /// orchestrator's `http_client()`); each request still times out after
+    /// one minute.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is not a plain `http://host:port` URL.
+    pub fn new_with_http(base_url: &str, http: reqwest::Client) -> Result<Self, JupyterError> {
+        let base_url = base_url.trim_end_matches('/').to_string();
+        let authority = base_url
+            .strip_prefix("http://")
+            .filter(|rest| !rest.contains('/'))
+            .ok_or_else(|| JupyterError::InvalidUrl(base_url.clone()))?;
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host.to_string(),
+                port.parse::<u16>()
+                    .map_err(|_| JupyterError::InvalidUrl(base_url.clone()))?,
+            ),
+            None => (authority.to_string(), 80),
+        };
+
+        Ok(Self {
+            base_url,
+            host,
+            port,
+            token: None,
+            exec_timeout: Duration::from_mins(10),
+            http,
+        })
+    }
+
+    /// Create a client for the Jupyter Server of a lease (port 8888).
+    ///
+    /// The token is read from `RUNPOD_JUPYTER_TOKEN` (optional; `RunPod`
+    /// templates use the pod's `JUPYTER_PASSWORD`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease has no Jupyter port mapped or the HTTP
+    /// client cannot be created.
+    pub fn for_lease(lease: &PodLease) -> Result<Self, JupyterError> {
+        let http = reqwest::Client::builder()
+            .build()
+            .map_err(JupyterError::Http)?;
+        Self::for_lease_with_http(lease, http)
+    }
+
+    /// Same as `for_lease()`, sending the REST requests through `http`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease has no Jupyter port mapped.
+    pub fn for_lease_with_http(
+        lease: &PodLease,
+        http: reqwest::Client,
+    ) -> Result<Self, JupyterError> {
+        let url = lease
+            .jupyter_endpoint()
+            .ok_or_else(|| JupyterError::NoJupyterEndpoint(lease.id.clone()))?;
+        let client = Self::new_with_http(&url, http)?;
+        Ok(match env::var("RUNPOD_JUPYTER_TOKEN") {
+            Ok(token) if !token.is_empty() => client.with_token(token),
+            _ => client,
+        })
+    }
+
+    /// Authenticate with `token` (`Authorization: token ...`).
+    #[must_use]
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(ApiKey::new(token.into()));
+        self
+    }
+
+    /// Set the maximum time one `execute()` may take (default: 10 minutes).
+    #[must_use]
+    pub const fn with_exec_timeout(mut self, timeout: Duration) -> Self {
+        self.exec_timeout = timeout;
+        self
+    }
+
+    /// Get the server URL.
+    #[must_use]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Upload a file to `remote_path` (relative to the server root).
+    ///
+    /// Parent directories must exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the server rejects it.
+    pub async fn upload_file(
+        &self,
+        remote_path: &str,
+        contents: &[u8],
+    ) -> Result<(), JupyterError> {
+        let body = json!({
+            "type": "file",
+            "format": "base64",
+            "content": base64::engine::general_purpose::STANDARD.encode(contents),
+        });
+        self.put_contents(remote_path, &body).await
+    }
+
+    /// Upload a notebook (nbformat JSON) to `remote_path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the server rejects it.
+    pub async fn upload_notebook(
+        &self,
+        remote_path: &str,
+        notebook: &Value,
+    ) -> Result<(), JupyterError> {
+        let body = json!({
+            "type": "notebook",
+            "format": "json",
+            "content": notebook,
+        });
+        self.put_contents(remote_path, &body).await
+    }
+
+    /// Start a kernel (`name`, or the server's default kernel) and connect to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the kernel cannot be started or connected to.
+    pub async fn start_kernel(&self, name: Option<&str>) -> Result<JupyterKernel, JupyterError> {
+        let body = name.map_or_else(|| json!({}), |name| json!({ "name": name }));
+        let req = self.authorize(self.http.post(format!("{}/api/kernels", self.base_url)))?;
+        let resp = req.json(&body).send().await.map_err(JupyterError::Http)?;
+        let kernel: KernelModel = Self::parse(resp).await?;
+        self.connect_kernel(&kernel.id).await
+    }
+
+    /// Connect to a running kernel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the websocket handshake fails.
+    pub async fn connect_kernel(&self, kernel_id: &str) -> Result<JupyterKernel, JupyterError> {
+        Ok(JupyterKernel {
+            id: kernel_id.to_string(),
+            session: random_hex(16),
+            client: self.clone(),
+            ws: self.connect_channels(kernel_id).await?,
+        })
+    }
+
+    /// Open the channel connection of a kernel.
+    async fn connect_channels(&self, kernel_id: &str) -> Result<WebSocket, JupyterError> {
+        let url = format!(
+            "ws://{}:{}/api/kernels/{kernel_id}/channels",
+            self.host, self.port
+        );
+        let mut request = url.into_client_request().map_err(JupyterError::WebSocket)?;
+        if let Some(value) = self.auth_header()? {
+            request.headers_mut().insert(AUTHORIZATION, value);
+        }
+        let config = WebSocketConfig::default().max_message_size(Some(MAX_MESSAGE_BYTES));
+        let (ws, _) = tokio_tungstenite::connect_async_with_config(request, Some(config), false)
+            .await
+            .map_err(JupyterError::WebSocket)?;
+        Ok(ws)
+    }
+
+    /// Shut a kernel down.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the server rejects it.
+    pub async fn shutdown_kernel(&self, kernel_id: &str) -> Result<(), JupyterError> {
+        let url = format!("{}/api/kernels/{kernel_id}", self.base_url);
+        let resp = self
+            .authorize(self.http.delete(url))?
+            .send()
+            .await
+            .map_err(JupyterError::Http)?;
+        Self::check(resp).await
+    }
+
+    async fn put_contents(&self, remote_path: &str, body: &Value) -> Result<(), JupyterError> {
+        let url = format!(
+            "{}/api/contents/{}",
+            self.base_url,
+            encode_path(remote_path.trim_start_matches('/'))
+        );
+        let resp = self
+            .authorize(self.http.put(url))?
+            .json(body)
+            .send()
+            .await
+            .map_err(JupyterError::Http)?;
+        Self::check(resp).await
+    }
+
+    fn authorize(
+        &self,
+        req: reqwest::RequestBuilder,
+    ) -> Result<reqwest::RequestBuilder, JupyterError> {
+        let req = req.timeout(REQUEST_TIMEOUT);
+        Ok(match self.auth_header()? {
+            Some(value) => req.header(AUTHORIZATION, value),
+            None => req,
+        })
+    }
+
+    /// `Authorization` header of the token, marked sensitive so the HTTP and
+    /// websocket stacks never log it.
+    fn auth_header(&self) -> Result<Option<HeaderValue>, JupyterError> {
+        let Some(token) = &self.token else {
+            return Ok(None);
+        };
+        let mut value = HeaderValue::from_str(&format!("token {}", token.expose()))
+            .map_err(|_| JupyterError::InvalidToken)?;
+        value.set_sensitive(true);
+        Ok(Some(value))
+    }
+
+    async fn check(resp: reqwest::Response) -> Result<(), JupyterError> {
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = resp.text().await.unwrap_or_default();
+        Err(JupyterError::Api { status, body })
+    }
+
+    async fn parse<T: serde::de::DeserializeOwned>(
+        resp: reqwest::Response,
+    ) -> Result<T, JupyterError> {
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(JupyterError::Api { status, body });
+        }
+        let body = resp.bytes().await.map_err(JupyterError::Http)?;
+        serde_json::from_slice(&body).map_err(JupyterError::Json)
+    }
+}
+
+#[derive(Deserialize)]
+struct KernelModel {
+    id: String,
+}
+
+// ============================================================================
+// Kernel
+// ============================================================================
+
+/// Outcome of one executed cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellStatus {
+    /// The code ran to completion.
+    Ok,
+    /// The code raised an error.
+    Error,
+    /// The cell was not run because an earlier one failed.
+    Aborted,
+}
+
+/// Error raised by executed code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellError {
+    /// Exception name.
+    pub ename: String,
+    /// Exception value.
+    pub evalue: String,
+    /// Traceback lines (may contain ANSI colors).
+    pub traceback: Vec<String>,
+}
+
+/// Result of executing code in a kernel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellResult {
+    /// How the execution ended.
+    pub status: CellStatus,
+    /// Execution counter, if the kernel reported one.
+    pub execution_count: Option<u64>,
+    /// Everything written to stdout.
+    pub stdout: String,
+    /// Everything written to stderr.
+    pub stderr: String,
+    /// Rich results (`execute_result` / `display_data` mime bundles), in order.
+    pub outputs: Vec<Value>,
+    /// The error, when `status` is `Error`.
+    pub error: Option<CellError>,
+}
+
+impl CellResult {
+    /// Plain-text representation of the last rich result, if any.
+    #[must_use]
+    pub fn text(&self) -> Option<&str> {
+        self.outputs
+            .iter()
+            .rev()
+            .find_map(|data| data.get("text/plain").and_then(Value::as_str))
+    }
+}
+
+/// A running kernel with an open channel connection.
+#[derive(Debug)]
+pub struct JupyterKernel {
+    id: String,
+    session: String,
+    client: JupyterClient,
+    ws: WebSocket,
+}
+
+impl JupyterKernel {
+    /// Get the kernel ID.
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Execute `code` and collect its outputs.
+    ///
+    /// An error raised by the code is reported in the result (`status` is
+    /// `Error`), not as an `Err`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection fails, the kernel closes it, or
+    /// execution exceeds the client's execution timeout (the execution is
+    /// then interrupted).
+    pub async fn execute(&mut self, code: &str) -> Result<CellResult, JupyterError> {
+        let msg_id = random_hex(16);
+        let content = json!({
+            "code": code,
+            "silent": false,
+            "store_history": true,
+            "user_expressions": {},
+            "allow_stdin": false,
+            "stop_on_error": true,
+        });
+        let request = self.message(&msg_id, "execute_request", "shell", &content);
+        self.ws
+            .send(request)
+            .await
+            .map_err(JupyterError::WebSocket)?;
+
+        let timeout = self.client.exec_timeout;
+        if let Ok(result) = tokio::time::timeout(timeout, self.collect(&msg_id)).await {
+            return result;
+        }
+        self.interrupt(&msg_id).await;
+        Err(JupyterError::Timeout)
+    }
+
+    /// Execute the code cells of a notebook (nbformat JSON) in order.
+    ///
+    /// Execution stops at the first failing cell; the cells after it are
+    /// reported as `Aborted`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the notebook has no `cells` array, or as `execute()`.
+    pub async fn execute_notebook(
+        &mut self,
+        notebook: &Value,
+    ) -> Result<Vec<CellResult>, JupyterError> {
+        let cells = notebook
+            .get("cells")
+            .and_then(Value::as_array)
+            .ok_or(JupyterError::InvalidNotebook("notebook has no cells array"))?;
+
+        let mut results = Vec::new();
+        let mut failed = false;
+        for cell in cells
+            .iter()
+            .filter(|c| c.get("cell_type").and_then(Value::as_str) == Some("code"))
+        {
+            if failed {
+                results.push(CellResult {
+                    status: CellStatus::Aborted,
+                    execution_count: None,
+                    stdout: String::new(),
+                    stderr: String::new(),
+                    outputs: Vec::new(),
+                    error: None,
+                });
+                continue;
+            }
+            let result = self.execute(&cell_source(cell)).await?;
+            failed = result.status == CellStatus::Error;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Close the connection and shut the kernel down.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the shutdown request fails.
+    pub async fn shutdown(mut self) -> Result<(), JupyterError> {
+        let _ = self.ws.close(None).await;
+        self.client.shutdown_kernel(&self.id).await
+    }
+
+    /// Interrupt the execution of `msg_id` (`interrupt_request` on the
+    /// control channel) and drain its last messages, so the next execution
+    /// starts on a free kernel. The channel connection is reopened if the
+    /// kernel does not go idle within `INTERRUPT_GRACE`.
+    async fn interrupt(&mut self, msg_id: &str) {
+        let request = self.message(&random_hex(16), "interrupt_request", "control", &json!({}));
+        let drained = self.ws.send(request).await.is_ok()
+            && tokio::time::timeout(INTERRUPT_GRACE, self.drain(msg_id))
+                .await
+                .is_ok_and(|drained| drained.is_ok());
+        if drained {
+            return;
+        }
+
+        log_warn!(
+            "kernel {} did not go idle after an interrupt, reconnecting",
+            self.id
+        );
+        let _ = self.ws.close(None).await;
+        match self.client.connect_channels(&self.id).await {
+            Ok(ws) => self.ws = ws,
+            Err(e) => log_warn!(
+                "reconnecting to kernel {} failed: {e}",
+                self.id,
+                e = ErrorChain(&e)
+            ),
+        }
+    }
+
+    /// Read messages until the kernel is idle after `msg_id`.
+    async fn drain(&mut self, msg_id: &str) -> Result<(), JupyterError> {
+        loop {
+            let msg = self.recv().await?;
+            if msg.pointer("/parent_header/msg_id").and_then(Value::as_str) == Some(msg_id)
+                && msg.pointer("/header/msg_type").and_then(Value::as_str) == Some("status")
+                && msg
+                    .pointer("/content/execution_state")
+                    .and_then(Value::as_str)
+                    == Some("idle")
+            {
+                return Ok(());
+            }
+        }
+    }
+
+    /// A message of `msg_type` on `channel`, in this kernel's session.
+    fn message(&self, msg_id: &str, msg_type: &str, channel: &str, content: &Value) -> Message {
+        let msg = json!({
+            "header": {
+                "msg_id": msg_id,
+                "msg_type": msg_type,
+                "session": self.session,
+                "username": "halldyll",
+                "version": PROTOCOL_VERSION,
+            },
+            "parent_header": {},
+            "metadata": {},
+            "content": content,
+            "buffers": [],
+            "channel": channel,
+        });
+        Message::text(msg.to_string())
+    }
+
+    /// Receive the next message (pings are answered by the websocket layer).
+    async fn recv(&mut self) -> Result<Value, JupyterError> {
+        loop {
+            match self.ws.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    return serde_json::from_str(text.as_str()).map_err(JupyterError::Json);
+                }
+                Some(Ok(Message::Close(_))) | None => return Err(JupyterError::Closed),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(JupyterError::WebSocket(e)),
+            }
+        }
+    }
+
+    /// Read messages until the reply to `msg_id` is in and the kernel is idle again.
+    async fn collect(&mut self, msg_id: &str) -> Result<CellResult, JupyterError> {
+        let mut result = CellResult {
+            status: CellStatus::Ok,
+            execution_count: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            outputs: Vec::new(),
+            error: None,
+        };
+        let mut replied = false;
+        let mut idle = false;
+
+        while !(replied && idle) {
+            let msg = self.recv().await?;
+            if msg.pointer("/parent_header/msg_id").and_then(Value::as_str) != Some(msg_id) {
+                continue;
+            }
+            let content = &msg["content"];
+
+            match msg
+                .pointer("/header/msg_type")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+            {
+                "stream" => {
+                    let chunk = content["text"].as_str().unwrap_or_default();
+                    if content["name"].as_str() == Some("stderr") {
+                        result.stderr.push_str(chunk);
+                    } else {
+                        result.stdout.push_str(chunk);
+                    }
+                }
+                "execute_result" | "display_data" => result.outputs.push(content["data"].clone()),
+                "error" => {
+                    result.status = CellStatus::Error;
+                    result.error = Some(CellError {
+                        ename: content["ename"].as_str().unwrap_or_default().to_string(),
+                        evalue: content["evalue"].as_str().unwrap_or_default().to_string(),
+                        traceback: content["traceback"]
+                            .as_array()
+                            .map(|lines| {
+                                lines
+                                    .iter()
+                                    .filter_map(Value::as_str)
+                                    .map(str::to_string)
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                    });
+                }
+                "execute_reply" => {
+                    replied = true;
+                    result.execution_count = content["execution_count"].as_u64();
+                    if content["status"].as_str() != Some("ok") {
+                        result.status = CellStatus::Error;
+                    }
+                }
+                "status" => idle = content["execution_state"].as_str() == Some("idle"),
+                _ => {}
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Source of a notebook cell (a string or a list of lines).
+fn cell_source(cell: &Value) -> String {
+    match &cell["source"] {
+        Value::String(source) => source.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+/// Random lowercase hex string of `bytes` bytes (message and session IDs).
+fn random_hex(bytes: usize) -> String {
+    let mut buf = vec![0_u8; bytes];
+    if aws_lc_rs::rand::fill(&mut buf).is_err() {
+        buf = crate::runpod_state::now_unix_ms().to_be_bytes().to_vec();
+    }
+    buf.iter().fold(String::new(), |mut hex, b| {
+        let _ = fmt::Write::write_fmt(&mut hex, format_args!("{b:02x}"));
+        hex
+    })
+}
+
+/// Percent-encode a contents API path (segments separated by `/` are kept).
+fn encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+// ============================================================================
+// Error type
+// ============================================================================
+
+/// Error type for Jupyter operations.
+#[derive(Debug)]
+pub enum JupyterError {
+    /// The pod has no Jupyter port (8888) mapped.
+    NoJupyterEndpoint(String),
+    /// The server URL is not a plain `http://host:port` URL.
+    InvalidUrl(String),
+    /// HTTP client error.
+    Http(reqwest::Error),
+    /// API error response.
+    Api {
+        /// HTTP status code.
+        status: reqwest::StatusCode,
+        /// Response body.
+        body: String,
+    },
+    /// JSON parsing error.
+    Json(serde_json::Error),
+    /// The notebook is not in the expected format.
+    InvalidNotebook(&'static str),
+    /// Websocket connection or protocol error.
+    WebSocket(tungstenite::Error),
+    /// The kernel closed the connection.
+    Closed,
+    /// Execution exceeded the execution timeout.
+    Timeout,
+    /// The token contains characters not allowed in an HTTP header.
+    InvalidToken,
+}
+
+impl fmt::Display for JupyterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoJupyterEndpoint(id) => {
+                write!(f, "pod {id} has no jupyter endpoint (port 8888)")
+            }
+            Self::InvalidUrl(url) => {
+                write!(f, "invalid jupyter url (expected http://host:port): {url}")
+            }
+            Self::Http(e) => crate::runpod_http::fmt_http_error(f, e),
+            Self::Api { status, body } => {
+                write!(f, "jupyter api error: status={status}, body={body}")
+            }
+            Self::Json(_) => write!(f, "invalid json response"),
+            Self::InvalidNotebook(e) => write!(f, "invalid notebook: {e}"),
+            Self::WebSocket(_) => write!(f, "kernel websocket error"),
+            Self::Closed => write!(f, "kernel connection closed"),
+            Self::Timeout => write!(f, "timeout waiting for code execution"),
+            Self::InvalidToken => write!(f, "jupyter token is not a valid header value"),
+        }
+    }
+}
+
+impl std::error::Error for JupyterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(e) => Some(e),
+            Self::Json(e) => Some(e),
+            Self::WebSocket(e) => Some(e),
+            _ => None,
+        }
+    }
+}
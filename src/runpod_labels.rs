@@ -0,0 +1,322 @@
+//! Pod labels and label selectors.
+//!
+//! Unique responsibility: encode `key=value` labels into a pod's environment
+//! and select pods by label instead of by exact name.
+//!
+//! Labels are stored in one pod env var, `HALLDYLL_LABELS`
+//! (`halldyll.role=worker,team=ml`), set when the pod is created; `RunPod`
+//! returns it with the pod listing, so no extra request is needed to read
+//! them. Keys take letters, digits and `._/-`; values letters, digits and
+//! `._-` (and may be empty).
+//!
+//! A selector is a comma-separated list of requirements, all of which must
+//! hold: `key=value` (or `key==value`), `key!=value`, `key` (label present)
+//! and `!key` (label absent).
+//!
+//! ```ignore
+//! // RUNPOD_POD_LABELS="halldyll.role=worker"
+//! // RUNPOD_POD_SELECTOR="halldyll.role=worker,!draining"
+//! let selector = LabelSelector::parse("halldyll.role=worker,!draining")?;
+//! let workers: Vec<_> = orchestrator
+//!     .list_pods()
+//!     .await?
+//!     .into_iter()
+//!     .filter(|pod| selector.matches(&pod.labels()))
+//!     .collect();
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    hash::BuildHasher,
+};
+
+/// Pod env var holding the labels.
+pub const LABELS_ENV: &str = "HALLDYLL_LABELS";
+
+/// Parse `key=value` labels separated by commas.
+///
+/// # Errors
+///
+/// Returns `LabelError::Invalid` if a label has no `=` or an invalid key or
+/// value.
+pub fn parse_labels(labels: &str) -> Result<BTreeMap<String, String>, LabelError> {
+    labels
+        .split(',')
+        .map(str::trim)
+        .filter(|label| !label.is_empty())
+        .map(|label| {
+            let (key, value) = label
+                .split_once('=')
+                .ok_or_else(|| LabelError::Invalid(label.to_string()))?;
+            let (key, value) = (key.trim(), value.trim());
+            if !is_valid_key(key) || !is_valid_value(value) {
+                return Err(LabelError::Invalid(label.to_string()));
+            }
+            Ok((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+/// Encode labels as the value of `LABELS_ENV`.
+#[must_use]
+pub fn encode_labels(labels: &BTreeMap<String, String>) -> String {
+    labels
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Labels of a pod, read from its environment (malformed labels are skipped).
+#[must_use]
+pub fn labels_from_env<S: BuildHasher>(
+    env: &HashMap<String, String, S>,
+) -> BTreeMap<String, String> {
+    let Some(value) = env.get(LABELS_ENV) else {
+        return BTreeMap::new();
+    };
+    value
+        .split(',')
+        .filter_map(|label| parse_labels(label).ok())
+        .flatten()
+        .collect()
+}
+
+/// One requirement of a `LabelSelector`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Requirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    Exists(String),
+    NotExists(String),
+}
+
+impl Requirement {
+    fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        match self {
+            Self::Equals(key, value) => labels.get(key) == Some(value),
+            Self::NotEquals(key, value) => labels.get(key) != Some(value),
+            Self::Exists(key) => labels.contains_key(key),
+            Self::NotExists(key) => !labels.contains_key(key),
+        }
+    }
+}
+
+impl fmt::Display for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Equals(key, value) => write!(f, "{key}={value}"),
+            Self::NotEquals(key, value) => write!(f, "{key}!={value}"),
+            Self::Exists(key) => f.write_str(key),
+            Self::NotExists(key) => write!(f, "!{key}"),
+        }
+    }
+}
+
+/// Requirements on pod labels, all of which must hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSelector {
+    requirements: Vec<Requirement>,
+}
+
+impl LabelSelector {
+    /// Parse a comma-separated selector (`key=value`, `key!=value`, `key`,
+    /// `!key`). An empty selector matches every pod.
+    ///
+    /// # Errors
+    ///
+    /// Returns `LabelError::Invalid` if a requirement is malformed.
+    pub fn parse(selector: &str) -> Result<Self, LabelError> {
+        let requirements = selector
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let invalid = || LabelError::Invalid(part.to_string());
+                let requirement = if let Some((key, value)) = part.split_once("!=") {
+                    Requirement::NotEquals(key.trim().to_string(), value.trim().to_string())
+                } else if let Some((key, value)) = part.split_once('=') {
+                    let value = value.strip_prefix('=').unwrap_or(value);
+                    Requirement::Equals(key.trim().to_string(), value.trim().to_string())
+                } else if let Some(key) = part.strip_prefix('!') {
+                    Requirement::NotExists(key.trim().to_string())
+                } else {
+                    Requirement::Exists(part.to_string())
+                };
+                let valid = match &requirement {
+                    Requirement::Equals(key, value) | Requirement::NotEquals(key, value) => {
+                        is_valid_key(key) && is_valid_value(value)
+                    }
+                    Requirement::Exists(key) | Requirement::NotExists(key) => is_valid_key(key),
+                };
+                valid.then_some(requirement).ok_or_else(invalid)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { requirements })
+    }
+
+    /// Whether `labels` satisfy every requirement.
+    #[must_use]
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        self.requirements.iter().all(|r| r.matches(labels))
+    }
+
+    /// Labels required by `key=value` requirements, to give the pods
+    /// created for this selector.
+    #[must_use]
+    pub fn required_labels(&self) -> BTreeMap<String, String> {
+        self.requirements
+            .iter()
+            .filter_map(|r| match r {
+                Requirement::Equals(key, value) => Some((key.clone(), value.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Whether the selector has no requirement.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.requirements.is_empty()
+    }
+}
+
+impl fmt::Display for LabelSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.requirements.iter().map(ToString::to_string).collect();
+        f.write_str(&parts.join(","))
+    }
+}
+
+// ============================================================================
+// Error type
+// ============================================================================
+
+/// Error type for labels and selectors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LabelError {
+    /// A label or selector requirement is malformed.
+    Invalid(String),
+}
+
+impl fmt::Display for LabelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(part) => write!(f, "invalid label or selector: {part:?}"),
+        }
+    }
+}
+
+impl std::error::Error for LabelError {}
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '/' | '-'))
+}
+
+fn is_valid_value(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn labels(value: &str) -> BTreeMap<String, String> {
+        parse_labels(value).unwrap()
+    }
+
+    #[test]
+    fn every_requirement_must_hold() {
+        let selector = LabelSelector::parse("halldyll.role=worker,!draining").unwrap();
+
+        assert!(selector.matches(&labels("halldyll.role=worker,team=ml")));
+        assert!(!selector.matches(&labels("halldyll.role=worker,draining=")));
+        assert!(!selector.matches(&labels("halldyll.role=trainer")));
+        assert!(!selector.matches(&labels("")));
+    }
+
+    #[test]
+    fn requirement_kinds() {
+        let pod = labels("tier=gpu,zone=eu");
+        let matches = |selector: &str| LabelSelector::parse(selector).unwrap().matches(&pod);
+
+        assert!(matches("tier=gpu"));
+        assert!(matches("tier==gpu"));
+        assert!(!matches("tier=cpu"));
+        assert!(matches("tier!=cpu"));
+        assert!(!matches("tier!=gpu"));
+        assert!(matches("zone"));
+        assert!(!matches("region"));
+        assert!(matches("!region"));
+        assert!(!matches("!zone"));
+        // A missing label is not equal to any value.
+        assert!(matches("region!=us"));
+    }
+
+    #[test]
+    fn an_empty_selector_matches_every_pod() {
+        let selector = LabelSelector::parse(" , ").unwrap();
+
+        assert!(selector.is_empty());
+        assert!(selector.matches(&labels("")));
+        assert!(selector.matches(&labels("a=b")));
+    }
+
+    #[test]
+    fn an_empty_value_is_a_value() {
+        let pod = labels("draining=");
+
+        assert!(LabelSelector::parse("draining=").unwrap().matches(&pod));
+        assert!(LabelSelector::parse("draining").unwrap().matches(&pod));
+        assert!(!LabelSelector::parse("draining=yes").unwrap().matches(&pod));
+    }
+
+    #[test]
+    fn malformed_requirements_are_rejected() {
+        for selector in ["=worker", "role=a b", "!", "role=x,ro le"] {
+            assert!(
+                matches!(LabelSelector::parse(selector), Err(LabelError::Invalid(_))),
+                "{selector}"
+            );
+        }
+    }
+
+    #[test]
+    fn required_labels_are_the_equalities() {
+        let selector = LabelSelector::parse("role=worker,team==ml,!draining,zone!=us").unwrap();
+
+        assert_eq!(selector.required_labels(), labels("role=worker,team=ml"));
+        assert_eq!(
+            selector.to_string(),
+            "role=worker,team=ml,!draining,zone!=us"
+        );
+    }
+
+    #[test]
+    fn selectors_match_labels_read_from_the_pod_env() {
+        let env = HashMap::from([(
+            LABELS_ENV.to_string(),
+            "role=worker,bad label,team=ml".to_string(),
+        )]);
+        let pod = labels_from_env(&env);
+
+        assert_eq!(pod, labels("role=worker,team=ml"));
+        assert!(
+            LabelSelector::parse("role=worker,team=ml")
+                .unwrap()
+                .matches(&pod)
+        );
+        assert_eq!(encode_labels(&pod), "role=worker,team=ml");
+    }
+}
@@ -0,0 +1,557 @@
+//! Liveness of leased pods.
+//!
+//! Unique responsibility: ping a leased pod in the background, remember when
+//! it last answered, and report when it stops (or starts again) answering.
+//!
+//! The default probe opens a TCP connection to the pod's SSH port (or its
+//! first mapped port); pods without a public TCP port are probed over HTTP
+//! through the proxy. UDP ports are never probed (nothing answers a bare
+//! UDP packet); neither is a given service. A pod counts as dead after `failure_threshold` probes in a row
+//! fail; the transition is sent to the `Notifier` as `pod_unresponsive`,
+//! and the way back as `pod_recovered`.
+//!
+//! A `ManagedLease` goes one step further: when its pod stops answering it
+//! re-runs the ensure flow (restarting, or replacing, the pod) and hands the
+//! new lease to a callback, so long-running services can reconnect. A pod
+//! that `RunPod` still reports running is terminated first: ensuring again
+//! would otherwise hand the same dead pod back.
+//!
+//! ```ignore
+//! let pod = orchestrator.ensure_ready_pod().await?;
+//! let pinger = pod.start_liveness(LivenessConfig::from_env()?, notifier);
+//!
+//! if !pinger.is_alive() {
+//!     println!("pod last seen at {:?}", pinger.last_seen_ms());
+//! }
+//!
+//! let managed = ManagedLease::start(orchestrator, PodOverrides::default(), cfg, notifier,
+//!     |old, new| println!("{} replaced by {}", old.id, new.id)).await?;
+//! let ssh = managed.lease().ssh_command("root", None, &[]);
+//! ```
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use tokio::{net::TcpStream, task::JoinHandle};
+
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_log::{ErrorChain, log_info, log_warn};
+use crate::runpod_notify::{Notification, Notifier, Severity};
+use crate::runpod_orchestrator::{
+    OrchestratorError, PodLease, PodOverrides, PodStatus, RunpodOrchestrator,
+};
+use crate::runpod_state::now_unix_ms;
+
+/// Callback of a `ManagedLease`, called with the old and the new lease.
+pub type LeaseSwapHook = Arc<dyn Fn(&PodLease, &PodLease) + Send + Sync>;
+
+/// Async check of whether a leased pod answers.
+pub type LivenessProbe =
+    Arc<dyn Fn(PodLease) -> Pin<Box<dyn Future<Output = bool> + Send>> + Send + Sync>;
+
+/// Liveness pinger configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LivenessConfig {
+    /// Delay between two probes in milliseconds.
+    /// Env: `RUNPOD_LIVENESS_INTERVAL_MS` (default: 15000)
+    pub interval_ms: u64,
+
+    /// Maximum duration of one probe in milliseconds.
+    /// Env: `RUNPOD_LIVENESS_TIMEOUT_MS` (default: 5000)
+    pub timeout_ms: u64,
+
+    /// Failed probes in a row before the pod counts as unresponsive.
+    /// Env: `RUNPOD_LIVENESS_FAILURES` (default: 3)
+    pub failure_threshold: u32,
+}
+
+impl Default for LivenessConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 15_000,
+            timeout_ms: 5_000,
+            failure_threshold: 3,
+        }
+    }
+}
+
+impl LivenessConfig {
+    /// Load the configuration from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the env-file or a variable is invalid.
+    pub fn from_env() -> Result<Self, LivenessError> {
+        runpod_env::load_dotenv().map_err(LivenessError::EnvFile)?;
+
+        Ok(Self {
+            interval_ms: parse_u64_env("RUNPOD_LIVENESS_INTERVAL_MS", 15_000)?,
+            timeout_ms: parse_u64_env("RUNPOD_LIVENESS_TIMEOUT_MS", 5_000)?,
+            failure_threshold: parse_u32_env("RUNPOD_LIVENESS_FAILURES", 3)?.max(1),
+        })
+    }
+}
+
+/// The default probe, by port type:
+/// - TCP: connect to the public SSH port (or the first mapped port)
+/// - HTTP: without a public TCP port, a request to the first proxy URL;
+///   an answer other than a gateway error (502-504) counts as alive
+/// - UDP: not probed (a pod exposing only UDP ports counts as dead)
+///
+/// It checks that the pod is reachable, not that a given service answers:
+/// use `LivenessPinger::with_probe()` for that.
+#[must_use]
+pub fn network_probe() -> LivenessProbe {
+    network_probe_with_http(reqwest::Client::new())
+}
+
+/// Same as `network_probe()`, sending the proxy requests through `http`
+/// (e.g. an orchestrator's `http_client()`).
+#[must_use]
+pub fn network_probe_with_http(http: reqwest::Client) -> LivenessProbe {
+    Arc::new(move |lease: PodLease| {
+        let http = http.clone();
+        Box::pin(async move {
+            let tcp_port = lease
+                .ssh_endpoint()
+                .map(|(_, port)| port)
+                .or_else(|| lease.port_mappings.values().min().copied())
+                .filter(|_| !lease.public_ip.is_empty());
+            if let Some(port) = tcp_port {
+                return TcpStream::connect((lease.public_ip.as_str(), port))
+                    .await
+                    .is_ok();
+            }
+
+            match lease.proxy_endpoints.values().min() {
+                Some(url) => http
+                    .get(url)
+                    .send()
+                    .await
+                    .is_ok_and(|resp| !matches!(resp.status().as_u16(), 502..=504)),
+                None => false,
+            }
+        })
+    })
+}
+
+/// State shared between a pinger and its background task.
+struct Shared {
+    lease: Mutex<PodLease>,
+    alive: AtomicBool,
+    failures: AtomicU32,
+    last_seen_ms: AtomicU64,
+}
+
+impl Shared {
+    fn lease(&self) -> PodLease {
+        self.lease
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// Background liveness checks of one leased pod.
+///
+/// The task stops when the pinger is dropped (or `stop()` is called).
+pub struct LivenessPinger {
+    shared: Arc<Shared>,
+    task: JoinHandle<()>,
+}
+
+impl LivenessPinger {
+    /// Start pinging `lease` with `network_probe()`.
+    ///
+    /// Must be called inside a Tokio runtime.
+    #[must_use]
+    pub fn start(lease: &PodLease, cfg: LivenessConfig, notifier: Arc<dyn Notifier>) -> Self {
+        Self::with_probe(lease, cfg, notifier, network_probe())
+    }
+
+    /// Start pinging `lease` with a custom probe.
+    ///
+    /// The pod counts as alive (and seen) at start: it was just leased.
+    /// Must be called inside a Tokio runtime.
+    #[must_use]
+    pub fn with_probe(
+        lease: &PodLease,
+        cfg: LivenessConfig,
+        notifier: Arc<dyn Notifier>,
+        probe: LivenessProbe,
+    ) -> Self {
+        let shared = Arc::new(Shared {
+            lease: Mutex::new(lease.clone()),
+            alive: AtomicBool::new(true),
+            failures: AtomicU32::new(0),
+            last_seen_ms: AtomicU64::new(now_unix_ms()),
+        });
+        let task = tokio::spawn(run(Arc::clone(&shared), cfg, notifier, probe));
+        Self { shared, task }
+    }
+
+    /// Whether the pod answered recently (fewer than `failure_threshold`
+    /// failed probes in a row).
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.shared.alive.load(Ordering::SeqCst)
+    }
+
+    /// When the pod last answered (ms since UNIX epoch).
+    #[must_use]
+    pub fn last_seen_ms(&self) -> u64 {
+        self.shared.last_seen_ms.load(Ordering::SeqCst)
+    }
+
+    /// Failed probes since the pod last answered.
+    #[must_use]
+    pub fn consecutive_failures(&self) -> u32 {
+        self.shared.failures.load(Ordering::SeqCst)
+    }
+
+    /// The lease being pinged.
+    #[must_use]
+    pub fn lease(&self) -> PodLease {
+        self.shared.lease()
+    }
+
+    /// Ping `lease` from now on (e.g. after the pod was replaced), counting
+    /// it as alive and seen now.
+    pub fn replace_lease(&self, lease: &PodLease) {
+        *self
+            .shared
+            .lease
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = lease.clone();
+        self.shared.failures.store(0, Ordering::SeqCst);
+        self.shared
+            .last_seen_ms
+            .store(now_unix_ms(), Ordering::SeqCst);
+        self.shared.alive.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop pinging.
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for LivenessPinger {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for LivenessPinger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LivenessPinger")
+            .field("pod_id", &self.shared.lease().id)
+            .field("alive", &self.is_alive())
+            .field("last_seen_ms", &self.last_seen_ms())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Probe loop of a pinger.
+async fn run(
+    shared: Arc<Shared>,
+    cfg: LivenessConfig,
+    notifier: Arc<dyn Notifier>,
+    probe: LivenessProbe,
+) {
+    let interval = Duration::from_millis(cfg.interval_ms.max(1));
+    let timeout = Duration::from_millis(cfg.timeout_ms);
+    loop {
+        tokio::time::sleep(interval).await;
+
+        let lease = shared.lease();
+        let answered = tokio::time::timeout(timeout, probe(lease.clone()))
+            .await
+            .unwrap_or(false);
+        let now = now_unix_ms();
+
+        if answered {
+            shared.failures.store(0, Ordering::SeqCst);
+            shared.last_seen_ms.store(now, Ordering::SeqCst);
+            if !shared.alive.swap(true, Ordering::SeqCst) {
+                log_info!("pod {} ({}) answers again", lease.name, lease.id);
+                notifier.notify(&Notification {
+                    severity: Severity::Info,
+                    kind: "pod_recovered",
+                    message: format!("pod {} ({}) answers again", lease.name, lease.id),
+                    at_ms: now,
+                });
+            }
+            continue;
+        }
+
+        let failures = shared
+            .failures
+            .fetch_add(1, Ordering::SeqCst)
+            .saturating_add(1);
+        if failures >= cfg.failure_threshold && shared.alive.swap(false, Ordering::SeqCst) {
+            let silent_s = now.saturating_sub(shared.last_seen_ms.load(Ordering::SeqCst)) / 1000;
+            let message = format!(
+                "pod {} ({}) unresponsive: {failures} failed probes, last seen {silent_s}s ago",
+                lease.name, lease.id
+            );
+            log_warn!("{message}");
+            notifier.notify(&Notification {
+                severity: Severity::Critical,
+                kind: "pod_unresponsive",
+                message,
+                at_ms: now,
+            });
+        }
+    }
+}
+
+// ============================================================================
+// Self-healing leases
+// ============================================================================
+
+/// State shared between a managed lease and its healing task.
+struct ManagedShared {
+    pinger: LivenessPinger,
+    generation: AtomicU64,
+}
+
+/// A lease that follows its pod: when the pod stops answering (dies, is
+/// preempted or terminated), the ensure flow runs again and the new lease
+/// replaces the old one.
+///
+/// Always read endpoints through `lease()`: they change when the pod does.
+/// The healing task stops when the managed lease is dropped.
+pub struct ManagedLease {
+    shared: Arc<ManagedShared>,
+    task: JoinHandle<()>,
+}
+
+impl ManagedLease {
+    /// Lease a pod with `orchestrator.ensure_ready_pod_with(&overrides)` and
+    /// keep it leased.
+    ///
+    /// Every `cfg.interval_ms`, a dead pod is re-leased with the same
+    /// overrides: a pod `RunPod` still reports running is terminated first,
+    /// a stopped or preempted one is restarted or replaced. `on_swap` is then
+    /// called with the old and the new lease (the same pod, with fresh
+    /// endpoints, when a restart was enough); `lease_renewed` is only sent
+    /// when the pod changed. Pods
+    /// of a simulating orchestrator are probed in the simulator, others with
+    /// `network_probe_with_http()` on the orchestrator's HTTP client.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the first lease fails.
+    pub async fn start(
+        orchestrator: Arc<RunpodOrchestrator>,
+        overrides: PodOverrides,
+        cfg: LivenessConfig,
+        notifier: Arc<dyn Notifier>,
+        on_swap: impl Fn(&PodLease, &PodLease) + Send + Sync + 'static,
+    ) -> Result<Self, OrchestratorError> {
+        let lease = orchestrator.ensure_ready_pod_with(&overrides).await?;
+        let probe = if orchestrator.simulator().is_some() {
+            simulator_probe(Arc::clone(&orchestrator))
+        } else {
+            network_probe_with_http(orchestrator.http_client().clone())
+        };
+        let pinger = LivenessPinger::with_probe(&lease, cfg, Arc::clone(&notifier), probe);
+        let shared = Arc::new(ManagedShared {
+            pinger,
+            generation: AtomicU64::new(0),
+        });
+        let task = tokio::spawn(heal(
+            Arc::clone(&shared),
+            orchestrator,
+            overrides,
+            cfg,
+            notifier,
+            Arc::new(on_swap),
+        ));
+        Ok(Self { shared, task })
+    }
+
+    /// The current lease.
+    #[must_use]
+    pub fn lease(&self) -> PodLease {
+        self.shared.pinger.lease()
+    }
+
+    /// Whether the current pod answered recently.
+    #[must_use]
+    pub fn is_alive(&self) -> bool {
+        self.shared.pinger.is_alive()
+    }
+
+    /// When the current pod last answered (ms since UNIX epoch).
+    #[must_use]
+    pub fn last_seen_ms(&self) -> u64 {
+        self.shared.pinger.last_seen_ms()
+    }
+
+    /// How many times the lease was renewed after its pod died.
+    #[must_use]
+    pub fn generation(&self) -> u64 {
+        self.shared.generation.load(Ordering::SeqCst)
+    }
+
+    /// Stop watching (the pod is left as it is).
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for ManagedLease {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for ManagedLease {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ManagedLease")
+            .field("pinger", &self.shared.pinger)
+            .field("generation", &self.generation())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Probe of simulated pods: alive while the simulator runs them.
+fn simulator_probe(orchestrator: Arc<RunpodOrchestrator>) -> LivenessProbe {
+    Arc::new(move |lease: PodLease| {
+        let alive = orchestrator
+            .simulator()
+            .and_then(|sim| sim.get_pod(&lease.id))
+            .is_some_and(|pod| pod.desiredStatus.as_deref() == Some("RUNNING"));
+        Box::pin(async move { alive })
+    })
+}
+
+/// Healing loop of a managed lease.
+async fn heal(
+    shared: Arc<ManagedShared>,
+    orchestrator: Arc<RunpodOrchestrator>,
+    overrides: PodOverrides,
+    cfg: LivenessConfig,
+    notifier: Arc<dyn Notifier>,
+    on_swap: LeaseSwapHook,
+) {
+    let interval = Duration::from_millis(cfg.interval_ms.max(1));
+    loop {
+        tokio::time::sleep(interval).await;
+        if shared.pinger.is_alive() {
+            continue;
+        }
+
+        let old = shared.pinger.lease();
+        log_info!(
+            "re-leasing pod {} ({}): it stopped answering",
+            old.name,
+            old.id
+        );
+        if let Err(e) = retire(&orchestrator, &old).await {
+            log_warn!(
+                "retiring dead pod {} failed, retrying: {e}",
+                old.id,
+                e = ErrorChain(&e)
+            );
+            continue;
+        }
+        let new = match orchestrator.ensure_ready_pod_with(&overrides).await {
+            Ok(new) => new,
+            Err(e) => {
+                log_warn!(
+                    "re-leasing pod {} failed, retrying: {e}",
+                    old.name,
+                    e = ErrorChain(&e)
+                );
+                continue;
+            }
+        };
+
+        shared.pinger.replace_lease(&new);
+        shared.generation.fetch_add(1, Ordering::SeqCst);
+        if new.id != old.id {
+            notifier.notify(&Notification {
+                severity: Severity::Info,
+                kind: "lease_renewed",
+                message: format!("pod {} re-leased: {} -> {}", new.name, old.id, new.id),
+                at_ms: now_unix_ms(),
+            });
+        }
+        on_swap(&old, &new);
+    }
+}
+
+/// Terminate the pod of `old` if `RunPod` still runs it: it does not answer,
+/// and the ensure flow would reuse it as it is.
+async fn retire(
+    orchestrator: &RunpodOrchestrator,
+    old: &PodLease,
+) -> Result<(), OrchestratorError> {
+    match orchestrator.pod_status(&old.id).await? {
+        PodStatus::Running | PodStatus::Starting => {
+            let timeout = Duration::from_millis(orchestrator.config().terminate_timeout_ms);
+            orchestrator.terminate_and_wait(&old.id, timeout).await?;
+            Ok(())
+        }
+        PodStatus::Created | PodStatus::Exited | PodStatus::Terminated => Ok(()),
+    }
+}
+
+// ============================================================================
+// Error type
+// ============================================================================
+
+/// Error type for liveness configuration.
+#[derive(Debug)]
+pub enum LivenessError {
+    /// Invalid environment variable value.
+    InvalidEnv {
+        /// The environment variable key.
+        key: &'static str,
+        /// The reason for invalidity.
+        reason: &'static str,
+    },
+    /// The env file (`RUNPOD_ENV_FILE`) could not be loaded.
+    EnvFile(EnvFileError),
+}
+
+impl fmt::Display for LivenessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
+            Self::EnvFile(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for LivenessError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::EnvFile(e) => std::error::Error::source(e),
+            Self::InvalidEnv { .. } => None,
+        }
+    }
+}
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+fn parse_u64_env(key: &'static str, default: u64) -> Result<u64, LivenessError> {
+    runpod_env::u64_var(key, default).map_err(|reason| LivenessError::InvalidEnv { key, reason })
+}
+
+fn parse_u32_env(key: &'static str, default: u32) -> Result<u32, LivenessError> {
+    runpod_env::u32_var(key, default).map_err(|reason| LivenessError::InvalidEnv { key, reason })
+}
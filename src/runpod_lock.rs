@@ -0,0 +1,383 @@
+//! Distributed locking.
+//!
+//! Unique responsibility: serialize critical sections (reconcile + create) of
+//! orchestrators running in several processes or on several hosts.
+//!
+//! A lock is a named record with an owner and an expiry (the semantics of
+//! Redis `SET key owner NX PX ttl`): acquiring succeeds if the record is
+//! absent, expired, or already held by the same owner (which refreshes it).
+//! The expiry guarantees a crashed holder never blocks the others forever;
+//! a live holder refreshes it while it works. Each acquisition uses its own
+//! owner token (`acquisition_token()`), so two critical sections of one
+//! process never mistake each other's lock for their own.
+//!
+//! Backends:
+//! - `FileLock`: one lock file per key in a shared directory (same host or
+//!   a shared filesystem), created with `O_EXCL`
+//! - `MemoryLock`: in-process, for tests and single-process deployments
+//! - Any other store (Redis, etcd, a database) by implementing `DistributedLock`
+//!
+//! ```ignore
+//! let orchestrator = RunpodOrchestrator::new(cfg)?
+//!     .with_lock(Arc::new(FileLock::new("/var/lock/runpod")));
+//! // Two replicas calling this concurrently create at most one pod.
+//! let pod = orchestrator.ensure_ready_pod().await?;
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt, fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::runpod_state::now_unix_ms;
+
+/// Lock shared between orchestrator processes.
+///
+/// Implementations must make `try_acquire()` atomic: two owners racing for
+/// the same free key never both succeed.
+pub trait DistributedLock: Send + Sync {
+    /// Try to take `key` for `owner` during `ttl`, without waiting.
+    ///
+    /// Returns `true` if `owner` now holds the lock (an expired lock is taken
+    /// over, a lock already held by `owner` is refreshed), `false` if another
+    /// owner holds it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend cannot be reached.
+    fn try_acquire(&self, key: &str, owner: &str, ttl: Duration) -> Result<bool, LockError>;
+
+    /// Release `key` if `owner` holds it (otherwise do nothing).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backend cannot be reached.
+    fn release(&self, key: &str, owner: &str) -> Result<(), LockError>;
+}
+
+/// Persisted lock record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockRecord {
+    /// Current holder.
+    pub owner: String,
+    /// Expiry (ms since UNIX epoch).
+    pub expires_at_ms: u64,
+}
+
+impl LockRecord {
+    fn new(owner: &str, ttl: Duration) -> Self {
+        let ttl_ms = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+        Self {
+            owner: owner.to_string(),
+            expires_at_ms: now_unix_ms().saturating_add(ttl_ms),
+        }
+    }
+
+    /// Check whether the lock has expired at `now_ms`.
+    #[must_use]
+    pub const fn is_expired(&self, now_ms: u64) -> bool {
+        now_ms >= self.expires_at_ms
+    }
+}
+
+/// How long an unreadable (partially written) lock file counts as held.
+const PARTIAL_RECORD_GRACE_MS: u64 = 10_000;
+
+/// Owner token unique to one acquisition (`<pid>-<ms>-<sequence>`).
+#[must_use]
+pub fn acquisition_token() -> String {
+    static SEQUENCE: AtomicU64 = AtomicU64::new(0);
+    format!(
+        "{}-{}-{}",
+        std::process::id(),
+        now_unix_ms(),
+        SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Run a (blocking) lock call on Tokio's blocking thread pool.
+///
+/// # Errors
+///
+/// Returns the call's error, or `LockError::Backend` if the call panicked.
+pub async fn run_blocking<T: Send + 'static>(
+    lock: &Arc<dyn DistributedLock>,
+    call: impl FnOnce(&dyn DistributedLock) -> Result<T, LockError> + Send + 'static,
+) -> Result<T, LockError> {
+    let lock = Arc::clone(lock);
+    tokio::task::spawn_blocking(move || call(lock.as_ref()))
+        .await
+        .map_err(|e| LockError::Backend(format!("lock call panicked: {e}")))?
+}
+
+// ============================================================================
+// File backend
+// ============================================================================
+
+/// Lock files in a directory: `<dir>/<key>.lock`, holding a JSON `LockRecord`.
+///
+/// Works across processes on one host, and across hosts on a shared
+/// filesystem with atomic exclusive create and rename (`NFSv3`+, most cloud
+/// file shares).
+#[derive(Debug, Clone)]
+pub struct FileLock {
+    dir: PathBuf,
+}
+
+impl FileLock {
+    /// Create a file lock backend storing its files in `dir` (created on demand).
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Get the lock directory.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the lock file for `key`.
+    ///
+    /// Characters outside `[A-Za-z0-9_-]` are replaced with `_` so a key can
+    /// never escape the lock directory.
+    #[must_use]
+    pub fn path_for(&self, key: &str) -> PathBuf {
+        let name: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        self.dir.join(format!("{name}.lock"))
+    }
+
+    fn read(path: &Path) -> Result<Option<LockRecord>, LockError> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(LockError::Io(e)),
+        };
+        if let Ok(record) = serde_json::from_slice(&bytes) {
+            return Ok(Some(record));
+        }
+
+        // Being written right now, or its holder crashed mid-write: live for
+        // a short grace period after creation, expired afterwards.
+        let created_ms = fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX));
+        Ok(Some(LockRecord {
+            owner: String::new(),
+            expires_at_ms: created_ms.saturating_add(PARTIAL_RECORD_GRACE_MS),
+        }))
+    }
+
+    fn create(path: &Path, record: &LockRecord) -> Result<bool, LockError> {
+        let mut file = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Ok(false),
+            Err(e) => return Err(LockError::Io(e)),
+        };
+        file.write_all(&serde_json::to_vec(record)?)?;
+        file.sync_all()?;
+        Ok(true)
+    }
+
+    /// Move the lock file out of the way, returning where it was moved
+    /// (`None` if there was none).
+    ///
+    /// The rename is atomic, so of several takers only one moves the file
+    /// and only the taker then looks at it.
+    fn take(path: &Path) -> Result<Option<PathBuf>, LockError> {
+        let taken = path.with_extension(format!("lock.{}.taken", acquisition_token()));
+        match fs::rename(path, &taken) {
+            Ok(()) => Ok(Some(taken)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(LockError::Io(e)),
+        }
+    }
+
+    /// Put a taken lock file back, unless another lock was created meanwhile.
+    fn put_back(taken: &Path, path: &Path) {
+        // `hard_link` never replaces an existing file.
+        let _ = fs::hard_link(taken, path);
+    }
+
+    /// Refresh a lock held by `owner`: the file is taken, checked and
+    /// re-created, so a holder whose lock was taken over never overwrites
+    /// the new holder's file.
+    fn refresh(path: &Path, owner: &str, record: &LockRecord) -> Result<bool, LockError> {
+        let Some(taken) = Self::take(path)? else {
+            return Self::create(path, record);
+        };
+        let ours = Self::read(&taken)?.is_some_and(|r| r.owner == owner);
+        let acquired = if ours {
+            Self::create(path, record)?
+        } else {
+            Self::put_back(&taken, path);
+            false
+        };
+        fs::remove_file(&taken)?;
+        Ok(acquired)
+    }
+
+    /// Move an expired lock file out of the way.
+    ///
+    /// If what was taken turns out to be a live lock (re-created in
+    /// between), it is put back.
+    fn evict(path: &Path) -> Result<(), LockError> {
+        let Some(taken) = Self::take(path)? else {
+            return Ok(());
+        };
+        if Self::read(&taken)?.is_some_and(|r| !r.is_expired(now_unix_ms())) {
+            Self::put_back(&taken, path);
+        }
+        fs::remove_file(&taken)?;
+        Ok(())
+    }
+}
+
+impl DistributedLock for FileLock {
+    fn try_acquire(&self, key: &str, owner: &str, ttl: Duration) -> Result<bool, LockError> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path_for(key);
+        let record = LockRecord::new(owner, ttl);
+
+        if Self::create(&path, &record)? {
+            return Ok(true);
+        }
+        match Self::read(&path)? {
+            Some(current) if current.owner == owner => Self::refresh(&path, owner, &record),
+            Some(current) if !current.is_expired(now_unix_ms()) => Ok(false),
+            _ => {
+                Self::evict(&path)?;
+                Self::create(&path, &record)
+            }
+        }
+    }
+
+    fn release(&self, key: &str, owner: &str) -> Result<(), LockError> {
+        // Compare-and-delete: take the file first, so a lock created by
+        // another owner after ours expired is never the one removed.
+        let path = self.path_for(key);
+        let Some(taken) = Self::take(&path)? else {
+            return Ok(());
+        };
+        if Self::read(&taken)?.is_none_or(|r| r.owner != owner) {
+            Self::put_back(&taken, &path);
+        }
+        fs::remove_file(&taken)?;
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Memory backend
+// ============================================================================
+
+/// In-process lock table.
+#[derive(Debug, Default)]
+pub struct MemoryLock {
+    locks: Mutex<HashMap<String, LockRecord>>,
+}
+
+impl MemoryLock {
+    /// Create an empty lock table.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl DistributedLock for MemoryLock {
+    fn try_acquire(&self, key: &str, owner: &str, ttl: Duration) -> Result<bool, LockError> {
+        let mut locks = self.locks.lock().map_err(|_| LockError::Poisoned)?;
+        let free = locks
+            .get(key)
+            .is_none_or(|r| r.owner == owner || r.is_expired(now_unix_ms()));
+        if free {
+            locks.insert(key.to_string(), LockRecord::new(owner, ttl));
+        }
+        drop(locks);
+        Ok(free)
+    }
+
+    fn release(&self, key: &str, owner: &str) -> Result<(), LockError> {
+        let mut locks = self.locks.lock().map_err(|_| LockError::Poisoned)?;
+        if locks.get(key).is_some_and(|r| r.owner == owner) {
+            locks.remove(key);
+        }
+        drop(locks);
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Error type
+// ============================================================================
+
+/// Error type for lock backends.
+#[derive(Debug)]
+pub enum LockError {
+    /// I/O error.
+    Io(io::Error),
+    /// Serialization error.
+    Serde(serde_json::Error),
+    /// A thread panicked while holding the in-process lock table.
+    Poisoned,
+    /// Backend-specific error (for custom implementations).
+    Backend(String),
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(_) => write!(f, "io error"),
+            Self::Serde(_) => write!(f, "serde error"),
+            Self::Poisoned => write!(f, "lock table poisoned"),
+            Self::Backend(e) => write!(f, "lock backend error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for LockError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Serde(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LockError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for LockError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Serde(e)
+    }
+}
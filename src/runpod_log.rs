@@ -0,0 +1,59 @@
+//! Internal diagnostics facade.
+//!
+//! Unique responsibility: route the crate's diagnostic messages (retries,
+//! failovers, pod lifecycle) to whichever logging ecosystem the consumer uses,
+//! without forcing a dependency on either.
+//!
+//! Backends are selected with Cargo features:
+//! - `log`: messages go through the `log` crate (target `halldyll_starter`)
+//! - `tracing`: messages are emitted as `tracing` events
+//!
+//! With neither feature enabled the macros compile to nothing. Enabling both
+//! emits every message to both backends.
+
+/// Emit a diagnostic message at `$level` (`error`, `warn`, `info`, `debug`, `trace`).
+macro_rules! emit {
+    ($level:ident, $($arg:tt)+) => {{
+        #[cfg(feature = "log")]
+        ::log::$level!(target: "halldyll_starter", $($arg)+);
+        #[cfg(feature = "tracing")]
+        ::tracing::$level!(target: "halldyll_starter", $($arg)+);
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        {
+            let _ = format_args!($($arg)+);
+        }
+    }};
+}
+
+/// Emit a warning (something failed but the crate recovered or moved on).
+macro_rules! log_warn {
+    ($($arg:tt)+) => { $crate::runpod_log::emit!(warn, $($arg)+) };
+}
+
+/// Emit an informational message (pod lifecycle steps).
+macro_rules! log_info {
+    ($($arg:tt)+) => { $crate::runpod_log::emit!(info, $($arg)+) };
+}
+
+/// Emit a debug message (retries and other routine detail).
+macro_rules! log_debug {
+    ($($arg:tt)+) => { $crate::runpod_log::emit!(debug, $($arg)+) };
+}
+
+pub(crate) use {emit, log_debug, log_info, log_warn};
+
+/// Display an error followed by its `source()` chain, `": "`-separated, so a
+/// logged error keeps its cause.
+pub struct ErrorChain<'a>(pub &'a dyn std::error::Error);
+
+impl std::fmt::Display for ErrorChain<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)?;
+        let mut source = self.0.source();
+        while let Some(e) = source {
+            write!(f, ": {e}")?;
+            source = e.source();
+        }
+        Ok(())
+    }
+}
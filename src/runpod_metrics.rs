@@ -0,0 +1,86 @@
+//! Pluggable metrics.
+//!
+//! Unique responsibility: define the `MetricsSink` trait through which the
+//! client, starter and orchestrator report counters, gauges and histograms.
+//!
+//! Nothing is recorded by default (`NoopMetrics`). Bridge to statsd,
+//! `CloudWatch`, Prometheus or your own telemetry by implementing the trait
+//! and passing it to `with_metrics()`:
+//!
+//! ```ignore
+//! struct Stdout;
+//!
+//! impl MetricsSink for Stdout {
+//!     fn counter(&self, name: &str, value: u64, tags: &[(&str, &str)]) {
+//!         eprintln!("{name} +{value} {tags:?}");
+//!     }
+//! }
+//!
+//! let orchestrator = RunpodOrchestrator::new(cfg)?.with_metrics(Arc::new(Stdout));
+//! ```
+//!
+//! Emitted metrics (see the constants below for names):
+//! - HTTP requests, latencies and retries (all components, tag `component`)
+//! - Pods created and readiness wait times (orchestrator)
+//! - `ensure_ready_pod()` duration and outcome (orchestrator, tag `action`/`outcome`)
+//! - Data center failovers (orchestrator, tags `data_center`/`reason`)
+//! - Pre-stop command runs (orchestrator, tag `outcome`)
+//! - Spot preemptions seen by `monitor()` (orchestrator, tag `policy`)
+
+use std::sync::Arc;
+
+/// Counter: HTTP requests sent (tags: `component`, `status`).
+pub const HTTP_REQUESTS: &str = "runpod.http.requests";
+/// Histogram: HTTP request latency in milliseconds (tags: `component`).
+pub const HTTP_REQUEST_MS: &str = "runpod.http.request_ms";
+/// Counter: HTTP retries (tags: `component`).
+pub const HTTP_RETRIES: &str = "runpod.http.retries";
+/// Counter: pods created (tags: `cloud_type`).
+pub const PODS_CREATED: &str = "runpod.orchestrator.pods_created";
+/// Histogram: time spent waiting for readiness in milliseconds.
+pub const READY_WAIT_MS: &str = "runpod.orchestrator.ready_wait_ms";
+/// Histogram: `ensure_ready_pod()` duration in milliseconds (tags: `outcome`, `action`).
+pub const ENSURE_READY_MS: &str = "runpod.orchestrator.ensure_ready_ms";
+/// Counter: data centers given up on during creation (tags: `data_center`, `reason`).
+pub const DATA_CENTER_FAILOVERS: &str = "runpod.orchestrator.data_center_failovers";
+/// Gauge: pods returned by the last listing.
+pub const PODS_LISTED: &str = "runpod.orchestrator.pods_listed";
+/// Histogram: pre-stop command duration in milliseconds (tags: `outcome`).
+pub const PRE_STOP_HOOK_MS: &str = "runpod.orchestrator.pre_stop_hook_ms";
+/// Histogram: host benchmark duration in milliseconds (tags: `outcome`).
+pub const HOST_BENCHMARK_MS: &str = "runpod.orchestrator.host_benchmark_ms";
+/// Counter: spot preemptions seen by `monitor()` (tags: `policy`).
+pub const PODS_PREEMPTED: &str = "runpod.orchestrator.pods_preempted";
+
+/// Receiver for metrics emitted by this crate.
+///
+/// Every method has a no-op default, so adapters only implement what they need.
+/// Calls happen inline on the async path: keep them cheap and non-blocking.
+pub trait MetricsSink: Send + Sync {
+    /// Increment a counter by `value`.
+    fn counter(&self, _name: &str, _value: u64, _tags: &[(&str, &str)]) {}
+
+    /// Set a gauge to `value`.
+    fn gauge(&self, _name: &str, _value: f64, _tags: &[(&str, &str)]) {}
+
+    /// Record one observation in a histogram.
+    fn histogram(&self, _name: &str, _value: f64, _tags: &[(&str, &str)]) {}
+}
+
+/// Metrics sink that discards everything (the default).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl MetricsSink for NoopMetrics {}
+
+/// The default sink, shared as `Arc<dyn MetricsSink>`.
+#[must_use]
+pub fn noop() -> Arc<dyn MetricsSink> {
+    Arc::new(NoopMetrics)
+}
+
+/// Milliseconds elapsed since `start`, as a histogram value.
+#[must_use]
+pub fn elapsed_ms(start: std::time::Instant) -> f64 {
+    start.elapsed().as_secs_f64() * 1000.0
+}
@@ -0,0 +1,120 @@
+//! Private networking between managed pods.
+//!
+//! Unique responsibility: name the private (pod-to-pod) endpoints of managed
+//! pods and check they are reachable.
+//!
+//! Two private networks exist on `RunPod`:
+//! - Global networking (`RUNPOD_GLOBAL_NETWORKING=true` at creation): every
+//!   pod is reachable from the others as `<pod_id>.runpod.internal`, on its
+//!   container ports (no public port mapping involved)
+//! - Instant Clusters: nodes talk over the cluster network using the
+//!   `cluster_ip` of each `ClusterNode`
+//!
+//! Private names only resolve inside pods on the same network, so
+//! `check_connectivity()` is meant to run from one of them (e.g. a
+//! coordinator pod checking its workers before a job).
+//!
+//! ```ignore
+//! let workers: Vec<_> = leases.iter().map(|l| l.private_endpoint(29500)).collect();
+//! for check in runpod_network::check_connectivity(&workers, Duration::from_secs(3)).await {
+//!     if !check.is_reachable() {
+//!         eprintln!("{} unreachable: {:?}", check.endpoint, check.error);
+//!     }
+//! }
+//! ```
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+/// DNS suffix of pods on the global private network.
+pub const PRIVATE_DNS_SUFFIX: &str = "runpod.internal";
+
+/// Private host name of a pod on the global network.
+#[must_use]
+pub fn private_host(pod_id: &str) -> String {
+    format!("{pod_id}.{PRIVATE_DNS_SUFFIX}")
+}
+
+/// Pod-to-pod endpoint on a private network.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct PrivateEndpoint {
+    /// Pod ID.
+    pub pod_id: String,
+    /// Private host name or address.
+    pub host: String,
+    /// Container port (private ports are not remapped).
+    pub port: u16,
+}
+
+impl PrivateEndpoint {
+    /// Endpoint of `pod_id` on the global network.
+    #[must_use]
+    pub fn global(pod_id: &str, port: u16) -> Self {
+        Self {
+            pod_id: pod_id.to_string(),
+            host: private_host(pod_id),
+            port,
+        }
+    }
+}
+
+impl fmt::Display for PrivateEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+/// Result of one reachability check.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectivityCheck {
+    /// Endpoint checked.
+    pub endpoint: PrivateEndpoint,
+    /// Time to establish the TCP connection, if it succeeded.
+    pub latency: Option<Duration>,
+    /// Why the connection failed (resolution, refusal, timeout).
+    pub error: Option<String>,
+}
+
+impl ConnectivityCheck {
+    /// Check whether the endpoint accepted a TCP connection.
+    #[must_use]
+    pub const fn is_reachable(&self) -> bool {
+        self.latency.is_some()
+    }
+}
+
+/// Open a TCP connection to every endpoint, concurrently.
+///
+/// Results are returned in the order of `endpoints`; a check never fails as
+/// a whole, unreachable endpoints carry an `error` instead.
+pub async fn check_connectivity(
+    endpoints: &[PrivateEndpoint],
+    timeout: Duration,
+) -> Vec<ConnectivityCheck> {
+    futures_util::future::join_all(
+        endpoints
+            .iter()
+            .map(|endpoint| check_one(endpoint, timeout)),
+    )
+    .await
+}
+
+async fn check_one(endpoint: &PrivateEndpoint, timeout: Duration) -> ConnectivityCheck {
+    let started = Instant::now();
+    let addr = (endpoint.host.as_str(), endpoint.port);
+    let result = match tokio::time::timeout(timeout, tokio::net::TcpStream::connect(addr)).await {
+        Ok(Ok(_)) => Ok(started.elapsed()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("timed out after {}ms", timeout.as_millis())),
+    };
+
+    ConnectivityCheck {
+        endpoint: endpoint.clone(),
+        latency: result.as_ref().ok().copied(),
+        error: result.err(),
+    }
+}
@@ -0,0 +1,141 @@
+//! Notifications.
+//!
+//! Unique responsibility: define the `Notifier` trait through which the
+//! crate reports events that need a human (budget alerts, enforcement).
+//!
+//! Nothing is sent by default (`NoopNotifier`). Any `Fn(&Notification)`
+//! closure is a notifier; `WebhookNotifier` posts to a Slack-compatible
+//! incoming webhook (`{"text": ...}`), which most chat tools accept.
+//!
+//! ```ignore
+//! let notifier: Arc<dyn Notifier> = match WebhookNotifier::from_env() {
+//!     Some(webhook) => Arc::new(webhook),
+//!     None => Arc::new(|n: &Notification| eprintln!("[{:?}] {}", n.severity, n.message)),
+//! };
+//! ```
+
+use std::{env, sync::Arc};
+
+use serde::Serialize;
+use tokio::runtime::Handle;
+
+use crate::runpod_log::log_warn;
+
+/// How urgent a notification is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// For information only.
+    Info,
+    /// Needs attention soon.
+    Warning,
+    /// Needs attention now (or an automatic action was taken).
+    Critical,
+}
+
+/// One notification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Notification {
+    /// How urgent it is.
+    pub severity: Severity,
+    /// Short machine-friendly kind (e.g. `budget_threshold`).
+    pub kind: &'static str,
+    /// Human-readable message.
+    pub message: String,
+    /// Timestamp (ms since UNIX epoch).
+    pub at_ms: u64,
+}
+
+/// Receiver for notifications emitted by this crate.
+///
+/// Calls happen inline: keep them cheap and non-blocking (spawn for I/O).
+/// Implemented for any `Fn(&Notification) + Send + Sync` closure.
+pub trait Notifier: Send + Sync {
+    /// Handle one notification.
+    fn notify(&self, notification: &Notification);
+}
+
+impl<F: Fn(&Notification) + Send + Sync> Notifier for F {
+    fn notify(&self, notification: &Notification) {
+        self(notification);
+    }
+}
+
+/// Notifier that drops everything.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopNotifier;
+
+impl Notifier for NoopNotifier {
+    fn notify(&self, _notification: &Notification) {}
+}
+
+/// Shared no-op notifier (the default everywhere).
+#[must_use]
+pub fn noop() -> Arc<dyn Notifier> {
+    Arc::new(NoopNotifier)
+}
+
+/// Notifier posting `{"text": "..."}` to an incoming webhook (Slack, Mattermost, ...).
+///
+/// Each notification is sent from a task spawned on the current Tokio
+/// runtime; outside a runtime it is dropped with a warning. Delivery failures
+/// are dropped too.
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    url: String,
+    http: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    /// Create a notifier posting to `url` with a client of its own.
+    #[must_use]
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::new_with_http(url, reqwest::Client::new())
+    }
+
+    /// Create a notifier posting to `url` through an existing client (e.g.
+    /// `RunpodOrchestrator::http_client()`), sharing its connections and
+    /// HTTP options.
+    #[must_use]
+    pub fn new_with_http(url: impl Into<String>, http: reqwest::Client) -> Self {
+        Self {
+            url: url.into(),
+            http,
+        }
+    }
+
+    /// Create a notifier from `RUNPOD_NOTIFY_WEBHOOK_URL`, if set.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        Self::from_env_with_http(reqwest::Client::new())
+    }
+
+    /// Create a notifier from `RUNPOD_NOTIFY_WEBHOOK_URL`, if set, posting
+    /// through `http`.
+    #[must_use]
+    pub fn from_env_with_http(http: reqwest::Client) -> Option<Self> {
+        env::var("RUNPOD_NOTIFY_WEBHOOK_URL")
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+            .map(|url| Self::new_with_http(url, http))
+    }
+}
+
+impl Notifier for WebhookNotifier {
+    fn notify(&self, notification: &Notification) {
+        let body = serde_json::json!({
+            "text": format!("[{:?}] {}", notification.severity, notification.message),
+        });
+        let Ok(handle) = Handle::try_current() else {
+            log_warn!(
+                "notification {} dropped: no Tokio runtime to send it",
+                notification.kind
+            );
+            return;
+        };
+        let req = self.http.post(&self.url).json(&body);
+        handle.spawn(async move {
+            let _ = req.send().await;
+        });
+    }
+}
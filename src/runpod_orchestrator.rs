@@ -10,29 +10,128 @@
 //! - List pods and filter by name
 //! - Check pod compatibility (image, ports, GPU)
 //! - Start stopped pods or create new ones
+//! - Update mutable fields (image, env, ports) in place instead of recreating
+//! - Replace pods while keeping their network volume (`replace_pod()`)
+//! - Optionally check the image exists in its registry before creating a pod
+//! - Optionally ask for confirmation (with the estimated cost) before creating a pod
+//! - Optionally report progress (phase, percentage, time estimates) while working
 //! - Wait for network readiness (publicIp + portMappings)
+//! - Clone / copy network volumes through temporary utility pods
+//! - Create multi-node Instant Clusters (`create_cluster()`), waiting for each node
+//!
+//! With `RUNPOD_SIMULATE=true`, no network call is made: every operation is
+//! served by the in-memory model of `runpod_simulator`.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    env, fmt,
+    future::Future,
+    hash::{Hash, Hasher},
+    path::Path,
+    pin::Pin,
+    sync::{Arc, mpsc},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::{self, Either};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::runpod_availability::{self, AvailabilityTracker, ProvisionOutcome};
+use crate::runpod_client::{
+    ClusterInfo, ClusterPod, CreateClusterInput, EnvVar, RunpodClient, RunpodClientConfig,
+};
+use crate::runpod_cost::{SpendCapAction, SpendCapStatus, SpendGuard, SpendGuardTask};
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_guard::{LeaseGuard, LeaseReleasePolicy};
+use crate::runpod_http::{
+    self, ApiKey, ApiResource, ApiVersion, HttpOptions, RedactedEnv, RetryPolicy,
+};
+use crate::runpod_idle::{IdleMonitor, IdlePolicy};
+#[cfg(feature = "jupyter")]
+use crate::runpod_jupyter::{JupyterClient, JupyterError};
+use crate::runpod_labels::{self, LABELS_ENV, LabelSelector};
+use crate::runpod_liveness::{LivenessConfig, LivenessPinger};
+use crate::runpod_lock::{self, DistributedLock, FileLock, LockError};
+use crate::runpod_log::{ErrorChain, log_debug, log_info, log_warn};
+use crate::runpod_metrics::{self, MetricsSink};
+use crate::runpod_network::{self, PrivateEndpoint};
+use crate::runpod_notify::Notifier;
+use crate::runpod_probe::{ReadinessCheck, ReadinessProbe};
+use crate::runpod_provisioner::{
+    CreatedPod, GpuFallback, RunpodError, RunpodProvisionConfig, RunpodProvisioner,
+};
+use crate::runpod_reconciler::{ReconcileAction, ReconcilerHandle};
+use crate::runpod_registry::{self, RegistryCredentials, RegistryError};
+use crate::runpod_schedule::Schedule;
+use crate::runpod_simulator::SimulatedCloud;
+#[cfg(feature = "ssh")]
+use crate::runpod_ssh::{SshError, SshLogin};
+use crate::runpod_state::{
+    self as runpod_state, ActionOutcome, FleetPlan, FleetState, PlanReason, PlannedAction,
+    PodDesiredStatus, PodId, PodUsage, RemoteObservation, RemotePodSnapshot, RunPodState,
+    StateStore, StateStoreError, TargetStatus, TerminateOutcome, is_pod_gone_error, now_unix_ms,
+};
+use crate::runpod_ttl::{self, LeaseHeartbeat, LeaseRecord, LeaseRegistry};
 
-use std::{collections::HashMap, env, fmt, time::Duration};
+/// Port the source utility pod of a volume copy serves its archive on,
+/// on the global private network only.
+#[cfg(feature = "ssh")]
+const COPY_STREAM_PORT: u16 = 9000;
 
-use serde::Deserialize;
+/// Start command of the destination utility pod of a volume copy: an SSH
+/// server accepting the account's keys (`PUBLIC_KEY`, set by `RunPod`),
+/// installed with `apk` if the image has none.
+#[cfg(feature = "ssh")]
+const COPY_SSHD_SCRIPT: &str = "command -v sshd >/dev/null || apk add --no-cache openssh-server \
+     >/dev/null; ssh-keygen -A >/dev/null; mkdir -p -m 700 /root/.ssh; \
+     echo \"$PUBLIC_KEY\" > /root/.ssh/authorized_keys; exec \"$(command -v sshd)\" -D -e";
 
-use crate::runpod_provisioner::{CreatedPod, RunpodProvisionConfig, RunpodProvisioner};
+/// Timeout of one proxy URL probe in proxy exposure mode.
+const PROXY_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Configuration for the `RunPod` orchestrator.
 #[derive(Clone, Debug)]
 pub struct RunpodOrchestratorConfig {
     /// `RunPod` API key for authentication.
     /// Env: `RUNPOD_API_KEY` (required)
-    pub api_key: String,
+    pub api_key: ApiKey,
+
+    /// REST API version: URL default and request/response mapping.
+    /// Env: `RUNPOD_API_VERSION` (default: "v1")
+    pub api_version: ApiVersion,
 
     /// REST API URL for `RunPod`.
-    /// Env: `RUNPOD_REST_URL` (default: "<https://rest.runpod.io/v1>")
+    /// Env: `RUNPOD_REST_URL` (default: the `api_version` URL, "<https://rest.runpod.io/v1>")
     pub rest_url: String,
 
+    /// GraphQL API URL for `RunPod` (prices, clusters, runtime readiness).
+    /// Env: `RUNPOD_GRAPHQL_URL` (default: "<https://api.runpod.io/graphql>")
+    pub graphql_url: String,
+
     /// Pod name to find or create.
     /// Env: `RUNPOD_POD_NAME` (default: "halldyll-pod")
     pub pod_name: String,
 
+    /// Names of the pods of `ensure_ready_pods()`: `{name}` stands for
+    /// `pod_name` and `{n}` for the pod index (0, 1, ...).
+    /// Env: `RUNPOD_POD_NAME_PATTERN` (default: "{name}-{n}")
+    pub pod_name_pattern: String,
+
+    /// Labels given to created pods (see `runpod_labels`).
+    /// Env: `RUNPOD_POD_LABELS` (optional, e.g. "halldyll.role=worker,team=ml")
+    pub pod_labels: BTreeMap<String, String>,
+
+    /// Find the pod by label instead of by `pod_name`: the first pod
+    /// matching it is used (running pods first), and pods created for it
+    /// get its `key=value` labels. A name override takes precedence.
+    /// Env: `RUNPOD_POD_SELECTOR` (optional, e.g. "halldyll.role=worker")
+    pub pod_selector: Option<LabelSelector>,
+
     /// Container image name.
     /// Env: `RUNPOD_IMAGE_NAME` (required)
     pub image_name: String,
@@ -41,11 +140,34 @@ pub struct RunpodOrchestratorConfig {
     /// Env: `RUNPOD_PORTS` (default: "22/tcp,8888/http")
     pub required_ports: Vec<String>,
 
+    /// Alternate container ports for required ports that conflict
+    /// (the same container port requested twice with different protocols).
+    /// Env: `RUNPOD_PORT_ALTERNATES` (default: none)
+    /// Format: "8888:8889|8890,3000:3001" (port:alternate|alternate,...)
+    ///
+    /// A conflicting port is remapped to its first alternate not already
+    /// requested; the remap is reported in `PodLease::port_remaps`.
+    pub port_alternates: HashMap<u16, Vec<u16>>,
+
+    /// Container ports readiness waits on; the other required ports are
+    /// exposed without blocking readiness (e.g. debug ports).
+    /// Env: `RUNPOD_READY_PORTS` (default: none = every required port)
+    /// Format: "8888" or "8888/http", comma-separated; a subset of `RUNPOD_PORTS`
+    pub ready_ports: Vec<u16>,
+
+    /// How each container port is checked once mapped, before the pod
+    /// counts as ready (public exposure; simulated pods are not probed).
+    /// Env: `RUNPOD_READY_PROBES` (default: none = ready once mapped)
+    /// Format: "22=ssh,8888=http:/healthz:200,9000=tcp" (probes: mapped, tcp,
+    /// ssh, http[:/path[:status]]); ports listed in `RUNPOD_PORTS`
+    pub ready_probes: HashMap<u16, ReadinessProbe>,
+
     /// GPU type IDs.
     /// Env: `RUNPOD_GPU_TYPE_IDS` (default: "NVIDIA A40")
     pub gpu_type_ids: Vec<String>,
 
-    /// HTTP request timeout in milliseconds.
+    /// Total HTTP request timeout in milliseconds (0 = none; see also
+    /// `HttpOptions::connect_timeout_ms` and `read_timeout_ms`).
     /// Env: `RUNPOD_HTTP_TIMEOUT_MS` (default: 30000)
     pub timeout_ms: u64,
 
@@ -53,14 +175,170 @@ pub struct RunpodOrchestratorConfig {
     /// Env: `RUNPOD_READY_TIMEOUT_MS` (default: 300000 = 5 minutes)
     pub ready_timeout_ms: u64,
 
-    /// Poll interval for readiness checks in milliseconds.
+    /// Maximum (ceiling) poll interval for readiness checks in milliseconds.
     /// Env: `RUNPOD_POLL_INTERVAL_MS` (default: 5000)
     pub poll_interval_ms: u64,
 
+    /// Where readiness checks read the pod's public IP and port mappings.
+    /// Env: `RUNPOD_READINESS_SOURCE` (default: "rest")
+    /// Options: "rest", "graphql" (`runtime.ports`), "both" (ready as soon
+    /// as either reports the required ports)
+    ///
+    /// Ignored in simulation mode (REST only).
+    pub readiness_source: ReadinessSource,
+
+    /// How the pod's ports are reached.
+    /// Env: `RUNPOD_EXPOSURE` (default: "public")
+    /// Options: "public" (public IP + mapped ports), "proxy" (HTTP ports
+    /// through the `RunPod` proxy only, no public IP needed)
+    pub exposure: ExposureMode,
+
+    /// Domain of the `RunPod` HTTP proxy (`https://<pod_id>-<port>.<domain>`).
+    /// Env: `RUNPOD_PROXY_DOMAIN` (default: "proxy.runpod.net")
+    pub proxy_domain: String,
+
+    /// First poll interval for readiness checks in milliseconds; grows by
+    /// `poll_backoff` after each unchanged check, up to `poll_interval_ms`.
+    /// Env: `RUNPOD_POLL_INITIAL_MS` (default: 1000)
+    ///
+    /// Set it equal to `poll_interval_ms` for fixed-interval polling.
+    pub poll_initial_ms: u64,
+
+    /// Growth factor of the poll interval between unchanged checks.
+    /// Env: `RUNPOD_POLL_BACKOFF` (default: 1.5)
+    pub poll_backoff: f64,
+
     /// Reconcile mode when pod exists.
     /// Env: `RUNPOD_RECONCILE_MODE` (default: "reuse")
-    /// Options: "reuse", "recreate"
+    /// Options: "reuse", "recreate", "update"
+    ///
+    /// "recreate" goes through `replace_pod()`, so an attached network
+    /// volume is carried over to the new pod. "update" compares the pod with
+    /// the spec on every `CompatibilityCheck` and only recreates it when the
+    /// drift cannot be fixed in place.
     pub reconcile_mode: ReconcileMode,
+
+    /// Pods the "adopt" mode may take over: only pods whose labels match
+    /// are candidates, so unrelated pods of the account are never adopted.
+    /// Env: `RUNPOD_ADOPT_SELECTOR` (required with `RUNPOD_RECONCILE_MODE=adopt`,
+    /// e.g. "halldyll.adoptable")
+    pub adopt_selector: Option<LabelSelector>,
+
+    /// Ordered data center preference list for new pods. When set, each
+    /// data center is tried in turn, moving on when it has no capacity or
+    /// its pods never become ready.
+    /// Env: `RUNPOD_DATA_CENTER_FAILOVER` (optional, comma-separated, e.g. "EU-RO-1,US-TX-3")
+    ///
+    /// Network volumes live in one data center: leave it empty for pods
+    /// that mount one.
+    pub data_center_failover: Vec<String>,
+
+    /// Pods tried per data center whose readiness times out before failing
+    /// over to the next one.
+    /// Env: `RUNPOD_DATA_CENTER_READY_ATTEMPTS` (default: 1)
+    pub data_center_ready_attempts: u32,
+
+    /// Lifetime of a pod lock (see `with_lock()`), in milliseconds: the
+    /// holder refreshes it every third of this while it works, and a
+    /// crashed holder's lock expires after it.
+    /// Env: `RUNPOD_LOCK_TTL_MS` (default: 900000 = 15 minutes)
+    pub lock_ttl_ms: u64,
+
+    /// Maximum time to wait for a pod lock held by another orchestrator,
+    /// in milliseconds.
+    /// Env: `RUNPOD_LOCK_WAIT_MS` (default: 900000 = 15 minutes)
+    pub lock_wait_ms: u64,
+
+    /// Directory of a `FileLock` installed as the pod lock, so orchestrators
+    /// sharing it (same host or a shared filesystem) never fight over one
+    /// pod name; `with_lock()` overrides it.
+    /// Env: `RUNPOD_LOCK_DIR` (optional)
+    pub lock_dir: Option<String>,
+
+    /// Check the image exists in its registry before creating or updating a pod.
+    /// Env: `RUNPOD_IMAGE_PRECHECK` (default: false)
+    pub image_precheck: bool,
+
+    /// Credentials for the image pre-check (private images).
+    /// Env: `RUNPOD_REGISTRY_USERNAME` + `RUNPOD_REGISTRY_PASSWORD` (optional)
+    pub registry_credentials: Option<RegistryCredentials>,
+
+    /// Serve every operation from an in-memory simulator (no network calls).
+    /// Env: `RUNPOD_SIMULATE` (default: false)
+    ///
+    /// `RUNPOD_API_KEY` is optional in this mode.
+    pub simulate: bool,
+
+    /// Status reads before a simulated pod becomes ready.
+    /// Env: `RUNPOD_SIMULATE_READY_TICKS` (default: 3)
+    pub simulate_ready_ticks: u32,
+
+    /// Image of the utility pods used by `copy_volume()` (needs `sh`, `tar`, `nc`).
+    /// Env: `RUNPOD_VOLUME_COPY_IMAGE` (default: "alpine:3.20")
+    pub volume_copy_image: String,
+
+    /// Maximum time for a volume copy in milliseconds (after the utility
+    /// pods are ready).
+    /// Env: `RUNPOD_VOLUME_COPY_TIMEOUT_MS` (default: 3600000 = 1 hour)
+    pub volume_copy_timeout_ms: u64,
+
+    /// Maximum time to wait for a terminated pod to be gone in milliseconds
+    /// (Recreate mode waits before creating the replacement).
+    /// Env: `RUNPOD_TERMINATE_TIMEOUT_MS` (default: 120000 = 2 minutes)
+    pub terminate_timeout_ms: u64,
+
+    /// Maximum number of pod actions `apply_fleet()` (or pods
+    /// `ensure_ready_pods()`) runs at once.
+    /// Env: `RUNPOD_FLEET_CONCURRENCY` (default: 4)
+    pub fleet_concurrency: u32,
+
+    /// What a `LeaseGuard` does to its pod when released or dropped.
+    /// Env: `RUNPOD_LEASE_RELEASE_POLICY` (default: "stop")
+    /// Options: "keep", "stop", "terminate"
+    pub lease_release_policy: LeaseReleasePolicy,
+
+    /// Properties an existing pod must match to be reused.
+    /// Env: `RUNPOD_COMPATIBILITY_CHECKS` (default: "image")
+    /// Options (comma-separated): "image", "`gpu_type`", "`gpu_count`", "ports",
+    /// "volume", "env"
+    ///
+    /// Mismatched image, ports or env are updated in place; other mismatches
+    /// recreate the pod. Properties the API does not report for the pod, and
+    /// checks needing a provisioning config that cannot be loaded, are
+    /// skipped. The network volume is only compared when one is requested.
+    pub compatibility_checks: Vec<CompatibilityCheck>,
+
+    /// What `monitor()` does when a spot pod is preempted.
+    /// Env: `RUNPOD_ON_PREEMPTION` (default: "resume")
+    /// Options: "resume", "recreate", "fail"
+    pub on_preemption: PreemptionPolicy,
+
+    /// Command run on the pod over SSH before it is stopped or terminated
+    /// (flush caches, checkpoint a model, upload logs...).
+    /// Env: `RUNPOD_PRE_STOP_COMMAND` (optional, run with the `RUNPOD_SSH_*`
+    /// login, see `SshLogin::from_env()`)
+    ///
+    /// The lifecycle call proceeds once the command completes, fails or
+    /// times out; pods without an SSH endpoint are stopped right away.
+    pub pre_stop_command: Option<String>,
+
+    /// Maximum duration of the pre-stop command in milliseconds.
+    /// Env: `RUNPOD_PRE_STOP_TIMEOUT_MS` (default: 60000)
+    pub pre_stop_timeout_ms: u64,
+
+    /// Host benchmark run on newly created pods before they are handed out;
+    /// pods below the thresholds are terminated and recreated.
+    /// Env: `RUNPOD_BENCHMARK_MIN` (optional, enables it), see `HostBenchmark`
+    pub benchmark: Option<HostBenchmark>,
+
+    /// Connection pool / keepalive / HTTP version tuning.
+    /// Env: `RUNPOD_HTTP_*` (see `HttpOptions`)
+    pub http: HttpOptions,
+
+    /// Retry policy for transient failures, also handed to the provisioner
+    /// and GraphQL client the orchestrator creates.
+    /// Env: `RUNPOD_HTTP_RETRY_*` (see `RetryPolicy`)
+    pub retry: RetryPolicy,
 }
 
 /// Mode for reconciling existing pods.
@@ -69,304 +347,5727 @@ pub enum ReconcileMode {
     /// Reuse compatible existing pods.
     #[default]
     Reuse,
-    /// Always recreate pods.
+    /// Always recreate pods (keeping the network volume).
     Recreate,
+    /// Update drifted pods in place (a smaller pod volume is grown),
+    /// recreating them only when the GPU drifted. Any other volume drift
+    /// fails with `VolumeDrift`: recreating would lose the pod volume.
+    Update,
+    /// Reuse compatible pods like `Reuse`, and when no pod has the configured
+    /// name (or selector), adopt a compatible pod matching `adopt_selector`
+    /// instead of creating one (pods locked or leased by another client are
+    /// skipped). The adopted pod's ID is recorded in the state store, or without one
+    /// the pod is renamed, so later runs keep using it.
+    Adopt,
 }
 
-impl RunpodOrchestratorConfig {
-    /// Load configuration from environment variables.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if required environment variables are missing or invalid.
-    pub fn from_env() -> Result<Self, OrchestratorError> {
-        let _ = dotenvy::dotenv();
-
-        let reconcile_mode = env::var("RUNPOD_RECONCILE_MODE").map_or(ReconcileMode::Reuse, |v| {
-            if v.to_lowercase() == "recreate" {
-                ReconcileMode::Recreate
-            } else {
-                ReconcileMode::Reuse
-            }
-        });
+impl ReconcileMode {
+    /// Parse "reuse", "recreate", "update" or "adopt" (case-insensitive).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "reuse" => Some(Self::Reuse),
+            "recreate" => Some(Self::Recreate),
+            "update" => Some(Self::Update),
+            "adopt" => Some(Self::Adopt),
+            _ => None,
+        }
+    }
 
-        Ok(Self {
-            api_key: must_env("RUNPOD_API_KEY")?,
-            rest_url: env::var("RUNPOD_REST_URL")
-                .unwrap_or_else(|_| "https://rest.runpod.io/v1".to_string()),
-            pod_name: env::var("RUNPOD_POD_NAME")
-                .unwrap_or_else(|_| "halldyll-pod".to_string()),
-            image_name: must_env("RUNPOD_IMAGE_NAME")?,
-            required_ports: split_csv_env("RUNPOD_PORTS", "22/tcp,8888/http"),
-            gpu_type_ids: split_csv_env("RUNPOD_GPU_TYPE_IDS", "NVIDIA A40"),
-            timeout_ms: parse_u64_env("RUNPOD_HTTP_TIMEOUT_MS", 30_000)?,
-            ready_timeout_ms: parse_u64_env("RUNPOD_READY_TIMEOUT_MS", 300_000)?,
-            poll_interval_ms: parse_u64_env("RUNPOD_POLL_INTERVAL_MS", 5_000)?,
-            reconcile_mode,
-        })
+    /// Mode name (as parsed).
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Reuse => "reuse",
+            Self::Recreate => "recreate",
+            Self::Update => "update",
+            Self::Adopt => "adopt",
+        }
     }
 }
 
-/// Handle to a running pod with connection helpers.
-#[derive(Debug, Clone)]
-pub struct PodLease {
-    /// Pod ID.
-    pub id: String,
-    /// Pod name.
-    pub name: String,
-    /// Public IP address.
-    pub public_ip: String,
-    /// Port mappings (container port -> public port).
-    pub port_mappings: HashMap<u16, u16>,
-    /// Desired status.
-    pub desired_status: String,
+/// A pod property compared by the compatibility check of existing pods.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompatibilityCheck {
+    /// Container image.
+    Image,
+    /// GPU type (one of the requested ones).
+    GpuType,
+    /// Number of GPUs.
+    GpuCount,
+    /// Exposed ports (every requested port).
+    Ports,
+    /// Pod volume size and network volume.
+    Volume,
+    /// Hash of the requested pod environment variables.
+    Env,
 }
 
-impl PodLease {
-    /// Get the SSH endpoint (IP, port).
-    ///
-    /// Returns `None` if SSH port (22) is not mapped.
+impl CompatibilityCheck {
+    /// Every check (compared by `ReconcileMode::Update`).
+    pub const ALL: [Self; 6] = [
+        Self::Image,
+        Self::GpuType,
+        Self::GpuCount,
+        Self::Ports,
+        Self::Volume,
+        Self::Env,
+    ];
+
+    /// Parse "image", "`gpu_type`", "`gpu_count`", "ports", "volume" or "env"
+    /// (case-insensitive).
     #[must_use]
-    pub fn ssh_endpoint(&self) -> Option<(&str, u16)> {
-        self.port_mappings
-            .get(&22)
-            .map(|port| (self.public_ip.as_str(), *port))
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "image" => Some(Self::Image),
+            "gpu_type" => Some(Self::GpuType),
+            "gpu_count" => Some(Self::GpuCount),
+            "ports" => Some(Self::Ports),
+            "volume" => Some(Self::Volume),
+            "env" => Some(Self::Env),
+            _ => None,
+        }
     }
 
-    /// Get the HTTP endpoint URL for a given container port.
-    ///
-    /// Returns `None` if the port is not mapped.
+    /// Check name (as parsed).
     #[must_use]
-    pub fn http_endpoint(&self, container_port: u16) -> Option<String> {
-        self.port_mappings
-            .get(&container_port)
-            .map(|public_port| format!("http://{}:{}", self.public_ip, public_port))
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Image => "image",
+            Self::GpuType => "gpu_type",
+            Self::GpuCount => "gpu_count",
+            Self::Ports => "ports",
+            Self::Volume => "volume",
+            Self::Env => "env",
+        }
     }
 
-    /// Get the Jupyter endpoint URL (port 8888).
-    ///
-    /// Returns `None` if Jupyter port is not mapped.
+    /// Whether a mismatch can be fixed by updating the pod in place.
     #[must_use]
-    pub fn jupyter_endpoint(&self) -> Option<String> {
-        self.http_endpoint(8888)
+    pub const fn is_mutable(self) -> bool {
+        matches!(self, Self::Image | Self::Ports | Self::Env)
     }
+}
 
-    /// Get raw TCP endpoint for a given container port.
-    ///
-    /// Returns `None` if the port is not mapped.
+/// How `monitor()` reacts when a spot pod is preempted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PreemptionPolicy {
+    /// Start the same pod again (keeps its ID and disks); falls back to
+    /// `Recreate` if the pod is gone or cannot be started.
+    #[default]
+    Resume,
+    /// Terminate the pod and create a replacement under the same name.
+    Recreate,
+    /// Stop monitoring with `OrchestratorError::Preempted`.
+    Fail,
+}
+
+impl PreemptionPolicy {
+    /// Parse "resume", "recreate" or "fail" (case-insensitive).
     #[must_use]
-    pub fn tcp_endpoint(&self, container_port: u16) -> Option<(String, u16)> {
-        self.port_mappings
-            .get(&container_port)
-            .map(|public_port| (self.public_ip.clone(), *public_port))
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "resume" => Some(Self::Resume),
+            "recreate" => Some(Self::Recreate),
+            "fail" => Some(Self::Fail),
+            _ => None,
+        }
+    }
+
+    /// Policy name ("resume", "recreate" or "fail").
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Resume => "resume",
+            Self::Recreate => "recreate",
+            Self::Fail => "fail",
+        }
     }
 }
 
-/// `RunPod` orchestrator for high-level pod management.
-pub struct RunpodOrchestrator {
-    cfg: RunpodOrchestratorConfig,
-    http: reqwest::Client,
+/// Data source of readiness checks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReadinessSource {
+    /// REST `publicIp` / `portMappings`.
+    #[default]
+    Rest,
+    /// GraphQL `runtime.ports`, which is often populated sooner. Only ports
+    /// on the public IP (`isIpPublic`) count.
+    Graphql,
+    /// Both, merged: the pod is ready as soon as either reports it.
+    Both,
 }
 
-impl RunpodOrchestrator {
-    /// Create a new orchestrator from the given configuration.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the HTTP client cannot be built.
-    pub fn new(cfg: RunpodOrchestratorConfig) -> Result<Self, OrchestratorError> {
-        let http = reqwest::Client::builder()
-            .timeout(Duration::from_millis(cfg.timeout_ms))
-            .build()
-            .map_err(OrchestratorError::Http)?;
+impl ReadinessSource {
+    /// Parse "rest", "graphql" or "both" (case-insensitive).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "rest" => Some(Self::Rest),
+            "graphql" => Some(Self::Graphql),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
 
-        Ok(Self { cfg, http })
+    const fn uses_rest(self) -> bool {
+        matches!(self, Self::Rest | Self::Both)
     }
 
-    /// Get a reference to the current configuration.
+    const fn uses_graphql(self) -> bool {
+        matches!(self, Self::Graphql | Self::Both)
+    }
+}
+
+/// How a pod's ports are reached, and so what readiness waits for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExposureMode {
+    /// Public IP with every required port mapped.
+    #[default]
+    Public,
+    /// HTTP ports through the `RunPod` proxy: ready once each required port
+    /// answers on its proxy URL. TCP ports cannot be exposed this way.
+    Proxy,
+}
+
+impl ExposureMode {
+    /// Parse "public" or "proxy" (case-insensitive).
     #[must_use]
-    pub const fn config(&self) -> &RunpodOrchestratorConfig {
-        &self.cfg
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "public" => Some(Self::Public),
+            "proxy" => Some(Self::Proxy),
+            _ => None,
+        }
     }
+}
 
-    /// Ensure a ready pod is available.
-    ///
-    /// This method will:
-    /// 1. List existing pods and find one matching the configured name
-    /// 2. If found and compatible, start it if needed
-    /// 3. If not found or incompatible, create a new pod
-    /// 4. Wait for the pod to be ready (publicIp + required ports)
-    ///
-    /// Returns a `PodLease` with connection helpers.
+impl RunpodOrchestratorConfig {
+    /// Load configuration from environment variables.
     ///
     /// # Errors
     ///
-    /// Returns an error if pod creation, starting, or readiness checks fail.
-    pub async fn ensure_ready_pod(&self) -> Result<PodLease, OrchestratorError> {
-        // Step 1: Find existing pod by name
-        let existing = self.find_pod_by_name(&self.cfg.pod_name).await?;
+    /// Returns an error if required environment variables are missing or invalid.
+    pub fn from_env() -> Result<Self, OrchestratorError> {
+        runpod_env::load_dotenv().map_err(OrchestratorError::EnvFile)?;
 
-        let pod_id = match existing {
-            Some(pod) if self.is_compatible(&pod) && self.cfg.reconcile_mode == ReconcileMode::Reuse => {
-                // Pod exists and is compatible
-                if pod.desiredStatus.as_deref() == Some("EXITED") {
-                    // Start the stopped pod
-                    self.start_pod(&pod.id).await?;
-                }
-                pod.id
-            }
-            Some(pod) if self.cfg.reconcile_mode == ReconcileMode::Recreate => {
-                // Terminate and recreate
-                let _ = self.terminate_pod(&pod.id).await;
-                self.create_new_pod().await?.id
-            }
-            Some(_) | None => {
-                // Create new pod
-                self.create_new_pod().await?.id
-            }
+        let (reconcile_mode, adopt_selector) = parse_reconcile_env()?;
+
+        let readiness_source = parse_choice_env(
+            "RUNPOD_READINESS_SOURCE",
+            ReadinessSource::Rest,
+            ReadinessSource::parse,
+            "expected rest, graphql or both",
+        )?;
+        let exposure = parse_choice_env(
+            "RUNPOD_EXPOSURE",
+            ExposureMode::Public,
+            ExposureMode::parse,
+            "expected public or proxy",
+        )?;
+        let lease_release_policy = parse_choice_env(
+            "RUNPOD_LEASE_RELEASE_POLICY",
+            LeaseReleasePolicy::Stop,
+            LeaseReleasePolicy::parse,
+            "expected keep, stop or terminate",
+        )?;
+        let on_preemption = parse_choice_env(
+            "RUNPOD_ON_PREEMPTION",
+            PreemptionPolicy::Resume,
+            PreemptionPolicy::parse,
+            "expected resume, recreate or fail",
+        )?;
+
+        let required_ports = split_csv_env("RUNPOD_PORTS", "22/tcp,8888/http");
+        let ready_ports = parse_ready_ports_env("RUNPOD_READY_PORTS", &required_ports)?;
+        let ready_probes = parse_ready_probes_env("RUNPOD_READY_PROBES", &required_ports)?;
+
+        let simulate = parse_bool_env("RUNPOD_SIMULATE", false)?;
+        let api_key = if simulate {
+            ApiKey::new(env::var("RUNPOD_API_KEY").unwrap_or_default())
+        } else {
+            ApiKey::new(must_env("RUNPOD_API_KEY")?)
         };
 
-        // Step 2: Wait for readiness
-        self.wait_for_ready(&pod_id).await
+        let api_version = ApiVersion::from_env().map_err(|e| OrchestratorError::InvalidEnv {
+            key: e.key,
+            reason: e.reason,
+        })?;
+        Ok(Self {
+            api_key,
+            api_version,
+            rest_url: api_version.rest_url_from_env(),
+            graphql_url: env::var("RUNPOD_GRAPHQL_URL")
+                .unwrap_or_else(|_| "https://api.runpod.io/graphql".to_string()),
+            pod_name: env::var("RUNPOD_POD_NAME")
+                .unwrap_or_else(|_| "halldyll-pod".to_string()),
+            pod_name_pattern: parse_pod_name_pattern_env()?,
+            pod_labels: parse_pod_labels_env()?,
+            pod_selector: parse_selector_env("RUNPOD_POD_SELECTOR")?,
+            image_name: must_env("RUNPOD_IMAGE_NAME")?,
+            required_ports,
+            port_alternates: parse_port_alternates_env("RUNPOD_PORT_ALTERNATES")?,
+            ready_ports,
+            ready_probes,
+            gpu_type_ids: split_csv_env("RUNPOD_GPU_TYPE_IDS", "NVIDIA A40"),
+            timeout_ms: parse_u64_env("RUNPOD_HTTP_TIMEOUT_MS", 30_000)?,
+            ready_timeout_ms: parse_u64_env("RUNPOD_READY_TIMEOUT_MS", 300_000)?,
+            poll_interval_ms: parse_u64_env("RUNPOD_POLL_INTERVAL_MS", 5_000)?,
+            readiness_source,
+            exposure,
+            proxy_domain: env::var("RUNPOD_PROXY_DOMAIN")
+                .unwrap_or_else(|_| "proxy.runpod.net".to_string()),
+            poll_initial_ms: parse_u64_env("RUNPOD_POLL_INITIAL_MS", 1_000)?,
+            poll_backoff: parse_f64_env("RUNPOD_POLL_BACKOFF", 1.5)?,
+            reconcile_mode,
+            adopt_selector,
+            data_center_failover: split_csv_env("RUNPOD_DATA_CENTER_FAILOVER", ""),
+            data_center_ready_attempts: parse_u32_env("RUNPOD_DATA_CENTER_READY_ATTEMPTS", 1)?,
+            lock_ttl_ms: parse_u64_env("RUNPOD_LOCK_TTL_MS", 900_000)?,
+            lock_wait_ms: parse_u64_env("RUNPOD_LOCK_WAIT_MS", 900_000)?,
+            lock_dir: env::var("RUNPOD_LOCK_DIR")
+                .ok()
+                .filter(|d| !d.trim().is_empty()),
+            image_precheck: parse_bool_env("RUNPOD_IMAGE_PRECHECK", false)?,
+            registry_credentials: RegistryCredentials::from_env(),
+            simulate,
+            simulate_ready_ticks: parse_u32_env("RUNPOD_SIMULATE_READY_TICKS", 3)?,
+            volume_copy_image: env::var("RUNPOD_VOLUME_COPY_IMAGE")
+                .unwrap_or_else(|_| "alpine:3.20".to_string()),
+            volume_copy_timeout_ms: parse_u64_env("RUNPOD_VOLUME_COPY_TIMEOUT_MS", 3_600_000)?,
+            terminate_timeout_ms: parse_u64_env("RUNPOD_TERMINATE_TIMEOUT_MS", 120_000)?,
+            fleet_concurrency: parse_u32_env("RUNPOD_FLEET_CONCURRENCY", 4)?,
+            lease_release_policy,
+            compatibility_checks: parse_compatibility_checks_env()?,
+            on_preemption,
+            pre_stop_command: env::var("RUNPOD_PRE_STOP_COMMAND")
+                .ok()
+                .filter(|c| !c.trim().is_empty()),
+            pre_stop_timeout_ms: parse_u64_env("RUNPOD_PRE_STOP_TIMEOUT_MS", 60_000)?,
+            benchmark: HostBenchmark::from_env()?,
+            http: HttpOptions::from_env().map_err(|e| OrchestratorError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?,
+            retry: RetryPolicy::from_env().map_err(|e| OrchestratorError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?,
+        })
     }
 
-    /// List all pods for the current user.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the HTTP request fails or the API returns an error.
-    pub async fn list_pods(&self) -> Result<Vec<PodInfo>, OrchestratorError> {
-        let url = format!("{}/pods", self.cfg.rest_url.trim_end_matches('/'));
-
-        let resp = self
-            .http
-            .get(&url)
-            .bearer_auth(&self.cfg.api_key)
-            .send()
-            .await
-            .map_err(OrchestratorError::Http)?;
+    /// Configuration of a simulated orchestrator (pods ready right away),
+    /// with the `from_env()` defaults and without reading the environment.
+    #[cfg(test)]
+    pub(crate) fn simulated(reconcile_mode: ReconcileMode) -> Self {
+        let api_version = ApiVersion::default();
+        Self {
+            api_key: ApiKey::new(String::new()),
+            api_version,
+            rest_url: api_version.default_rest_url().to_string(),
+            graphql_url: "https://api.runpod.io/graphql".to_string(),
+            pod_name: "halldyll-pod".to_string(),
+            pod_name_pattern: "{name}-{n}".to_string(),
+            pod_labels: BTreeMap::new(),
+            pod_selector: None,
+            image_name: "halldyll/test:1".to_string(),
+            required_ports: vec!["22/tcp".to_string(), "8888/http".to_string()],
+            port_alternates: HashMap::new(),
+            ready_ports: vec![22, 8888],
+            ready_probes: HashMap::new(),
+            gpu_type_ids: vec!["NVIDIA A40".to_string()],
+            timeout_ms: 30_000,
+            ready_timeout_ms: 10_000,
+            poll_interval_ms: 10,
+            readiness_source: ReadinessSource::Rest,
+            exposure: ExposureMode::Public,
+            proxy_domain: "proxy.runpod.net".to_string(),
+            poll_initial_ms: 1,
+            poll_backoff: 1.0,
+            reconcile_mode,
+            adopt_selector: None,
+            data_center_failover: Vec::new(),
+            data_center_ready_attempts: 1,
+            lock_ttl_ms: 900_000,
+            lock_wait_ms: 900_000,
+            lock_dir: None,
+            image_precheck: false,
+            registry_credentials: None,
+            simulate: true,
+            simulate_ready_ticks: 0,
+            volume_copy_image: "alpine:3.20".to_string(),
+            volume_copy_timeout_ms: 3_600_000,
+            terminate_timeout_ms: 120_000,
+            fleet_concurrency: 4,
+            lease_release_policy: LeaseReleasePolicy::Stop,
+            compatibility_checks: vec![CompatibilityCheck::Image],
+            on_preemption: PreemptionPolicy::Resume,
+            pre_stop_command: None,
+            pre_stop_timeout_ms: 60_000,
+            benchmark: None,
+            http: HttpOptions::default(),
+            retry: RetryPolicy::none(),
+        }
+    }
+}
 
-        let status = resp.status();
-        let body = resp.text().await.unwrap_or_default();
+/// Options for `update_image_with()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageUpdateOptions {
+    /// Leave the old pod untouched when the replacement fails to create or
+    /// become ready (default: true). Otherwise the old pod is terminated
+    /// anyway, e.g. to free its GPU.
+    pub keep_old_on_failure: bool,
+    /// Stop the old pod instead of terminating it, once the replacement is
+    /// ready, to roll back by starting it again (default: false). The
+    /// stopped pod is renamed `<name>-previous` so that it is not found by
+    /// name anymore.
+    pub stop_old: bool,
+}
 
-        if !status.is_success() {
-            return Err(OrchestratorError::Api { status, body });
+impl Default for ImageUpdateOptions {
+    fn default() -> Self {
+        Self {
+            keep_old_on_failure: true,
+            stop_old: false,
         }
+    }
+}
 
-        let pods: Vec<PodInfo> = serde_json::from_str(&body)
-            .map_err(|e| OrchestratorError::Json(e.to_string()))?;
+/// Per-call overrides for `ensure_ready_pod_with()`.
+///
+/// Every `None` field falls back to the orchestrator configuration
+/// (or, for `gpu_count`, to the provisioning configuration).
+#[derive(Clone, Debug, Default)]
+pub struct PodOverrides {
+    /// Pod name to find or create.
+    pub name: Option<String>,
+    /// Container image name.
+    pub image_name: Option<String>,
+    /// GPU type IDs.
+    pub gpu_type_ids: Option<Vec<String>>,
+    /// Number of GPUs.
+    pub gpu_count: Option<u32>,
+    /// Required/exposed ports.
+    pub ports: Option<Vec<String>>,
+    /// Labels given to a created pod.
+    pub labels: Option<BTreeMap<String, String>>,
+    /// Label selector to find the pod by (instead of its name).
+    pub selector: Option<LabelSelector>,
+}
 
-        Ok(pods)
+/// Effective pod spec for one orchestration call.
+#[derive(Clone, Debug)]
+struct PodSpec {
+    name: String,
+    image_name: String,
+    gpu_type_ids: Vec<String>,
+    gpu_count: Option<u32>,
+    ports: Vec<String>,
+    network_volume_id: Option<String>,
+    volume_mount_path: Option<String>,
+    docker_start_cmd: Option<Vec<String>>,
+    data_center_id: Option<String>,
+    /// Labels of a created pod.
+    labels: BTreeMap<String, String>,
+    /// Selector the existing pod is found by (instead of `name`).
+    selector: Option<LabelSelector>,
+    /// Pod settings restored from a `PodSnapshot` (none for regular calls).
+    restore: Option<RestoreSettings>,
+    /// Temporary CPU pod on the global network (volume copies).
+    utility: bool,
+}
+
+/// What `ensure_ready_spec()` does with the pod named after a spec.
+struct Decision {
+    /// Pod with the spec's name, if any.
+    existing: Option<PodInfo>,
+    /// How the lease will be obtained.
+    action: LeaseAction,
+    /// Whether the existing pod is used.
+    accepted: bool,
+    /// Why the existing pod is used or rejected.
+    reason: String,
+    /// Comparison of the existing pod with the spec (not in recreate mode).
+    compatibility: Option<CompatibilityReport>,
+    /// Changes to apply in place (`LeaseAction::Updated` only).
+    update: Option<PodUpdate>,
+    /// Whether `existing` is a pod adopted under another name.
+    adopted: bool,
+    /// Why the state policy forbids leasing a pod (`existing` is terminated).
+    blocked: Option<PlanReason>,
+    /// Pod lock of the adopted pod, held until the adoption is recorded.
+    adoption_lock: Option<HeldPodLock>,
+}
+
+impl Decision {
+    const fn new(
+        existing: Option<PodInfo>,
+        action: LeaseAction,
+        accepted: bool,
+        reason: String,
+    ) -> Self {
+        Self {
+            existing,
+            action,
+            accepted,
+            reason,
+            compatibility: None,
+            update: None,
+            adopted: false,
+            blocked: None,
+            adoption_lock: None,
+        }
     }
+}
 
-    /// Stop a running pod (puts it in EXITED state, can be restarted later).
-    ///
-    /// Use this to pause billing while keeping the pod configuration.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the HTTP request fails or the API returns an error.
-    pub async fn stop_pod(&self, pod_id: &str) -> Result<(), OrchestratorError> {
-        let url = format!(
-            "{}/pods/{}/stop",
-            self.cfg.rest_url.trim_end_matches('/'),
-            pod_id
-        );
+/// Pod lock taken by `find_adoptable_pod()` for its candidate.
+struct HeldPodLock {
+    lock: Arc<dyn DistributedLock>,
+    key: String,
+    owner: String,
+}
 
-        let resp = self
-            .http
-            .post(&url)
-            .bearer_auth(&self.cfg.api_key)
-            .send()
-            .await
-            .map_err(OrchestratorError::Http)?;
+impl HeldPodLock {
+    /// Release the lock; a failure is logged and the lock left to expire.
+    async fn release(self) {
+        let Self { lock, key, owner } = self;
+        let released = runpod_lock::run_blocking(&lock, move |lock| lock.release(&key, &owner));
+        if let Err(e) = released.await {
+            log_warn!("could not release adoption lock: {e}", e = ErrorChain(&e));
+        }
+    }
+}
 
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            return Err(OrchestratorError::Api { status, body });
+/// Settings of a `PodSnapshot` that regular pod specs take from the environment.
+#[derive(Clone, Debug)]
+struct RestoreSettings {
+    interruptible: Option<bool>,
+    container_disk_gb: Option<u32>,
+    volume_gb: Option<u32>,
+    env_keys: Vec<String>,
+}
+
+impl PodSpec {
+    fn resolve(cfg: &RunpodOrchestratorConfig, overrides: &PodOverrides) -> Self {
+        let selector = overrides.selector.clone().or_else(|| {
+            overrides
+                .name
+                .is_none()
+                .then(|| cfg.pod_selector.clone())
+                .flatten()
+        });
+        let mut labels = overrides
+            .labels
+            .clone()
+            .unwrap_or_else(|| cfg.pod_labels.clone());
+        if let Some(selector) = &selector {
+            labels.extend(selector.required_labels());
+        }
+        Self {
+            name: overrides
+                .name
+                .clone()
+                .unwrap_or_else(|| cfg.pod_name.clone()),
+            image_name: overrides
+                .image_name
+                .clone()
+                .unwrap_or_else(|| cfg.image_name.clone()),
+            gpu_type_ids: overrides
+                .gpu_type_ids
+                .clone()
+                .unwrap_or_else(|| cfg.gpu_type_ids.clone()),
+            gpu_count: overrides.gpu_count,
+            ports: overrides
+                .ports
+                .clone()
+                .unwrap_or_else(|| cfg.required_ports.clone()),
+            network_volume_id: None,
+            volume_mount_path: None,
+            docker_start_cmd: None,
+            data_center_id: None,
+            labels,
+            selector,
+            restore: None,
+            utility: false,
         }
+    }
 
-        Ok(())
+    fn from_snapshot(snapshot: &PodSnapshot) -> Self {
+        Self {
+            name: snapshot.name.clone(),
+            image_name: snapshot.image_name.clone(),
+            gpu_type_ids: snapshot.gpu_type_id.iter().cloned().collect(),
+            gpu_count: snapshot.gpu_count,
+            ports: snapshot.ports.clone(),
+            network_volume_id: snapshot.network_volume_id.clone(),
+            volume_mount_path: snapshot.volume_mount_path.clone(),
+            docker_start_cmd: Some(snapshot.docker_start_cmd.clone()),
+            data_center_id: snapshot.data_center_id.clone(),
+            labels: BTreeMap::new(),
+            selector: None,
+            restore: Some(RestoreSettings {
+                interruptible: snapshot.interruptible,
+                container_disk_gb: snapshot.container_disk_gb,
+                volume_gb: snapshot.volume_gb,
+                env_keys: snapshot.env_keys.clone(),
+            }),
+            utility: false,
+        }
+    }
+}
+
+/// A required port spec that cannot be exposed as requested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortConflict {
+    /// The offending spec (e.g. "8888/tcp").
+    pub spec: String,
+    /// The spec it collides with, if any.
+    pub conflicts_with: Option<String>,
+    /// Why it cannot be exposed.
+    pub reason: String,
+}
+
+impl fmt::Display for PortConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.conflicts_with {
+            Some(other) => write!(f, "{} conflicts with {other}: {}", self.spec, self.reason),
+            None => write!(f, "{}: {}", self.spec, self.reason),
+        }
+    }
+}
+
+/// Port specs to expose after negotiation.
+struct NegotiatedPorts {
+    ports: Vec<String>,
+    /// Requested spec ("8888/tcp") -> exposed container port.
+    remaps: HashMap<String, u16>,
+}
+
+/// Validate port specs ("22/tcp", "8888/http") and resolve conflicts.
+///
+/// Exact duplicates are dropped. A container port requested again with
+/// another protocol is moved to its first free alternate from `alternates`,
+/// or reported as a conflict. In proxy exposure, TCP specs are conflicts.
+fn negotiate_ports(
+    specs: &[String],
+    alternates: &HashMap<u16, Vec<u16>>,
+    exposure: ExposureMode,
+) -> Result<NegotiatedPorts, Vec<PortConflict>> {
+    let mut parsed = Vec::new();
+    let mut conflicts = Vec::new();
+    for spec in specs {
+        match parse_port_spec(spec) {
+            Some((_, "tcp")) if exposure == ExposureMode::Proxy => conflicts.push(PortConflict {
+                spec: spec.clone(),
+                conflicts_with: None,
+                reason: "tcp ports need a public IP (proxy exposure serves http ports only)"
+                    .to_string(),
+            }),
+            Some(port) => parsed.push(port),
+            None => conflicts.push(PortConflict {
+                spec: spec.clone(),
+                conflicts_with: None,
+                reason: "expected <port>/<tcp|http> with a non-zero port".to_string(),
+            }),
+        }
+    }
+
+    let requested: HashSet<u16> = parsed.iter().map(|(port, _)| *port).collect();
+    let mut exposed: Vec<(u16, &str)> = Vec::new();
+    let mut remaps = HashMap::new();
+    for (port, protocol) in parsed {
+        let Some(&(_, taken_by)) = exposed.iter().find(|(p, _)| *p == port) else {
+            exposed.push((port, protocol));
+            continue;
+        };
+        if taken_by == protocol {
+            continue;
+        }
+
+        let free = alternates.get(&port).and_then(|alts| {
+            alts.iter()
+                .copied()
+                .find(|alt| !requested.contains(alt) && !exposed.iter().any(|(p, _)| p == alt))
+        });
+        if let Some(alt) = free {
+            exposed.push((alt, protocol));
+            remaps.insert(format!("{port}/{protocol}"), alt);
+        } else {
+            conflicts.push(PortConflict {
+                spec: format!("{port}/{protocol}"),
+                conflicts_with: Some(format!("{port}/{taken_by}")),
+                reason: "container port already exposed with another protocol (no free alternate)"
+                    .to_string(),
+            });
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(conflicts);
+    }
+    let ports = exposed
+        .into_iter()
+        .map(|(port, protocol)| format!("{port}/{protocol}"))
+        .collect();
+    Ok(NegotiatedPorts { ports, remaps })
+}
+
+/// Parse "8888/http" into (8888, "http"); `None` if malformed.
+fn parse_port_spec(spec: &str) -> Option<(u16, &'static str)> {
+    let (port, protocol) = spec.trim().split_once('/')?;
+    let port = port.trim().parse::<u16>().ok().filter(|p| *p != 0)?;
+    let protocol = match protocol.trim().to_lowercase().as_str() {
+        "tcp" => "tcp",
+        "http" => "http",
+        _ => return None,
+    };
+    Some((port, protocol))
+}
+
+/// Pod about to be created, with its estimated cost.
+///
+/// Passed to the confirmation hook installed with `with_confirmation()`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostEstimate {
+    /// Pod name.
+    pub pod_name: String,
+    /// Container image name.
+    pub image_name: String,
+    /// Cloud type ("SECURE" | "COMMUNITY").
+    pub cloud_type: String,
+    /// Acceptable GPU type IDs.
+    pub gpu_type_ids: Vec<String>,
+    /// Number of GPUs.
+    pub gpu_count: u32,
+    /// Estimated cost per hour in USD, using the most expensive acceptable
+    /// GPU type. `None` if no price is known.
+    pub hourly_usd: Option<f64>,
+}
+
+/// Async confirmation hook: resolves to `true` to allow the creation.
+pub type ConfirmHook =
+    Arc<dyn Fn(CostEstimate) -> Pin<Box<dyn Future<Output = bool> + Send>> + Send + Sync>;
+
+/// Phase of `ensure_ready_pod()`, reported to a `ProgressReporter`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressPhase {
+    /// Looking for an existing pod.
+    Discovering,
+    /// Creating a new pod.
+    Creating,
+    /// Starting or updating an existing pod.
+    Starting,
+    /// Waiting for the pod to run and get a public IP.
+    WaitingForIp,
+    /// Waiting for the required ports to be mapped.
+    WaitingForPorts,
+    /// The pod is ready.
+    Ready,
+}
+
+impl fmt::Display for ProgressPhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Discovering => "discovering",
+            Self::Creating => "creating",
+            Self::Starting => "starting",
+            Self::WaitingForIp => "waiting for ip",
+            Self::WaitingForPorts => "waiting for ports",
+            Self::Ready => "ready",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Lifecycle status of a pod, as reported by `watch()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PodStatus {
+    /// The pod exists but was never started.
+    Created,
+    /// The pod is starting: no public IP yet, or required ports still
+    /// unmapped.
+    Starting,
+    /// The pod runs with a public IP and every required port mapped, as
+    /// readiness waits for (any running pod in proxy exposure mode).
+    Running,
+    /// The pod is stopped.
+    Exited,
+    /// The pod is terminated or gone.
+    Terminated,
+}
+
+impl PodStatus {
+    /// Get the status as a `snake_case` string.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Starting => "starting",
+            Self::Running => "running",
+            Self::Exited => "exited",
+            Self::Terminated => "terminated",
+        }
+    }
+
+    fn of(pod: Option<&ReadinessView>, exposure: ExposureMode, required_ports: &[String]) -> Self {
+        let Some(pod) = pod else {
+            return Self::Terminated;
+        };
+        match pod.desired_status.as_deref() {
+            Some("RUNNING")
+                if exposure == ExposureMode::Proxy || pod.is_reachable(required_ports) =>
+            {
+                Self::Running
+            }
+            Some("RUNNING") => Self::Starting,
+            Some("EXITED") => Self::Exited,
+            Some("TERMINATED") => Self::Terminated,
+            _ => Self::Created,
+        }
+    }
+}
+
+impl fmt::Display for PodStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A change of pod status seen by `watch()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PodTransition {
+    /// Pod ID.
+    pub pod_id: String,
+    /// Previous status (`None` for the first observation).
+    pub from: Option<PodStatus>,
+    /// New status.
+    pub to: PodStatus,
+    /// When the change was seen (ms since UNIX epoch).
+    pub at_ms: u64,
+}
+
+/// Progress update for UIs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Progress {
+    /// Pod name being orchestrated.
+    pub pod_name: String,
+    /// Current phase.
+    pub phase: ProgressPhase,
+    /// Rough completion percentage (0-100).
+    pub percent: u8,
+    /// Time since `ensure_ready_pod()` was called.
+    pub elapsed: Duration,
+    /// Upper bound on the remaining wait (readiness timeout minus time
+    /// already waited); `None` outside the waiting phases.
+    pub remaining: Option<Duration>,
+}
+
+/// Receiver for progress updates.
+///
+/// Called on every phase transition and on every readiness poll.
+/// Implemented for any `Fn(&Progress) + Send + Sync` closure, and for
+/// `mpsc::Sender<Progress>` to consume updates from another thread.
+pub trait ProgressReporter: Send + Sync {
+    /// Handle one progress update.
+    fn report(&self, progress: &Progress);
+}
+
+impl<F: Fn(&Progress) + Send + Sync> ProgressReporter for F {
+    fn report(&self, progress: &Progress) {
+        self(progress);
+    }
+}
+
+/// Updates are dropped once the receiver is gone.
+impl ProgressReporter for mpsc::Sender<Progress> {
+    fn report(&self, progress: &Progress) {
+        let _ = self.send(progress.clone());
+    }
+}
+
+/// How a lease's pod was obtained.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaseAction {
+    /// An existing running pod was reused.
+    #[default]
+    Reused,
+    /// An existing stopped pod was started.
+    Started,
+    /// An existing pod was updated in place.
+    Updated,
+    /// A new pod was created.
+    Created,
+    /// An existing pod was replaced by a new one.
+    Replaced,
+    /// A new pod was created from a `PodSnapshot`.
+    Restored,
+}
+
+impl LeaseAction {
+    /// Get the action as a `snake_case` string.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Reused => "reused",
+            Self::Started => "started",
+            Self::Updated => "updated",
+            Self::Created => "created",
+            Self::Replaced => "replaced",
+            Self::Restored => "restored",
+        }
+    }
+}
+
+/// Where a lease's pod came from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LeaseProvenance {
+    /// How the pod was obtained.
+    pub action: LeaseAction,
+    /// Cloud type the pod was created in (new pods only).
+    pub cloud_type: Option<String>,
+    /// Cloud types tried first that had no capacity.
+    pub skipped_cloud_types: Vec<String>,
+    /// Data center the pod was created in (new pods only, when `RunPod`
+    /// reports it or with `data_center_failover`).
+    pub data_center_id: Option<String>,
+    /// Data centers given up on before this one, in order.
+    pub failovers: Vec<DataCenterFailover>,
+}
+
+/// Why a data center was given up on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailoverReason {
+    /// The data center had no capacity for the pod.
+    NoCapacity,
+    /// The pod was created but never became ready (it was terminated).
+    NotReady,
+    /// The pod's host failed the host benchmark (it was terminated).
+    BelowBenchmark,
+}
+
+impl FailoverReason {
+    /// Get the reason as a `snake_case` string.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::NoCapacity => "no_capacity",
+            Self::NotReady => "not_ready",
+            Self::BelowBenchmark => "below_benchmark",
+        }
+    }
+}
+
+/// Built-in host benchmark: `PyTorch` fp16 matmul throughput (`tflops`) and
+/// pinned host-to-device copy bandwidth (`h2d_gbps`).
+pub const DEFAULT_BENCHMARK_COMMAND: &str = r#"python3 - <<'PY'
+import time, torch
+d = torch.device("cuda")
+a = torch.randn(4096, 4096, device=d, dtype=torch.float16)
+b = torch.randn_like(a)
+for _ in range(3): a @ b
+torch.cuda.synchronize(); t = time.time()
+for _ in range(20): a @ b
+torch.cuda.synchronize()
+print("tflops=%.2f" % (20 * 2 * 4096 ** 3 / (time.time() - t) / 1e12))
+h = torch.empty(256 * 2 ** 20, dtype=torch.uint8).pin_memory()
+h.to(d); torch.cuda.synchronize(); t = time.time()
+for _ in range(5): h.to(d, non_blocking=True)
+torch.cuda.synchronize()
+print("h2d_gbps=%.2f" % (5 * h.numel() / (time.time() - t) / 1e9))
+PY"#;
+
+/// Host validation run on newly created pods before they are handed out.
+///
+/// Identical GPU types perform very differently across community-cloud
+/// hosts; a pod whose host misses a threshold is terminated and recreated
+/// (in the next data center with `data_center_failover`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostBenchmark {
+    /// Command run on the pod over SSH (with the `RUNPOD_SSH_*` login); it
+    /// must print one `metric=value` line per metric.
+    /// Env: `RUNPOD_BENCHMARK_COMMAND` (default: `DEFAULT_BENCHMARK_COMMAND`)
+    pub command: String,
+
+    /// Minimum value per metric; a missing metric fails the host.
+    /// Env: `RUNPOD_BENCHMARK_MIN` (e.g. `tflops=50,h2d_gbps=10`)
+    pub thresholds: BTreeMap<String, f64>,
+
+    /// Maximum duration of the benchmark command in milliseconds.
+    /// Env: `RUNPOD_BENCHMARK_TIMEOUT_MS` (default: 180000 = 3 minutes)
+    pub timeout_ms: u64,
+
+    /// Pods created before giving up when hosts keep failing (without
+    /// `data_center_failover`).
+    /// Env: `RUNPOD_BENCHMARK_MAX_ATTEMPTS` (default: 3)
+    pub max_attempts: u32,
+}
+
+impl HostBenchmark {
+    /// Load the benchmark from environment variables; `None` unless
+    /// `RUNPOD_BENCHMARK_MIN` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is invalid.
+    pub fn from_env() -> Result<Option<Self>, OrchestratorError> {
+        let thresholds = split_csv_env("RUNPOD_BENCHMARK_MIN", "")
+            .iter()
+            .map(|entry| {
+                let (metric, min) = entry.split_once('=')?;
+                let min = min.trim().parse::<f64>().ok().filter(|m| m.is_finite())?;
+                Some((metric.trim().to_string(), min))
+            })
+            .collect::<Option<BTreeMap<_, _>>>()
+            .ok_or(OrchestratorError::InvalidEnv {
+                key: "RUNPOD_BENCHMARK_MIN",
+                reason: "expected comma-separated metric=minimum pairs",
+            })?;
+        if thresholds.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            command: env::var("RUNPOD_BENCHMARK_COMMAND")
+                .ok()
+                .filter(|c| !c.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_BENCHMARK_COMMAND.to_string()),
+            thresholds,
+            timeout_ms: parse_u64_env("RUNPOD_BENCHMARK_TIMEOUT_MS", 180_000)?,
+            max_attempts: parse_u32_env("RUNPOD_BENCHMARK_MAX_ATTEMPTS", 3)?,
+        }))
+    }
+
+    /// Score the output of the benchmark command against the thresholds.
+    #[must_use]
+    pub fn evaluate(&self, stdout: &str) -> BenchmarkResult {
+        let metrics: BTreeMap<String, f64> = stdout
+            .lines()
+            .filter_map(|line| {
+                let (metric, value) = line.trim().split_once('=')?;
+                Some((metric.trim().to_string(), value.trim().parse::<f64>().ok()?))
+            })
+            .collect();
+        let failures = self
+            .thresholds
+            .iter()
+            .filter_map(|(metric, min)| match metrics.get(metric) {
+                Some(value) if value >= min => None,
+                Some(value) => Some(format!("{metric} {value} < {min}")),
+                None => Some(format!("{metric} not reported")),
+            })
+            .collect();
+        BenchmarkResult { metrics, failures }
+    }
+}
+
+/// Outcome of the host benchmark of a pod.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BenchmarkResult {
+    /// Metrics printed by the benchmark command.
+    pub metrics: BTreeMap<String, f64>,
+    /// Why the host failed ("tflops 31.2 < 50"); empty if it passed.
+    pub failures: Vec<String>,
+}
+
+impl BenchmarkResult {
+    /// Check whether the host met every threshold.
+    #[must_use]
+    pub const fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// One data center given up on during creation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DataCenterFailover {
+    /// Data center ID.
+    pub data_center_id: String,
+    /// Why it was given up on.
+    pub reason: FailoverReason,
+    /// Pod created there and terminated, if any.
+    pub pod_id: Option<String>,
+    /// Error reported by the API or the readiness check.
+    pub detail: String,
+}
+
+impl fmt::Display for DataCenterFailover {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.data_center_id, self.reason.as_str())
+    }
+}
+
+impl LeaseProvenance {
+    /// Check whether creation fell back to another cloud type.
+    #[must_use]
+    pub const fn fallback_used(&self) -> bool {
+        !self.skipped_cloud_types.is_empty()
+    }
+
+    /// Check whether creation failed over to another data center.
+    #[must_use]
+    pub const fn failover_used(&self) -> bool {
+        !self.failovers.is_empty()
+    }
+
+    fn created(action: LeaseAction, created: CreatedPod) -> Self {
+        Self {
+            action,
+            cloud_type: created.cloud_type,
+            skipped_cloud_types: created.skipped_cloud_types,
+            data_center_id: created.data_center_id,
+            failovers: Vec::new(),
+        }
+    }
+
+    const fn existing(action: LeaseAction) -> Self {
+        Self {
+            action,
+            cloud_type: None,
+            skipped_cloud_types: Vec::new(),
+            data_center_id: None,
+            failovers: Vec::new(),
+        }
+    }
+}
+
+/// What one `ensure_ready_pod_with_report()` call did.
+///
+/// Serializes to JSON, so automation can log every run as one record.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OrchestrationReport {
+    /// Pod name the call was for.
+    pub pod_name: String,
+    /// How the leased pod was obtained (`None` if the call failed).
+    pub action: Option<LeaseAction>,
+    /// ID of the leased pod (`None` if the call failed).
+    pub pod_id: Option<String>,
+    /// Data center the leased pod runs in (when known).
+    pub data_center_id: Option<String>,
+    /// Existing pods examined, and whether they were used.
+    pub considered: Vec<ConsideredPod>,
+    /// Actions taken against pods, in order.
+    pub steps: Vec<ReportStep>,
+    /// HTTP requests retried during the call.
+    pub retries: u32,
+    /// Cloud types skipped for lack of capacity.
+    pub skipped_cloud_types: Vec<String>,
+    /// Data centers given up on (with `data_center_failover`).
+    pub failovers: Vec<DataCenterFailover>,
+    /// Time spent waiting for readiness, in milliseconds.
+    pub ready_wait_ms: u64,
+    /// Total duration of the call, in milliseconds.
+    pub duration_ms: u64,
+    /// The error the call failed with, if any.
+    pub error: Option<String>,
+}
+
+/// An existing pod examined by the orchestrator.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConsideredPod {
+    /// Pod ID.
+    pub pod_id: String,
+    /// Desired status when examined.
+    pub status: Option<String>,
+    /// Whether the pod was used for the lease.
+    pub accepted: bool,
+    /// Why it was used or rejected.
+    pub reason: String,
+}
+
+/// One property of an existing pod that differs from the requested spec.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompatibilityMismatch {
+    /// Property compared.
+    pub check: CompatibilityCheck,
+    /// Requested value.
+    pub expected: String,
+    /// Value of the pod ("none" if unknown).
+    pub actual: String,
+}
+
+/// Why an existing pod can or cannot be reused (see `compatibility_checks`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct CompatibilityReport {
+    /// Pod ID.
+    pub pod_id: String,
+    /// Properties that differ, in `compatibility_checks` order.
+    pub mismatches: Vec<CompatibilityMismatch>,
+    /// Size (GB) the pod volume can be grown to in place, when the volume
+    /// differs only by being smaller than requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume_growth_gb: Option<u32>,
+}
+
+impl CompatibilityReport {
+    /// Whether the pod matches every checked property.
+    #[must_use]
+    pub const fn is_compatible(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// Whether the pod differs, but only in properties updatable in place.
+    #[must_use]
+    pub fn is_updatable(&self) -> bool {
+        !self.mismatches.is_empty()
+            && self.mismatches.iter().all(|m| {
+                m.check.is_mutable()
+                    || (m.check == CompatibilityCheck::Volume && self.volume_growth_gb.is_some())
+            })
+    }
+
+    /// Whether `check` differs.
+    #[must_use]
+    pub fn differs(&self, check: CompatibilityCheck) -> bool {
+        self.mismatches.iter().any(|m| m.check == check)
+    }
+
+    fn push(&mut self, check: CompatibilityCheck, expected: String, actual: Option<String>) {
+        self.mismatches.push(CompatibilityMismatch {
+            check,
+            expected,
+            actual: actual.unwrap_or_else(|| "none".to_string()),
+        });
+    }
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.mismatches.is_empty() {
+            return f.write_str("compatible");
+        }
+        for (i, m) in self.mismatches.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(
+                f,
+                "{}: expected {}, got {}",
+                m.check.as_str(),
+                m.expected,
+                m.actual
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// What `plan()` expects `ensure_ready_pod()` to do, without doing it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OrchestrationPlan {
+    /// Pod name the plan is for.
+    pub pod_name: String,
+    /// How the lease would be obtained.
+    pub action: LeaseAction,
+    /// Existing pod examined, and whether it would be used.
+    pub considered: Option<ConsideredPod>,
+    /// Comparison of the existing pod with the spec (not in recreate mode).
+    pub compatibility: Option<CompatibilityReport>,
+    /// Changes that would be applied in place (`LeaseAction::Updated` only).
+    pub update: Option<PodUpdate>,
+    /// Actions that would be taken, in order.
+    pub steps: Vec<PlannedStep>,
+}
+
+/// One action of an `OrchestrationPlan`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedStep {
+    /// What would be done.
+    pub kind: StepKind,
+    /// The pod it would be done to (`None` for a pod still to create).
+    pub pod_id: Option<String>,
+}
+
+/// Kind of action taken against a pod.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepKind {
+    /// A stopped pod was started.
+    Start,
+    /// A pod was updated in place.
+    Update,
+    /// A new pod was created.
+    Create,
+    /// A pod was terminated.
+    Terminate,
+    /// The orchestrator waited for a pod to become ready.
+    WaitReady,
+}
+
+/// One action taken during an orchestration call.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReportStep {
+    /// What was done.
+    pub kind: StepKind,
+    /// The pod it was done to.
+    pub pod_id: String,
+    /// Milliseconds since the call started.
+    pub at_ms: u64,
+}
+
+/// Report being filled in by the current task (see `record()`).
+struct ReportRecorder {
+    started: Instant,
+    report: OrchestrationReport,
+}
+
+tokio::task_local! {
+    static REPORT: RefCell<Option<ReportRecorder>>;
+}
+
+/// Cancellation of the current call (see `ensure_ready_pod_cancellable()`).
+struct CancelScope {
+    token: CancellationToken,
+    /// Pods created (and not terminated) by the call so far.
+    created: Vec<String>,
+}
+
+tokio::task_local! {
+    static CANCEL: RefCell<CancelScope>;
+}
+
+/// Token of the current call, if it is cancellable.
+fn cancel_token() -> Option<CancellationToken> {
+    CANCEL.try_with(|scope| scope.borrow().token.clone()).ok()
+}
+
+/// Run `future`, unless the current call is cancelled first.
+async fn cancellable<T>(
+    pod_id: Option<&str>,
+    future: impl Future<Output = Result<T, OrchestratorError>>,
+) -> Result<T, OrchestratorError> {
+    let Some(token) = cancel_token() else {
+        return future.await;
+    };
+    tokio::select! {
+        result = future => result,
+        () = token.cancelled() => Err(OrchestratorError::Cancelled {
+            pod_id: pod_id.map(str::to_string),
+        }),
+    }
+}
+
+/// Update the report of the current call, if it is being recorded.
+fn record(update: impl FnOnce(&mut OrchestrationReport)) {
+    let _ = REPORT.try_with(|cell| {
+        if let Some(recorder) = cell.borrow_mut().as_mut() {
+            update(&mut recorder.report);
+        }
+    });
+}
+
+/// Record an action taken against `pod_id`.
+fn record_step(kind: StepKind, pod_id: &str) {
+    let _ = CANCEL.try_with(|scope| {
+        let created = &mut scope.borrow_mut().created;
+        match kind {
+            StepKind::Create => created.push(pod_id.to_string()),
+            StepKind::Terminate => created.retain(|id| id != pod_id),
+            _ => {}
+        }
+    });
+    let _ = REPORT.try_with(|cell| {
+        if let Some(recorder) = cell.borrow_mut().as_mut() {
+            let at_ms = millis_since(recorder.started);
+            recorder.report.steps.push(ReportStep {
+                kind,
+                pod_id: pod_id.to_string(),
+                at_ms,
+            });
+        }
+    });
+}
+
+impl ReportRecorder {
+    /// Complete the report with the outcome of the call.
+    fn finish(self, result: &Result<PodLease, OrchestratorError>) -> OrchestrationReport {
+        let mut report = self.report;
+        report.duration_ms = millis_since(self.started);
+        match result {
+            Ok(lease) => {
+                report.action = Some(lease.provenance.action);
+                report.pod_id = Some(lease.id.clone());
+                report.data_center_id.clone_from(&lease.data_center_id);
+                report
+                    .skipped_cloud_types
+                    .clone_from(&lease.provenance.skipped_cloud_types);
+                report.failovers.clone_from(&lease.provenance.failovers);
+            }
+            Err(e) => {
+                if let OrchestratorError::FailoverExhausted(failovers) = e {
+                    report.failovers.clone_from(failovers);
+                }
+                report.error = Some(e.to_string());
+            }
+        }
+        report
+    }
+}
+
+fn record_considered(pod: &PodInfo, accepted: bool, reason: &str) {
+    record(|report| {
+        report.considered.push(ConsideredPod {
+            pod_id: pod.id.clone(),
+            status: pod.desiredStatus.clone(),
+            accepted,
+            reason: reason.to_string(),
+        });
+    });
+}
+
+fn millis_since(start: Instant) -> u64 {
+    u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Result of one action of `apply_fleet()`.
+#[derive(Debug)]
+pub struct FleetActionResult {
+    /// Group name.
+    pub group: String,
+    /// Logical pod name.
+    pub pod_name: String,
+    /// The action executed.
+    pub action: PlannedAction,
+    /// Its outcome, or the error for this pod.
+    pub outcome: Result<ActionOutcome, OrchestratorError>,
+}
+
+/// Aggregate result of `apply_fleet()`.
+#[derive(Debug)]
+pub struct FleetApplyResult {
+    /// One result per executed (non-`Noop`) plan, in plan order.
+    pub results: Vec<FleetActionResult>,
+    /// Total duration in milliseconds.
+    pub duration_ms: u64,
+}
+
+impl FleetApplyResult {
+    /// Check whether every action succeeded.
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|r| r.outcome.is_ok())
+    }
+
+    /// Results of the actions that succeeded.
+    pub fn succeeded(&self) -> impl Iterator<Item = &FleetActionResult> {
+        self.results.iter().filter(|r| r.outcome.is_ok())
+    }
+
+    /// Results of the actions that failed.
+    pub fn failed(&self) -> impl Iterator<Item = &FleetActionResult> {
+        self.results.iter().filter(|r| r.outcome.is_err())
+    }
+
+    /// Record successful outcomes in `fleet` (created pod IDs, terminations).
+    ///
+    /// Failed actions leave their pod's state untouched, so the next
+    /// `reconcile()` plans them again.
+    pub fn record(&self, fleet: &mut FleetState, now_ms: u64) {
+        for result in self.succeeded() {
+            let Some(pod) = fleet
+                .groups
+                .get_mut(&result.group)
+                .and_then(|g| g.pod_mut(&result.pod_name))
+            else {
+                continue;
+            };
+            match &result.outcome {
+                Ok(ActionOutcome::Created(id)) => pod.apply_created(id.clone(), now_ms),
+                Ok(ActionOutcome::Terminated) => pod.apply_terminated(now_ms),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Handle to a running pod with connection helpers.
+#[derive(Debug, Clone)]
+pub struct PodLease {
+    /// Pod ID.
+    pub id: String,
+    /// Pod name.
+    pub name: String,
+    /// Public IP address.
+    pub public_ip: String,
+    /// Port mappings (container port -> public port).
+    pub port_mappings: HashMap<u16, u16>,
+    /// Desired status.
+    pub desired_status: String,
+    /// Data center the pod runs in (when `RunPod` reports it, or the one a
+    /// `data_center_failover` created it in).
+    pub data_center_id: Option<String>,
+    /// How the pod was obtained (reused, created, fallback cloud type, ...).
+    pub provenance: LeaseProvenance,
+    /// Required port specs moved to an alternate container port during
+    /// port negotiation ("8888/tcp" -> 8889; see `port_alternates`).
+    pub port_remaps: HashMap<String, u16>,
+    /// Proxy URLs of the HTTP ports (container port -> URL), set in proxy
+    /// exposure mode, where `public_ip` and `port_mappings` are empty.
+    pub proxy_endpoints: HashMap<u16, String>,
+    /// Host benchmark of a newly created pod (with `benchmark` configured).
+    pub benchmark: Option<BenchmarkResult>,
+    /// Deadline of the lease (ms since UNIX epoch) when the orchestrator has
+    /// a lease registry: renew it before then, or the pod may be reaped.
+    pub expires_ms: Option<u64>,
+    /// Whether the pod is a spot (interruptible) pod, as `RunPod` reported
+    /// it when the lease was granted.
+    pub interruptible: bool,
+}
+
+impl PodLease {
+    /// Get the container port actually exposed for a requested port and
+    /// protocol ("tcp" | "http").
+    ///
+    /// The service must listen on this port inside the container.
+    #[must_use]
+    pub fn container_port(&self, requested: u16, protocol: &str) -> u16 {
+        self.port_remaps
+            .get(&format!("{requested}/{protocol}"))
+            .copied()
+            .unwrap_or(requested)
+    }
+
+    /// Get the SSH endpoint (IP, port).
+    ///
+    /// Returns `None` if SSH port (22) is not mapped.
+    #[must_use]
+    pub fn ssh_endpoint(&self) -> Option<(&str, u16)> {
+        self.port_mappings
+            .get(&self.container_port(22, "tcp"))
+            .map(|port| (self.public_ip.as_str(), *port))
+    }
+
+    /// Get the `ssh` arguments connecting to the pod, for
+    /// `std::process::Command::new("ssh").args(...)`.
+    ///
+    /// `extra_opts` go before the destination, e.g.
+    /// `["-o", "StrictHostKeyChecking=no"]` or `["-L", "8888:localhost:8888"]`.
+    /// Returns `None` if SSH port (22) is not mapped.
+    #[must_use]
+    pub fn ssh_args(
+        &self,
+        user: &str,
+        identity_file: Option<&Path>,
+        extra_opts: &[&str],
+    ) -> Option<Vec<String>> {
+        let (host, port) = self.ssh_endpoint()?;
+        Some(ssh_destination_args(
+            host,
+            port,
+            user,
+            identity_file,
+            extra_opts,
+        ))
+    }
+
+    /// Get a copy-pasteable `ssh` command line, e.g.
+    /// `ssh -i ~/.ssh/id_ed25519 -p 40100 root@203.0.113.7`.
+    ///
+    /// Arguments are quoted for a POSIX shell where needed.
+    /// Returns `None` if SSH port (22) is not mapped.
+    #[must_use]
+    pub fn ssh_command(
+        &self,
+        user: &str,
+        identity_file: Option<&Path>,
+        extra_opts: &[&str],
+    ) -> Option<String> {
+        let args = self.ssh_args(user, identity_file, extra_opts)?;
+        let mut line = "ssh".to_string();
+        for arg in &args {
+            line.push(' ');
+            line.push_str(&shell_arg(arg));
+        }
+        Some(line)
+    }
+
+    /// Get the HTTP endpoint URL for a given (requested) container port.
+    ///
+    /// In proxy exposure mode this is the port's proxy URL.
+    /// Returns `None` if the port is not mapped.
+    #[must_use]
+    pub fn http_endpoint(&self, container_port: u16) -> Option<String> {
+        let port = self.container_port(container_port, "http");
+        self.port_mappings
+            .get(&port)
+            .map(|public_port| format!("http://{}:{}", self.public_ip, public_port))
+            .or_else(|| self.proxy_endpoints.get(&port).cloned())
+    }
+
+    /// Check whether the pod is only reachable through the `RunPod` proxy.
+    #[must_use]
+    pub fn is_proxy_only(&self) -> bool {
+        self.public_ip.is_empty() && !self.proxy_endpoints.is_empty()
+    }
+
+    /// Get the Jupyter endpoint URL (port 8888).
+    ///
+    /// Returns `None` if Jupyter port is not mapped.
+    #[must_use]
+    pub fn jupyter_endpoint(&self) -> Option<String> {
+        self.http_endpoint(8888)
+    }
+
+    /// Get a client for the pod's Jupyter Server (port 8888).
+    ///
+    /// The token is read from `RUNPOD_JUPYTER_TOKEN` (see `JupyterClient::for_lease()`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Jupyter port is not mapped.
+    #[cfg(feature = "jupyter")]
+    pub fn jupyter(&self) -> Result<JupyterClient, JupyterError> {
+        JupyterClient::for_lease(self)
+    }
+
+    /// Tie the pod to the returned guard: releasing or dropping it applies
+    /// `policy` (stop or terminate the pod) through `orchestrator`.
+    #[must_use]
+    pub const fn into_guard(
+        self,
+        orchestrator: Arc<RunpodOrchestrator>,
+        policy: LeaseReleasePolicy,
+    ) -> LeaseGuard {
+        LeaseGuard::new(self, orchestrator, policy)
+    }
+
+    /// Start pinging the pod in the background (see `runpod_liveness`).
+    ///
+    /// Must be called inside a Tokio runtime.
+    #[must_use]
+    pub fn start_liveness(
+        &self,
+        cfg: LivenessConfig,
+        notifier: Arc<dyn Notifier>,
+    ) -> LivenessPinger {
+        LivenessPinger::start(self, cfg, notifier)
+    }
+
+    /// Get the pod's private host name on the global network.
+    ///
+    /// Only resolves from pods on the same network (see `runpod_network`).
+    #[must_use]
+    pub fn private_host(&self) -> String {
+        runpod_network::private_host(&self.id)
+    }
+
+    /// Get the pod-to-pod endpoint for a container port on the global network.
+    ///
+    /// Private ports are not remapped, so this works for any container port,
+    /// mapped publicly or not.
+    #[must_use]
+    pub fn private_endpoint(&self, container_port: u16) -> PrivateEndpoint {
+        PrivateEndpoint::global(&self.id, container_port)
+    }
+
+    /// Get raw TCP endpoint for a given (requested) container port.
+    ///
+    /// Returns `None` if the port is not mapped.
+    #[must_use]
+    pub fn tcp_endpoint(&self, container_port: u16) -> Option<(String, u16)> {
+        self.port_mappings
+            .get(&self.container_port(container_port, "tcp"))
+            .map(|public_port| (self.public_ip.clone(), *public_port))
+    }
+}
+
+/// Instant Cluster to create with `create_cluster()`.
+#[derive(Clone, Debug)]
+pub struct ClusterSpec {
+    /// Cluster name (nodes are named `<name>-<index>`).
+    pub name: String,
+    /// Container image name (same on every node).
+    pub image_name: String,
+    /// GPU type ID.
+    pub gpu_type_id: String,
+    /// Number of nodes.
+    pub node_count: u32,
+    /// GPUs per node.
+    pub gpus_per_node: u32,
+    /// Ports each node must expose before it counts as ready.
+    pub ports: Vec<String>,
+    /// Network volume mounted on every node.
+    pub network_volume_id: Option<String>,
+}
+
+impl ClusterSpec {
+    /// Spec from the orchestrator configuration: its pod name, image, first
+    /// GPU type and ports.
+    #[must_use]
+    pub fn from_config(
+        cfg: &RunpodOrchestratorConfig,
+        node_count: u32,
+        gpus_per_node: u32,
+    ) -> Self {
+        Self {
+            name: cfg.pod_name.clone(),
+            image_name: cfg.image_name.clone(),
+            gpu_type_id: cfg.gpu_type_ids.first().cloned().unwrap_or_default(),
+            node_count,
+            gpus_per_node,
+            ports: cfg.required_ports.clone(),
+            network_volume_id: None,
+        }
+    }
+}
+
+/// One node of a `ClusterLease`.
+#[derive(Debug, Clone)]
+pub struct ClusterNode {
+    /// Node index (0 = primary).
+    pub index: u32,
+    /// Address on the cluster's private network.
+    pub cluster_ip: Option<String>,
+    /// Public lease of the node's pod (SSH, HTTP endpoints).
+    pub lease: PodLease,
+}
+
+impl ClusterNode {
+    /// Get the node's endpoint for a container port on the cluster network.
+    ///
+    /// Returns `None` if the cluster address is unknown.
+    #[must_use]
+    pub fn private_endpoint(&self, container_port: u16) -> Option<PrivateEndpoint> {
+        self.cluster_ip.as_ref().map(|ip| PrivateEndpoint {
+            pod_id: self.lease.id.clone(),
+            host: ip.clone(),
+            port: container_port,
+        })
+    }
+}
+
+/// Handle to a ready Instant Cluster.
+#[derive(Debug, Clone)]
+pub struct ClusterLease {
+    /// Cluster ID.
+    pub id: String,
+    /// Cluster name.
+    pub name: String,
+    /// GPUs per node.
+    pub gpus_per_node: u32,
+    /// Nodes, in index order.
+    pub nodes: Vec<ClusterNode>,
+}
+
+impl ClusterLease {
+    /// The primary node (index 0), which hosts the rendezvous.
+    #[must_use]
+    pub fn primary(&self) -> Option<&ClusterNode> {
+        self.nodes.first()
+    }
+
+    /// Private address of the primary node (`MASTER_ADDR` for `torchrun`).
+    #[must_use]
+    pub fn primary_addr(&self) -> Option<&str> {
+        self.primary().and_then(|n| n.cluster_ip.as_deref())
+    }
+
+    /// Private addresses of every node, in index order.
+    #[must_use]
+    pub fn private_addrs(&self) -> Vec<&str> {
+        self.nodes
+            .iter()
+            .filter_map(|n| n.cluster_ip.as_deref())
+            .collect()
+    }
+
+    /// Cluster-network endpoint of every node for a container port, in index order.
+    #[must_use]
+    pub fn private_endpoints(&self, container_port: u16) -> Vec<PrivateEndpoint> {
+        self.nodes
+            .iter()
+            .filter_map(|n| n.private_endpoint(container_port))
+            .collect()
+    }
+
+    /// SSH endpoint (IP, port) of every node, in index order.
+    ///
+    /// Nodes without SSH mapped are skipped.
+    #[must_use]
+    pub fn ssh_endpoints(&self) -> Vec<(&str, u16)> {
+        self.nodes
+            .iter()
+            .filter_map(|n| n.lease.ssh_endpoint())
+            .collect()
+    }
+
+    /// Total GPU count (the distributed world size).
+    #[must_use]
+    pub fn world_size(&self) -> u32 {
+        u32::try_from(self.nodes.len())
+            .unwrap_or(u32::MAX)
+            .saturating_mul(self.gpus_per_node)
+    }
+}
+
+/// `RunPod` orchestrator for high-level pod management.
+pub struct RunpodOrchestrator {
+    cfg: RunpodOrchestratorConfig,
+    http: reqwest::Client,
+    graphql: RunpodClient,
+    confirm: Option<ConfirmHook>,
+    provision: Option<RunpodProvisionConfig>,
+    readiness_check: Option<ReadinessCheck>,
+    sim: Option<SimulatedCloud>,
+    metrics: Arc<dyn MetricsSink>,
+    progress: Option<Arc<dyn ProgressReporter>>,
+    lock: Option<Arc<dyn DistributedLock>>,
+    state: Option<Arc<dyn StateStore + Send + Sync>>,
+    #[cfg(feature = "ssh")]
+    ssh_login: Option<SshLogin>,
+    availability: Option<Arc<AvailabilityTracker>>,
+    leases: Option<Arc<LeaseRegistry>>,
+    spend_guard: Option<Arc<SpendGuard>>,
+}
+
+impl RunpodOrchestrator {
+    /// Create a new orchestrator from the given configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP options are invalid or the HTTP client
+    /// cannot be built.
+    pub fn new(cfg: RunpodOrchestratorConfig) -> Result<Self, OrchestratorError> {
+        let http = cfg
+            .http
+            .client_builder(cfg.timeout_ms)
+            .map_err(|e| OrchestratorError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?
+            .build()
+            .map_err(OrchestratorError::Http)?;
+
+        Ok(Self::new_with_http(cfg, http))
+    }
+
+    /// Create an orchestrator sending its requests through `http` (e.g. a
+    /// client shared with other components, see `HttpOptions::shared_client()`).
+    ///
+    /// `cfg.http` options are those of `http`; `cfg.timeout_ms` still applies
+    /// per request. The provisioners and GraphQL clients the orchestrator
+    /// creates use `http` too.
+    #[must_use]
+    pub fn new_with_http(cfg: RunpodOrchestratorConfig, http: reqwest::Client) -> Self {
+        let sim = cfg
+            .simulate
+            .then(|| SimulatedCloud::new(cfg.simulate_ready_ticks));
+        let lock = cfg
+            .lock_dir
+            .as_deref()
+            .map(|dir| Arc::new(FileLock::new(dir.trim())) as Arc<dyn DistributedLock>);
+        let graphql_cfg = RunpodClientConfig {
+            api_key: cfg.api_key.clone(),
+            graphql_url: cfg.graphql_url.clone(),
+            timeout_ms: cfg.timeout_ms,
+            retry: cfg.retry.clone(),
+            http: cfg.http.clone(),
+        };
+        let graphql = RunpodClient::new_with_http(graphql_cfg, http.clone());
+
+        Self {
+            cfg,
+            http,
+            graphql,
+            confirm: None,
+            provision: None,
+            readiness_check: None,
+            sim,
+            metrics: runpod_metrics::noop(),
+            progress: None,
+            lock,
+            state: None,
+            #[cfg(feature = "ssh")]
+            ssh_login: None,
+            availability: None,
+            leases: None,
+            spend_guard: None,
+        }
+    }
+
+    /// The HTTP client of this orchestrator, to share with other components.
+    #[must_use]
+    pub const fn http_client(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    /// Report phase transitions and wait estimates to `reporter`.
+    #[must_use]
+    pub fn with_progress(mut self, reporter: Arc<dyn ProgressReporter>) -> Self {
+        self.progress = Some(reporter);
+        self
+    }
+
+    /// Serialize `ensure_ready_pod()` / `replace_pod()` per pod name across
+    /// every orchestrator sharing `lock`.
+    ///
+    /// Without a lock, two replicas reconciling the same pod name at the
+    /// same time can both decide to create it. Replaces the `FileLock` of
+    /// `lock_dir`, if any.
+    #[must_use]
+    pub fn with_lock(mut self, lock: Arc<dyn DistributedLock>) -> Self {
+        self.lock = Some(lock);
+        self
+    }
+
+    /// Persist the `RunPodState` of the configured pod in `store`, so its ID
+    /// survives restarts.
+    ///
+    /// `ensure_ready_pod()` then looks the recorded pod up by ID before
+    /// falling back to its name, lets the state machine
+    /// (`RunPodState::reconcile()`) decide whether a compatible pod is
+    /// reused, started or replaced, and saves the state once the pod is
+    /// ready; `terminate()` forgets the pod. Other pod names are not
+    /// recorded.
+    #[must_use]
+    pub fn with_state_store(mut self, store: Arc<dyn StateStore + Send + Sync>) -> Self {
+        self.state = Some(store);
+        self
+    }
+
+    /// The state store installed with `with_state_store()`, if any.
+    #[must_use]
+    pub const fn state_store(&self) -> Option<&Arc<dyn StateStore + Send + Sync>> {
+        self.state.as_ref()
+    }
+
+    /// Run the pre-stop command (`pre_stop_command`) with `login` instead of
+    /// `SshLogin::from_env()`.
+    #[cfg(feature = "ssh")]
+    #[must_use]
+    pub fn with_ssh_login(mut self, login: SshLogin) -> Self {
+        self.ssh_login = Some(login);
+        self
+    }
+
+    /// Record creations and capacity errors in `tracker`, and use its history
+    /// to order GPU types, data centers and `data_center_failover` so that
+    /// chronically unavailable combinations are tried last.
+    ///
+    /// When the order changes, the request asks `RunPod` to honor it
+    /// (`gpuTypePriority` / `dataCenterPriority` "custom").
+    #[must_use]
+    pub fn with_availability(mut self, tracker: Arc<AvailabilityTracker>) -> Self {
+        self.availability = Some(tracker);
+        self
+    }
+
+    /// Get the availability tracker, if one is installed.
+    #[must_use]
+    pub const fn availability(&self) -> Option<&Arc<AvailabilityTracker>> {
+        self.availability.as_ref()
+    }
+
+    /// Grant a lease with a TTL on every pod this orchestrator ensures (see
+    /// `runpod_ttl`): leases not renewed in time are reaped by
+    /// `reap_expired_leases()`.
+    #[must_use]
+    pub fn with_lease_registry(mut self, registry: Arc<LeaseRegistry>) -> Self {
+        self.leases = Some(registry);
+        self
+    }
+
+    /// Get the lease registry, if one is installed.
+    #[must_use]
+    pub const fn lease_registry(&self) -> Option<&Arc<LeaseRegistry>> {
+        self.leases.as_ref()
+    }
+
+    /// Enforce a hard spending cap (see `SpendGuard`): once it is reached,
+    /// pod creations and starts fail with `OrchestratorError::SpendCapReached`,
+    /// and `enforce_spend_cap()` stops (or terminates) the pods running over it.
+    #[must_use]
+    pub fn with_spend_guard(mut self, guard: Arc<SpendGuard>) -> Self {
+        self.spend_guard = Some(guard);
+        self
+    }
+
+    /// Get the spend guard, if one is installed.
+    #[must_use]
+    pub const fn spend_guard(&self) -> Option<&Arc<SpendGuard>> {
+        self.spend_guard.as_ref()
+    }
+
+    /// Report metrics to `sink` (HTTP calls, creations, readiness waits).
+    #[must_use]
+    pub fn with_metrics(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.graphql = self.graphql.with_metrics(Arc::clone(&sink));
+        self.metrics = sink;
+        self
+    }
+
+    /// Get the simulator backing this orchestrator (`simulate` mode only).
+    #[must_use]
+    pub const fn simulator(&self) -> Option<&SimulatedCloud> {
+        self.sim.as_ref()
+    }
+
+    /// Require confirmation before any pod creation.
+    ///
+    /// The hook receives the pod spec and its estimated hourly cost; if it
+    /// resolves to `false`, orchestration aborts with
+    /// `OrchestratorError::CreationDeclined`. Starting or updating existing
+    /// pods is not gated.
+    #[must_use]
+    pub fn with_confirmation<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(CostEstimate) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        self.confirm = Some(Arc::new(move |estimate| Box::pin(hook(estimate))));
+        self
+    }
+
+    /// Create pods from `provision_cfg` instead of reading
+    /// `RunpodProvisionConfig::from_env()` on every creation.
+    ///
+    /// The pod name, image, GPU types and ports still come from the
+    /// orchestrator (and per-call `PodOverrides`), as do the client settings
+    /// (API key and version, REST and GraphQL URLs, timeouts, HTTP options,
+    /// retry policy), so both configs stay consistent; `provision_cfg`
+    /// supplies the rest (cloud type, disks, pod env, spot bid, data
+    /// centers...).
+    #[must_use]
+    pub fn with_provision_config(mut self, provision_cfg: RunpodProvisionConfig) -> Self {
+        self.provision = Some(provision_cfg);
+        self
+    }
+
+    /// Install an application-level readiness check (e.g. "`/healthz`
+    /// returns 200").
+    ///
+    /// The check runs on every readiness poll once the ports are mapped and
+    /// the `ready_probes` pass (simulated pods included), and receives the
+    /// candidate lease; the pod is ready once it returns `true`. Polling and
+    /// the `ready_timeout_ms` deadline are the usual ones: a check still
+    /// running at the deadline is cancelled and counts as failed.
+    #[must_use]
+    pub fn with_readiness_check<F, Fut>(mut self, check: F) -> Self
+    where
+        F: Fn(PodLease) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        self.readiness_check = Some(Arc::new(move |lease| Box::pin(check(lease))));
+        self
+    }
+
+    /// Get a reference to the current configuration.
+    #[must_use]
+    pub const fn config(&self) -> &RunpodOrchestratorConfig {
+        &self.cfg
+    }
+
+    /// Ensure a ready pod is available.
+    ///
+    /// This method will:
+    /// 1. List existing pods and find one matching the configured name
+    /// 2. If found and compatible, start it if needed
+    /// 3. If not found or incompatible, create a new pod
+    /// 4. Wait for the pod to be ready (publicIp + required ports)
+    ///
+    /// Returns a `PodLease` with connection helpers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if pod creation, starting, or readiness checks fail.
+    pub async fn ensure_ready_pod(&self) -> Result<PodLease, OrchestratorError> {
+        self.ensure_ready_pod_with(&PodOverrides::default()).await
+    }
+
+    /// Ensure a ready pod is available, overriding parts of the configuration
+    /// for this call only.
+    ///
+    /// Behaves like `ensure_ready_pod()`, but the pod name, image, GPU types,
+    /// GPU count and ports from `overrides` take precedence over the base config.
+    /// This lets one orchestrator serve several similar workloads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if pod creation, starting, or readiness checks fail.
+    pub async fn ensure_ready_pod_with(
+        &self,
+        overrides: &PodOverrides,
+    ) -> Result<PodLease, OrchestratorError> {
+        let start = Instant::now();
+        let mut spec = PodSpec::resolve(&self.cfg, overrides);
+        let negotiated = negotiate_ports(&spec.ports, &self.cfg.port_alternates, self.cfg.exposure)
+            .map_err(OrchestratorError::PortConflict)?;
+        spec.ports = negotiated.ports;
+        let port_remaps = negotiated.remaps;
+        let name = spec.name.clone();
+        let result = match self
+            .with_pod_lock(&name, self.ensure_ready_spec(spec, start))
+            .await
+        {
+            Ok(lease) => Ok(self
+                .grant_lease(PodLease {
+                    port_remaps,
+                    ..lease
+                })
+                .await),
+            Err(e) => Err(e),
+        };
+
+        match &result {
+            Ok(lease) => log_info!(
+                "pod {} ({}) ready: {}",
+                lease.name,
+                lease.id,
+                lease.provenance.action.as_str()
+            ),
+            Err(e) => log_warn!(
+                "ensure_ready_pod for {name} failed: {e}",
+                e = ErrorChain(&e)
+            ),
+        }
+        let tags = result
+            .as_ref()
+            .map_or([("outcome", "error"), ("action", "none")], |lease| {
+                [
+                    ("outcome", "ok"),
+                    ("action", lease.provenance.action.as_str()),
+                ]
+            });
+        self.metrics.histogram(
+            runpod_metrics::ENSURE_READY_MS,
+            runpod_metrics::elapsed_ms(start),
+            &tags,
+        );
+
+        result
+    }
+
+    /// Ensure a ready pod (as `ensure_ready_pod()`) guarded by
+    /// `lease_release_policy`: the pod is stopped or terminated when the
+    /// guard is released or dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if pod creation, starting, or readiness checks fail.
+    pub async fn ensure_guarded_pod(self: &Arc<Self>) -> Result<LeaseGuard, OrchestratorError> {
+        let lease = self.ensure_ready_pod().await?;
+        Ok(lease.into_guard(Arc::clone(self), self.cfg.lease_release_policy))
+    }
+
+    /// Ensure a ready pod (as `ensure_ready_pod_with()`), giving up when
+    /// `cancel` is cancelled.
+    ///
+    /// Cancelling interrupts the lock and readiness waits: the pod lock is
+    /// released, and pods created by this call that never became ready are
+    /// terminated rather than left billing. Dropping the future instead
+    /// would leave both behind.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OrchestratorError::Cancelled` (with the pod that was being
+    /// waited for) once cancelled, or any error of `ensure_ready_pod_with()`.
+    pub async fn ensure_ready_pod_cancellable(
+        &self,
+        overrides: &PodOverrides,
+        cancel: &CancellationToken,
+    ) -> Result<PodLease, OrchestratorError> {
+        if cancel.is_cancelled() {
+            return Err(OrchestratorError::Cancelled { pod_id: None });
+        }
+        let scope = CancelScope {
+            token: cancel.clone(),
+            created: Vec::new(),
+        };
+        let (result, created) = CANCEL
+            .scope(RefCell::new(scope), async {
+                let result = self.ensure_ready_pod_with(overrides).await;
+                let created = CANCEL.with(|cell| std::mem::take(&mut cell.borrow_mut().created));
+                (result, created)
+            })
+            .await;
+
+        if matches!(result, Err(OrchestratorError::Cancelled { .. })) {
+            // Never leave a billed, half-created pod behind.
+            for pod_id in &created {
+                match self.terminate(pod_id).await {
+                    Ok(_) => log_info!("terminated pod {pod_id} after cancellation"),
+                    Err(e) => log_warn!(
+                        "could not terminate cancelled pod {pod_id}: {e}",
+                        e = ErrorChain(&e)
+                    ),
+                }
+            }
+        }
+        result
+    }
+
+    /// Record a lease with a TTL on a ready pod (with a lease registry).
+    ///
+    /// A registry failure is logged, not returned: the pod is usable, it
+    /// just cannot be reaped.
+    async fn grant_lease(&self, lease: PodLease) -> PodLease {
+        let Some(registry) = &self.leases else {
+            return lease;
+        };
+        let (pod_id, pod_name) = (lease.id.clone(), lease.name.clone());
+        let granted = runpod_ttl::run_blocking(registry, move |r| {
+            r.grant(&pod_id, &pod_name, now_unix_ms())
+        });
+        match granted.await {
+            Ok(expires_ms) => PodLease {
+                expires_ms: Some(expires_ms),
+                ..lease
+            },
+            Err(e) => {
+                log_warn!(
+                    "granting lease of pod {} failed: {e}",
+                    lease.id,
+                    e = ErrorChain(&e)
+                );
+                lease
+            }
+        }
+    }
+
+    /// Renew the lease of `lease`'s pod and update its `expires_ms`.
+    ///
+    /// Returns the new deadline, or `None` without a lease registry (leases
+    /// do not expire).
+    ///
+    /// # Errors
+    ///
+    /// Returns `LeaseExpired` if the lease was released or reaped, or an
+    /// error if the lease file cannot be updated.
+    pub async fn renew(&self, lease: &mut PodLease) -> Result<Option<u64>, OrchestratorError> {
+        let Some(registry) = &self.leases else {
+            return Ok(None);
+        };
+        let pod_id = lease.id.clone();
+        let renewed = runpod_ttl::run_blocking(registry, move |r| r.renew(&pod_id, now_unix_ms()));
+        let expires_ms = renewed
+            .await
+            .map_err(OrchestratorError::State)?
+            .ok_or_else(|| OrchestratorError::LeaseExpired(lease.id.clone()))?;
+        lease.expires_ms = Some(expires_ms);
+        Ok(Some(expires_ms))
+    }
+
+    /// Renew the lease of `lease`'s pod in the background, every third of
+    /// the TTL (`None` without a lease registry).
+    ///
+    /// Must be called inside a Tokio runtime.
+    #[must_use]
+    pub fn start_heartbeat(&self, lease: &PodLease) -> Option<LeaseHeartbeat> {
+        self.leases
+            .as_ref()
+            .map(|registry| LeaseHeartbeat::start(Arc::clone(registry), &lease.id))
+    }
+
+    /// Terminate the pods whose lease expired, and drop their leases.
+    ///
+    /// Each lease is checked again right before its pod is terminated (and
+    /// dropped in the same locked update), so a lease renewed in between is
+    /// kept. Returns the reaped leases. A pod that cannot be terminated gets
+    /// its lease back, so the next call retries it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease file cannot be read or updated.
+    pub async fn reap_expired_leases(&self) -> Result<Vec<LeaseRecord>, OrchestratorError> {
+        let Some(registry) = &self.leases else {
+            return Ok(Vec::new());
+        };
+
+        let mut reaped = Vec::new();
+        let listed = runpod_ttl::run_blocking(registry, |r| r.expired(now_unix_ms()))
+            .await
+            .map_err(OrchestratorError::State)?;
+        for expired in listed {
+            let record = expired.clone();
+            let claimed = runpod_ttl::run_blocking(registry, move |r| {
+                r.claim_expired(&record, now_unix_ms())
+            })
+            .await
+            .map_err(OrchestratorError::State)?;
+            if !claimed {
+                log_debug!("lease of pod {} renewed before reaping", expired.pod_id);
+                continue;
+            }
+            match self.terminate(&expired.pod_id).await {
+                Ok(outcome) => {
+                    log_info!(
+                        "lease of pod {} ({}) expired at {}: {outcome:?}",
+                        expired.pod_name,
+                        expired.pod_id,
+                        expired.expires_ms
+                    );
+                    reaped.push(expired);
+                }
+                Err(e) => {
+                    log_warn!(
+                        "reaping pod {} failed: {e}",
+                        expired.pod_id,
+                        e = ErrorChain(&e)
+                    );
+                    runpod_ttl::run_blocking(registry, move |r| r.restore(expired))
+                        .await
+                        .map_err(OrchestratorError::State)?;
+                }
+            }
+        }
+        Ok(reaped)
+    }
+
+    /// Call `reap_expired_leases()` every `interval`, forever.
+    ///
+    /// Run it in a companion process (or a spawned task) sharing the state
+    /// directory with the clients. Errors are logged and retried.
+    pub async fn run_lease_reaper(&self, interval: Duration) {
+        loop {
+            if let Err(e) = self.reap_expired_leases().await {
+                log_warn!("lease reaper: {e}", e = ErrorChain(&e));
+            }
+            tokio::time::sleep(interval).await;
+        }
+    }
+
+    /// Bring the configured pod to `target` once: ensure it is ready
+    /// (`Running`), stop it (`Exited`) or terminate it (`Terminated`).
+    ///
+    /// A running target only calls `ensure_ready_pod()` when `plan()` would
+    /// not simply reuse the pod, so a converged pod is left alone. In
+    /// `ReconcileMode::Recreate`, where `plan()` never reuses, a running pod
+    /// with the configured name counts as converged: the pod is recreated
+    /// by an explicit `ensure_ready_pod()`, not on every pass.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pods cannot be read or the action fails.
+    pub async fn reconcile_to(
+        &self,
+        target: TargetStatus,
+    ) -> Result<ReconcileAction, OrchestratorError> {
+        if target == TargetStatus::Running {
+            let converged = if self.cfg.reconcile_mode == ReconcileMode::Recreate {
+                self.find_pod_by_name(&self.cfg.pod_name)
+                    .await?
+                    .is_some_and(|pod| pod.desiredStatus.as_deref() == Some("RUNNING"))
+            } else {
+                self.plan().await?.action == LeaseAction::Reused
+            };
+            if converged {
+                return Ok(ReconcileAction::Noop);
+            }
+            let lease = self.ensure_ready_pod().await?;
+            return Ok(ReconcileAction::Ensured {
+                pod_id: lease.id,
+                how: lease.provenance.action,
+            });
+        }
+
+        let Some(pod) = self.find_pod_by_name(&self.cfg.pod_name).await? else {
+            return Ok(ReconcileAction::Noop);
+        };
+        match (target, pod.desiredStatus.as_deref()) {
+            (TargetStatus::Exited, Some("RUNNING")) => {
+                self.stop_pod(&pod.id).await?;
+                Ok(ReconcileAction::Stopped { pod_id: pod.id })
+            }
+            (TargetStatus::Terminated, status) if status != Some("TERMINATED") => {
+                self.terminate(&pod.id).await?;
+                Ok(ReconcileAction::Terminated { pod_id: pod.id })
+            }
+            _ => Ok(ReconcileAction::Noop),
+        }
+    }
+
+    /// Keep the configured pod at a target status from a background task,
+    /// reconciling with `reconcile_to()` every `interval`.
+    ///
+    /// The target starts as `TargetStatus::Running`; change it with
+    /// `ReconcilerHandle::set_target()`. The loop stops when the handle is
+    /// dropped or shut down. Must be called inside a Tokio runtime.
+    #[must_use]
+    pub fn run_reconciler(self: &Arc<Self>, interval: Duration) -> ReconcilerHandle {
+        ReconcilerHandle::start(Arc::clone(self), interval, TargetStatus::Running)
+    }
+
+    /// Highest GPU utilization (in %) of the configured pod, with its ID.
+    ///
+    /// `None` if the pod is not running or reports no GPU metrics yet.
+    /// Simulated pods are always idle (0%).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pod or its metrics cannot be read.
+    pub async fn running_gpu_utilization(
+        &self,
+    ) -> Result<Option<(String, f32)>, OrchestratorError> {
+        let Some(pod) = self
+            .find_pod_by_name(&self.cfg.pod_name)
+            .await?
+            .filter(|pod| pod.desiredStatus.as_deref() == Some("RUNNING"))
+        else {
+            return Ok(None);
+        };
+        if self.sim.is_some() {
+            return Ok(Some((pod.id, 0.0)));
+        }
+
+        let details = self
+            .graphql_client()
+            .get_pod(&pod.id)
+            .await
+            .map_err(|e| OrchestratorError::Provision(Box::new(e)))?;
+        let percent = details
+            .and_then(|d| d.runtime)
+            .and_then(|r| r.gpus)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|gpu| gpu.gpuUtilPercent)
+            .reduce(f32::max);
+        Ok(percent.map(|percent| (pod.id, percent)))
+    }
+
+    /// Stop (or terminate) the configured pod from a background task once
+    /// its GPUs stay under `policy.threshold_percent` for
+    /// `policy.stop_after_ms` (see `runpod_idle`).
+    ///
+    /// The task stops when the monitor is dropped. Must be called inside a
+    /// Tokio runtime.
+    #[must_use]
+    pub fn enable_idle_stop(self: &Arc<Self>, policy: IdlePolicy) -> IdleMonitor {
+        IdleMonitor::start(Arc::clone(self), policy)
+    }
+
+    /// Observe this orchestrator's pods with the spend guard, then stop (or
+    /// terminate) the running pods over its cap, and save the guard's ledger.
+    ///
+    /// The cap only covers the pods named `pod_name` (or after
+    /// `pod_name_pattern`), matching `pod_selector`, carrying all of
+    /// `pod_labels`, or created by this orchestrator; other pods of the
+    /// account are neither counted nor stopped.
+    ///
+    /// Returns `None` without a spend guard. Pods that could not be stopped
+    /// are logged and left out of `enforced`: the next check retries them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pods cannot be listed.
+    pub async fn enforce_spend_cap(&self) -> Result<Option<SpendCapStatus>, OrchestratorError> {
+        let Some(guard) = &self.spend_guard else {
+            return Ok(None);
+        };
+        self.observe_spend(guard).await?;
+
+        let mut status = guard.check(now_unix_ms());
+        let action = guard.cap().action;
+        for name in &status.over_cap {
+            let Some(pod_id) = guard.pod_id(name) else {
+                continue;
+            };
+            let result = match action {
+                SpendCapAction::Stop => self.stop_pod(&pod_id).await,
+                SpendCapAction::Terminate => self.terminate(&pod_id).await.map(|_| ()),
+            };
+            match result {
+                Ok(()) => {
+                    log_warn!(
+                        "spend cap reached: {} pod {name} ({pod_id})",
+                        action.as_str()
+                    );
+                    status.enforced.push(name.clone());
+                }
+                Err(e) => log_warn!(
+                    "spend cap reached but pod {name} could not be stopped: {e}",
+                    e = ErrorChain(&e)
+                ),
+            }
+        }
+        if !status.enforced.is_empty() {
+            // Count the stopped pods out of the ledger right away.
+            self.observe_spend(guard).await?;
+        }
+
+        let ledger = Arc::clone(guard);
+        match tokio::task::spawn_blocking(move || ledger.save()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => log_warn!("could not save the spend ledger: {e}", e = ErrorChain(&e)),
+            Err(e) => log_warn!("could not save the spend ledger: {e}", e = ErrorChain(&e)),
+        }
+        Ok(Some(status))
+    }
+
+    /// Feed the pods in the spend cap's scope to `guard`.
+    async fn observe_spend(&self, guard: &SpendGuard) -> Result<(), OrchestratorError> {
+        let pods: Vec<PodInfo> = self
+            .list_pods()
+            .await?
+            .into_iter()
+            .filter(|pod| self.in_spend_scope(pod, guard))
+            .collect();
+        guard.observe_pods(&pods, now_unix_ms());
+        Ok(())
+    }
+
+    /// Whether the spend cap covers `pod` (see `enforce_spend_cap()`).
+    fn in_spend_scope(&self, pod: &PodInfo, guard: &SpendGuard) -> bool {
+        let name = pod.name.as_deref().unwrap_or(&pod.id);
+        if name == self.cfg.pod_name || self.is_replica_name(name) || guard.tracks(name) {
+            return true;
+        }
+        let labels = pod.labels();
+        self.cfg
+            .pod_selector
+            .as_ref()
+            .is_some_and(|s| s.matches(&labels))
+            || (!self.cfg.pod_labels.is_empty()
+                && self
+                    .cfg
+                    .pod_labels
+                    .iter()
+                    .all(|(k, v)| labels.get(k) == Some(v)))
+    }
+
+    /// Whether `candidate` is a `replica_name()`.
+    fn is_replica_name(&self, candidate: &str) -> bool {
+        let pattern = self
+            .cfg
+            .pod_name_pattern
+            .replace("{name}", &self.cfg.pod_name);
+        let Some((prefix, suffix)) = pattern.split_once("{n}") else {
+            return candidate == pattern;
+        };
+        candidate
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+    }
+
+    /// Run `enforce_spend_cap()` every `interval` from a background task.
+    ///
+    /// The task stops when the handle is dropped. Must be called inside a
+    /// Tokio runtime.
+    #[must_use]
+    pub fn run_spend_guard(self: &Arc<Self>, interval: Duration) -> SpendGuardTask {
+        SpendGuardTask::start(Arc::clone(self), interval)
+    }
+
+    /// Keep the configured pod running inside the windows of `schedule` and
+    /// stopped (or terminated) outside them, from a background reconciler
+    /// passing every `interval` and at every window boundary.
+    ///
+    /// `ReconcilerHandle::set_target()` overrides the schedule until the next
+    /// boundary. The loop stops when the handle is dropped or shut down.
+    /// Must be called inside a Tokio runtime.
+    #[must_use]
+    pub fn run_schedule(
+        self: &Arc<Self>,
+        schedule: Schedule,
+        interval: Duration,
+    ) -> ReconcilerHandle {
+        ReconcilerHandle::start_scheduled(Arc::clone(self), interval, schedule)
+    }
+
+    /// Current lifecycle status of a pod, read from the configured
+    /// `readiness_source` (`Terminated` if the pod is gone).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pod cannot be read.
+    pub async fn pod_status(&self, pod_id: &str) -> Result<PodStatus, OrchestratorError> {
+        let (source, graphql) = self.readiness_reader();
+        let pod = self.readiness_view(pod_id, source, graphql).await?;
+        let required_ports = self.gating_ports(&self.cfg.required_ports);
+        Ok(PodStatus::of(
+            pod.as_ref(),
+            self.cfg.exposure,
+            &required_ports,
+        ))
+    }
+
+    /// Follow the status of a pod (Created → Starting → Running → Exited →
+    /// Terminated), polling every `poll_interval_ms`.
+    ///
+    /// The receiver starts with the current status (`from: None`) and holds
+    /// the latest transition: transitions not read before the next one are
+    /// skipped. Polling ends once the pod is terminated (the channel then
+    /// closes) or every receiver is dropped; failed reads are logged and
+    /// retried. Must be called inside a Tokio runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the first read fails.
+    pub async fn watch(
+        self: &Arc<Self>,
+        pod_id: &str,
+    ) -> Result<watch::Receiver<PodTransition>, OrchestratorError> {
+        let mut current = self.pod_status(pod_id).await?;
+        let (tx, rx) = watch::channel(PodTransition {
+            pod_id: pod_id.to_string(),
+            from: None,
+            to: current,
+            at_ms: now_unix_ms(),
+        });
+        if current == PodStatus::Terminated {
+            return Ok(rx);
+        }
+
+        let orchestrator = Arc::clone(self);
+        let id = pod_id.to_string();
+        let interval = Duration::from_millis(self.cfg.poll_interval_ms.max(1));
+        tokio::spawn(async move {
+            while current != PodStatus::Terminated {
+                tokio::select! {
+                    () = tx.closed() => return,
+                    () = tokio::time::sleep(interval) => {}
+                }
+                let status = match orchestrator.pod_status(&id).await {
+                    Ok(status) => status,
+                    Err(e) => {
+                        log_warn!("watching pod {id} failed: {e}", e = ErrorChain(&e));
+                        continue;
+                    }
+                };
+                if status != current {
+                    log_debug!("pod {id}: {current} -> {status}");
+                    tx.send_replace(PodTransition {
+                        pod_id: id.clone(),
+                        from: Some(current),
+                        to: status,
+                        at_ms: now_unix_ms(),
+                    });
+                    current = status;
+                }
+            }
+        });
+        Ok(rx)
+    }
+
+    /// Watch a leased pod every `interval` and recover it from spot
+    /// preemption according to `on_preemption`.
+    ///
+    /// The pod counts as a spot pod when the lease says so, when pods are
+    /// created interruptible (`with_provision_config()`), or once `RunPod`
+    /// reports it interruptible. A spot pod that leaves `RUNNING` (or
+    /// disappears) counts as preempted: it is recorded in the availability
+    /// history, then resumed or recreated and `on_recovered` receives the
+    /// new lease. Recreated pods use the base
+    /// configuration under the lease's pod name. Monitoring ends when a
+    /// non-spot pod stops or goes away, returning the last lease. Note that
+    /// deliberately stopping a monitored spot pod looks like a preemption:
+    /// stop the monitor first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Preempted` with the `fail` policy, or an error if the pod
+    /// cannot be recovered. Failed status reads are logged and retried.
+    pub async fn monitor(
+        &self,
+        mut lease: PodLease,
+        interval: Duration,
+        mut on_recovered: impl FnMut(&PodLease),
+    ) -> Result<PodLease, OrchestratorError> {
+        let spot_config = self.provision.as_ref().is_some_and(|p| p.interruptible);
+        let mut spot = spot_config || lease.interruptible;
+        loop {
+            tokio::time::sleep(interval).await;
+            let pod = match self.get_pod(&lease.id).await {
+                Ok(pod) => pod,
+                Err(e) => {
+                    log_warn!(
+                        "monitoring pod {} failed: {e}",
+                        lease.id,
+                        e = ErrorChain(&e)
+                    );
+                    continue;
+                }
+            };
+
+            spot |= pod.as_ref().and_then(|p| p.interruptible).unwrap_or(false);
+            let status = pod.as_ref().and_then(|p| p.desiredStatus.as_deref());
+            if status == Some("RUNNING") {
+                continue;
+            }
+            if !spot {
+                log_info!(
+                    "pod {} ({}) is {}: monitoring ended",
+                    lease.name,
+                    lease.id,
+                    status.unwrap_or("gone")
+                );
+                return Ok(lease);
+            }
+
+            lease = self.recover_preempted(&lease, pod.as_ref()).await?;
+            spot = spot_config || lease.interruptible;
+            on_recovered(&lease);
+        }
+    }
+
+    /// Record a preemption and apply `on_preemption` to the pod of `lease`.
+    async fn recover_preempted(
+        &self,
+        lease: &PodLease,
+        pod: Option<&PodDetails>,
+    ) -> Result<PodLease, OrchestratorError> {
+        let policy = self.cfg.on_preemption;
+        log_warn!(
+            "spot pod {} ({}) preempted: {}",
+            lease.name,
+            lease.id,
+            policy.as_str()
+        );
+        self.metrics.counter(
+            runpod_metrics::PODS_PREEMPTED,
+            1,
+            &[("policy", policy.as_str())],
+        );
+
+        let gpu = pod
+            .and_then(|p| p.gpu.as_ref())
+            .and_then(|g| g.id.as_deref());
+        if let (Some(tracker), Some(gpu)) = (&self.availability, gpu) {
+            let data_center_id = pod
+                .and_then(|p| p.machine.as_ref())
+                .and_then(|m| m.dataCenterId.clone());
+            let (gpu_type_id, now) = (gpu.to_string(), now_unix_ms());
+            let recorded = runpod_availability::run_blocking(tracker, move |tracker| {
+                let outcome = ProvisionOutcome::Preempted;
+                tracker.record(&gpu_type_id, data_center_id.as_deref(), outcome, now)
+            })
+            .await;
+            if let Err(e) = recorded {
+                log_warn!(
+                    "could not record preemption of {gpu}: {e}",
+                    e = ErrorChain(&e)
+                );
+            }
+        }
+
+        match policy {
+            PreemptionPolicy::Fail => return Err(OrchestratorError::Preempted(lease.id.clone())),
+            PreemptionPolicy::Resume if pod.is_some() => {
+                let started = Instant::now();
+                self.report_progress(&lease.name, ProgressPhase::Starting, started, None);
+                match self.start_pod(&lease.id).await {
+                    Ok(()) => {
+                        let required_ports = self.cfg.required_ports.clone();
+                        let resumed = self
+                            .wait_for_ready(&lease.id, &lease.name, &required_ports, started)
+                            .await?;
+                        return Ok(self.grant_lease(resumed).await);
+                    }
+                    Err(e) => log_warn!(
+                        "resuming pod {} failed, recreating: {e}",
+                        lease.id,
+                        e = ErrorChain(&e)
+                    ),
+                }
+            }
+            PreemptionPolicy::Resume | PreemptionPolicy::Recreate => {}
+        }
+
+        if pod.is_some() {
+            self.terminate_and_wait(
+                &lease.id,
+                Duration::from_millis(self.cfg.terminate_timeout_ms),
+            )
+            .await?;
+        }
+        let overrides = PodOverrides {
+            name: Some(lease.name.clone()),
+            ..PodOverrides::default()
+        };
+        self.ensure_ready_pod_with(&overrides).await
+    }
+
+    /// Name of pod `index` of `ensure_ready_pods()` (see `pod_name_pattern`).
+    #[must_use]
+    pub fn replica_name(&self, index: usize) -> String {
+        self.cfg
+            .pod_name_pattern
+            .replace("{name}", &self.cfg.pod_name)
+            .replace("{n}", &index.to_string())
+    }
+
+    /// Ensure `count` ready pods, named `replica_name(0..count)`.
+    ///
+    /// Each pod goes through `ensure_ready_pod_with()`: existing compatible
+    /// pods are reused (and started if needed), missing ones are created. At
+    /// most `fleet_concurrency` pods are handled at once. Leases are returned
+    /// in index order.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error (by index) if any pod could not be made ready.
+    /// The other pods are still ensured, so calling again only retries the
+    /// failed ones.
+    pub async fn ensure_ready_pods(
+        &self,
+        count: usize,
+    ) -> Result<Vec<PodLease>, OrchestratorError> {
+        let limit = usize::try_from(self.cfg.fleet_concurrency.max(1)).unwrap_or(1);
+
+        let results: Vec<Result<PodLease, OrchestratorError>> = stream::iter(0..count)
+            .map(|index| async move {
+                let overrides = PodOverrides {
+                    name: Some(self.replica_name(index)),
+                    ..PodOverrides::default()
+                };
+                self.ensure_ready_pod_with(&overrides).await
+            })
+            .buffered(limit)
+            .collect()
+            .await;
+
+        results.into_iter().collect()
+    }
+
+    /// Ensure a ready pod is available (as `ensure_ready_pod_with()`), and
+    /// report what was done: pods considered and rejected, actions taken,
+    /// retries and timings.
+    ///
+    /// The report is returned whether or not the call succeeded.
+    pub async fn ensure_ready_pod_with_report(
+        &self,
+        overrides: &PodOverrides,
+    ) -> (Result<PodLease, OrchestratorError>, OrchestrationReport) {
+        let recorder = ReportRecorder {
+            started: Instant::now(),
+            report: OrchestrationReport {
+                pod_name: PodSpec::resolve(&self.cfg, overrides).name,
+                ..OrchestrationReport::default()
+            },
+        };
+        REPORT
+            .scope(RefCell::new(Some(recorder)), async {
+                let ensure = Box::pin(self.ensure_ready_pod_with(overrides));
+                let (result, retries) = runpod_http::count_retries(ensure).await;
+                let mut report = REPORT
+                    .try_with(|cell| cell.borrow_mut().take())
+                    .ok()
+                    .flatten()
+                    .map(|finished| finished.finish(&result))
+                    .unwrap_or_default();
+                report.retries = retries;
+                (result, report)
+            })
+            .await
+    }
+
+    async fn ensure_ready_spec(
+        &self,
+        spec: PodSpec,
+        started: Instant,
+    ) -> Result<PodLease, OrchestratorError> {
+        // Step 1: Find existing pod by name and decide what to do with it
+        self.report_progress(&spec.name, ProgressPhase::Discovering, started, None);
+        let mut state = self.load_state(&spec.name).await;
+        let mut decision = Box::pin(self.decide(&spec, state.as_mut())).await?;
+        // Released once the adoption is recorded (renamed or saved to state).
+        let adoption_lock = decision.adoption_lock.take();
+        let result = if let Some(reason) = decision.blocked {
+            Err(Box::pin(self.block(decision, state, &spec.name, reason)).await)
+        } else {
+            match Box::pin(self.apply_decision(decision, spec, started)).await {
+                Ok(lease) => {
+                    self.save_state(state, &lease).await;
+                    Ok(lease)
+                }
+                Err(e) => Err(e),
+            }
+        };
+        if let Some(held) = adoption_lock {
+            held.release().await;
+        }
+        result
+    }
+
+    /// Terminate the decision's existing pod (if any) as the state policy
+    /// demands, record it in `state` and return the `BlockedByPolicy` error.
+    async fn block(
+        &self,
+        decision: Decision,
+        state: Option<RunPodState>,
+        pod_name: &str,
+        reason: PlanReason,
+    ) -> OrchestratorError {
+        if let Some(pod) = &decision.existing {
+            record_considered(pod, false, &decision.reason);
+        }
+        let terminated = decision.existing.map(|pod| pod.id);
+        if let Some(pod_id) = &terminated {
+            self.run_pre_stop_hook(pod_id).await;
+            match self.terminate_pod(pod_id).await {
+                Ok(outcome) => {
+                    log_info!("pod {pod_id} terminate ({reason}): {outcome:?}");
+                    if outcome.was_terminated() {
+                        record_step(StepKind::Terminate, pod_id);
+                    }
+                }
+                Err(e) => return e,
+            }
+        }
+        if let (Some(store), Some(mut state)) = (&self.state, state) {
+            state.apply_terminated(now_unix_ms());
+            if let Err(e) = runpod_state::run_blocking(store, move |s| s.save(&mut state)).await {
+                log_warn!(
+                    "could not save state of pod {pod_name}: {e}",
+                    e = ErrorChain(&e)
+                );
+            }
+        }
+        OrchestratorError::BlockedByPolicy {
+            pod_name: pod_name.to_string(),
+            terminated,
+            reason,
+        }
+    }
+
+    /// Act on `decision` and wait for the resulting pod to be ready.
+    async fn apply_decision(
+        &self,
+        decision: Decision,
+        mut spec: PodSpec,
+        started: Instant,
+    ) -> Result<PodLease, OrchestratorError> {
+        if let Some(pod) = &decision.existing {
+            record_considered(pod, decision.accepted, &decision.reason);
+            if decision.adopted && self.state.is_none() {
+                // No state store to remember the pod: give it the spec's name.
+                let rename = PodUpdate {
+                    name: Some(spec.name.clone()),
+                    ..PodUpdate::default()
+                };
+                self.update_pod(&pod.id, &rename).await?;
+                log_info!("adopted pod {} renamed to {}", pod.id, spec.name);
+            }
+        }
+
+        let pod_id = match (decision.action, decision.existing) {
+            (LeaseAction::Reused, Some(pod)) => pod.id,
+            (LeaseAction::Started, Some(pod)) => {
+                // Start the stopped pod
+                self.report_progress(&spec.name, ProgressPhase::Starting, started, None);
+                self.start_pod(&pod.id).await?;
+                record_step(StepKind::Start, &pod.id);
+                pod.id
+            }
+            (LeaseAction::Updated, Some(pod)) => {
+                // Only mutable fields differ: update in place
+                self.report_progress(&spec.name, ProgressPhase::Starting, started, None);
+                self.precheck_image(&spec.image_name).await?;
+                self.update_pod(&pod.id, &decision.update.unwrap_or_default())
+                    .await?;
+                record_step(StepKind::Update, &pod.id);
+                if pod.desiredStatus.as_deref() == Some("EXITED") {
+                    self.start_pod(&pod.id).await?;
+                    record_step(StepKind::Start, &pod.id);
+                }
+                pod.id
+            }
+            (LeaseAction::Replaced, Some(pod)) => {
+                // Replace, keeping the network volume unless another one drifted in
+                let volume_drifted = decision
+                    .compatibility
+                    .is_some_and(|r| r.differs(CompatibilityCheck::Volume));
+                if volume_drifted
+                    && spec.network_volume_id.is_none()
+                    && let Ok(requested) = self.provision_config(&spec)
+                {
+                    spec.network_volume_id = requested.network_volume_id;
+                }
+                return self.replace_with_spec(&pod.id, spec, started).await;
+            }
+            _ => {
+                // Create new pod
+                return self.create_ready_pod(&spec, started).await;
+            }
+        };
+
+        // Step 2: Wait for readiness
+        let mut lease = self
+            .wait_for_ready(&pod_id, &spec.name, &spec.ports, started)
+            .await?;
+        lease.provenance = LeaseProvenance::existing(decision.action);
+        Ok(lease)
+    }
+
+    /// Decide what `ensure_ready_spec()` does with the pod named after `spec`,
+    /// without acting on it.
+    ///
+    /// With a persisted `state`, the recorded pod is looked up first and the
+    /// state machine plans what to do with a compatible pod.
+    async fn decide(
+        &self,
+        spec: &PodSpec,
+        state: Option<&mut RunPodState>,
+    ) -> Result<Decision, OrchestratorError> {
+        let (pod, adoption_lock) = self.find_pod_to_decide(spec, state.as_deref()).await?;
+        let Some(pod) = pod else {
+            let mut decision = Decision::new(None, LeaseAction::Created, false, String::new());
+            if let Some(state) = state {
+                let now = now_unix_ms();
+                state.set_target(TargetStatus::Running, now);
+                state.reconcile(RemoteObservation::NotFound, now);
+                // Only the budget overrides a running target when no pod exists.
+                if state.target == TargetStatus::Terminated {
+                    decision.blocked = Some(PlanReason::BudgetExceeded);
+                }
+            }
+            return Ok(decision);
+        };
+        let mode = &self.cfg.reconcile_mode;
+        let checks: &[CompatibilityCheck] = match mode {
+            ReconcileMode::Reuse | ReconcileMode::Adopt => &self.cfg.compatibility_checks,
+            ReconcileMode::Update => &CompatibilityCheck::ALL,
+            ReconcileMode::Recreate => &[],
+        };
+        if checks.is_empty() {
+            let reason = "replaced (reconcile mode recreate)".to_string();
+            let decision = Decision::new(Some(pod), LeaseAction::Replaced, false, reason);
+            return Ok(decision);
+        }
+
+        let report = self
+            .compatibility(&pod.id, pod.imageName.as_deref(), spec, checks)
+            .await?;
+        let terminated = pod.desiredStatus.as_deref() == Some("TERMINATED");
+        if *mode == ReconcileMode::Update && !report.is_compatible() && !terminated {
+            log_info!("pod {} drifted from its spec: {report}", pod.id);
+        }
+
+        let mut update = None;
+        let mut blocked = None;
+        let (action, accepted, reason) = if Self::is_compatible(&pod, Some(&report)) {
+            match state.map(|state| planned_reuse(state, &pod)) {
+                Some(Ok(planned)) => planned,
+                Some(Err(policy)) => {
+                    blocked = Some(policy);
+                    (
+                        LeaseAction::Replaced,
+                        false,
+                        format!("terminated by policy: {policy}"),
+                    )
+                }
+                None if pod.desiredStatus.as_deref() == Some("EXITED") => {
+                    (LeaseAction::Started, true, "compatible".to_string())
+                }
+                None => (LeaseAction::Reused, true, "compatible".to_string()),
+            }
+        } else if let Some(changes) = self.mutable_update(&pod, spec, &report) {
+            update = Some(changes);
+            let reason = format!("updatable in place: {report}");
+            (LeaseAction::Updated, true, reason)
+        } else if *mode == ReconcileMode::Update && !terminated {
+            if report.differs(CompatibilityCheck::Volume) && report.volume_growth_gb.is_none() {
+                return Err(OrchestratorError::VolumeDrift {
+                    pod_id: pod.id,
+                    report,
+                });
+            }
+            (
+                LeaseAction::Replaced,
+                false,
+                format!("drifted, replaced: {report}"),
+            )
+        } else {
+            let reason = format!("incompatible and not updatable in place: {report}");
+            (LeaseAction::Created, false, reason)
+        };
+
+        let adopted = pod.name.as_deref() != Some(spec.name.as_str())
+            && spec
+                .selector
+                .as_ref()
+                .is_none_or(|s| !s.matches(&pod.labels()));
+        let reason = match (adopted, &pod.name) {
+            (true, Some(name)) => format!("adopted from {name}: {reason}"),
+            (true, None) => format!("adopted: {reason}"),
+            (false, _) => reason,
+        };
+        let mut decision = Decision::new(Some(pod), action, accepted, reason);
+        decision.compatibility = Some(report);
+        decision.update = update;
+        decision.adopted = adopted && accepted;
+        decision.blocked = blocked;
+        decision.adoption_lock = adoption_lock;
+        Ok(decision)
+    }
+
+    /// Show what `ensure_ready_pod()` would do right now, without doing it.
+    ///
+    /// See `plan_with()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ports cannot be negotiated or pods cannot be read.
+    pub async fn plan(&self) -> Result<OrchestrationPlan, OrchestratorError> {
+        self.plan_with(&PodOverrides::default()).await
+    }
+
+    /// Show what `ensure_ready_pod_with()` would do right now, without doing
+    /// it: the existing pod it would examine, whether it would reuse, start,
+    /// update, replace or create a pod, and the steps in order.
+    ///
+    /// The plan is driven by live API data (only reads are made). It does not
+    /// foresee capacity failures, data center failover or readiness timeouts,
+    /// and a concurrent caller may change the pods before the plan is run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ports cannot be negotiated or pods cannot be read.
+    pub async fn plan_with(
+        &self,
+        overrides: &PodOverrides,
+    ) -> Result<OrchestrationPlan, OrchestratorError> {
+        let mut spec = PodSpec::resolve(&self.cfg, overrides);
+        spec.ports = negotiate_ports(&spec.ports, &self.cfg.port_alternates, self.cfg.exposure)
+            .map_err(OrchestratorError::PortConflict)?
+            .ports;
+        let mut state = self.load_state(&spec.name).await;
+        let mut decision = self.decide(&spec, state.as_mut()).await?;
+        if let Some(held) = decision.adoption_lock.take() {
+            held.release().await;
+        }
+
+        let existing_id = decision.existing.as_ref().map(|pod| pod.id.clone());
+        let exited = decision
+            .existing
+            .as_ref()
+            .is_some_and(|pod| pod.desiredStatus.as_deref() == Some("EXITED"));
+        let terminated = decision
+            .existing
+            .as_ref()
+            .is_some_and(|pod| pod.desiredStatus.as_deref() == Some("TERMINATED"));
+        let step = |kind, pod_id: &Option<String>| PlannedStep {
+            kind,
+            pod_id: pod_id.clone(),
+        };
+        let steps = match decision.action {
+            _ if decision.blocked.is_some() => existing_id
+                .iter()
+                .map(|id| step(StepKind::Terminate, &Some(id.clone())))
+                .collect(),
+            LeaseAction::Reused => vec![step(StepKind::WaitReady, &existing_id)],
+            LeaseAction::Started => vec![
+                step(StepKind::Start, &existing_id),
+                step(StepKind::WaitReady, &existing_id),
+            ],
+            LeaseAction::Updated => {
+                let mut steps = vec![step(StepKind::Update, &existing_id)];
+                if exited {
+                    steps.push(step(StepKind::Start, &existing_id));
+                }
+                steps.push(step(StepKind::WaitReady, &existing_id));
+                steps
+            }
+            LeaseAction::Replaced if !terminated => vec![
+                step(StepKind::Terminate, &existing_id),
+                step(StepKind::Create, &None),
+                step(StepKind::WaitReady, &None),
+            ],
+            _ => vec![
+                step(StepKind::Create, &None),
+                step(StepKind::WaitReady, &None),
+            ],
+        };
+
+        Ok(OrchestrationPlan {
+            pod_name: spec.name,
+            action: decision.action,
+            considered: decision.existing.map(|pod| ConsideredPod {
+                pod_id: pod.id,
+                status: pod.desiredStatus,
+                accepted: decision.accepted,
+                reason: decision.reason,
+            }),
+            compatibility: decision.compatibility,
+            update: decision.update,
+            steps,
+        })
+    }
+
+    /// Re-provision a pod without losing its persistent data.
+    ///
+    /// This method will:
+    /// 1. Read the old pod's network volume and mount path
+    /// 2. Terminate the old pod and wait until it is gone (the network volume
+    ///    outlives it)
+    /// 3. Create a new pod with the same name, volume and mount path
+    /// 4. Verify the volume is attached, then wait for readiness
+    ///
+    /// Pods without a network volume are simply recreated: their container
+    /// and pod volumes are lost, as with any termination.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the old pod is not found, termination or creation
+    /// fails, the volume is not attached to the new pod, or readiness times out.
+    pub async fn replace_pod(&self, pod_id: &str) -> Result<PodLease, OrchestratorError> {
+        let spec = PodSpec::resolve(&self.cfg, &PodOverrides::default());
+        let name = spec.name.clone();
+        self.with_pod_lock(
+            &name,
+            Box::pin(self.replace_with_spec(pod_id, spec, Instant::now())),
+        )
+        .await
+    }
+
+    /// Switch the configured pod to `new_image` with minimal downtime, with
+    /// the default `ImageUpdateOptions` (see `update_image_with()`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no pod to update is found, or the replacement
+    /// fails to create or become ready (the old pod is then kept).
+    pub async fn update_image(&self, new_image: &str) -> Result<PodLease, OrchestratorError> {
+        self.update_image_with(new_image, ImageUpdateOptions::default())
+            .await
+    }
+
+    /// Switch the configured pod to `new_image` with minimal downtime.
+    ///
+    /// Unlike `replace_pod()`, the old pod keeps serving while its
+    /// replacement starts. This method will:
+    /// 1. Find the current pod (recorded in the state store, matching
+    ///    `pod_selector`, or named `pod_name`)
+    /// 2. Create a replacement with `new_image` and the old pod's name,
+    ///    labels, network volume and mount path
+    /// 3. Wait for the replacement to be ready
+    /// 4. Terminate the old pod (or stop it, with `options.stop_old`)
+    ///
+    /// If step 2 or 3 fails, the replacement is terminated, and so is the
+    /// old pod unless `options.keep_old_on_failure`. A failure to retire
+    /// the old pod once the replacement is ready is logged, not returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PodNotFound` if there is no pod to update, or the error that
+    /// made the replacement fail.
+    pub async fn update_image_with(
+        &self,
+        new_image: &str,
+        options: ImageUpdateOptions,
+    ) -> Result<PodLease, OrchestratorError> {
+        let overrides = PodOverrides {
+            image_name: Some(new_image.to_string()),
+            ..PodOverrides::default()
+        };
+        let spec = PodSpec::resolve(&self.cfg, &overrides);
+        let name = spec.name.clone();
+        let update = self.roll_out_spec(spec, options, Instant::now());
+        self.with_pod_lock(&name, Box::pin(update)).await
+    }
+
+    /// Export the metadata of a pod, for recreating it later with
+    /// `recreate_from_snapshot()` (e.g. after losing it or its data center).
+    ///
+    /// The snapshot serializes to JSON; env values are not included (see
+    /// `PodSnapshot`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pod is not found or the API request fails.
+    pub async fn export_pod(&self, pod_id: &str) -> Result<PodSnapshot, OrchestratorError> {
+        let pod = self
+            .get_pod(pod_id)
+            .await?
+            .ok_or_else(|| OrchestratorError::PodNotFound(pod_id.to_string()))?;
+        Ok(PodSnapshot::from_details(&pod))
+    }
+
+    /// Create a new pod from a snapshot taken by `export_pod()`, and wait for
+    /// it to be ready.
+    ///
+    /// The pod gets the snapshot's name, image, GPU, disks, ports, start
+    /// command and env keys (values resolved as described on `PodSnapshot`).
+    /// With a network volume, the pod is pinned to the snapshot's data center
+    /// (where the volume lives) and the volume is verified attached; without
+    /// one, the snapshot's data center is used, or `data_center_failover`
+    /// if configured.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a pod with the snapshot's name still exists, an env
+    /// key has no value, creation fails, the volume is not attached, or
+    /// readiness times out.
+    pub async fn recreate_from_snapshot(
+        &self,
+        snapshot: &PodSnapshot,
+    ) -> Result<PodLease, OrchestratorError> {
+        self.with_pod_lock(
+            &snapshot.name,
+            Box::pin(self.recreate_spec(snapshot, Instant::now())),
+        )
+        .await
+    }
+
+    async fn recreate_spec(
+        &self,
+        snapshot: &PodSnapshot,
+        started: Instant,
+    ) -> Result<PodLease, OrchestratorError> {
+        let mut spec = PodSpec::from_snapshot(snapshot);
+        if spec.gpu_type_ids.is_empty() {
+            spec.gpu_type_ids.clone_from(&self.cfg.gpu_type_ids);
+        }
+
+        self.report_progress(&spec.name, ProgressPhase::Discovering, started, None);
+        if let Some(pod) = self
+            .find_pod_by_name(&spec.name)
+            .await?
+            .filter(|p| p.desiredStatus.as_deref() != Some("TERMINATED"))
+        {
+            return Err(OrchestratorError::PodExists {
+                name: spec.name,
+                pod_id: pod.id,
+            });
+        }
+
+        let Some(volume_id) = spec.network_volume_id.clone() else {
+            if !self.cfg.data_center_failover.is_empty() {
+                spec.data_center_id = None;
+            }
+            let mut lease = self.create_ready_pod(&spec, started).await?;
+            lease.provenance.action = LeaseAction::Restored;
+            return Ok(lease);
+        };
+
+        self.report_progress(&spec.name, ProgressPhase::Creating, started, None);
+        let created = self.create_new_pod(&spec).await?;
+        self.verify_volume_attached(&created.id, &volume_id).await?;
+
+        let mut lease = self
+            .wait_for_ready(&created.id, &spec.name, &spec.ports, started)
+            .await?;
+        lease.provenance = LeaseProvenance {
+            data_center_id: spec.data_center_id,
+            ..LeaseProvenance::created(LeaseAction::Restored, created)
+        };
+        Ok(lease)
+    }
+
+    /// List all pods for the current user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API returns an error.
+    pub async fn list_pods(&self) -> Result<Vec<PodInfo>, OrchestratorError> {
+        let mut pods = Vec::new();
+        self.for_each_pod(|pod| pods.push(pod)).await?;
+        Ok(pods)
+    }
+
+    /// Visit every pod of the current user, one at a time.
+    ///
+    /// Unlike `list_pods()`, the listing is never held in memory: each pod is
+    /// deserialized, handed to `f` and dropped. Prefer this on accounts with
+    /// many pods when only a few are of interest.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API returns an error.
+    pub async fn for_each_pod(&self, mut f: impl FnMut(PodInfo)) -> Result<(), OrchestratorError> {
+        if let Some(sim) = &self.sim {
+            sim.list_pods().into_iter().for_each(f);
+            return Ok(());
+        }
+
+        let count = self.for_each_listed(|pod: PodInfo| f(pod)).await?;
+
+        #[allow(clippy::cast_precision_loss)]
+        self.metrics
+            .gauge(runpod_metrics::PODS_LISTED, count as f64, &[]);
+
+        Ok(())
+    }
+
+    /// Visit every pod of the listing, deserialized as `T` (`PodInfo` or the
+    /// full `PodDetails`), and return how many were visited.
+    async fn for_each_listed<T: DeserializeOwned>(
+        &self,
+        mut f: impl FnMut(T),
+    ) -> Result<usize, OrchestratorError> {
+        let mut count: usize = 0;
+        self.fetch_pods(&PodFilter::default(), |pod| {
+            count += 1;
+            f(pod);
+        })
+        .await?;
+        Ok(count)
+    }
+
+    /// Stream the pods passing `filter`.
+    ///
+    /// The listing is requested when the stream is first polled (with the
+    /// status and image filters applied server-side). An error ends the
+    /// stream.
+    ///
+    /// ```ignore
+    /// let filter = PodFilter {
+    ///     name_contains: Some("worker".to_string()),
+    ///     desired_status: Some("RUNNING".to_string()),
+    ///     ..PodFilter::default()
+    /// };
+    /// let mut pods = std::pin::pin!(orchestrator.pods_stream(filter));
+    /// while let Some(pod) = pods.next().await {
+    ///     println!("{}", pod?.id);
+    /// }
+    /// ```
+    pub fn pods_stream(
+        &self,
+        filter: PodFilter,
+    ) -> impl Stream<Item = Result<PodInfo, OrchestratorError>> + '_ {
+        stream::once(async move {
+            let mut pods = Vec::new();
+            let fetched = if let Some(sim) = &self.sim {
+                pods = sim.list_pods();
+                Ok(())
+            } else {
+                self.fetch_pods(&filter, |pod| pods.push(pod)).await
+            };
+            let listed: Vec<_> = match fetched {
+                Ok(()) => pods
+                    .into_iter()
+                    .filter(|pod| filter.matches(pod))
+                    .map(Ok)
+                    .collect(),
+                Err(e) => vec![Err(e)],
+            };
+            stream::iter(listed)
+        })
+        .flatten()
+    }
+
+    /// Fetch the pod listing (`GET /pods`, a JSON array), handing each pod
+    /// to `f` as it is deserialized.
+    async fn fetch_pods<T: DeserializeOwned>(
+        &self,
+        filter: &PodFilter,
+        f: impl FnMut(T),
+    ) -> Result<(), OrchestratorError> {
+        let base = format!("{}/pods", self.cfg.rest_url.trim_end_matches('/'));
+        let mut url = reqwest::Url::parse(&base).map_err(|_| OrchestratorError::InvalidEnv {
+            key: "RUNPOD_REST_URL",
+            reason: "not a valid URL",
+        })?;
+        {
+            let mut query = url.query_pairs_mut();
+            if let Some(status) = &filter.desired_status {
+                query.append_pair("desiredStatus", status);
+            }
+            if let Some(image) = &filter.image_name {
+                query.append_pair("imageName", image);
+            }
+        }
+        if url.query() == Some("") {
+            url.set_query(None);
+        }
+
+        let req = self.http.get(url).bearer_auth(self.cfg.api_key.expose());
+        let resp = self.send(req).await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(OrchestratorError::Api { status, body });
+        }
+
+        let body = resp.bytes().await.map_err(OrchestratorError::Http)?;
+        self.cfg
+            .api_version
+            .decode_each(ApiResource::PodList, body, f)
+            .map_err(OrchestratorError::Json)
+    }
+
+    /// Stop a running pod (puts it in EXITED state, can be restarted later).
+    ///
+    /// Use this to pause billing while keeping the pod configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API returns an error.
+    pub async fn stop_pod(&self, pod_id: &str) -> Result<(), OrchestratorError> {
+        self.run_pre_stop_hook(pod_id).await;
+        if let Some(sim) = &self.sim {
+            return sim
+                .stop_pod(pod_id)
+                .then_some(())
+                .ok_or_else(|| OrchestratorError::PodNotFound(pod_id.to_string()));
+        }
+
+        let url = format!(
+            "{}/pods/{}/stop",
+            self.cfg.rest_url.trim_end_matches('/'),
+            pod_id
+        );
+
+        let req = self.http.post(&url).bearer_auth(self.cfg.api_key.expose());
+        let resp = self.send(req).await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(OrchestratorError::Api { status, body });
+        }
+
+        Ok(())
+    }
+
+    /// Update a pod in place (REST `PATCH /pods/{podId}`).
+    ///
+    /// Only the fields set in `changes` are sent. `RunPod` restarts the
+    /// container to apply them; the pod ID and storage are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API returns an error.
+    pub async fn update_pod(
+        &self,
+        pod_id: &str,
+        changes: &PodUpdate,
+    ) -> Result<PodDetails, OrchestratorError> {
+        if let Some(sim) = &self.sim {
+            return sim
+                .update_pod(pod_id, changes)
+                .ok_or_else(|| OrchestratorError::PodNotFound(pod_id.to_string()));
+        }
+
+        let url = format!(
+            "{}/pods/{}",
+            self.cfg.rest_url.trim_end_matches('/'),
+            pod_id
+        );
+
+        let payload = self
+            .cfg
+            .api_version
+            .encode(ApiResource::Pod, changes)
+            .map_err(OrchestratorError::Json)?;
+        let req = self
+            .http
+            .patch(&url)
+            .bearer_auth(self.cfg.api_key.expose())
+            .json(&payload);
+        let resp = self.send(req).await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(OrchestratorError::Api { status, body });
+        }
+
+        let body = resp.bytes().await.map_err(OrchestratorError::Http)?;
+        self.cfg
+            .api_version
+            .decode_slice(ApiResource::Pod, body)
+            .map_err(OrchestratorError::Json)
     }
 
     /// Stop the pod by name (uses the configured pod name).
     ///
-    /// Convenience method that finds the pod by name and stops it.
+    /// Convenience method that finds the pod by name and stops it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pod is not found or the stop operation fails.
+    pub async fn stop_current_pod(&self) -> Result<(), OrchestratorError> {
+        let pod = self
+            .find_pod_by_name(&self.cfg.pod_name)
+            .await?
+            .ok_or_else(|| OrchestratorError::PodNotFound(self.cfg.pod_name.clone()))?;
+
+        self.stop_pod(&pod.id).await
+    }
+
+    /// Start the pod by name (uses the configured pod name).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pod is not found or the start operation fails.
+    pub async fn start_current_pod(&self) -> Result<(), OrchestratorError> {
+        let pod = self
+            .find_pod_by_name(&self.cfg.pod_name)
+            .await?
+            .ok_or_else(|| OrchestratorError::PodNotFound(self.cfg.pod_name.clone()))?;
+
+        self.start_pod(&pod.id).await
+    }
+
+    /// Restart a pod's container (REST `POST /pods/{podId}/restart`) and
+    /// wait until it is ready again.
+    ///
+    /// The pod keeps its ID, GPU and disks; the pre-stop hook runs first.
+    /// A stopped pod is started instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pod is not found, the restart fails, or the
+    /// pod is not ready within `ready_timeout_ms`.
+    pub async fn restart_pod(&self, pod_id: &str) -> Result<PodLease, OrchestratorError> {
+        let pod = self
+            .get_pod(pod_id)
+            .await?
+            .ok_or_else(|| OrchestratorError::PodNotFound(pod_id.to_string()))?;
+        let name = pod.name.clone().unwrap_or_default();
+        let started = Instant::now();
+
+        self.report_progress(&name, ProgressPhase::Starting, started, None);
+        if pod.desiredStatus.as_deref() == Some("EXITED") {
+            self.start_pod(pod_id).await?;
+        } else {
+            self.run_pre_stop_hook(pod_id).await;
+            self.restart_container(pod_id).await?;
+        }
+        log_info!("pod {name} ({pod_id}) restarted");
+
+        let required_ports = self.cfg.required_ports.clone();
+        let lease = self
+            .wait_for_ready(pod_id, &name, &required_ports, started)
+            .await?;
+        Ok(self.grant_lease(lease).await)
+    }
+
+    /// Restart the pod by name (uses the configured pod name).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pod is not found or the restart fails.
+    pub async fn restart_current_pod(&self) -> Result<PodLease, OrchestratorError> {
+        let pod = self
+            .find_pod_by_name(&self.cfg.pod_name)
+            .await?
+            .ok_or_else(|| OrchestratorError::PodNotFound(self.cfg.pod_name.clone()))?;
+
+        self.restart_pod(&pod.id).await
+    }
+
+    /// Restart a running pod's container.
+    async fn restart_container(&self, pod_id: &str) -> Result<(), OrchestratorError> {
+        if let Some(sim) = &self.sim {
+            return (sim.stop_pod(pod_id) && sim.start_pod(pod_id))
+                .then_some(())
+                .ok_or_else(|| OrchestratorError::PodNotFound(pod_id.to_string()));
+        }
+
+        let url = format!(
+            "{}/pods/{}/restart",
+            self.cfg.rest_url.trim_end_matches('/'),
+            pod_id
+        );
+
+        let req = self.http.post(&url).bearer_auth(self.cfg.api_key.expose());
+        let resp = self.send(req).await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(OrchestratorError::Api { status, body });
+        }
+
+        Ok(())
+    }
+
+    /// Terminate a pod completely (removes it from `RunPod`).
+    ///
+    /// Use this when you no longer need the pod. The pod cannot be restarted.
+    /// Idempotent: a pod that is already gone yields `AlreadyGone`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API returns an error.
+    pub async fn terminate(&self, pod_id: &str) -> Result<TerminateOutcome, OrchestratorError> {
+        self.run_pre_stop_hook(pod_id).await;
+        let outcome = self.terminate_pod(pod_id).await?;
+        log_info!("pod {pod_id} terminate: {outcome:?}");
+        if outcome.was_terminated() {
+            record_step(StepKind::Terminate, pod_id);
+        }
+        self.forget_state(pod_id).await;
+        Ok(outcome)
+    }
+
+    /// Terminate a pod and wait until `RunPod` reports it gone (not found or
+    /// `TERMINATED`), so it no longer bills or holds its name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `TerminationTimeout` if the pod still exists after `timeout`,
+    /// or an error if a request fails.
+    pub async fn terminate_and_wait(
+        &self,
+        pod_id: &str,
+        timeout: Duration,
+    ) -> Result<TerminateOutcome, OrchestratorError> {
+        let outcome = self.terminate(pod_id).await?;
+        let start = Instant::now();
+        let mut poll = AdaptivePoll::new(&self.cfg);
+
+        loop {
+            let gone = self
+                .get_pod(pod_id)
+                .await?
+                .is_none_or(|pod| pod.desiredStatus.as_deref() == Some("TERMINATED"));
+            if gone {
+                return Ok(outcome);
+            }
+            if start.elapsed() > timeout {
+                return Err(OrchestratorError::TerminationTimeout(pod_id.to_string()));
+            }
+            poll.wait("terminating").await;
+        }
+    }
+
+    /// Terminate the pod by name (uses the configured pod name).
+    ///
+    /// Idempotent: if no pod has that name (or it is already terminated),
+    /// returns `AlreadyGone`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the terminate operation fails.
+    pub async fn terminate_current_pod(&self) -> Result<TerminateOutcome, OrchestratorError> {
+        match self.find_pod_by_name(&self.cfg.pod_name).await? {
+            Some(pod) if pod.desiredStatus.as_deref() != Some("TERMINATED") => {
+                self.terminate(&pod.id).await
+            }
+            _ => Ok(TerminateOutcome::AlreadyGone),
+        }
+    }
+
+    /// Execute the plans of `FleetState::reconcile()`, at most
+    /// `fleet_concurrency` at once.
+    ///
+    /// Each pod is handled independently: a failing action does not abort the
+    /// others. `Noop` plans are skipped. Creations go through
+    /// `ensure_ready_pod_with()` (with the plan's pod name), so they finish
+    /// once the pod is ready. Spot pods are resumed at their existing bid.
+    ///
+    /// Feed the result back with `FleetApplyResult::record()` before saving
+    /// the fleet.
+    pub async fn apply_fleet(&self, plans: &[FleetPlan]) -> FleetApplyResult {
+        let limit = usize::try_from(self.cfg.fleet_concurrency.max(1)).unwrap_or(1);
+        let started = Instant::now();
+
+        let results = stream::iter(
+            plans
+                .iter()
+                .filter(|p| p.plan.action != PlannedAction::Noop),
+        )
+        .map(|plan| async move {
+            FleetActionResult {
+                group: plan.group.clone(),
+                pod_name: plan.pod_name.clone(),
+                action: plan.plan.action.clone(),
+                outcome: self.apply_fleet_action(plan).await,
+            }
+        })
+        .buffered(limit)
+        .collect()
+        .await;
+
+        FleetApplyResult {
+            results,
+            duration_ms: millis_since(started),
+        }
+    }
+
+    async fn apply_fleet_action(
+        &self,
+        plan: &FleetPlan,
+    ) -> Result<ActionOutcome, OrchestratorError> {
+        match &plan.plan.action {
+            PlannedAction::Noop => Ok(ActionOutcome::Applied),
+            PlannedAction::CreatePod { name } => {
+                let overrides = PodOverrides {
+                    name: Some(name.clone()),
+                    ..PodOverrides::default()
+                };
+                let lease = self.ensure_ready_pod_with(&overrides).await?;
+                Ok(ActionOutcome::Created(PodId::new(lease.id)))
+            }
+            PlannedAction::StartPod { id } | PlannedAction::ResumePod { id, .. } => {
+                self.start_pod(id.as_str()).await?;
+                Ok(ActionOutcome::Applied)
+            }
+            PlannedAction::StopPod { id } => {
+                self.stop_pod(id.as_str()).await?;
+                Ok(ActionOutcome::Applied)
+            }
+            PlannedAction::TerminatePod { id } => {
+                self.terminate(id.as_str()).await?;
+                Ok(ActionOutcome::Terminated)
+            }
+        }
+    }
+
+    /// Shrink a fleet group to `size` pods and stop or terminate the victims.
+    ///
+    /// Victims are chosen by `FleetState::scale_down()` (idle pods first)
+    /// and disposed of per the group's `scale_down` action. `usage` carries
+    /// what only the caller knows (leases, last use); GPU utilization is
+    /// fetched for every created pod that has none.
+    ///
+    /// Record the result with `FleetApplyResult::record()` before saving the
+    /// fleet.
+    pub async fn scale_down(
+        &self,
+        fleet: &mut FleetState,
+        group: &str,
+        size: usize,
+        mut usage: HashMap<String, PodUsage>,
+    ) -> FleetApplyResult {
+        let pods: Vec<(String, PodId)> = fleet
+            .group(group)
+            .filter(|g| g.pods.len() > size)
+            .map(|g| {
+                g.pods
+                    .iter()
+                    .filter_map(|p| p.pod_id.clone().map(|id| (p.pod_name.clone(), id)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        for (pod_name, id) in pods {
+            let entry = usage.entry(pod_name).or_default();
+            if entry.gpu_util_percent.is_none() {
+                // No signal is not fatal: the pod is ranked as idle.
+                entry.gpu_util_percent = self.gpu_utilization(id.as_str()).await.ok().flatten();
+            }
+        }
+
+        let plans = fleet.scale_down(group, size, &usage, now_unix_ms());
+        for plan in &plans {
+            log_info!(
+                "scaling down {}/{}: {:?}",
+                plan.group,
+                plan.pod_name,
+                plan.plan.action
+            );
+        }
+        self.apply_fleet(&plans).await
+    }
+
+    /// Average GPU utilization (0-100) of a running pod.
+    ///
+    /// Returns `None` when the pod is not running, reports no GPU telemetry,
+    /// or in simulation mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pod query fails.
+    pub async fn gpu_utilization(&self, pod_id: &str) -> Result<Option<f32>, OrchestratorError> {
+        if self.sim.is_some() {
+            return Ok(None);
+        }
+
+        let pod = self
+            .graphql_client()
+            .get_pod(pod_id)
+            .await
+            .map_err(|e| OrchestratorError::Provision(Box::new(e)))?;
+        let samples: Vec<f32> = pod
+            .and_then(|p| p.runtime)
+            .and_then(|r| r.gpus)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|g| g.gpuUtilPercent)
+            .collect();
+        if samples.is_empty() {
+            return Ok(None);
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let count = samples.len() as f32;
+        Ok(Some(samples.iter().sum::<f32>() / count))
+    }
+
+    /// Create an Instant Cluster and wait until every node is ready.
+    ///
+    /// This method will:
+    /// 1. Create the cluster (one pod per node on a shared private network)
+    /// 2. Wait for each node like `ensure_ready_pod()` (publicIp + ports)
+    /// 3. Return a `ClusterLease` with each node's lease and private address
+    ///
+    /// If a node never becomes ready, the whole cluster is deleted before
+    /// returning the error, so no partial cluster keeps billing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the spec is empty, creation is declined or fails,
+    /// or a node does not become ready.
+    pub async fn create_cluster(
+        &self,
+        spec: &ClusterSpec,
+    ) -> Result<ClusterLease, OrchestratorError> {
+        if spec.node_count == 0 || spec.gpus_per_node == 0 {
+            return Err(OrchestratorError::Provision(
+                "a cluster needs at least one node and one GPU per node".into(),
+            ));
+        }
+
+        let started = Instant::now();
+        self.report_progress(&spec.name, ProgressPhase::Creating, started, None);
+        self.precheck_image(&spec.image_name).await?;
+        let cluster = self.create_cluster_nodes(spec).await?;
+
+        let waits = cluster.pods.iter().enumerate().map(|(i, pod)| {
+            let index = pod
+                .clusterIdx
+                .unwrap_or_else(|| u32::try_from(i).unwrap_or(u32::MAX));
+            async move {
+                let node_name = format!("{}-{index}", spec.name);
+                let mut lease = self
+                    .wait_for_ready(&pod.id, &node_name, &spec.ports, started)
+                    .await?;
+                lease.provenance = LeaseProvenance {
+                    action: LeaseAction::Created,
+                    ..LeaseProvenance::default()
+                };
+                Ok::<_, OrchestratorError>(ClusterNode {
+                    index,
+                    cluster_ip: pod.clusterIp.clone(),
+                    lease,
+                })
+            }
+        });
+
+        match futures_util::future::try_join_all(waits).await {
+            Ok(nodes) => Ok(ClusterLease {
+                id: cluster.id,
+                name: cluster.name.unwrap_or_else(|| spec.name.clone()),
+                gpus_per_node: spec.gpus_per_node,
+                nodes,
+            }),
+            Err(e) => {
+                let _ = self.delete_cluster(&cluster).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Terminate an Instant Cluster and all of its nodes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the API returns an error.
+    pub async fn terminate_cluster(&self, cluster: &ClusterLease) -> Result<(), OrchestratorError> {
+        self.delete_cluster(&ClusterInfo {
+            id: cluster.id.clone(),
+            name: Some(cluster.name.clone()),
+            pods: cluster
+                .nodes
+                .iter()
+                .map(|n| ClusterPod {
+                    id: n.lease.id.clone(),
+                    clusterIp: n.cluster_ip.clone(),
+                    clusterIdx: Some(n.index),
+                })
+                .collect(),
+        })
+        .await
+    }
+
+    /// Get a network volume.
+    ///
+    /// Returns `None` if the volume does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API returns an error.
+    pub async fn get_volume(
+        &self,
+        volume_id: &str,
+    ) -> Result<Option<NetworkVolume>, OrchestratorError> {
+        Self::volumes_supported(self.sim.as_ref())?;
+
+        let url = format!(
+            "{}/networkvolumes/{}",
+            self.cfg.rest_url.trim_end_matches('/'),
+            volume_id
+        );
+
+        let req = self.http.get(&url).bearer_auth(self.cfg.api_key.expose());
+        let resp = self.send(req).await?;
+
+        let status = resp.status();
+        if status.as_u16() == 404 {
+            return Ok(None);
+        }
+
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(OrchestratorError::Api { status, body });
+        }
+
+        let body = resp.bytes().await.map_err(OrchestratorError::Http)?;
+        self.cfg
+            .api_version
+            .decode_slice(ApiResource::NetworkVolume, body)
+            .map(Some)
+            .map_err(OrchestratorError::Json)
+    }
+
+    /// Create an empty network volume.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API returns an error.
+    pub async fn create_volume(
+        &self,
+        name: &str,
+        size_gb: u32,
+        data_center_id: &str,
+    ) -> Result<NetworkVolume, OrchestratorError> {
+        Self::volumes_supported(self.sim.as_ref())?;
+
+        let url = format!("{}/networkvolumes", self.cfg.rest_url.trim_end_matches('/'));
+        let request = self.cfg.api_version.map_request(
+            ApiResource::NetworkVolume,
+            serde_json::json!({
+                "name": name,
+                "size": size_gb,
+                "dataCenterId": data_center_id,
+            }),
+        );
+
+        let req = self
+            .http
+            .post(&url)
+            .bearer_auth(self.cfg.api_key.expose())
+            .json(&request);
+        let resp = self.send(req).await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(OrchestratorError::Api { status, body });
+        }
+
+        let body = resp.bytes().await.map_err(OrchestratorError::Http)?;
+        self.cfg
+            .api_version
+            .decode_slice(ApiResource::NetworkVolume, body)
+            .map_err(OrchestratorError::Json)
+    }
+
+    /// Fork a network volume.
+    ///
+    /// Creates `name` in the source's data center with the source's size,
+    /// then copies the source into it with `copy_volume()`. The new volume
+    /// is deleted again if the copy fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the source does not exist, the new volume cannot
+    /// be created, or the copy fails.
+    #[cfg(feature = "ssh")]
+    pub async fn clone_volume(
+        &self,
+        source_id: &str,
+        name: &str,
+    ) -> Result<VolumeCopy, OrchestratorError> {
+        let source = self
+            .get_volume(source_id)
+            .await?
+            .ok_or_else(|| OrchestratorError::VolumeNotFound(source_id.to_string()))?;
+        let data_center_id = source.dataCenterId.as_deref().unwrap_or_default();
+
+        let dest = self
+            .create_volume(name, source.size, data_center_id)
+            .await?;
+
+        match self.copy_volume(&source.id, &dest.id).await {
+            Ok(copy) => Ok(copy),
+            Err(e) => {
+                let _ = self.delete_volume(&dest.id).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Copy the contents of one network volume into another.
+    ///
+    /// `RunPod` has no native copy and a pod mounts a single network volume,
+    /// so the copy goes through two temporary CPU utility pods
+    /// (`volume_copy_image`) on the global private network: one mounts the
+    /// source and serves a tar archive of it on that network only, the other
+    /// mounts the destination and, told over SSH (`with_ssh_login()` or
+    /// `SshLogin::from_env()`), fetches and extracts it. Only the
+    /// destination's SSH port is public, and it accepts the account's SSH
+    /// keys only. Both pods are billed while the copy runs (confirmation hook
+    /// applies) and are terminated afterwards, whether the copy succeeded or not.
+    ///
+    /// Existing files in the destination are overwritten; others are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a utility pod cannot be created or become ready,
+    /// the copy fails, or it exceeds `volume_copy_timeout_ms`.
+    #[cfg(feature = "ssh")]
+    pub async fn copy_volume(
+        &self,
+        source_id: &str,
+        dest_id: &str,
+    ) -> Result<VolumeCopy, OrchestratorError> {
+        Self::volumes_supported(self.sim.as_ref())?;
+
+        let started = Instant::now();
+        let mut utility_pods = Vec::new();
+        let result = self
+            .run_volume_copy(source_id, dest_id, started, &mut utility_pods)
+            .await;
+
+        for pod_id in &utility_pods {
+            let _ = self.terminate_pod(pod_id).await;
+        }
+
+        result.map(|()| VolumeCopy {
+            source_id: source_id.to_string(),
+            dest_id: dest_id.to_string(),
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// Find a pod by name.
+    async fn find_pod_by_name(&self, name: &str) -> Result<Option<PodInfo>, OrchestratorError> {
+        let mut found = None;
+        self.for_each_pod(|pod| {
+            if found.is_none() && pod.name.as_deref() == Some(name) {
+                found = Some(pod);
+            }
+        })
+        .await?;
+        Ok(found)
+    }
+
+    /// Find the pod matching `selector`: a running pod first, then a stopped
+    /// one (terminated pods are ignored).
+    async fn find_pod_by_selector(
+        &self,
+        selector: &LabelSelector,
+    ) -> Result<Option<PodInfo>, OrchestratorError> {
+        let mut found: Option<PodInfo> = None;
+        self.for_each_pod(|pod| {
+            let status = pod.desiredStatus.as_deref();
+            if status == Some("TERMINATED") || !selector.matches(&pod.labels()) {
+                return;
+            }
+            let running = |p: &PodInfo| p.desiredStatus.as_deref() == Some("RUNNING");
+            if found.as_ref().is_none_or(|f| !running(f) && running(&pod)) {
+                found = Some(pod);
+            }
+        })
+        .await?;
+        Ok(found)
+    }
+
+    /// Pod a spec refers to: the one recorded in `state`, else the one
+    /// matching its selector, else the one with its name.
+    async fn find_current_pod(
+        &self,
+        spec: &PodSpec,
+        state: Option<&RunPodState>,
+    ) -> Result<Option<PodInfo>, OrchestratorError> {
+        if let Some(id) = state.and_then(RunPodState::pod_id)
+            && let Some(pod) = self.find_pod_by_id(id.as_str()).await?
+        {
+            return Ok(Some(pod));
+        }
+        match &spec.selector {
+            Some(selector) => self.find_pod_by_selector(selector).await,
+            None => self.find_pod_by_name(&spec.name).await,
+        }
+    }
+
+    /// `find_current_pod()`, falling back in adopt mode to
+    /// `find_adoptable_pod()` (with the adopted pod's lock).
+    async fn find_pod_to_decide(
+        &self,
+        spec: &PodSpec,
+        state: Option<&RunPodState>,
+    ) -> Result<(Option<PodInfo>, Option<HeldPodLock>), OrchestratorError> {
+        let pod = self.find_current_pod(spec, state).await?;
+        if pod.is_none()
+            && self.cfg.reconcile_mode == ReconcileMode::Adopt
+            && spec.name == self.cfg.pod_name
+        {
+            let adoptable = self.find_adoptable_pod(spec).await?;
+            return Ok(adoptable.map_or((None, None), |(found, held)| (Some(found), held)));
+        }
+        Ok((pod, None))
+    }
+
+    /// First pod matching `adopt_selector` and compatible with `spec` on
+    /// `compatibility_checks`, whatever its name (running pods first).
+    ///
+    /// Terminated pods and pods another client holds (pod lock or unexpired
+    /// lease) are skipped. Pods are compared on the listing data, without
+    /// reading each one.
+    ///
+    /// With a lock installed, the pod lock of the chosen pod is taken and
+    /// returned: the caller holds it until the adoption is recorded, so two
+    /// clients never adopt the same pod. A pod whose lock cannot be taken is
+    /// skipped.
+    async fn find_adoptable_pod(
+        &self,
+        spec: &PodSpec,
+    ) -> Result<Option<(PodInfo, Option<HeldPodLock>)>, OrchestratorError> {
+        let Some(selector) = &self.cfg.adopt_selector else {
+            return Ok(None);
+        };
+        let mut candidates = Vec::new();
+        let mut keep = |pod: PodDetails| {
+            let labels = pod.env.as_ref().map(runpod_labels::labels_from_env);
+            if pod.desiredStatus.as_deref() != Some("TERMINATED")
+                && selector.matches(&labels.unwrap_or_default())
+            {
+                candidates.push(pod);
+            }
+        };
+        match &self.sim {
+            Some(sim) => sim.list_pod_details().into_iter().for_each(&mut keep),
+            None => {
+                self.for_each_listed(&mut keep).await?;
+            }
+        }
+        candidates.sort_by_key(|pod| pod.desiredStatus.as_deref() != Some("RUNNING"));
+
+        let leased: Vec<String> = match &self.leases {
+            Some(registry) => runpod_ttl::run_blocking(registry, |r| r.active(now_unix_ms()))
+                .await
+                .map_err(OrchestratorError::State)?
+                .into_iter()
+                .map(|lease| lease.pod_id)
+                .collect(),
+            None => Vec::new(),
+        };
+        let checks = &self.cfg.compatibility_checks;
+        for pod in candidates {
+            if leased.contains(&pod.id) {
+                log_debug!("pod {} not adopted: leased", pod.id);
+                continue;
+            }
+            let image_name = pod.imageName.as_deref();
+            if !self
+                .compare_pod(&pod.id, image_name, Some(&pod), spec, checks)
+                .is_compatible()
+            {
+                continue;
+            }
+            let held = if let (Some(lock), Some(name)) = (&self.lock, pod.name.as_deref()) {
+                let Some(taken) = self.try_hold_pod_lock(lock, name).await? else {
+                    log_debug!("pod {} not adopted: locked", pod.id);
+                    continue;
+                };
+                Some(taken)
+            } else {
+                None
+            };
+            log_info!(
+                "adopting pod {} ({})",
+                pod.id,
+                pod.name.as_deref().unwrap_or("-")
+            );
+            return Ok(Some((pod.into(), held)));
+        }
+        Ok(None)
+    }
+
+    /// Take the pod lock of `name` (see `with_pod_lock()`) without waiting:
+    /// `None` if another client holds it.
+    async fn try_hold_pod_lock(
+        &self,
+        lock: &Arc<dyn DistributedLock>,
+        name: &str,
+    ) -> Result<Option<HeldPodLock>, OrchestratorError> {
+        let key = format!("pod-{name}");
+        let owner = runpod_lock::acquisition_token();
+        let ttl = Duration::from_millis(self.cfg.lock_ttl_ms);
+        let taken = Self::try_acquire_lock(lock, &key, &owner, ttl)
+            .await
+            .map_err(OrchestratorError::Lock)?;
+        Ok(taken.then(|| HeldPodLock {
+            lock: Arc::clone(lock),
+            key,
+            owner,
+        }))
+    }
+
+    /// Find a pod by ID (`None` if it no longer exists).
+    async fn find_pod_by_id(&self, pod_id: &str) -> Result<Option<PodInfo>, OrchestratorError> {
+        Ok(self.get_pod(pod_id).await?.map(PodInfo::from))
+    }
+
+    /// Persisted state of pod `name` (with a state store), or a fresh one
+    /// for the configured pod when nothing is stored yet.
+    ///
+    /// A store failure is logged: the pod is then found by name only.
+    async fn load_state(&self, name: &str) -> Option<RunPodState> {
+        let store = self.state.as_ref()?;
+        match runpod_state::run_blocking(store, |s| s.load()).await {
+            Ok(Some(state)) => (state.pod_name == name).then_some(state),
+            Ok(None) => (name == self.cfg.pod_name).then(|| RunPodState::new(name, now_unix_ms())),
+            Err(e) => {
+                log_warn!("could not load pod state: {e}", e = ErrorChain(&e));
+                None
+            }
+        }
+    }
+
+    /// Record the ready pod of `lease` in `state` and save it.
+    ///
+    /// A store failure (or a concurrent save) is logged, not returned: the
+    /// pod is usable, it is just found by name on the next run.
+    async fn save_state(&self, state: Option<RunPodState>, lease: &PodLease) {
+        let (Some(store), Some(mut state)) = (&self.state, state) else {
+            return;
+        };
+        let now = now_unix_ms();
+        state.set_target(TargetStatus::Running, now);
+        let snapshot = RemotePodSnapshot {
+            id: PodId::new(lease.id.clone()),
+            name: lease.name.clone(),
+            desired_status: PodDesiredStatus::Running,
+            observed_at_ms: now,
+        };
+        state.reconcile(RemoteObservation::Found(snapshot), now);
+        if let Err(e) = runpod_state::run_blocking(store, move |s| s.save(&mut state)).await {
+            log_warn!(
+                "could not save state of pod {}: {e}",
+                lease.id,
+                e = ErrorChain(&e)
+            );
+        }
+    }
+
+    /// Forget the terminated pod `pod_id` if it is the recorded one.
+    async fn forget_state(&self, pod_id: &str) {
+        let Some(store) = &self.state else {
+            return;
+        };
+        let mut state = match runpod_state::run_blocking(store, |s| s.load()).await {
+            Ok(Some(state)) if state.pod_id().is_some_and(|id| id.as_str() == pod_id) => state,
+            Ok(_) => return,
+            Err(e) => {
+                log_warn!("could not load pod state: {e}", e = ErrorChain(&e));
+                return;
+            }
+        };
+        state.apply_terminated(now_unix_ms());
+        if let Err(e) = runpod_state::run_blocking(store, move |s| s.save(&mut state)).await {
+            log_warn!(
+                "could not save state after terminating pod {pod_id}: {e}",
+                e = ErrorChain(&e)
+            );
+        }
+    }
+
+    /// Check if a pod is compatible with the requested spec.
+    fn is_compatible(pod: &PodInfo, compatibility: Option<&CompatibilityReport>) -> bool {
+        pod.desiredStatus.as_deref() != Some("TERMINATED")
+            && compatibility.is_some_and(CompatibilityReport::is_compatible)
+    }
+
+    /// Compare an existing pod with the configured spec, on the configured
+    /// `compatibility_checks` (as `ensure_ready_pod()` does before reusing it).
     ///
     /// # Errors
     ///
-    /// Returns an error if the pod is not found or the stop operation fails.
-    pub async fn stop_current_pod(&self) -> Result<(), OrchestratorError> {
+    /// Returns an error if the pod is not found or cannot be read.
+    pub async fn check_compatibility(
+        &self,
+        pod_id: &str,
+    ) -> Result<CompatibilityReport, OrchestratorError> {
         let pod = self
-            .find_pod_by_name(&self.cfg.pod_name)
+            .get_pod(pod_id)
             .await?
-            .ok_or_else(|| OrchestratorError::PodNotFound(self.cfg.pod_name.clone()))?;
+            .ok_or_else(|| OrchestratorError::PodNotFound(pod_id.to_string()))?;
+        let spec = PodSpec::resolve(&self.cfg, &PodOverrides::default());
+        let checks = &self.cfg.compatibility_checks;
+        Ok(self.compare_pod(pod_id, pod.imageName.as_deref(), Some(&pod), &spec, checks))
+    }
+
+    /// Compare pod `pod_id` (running `image_name`) with `spec` on `checks`.
+    ///
+    /// Pod details are only read when a check needs more than the image.
+    async fn compatibility(
+        &self,
+        pod_id: &str,
+        image_name: Option<&str>,
+        spec: &PodSpec,
+        checks: &[CompatibilityCheck],
+    ) -> Result<CompatibilityReport, OrchestratorError> {
+        let details = if checks.iter().all(|c| *c == CompatibilityCheck::Image) {
+            None
+        } else {
+            self.get_pod(pod_id).await?
+        };
+        Ok(self.compare_pod(pod_id, image_name, details.as_ref(), spec, checks))
+    }
+
+    /// Compare pod `pod_id` (running `image_name`) with `spec` on `checks`,
+    /// using its `details` for every check but the image (skipped without).
+    fn compare_pod(
+        &self,
+        pod_id: &str,
+        image_name: Option<&str>,
+        details: Option<&PodDetails>,
+        spec: &PodSpec,
+        checks: &[CompatibilityCheck],
+    ) -> CompatibilityReport {
+        let mut report = CompatibilityReport {
+            pod_id: pod_id.to_string(),
+            mismatches: Vec::new(),
+            volume_growth_gb: None,
+        };
+        if checks.contains(&CompatibilityCheck::Image) && image_name != Some(&spec.image_name) {
+            report.push(
+                CompatibilityCheck::Image,
+                spec.image_name.clone(),
+                image_name.map(str::to_string),
+            );
+        }
+        let Some(pod) = details else {
+            return report;
+        };
+        let requested = self.provision_config(spec).ok();
+        for check in checks {
+            match check {
+                CompatibilityCheck::Image => {}
+                CompatibilityCheck::GpuType => {
+                    let actual = pod.gpu.as_ref().and_then(|g| g.id.clone());
+                    if actual
+                        .as_ref()
+                        .is_some_and(|id| !spec.gpu_type_ids.contains(id))
+                    {
+                        report.push(*check, spec.gpu_type_ids.join(" | "), actual);
+                    }
+                }
+                CompatibilityCheck::GpuCount => {
+                    let expected = spec
+                        .gpu_count
+                        .or_else(|| requested.as_ref().map(|r| r.gpu_count));
+                    let actual = pod.gpu.as_ref().and_then(|g| g.count);
+                    if let (Some(expected), Some(actual)) = (expected, actual)
+                        && expected != actual
+                    {
+                        report.push(*check, expected.to_string(), Some(actual.to_string()));
+                    }
+                }
+                CompatibilityCheck::Ports => {
+                    let actual = pod.ports.clone().unwrap_or_default();
+                    if spec.ports.iter().any(|p| !actual.contains(p)) {
+                        report.push(*check, spec.ports.join(","), Some(actual.join(",")));
+                    }
+                }
+                CompatibilityCheck::Volume => {
+                    let Some(requested) = &requested else {
+                        continue;
+                    };
+                    let wanted_volume = requested.network_volume_id.as_deref();
+                    let actual_volume = pod.networkVolumeId.as_deref().filter(|v| !v.is_empty());
+                    let size_differs = pod.volumeInGb.is_some_and(|gb| gb != requested.volume_gb);
+                    let other_volume = wanted_volume.is_some_and(|v| actual_volume != Some(v));
+                    if size_differs || other_volume {
+                        let expected = describe_volume(Some(requested.volume_gb), wanted_volume);
+                        let actual = describe_volume(pod.volumeInGb, actual_volume);
+                        report.push(*check, expected, Some(actual));
+                    }
+                    // Pod volumes can grow in place, never shrink.
+                    if !other_volume && pod.volumeInGb.is_some_and(|gb| gb < requested.volume_gb) {
+                        report.volume_growth_gb = Some(requested.volume_gb);
+                    }
+                }
+                CompatibilityCheck::Env => {
+                    let Some(requested) = &requested else {
+                        continue;
+                    };
+                    let expected = env_hash(&requested.pod_env);
+                    let actual = env_hash(&pod.env.clone().unwrap_or_default());
+                    if expected != actual {
+                        report.push(*check, expected, Some(actual));
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// Compute the in-place update that would make `pod` match `spec`.
+    ///
+    /// Returns `None` if the pod cannot be updated (terminated) or nothing
+    /// mutable differs.
+    fn mutable_update(
+        &self,
+        pod: &PodInfo,
+        spec: &PodSpec,
+        report: &CompatibilityReport,
+    ) -> Option<PodUpdate> {
+        if pod.desiredStatus.as_deref() == Some("TERMINATED") || !report.is_updatable() {
+            return None;
+        }
+
+        let env = if report.differs(CompatibilityCheck::Env) {
+            Some(self.provision_config(spec).ok()?.pod_env)
+        } else {
+            None
+        };
+        let changes = PodUpdate {
+            name: None,
+            image_name: report
+                .differs(CompatibilityCheck::Image)
+                .then(|| spec.image_name.clone()),
+            env,
+            ports: report
+                .differs(CompatibilityCheck::Ports)
+                .then(|| spec.ports.clone()),
+            volume_in_gb: report.volume_growth_gb,
+        };
+
+        (!changes.is_empty()).then_some(changes)
+    }
+
+    /// Replace `pod_id` with a new pod built from `spec`, carrying over
+    /// its name, network volume and mount path.
+    async fn replace_with_spec(
+        &self,
+        pod_id: &str,
+        mut spec: PodSpec,
+        started: Instant,
+    ) -> Result<PodLease, OrchestratorError> {
+        let old = self
+            .get_pod(pod_id)
+            .await?
+            .ok_or_else(|| OrchestratorError::PodNotFound(pod_id.to_string()))?;
+
+        if let Some(name) = old.name.filter(|n| !n.is_empty()) {
+            spec.name = name;
+        }
+        if spec.network_volume_id.is_none()
+            && let Some(volume_id) = old.networkVolumeId.filter(|v| !v.is_empty())
+        {
+            spec.network_volume_id = Some(volume_id);
+            spec.volume_mount_path = old.volumeMountPath.filter(|p| !p.is_empty());
+        }
+
+        if old.desiredStatus.as_deref() != Some("TERMINATED") {
+            // The replacement reuses the name: make sure the old pod is gone first.
+            let timeout = Duration::from_millis(self.cfg.terminate_timeout_ms);
+            self.terminate_and_wait(pod_id, timeout).await?;
+        }
+
+        self.report_progress(&spec.name, ProgressPhase::Creating, started, None);
+        let created = self.create_new_pod(&spec).await?;
+
+        if let Some(volume_id) = &spec.network_volume_id {
+            self.verify_volume_attached(&created.id, volume_id).await?;
+        }
+
+        let mut lease = self
+            .wait_for_ready(&created.id, &spec.name, &spec.ports, started)
+            .await?;
+        lease.provenance = LeaseProvenance::created(LeaseAction::Replaced, created);
+        Ok(lease)
+    }
+
+    /// Start a replacement for the current pod of `spec` next to it, then
+    /// retire the old pod once the replacement is ready.
+    async fn roll_out_spec(
+        &self,
+        mut spec: PodSpec,
+        options: ImageUpdateOptions,
+        started: Instant,
+    ) -> Result<PodLease, OrchestratorError> {
+        self.report_progress(&spec.name, ProgressPhase::Discovering, started, None);
+        let state = self.load_state(&spec.name).await;
+        let old = self
+            .find_current_pod(&spec, state.as_ref())
+            .await?
+            .filter(|p| p.desiredStatus.as_deref() != Some("TERMINATED"))
+            .ok_or_else(|| OrchestratorError::PodNotFound(spec.name.clone()))?;
+
+        if let Some(name) = old.name.clone().filter(|n| !n.is_empty()) {
+            spec.name = name;
+        }
+        let mut labels = old.labels();
+        labels.append(&mut spec.labels);
+        spec.labels = labels;
+        let details = self
+            .get_pod(&old.id)
+            .await?
+            .ok_or_else(|| OrchestratorError::PodNotFound(old.id.clone()))?;
+        if let Some(volume_id) = details.networkVolumeId.filter(|v| !v.is_empty()) {
+            spec.network_volume_id = Some(volume_id);
+            spec.volume_mount_path = details.volumeMountPath.filter(|p| !p.is_empty());
+        }
+
+        self.report_progress(&spec.name, ProgressPhase::Creating, started, None);
+        let created = match self.create_new_pod(&spec).await {
+            Ok(created) => created,
+            Err(e) => return Err(self.abort_roll_out(&old.id, None, options, e).await),
+        };
+        let new_id = created.id.clone();
+        let ready = async {
+            if let Some(volume_id) = &spec.network_volume_id {
+                self.verify_volume_attached(&new_id, volume_id).await?;
+            }
+            self.wait_for_ready(&new_id, &spec.name, &spec.ports, started)
+                .await
+        };
+        let mut lease = match Box::pin(ready).await {
+            Ok(lease) => lease,
+            Err(e) => {
+                return Err(self
+                    .abort_roll_out(&old.id, Some(&new_id), options, e)
+                    .await);
+            }
+        };
+        lease.provenance = LeaseProvenance::created(LeaseAction::Replaced, created);
+        // Record the replacement first: terminating the old pod then leaves it.
+        self.save_state(state, &lease).await;
+
+        if options.stop_old {
+            let rename = PodUpdate {
+                name: Some(format!("{}-previous", spec.name)),
+                ..PodUpdate::default()
+            };
+            let stopped = match self.update_pod(&old.id, &rename).await {
+                Ok(_) => self.stop_pod(&old.id).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = stopped {
+                log_warn!(
+                    "could not stop old pod {} after image update: {e}",
+                    old.id,
+                    e = ErrorChain(&e)
+                );
+            }
+        } else if let Err(e) = self.terminate(&old.id).await {
+            log_warn!(
+                "could not terminate old pod {} after image update: {e}",
+                old.id,
+                e = ErrorChain(&e)
+            );
+        }
+        log_info!(
+            "pod {} replaced by {} ({})",
+            old.id,
+            lease.id,
+            spec.image_name
+        );
+        Ok(lease)
+    }
+
+    /// Clean up after a failed image update: terminate the replacement (if
+    /// created) and, unless `keep_old_on_failure`, the old pod. Returns the
+    /// error that made the update fail.
+    async fn abort_roll_out(
+        &self,
+        old_id: &str,
+        new_id: Option<&str>,
+        options: ImageUpdateOptions,
+        error: OrchestratorError,
+    ) -> OrchestratorError {
+        log_warn!(
+            "image update of pod {old_id} failed: {error}",
+            error = ErrorChain(&error)
+        );
+        let mut doomed: Vec<&str> = new_id.into_iter().collect();
+        if !options.keep_old_on_failure {
+            doomed.push(old_id);
+        }
+        for pod_id in doomed {
+            if let Err(e) = self.terminate(pod_id).await {
+                log_warn!(
+                    "could not terminate pod {pod_id} after failed image update: {e}",
+                    e = ErrorChain(&e)
+                );
+            }
+        }
+        error
+    }
+
+    /// Check that `volume_id` is attached to the pod.
+    async fn verify_volume_attached(
+        &self,
+        pod_id: &str,
+        volume_id: &str,
+    ) -> Result<(), OrchestratorError> {
+        let pod = self
+            .get_pod(pod_id)
+            .await?
+            .ok_or_else(|| OrchestratorError::PodNotFound(pod_id.to_string()))?;
+
+        if pod.networkVolumeId.as_deref() == Some(volume_id) {
+            Ok(())
+        } else {
+            Err(OrchestratorError::VolumeNotAttached {
+                pod_id: pod_id.to_string(),
+                volume_id: volume_id.to_string(),
+            })
+        }
+    }
+
+    /// Start a stopped pod (REST `POST /pods/{podId}/start`).
+    ///
+    /// Returns once `RunPod` accepted the request; use `restart_pod()` or
+    /// `ensure_ready_pod()` to also wait for readiness.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API returns an error.
+    pub async fn start_pod(&self, pod_id: &str) -> Result<(), OrchestratorError> {
+        if let Some(guard) = &self.spend_guard {
+            let name = guard.pod_name(pod_id).unwrap_or_else(|| pod_id.to_string());
+            self.check_spend_cap(&name)?;
+        }
+        if let Some(sim) = &self.sim {
+            return sim
+                .start_pod(pod_id)
+                .then_some(())
+                .ok_or_else(|| OrchestratorError::PodNotFound(pod_id.to_string()));
+        }
+
+        let url = format!(
+            "{}/pods/{}/start",
+            self.cfg.rest_url.trim_end_matches('/'),
+            pod_id
+        );
+
+        let req = self.http.post(&url).bearer_auth(self.cfg.api_key.expose());
+        let resp = self.send(req).await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(OrchestratorError::Api { status, body });
+        }
+
+        Ok(())
+    }
+
+    /// Run `pre_stop_command` on a running pod over SSH, if configured.
+    ///
+    /// Never fails: the outcome ("ok", "failed", "timeout", "error") is
+    /// reported as the `PRE_STOP_HOOK_MS` metric, and the caller proceeds
+    /// with the lifecycle call either way. Simulated pods, stopped pods and
+    /// pods without an SSH endpoint are skipped.
+    async fn run_pre_stop_hook(&self, pod_id: &str) {
+        let Some(command) = self.cfg.pre_stop_command.as_deref() else {
+            return;
+        };
+        if self.sim.is_some() {
+            return;
+        }
+
+        let Ok(Some(pod)) = self.get_pod(pod_id).await else {
+            return;
+        };
+        let pod = ReadinessView::from_rest(pod);
+        let (Some("RUNNING"), Some(host), Some(port)) = (
+            pod.desired_status.as_deref(),
+            pod.public_ip.as_deref().filter(|ip| !ip.is_empty()),
+            pod.port_mappings.get(&22).copied(),
+        ) else {
+            return;
+        };
+
+        let start = Instant::now();
+        let outcome = self.exec_pre_stop(host, port, command).await;
+        if outcome == "ok" {
+            log_debug!("pre-stop command on pod {pod_id} succeeded");
+        } else {
+            log_warn!("pre-stop command on pod {pod_id} did not succeed ({outcome})");
+        }
+        self.metrics.histogram(
+            runpod_metrics::PRE_STOP_HOOK_MS,
+            runpod_metrics::elapsed_ms(start),
+            &[("outcome", outcome)],
+        );
+    }
+
+    /// Run the pre-stop command on `host:port`, returning its outcome.
+    #[cfg(feature = "ssh")]
+    async fn exec_pre_stop(&self, host: &str, port: u16, command: &str) -> &'static str {
+        let timeout = Duration::from_millis(self.cfg.pre_stop_timeout_ms);
+        match self.login() {
+            Ok(login) => match login.exec(host, port, command, timeout).await {
+                Ok(output) if output.success() => "ok",
+                Ok(_) => "failed",
+                Err(SshError::CommandTimeout { .. }) => "timeout",
+                Err(_) => "error",
+            },
+            Err(_) => "error",
+        }
+    }
+
+    /// Without the `ssh` feature, pre-stop commands cannot run.
+    #[cfg(not(feature = "ssh"))]
+    #[allow(clippy::unused_async)] // Same signature as with the feature.
+    async fn exec_pre_stop(&self, _host: &str, _port: u16, _command: &str) -> &'static str {
+        log_warn!("pre-stop command skipped: built without the `ssh` feature");
+        "skipped"
+    }
+
+    /// SSH login of pre-stop commands and host benchmarks: the one set with
+    /// `with_ssh_login()`, else `SshLogin::from_env()`.
+    #[cfg(feature = "ssh")]
+    fn login(&self) -> Result<SshLogin, SshError> {
+        self.ssh_login
+            .as_ref()
+            .map_or_else(SshLogin::from_env, |login| Ok(login.clone()))
+    }
+
+    /// Terminate a pod (a pod that is not found counts as already gone).
+    async fn terminate_pod(&self, pod_id: &str) -> Result<TerminateOutcome, OrchestratorError> {
+        if let Some(sim) = &self.sim {
+            return Ok(if sim.terminate_pod(pod_id) {
+                TerminateOutcome::Terminated
+            } else {
+                TerminateOutcome::AlreadyGone
+            });
+        }
+
+        let url = format!(
+            "{}/pods/{}",
+            self.cfg.rest_url.trim_end_matches('/'),
+            pod_id
+        );
+
+        let req = self
+            .http
+            .delete(&url)
+            .bearer_auth(self.cfg.api_key.expose());
+        let resp = self.send(req).await?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            if is_pod_gone_error(status.as_u16(), &body) {
+                return Ok(TerminateOutcome::AlreadyGone);
+            }
+            return Err(OrchestratorError::Api { status, body });
+        }
+
+        Ok(TerminateOutcome::Terminated)
+    }
+
+    /// Send a progress update to the reporter, if any.
+    ///
+    /// `wait_started` is set during the waiting phases, to estimate the
+    /// remaining time and interpolate the percentage.
+    fn report_progress(
+        &self,
+        pod_name: &str,
+        phase: ProgressPhase,
+        started: Instant,
+        wait_started: Option<Instant>,
+    ) {
+        let Some(reporter) = &self.progress else {
+            return;
+        };
+
+        let timeout = Duration::from_millis(self.cfg.ready_timeout_ms);
+        let waited = wait_started.map(|w| w.elapsed());
+        let fraction = waited.map_or(0.0, |w| {
+            (w.as_secs_f64() / timeout.as_secs_f64().max(f64::EPSILON)).min(1.0)
+        });
+        let (from, to) = match phase {
+            ProgressPhase::Discovering => (0.0, 0.0),
+            ProgressPhase::Creating | ProgressPhase::Starting => (10.0, 10.0),
+            ProgressPhase::WaitingForIp => (25.0, 60.0),
+            ProgressPhase::WaitingForPorts => (60.0, 95.0),
+            ProgressPhase::Ready => (100.0, 100.0),
+        };
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let percent = fraction.mul_add(to - from, from).round() as u8;
+
+        reporter.report(&Progress {
+            pod_name: pod_name.to_string(),
+            phase,
+            percent,
+            elapsed: started.elapsed(),
+            remaining: match phase {
+                ProgressPhase::Ready => Some(Duration::ZERO),
+                _ => waited.map(|w| timeout.saturating_sub(w)),
+            },
+        });
+    }
+
+    /// Send a REST request, recording request count and latency.
+    ///
+    /// Transient failures are retried per `retry` (requests with streaming
+    /// bodies, which cannot be cloned, are sent once). Non-idempotent
+    /// requests, such as `POST /pods`, are only retried when the server did
+    /// not act on them, so a lost response never creates a second pod.
+    async fn send(
+        &self,
+        req: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, OrchestratorError> {
+        let (client, req) = req.build_split();
+        let mut req = req.map_err(OrchestratorError::Http)?;
+        if req.timeout().is_none() {
+            *req.timeout_mut() = runpod_http::total_timeout(self.cfg.timeout_ms);
+        }
+        let operation = format!("{} {}", req.method(), req.url().path());
+        let idempotent = runpod_http::is_idempotent(req.method());
+        let mut attempt: u32 = 0;
+        loop {
+            attempt = attempt.saturating_add(1);
+            let retry_req = req.try_clone();
+
+            let start = std::time::Instant::now();
+            let res = client.execute(req).await;
+
+            let status = res
+                .as_ref()
+                .map_or_else(|_| "error".to_string(), |r| r.status().as_u16().to_string());
+            self.metrics.counter(
+                runpod_metrics::HTTP_REQUESTS,
+                1,
+                &[("component", "orchestrator"), ("status", &status)],
+            );
+            self.metrics.histogram(
+                runpod_metrics::HTTP_REQUEST_MS,
+                runpod_metrics::elapsed_ms(start),
+                &[("component", "orchestrator")],
+            );
+
+            let retryable = match &res {
+                Ok(resp) => self
+                    .cfg
+                    .retry
+                    .retries_status(attempt, resp.status(), idempotent),
+                Err(e) => self.cfg.retry.retries_error(attempt, e, idempotent),
+            };
+            match retry_req {
+                Some(next) if retryable => {
+                    log_debug!(
+                        "orchestrator request returned {status}, retrying (attempt {attempt})"
+                    );
+                    runpod_http::record_retry(self.metrics.as_ref(), "orchestrator");
+                    tokio::time::sleep(self.cfg.retry.delay(attempt)).await;
+                    req = next;
+                }
+                _ => {
+                    return res.map_err(|source| OrchestratorError::Request {
+                        operation,
+                        attempts: attempt,
+                        source,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Fail fast if the image does not exist (when `image_precheck` is enabled).
+    async fn precheck_image(&self, image: &str) -> Result<(), OrchestratorError> {
+        if !self.cfg.image_precheck || self.sim.is_some() {
+            return Ok(());
+        }
+
+        runpod_registry::check_image_exists(
+            &self.http,
+            image,
+            self.cfg.registry_credentials.as_ref(),
+        )
+        .await
+        .map_err(|e| match e {
+            RegistryError::ImageNotFound(i) => OrchestratorError::ImageNotFound(i),
+            other => OrchestratorError::Registry(other),
+        })
+    }
+
+    /// Create a new pod using the provisioner, tracking it with the spend guard.
+    async fn create_new_pod(&self, spec: &PodSpec) -> Result<CreatedPod, OrchestratorError> {
+        self.check_spend_cap(&spec.name)?;
+        let created = self.provision_new_pod(spec).await?;
+        if let Some(guard) = &self.spend_guard {
+            guard.track(&spec.name, &created.id, now_unix_ms());
+        }
+        Ok(created)
+    }
+
+    /// Create a new pod using the provisioner.
+    async fn provision_new_pod(&self, spec: &PodSpec) -> Result<CreatedPod, OrchestratorError> {
+        self.precheck_image(&spec.image_name).await?;
+
+        if let Some(sim) = &self.sim {
+            self.confirm_creation(CostEstimate {
+                pod_name: spec.name.clone(),
+                image_name: spec.image_name.clone(),
+                cloud_type: "SIMULATED".to_string(),
+                gpu_type_ids: spec.gpu_type_ids.clone(),
+                gpu_count: spec.gpu_count.unwrap_or(1),
+                hourly_usd: None,
+            })
+            .await?;
+
+            let network_volume = spec.network_volume_id.as_deref().map(|id| {
+                (
+                    id,
+                    spec.volume_mount_path.as_deref().unwrap_or("/workspace"),
+                )
+            });
+            let created = sim.create_pod(&spec.name, &spec.image_name, &spec.ports, network_volume);
+            if !spec.labels.is_empty() {
+                let env = HashMap::from([(
+                    LABELS_ENV.to_string(),
+                    runpod_labels::encode_labels(&spec.labels),
+                )]);
+                let labels = PodUpdate {
+                    env: Some(env),
+                    ..PodUpdate::default()
+                };
+                let _ = sim.update_pod(&created.id, &labels);
+            }
+            record_step(StepKind::Create, &created.id);
+            return Ok(created);
+        }
+
+        let provision_cfg = self.provision_config(spec)?;
+
+        if self.confirm.is_some() {
+            self.confirm_creation(self.estimate_cost(&provision_cfg).await)
+                .await?;
+        }
+
+        let provisioner = RunpodProvisioner::new_with_http(provision_cfg, self.http.clone())
+            .with_metrics(Arc::clone(&self.metrics));
+
+        let created = provisioner.create_pod().await;
+        self.record_availability(provisioner.config(), &created)
+            .await;
+        let created = created.map_err(|e| {
+            if e.is_capacity_error() {
+                OrchestratorError::NoCapacity(Box::new(e))
+            } else {
+                OrchestratorError::Provision(Box::new(e))
+            }
+        })?;
+
+        log_info!("created pod {} ({})", created.id, spec.name);
+        record_step(StepKind::Create, &created.id);
+        self.metrics.counter(
+            runpod_metrics::PODS_CREATED,
+            1,
+            &[(
+                "cloud_type",
+                created.cloud_type.as_deref().unwrap_or("unknown"),
+            )],
+        );
+
+        Ok(created)
+    }
+
+    /// Feed the outcome of a creation into the availability history, if tracked.
+    ///
+    /// A success is recorded for the GPU type / data center the pod got
+    /// (as reported by `RunPod`, or the only one requested); a capacity error
+    /// for every requested combination. The history is written on the
+    /// blocking thread pool.
+    async fn record_availability(
+        &self,
+        provision_cfg: &RunpodProvisionConfig,
+        result: &Result<CreatedPod, RunpodError>,
+    ) {
+        let Some(tracker) = &self.availability else {
+            return;
+        };
+
+        let only = |ids: &[String]| match ids {
+            [id] => Some(id.clone()),
+            _ => None,
+        };
+        let outcomes: Vec<(String, Option<String>, ProvisionOutcome)> = match result {
+            // GPU types given up on by the fallback had no capacity anywhere.
+            Ok(created) => created
+                .skipped_gpu_types
+                .iter()
+                .map(|gpu| (gpu.clone(), None, ProvisionOutcome::NoCapacity))
+                .chain(
+                    created
+                        .gpu_type_id
+                        .clone()
+                        .or_else(|| only(&provision_cfg.gpu_type_ids))
+                        .map(|gpu| {
+                            let data_center_id = created
+                                .data_center_id
+                                .clone()
+                                .or_else(|| only(&provision_cfg.data_center_ids));
+                            (gpu, data_center_id, ProvisionOutcome::Success)
+                        }),
+                )
+                .collect(),
+            Err(e) if e.is_capacity_error() => {
+                let data_centers: Vec<Option<String>> = if provision_cfg.data_center_ids.is_empty()
+                {
+                    vec![None]
+                } else {
+                    provision_cfg
+                        .data_center_ids
+                        .iter()
+                        .cloned()
+                        .map(Some)
+                        .collect()
+                };
+                provision_cfg
+                    .gpu_type_ids
+                    .iter()
+                    .flat_map(|gpu| {
+                        data_centers
+                            .iter()
+                            .map(move |dc| (gpu.clone(), dc.clone(), ProvisionOutcome::NoCapacity))
+                    })
+                    .collect()
+            }
+            Err(_) => return,
+        };
+
+        let now = now_unix_ms();
+        let recorded = runpod_availability::run_blocking(tracker, move |tracker| {
+            for (gpu, data_center_id, outcome) in outcomes {
+                if let Err(e) = tracker.record(&gpu, data_center_id.as_deref(), outcome, now) {
+                    log_warn!(
+                        "could not record availability of {gpu}: {e}",
+                        e = ErrorChain(&e)
+                    );
+                }
+            }
+            Ok(())
+        })
+        .await;
+        if let Err(e) = recorded {
+            log_warn!("could not record availability: {e}", e = ErrorChain(&e));
+        }
+    }
+
+    /// Run `critical` holding the lock of pod `name` (if a lock is installed).
+    ///
+    /// Each call acquires the lock with its own owner token and refreshes it
+    /// every third of `lock_ttl_ms` while `critical` runs. The lock is
+    /// released whatever the outcome; it is only left to expire if this
+    /// future is dropped mid-way. Lock calls run on the blocking thread pool.
+    async fn with_pod_lock<T>(
+        &self,
+        name: &str,
+        critical: impl Future<Output = Result<T, OrchestratorError>>,
+    ) -> Result<T, OrchestratorError> {
+        let Some(lock) = &self.lock else {
+            return critical.await;
+        };
+
+        let key = format!("pod-{name}");
+        let owner = runpod_lock::acquisition_token();
+        let ttl = Duration::from_millis(self.cfg.lock_ttl_ms);
+        let wait = Duration::from_millis(self.cfg.lock_wait_ms);
+        let started = Instant::now();
+        while !Self::try_acquire_lock(lock, &key, &owner, ttl)
+            .await
+            .map_err(OrchestratorError::Lock)?
+        {
+            if started.elapsed() >= wait {
+                return Err(OrchestratorError::LockTimeout(key));
+            }
+            let pause = Duration::from_millis(self.cfg.poll_interval_ms).min(wait);
+            cancellable(None, async {
+                tokio::time::sleep(pause).await;
+                Ok(())
+            })
+            .await?;
+        }
 
-        self.stop_pod(&pod.id).await
+        let renew = async {
+            loop {
+                tokio::time::sleep(ttl / 3).await;
+                match Self::try_acquire_lock(lock, &key, &owner, ttl).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        log_warn!("lock {key} expired and was taken over while held");
+                        return;
+                    }
+                    Err(e) => log_warn!("could not refresh lock {key}: {e}", e = ErrorChain(&e)),
+                }
+            }
+        };
+        let result = match future::select(Box::pin(critical), std::pin::pin!(renew)).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), critical)) => critical.await,
+        };
+
+        let (release_key, release_owner) = (key.clone(), owner.clone());
+        let released =
+            runpod_lock::run_blocking(lock, move |lock| lock.release(&release_key, &release_owner))
+                .await
+                .map_err(OrchestratorError::Lock);
+        let value = result?;
+        released?;
+        Ok(value)
     }
 
-    /// Terminate a pod completely (removes it from RunPod).
-    ///
-    /// Use this when you no longer need the pod. The pod cannot be restarted.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the HTTP request fails or the API returns an error.
-    pub async fn terminate(&self, pod_id: &str) -> Result<(), OrchestratorError> {
-        self.terminate_pod(pod_id).await
+    /// `DistributedLock::try_acquire()` on the blocking thread pool.
+    async fn try_acquire_lock(
+        lock: &Arc<dyn DistributedLock>,
+        key: &str,
+        owner: &str,
+        ttl: Duration,
+    ) -> Result<bool, LockError> {
+        let (key, owner) = (key.to_string(), owner.to_string());
+        runpod_lock::run_blocking(lock, move |lock| lock.try_acquire(&key, &owner, ttl)).await
     }
 
-    /// Terminate the pod by name (uses the configured pod name).
-    ///
-    /// # Errors
+    /// Create a pod and wait for it to be ready, failing over across
+    /// `data_center_failover` when configured.
+    async fn create_ready_pod(
+        &self,
+        spec: &PodSpec,
+        started: Instant,
+    ) -> Result<PodLease, OrchestratorError> {
+        if self.cfg.data_center_failover.is_empty() {
+            return self.create_ready_pod_without_failover(spec, started).await;
+        }
+
+        let data_centers = self.availability.as_ref().map_or_else(
+            || self.cfg.data_center_failover.clone(),
+            |tracker| {
+                let failover = &self.cfg.data_center_failover;
+                tracker.rank_data_centers(&spec.gpu_type_ids, failover, now_unix_ms())
+            },
+        );
+        let mut failovers = Vec::new();
+        let mut rejected = Vec::new();
+        for data_center_id in &data_centers {
+            let dc_spec = PodSpec {
+                data_center_id: Some(data_center_id.clone()),
+                ..spec.clone()
+            };
+
+            for _ in 0..self.cfg.data_center_ready_attempts.max(1) {
+                self.report_progress(&spec.name, ProgressPhase::Creating, started, None);
+                let created = match self.create_new_pod(&dc_spec).await {
+                    Ok(created) => created,
+                    Err(OrchestratorError::NoCapacity(e)) => {
+                        let detail = e.to_string();
+                        failovers.push(self.failover(
+                            data_center_id,
+                            FailoverReason::NoCapacity,
+                            None,
+                            detail,
+                        ));
+                        break;
+                    }
+                    Err(e) => return Err(e),
+                };
+
+                match self
+                    .wait_for_ready(&created.id, &spec.name, &spec.ports, started)
+                    .await
+                {
+                    Ok(mut lease) => {
+                        match self.accept_host(&mut lease, started, &mut rejected).await {
+                            Ok(()) => {
+                                lease.provenance = LeaseProvenance {
+                                    data_center_id: Some(data_center_id.clone()),
+                                    failovers,
+                                    ..LeaseProvenance::created(LeaseAction::Created, created)
+                                };
+                                lease.data_center_id = Some(data_center_id.clone());
+                                return Ok(lease);
+                            }
+                            Err(e) => failovers.push(self.failover(
+                                data_center_id,
+                                FailoverReason::BelowBenchmark,
+                                Some(created.id),
+                                e.to_string(),
+                            )),
+                        }
+                    }
+                    Err(
+                        e @ (OrchestratorError::Timeout { .. }
+                        | OrchestratorError::PortsNotExposed { .. }
+                        | OrchestratorError::ProbesFailed { .. }
+                        | OrchestratorError::ReadinessCheckFailed(_)),
+                    ) => {
+                        // Never leave a billed, unusable pod behind.
+                        self.terminate_pod(&created.id).await?;
+                        record_step(StepKind::Terminate, &created.id);
+                        failovers.push(self.failover(
+                            data_center_id,
+                            FailoverReason::NotReady,
+                            Some(created.id),
+                            e.to_string(),
+                        ));
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        Err(OrchestratorError::FailoverExhausted(failovers))
+    }
+
+    /// Create a pod in whichever data center has capacity and wait for it to
+    /// be ready, recreating it while the host benchmark rejects it.
+    async fn create_ready_pod_without_failover(
+        &self,
+        spec: &PodSpec,
+        started: Instant,
+    ) -> Result<PodLease, OrchestratorError> {
+        let attempts = self
+            .cfg
+            .benchmark
+            .as_ref()
+            .map_or(1, |b| b.max_attempts.max(1));
+        let mut attempt = 0;
+        let mut rejected = Vec::new();
+        loop {
+            attempt += 1;
+            self.report_progress(&spec.name, ProgressPhase::Creating, started, None);
+            let created = self.create_new_pod(spec).await?;
+            let mut lease = self
+                .wait_for_ready(&created.id, &spec.name, &spec.ports, started)
+                .await?;
+            match self.accept_host(&mut lease, started, &mut rejected).await {
+                Ok(()) => {
+                    lease.provenance = LeaseProvenance::created(LeaseAction::Created, created);
+                    if lease.data_center_id.is_none() {
+                        lease
+                            .data_center_id
+                            .clone_from(&lease.provenance.data_center_id);
+                    }
+                    return Ok(lease);
+                }
+                Err(e) if attempt < attempts => {
+                    log_warn!("recreating {}: {e}", spec.name, e = ErrorChain(&e));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Run the host benchmark on a newly created pod, if configured.
     ///
-    /// Returns an error if the pod is not found or the terminate operation fails.
-    pub async fn terminate_current_pod(&self) -> Result<(), OrchestratorError> {
-        let pod = self
-            .find_pod_by_name(&self.cfg.pod_name)
-            .await?
-            .ok_or_else(|| OrchestratorError::PodNotFound(self.cfg.pod_name.clone()))?;
+    /// The result is stored on the lease. A host that fails (or a benchmark
+    /// that cannot run) gets its pod terminated and `HostRejected` returned;
+    /// its machine is added to `rejected`, and a later pod landing on a
+    /// machine in `rejected` is turned down without benchmarking it again.
+    /// Simulated pods are never benchmarked.
+    async fn accept_host(
+        &self,
+        lease: &mut PodLease,
+        started: Instant,
+        rejected: &mut Vec<String>,
+    ) -> Result<(), OrchestratorError> {
+        let Some(bench) = self.cfg.benchmark.as_ref() else {
+            return Ok(());
+        };
+        if self.sim.is_some() {
+            return Ok(());
+        }
+
+        let machine_id = self.get_pod(&lease.id).await?.and_then(|pod| pod.machineId);
+        let start = Instant::now();
+        let result = match &machine_id {
+            Some(machine) if rejected.contains(machine) => BenchmarkResult {
+                failures: vec![format!("machine {machine} already failed the benchmark")],
+                ..BenchmarkResult::default()
+            },
+            _ => {
+                // The benchmark needs sshd, which may come up after the ports.
+                let ready_budget = Duration::from_millis(self.cfg.ready_timeout_ms);
+                let ssh_timeout = ready_budget.saturating_sub(started.elapsed());
+                let Some(result) = self.run_benchmark(lease, bench, ssh_timeout).await else {
+                    return Ok(());
+                };
+                result
+            }
+        };
+
+        let outcome = if result.passed() {
+            "passed"
+        } else {
+            "rejected"
+        };
+        self.metrics.histogram(
+            runpod_metrics::HOST_BENCHMARK_MS,
+            runpod_metrics::elapsed_ms(start),
+            &[("outcome", outcome)],
+        );
+        lease.benchmark = Some(result.clone());
+        if result.passed() {
+            log_info!(
+                "pod {} passed the host benchmark: {:?}",
+                lease.id,
+                result.metrics
+            );
+            return Ok(());
+        }
+
+        // Never leave a billed, underperforming pod behind, and make sure
+        // it is gone before a replacement is requested.
+        if let Some(machine) = machine_id
+            && !rejected.contains(&machine)
+        {
+            rejected.push(machine);
+        }
+        let timeout = Duration::from_millis(self.cfg.terminate_timeout_ms);
+        self.terminate_and_wait(&lease.id, timeout).await?;
+        Err(OrchestratorError::HostRejected {
+            pod_id: lease.id.clone(),
+            result,
+        })
+    }
 
-        self.terminate_pod(&pod.id).await
+    /// Run `bench` on the pod of `lease` over SSH (`None`: not run), after
+    /// waiting up to `ssh_timeout` for its SSH server.
+    #[cfg(feature = "ssh")]
+    async fn run_benchmark(
+        &self,
+        lease: &PodLease,
+        bench: &HostBenchmark,
+        ssh_timeout: Duration,
+    ) -> Option<BenchmarkResult> {
+        if let Err(e) = self.wait_for_ssh(lease, ssh_timeout).await {
+            return Some(BenchmarkResult {
+                failures: vec![format!("benchmark did not run: {e}")],
+                ..BenchmarkResult::default()
+            });
+        }
+        let timeout = Duration::from_millis(bench.timeout_ms);
+        let output = match self.login() {
+            Ok(login) => login.exec_lease(lease, &bench.command, timeout).await,
+            Err(e) => Err(e),
+        };
+        Some(match output {
+            Ok(output) if output.success() => bench.evaluate(&output.stdout),
+            Ok(output) => BenchmarkResult {
+                failures: vec![format!(
+                    "benchmark exited with {:?}: {}",
+                    output.status,
+                    output.stderr.trim()
+                )],
+                ..BenchmarkResult::default()
+            },
+            Err(e) => BenchmarkResult {
+                failures: vec![format!("benchmark did not run: {e}")],
+                ..BenchmarkResult::default()
+            },
+        })
     }
 
-    /// Find a pod by name.
-    async fn find_pod_by_name(&self, name: &str) -> Result<Option<PodInfo>, OrchestratorError> {
-        let pods = self.list_pods().await?;
-        Ok(pods.into_iter().find(|p| p.name.as_deref() == Some(name)))
+    /// Without the `ssh` feature, host benchmarks cannot run: hosts are
+    /// accepted unchecked.
+    #[cfg(not(feature = "ssh"))]
+    #[allow(clippy::unused_async)] // Same signature as with the feature.
+    async fn run_benchmark(
+        &self,
+        lease: &PodLease,
+        _bench: &HostBenchmark,
+        _ssh_timeout: Duration,
+    ) -> Option<BenchmarkResult> {
+        log_warn!(
+            "host benchmark of pod {} skipped: built without the `ssh` feature",
+            lease.id
+        );
+        None
+    }
+
+    /// Wait until the SSH server of `lease` sends its banner: a mapped port
+    /// 22 does not mean `sshd` is up yet.
+    #[cfg(feature = "ssh")]
+    async fn wait_for_ssh(
+        &self,
+        lease: &PodLease,
+        timeout: Duration,
+    ) -> Result<(), OrchestratorError> {
+        let not_ready = || OrchestratorError::ProbesFailed {
+            pod_id: lease.id.clone(),
+            failing: vec![format!("22={}", ReadinessProbe::SshBanner)],
+        };
+        let (host, port) = lease.ssh_endpoint().ok_or_else(not_ready)?;
+        let start = Instant::now();
+        let mut poll = AdaptivePoll::new(&self.cfg);
+        while !ReadinessProbe::SshBanner
+            .check(&self.http, host, port)
+            .await
+        {
+            if start.elapsed() > timeout {
+                return Err(not_ready());
+            }
+            poll.wait("no_ssh_banner").await;
+        }
+        Ok(())
+    }
+
+    /// Record that `data_center_id` was given up on.
+    fn failover(
+        &self,
+        data_center_id: &str,
+        reason: FailoverReason,
+        pod_id: Option<String>,
+        detail: String,
+    ) -> DataCenterFailover {
+        let failover = DataCenterFailover {
+            data_center_id: data_center_id.to_string(),
+            reason,
+            pod_id,
+            detail,
+        };
+        log_warn!(
+            "giving up on data center {data_center_id} ({}): {}",
+            reason.as_str(),
+            failover.detail
+        );
+        self.metrics.counter(
+            runpod_metrics::DATA_CENTER_FAILOVERS,
+            1,
+            &[("data_center", data_center_id), ("reason", reason.as_str())],
+        );
+        failover
+    }
+
+    /// Refuse to create or start pod `pod_name` once the spend cap is reached.
+    fn check_spend_cap(&self, pod_name: &str) -> Result<(), OrchestratorError> {
+        let Some(guard) = &self.spend_guard else {
+            return Ok(());
+        };
+        let spend_usd = guard.spend_for(pod_name, now_unix_ms());
+        let max_spend_usd = guard.cap().max_spend_usd;
+        if spend_usd < max_spend_usd {
+            return Ok(());
+        }
+        log_warn!("spend cap reached (${spend_usd:.2}), not creating or starting {pod_name}");
+        Err(OrchestratorError::SpendCapReached {
+            pod_name: pod_name.to_string(),
+            spend_usd,
+            max_spend_usd,
+        })
+    }
+
+    /// Run the confirmation hook, if any.
+    async fn confirm_creation(&self, estimate: CostEstimate) -> Result<(), OrchestratorError> {
+        let Some(confirm) = &self.confirm else {
+            return Ok(());
+        };
+
+        let pod_name = estimate.pod_name.clone();
+        let hourly_usd = estimate.hourly_usd;
+        if confirm(estimate).await {
+            Ok(())
+        } else {
+            Err(OrchestratorError::CreationDeclined {
+                pod_name,
+                hourly_usd,
+            })
+        }
+    }
+
+    /// Provisioning configuration for `spec` (environment + spec fields).
+    fn provision_config(&self, spec: &PodSpec) -> Result<RunpodProvisionConfig, OrchestratorError> {
+        let mut provision_cfg = match &self.provision {
+            Some(provision_cfg) => provision_cfg.clone(),
+            None => RunpodProvisionConfig::from_env()
+                .map_err(|e| OrchestratorError::Provision(Box::new(e)))?,
+        };
+        provision_cfg.api_key.clone_from(&self.cfg.api_key);
+        provision_cfg.api_version = self.cfg.api_version;
+        provision_cfg.rest_url.clone_from(&self.cfg.rest_url);
+        provision_cfg.graphql_url.clone_from(&self.cfg.graphql_url);
+        provision_cfg.timeout_ms = self.cfg.timeout_ms;
+        provision_cfg.http.clone_from(&self.cfg.http);
+        provision_cfg.retry.clone_from(&self.cfg.retry);
+
+        provision_cfg.name.clone_from(&spec.name);
+        provision_cfg.image_name.clone_from(&spec.image_name);
+        provision_cfg.gpu_type_ids.clone_from(&spec.gpu_type_ids);
+        provision_cfg.ports.clone_from(&spec.ports);
+        if let Some(gpu_count) = spec.gpu_count {
+            provision_cfg.gpu_count = gpu_count;
+        }
+        if let Some(volume_id) = &spec.network_volume_id {
+            provision_cfg.network_volume_id = Some(volume_id.clone());
+        }
+        if let Some(mount_path) = &spec.volume_mount_path {
+            provision_cfg.volume_mount_path.clone_from(mount_path);
+        }
+        if let Some(cmd) = &spec.docker_start_cmd {
+            provision_cfg.docker_start_cmd.clone_from(cmd);
+        }
+        if let Some(data_center_id) = &spec.data_center_id {
+            provision_cfg.data_center_ids = vec![data_center_id.clone()];
+        }
+        if !spec.labels.is_empty() {
+            let labels = runpod_labels::encode_labels(&spec.labels);
+            provision_cfg.pod_env.insert(LABELS_ENV.to_string(), labels);
+        }
+        if let Some(restore) = &spec.restore {
+            Self::apply_restore(&mut provision_cfg, restore)?;
+        }
+        if spec.utility {
+            provision_cfg.compute_type = "CPU".to_string();
+            provision_cfg.gpu_fallback = GpuFallback::Off;
+            provision_cfg.global_networking = true;
+            provision_cfg.interruptible = false;
+            provision_cfg.bid_per_gpu = None;
+            provision_cfg.bid_strategy = None;
+        }
+        if let Some(tracker) = &self.availability {
+            Self::apply_availability(&mut provision_cfg, tracker);
+        }
+
+        Ok(provision_cfg)
+    }
+
+    /// Order GPU types and data centers by availability history, asking
+    /// `RunPod` to follow the order when it differs from the configured one.
+    fn apply_availability(
+        provision_cfg: &mut RunpodProvisionConfig,
+        tracker: &AvailabilityTracker,
+    ) {
+        let now = now_unix_ms();
+        let data_center_id = match provision_cfg.data_center_ids.as_slice() {
+            [id] => Some(id.as_str()),
+            _ => None,
+        };
+        let gpu_type_ids = tracker.rank_gpu_types(&provision_cfg.gpu_type_ids, data_center_id, now);
+        if gpu_type_ids != provision_cfg.gpu_type_ids {
+            log_debug!(
+                "GPU types ordered by availability: {}",
+                gpu_type_ids.join(", ")
+            );
+            provision_cfg.gpu_type_ids = gpu_type_ids;
+            provision_cfg.gpu_type_priority = Some("custom".to_string());
+        }
+
+        let data_center_ids = tracker.rank_data_centers(
+            &provision_cfg.gpu_type_ids,
+            &provision_cfg.data_center_ids,
+            now,
+        );
+        if data_center_ids != provision_cfg.data_center_ids {
+            log_debug!(
+                "data centers ordered by availability: {}",
+                data_center_ids.join(", ")
+            );
+            provision_cfg.data_center_ids = data_center_ids;
+            provision_cfg.data_center_priority = Some("custom".to_string());
+        }
     }
 
-    /// Check if a pod is compatible with the current configuration.
-    fn is_compatible(&self, pod: &PodInfo) -> bool {
-        // Check image
-        if pod.imageName.as_deref() != Some(&self.cfg.image_name) {
-            return false;
+    /// Apply snapshot settings, resolving the values of its env keys from the
+    /// pod env configuration, then the process environment.
+    fn apply_restore(
+        provision_cfg: &mut RunpodProvisionConfig,
+        restore: &RestoreSettings,
+    ) -> Result<(), OrchestratorError> {
+        if let Some(interruptible) = restore.interruptible {
+            provision_cfg.interruptible = interruptible;
+        }
+        if let Some(container_disk_gb) = restore.container_disk_gb {
+            provision_cfg.container_disk_gb = container_disk_gb;
+        }
+        if let Some(volume_gb) = restore.volume_gb {
+            provision_cfg.volume_gb = volume_gb;
+        }
+
+        let mut pod_env = HashMap::new();
+        let mut missing = Vec::new();
+        for key in &restore.env_keys {
+            match provision_cfg
+                .pod_env
+                .get(key)
+                .cloned()
+                .or_else(|| env::var(key).ok())
+            {
+                Some(value) => {
+                    pod_env.insert(key.clone(), value);
+                }
+                None => missing.push(key.clone()),
+            }
         }
+        if !missing.is_empty() {
+            return Err(OrchestratorError::MissingPodEnv(missing));
+        }
+        provision_cfg.pod_env = pod_env;
+        Ok(())
+    }
+
+    /// Estimate the hourly cost of a pod from `RunPod` GPU prices.
+    ///
+    /// Price lookup failures leave `hourly_usd` unset rather than failing.
+    /// Spot pods with a bid are estimated at the bid (the most they can cost).
+    async fn estimate_cost(&self, provision_cfg: &RunpodProvisionConfig) -> CostEstimate {
+        let bid_usd = provision_cfg
+            .bid_per_gpu
+            .filter(|_| provision_cfg.interruptible)
+            .map(|bid| bid * f64::from(provision_cfg.gpu_count));
+        let hourly_usd = if bid_usd.is_some() {
+            bid_usd
+        } else {
+            self.gpu_prices(&provision_cfg.cloud_type)
+                .await
+                .ok()
+                .and_then(|prices| {
+                    provision_cfg
+                        .gpu_type_ids
+                        .iter()
+                        .filter_map(|id| prices.get(id).copied())
+                        .reduce(f64::max)
+                })
+                .map(|price| price * f64::from(provision_cfg.gpu_count))
+        };
 
-        // Check if not terminated
-        if pod.desiredStatus.as_deref() == Some("TERMINATED") {
-            return false;
+        CostEstimate {
+            pod_name: provision_cfg.name.clone(),
+            image_name: provision_cfg.image_name.clone(),
+            cloud_type: provision_cfg.cloud_type.clone(),
+            gpu_type_ids: provision_cfg.gpu_type_ids.clone(),
+            gpu_count: provision_cfg.gpu_count,
+            hourly_usd,
         }
+    }
+
+    /// GPU type ID -> price per GPU per hour for `cloud_type`.
+    async fn gpu_prices(
+        &self,
+        cloud_type: &str,
+    ) -> Result<HashMap<String, f64>, OrchestratorError> {
+        let gpu_types = self
+            .graphql_client()
+            .list_gpu_types()
+            .await
+            .map_err(|e| OrchestratorError::Provision(Box::new(e)))?;
+
+        Ok(gpu_types
+            .into_iter()
+            .filter_map(|g| g.price_for(cloud_type).map(|p| (g.id, p)))
+            .collect())
+    }
 
-        true
+    /// Network volumes are not part of the simulator's model.
+    fn volumes_supported(sim: Option<&SimulatedCloud>) -> Result<(), OrchestratorError> {
+        sim.map_or(Ok(()), |_| {
+            Err(OrchestratorError::Provision(
+                "network volumes are not available in simulate mode".into(),
+            ))
+        })
     }
 
-    /// Start a stopped pod.
-    async fn start_pod(&self, pod_id: &str) -> Result<(), OrchestratorError> {
+    /// Delete a network volume.
+    #[cfg(feature = "ssh")]
+    async fn delete_volume(&self, volume_id: &str) -> Result<(), OrchestratorError> {
         let url = format!(
-            "{}/pods/{}/start",
+            "{}/networkvolumes/{}",
             self.cfg.rest_url.trim_end_matches('/'),
-            pod_id
+            volume_id
         );
 
-        let resp = self
+        let req = self
             .http
-            .post(&url)
-            .bearer_auth(&self.cfg.api_key)
-            .send()
-            .await
-            .map_err(OrchestratorError::Http)?;
+            .delete(&url)
+            .bearer_auth(self.cfg.api_key.expose());
+        let resp = self.send(req).await?;
 
         let status = resp.status();
         if !status.is_success() {
@@ -377,142 +6078,755 @@ impl RunpodOrchestrator {
         Ok(())
     }
 
-    /// Terminate a pod.
-    async fn terminate_pod(&self, pod_id: &str) -> Result<(), OrchestratorError> {
+    /// Start the two utility pods and run the copy on the destination one.
+    ///
+    /// Every pod created is pushed to `utility_pods`, so the caller can
+    /// clean up whatever happens here.
+    #[cfg(feature = "ssh")]
+    async fn run_volume_copy(
+        &self,
+        source_id: &str,
+        dest_id: &str,
+        started: Instant,
+        utility_pods: &mut Vec<String>,
+    ) -> Result<(), OrchestratorError> {
+        // Served on the private network only, once per connection.
+        let serve =
+            format!("while true; do tar -cf - -C /src . | nc -l -p {COPY_STREAM_PORT}; done");
+        let (source_pod, _) = self
+            .create_copy_pod(source_id, "/src", &[], serve, started, utility_pods)
+            .await?;
+
+        let ssh_port = vec!["22/tcp".to_string()];
+        let sshd = COPY_SSHD_SCRIPT.to_string();
+        let (dest_pod, dest_name) = self
+            .create_copy_pod(dest_id, "/dst", &ssh_port, sshd, started, utility_pods)
+            .await?;
+        let dest = self
+            .wait_for_ready(&dest_pod, &dest_name, &ssh_port, started)
+            .await?;
+
+        let timeout = Duration::from_millis(self.cfg.volume_copy_timeout_ms);
+        self.wait_for_ssh(&dest, timeout).await?;
+        let login = self
+            .login()
+            .map_err(|e| OrchestratorError::VolumeCopy(e.to_string()))?;
+        let host = runpod_network::private_host(&source_pod);
+        // Wait for the source to listen (a probe costs it one archive), then copy.
+        let script = format!(
+            "until nc {host} {COPY_STREAM_PORT} </dev/null >/dev/null 2>&1; do sleep 5; done; \
+             set -o pipefail; nc {host} {COPY_STREAM_PORT} | tar -xf - -C /dst"
+        );
+        let output = login.exec_lease(&dest, &script, timeout).await;
+        if let (Some(known_hosts), Some((ip, port))) = (&login.known_hosts, dest.ssh_endpoint()) {
+            // The address goes to another pod, with another key, after this one.
+            let _ = known_hosts.forget(ip, port);
+        }
+        match output {
+            Ok(output) if output.success() => Ok(()),
+            Ok(output) => Err(OrchestratorError::VolumeCopy(format!(
+                "copy exited with {:?}: {}",
+                output.status,
+                output.stderr.trim()
+            ))),
+            Err(SshError::CommandTimeout { .. }) => Err(OrchestratorError::VolumeCopy(format!(
+                "not finished after {}s",
+                timeout.as_secs()
+            ))),
+            Err(e) => Err(OrchestratorError::VolumeCopy(e.to_string())),
+        }
+    }
+
+    /// Create a CPU utility pod mounting `volume_id` on the global network,
+    /// without waiting for it to be ready. Returns its ID and name.
+    #[cfg(feature = "ssh")]
+    async fn create_copy_pod(
+        &self,
+        volume_id: &str,
+        mount_path: &str,
+        ports: &[String],
+        script: String,
+        started: Instant,
+        utility_pods: &mut Vec<String>,
+    ) -> Result<(String, String), OrchestratorError> {
+        let spec = PodSpec {
+            name: format!("volume-copy-{volume_id}{}", mount_path.replace('/', "-")),
+            image_name: self.cfg.volume_copy_image.clone(),
+            gpu_type_ids: Vec::new(),
+            gpu_count: Some(0),
+            ports: ports.to_vec(),
+            network_volume_id: Some(volume_id.to_string()),
+            volume_mount_path: Some(mount_path.to_string()),
+            docker_start_cmd: Some(vec!["sh".to_string(), "-c".to_string(), script]),
+            data_center_id: None,
+            labels: BTreeMap::new(),
+            selector: None,
+            restore: None,
+            utility: true,
+        };
+
+        self.report_progress(&spec.name, ProgressPhase::Creating, started, None);
+        let created = self.create_new_pod(&spec).await?;
+        utility_pods.push(created.id.clone());
+
+        self.verify_volume_attached(&created.id, volume_id).await?;
+        Ok((created.id, spec.name))
+    }
+
+    /// GraphQL client built from this orchestrator's configuration and
+    /// sharing its HTTP client.
+    const fn graphql_client(&self) -> &RunpodClient {
+        &self.graphql
+    }
+
+    /// Create the cluster's nodes (after confirmation), without waiting.
+    async fn create_cluster_nodes(
+        &self,
+        spec: &ClusterSpec,
+    ) -> Result<ClusterInfo, OrchestratorError> {
+        self.check_spend_cap(&spec.name)?;
+        if let Some(sim) = &self.sim {
+            self.confirm_creation(Self::cluster_estimate(spec, "SIMULATED", None))
+                .await?;
+
+            let network_volume = spec
+                .network_volume_id
+                .as_deref()
+                .map(|id| (id, "/workspace"));
+            let pods = (0..spec.node_count)
+                .map(|index| ClusterPod {
+                    id: sim
+                        .create_pod(
+                            &format!("{}-{index}", spec.name),
+                            &spec.image_name,
+                            &spec.ports,
+                            network_volume,
+                        )
+                        .id,
+                    clusterIp: Some(format!("10.65.0.{}", index.saturating_add(2))),
+                    clusterIdx: Some(index),
+                })
+                .collect();
+            return Ok(ClusterInfo {
+                id: format!("sim-cluster-{}", spec.name),
+                name: Some(spec.name.clone()),
+                pods,
+            });
+        }
+
+        let mut provision_cfg = self.provision_config(&PodSpec {
+            name: spec.name.clone(),
+            image_name: spec.image_name.clone(),
+            gpu_type_ids: vec![spec.gpu_type_id.clone()],
+            gpu_count: Some(spec.gpus_per_node),
+            ports: spec.ports.clone(),
+            network_volume_id: spec.network_volume_id.clone(),
+            volume_mount_path: None,
+            docker_start_cmd: None,
+            data_center_id: None,
+            labels: self.cfg.pod_labels.clone(),
+            selector: None,
+            restore: None,
+            utility: false,
+        })?;
+        provision_cfg.gpu_count = spec.gpus_per_node;
+
+        if self.confirm.is_some() {
+            let hourly_usd = self
+                .gpu_prices(&provision_cfg.cloud_type)
+                .await
+                .ok()
+                .and_then(|prices| prices.get(&spec.gpu_type_id).copied())
+                .map(|price| price * f64::from(spec.gpus_per_node.saturating_mul(spec.node_count)));
+            self.confirm_creation(Self::cluster_estimate(
+                spec,
+                &provision_cfg.cloud_type,
+                hourly_usd,
+            ))
+            .await?;
+        }
+
+        let env: Vec<EnvVar> = provision_cfg
+            .pod_env
+            .into_iter()
+            .map(|(key, value)| EnvVar { key, value })
+            .collect();
+        let input = CreateClusterInput {
+            clusterName: spec.name.clone(),
+            gpuTypeId: spec.gpu_type_id.clone(),
+            podCount: spec.node_count,
+            gpuCountPerPod: spec.gpus_per_node,
+            cluster_type: "TRAINING".to_string(),
+            imageName: spec.image_name.clone(),
+            containerDiskInGb: provision_cfg.container_disk_gb,
+            volumeInGb: provision_cfg.volume_gb,
+            volumeMountPath: provision_cfg.volume_mount_path,
+            ports: (!spec.ports.is_empty()).then(|| spec.ports.join(",")),
+            env: (!env.is_empty()).then_some(env),
+            networkVolumeId: spec.network_volume_id.clone(),
+        };
+
+        let cluster = self
+            .graphql_client()
+            .create_cluster(input)
+            .await
+            .map_err(|e| OrchestratorError::Provision(Box::new(e)))?;
+
+        self.metrics.counter(
+            runpod_metrics::PODS_CREATED,
+            u64::from(spec.node_count),
+            &[("cloud_type", provision_cfg.cloud_type.as_str())],
+        );
+
+        Ok(cluster)
+    }
+
+    fn cluster_estimate(
+        spec: &ClusterSpec,
+        cloud_type: &str,
+        hourly_usd: Option<f64>,
+    ) -> CostEstimate {
+        CostEstimate {
+            pod_name: spec.name.clone(),
+            image_name: spec.image_name.clone(),
+            cloud_type: cloud_type.to_string(),
+            gpu_type_ids: vec![spec.gpu_type_id.clone()],
+            gpu_count: spec.gpus_per_node.saturating_mul(spec.node_count),
+            hourly_usd,
+        }
+    }
+
+    /// Delete a cluster (in simulate mode, its member pods).
+    async fn delete_cluster(&self, cluster: &ClusterInfo) -> Result<(), OrchestratorError> {
+        if let Some(sim) = &self.sim {
+            for pod in &cluster.pods {
+                let _ = sim.terminate_pod(&pod.id);
+            }
+            return Ok(());
+        }
+
+        self.graphql_client()
+            .delete_cluster(&cluster.id)
+            .await
+            .map_err(|e| OrchestratorError::Provision(Box::new(e)))
+    }
+
+    /// Get detailed pod information.
+    async fn get_pod(&self, pod_id: &str) -> Result<Option<PodDetails>, OrchestratorError> {
+        if let Some(sim) = &self.sim {
+            return Ok(sim.get_pod(pod_id));
+        }
+
         let url = format!(
             "{}/pods/{}",
             self.cfg.rest_url.trim_end_matches('/'),
             pod_id
         );
 
-        let resp = self
-            .http
-            .delete(&url)
-            .bearer_auth(&self.cfg.api_key)
-            .send()
-            .await
-            .map_err(OrchestratorError::Http)?;
+        let req = self.http.get(&url).bearer_auth(self.cfg.api_key.expose());
+        let resp = self.send(req).await?;
 
         let status = resp.status();
+        if status.as_u16() == 404 {
+            return Ok(None);
+        }
+
         if !status.is_success() {
             let body = resp.text().await.unwrap_or_default();
             return Err(OrchestratorError::Api { status, body });
         }
 
-        Ok(())
+        let body = resp.bytes().await.map_err(OrchestratorError::Http)?;
+        let pod: PodDetails = self
+            .cfg
+            .api_version
+            .decode_slice(ApiResource::Pod, body)
+            .map_err(OrchestratorError::Json)?;
+
+        Ok(Some(pod))
+    }
+
+    /// Wait for a pod to be ready (has publicIp and required port mappings).
+    async fn wait_for_ready(
+        &self,
+        pod_id: &str,
+        pod_name: &str,
+        required_ports: &[String],
+        started: Instant,
+    ) -> Result<PodLease, OrchestratorError> {
+        let start = Instant::now();
+        let poll = self.poll_until_ready(pod_id, pod_name, required_ports, started);
+        let result = cancellable(Some(pod_id), Box::pin(poll)).await;
+        record_step(StepKind::WaitReady, pod_id);
+        record(|report| {
+            report.ready_wait_ms = report.ready_wait_ms.saturating_add(millis_since(start));
+        });
+        result
+    }
+
+    async fn poll_until_ready(
+        &self,
+        pod_id: &str,
+        pod_name: &str,
+        ports: &[String],
+        started: Instant,
+    ) -> Result<PodLease, OrchestratorError> {
+        let start = Instant::now();
+        let required_ports = self.gating_ports(ports);
+        let timeout = Duration::from_millis(self.cfg.ready_timeout_ms);
+        let mut poll = AdaptivePoll::new(&self.cfg);
+        let (source, graphql) = self.readiness_reader();
+
+        let mut missing_ports: Vec<String> = Vec::new();
+        let mut failing_probes: Vec<String> = Vec::new();
+        let mut check_failed = false;
+        loop {
+            if start.elapsed() > timeout {
+                return Err(not_ready_error(
+                    pod_id,
+                    timeout,
+                    missing_ports,
+                    failing_probes,
+                    check_failed,
+                ));
+            }
+
+            let Some(pod) = self.readiness_view(pod_id, source, graphql).await? else {
+                return Err(OrchestratorError::PodNotFound(pod_id.to_string()));
+            };
+
+            // Check if running
+            if pod.desired_status.as_deref() != Some("RUNNING") {
+                self.report_progress(pod_name, ProgressPhase::WaitingForIp, started, Some(start));
+                poll.wait("not_running").await;
+                continue;
+            }
+
+            let lease = if self.cfg.exposure == ExposureMode::Proxy {
+                let proxy_endpoints = self.proxy_endpoints(&pod.id, ports);
+                missing_ports = self
+                    .unreachable_proxy_ports(&required_ports, &proxy_endpoints)
+                    .await;
+                if !missing_ports.is_empty() {
+                    let phase = ProgressPhase::WaitingForPorts;
+                    self.report_progress(pod_name, phase, started, Some(start));
+                    poll.wait("proxy_unreachable").await;
+                    continue;
+                }
+
+                pod.into_lease(Some(proxy_endpoints))
+            } else {
+                // Check for public IP
+                let Some(public_ip) = pod.reachable_ip().map(str::to_string) else {
+                    let phase = ProgressPhase::WaitingForIp;
+                    self.report_progress(pod_name, phase, started, Some(start));
+                    poll.wait("no_ip").await;
+                    continue;
+                };
+
+                // Check if required ports are mapped
+                missing_ports = pod.unmapped_ports(&required_ports);
+
+                if !missing_ports.is_empty() {
+                    let phase = ProgressPhase::WaitingForPorts;
+                    self.report_progress(pod_name, phase, started, Some(start));
+                    poll.wait("no_ports").await;
+                    continue;
+                }
+
+                // Check that the probed services answer
+                failing_probes = self
+                    .failing_probes(&public_ip, &pod.port_mappings, &required_ports)
+                    .await;
+                if !failing_probes.is_empty() {
+                    let phase = ProgressPhase::WaitingForPorts;
+                    self.report_progress(pod_name, phase, started, Some(start));
+                    poll.wait("probe_failed").await;
+                    continue;
+                }
+
+                pod.into_lease(None)
+            };
+
+            // Application-level check, last
+            if let Some(check) = &self.readiness_check {
+                let remaining = timeout.saturating_sub(start.elapsed());
+                check_failed = !tokio::time::timeout(remaining, check(lease.clone()))
+                    .await
+                    .unwrap_or(false);
+                if check_failed {
+                    let phase = ProgressPhase::WaitingForPorts;
+                    self.report_progress(pod_name, phase, started, Some(start));
+                    poll.wait("check_failed").await;
+                    continue;
+                }
+            }
+
+            // Pod is ready!
+            self.report_progress(pod_name, ProgressPhase::Ready, started, Some(start));
+            self.metrics.histogram(
+                runpod_metrics::READY_WAIT_MS,
+                runpod_metrics::elapsed_ms(start),
+                &[],
+            );
+            return Ok(lease);
+        }
+    }
+
+    /// Port specs among `ports` readiness waits on (all without `ready_ports`).
+    fn gating_ports(&self, ports: &[String]) -> Vec<String> {
+        if self.cfg.ready_ports.is_empty() {
+            return ports.to_vec();
+        }
+        ports
+            .iter()
+            .filter(|spec| {
+                parse_port_spec(spec).is_some_and(|(port, _)| self.cfg.ready_ports.contains(&port))
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Port specs among `port_specs` whose readiness probe fails.
+    ///
+    /// Ports without a probe, or not mapped, pass; simulated pods are not
+    /// probed.
+    async fn failing_probes(
+        &self,
+        public_ip: &str,
+        port_mappings: &HashMap<u16, u16>,
+        port_specs: &[String],
+    ) -> Vec<String> {
+        if self.sim.is_some() {
+            return Vec::new();
+        }
+
+        let mut failing = Vec::new();
+        for spec in port_specs {
+            let Some((port, _)) = parse_port_spec(spec) else {
+                continue;
+            };
+            let (Some(probe), Some(public_port)) =
+                (self.cfg.ready_probes.get(&port), port_mappings.get(&port))
+            else {
+                continue;
+            };
+            if !probe.check(&self.http, public_ip, *public_port).await {
+                log_debug!("readiness probe {probe} of port {port} failed");
+                failing.push(spec.clone());
+            }
+        }
+        failing
+    }
+
+    /// Proxy URLs of the HTTP ports among `port_specs`.
+    fn proxy_endpoints(&self, pod_id: &str, port_specs: &[String]) -> HashMap<u16, String> {
+        port_specs
+            .iter()
+            .filter_map(|spec| parse_port_spec(spec))
+            .filter(|(_, protocol)| *protocol == "http")
+            .map(|(port, _)| {
+                (
+                    port,
+                    format!("https://{pod_id}-{port}.{}", self.cfg.proxy_domain),
+                )
+            })
+            .collect()
+    }
+
+    /// Port specs not (yet) reachable through the proxy.
+    ///
+    /// A port counts as reachable once its proxy URL answers with anything
+    /// but a gateway error (the proxy returns 502 until the service listens).
+    /// TCP specs are never reachable; simulated pods are not probed.
+    async fn unreachable_proxy_ports(
+        &self,
+        port_specs: &[String],
+        proxy_endpoints: &HashMap<u16, String>,
+    ) -> Vec<String> {
+        let mut unreachable = Vec::new();
+        for spec in port_specs {
+            let Some(url) = parse_port_spec(spec).and_then(|(port, _)| proxy_endpoints.get(&port))
+            else {
+                unreachable.push(spec.clone());
+                continue;
+            };
+            if self.sim.is_some() {
+                continue;
+            }
+            let reachable = self
+                .http
+                .get(url)
+                .timeout(PROXY_PROBE_TIMEOUT)
+                .send()
+                .await
+                .is_ok_and(|resp| !matches!(resp.status().as_u16(), 502..=504));
+            if !reachable {
+                unreachable.push(spec.clone());
+            }
+        }
+        unreachable
+    }
+
+    /// Readiness source to read pods from (REST in simulation), with the
+    /// GraphQL client it needs.
+    fn readiness_reader(&self) -> (ReadinessSource, Option<&RunpodClient>) {
+        let source = if self.sim.is_some() {
+            ReadinessSource::Rest
+        } else {
+            self.cfg.readiness_source
+        };
+        let graphql = source.uses_graphql().then(|| self.graphql_client());
+        (source, graphql)
+    }
+
+    /// Read the pod's readiness data from `source`.
+    ///
+    /// With `Both`, a GraphQL failure falls back to the REST data alone.
+    async fn readiness_view(
+        &self,
+        pod_id: &str,
+        source: ReadinessSource,
+        graphql: Option<&RunpodClient>,
+    ) -> Result<Option<ReadinessView>, OrchestratorError> {
+        let rest = if source.uses_rest() {
+            match self.get_pod(pod_id).await? {
+                Some(pod) => Some(ReadinessView::from_rest(pod)),
+                None => return Ok(None),
+            }
+        } else {
+            None
+        };
+
+        let runtime = match graphql {
+            Some(client) => match client.get_pod(pod_id).await {
+                Ok(pod) => pod.map(ReadinessView::from_graphql),
+                Err(_) if rest.is_some() => None,
+                Err(e) => return Err(OrchestratorError::Provision(Box::new(e))),
+            },
+            None => None,
+        };
+
+        Ok(match (rest, runtime) {
+            (Some(rest), Some(runtime)) => Some(rest.merge(runtime)),
+            (rest, runtime) => rest.or(runtime),
+        })
     }
+}
 
-    /// Create a new pod using the provisioner.
-    async fn create_new_pod(&self) -> Result<CreatedPod, OrchestratorError> {
-        let provision_cfg = RunpodProvisionConfig::from_env()
-            .map_err(|e| OrchestratorError::Provision(e.to_string()))?;
+/// Pod fields readiness depends on, from REST and/or GraphQL.
+struct ReadinessView {
+    id: String,
+    name: Option<String>,
+    desired_status: Option<String>,
+    public_ip: Option<String>,
+    port_mappings: HashMap<u16, u16>,
+    data_center_id: Option<String>,
+    interruptible: bool,
+}
 
-        let provisioner = RunpodProvisioner::new(provision_cfg)
-            .map_err(|e| OrchestratorError::Provision(e.to_string()))?;
+impl ReadinessView {
+    fn from_rest(pod: PodDetails) -> Self {
+        let port_mappings = pod
+            .portMappings
+            .iter()
+            .flatten()
+            .filter_map(|(container, public)| Some((container.parse::<u16>().ok()?, *public)))
+            .collect();
+        Self {
+            id: pod.id,
+            name: pod.name,
+            desired_status: pod.desiredStatus,
+            public_ip: pod.publicIp,
+            port_mappings,
+            data_center_id: pod.machine.and_then(|m| m.dataCenterId),
+            interruptible: pod.interruptible.unwrap_or(false),
+        }
+    }
 
-        provisioner
-            .create_pod()
-            .await
-            .map_err(|e| OrchestratorError::Provision(e.to_string()))
+    fn from_graphql(pod: crate::runpod_client::PodDetails) -> Self {
+        // Only ports mapped on the public IP are reachable from outside;
+        // the others are internal to RunPod's network.
+        let ports: Vec<_> = pod
+            .runtime
+            .and_then(|r| r.ports)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| p.isIpPublic == Some(true))
+            .collect();
+        let public_ip = ports
+            .iter()
+            .find_map(|p| p.ip.clone().filter(|ip| !ip.is_empty()));
+        let port_mappings = ports
+            .iter()
+            .filter_map(|p| Some((p.privatePort?, p.publicPort?)))
+            .collect();
+        Self {
+            id: pod.id,
+            name: pod.name,
+            desired_status: pod.desiredStatus,
+            public_ip,
+            port_mappings,
+            data_center_id: None,
+            interruptible: false,
+        }
     }
 
-    /// Get detailed pod information.
-    async fn get_pod(&self, pod_id: &str) -> Result<Option<PodDetails>, OrchestratorError> {
-        let url = format!(
-            "{}/pods/{}",
-            self.cfg.rest_url.trim_end_matches('/'),
-            pod_id
-        );
+    /// Lease of the ready pod: reached through `proxy_endpoints` in proxy
+    /// exposure mode, its public IP and port mappings otherwise.
+    fn into_lease(self, proxy_endpoints: Option<HashMap<u16, String>>) -> PodLease {
+        let (public_ip, port_mappings, proxy_endpoints) = match proxy_endpoints {
+            Some(endpoints) => (String::new(), HashMap::new(), endpoints),
+            None => (
+                self.public_ip.unwrap_or_default(),
+                self.port_mappings,
+                HashMap::new(),
+            ),
+        };
+        PodLease {
+            id: self.id,
+            name: self.name.unwrap_or_default(),
+            public_ip,
+            port_mappings,
+            desired_status: self.desired_status.unwrap_or_default(),
+            data_center_id: self.data_center_id,
+            provenance: LeaseProvenance::default(),
+            port_remaps: HashMap::new(),
+            proxy_endpoints,
+            benchmark: None,
+            expires_ms: None,
+            interruptible: self.interruptible,
+        }
+    }
 
-        let resp = self
-            .http
-            .get(&url)
-            .bearer_auth(&self.cfg.api_key)
-            .send()
-            .await
-            .map_err(OrchestratorError::Http)?;
+    /// Public IP, when a non-empty one is reported.
+    fn reachable_ip(&self) -> Option<&str> {
+        self.public_ip.as_deref().filter(|ip| !ip.is_empty())
+    }
 
-        let status = resp.status();
-        let body = resp.text().await.unwrap_or_default();
+    /// Port specs ("22/tcp", "8888/http") among `required_ports` not mapped
+    /// to a public port yet.
+    fn unmapped_ports(&self, required_ports: &[String]) -> Vec<String> {
+        required_ports
+            .iter()
+            .filter(|port_spec| {
+                parse_port_spec(port_spec)
+                    .is_none_or(|(port, _)| !self.port_mappings.contains_key(&port))
+            })
+            .cloned()
+            .collect()
+    }
 
-        if status.as_u16() == 404 {
-            return Ok(None);
-        }
+    /// Whether the pod is reachable directly: a public IP and every port of
+    /// `required_ports` mapped (what readiness waits for before probing).
+    fn is_reachable(&self, required_ports: &[String]) -> bool {
+        self.reachable_ip().is_some() && self.unmapped_ports(required_ports).is_empty()
+    }
 
-        if !status.is_success() {
-            return Err(OrchestratorError::Api { status, body });
+    /// Combine REST data with GraphQL runtime data (REST wins on conflicts).
+    fn merge(mut self, runtime: Self) -> Self {
+        if self.public_ip.as_deref().is_none_or(str::is_empty) {
+            self.public_ip = runtime.public_ip;
+        }
+        for (container, public) in runtime.port_mappings {
+            self.port_mappings.entry(container).or_insert(public);
+        }
+        if self.desired_status.as_deref() != Some("RUNNING")
+            && runtime.desired_status.as_deref() == Some("RUNNING")
+        {
+            self.desired_status = runtime.desired_status;
         }
+        self
+    }
+}
 
-        let pod: PodDetails = serde_json::from_str(&body)
-            .map_err(|e| OrchestratorError::Json(e.to_string()))?;
+/// Adaptive readiness polling: short intervals first, backing off toward a
+/// ceiling, with an immediate re-check whenever the observed state changes.
+struct AdaptivePoll {
+    initial: Duration,
+    max: Duration,
+    factor: f64,
+    current: Duration,
+    last_state: Option<&'static str>,
+}
 
-        Ok(Some(pod))
+impl AdaptivePoll {
+    fn new(cfg: &RunpodOrchestratorConfig) -> Self {
+        let max = Duration::from_millis(cfg.poll_interval_ms);
+        let initial = Duration::from_millis(cfg.poll_initial_ms).min(max);
+        Self {
+            initial,
+            max,
+            factor: cfg.poll_backoff.max(1.0),
+            current: initial,
+            last_state: None,
+        }
     }
 
-    /// Wait for a pod to be ready (has publicIp and required port mappings).
-    async fn wait_for_ready(&self, pod_id: &str) -> Result<PodLease, OrchestratorError> {
-        let start = std::time::Instant::now();
-        let timeout = Duration::from_millis(self.cfg.ready_timeout_ms);
-        let poll_interval = Duration::from_millis(self.cfg.poll_interval_ms);
-
-        loop {
-            if start.elapsed() > timeout {
-                return Err(OrchestratorError::Timeout);
-            }
+    /// Wait before the next check, given the state just observed.
+    async fn wait(&mut self, state: &'static str) {
+        let changed = self.last_state.is_some_and(|last| last != state);
+        self.last_state = Some(state);
 
-            if let Some(pod) = self.get_pod(pod_id).await? {
-                // Check if running
-                if pod.desiredStatus.as_deref() != Some("RUNNING") {
-                    tokio::time::sleep(poll_interval).await;
-                    continue;
-                }
+        if changed {
+            // Progress was made: re-check now, then poll quickly again.
+            self.current = self.initial;
+            return;
+        }
 
-                // Check for public IP
-                let public_ip = match &pod.publicIp {
-                    Some(ip) if !ip.is_empty() => ip.clone(),
-                    _ => {
-                        tokio::time::sleep(poll_interval).await;
-                        continue;
-                    }
-                };
+        tokio::time::sleep(self.current).await;
+        self.current = self.current.mul_f64(self.factor).min(self.max);
+    }
+}
 
-                // Build port mappings
-                let mut port_mappings = HashMap::new();
-                if let Some(mappings) = &pod.portMappings {
-                    for (container_port_str, public_port) in mappings {
-                        if let Ok(container_port) = container_port_str.parse::<u16>() {
-                            port_mappings.insert(container_port, *public_port);
-                        }
-                    }
-                }
+// ============================================================================
+// Request types
+// ============================================================================
 
-                // Check if required ports are mapped
-                let has_required_ports = self.cfg.required_ports.iter().all(|port_spec| {
-                    // Parse "22/tcp" or "8888/http"
-                    if let Some(port_str) = port_spec.split('/').next()
-                        && let Ok(port) = port_str.parse::<u16>()
-                    {
-                        return port_mappings.contains_key(&port);
-                    }
-                    false
-                });
+/// Mutable pod fields for `update_pod()`. Unset fields are left unchanged.
+///
+/// `Debug` shows the keys of `env`, not its values.
+#[derive(Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PodUpdate {
+    /// New pod name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// New container image.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_name: Option<String>,
+    /// New environment variables (replaces the whole set).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<HashMap<String, String>>,
+    /// New exposed ports (format: "22/tcp", "8888/http").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ports: Option<Vec<String>>,
+    /// New pod volume size in GB (pod volumes can only grow).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume_in_gb: Option<u32>,
+}
 
-                if !has_required_ports {
-                    tokio::time::sleep(poll_interval).await;
-                    continue;
-                }
+impl PodUpdate {
+    /// Check whether no field is set.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.image_name.is_none()
+            && self.env.is_none()
+            && self.ports.is_none()
+            && self.volume_in_gb.is_none()
+    }
+}
 
-                // Pod is ready!
-                return Ok(PodLease {
-                    id: pod.id,
-                    name: pod.name.unwrap_or_default(),
-                    public_ip,
-                    port_mappings,
-                    desired_status: pod.desiredStatus.unwrap_or_default(),
-                });
-            }
-            return Err(OrchestratorError::PodNotFound(pod_id.to_string()));
-        }
+impl fmt::Debug for PodUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PodUpdate")
+            .field("name", &self.name)
+            .field("image_name", &self.image_name)
+            .field("env", &self.env.as_ref().map(RedactedEnv))
+            .field("ports", &self.ports)
+            .field("volume_in_gb", &self.volume_in_gb)
+            .finish()
     }
 }
 
@@ -521,7 +6835,9 @@ impl RunpodOrchestrator {
 // ============================================================================
 
 /// Basic pod information from list endpoint.
-#[derive(Debug, Clone, Deserialize)]
+///
+/// `Debug` shows the keys of `env`, not its values.
+#[derive(Clone, Deserialize)]
 #[allow(non_snake_case)]
 pub struct PodInfo {
     /// Pod ID.
@@ -534,11 +6850,130 @@ pub struct PodInfo {
     pub imageName: Option<String>,
     /// Machine ID.
     pub machineId: Option<String>,
+    /// Cost per hour in USD, as billed by `RunPod`.
+    #[serde(default)]
+    pub costPerHr: Option<f64>,
+    /// Pod environment variables.
+    #[serde(default)]
+    pub env: Option<HashMap<String, String>>,
+    /// When the pod was last started (UTC, e.g. `2024-07-12T19:14:40.144Z`).
+    #[serde(default)]
+    pub lastStartedAt: Option<String>,
 }
 
-/// Detailed pod information.
+impl PodInfo {
+    /// Labels of the pod (see `runpod_labels`).
+    #[must_use]
+    pub fn labels(&self) -> BTreeMap<String, String> {
+        self.env
+            .as_ref()
+            .map(runpod_labels::labels_from_env)
+            .unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for PodInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PodInfo")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("desiredStatus", &self.desiredStatus)
+            .field("imageName", &self.imageName)
+            .field("machineId", &self.machineId)
+            .field("costPerHr", &self.costPerHr)
+            .field("env", &self.env.as_ref().map(RedactedEnv))
+            .field("lastStartedAt", &self.lastStartedAt)
+            .finish()
+    }
+}
+
+impl From<PodDetails> for PodInfo {
+    fn from(details: PodDetails) -> Self {
+        Self {
+            id: details.id,
+            name: details.name,
+            desiredStatus: details.desiredStatus,
+            imageName: details.imageName,
+            machineId: details.machineId,
+            costPerHr: details.costPerHr,
+            env: details.env,
+            lastStartedAt: details.lastStartedAt,
+        }
+    }
+}
+
+/// Filter of `pods_stream()`.
+///
+/// Every `None` field matches any pod. The status and image filters are
+/// sent to the API (`desiredStatus`, `imageName`) and checked again on
+/// each pod; the name and label filters are applied client-side.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PodFilter {
+    /// Substring of the pod name.
+    pub name_contains: Option<String>,
+    /// Desired status, e.g. "RUNNING" or "EXITED".
+    pub desired_status: Option<String>,
+    /// Exact image name.
+    pub image_name: Option<String>,
+    /// Label selector.
+    pub selector: Option<LabelSelector>,
+}
+
+impl PodFilter {
+    /// Whether `pod` passes the filter.
+    #[must_use]
+    pub fn matches(&self, pod: &PodInfo) -> bool {
+        let name_ok = self
+            .name_contains
+            .as_deref()
+            .is_none_or(|part| pod.name.as_deref().is_some_and(|name| name.contains(part)));
+        let status_ok = self
+            .desired_status
+            .as_deref()
+            .is_none_or(|status| pod.desiredStatus.as_deref() == Some(status));
+        let image_ok = self
+            .image_name
+            .as_deref()
+            .is_none_or(|image| pod.imageName.as_deref() == Some(image));
+        let labels_ok = self
+            .selector
+            .as_ref()
+            .is_none_or(|selector| selector.matches(&pod.labels()));
+        name_ok && status_ok && image_ok && labels_ok
+    }
+}
+
+/// Network volume information.
 #[derive(Debug, Clone, Deserialize)]
 #[allow(non_snake_case)]
+pub struct NetworkVolume {
+    /// Volume ID.
+    pub id: String,
+    /// Volume name.
+    pub name: Option<String>,
+    /// Size in GB.
+    pub size: u32,
+    /// Data center the volume lives in.
+    #[serde(default)]
+    pub dataCenterId: Option<String>,
+}
+
+/// Result of `copy_volume()` / `clone_volume()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct VolumeCopy {
+    /// Volume copied from.
+    pub source_id: String,
+    /// Volume copied into.
+    pub dest_id: String,
+    /// Total time, including utility pod startup.
+    pub elapsed: Duration,
+}
+
+/// Detailed pod information.
+///
+/// `Debug` shows the keys of `env`, not its values.
+#[derive(Clone, Deserialize)]
+#[allow(non_snake_case)]
 pub struct PodDetails {
     /// Pod ID.
     pub id: String,
@@ -554,6 +6989,173 @@ pub struct PodDetails {
     pub portMappings: Option<HashMap<String, u16>>,
     /// Exposed ports.
     pub ports: Option<Vec<String>>,
+    /// Attached network volume ID.
+    #[serde(default)]
+    pub networkVolumeId: Option<String>,
+    /// Volume mount path.
+    #[serde(default)]
+    pub volumeMountPath: Option<String>,
+    /// Pod environment variables.
+    #[serde(default)]
+    pub env: Option<HashMap<String, String>>,
+    /// GPU type and count.
+    #[serde(default)]
+    pub gpu: Option<PodGpu>,
+    /// Container disk size in GB.
+    #[serde(default)]
+    pub containerDiskInGb: Option<u32>,
+    /// Pod volume size in GB.
+    #[serde(default)]
+    pub volumeInGb: Option<u32>,
+    /// Command run instead of the image's default `CMD`.
+    #[serde(default)]
+    pub dockerStartCmd: Option<Vec<String>>,
+    /// Whether the pod is a spot (interruptible) pod.
+    #[serde(default)]
+    pub interruptible: Option<bool>,
+    /// Machine the pod runs on.
+    #[serde(default)]
+    pub machine: Option<PodMachine>,
+    /// Machine ID.
+    #[serde(default)]
+    pub machineId: Option<String>,
+    /// Cost per hour in USD, as billed by `RunPod`.
+    #[serde(default)]
+    pub costPerHr: Option<f64>,
+    /// When the pod was last started (UTC, e.g. `2024-07-12T19:14:40.144Z`).
+    #[serde(default)]
+    pub lastStartedAt: Option<String>,
+}
+
+impl fmt::Debug for PodDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PodDetails")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("desiredStatus", &self.desiredStatus)
+            .field("imageName", &self.imageName)
+            .field("publicIp", &self.publicIp)
+            .field("portMappings", &self.portMappings)
+            .field("ports", &self.ports)
+            .field("networkVolumeId", &self.networkVolumeId)
+            .field("volumeMountPath", &self.volumeMountPath)
+            .field("env", &self.env.as_ref().map(RedactedEnv))
+            .field("gpu", &self.gpu)
+            .field("containerDiskInGb", &self.containerDiskInGb)
+            .field("volumeInGb", &self.volumeInGb)
+            .field("dockerStartCmd", &self.dockerStartCmd)
+            .field("interruptible", &self.interruptible)
+            .field("machine", &self.machine)
+            .field("machineId", &self.machineId)
+            .field("costPerHr", &self.costPerHr)
+            .field("lastStartedAt", &self.lastStartedAt)
+            .finish()
+    }
+}
+
+/// GPU attached to a pod.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PodGpu {
+    /// GPU type ID (e.g. "NVIDIA A40").
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Number of GPUs.
+    #[serde(default)]
+    pub count: Option<u32>,
+}
+
+/// Machine hosting a pod.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[allow(non_snake_case)]
+pub struct PodMachine {
+    /// Data center ID (e.g. "EU-RO-1").
+    #[serde(default)]
+    pub dataCenterId: Option<String>,
+}
+
+/// Version of the `PodSnapshot` format written by `export_pod()`.
+pub const POD_SNAPSHOT_VERSION: u32 = 1;
+
+/// Everything needed to rebuild a pod elsewhere, as saved by `export_pod()`.
+///
+/// Env values are secrets and are not exported: only their keys are, and
+/// `recreate_from_snapshot()` takes the values from the pod env configuration
+/// (`RUNPOD_POD_ENV` / `RUNPOD_POD_ENV_FILE`) or the process environment.
+/// Every field is public, so a snapshot can be edited before recreating
+/// (e.g. clear `data_center_id` to let `RunPod` place the pod anywhere).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PodSnapshot {
+    /// Snapshot format version (`POD_SNAPSHOT_VERSION`).
+    pub version: u32,
+    /// Export time (ms since UNIX epoch).
+    pub exported_at_ms: u64,
+    /// ID of the exported pod.
+    pub pod_id: String,
+    /// Pod name.
+    pub name: String,
+    /// Container image name.
+    pub image_name: String,
+    /// GPU type ID.
+    #[serde(default)]
+    pub gpu_type_id: Option<String>,
+    /// Number of GPUs.
+    #[serde(default)]
+    pub gpu_count: Option<u32>,
+    /// Whether the pod was a spot pod.
+    #[serde(default)]
+    pub interruptible: Option<bool>,
+    /// Container disk size in GB.
+    #[serde(default)]
+    pub container_disk_gb: Option<u32>,
+    /// Pod volume size in GB.
+    #[serde(default)]
+    pub volume_gb: Option<u32>,
+    /// Exposed ports (e.g. "22/tcp").
+    #[serde(default)]
+    pub ports: Vec<String>,
+    /// Command run instead of the image's default `CMD` (empty = image default).
+    #[serde(default)]
+    pub docker_start_cmd: Vec<String>,
+    /// Names of the pod environment variables (sorted; values are not exported).
+    #[serde(default)]
+    pub env_keys: Vec<String>,
+    /// Attached network volume ID.
+    #[serde(default)]
+    pub network_volume_id: Option<String>,
+    /// Volume mount path.
+    #[serde(default)]
+    pub volume_mount_path: Option<String>,
+    /// Data center the pod ran in.
+    #[serde(default)]
+    pub data_center_id: Option<String>,
+}
+
+impl PodSnapshot {
+    /// Build a snapshot from the details of a pod.
+    #[must_use]
+    pub fn from_details(pod: &PodDetails) -> Self {
+        let mut env_keys: Vec<String> = pod.env.iter().flat_map(HashMap::keys).cloned().collect();
+        env_keys.sort_unstable();
+
+        Self {
+            version: POD_SNAPSHOT_VERSION,
+            exported_at_ms: now_unix_ms(),
+            pod_id: pod.id.clone(),
+            name: pod.name.clone().unwrap_or_default(),
+            image_name: pod.imageName.clone().unwrap_or_default(),
+            gpu_type_id: pod.gpu.as_ref().and_then(|g| g.id.clone()),
+            gpu_count: pod.gpu.as_ref().and_then(|g| g.count),
+            interruptible: pod.interruptible,
+            container_disk_gb: pod.containerDiskInGb,
+            volume_gb: pod.volumeInGb,
+            ports: pod.ports.clone().unwrap_or_default(),
+            docker_start_cmd: pod.dockerStartCmd.clone().unwrap_or_default(),
+            env_keys,
+            network_volume_id: pod.networkVolumeId.clone().filter(|v| !v.is_empty()),
+            volume_mount_path: pod.volumeMountPath.clone().filter(|p| !p.is_empty()),
+            data_center_id: pod.machine.as_ref().and_then(|m| m.dataCenterId.clone()),
+        }
+    }
 }
 
 // ============================================================================
@@ -574,8 +7176,17 @@ pub enum OrchestratorError {
     },
     /// HTTP client error.
     Http(reqwest::Error),
+    /// A REST request failed (after `attempts` tries).
+    Request {
+        /// Method and path, e.g. `GET /v1/pods/abc`.
+        operation: String,
+        /// Number of attempts made.
+        attempts: u32,
+        /// The last HTTP client error.
+        source: reqwest::Error,
+    },
     /// JSON parsing error.
-    Json(String),
+    Json(serde_json::Error),
     /// API error response.
     Api {
         /// HTTP status code.
@@ -583,12 +7194,126 @@ pub enum OrchestratorError {
         /// Response body.
         body: String,
     },
-    /// Provisioning error.
-    Provision(String),
+    /// Provisioning error (from the provisioner or the GraphQL client).
+    Provision(Box<dyn std::error::Error + Send + Sync>),
     /// Pod not found.
     PodNotFound(String),
     /// Timeout waiting for pod readiness.
-    Timeout,
+    Timeout {
+        /// Pod ID.
+        pod_id: String,
+        /// The readiness timeout that elapsed.
+        timeout: Duration,
+    },
+    /// No capacity for the pod (in any allowed cloud type).
+    NoCapacity(Box<dyn std::error::Error + Send + Sync>),
+    /// Every data center of `data_center_failover` was given up on.
+    FailoverExhausted(Vec<DataCenterFailover>),
+    /// The pod lock backend failed.
+    Lock(LockError),
+    /// Another orchestrator held the pod lock for longer than `lock_wait_ms`.
+    LockTimeout(String),
+    /// The call was cancelled (see `ensure_ready_pod_cancellable()`).
+    Cancelled {
+        /// Pod that was being waited for, if any (terminated if the call
+        /// created it).
+        pod_id: Option<String>,
+    },
+    /// The confirmation hook declined the pod creation.
+    CreationDeclined {
+        /// Pod name.
+        pod_name: String,
+        /// Estimated cost per hour in USD, if known.
+        hourly_usd: Option<f64>,
+    },
+    /// The spend guard's cap is reached: no pod may be created or started.
+    SpendCapReached {
+        /// Pod name.
+        pod_name: String,
+        /// Spend counted against the cap (USD).
+        spend_usd: f64,
+        /// The cap (USD).
+        max_spend_usd: f64,
+    },
+    /// The image does not exist in its registry.
+    ImageNotFound(String),
+    /// The registry pre-check could not be completed.
+    Registry(RegistryError),
+    /// The network volume is not attached to the pod.
+    VolumeNotAttached {
+        /// Pod ID.
+        pod_id: String,
+        /// Expected network volume ID.
+        volume_id: String,
+    },
+    /// The network volume does not exist.
+    VolumeNotFound(String),
+    /// A volume copy failed or timed out.
+    VolumeCopy(String),
+    /// A terminated pod still existed after the wait timeout.
+    TerminationTimeout(String),
+    /// The env file (`RUNPOD_ENV_FILE` / `RUNPOD_POD_ENV_FILE`) could not be loaded.
+    EnvFile(EnvFileError),
+    /// No value is configured for these env keys of a `PodSnapshot`.
+    MissingPodEnv(Vec<String>),
+    /// A pod with this name already exists.
+    PodExists {
+        /// Pod name.
+        name: String,
+        /// ID of the existing pod.
+        pod_id: String,
+    },
+    /// Required port specs cannot be exposed as requested.
+    PortConflict(Vec<PortConflict>),
+    /// The pod ran, but these required ports were never mapped.
+    PortsNotExposed {
+        /// Pod ID.
+        pod_id: String,
+        /// Port specs still unmapped at the readiness timeout.
+        missing: Vec<String>,
+    },
+    /// Readiness probes still failed at the readiness timeout.
+    ProbesFailed {
+        /// Pod ID.
+        pod_id: String,
+        /// Port specs whose probe failed.
+        failing: Vec<String>,
+    },
+    /// The readiness check (`with_readiness_check()`) still failed at the
+    /// readiness timeout.
+    ReadinessCheckFailed(String),
+    /// The lease file could not be read or updated.
+    State(StateStoreError),
+    /// The pod's lease was released or reaped: it may be terminated.
+    LeaseExpired(String),
+    /// A monitored spot pod was preempted (with the `fail` policy).
+    Preempted(String),
+    /// The pod's host failed the host benchmark (the pod was terminated).
+    HostRejected {
+        /// Pod ID.
+        pod_id: String,
+        /// Benchmark result.
+        result: BenchmarkResult,
+    },
+    /// `ReconcileMode::Update` found a pod volume that cannot be updated in
+    /// place (smaller than the pod's, or another network volume): recreating
+    /// the pod would lose its volume.
+    VolumeDrift {
+        /// Pod ID.
+        pod_id: String,
+        /// What differs.
+        report: CompatibilityReport,
+    },
+    /// The persisted state policy (budget or auto-terminate) forbids leasing
+    /// the pod.
+    BlockedByPolicy {
+        /// Pod name.
+        pod_name: String,
+        /// ID of the pod terminated by the policy, if one existed.
+        terminated: Option<String>,
+        /// Policy that decided.
+        reason: PlanReason,
+    },
 }
 
 impl fmt::Display for OrchestratorError {
@@ -596,38 +7321,422 @@ impl fmt::Display for OrchestratorError {
         match self {
             Self::MissingEnv(k) => write!(f, "missing required env var: {k}"),
             Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
-            Self::Http(e) => write!(f, "http error: {e}"),
-            Self::Json(e) => write!(f, "json error: {e}"),
+            Self::Http(e) => crate::runpod_http::fmt_http_error(f, e),
+            Self::Request {
+                operation,
+                attempts,
+                source,
+            } => {
+                write!(f, "{operation} failed after {attempts} attempt(s)")?;
+                crate::runpod_http::tls_pin_mismatch(source)
+                    .map_or(Ok(()), |mismatch| write!(f, ": {mismatch}"))
+            }
+            Self::Json(_) => write!(f, "invalid json response"),
             Self::Api { status, body } => write!(f, "api error: status={status}, body={body}"),
-            Self::Provision(e) => write!(f, "provisioning error: {e}"),
+            Self::Provision(_) => write!(f, "provisioning failed"),
             Self::PodNotFound(id) => write!(f, "pod not found: {id}"),
-            Self::Timeout => write!(f, "timeout waiting for pod readiness"),
+            Self::Timeout { pod_id, timeout } => {
+                write!(f, "pod {pod_id} not ready after {timeout:?}")
+            }
+            Self::NoCapacity(_) => write!(f, "no capacity"),
+            Self::FailoverExhausted(failovers) => {
+                let tried: Vec<String> = failovers.iter().map(ToString::to_string).collect();
+                write!(f, "all data centers failed: {}", tried.join(", "))
+            }
+            Self::Lock(_) => write!(f, "lock error"),
+            Self::LockTimeout(key) => write!(f, "timeout waiting for lock {key}"),
+            Self::Cancelled { pod_id: Some(id) } => write!(f, "cancelled waiting for pod {id}"),
+            Self::Cancelled { pod_id: None } => write!(f, "cancelled"),
+            Self::CreationDeclined {
+                pod_name,
+                hourly_usd,
+            } => match hourly_usd {
+                Some(usd) => write!(f, "creation of pod {pod_name} declined (~${usd:.2}/h)"),
+                None => write!(f, "creation of pod {pod_name} declined"),
+            },
+            Self::SpendCapReached {
+                pod_name,
+                spend_usd,
+                max_spend_usd,
+            } => write!(
+                f,
+                "spend cap reached (${spend_usd:.2} of ${max_spend_usd:.2}): \
+                 pod {pod_name} not created"
+            ),
+            Self::ImageNotFound(i) => write!(f, "image not found in registry: {i}"),
+            Self::Registry(_) => write!(f, "registry error"),
+            Self::VolumeNotAttached { pod_id, volume_id } => {
+                write!(f, "network volume {volume_id} not attached to pod {pod_id}")
+            }
+            Self::VolumeNotFound(id) => write!(f, "network volume not found: {id}"),
+            Self::VolumeCopy(e) => write!(f, "volume copy failed: {e}"),
+            Self::TerminationTimeout(id) => write!(f, "timeout waiting for pod {id} to terminate"),
+            Self::EnvFile(e) => write!(f, "{e}"),
+            Self::MissingPodEnv(keys) => {
+                write!(f, "no value for snapshot pod env vars: {}", keys.join(", "))
+            }
+            Self::PodExists { name, pod_id } => write!(f, "pod {name} already exists: {pod_id}"),
+            Self::PortConflict(conflicts) => {
+                let listed: Vec<String> = conflicts.iter().map(ToString::to_string).collect();
+                write!(f, "port conflict: {}", listed.join("; "))
+            }
+            Self::PortsNotExposed { pod_id, missing } => write!(
+                f,
+                "pod {pod_id} never exposed ports: {}",
+                missing.join(", ")
+            ),
+            Self::ProbesFailed { pod_id, failing } => write!(
+                f,
+                "pod {pod_id} never passed readiness probes: {}",
+                failing.join(", ")
+            ),
+            Self::ReadinessCheckFailed(id) => {
+                write!(f, "pod {id} never passed the readiness check")
+            }
+            Self::State(_) => write!(f, "lease registry error"),
+            Self::LeaseExpired(id) => write!(f, "lease of pod {id} expired"),
+            Self::Preempted(id) => write!(f, "spot pod {id} was preempted"),
+            Self::HostRejected { pod_id, result } => write!(
+                f,
+                "host of pod {pod_id} failed the benchmark: {}",
+                result.failures.join(", ")
+            ),
+            Self::VolumeDrift { pod_id, report } => write!(
+                f,
+                "pod {pod_id} volume cannot be updated in place: {report}"
+            ),
+            Self::BlockedByPolicy {
+                pod_name,
+                terminated,
+                reason,
+            } => match terminated {
+                Some(id) => write!(
+                    f,
+                    "pod {pod_name} blocked by policy ({reason}): {id} terminated"
+                ),
+                None => write!(f, "pod {pod_name} blocked by policy ({reason})"),
+            },
         }
     }
 }
 
-impl std::error::Error for OrchestratorError {}
+impl std::error::Error for OrchestratorError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(e) | Self::Request { source: e, .. } => Some(e),
+            Self::Json(e) => Some(e),
+            Self::Provision(e) | Self::NoCapacity(e) => Some(e.as_ref()),
+            Self::Lock(e) => Some(e),
+            Self::Registry(e) => Some(e),
+            Self::EnvFile(e) => std::error::Error::source(e),
+            Self::State(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 // ============================================================================
 // Helper functions
 // ============================================================================
 
+/// Whether a compatible existing pod is reused, started or replaced by a new
+/// one, as planned by its state machine.
+///
+/// Returns the policy reason when the state machine terminates the pod
+/// (budget or auto-terminate): no pod may then be leased.
+fn planned_reuse(
+    state: &mut RunPodState,
+    pod: &PodInfo,
+) -> Result<(LeaseAction, bool, String), PlanReason> {
+    let now = now_unix_ms();
+    let desired_status = match pod.desiredStatus.as_deref() {
+        Some("EXITED") => PodDesiredStatus::Exited,
+        Some("TERMINATED") => PodDesiredStatus::Terminated,
+        _ => PodDesiredStatus::Running,
+    };
+    let snapshot = RemotePodSnapshot {
+        id: PodId::new(pod.id.clone()),
+        name: pod.name.clone().unwrap_or_default(),
+        desired_status,
+        observed_at_ms: now,
+    };
+    state.set_target(TargetStatus::Running, now);
+    let plan = state.reconcile_explained(RemoteObservation::Found(snapshot), now);
+    Ok(match plan.action {
+        PlannedAction::StartPod { .. } | PlannedAction::ResumePod { .. } => (
+            LeaseAction::Started,
+            true,
+            format!("compatible ({})", plan.reason),
+        ),
+        // The old pod is terminated before its replacement is created.
+        PlannedAction::CreatePod { .. } => (
+            LeaseAction::Replaced,
+            false,
+            format!("compatible, not reused: {}", plan.reason),
+        ),
+        PlannedAction::TerminatePod { .. } => return Err(plan.reason),
+        PlannedAction::Noop | PlannedAction::StopPod { .. } => {
+            (LeaseAction::Reused, true, "compatible".to_string())
+        }
+    })
+}
+
+fn parse_pod_name_pattern_env() -> Result<String, OrchestratorError> {
+    let pattern = env::var("RUNPOD_POD_NAME_PATTERN").unwrap_or_else(|_| "{name}-{n}".to_string());
+    if !pattern.contains("{n}") {
+        return Err(OrchestratorError::InvalidEnv {
+            key: "RUNPOD_POD_NAME_PATTERN",
+            reason: "must contain {n} (the pod index)",
+        });
+    }
+    Ok(pattern)
+}
+
+fn parse_pod_labels_env() -> Result<BTreeMap<String, String>, OrchestratorError> {
+    env::var("RUNPOD_POD_LABELS").map_or(Ok(BTreeMap::new()), |v| {
+        runpod_labels::parse_labels(&v).map_err(|_| OrchestratorError::InvalidEnv {
+            key: "RUNPOD_POD_LABELS",
+            reason: "expected comma-separated key=value labels",
+        })
+    })
+}
+
+/// `RUNPOD_RECONCILE_MODE` and the `RUNPOD_ADOPT_SELECTOR` the "adopt"
+/// mode requires.
+fn parse_reconcile_env() -> Result<(ReconcileMode, Option<LabelSelector>), OrchestratorError> {
+    let reconcile_mode = env::var("RUNPOD_RECONCILE_MODE")
+        .map_or(Some(ReconcileMode::Reuse), |v| ReconcileMode::parse(&v))
+        .ok_or(OrchestratorError::InvalidEnv {
+            key: "RUNPOD_RECONCILE_MODE",
+            reason: "expected reuse, recreate, update or adopt",
+        })?;
+    let adopt_selector = parse_selector_env("RUNPOD_ADOPT_SELECTOR")?;
+    if reconcile_mode == ReconcileMode::Adopt && adopt_selector.is_none() {
+        return Err(OrchestratorError::InvalidEnv {
+            key: "RUNPOD_ADOPT_SELECTOR",
+            reason: "required with RUNPOD_RECONCILE_MODE=adopt",
+        });
+    }
+    Ok((reconcile_mode, adopt_selector))
+}
+
+fn parse_selector_env(key: &'static str) -> Result<Option<LabelSelector>, OrchestratorError> {
+    let selector = env::var(key)
+        .ok()
+        .map(|v| LabelSelector::parse(&v))
+        .transpose()
+        .map_err(|_| OrchestratorError::InvalidEnv {
+            key,
+            reason: "expected comma-separated key=value, key!=value, key or !key",
+        })?;
+    Ok(selector.filter(|selector| !selector.is_empty()))
+}
+
 fn must_env(key: &'static str) -> Result<String, OrchestratorError> {
     env::var(key).map_err(|_| OrchestratorError::MissingEnv(key))
 }
 
 fn parse_u64_env(key: &'static str, default: u64) -> Result<u64, OrchestratorError> {
+    runpod_env::u64_var(key, default)
+        .map_err(|reason| OrchestratorError::InvalidEnv { key, reason })
+}
+
+fn parse_u32_env(key: &'static str, default: u32) -> Result<u32, OrchestratorError> {
+    runpod_env::u32_var(key, default)
+        .map_err(|reason| OrchestratorError::InvalidEnv { key, reason })
+}
+
+fn parse_f64_env(key: &'static str, default: f64) -> Result<f64, OrchestratorError> {
     env::var(key).map_or_else(
         |_| Ok(default),
         |v| {
-            v.parse::<u64>().map_err(|_| OrchestratorError::InvalidEnv {
-                key,
-                reason: "expected an unsigned integer",
-            })
+            v.parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite() && *f > 0.0)
+                .ok_or(OrchestratorError::InvalidEnv {
+                    key,
+                    reason: "expected a positive number",
+                })
         },
     )
 }
 
+fn parse_bool_env(key: &'static str, default: bool) -> Result<bool, OrchestratorError> {
+    runpod_env::bool_var(key, default)
+        .map_err(|reason| OrchestratorError::InvalidEnv { key, reason })
+}
+
+/// Parse one of a fixed set of keywords, falling back to `default` when unset.
+fn parse_choice_env<T>(
+    key: &'static str,
+    default: T,
+    parse: fn(&str) -> Option<T>,
+    reason: &'static str,
+) -> Result<T, OrchestratorError> {
+    env::var(key)
+        .map_or(Some(default), |v| parse(&v))
+        .ok_or(OrchestratorError::InvalidEnv { key, reason })
+}
+
+/// Parse "8888:8889|8890,3000:3001" (port:alternate|alternate,...).
+fn parse_port_alternates_env(
+    key: &'static str,
+) -> Result<HashMap<u16, Vec<u16>>, OrchestratorError> {
+    let invalid = || OrchestratorError::InvalidEnv {
+        key,
+        reason: "expected port:alternate|alternate,... (e.g. 8888:8889|8890)",
+    };
+    let mut alternates = HashMap::new();
+    for entry in split_csv_env(key, "") {
+        let (port, alts) = entry.split_once(':').ok_or_else(invalid)?;
+        let port = port.trim().parse::<u16>().map_err(|_| invalid())?;
+        let alts = alts
+            .split('|')
+            .map(|alt| alt.trim().parse::<u16>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        alternates.insert(port, alts);
+    }
+    Ok(alternates)
+}
+
+/// Build the `ssh` arguments reaching `user@host:port`: identity, port,
+/// `extra_opts`, then the destination. Shared by `PodLease::ssh_args()` and
+/// `SshLogin::command()`.
+pub(crate) fn ssh_destination_args<S: AsRef<str>>(
+    host: &str,
+    port: u16,
+    user: &str,
+    identity_file: Option<&Path>,
+    extra_opts: &[S],
+) -> Vec<String> {
+    let mut args = Vec::new();
+    if let Some(identity) = identity_file {
+        args.push("-i".to_string());
+        args.push(identity.display().to_string());
+    }
+    args.push("-p".to_string());
+    args.push(port.to_string());
+    args.extend(extra_opts.iter().map(|opt| opt.as_ref().to_string()));
+    args.push(format!("{user}@{host}"));
+    args
+}
+
+/// Quote `value` for a POSIX shell unless it only has safe characters.
+fn shell_arg(value: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./_-~".contains(c);
+    if !value.is_empty() && value.chars().all(safe) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+fn parse_ready_ports_env(
+    key: &'static str,
+    required: &[String],
+) -> Result<Vec<u16>, OrchestratorError> {
+    let exposed: HashSet<u16> = required
+        .iter()
+        .filter_map(|spec| parse_port_spec(spec))
+        .map(|(port, _)| port)
+        .collect();
+    split_csv_env(key, "")
+        .iter()
+        .map(|entry| {
+            let port = entry
+                .split_once('/')
+                .map_or(entry.as_str(), |(port, _)| port);
+            match port.trim().parse::<u16>() {
+                Ok(port) if exposed.contains(&port) => Ok(port),
+                _ => Err(OrchestratorError::InvalidEnv {
+                    key,
+                    reason: "expected ports (e.g. 8888 or 8888/http) listed in RUNPOD_PORTS",
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Error of a pod not ready at the readiness timeout: unmapped ports first,
+/// then failing probes, then a failing readiness check.
+fn not_ready_error(
+    pod_id: &str,
+    timeout: Duration,
+    missing_ports: Vec<String>,
+    failing_probes: Vec<String>,
+    check_failed: bool,
+) -> OrchestratorError {
+    let pod_id = pod_id.to_string();
+    if !missing_ports.is_empty() {
+        OrchestratorError::PortsNotExposed {
+            pod_id,
+            missing: missing_ports,
+        }
+    } else if !failing_probes.is_empty() {
+        OrchestratorError::ProbesFailed {
+            pod_id,
+            failing: failing_probes,
+        }
+    } else if check_failed {
+        OrchestratorError::ReadinessCheckFailed(pod_id)
+    } else {
+        OrchestratorError::Timeout { pod_id, timeout }
+    }
+}
+
+fn parse_compatibility_checks_env() -> Result<Vec<CompatibilityCheck>, OrchestratorError> {
+    split_csv_env("RUNPOD_COMPATIBILITY_CHECKS", "image")
+        .iter()
+        .map(|v| CompatibilityCheck::parse(v))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(OrchestratorError::InvalidEnv {
+            key: "RUNPOD_COMPATIBILITY_CHECKS",
+            reason: "expected image, gpu_type, gpu_count, ports, volume or env",
+        })
+}
+
+/// Stable description of a pod volume ("20 GB + vol-1").
+fn describe_volume(volume_gb: Option<u32>, network_volume_id: Option<&str>) -> String {
+    let size = volume_gb.map_or_else(|| "none".to_string(), |gb| format!("{gb} GB"));
+    match network_volume_id {
+        Some(id) => format!("{size} + {id}"),
+        None => size,
+    }
+}
+
+/// Hash of pod environment variables, independent of their order.
+fn env_hash(env: &HashMap<String, String>) -> String {
+    let mut hasher = std::hash::DefaultHasher::new();
+    env.iter().collect::<BTreeMap<_, _>>().hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+fn parse_ready_probes_env(
+    key: &'static str,
+    required: &[String],
+) -> Result<HashMap<u16, ReadinessProbe>, OrchestratorError> {
+    let Ok(raw) = env::var(key) else {
+        return Ok(HashMap::new());
+    };
+    let probes = ReadinessProbe::parse_list(&raw).ok_or(OrchestratorError::InvalidEnv {
+        key,
+        reason: "expected port=probe pairs (probes: mapped, tcp, ssh, http[:/path[:status]])",
+    })?;
+    let exposed: HashSet<u16> = required
+        .iter()
+        .filter_map(|spec| parse_port_spec(spec))
+        .map(|(port, _)| port)
+        .collect();
+    if probes.keys().any(|port| !exposed.contains(port)) {
+        return Err(OrchestratorError::InvalidEnv {
+            key,
+            reason: "probed ports must be listed in RUNPOD_PORTS",
+        });
+    }
+    Ok(probes)
+}
+
 fn split_csv_env(key: &'static str, default: &str) -> Vec<String> {
     let raw = env::var(key).unwrap_or_else(|_| default.to_string());
     raw.split(',')
@@ -635,3 +7744,210 @@ fn split_csv_env(key: &'static str, default: &str) -> Vec<String> {
         .filter(|s| !s.is_empty())
         .collect()
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::runpod_lock::MemoryLock;
+
+    const OLD_IMAGE: &str = "halldyll/test:0";
+
+    fn orchestrator(cfg: RunpodOrchestratorConfig) -> RunpodOrchestrator {
+        let image_name = cfg.image_name.clone();
+        RunpodOrchestrator::new_with_http(cfg, reqwest::Client::new())
+            .with_provision_config(RunpodProvisionConfig::new(String::new(), image_name))
+    }
+
+    /// Create a simulated pod named `name` running `image_name`.
+    fn sim_pod(orch: &RunpodOrchestrator, name: &str, image_name: &str) -> String {
+        let sim = orch.simulator().unwrap();
+        sim.create_pod(name, image_name, &orch.cfg.required_ports, None)
+            .id
+    }
+
+    fn label(orch: &RunpodOrchestrator, pod_id: &str, key: &str) {
+        let labels = BTreeMap::from([(key.to_string(), "true".to_string())]);
+        let env = HashMap::from([(
+            LABELS_ENV.to_string(),
+            runpod_labels::encode_labels(&labels),
+        )]);
+        let changes = PodUpdate {
+            env: Some(env),
+            ..PodUpdate::default()
+        };
+        let sim = orch.simulator().unwrap();
+        assert!(sim.update_pod(pod_id, &changes).is_some());
+    }
+
+    async fn decide(orch: &RunpodOrchestrator) -> Decision {
+        let spec = PodSpec::resolve(&orch.cfg, &PodOverrides::default());
+        orch.decide(&spec, None).await.unwrap()
+    }
+
+    fn existing_id(decision: &Decision) -> Option<&str> {
+        decision.existing.as_ref().map(|pod| pod.id.as_str())
+    }
+
+    fn adopt_config() -> RunpodOrchestratorConfig {
+        let mut cfg = RunpodOrchestratorConfig::simulated(ReconcileMode::Adopt);
+        cfg.adopt_selector = Some(LabelSelector::parse("halldyll.adoptable").unwrap());
+        cfg
+    }
+
+    #[tokio::test]
+    async fn every_mode_creates_a_missing_pod() {
+        let modes = [
+            ReconcileMode::Reuse,
+            ReconcileMode::Recreate,
+            ReconcileMode::Update,
+            ReconcileMode::Adopt,
+        ];
+        for mode in modes {
+            let orch = orchestrator(RunpodOrchestratorConfig::simulated(mode.clone()));
+            let decision = decide(&orch).await;
+            assert_eq!(decision.action, LeaseAction::Created, "{mode:?}");
+            assert!(decision.existing.is_none(), "{mode:?}");
+            assert!(!decision.accepted, "{mode:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn reuse_mode_reuses_a_compatible_pod() {
+        let orch = orchestrator(RunpodOrchestratorConfig::simulated(ReconcileMode::Reuse));
+        let image_name = orch.cfg.image_name.clone();
+        let id = sim_pod(&orch, "halldyll-pod", &image_name);
+
+        let decision = decide(&orch).await;
+        assert_eq!(decision.action, LeaseAction::Reused);
+        assert!(decision.accepted);
+        assert_eq!(existing_id(&decision), Some(id.as_str()));
+        assert!(!decision.adopted);
+    }
+
+    #[tokio::test]
+    async fn reuse_mode_starts_a_stopped_pod() {
+        let orch = orchestrator(RunpodOrchestratorConfig::simulated(ReconcileMode::Reuse));
+        let image_name = orch.cfg.image_name.clone();
+        let id = sim_pod(&orch, "halldyll-pod", &image_name);
+        assert!(orch.simulator().unwrap().stop_pod(&id));
+
+        let decision = decide(&orch).await;
+        assert_eq!(decision.action, LeaseAction::Started);
+        assert!(decision.accepted);
+    }
+
+    #[tokio::test]
+    async fn reuse_mode_updates_the_image_in_place() {
+        let orch = orchestrator(RunpodOrchestratorConfig::simulated(ReconcileMode::Reuse));
+        let id = sim_pod(&orch, "halldyll-pod", OLD_IMAGE);
+
+        let decision = decide(&orch).await;
+        assert_eq!(decision.action, LeaseAction::Updated);
+        assert!(decision.accepted);
+        assert_eq!(existing_id(&decision), Some(id.as_str()));
+        let update = decision.update.unwrap();
+        assert_eq!(
+            update.image_name.as_deref(),
+            Some(orch.cfg.image_name.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn recreate_mode_replaces_even_a_compatible_pod() {
+        let orch = orchestrator(RunpodOrchestratorConfig::simulated(ReconcileMode::Recreate));
+        let image_name = orch.cfg.image_name.clone();
+        let id = sim_pod(&orch, "halldyll-pod", &image_name);
+
+        let decision = decide(&orch).await;
+        assert_eq!(decision.action, LeaseAction::Replaced);
+        assert!(!decision.accepted);
+        assert_eq!(existing_id(&decision), Some(id.as_str()));
+        assert!(decision.compatibility.is_none());
+    }
+
+    #[tokio::test]
+    async fn update_mode_checks_every_property() {
+        let orch = orchestrator(RunpodOrchestratorConfig::simulated(ReconcileMode::Update));
+        let image_name = orch.cfg.image_name.clone();
+        sim_pod(&orch, "halldyll-pod", &image_name);
+
+        let decision = decide(&orch).await;
+        assert_eq!(decision.action, LeaseAction::Reused);
+        assert!(decision.compatibility.unwrap().is_compatible());
+    }
+
+    #[tokio::test]
+    async fn update_mode_updates_a_drifted_pod_in_place() {
+        let orch = orchestrator(RunpodOrchestratorConfig::simulated(ReconcileMode::Update));
+        let id = sim_pod(&orch, "halldyll-pod", OLD_IMAGE);
+
+        let decision = decide(&orch).await;
+        assert_eq!(decision.action, LeaseAction::Updated);
+        assert_eq!(existing_id(&decision), Some(id.as_str()));
+        let report = decision.compatibility.unwrap();
+        assert!(report.differs(CompatibilityCheck::Image));
+    }
+
+    #[tokio::test]
+    async fn adopt_mode_adopts_a_matching_pod() {
+        let orch = orchestrator(adopt_config());
+        let image_name = orch.cfg.image_name.clone();
+        sim_pod(&orch, "unrelated", &image_name);
+        let id = sim_pod(&orch, "spare", &image_name);
+        label(&orch, &id, "halldyll.adoptable");
+
+        let decision = decide(&orch).await;
+        assert_eq!(decision.action, LeaseAction::Reused);
+        assert!(decision.adopted);
+        assert_eq!(existing_id(&decision), Some(id.as_str()));
+        assert!(decision.reason.starts_with("adopted from spare"));
+    }
+
+    #[tokio::test]
+    async fn adopt_mode_prefers_the_pod_with_the_configured_name() {
+        let orch = orchestrator(adopt_config());
+        let image_name = orch.cfg.image_name.clone();
+        let spare = sim_pod(&orch, "spare", &image_name);
+        label(&orch, &spare, "halldyll.adoptable");
+        let id = sim_pod(&orch, "halldyll-pod", &image_name);
+
+        let decision = decide(&orch).await;
+        assert_eq!(decision.action, LeaseAction::Reused);
+        assert!(!decision.adopted);
+        assert_eq!(existing_id(&decision), Some(id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn adopt_mode_skips_incompatible_and_unlabelled_pods() {
+        let orch = orchestrator(adopt_config());
+        let image_name = orch.cfg.image_name.clone();
+        sim_pod(&orch, "unlabelled", &image_name);
+        let outdated = sim_pod(&orch, "outdated", OLD_IMAGE);
+        label(&orch, &outdated, "halldyll.adoptable");
+
+        let decision = decide(&orch).await;
+        assert_eq!(decision.action, LeaseAction::Created);
+        assert!(decision.existing.is_none());
+    }
+
+    #[tokio::test]
+    async fn adopt_mode_holds_the_lock_of_the_adopted_pod() {
+        let lock: Arc<dyn DistributedLock> = Arc::new(MemoryLock::new());
+        let orch = orchestrator(adopt_config()).with_lock(Arc::clone(&lock));
+        let image_name = orch.cfg.image_name.clone();
+        let id = sim_pod(&orch, "spare", &image_name);
+        label(&orch, &id, "halldyll.adoptable");
+        let ttl = Duration::from_mins(1);
+
+        let mut decision = decide(&orch).await;
+        assert!(decision.adopted);
+        assert!(!lock.try_acquire("pod-spare", "rival", ttl).unwrap());
+
+        decision.adoption_lock.take().unwrap().release().await;
+        assert!(lock.try_acquire("pod-spare", "rival", ttl).unwrap());
+        let retried = decide(&orch).await;
+        assert_eq!(retried.action, LeaseAction::Created);
+        assert!(retried.adoption_lock.is_none());
+    }
+}
@@ -0,0 +1,707 @@
+//! Shared pod pools.
+//!
+//! Unique responsibility: hand the pods of a fixed-size pool out to workers
+//! one at a time (`checkout()` / `checkin()`), never assigning a pod twice.
+//!
+//! Each pool pod (`"{name}-{n}"`) has its own `RunPodState`, stored in the
+//! state backend under a profile named after the pod. A checkout marks the
+//! state with a unique holder ID and saves it with compare-and-swap (checked
+//! and written under the state file's lock with the JSON backend): when two
+//! workers race for the same pod, only one save succeeds and the other moves
+//! on to the next pod. Workers of one process share the pool through an
+//! `Arc`; workers of several processes share it through the state backend.
+//!
+//! A checkout expires after `checkout_ttl_ms` unless renewed (`renew()`, or
+//! `hold()` around the work), so the pod of a worker that died is reclaimed
+//! by the next checkout.
+//!
+//! ```ignore
+//! let pool = PodPool::new(Arc::new(orchestrator), PodPoolConfig::from_env()?)?;
+//!
+//! let lease = pool.checkout().await?;
+//! pool.hold(&lease, work_on(&lease.pod)).await;
+//! pool.checkin(&lease).await?;
+//! ```
+
+use std::{
+    collections::HashMap,
+    env, fmt,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use futures_util::future::{self, Either};
+
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_log::{ErrorChain, log_debug, log_warn};
+use crate::runpod_orchestrator::{OrchestratorError, PodLease, PodOverrides, RunpodOrchestrator};
+use crate::runpod_state::{
+    self, PodId, PodUsage, RunPodState, StateStore, StateStoreError, now_unix_ms,
+    profile_state_store_from_env,
+};
+
+/// Attempts of a read-modify-write on a pod state before giving up on conflicts.
+const UPDATE_ATTEMPTS: u32 = 5;
+
+/// Shared handle to the state store of one pool pod.
+type PodStore = Arc<dyn StateStore + Send + Sync>;
+
+/// Builds the state store of a pool pod from its logical name.
+pub type PoolStoreFactory =
+    dyn Fn(&str) -> Result<Box<dyn StateStore + Send + Sync>, StateStoreError> + Send + Sync;
+
+/// Pool configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PodPoolConfig {
+    /// Pool name; pods are named `"{name}-0"`, `"{name}-1"`, ...
+    /// Env: `RUNPOD_POOL_NAME` (default: "pool")
+    pub name: String,
+
+    /// Number of pods in the pool.
+    /// Env: `RUNPOD_POOL_SIZE` (default: 1)
+    pub size: usize,
+
+    /// Maximum time `checkout()` waits for a free pod in milliseconds.
+    /// Env: `RUNPOD_POOL_CHECKOUT_TIMEOUT_MS` (default: 300000 = 5 minutes)
+    pub checkout_timeout_ms: u64,
+
+    /// Delay between two scans of the pool while every pod is taken, in
+    /// milliseconds.
+    /// Env: `RUNPOD_POOL_POLL_INTERVAL_MS` (default: 2000)
+    pub poll_interval_ms: u64,
+
+    /// Time after which a checkout that was neither renewed nor checked in
+    /// is reclaimed, in milliseconds (0 = never).
+    /// Env: `RUNPOD_POOL_CHECKOUT_TTL_MS` (default: 7200000 = 2 hours)
+    pub checkout_ttl_ms: u64,
+}
+
+impl PodPoolConfig {
+    /// Load configuration from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is invalid.
+    pub fn from_env() -> Result<Self, PoolError> {
+        runpod_env::load_dotenv().map_err(PoolError::EnvFile)?;
+
+        let name = env::var("RUNPOD_POOL_NAME")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "pool".to_string());
+
+        let size = usize::try_from(parse_u64_env("RUNPOD_POOL_SIZE", 1)?)
+            .ok()
+            .filter(|s| *s > 0)
+            .ok_or(PoolError::InvalidEnv {
+                key: "RUNPOD_POOL_SIZE",
+                reason: "expected a positive integer",
+            })?;
+
+        Ok(Self {
+            name,
+            size,
+            checkout_timeout_ms: parse_u64_env("RUNPOD_POOL_CHECKOUT_TIMEOUT_MS", 300_000)?,
+            poll_interval_ms: parse_u64_env("RUNPOD_POOL_POLL_INTERVAL_MS", 2_000)?,
+            checkout_ttl_ms: parse_u64_env("RUNPOD_POOL_CHECKOUT_TTL_MS", 7_200_000)?,
+        })
+    }
+}
+
+/// A pod checked out of a pool.
+///
+/// Give it back with `PodPool::checkin()`; until then (or until the checkout
+/// expires) no other worker gets this pod.
+#[derive(Debug, Clone)]
+pub struct PoolLease {
+    /// Logical name of the pool pod.
+    pub pod_name: String,
+    /// Unique ID of this checkout, recorded in the pod's state.
+    pub holder: String,
+    /// The ready pod.
+    pub pod: PodLease,
+}
+
+/// Fixed-size pool of pods shared by several workers.
+pub struct PodPool {
+    orchestrator: Arc<RunpodOrchestrator>,
+    cfg: PodPoolConfig,
+    store_factory: Box<PoolStoreFactory>,
+    stores: Mutex<HashMap<String, PodStore>>,
+    next_checkout: AtomicU64,
+}
+
+impl fmt::Debug for PodPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PodPool")
+            .field("cfg", &self.cfg)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PodPool {
+    /// Create a pool whose pod states live in the configured state backend
+    /// (`RUNPOD_STATE_BACKEND`, one profile per pod).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pool size is 0.
+    pub fn new(
+        orchestrator: Arc<RunpodOrchestrator>,
+        cfg: PodPoolConfig,
+    ) -> Result<Self, PoolError> {
+        Self::with_store_factory(orchestrator, cfg, Box::new(profile_state_store_from_env))
+    }
+
+    /// Create a pool with a custom state store per pod.
+    ///
+    /// The factory is called once per pod; the store is then reused for the
+    /// lifetime of the pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pool size is 0.
+    pub fn with_store_factory(
+        orchestrator: Arc<RunpodOrchestrator>,
+        cfg: PodPoolConfig,
+        store_factory: Box<PoolStoreFactory>,
+    ) -> Result<Self, PoolError> {
+        if cfg.size == 0 {
+            return Err(PoolError::InvalidEnv {
+                key: "RUNPOD_POOL_SIZE",
+                reason: "expected a positive integer",
+            });
+        }
+        Ok(Self {
+            orchestrator,
+            cfg,
+            store_factory,
+            stores: Mutex::new(HashMap::new()),
+            next_checkout: AtomicU64::new(0),
+        })
+    }
+
+    /// Get the pool configuration.
+    #[must_use]
+    pub const fn config(&self) -> &PodPoolConfig {
+        &self.cfg
+    }
+
+    /// Get the orchestrator used to bring pool pods up.
+    #[must_use]
+    pub const fn orchestrator(&self) -> &Arc<RunpodOrchestrator> {
+        &self.orchestrator
+    }
+
+    /// Logical names of the pool pods.
+    #[must_use]
+    pub fn pod_names(&self) -> Vec<String> {
+        (0..self.cfg.size)
+            .map(|n| format!("{}-{n}", self.cfg.name))
+            .collect()
+    }
+
+    /// Check out a free pod, waiting up to `checkout_timeout_ms` for one.
+    ///
+    /// The pod is brought up with `ensure_ready_pod_with()` (reused when it
+    /// already exists) before being handed out.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PoolError::Exhausted` if every pod stays checked out until the
+    /// timeout, or an error if the state backend or the orchestrator fails.
+    pub async fn checkout(&self) -> Result<PoolLease, PoolError> {
+        self.checkout_excluding(&[]).await
+    }
+
+    /// Check out a free pod other than the `excluded` pool pods (e.g. pods a
+    /// job already failed on), waiting up to `checkout_timeout_ms` for one.
+    ///
+    /// # Errors
+    ///
+    /// As `checkout()`.
+    pub async fn checkout_excluding(&self, excluded: &[String]) -> Result<PoolLease, PoolError> {
+        let started = Instant::now();
+        let timeout = Duration::from_millis(self.cfg.checkout_timeout_ms);
+        loop {
+            if let Some(lease) = Box::pin(self.try_checkout_excluding(excluded)).await? {
+                return Ok(lease);
+            }
+            if started.elapsed() >= timeout {
+                return Err(PoolError::Exhausted {
+                    pool: self.cfg.name.clone(),
+                    size: self.cfg.size,
+                });
+            }
+            tokio::time::sleep(Duration::from_millis(self.cfg.poll_interval_ms.max(1))).await;
+        }
+    }
+
+    /// Check out a free pod without waiting; `None` if every pod is taken.
+    ///
+    /// A pod whose checkout expired (see `checkout_ttl_ms`) counts as free.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state backend or the orchestrator fails. A pod
+    /// that fails to come up is released before returning.
+    pub async fn try_checkout(&self) -> Result<Option<PoolLease>, PoolError> {
+        self.try_checkout_excluding(&[]).await
+    }
+
+    /// Check out a free pod other than the `excluded` pool pods, without
+    /// waiting; `None` if every other pod is taken.
+    ///
+    /// # Errors
+    ///
+    /// As `try_checkout()`.
+    pub async fn try_checkout_excluding(
+        &self,
+        excluded: &[String],
+    ) -> Result<Option<PoolLease>, PoolError> {
+        let holder = self.next_holder();
+        for pod_name in self.pod_names() {
+            if excluded.contains(&pod_name) {
+                continue;
+            }
+            let store = self.store(&pod_name)?;
+            let mut state = load(&store, &pod_name).await?;
+            let now = now_unix_ms();
+            let ttl_ms = self.cfg.checkout_ttl_ms;
+            let reclaimed = (ttl_ms > 0)
+                .then(|| state.reclaim_checkout(ttl_ms, now))
+                .flatten();
+            if !state.check_out(&holder, now) {
+                continue;
+            }
+            match save(&store, state).await {
+                Ok(()) => {}
+                // Another worker took it between our load and save.
+                Err(StateStoreError::Conflict { .. }) => continue,
+                Err(e) => return Err(e.into()),
+            }
+            if let Some(stale) = reclaimed {
+                log_warn!(
+                    "pool {}: checkout of {pod_name} by {stale} expired",
+                    self.cfg.name
+                );
+            }
+            log_debug!("pool {}: {pod_name} checked out by {holder}", self.cfg.name);
+
+            let overrides = PodOverrides {
+                name: Some(pod_name.clone()),
+                ..PodOverrides::default()
+            };
+            let pod = match self.orchestrator.ensure_ready_pod_with(&overrides).await {
+                Ok(pod) => pod,
+                Err(e) => {
+                    self.release(&pod_name, &holder).await?;
+                    return Err(e.into());
+                }
+            };
+            let id = PodId::new(pod.id.as_str());
+            self.update(&pod_name, |s| {
+                s.pod_id = Some(id.clone());
+                true
+            })
+            .await?;
+            return Ok(Some(PoolLease {
+                pod_name,
+                holder,
+                pod,
+            }));
+        }
+        Ok(None)
+    }
+
+    /// Give a checked-out pod back to the pool.
+    ///
+    /// The pod keeps running for the next worker; its last use is recorded
+    /// for scale-down ranking.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PoolError::NotCheckedOut` if the lease no longer holds the pod
+    /// (already checked in), or an error if the state backend fails.
+    pub async fn checkin(&self, lease: &PoolLease) -> Result<(), PoolError> {
+        if self.release(&lease.pod_name, &lease.holder).await? {
+            log_debug!("pool {}: {} checked in", self.cfg.name, lease.pod_name);
+            Ok(())
+        } else {
+            Err(PoolError::NotCheckedOut {
+                pod_name: lease.pod_name.clone(),
+                holder: lease.holder.clone(),
+            })
+        }
+    }
+
+    /// Restart the checkout TTL of `lease`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PoolError::NotCheckedOut` if the lease no longer holds the pod
+    /// (checked in, or expired and reclaimed), or an error if the state
+    /// backend fails.
+    pub async fn renew(&self, lease: &PoolLease) -> Result<(), PoolError> {
+        let now = now_unix_ms();
+        if self
+            .update(&lease.pod_name, |s| s.renew_checkout(&lease.holder, now))
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(PoolError::NotCheckedOut {
+                pod_name: lease.pod_name.clone(),
+                holder: lease.holder.clone(),
+            })
+        }
+    }
+
+    /// Run `work` while renewing the checkout of `lease` every third of
+    /// `checkout_ttl_ms`, so work longer than the TTL keeps its pod.
+    ///
+    /// Renewal failures are logged; `work` is never interrupted.
+    pub async fn hold<T>(&self, lease: &PoolLease, work: impl Future<Output = T>) -> T {
+        if self.cfg.checkout_ttl_ms == 0 {
+            return work.await;
+        }
+        let interval = Duration::from_millis((self.cfg.checkout_ttl_ms / 3).max(1));
+        let renew = async {
+            loop {
+                tokio::time::sleep(interval).await;
+                if let Err(e) = self.renew(lease).await {
+                    log_warn!(
+                        "pool {}: renewing {} failed: {e}",
+                        self.cfg.name,
+                        lease.pod_name,
+                        e = ErrorChain(&e)
+                    );
+                }
+            }
+        };
+        match future::select(Box::pin(work), std::pin::pin!(renew)).await {
+            Either::Left((output, _)) => output,
+            Either::Right(((), work)) => work.await,
+        }
+    }
+
+    /// Usage of every pool pod (checked out, last use), keyed by pod name.
+    ///
+    /// Pass it to `RunpodOrchestrator::scale_down()` to spare pods in use.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state backend fails.
+    pub async fn usage(&self) -> Result<HashMap<String, PodUsage>, PoolError> {
+        let mut usage = HashMap::new();
+        for pod_name in self.pod_names() {
+            let store = self.store(&pod_name)?;
+            if let Some(state) = runpod_state::run_blocking(&store, |s| s.load()).await? {
+                usage.insert(pod_name, state.usage());
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Clear the checkout of `holder`; `false` if it did not hold the pod.
+    async fn release(&self, pod_name: &str, holder: &str) -> Result<bool, PoolError> {
+        let now = now_unix_ms();
+        self.update(pod_name, |s| s.check_in(holder, now)).await
+    }
+
+    /// Load, modify and save a pod state, reloading on conflicts.
+    ///
+    /// `apply` returns `false` to skip the save; the result is its last return.
+    async fn update(
+        &self,
+        pod_name: &str,
+        mut apply: impl FnMut(&mut RunPodState) -> bool,
+    ) -> Result<bool, PoolError> {
+        let store = self.store(pod_name)?;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let mut state = load(&store, pod_name).await?;
+            if !apply(&mut state) {
+                return Ok(false);
+            }
+            match save(&store, state).await {
+                Ok(()) => return Ok(true),
+                Err(StateStoreError::Conflict { .. }) if attempt < UPDATE_ATTEMPTS => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Get (or create) the state store of a pool pod.
+    fn store(&self, pod_name: &str) -> Result<PodStore, PoolError> {
+        let mut stores = self
+            .stores
+            .lock()
+            .map_err(|_| PoolError::State(StateStoreError::InvalidState("pool lock poisoned")))?;
+        let store = if let Some(store) = stores.get(pod_name) {
+            Arc::clone(store)
+        } else {
+            let store: PodStore = Arc::from((self.store_factory)(pod_name)?);
+            stores.insert(pod_name.to_string(), Arc::clone(&store));
+            store
+        };
+        drop(stores);
+        Ok(store)
+    }
+
+    /// Unique checkout ID: process, start time and a per-pool counter.
+    fn next_holder(&self) -> String {
+        let n = self.next_checkout.fetch_add(1, Ordering::Relaxed);
+        format!("{}-{}-{n}", std::process::id(), now_unix_ms())
+    }
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// Pool error.
+#[derive(Debug)]
+pub enum PoolError {
+    /// Invalid environment variable value.
+    InvalidEnv {
+        /// The environment variable key.
+        key: &'static str,
+        /// The reason for invalidity.
+        reason: &'static str,
+    },
+    /// The env file (`RUNPOD_ENV_FILE`) could not be loaded.
+    EnvFile(EnvFileError),
+    /// The state backend failed.
+    State(StateStoreError),
+    /// A pool pod could not be brought up.
+    Orchestrator(OrchestratorError),
+    /// Every pod stayed checked out until the checkout timeout.
+    Exhausted {
+        /// Pool name.
+        pool: String,
+        /// Pool size.
+        size: usize,
+    },
+    /// The lease does not hold the pod anymore (already checked in).
+    NotCheckedOut {
+        /// Logical pod name.
+        pod_name: String,
+        /// Holder ID of the lease.
+        holder: String,
+    },
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
+            Self::EnvFile(e) => write!(f, "{e}"),
+            Self::State(_) => write!(f, "pool state error"),
+            Self::Orchestrator(_) => write!(f, "pool pod error"),
+            Self::Exhausted { pool, size } => {
+                write!(f, "all {size} pod(s) of pool {pool} are checked out")
+            }
+            Self::NotCheckedOut { pod_name, holder } => {
+                write!(f, "pod {pod_name} is not checked out by {holder}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PoolError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::EnvFile(e) => std::error::Error::source(e),
+            Self::State(e) => Some(e),
+            Self::Orchestrator(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<StateStoreError> for PoolError {
+    fn from(e: StateStoreError) -> Self {
+        Self::State(e)
+    }
+}
+
+impl From<OrchestratorError> for PoolError {
+    fn from(e: OrchestratorError) -> Self {
+        Self::Orchestrator(e)
+    }
+}
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+/// Load the state of a pool pod (a fresh one if none is stored), off the
+/// async runtime.
+async fn load(store: &PodStore, pod_name: &str) -> Result<RunPodState, StateStoreError> {
+    let state = runpod_state::run_blocking(store, |s| s.load()).await?;
+    Ok(state.unwrap_or_else(|| RunPodState::new(pod_name, now_unix_ms())))
+}
+
+/// Save the state of a pool pod (compare-and-swap), off the async runtime.
+async fn save(store: &PodStore, mut state: RunPodState) -> Result<(), StateStoreError> {
+    runpod_state::run_blocking(store, move |s| s.save(&mut state)).await
+}
+
+fn parse_u64_env(key: &'static str, default: u64) -> Result<u64, PoolError> {
+    runpod_env::u64_var(key, default).map_err(|reason| PoolError::InvalidEnv { key, reason })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use super::*;
+    use crate::runpod_orchestrator::{ReconcileMode, RunpodOrchestratorConfig};
+    use crate::runpod_state::MemoryStateStore;
+
+    /// Store shared by several pools, as a state backend is by several processes.
+    struct Shared(Arc<MemoryStateStore>);
+
+    impl StateStore for Shared {
+        fn load(&self) -> Result<Option<RunPodState>, StateStoreError> {
+            self.0.load()
+        }
+
+        fn save(&self, state: &mut RunPodState) -> Result<(), StateStoreError> {
+            self.0.save(state)
+        }
+    }
+
+    /// Store where a rival worker checks the pod out right before our first save.
+    struct Raced {
+        inner: MemoryStateStore,
+        raced: AtomicBool,
+    }
+
+    impl StateStore for Raced {
+        fn load(&self) -> Result<Option<RunPodState>, StateStoreError> {
+            self.inner.load()
+        }
+
+        fn save(&self, state: &mut RunPodState) -> Result<(), StateStoreError> {
+            if !self.raced.swap(true, Ordering::SeqCst) {
+                let mut rival = self
+                    .inner
+                    .load()?
+                    .unwrap_or_else(|| RunPodState::new(&state.pod_name, now_unix_ms()));
+                assert!(rival.check_out("rival", now_unix_ms()));
+                self.inner.save(&mut rival)?;
+            }
+            self.inner.save(state)
+        }
+    }
+
+    fn config(size: usize) -> PodPoolConfig {
+        PodPoolConfig {
+            name: "pool".to_string(),
+            size,
+            checkout_timeout_ms: 0,
+            poll_interval_ms: 1,
+            checkout_ttl_ms: 0,
+        }
+    }
+
+    fn orchestrator() -> Arc<RunpodOrchestrator> {
+        let cfg = RunpodOrchestratorConfig::simulated(ReconcileMode::Reuse);
+        Arc::new(RunpodOrchestrator::new_with_http(
+            cfg,
+            reqwest::Client::new(),
+        ))
+    }
+
+    fn shared_pool(
+        orchestrator: &Arc<RunpodOrchestrator>,
+        stores: &HashMap<String, Arc<MemoryStateStore>>,
+        size: usize,
+    ) -> PodPool {
+        let stores = stores.clone();
+        let factory = move |pod_name: &str| {
+            let store = stores.get(pod_name).unwrap();
+            Ok(Box::new(Shared(Arc::clone(store))) as Box<dyn StateStore + Send + Sync>)
+        };
+        PodPool::with_store_factory(Arc::clone(orchestrator), config(size), Box::new(factory))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn a_lost_save_moves_on_to_the_next_pod() {
+        let factory = |pod_name: &str| -> Result<Box<dyn StateStore + Send + Sync>, _> {
+            Ok(if pod_name == "pool-0" {
+                Box::new(Raced {
+                    inner: MemoryStateStore::new(),
+                    raced: AtomicBool::new(false),
+                })
+            } else {
+                Box::new(MemoryStateStore::new())
+            })
+        };
+        let pool =
+            PodPool::with_store_factory(orchestrator(), config(2), Box::new(factory)).unwrap();
+
+        let lease = pool.try_checkout().await.unwrap().unwrap();
+        assert_eq!(lease.pod_name, "pool-1");
+
+        let holder = |pod_name: &'static str| async {
+            let store = pool.store(pod_name).unwrap();
+            load(&store, pod_name).await.unwrap().checked_out_by
+        };
+        assert_eq!(holder("pool-0").await.as_deref(), Some("rival"));
+        assert_eq!(holder("pool-1").await, Some(lease.holder.clone()));
+        assert!(pool.try_checkout().await.unwrap().is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn racing_workers_never_share_a_pod() {
+        let orchestrator = orchestrator();
+        let stores: HashMap<_, _> = (0..2)
+            .map(|n| (format!("pool-{n}"), Arc::new(MemoryStateStore::new())))
+            .collect();
+        let pools: Vec<PodPool> = (0..4)
+            .map(|_| shared_pool(&orchestrator, &stores, 2))
+            .collect();
+
+        let leases = future::join_all(pools.iter().map(PodPool::try_checkout)).await;
+        let mut names: Vec<String> = leases
+            .into_iter()
+            .filter_map(|lease| lease.unwrap())
+            .map(|lease| lease.pod_name)
+            .collect();
+        names.sort();
+        assert_eq!(names, ["pool-0", "pool-1"]);
+    }
+
+    #[tokio::test]
+    async fn a_checked_in_pod_is_handed_out_again() {
+        let orchestrator = orchestrator();
+        let stores = HashMap::from([("pool-0".to_string(), Arc::new(MemoryStateStore::new()))]);
+        let first = shared_pool(&orchestrator, &stores, 1);
+        let second = shared_pool(&orchestrator, &stores, 1);
+
+        let lease = first.try_checkout().await.unwrap().unwrap();
+        assert!(second.try_checkout().await.unwrap().is_none());
+        assert!(matches!(
+            second.checkout().await,
+            Err(PoolError::Exhausted { size: 1, .. })
+        ));
+
+        first.checkin(&lease).await.unwrap();
+        let again = second.try_checkout().await.unwrap().unwrap();
+        assert_eq!(again.pod.id, lease.pod.id);
+        assert!(matches!(
+            first.checkin(&lease).await,
+            Err(PoolError::NotCheckedOut { .. })
+        ));
+    }
+}
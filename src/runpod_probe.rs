@@ -0,0 +1,165 @@
+//! Readiness probes.
+//!
+//! Unique responsibility: check that the service behind a mapped pod port
+//! really answers (TCP connect, HTTP status, SSH banner), so a pod is not
+//! reported ready while its container is still booting.
+//!
+//! Probes are configured per container port with `RUNPOD_READY_PROBES`
+//! ("22=ssh,8888=http:/healthz,9000=tcp") and run by the orchestrator once
+//! the ports are mapped. Ports without a probe are ready as soon as they are
+//! mapped (`ReadinessProbe::PortMapped`). Checks no probe can express go in
+//! a `ReadinessCheck`, installed with `with_readiness_check()`.
+//!
+//! ```ignore
+//! let probe = ReadinessProbe::parse("http:/healthz:204").unwrap();
+//! if probe.check(&http, "203.0.113.7", 40123).await {
+//!     println!("service answers");
+//! }
+//!
+//! let orchestrator = orchestrator.with_readiness_check(|lease| async move {
+//!     let Some(url) = lease.http_endpoint(8000) else { return false };
+//!     reqwest::get(format!("{url}/healthz")).await.is_ok_and(|r| r.status().is_success())
+//! });
+//! ```
+
+use std::{collections::HashMap, fmt, future::Future, io, pin::Pin, sync::Arc, time::Duration};
+
+use tokio::net::TcpStream;
+
+use crate::runpod_orchestrator::PodLease;
+
+/// Maximum duration of one probe attempt.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest SSH identification line read (RFC 4253 allows 255 bytes).
+const SSH_BANNER_MAX: usize = 255;
+
+/// Application-level readiness check: receives the candidate lease of a pod
+/// whose ports are mapped, returns whether it is ready.
+pub type ReadinessCheck =
+    Arc<dyn Fn(PodLease) -> Pin<Box<dyn Future<Output = bool> + Send>> + Send + Sync>;
+
+/// How a mapped port is checked before the pod counts as ready.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ReadinessProbe {
+    /// Ready once `RunPod` maps the port.
+    #[default]
+    PortMapped,
+    /// Ready once a TCP connection succeeds.
+    TcpConnect,
+    /// Ready once `GET http://<ip>:<port><path>` returns `status`.
+    HttpGet {
+        /// Request path (starts with "/").
+        path: String,
+        /// Expected status code.
+        status: u16,
+    },
+    /// Ready once the port sends an SSH identification line ("SSH-...").
+    SshBanner,
+}
+
+impl ReadinessProbe {
+    /// Parse "mapped", "tcp", "ssh", or "http[:/path[:status]]"
+    /// (path defaults to "/", status to 200).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        match value.to_lowercase().as_str() {
+            "mapped" => return Some(Self::PortMapped),
+            "tcp" => return Some(Self::TcpConnect),
+            "ssh" => return Some(Self::SshBanner),
+            _ => {}
+        }
+
+        let rest = value.strip_prefix("http")?;
+        let target = match rest.strip_prefix(':') {
+            Some(target) => target,
+            None if rest.is_empty() => "/",
+            None => return None,
+        };
+        let (path, status) = match target.rsplit_once(':') {
+            Some((path, status)) => (path, status.parse::<u16>().ok()?),
+            None => (target, 200),
+        };
+        (path.starts_with('/') && (100..=599).contains(&status)).then(|| Self::HttpGet {
+            path: path.to_string(),
+            status,
+        })
+    }
+
+    /// Parse per-port probes: "22=ssh,8888=http:/healthz" (container port =
+    /// probe, comma-separated; empty entries are skipped).
+    #[must_use]
+    pub fn parse_list(value: &str) -> Option<HashMap<u16, Self>> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (port, probe) = entry.split_once('=')?;
+                Some((port.trim().parse::<u16>().ok()?, Self::parse(probe)?))
+            })
+            .collect()
+    }
+
+    /// Run the probe once against `host:port` (the public IP and port).
+    pub async fn check(&self, http: &reqwest::Client, host: &str, port: u16) -> bool {
+        match self {
+            Self::PortMapped => true,
+            Self::TcpConnect => connect(host, port).await.is_some(),
+            Self::HttpGet { path, status } => http
+                .get(format!("http://{host}:{port}{path}"))
+                .timeout(PROBE_TIMEOUT)
+                .send()
+                .await
+                .is_ok_and(|resp| resp.status().as_u16() == *status),
+            Self::SshBanner => ssh_banner(host, port).await,
+        }
+    }
+}
+
+impl fmt::Display for ReadinessProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PortMapped => f.write_str("mapped"),
+            Self::TcpConnect => f.write_str("tcp"),
+            Self::HttpGet { path, status } => write!(f, "http:{path}:{status}"),
+            Self::SshBanner => f.write_str("ssh"),
+        }
+    }
+}
+
+/// Open a TCP connection, giving up after `PROBE_TIMEOUT`.
+async fn connect(host: &str, port: u16) -> Option<TcpStream> {
+    tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect((host, port)))
+        .await
+        .ok()?
+        .ok()
+}
+
+/// Whether the server at `host:port` starts with an SSH identification line.
+async fn ssh_banner(host: &str, port: u16) -> bool {
+    let Some(stream) = connect(host, port).await else {
+        return false;
+    };
+
+    let read = async {
+        let mut banner = Vec::with_capacity(SSH_BANNER_MAX);
+        let mut chunk = [0_u8; 64];
+        while !banner.contains(&b'\n') && banner.len() < SSH_BANNER_MAX {
+            if stream.readable().await.is_err() {
+                break;
+            }
+            match stream.try_read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => banner.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(_) => break,
+            }
+        }
+        banner
+    };
+    tokio::time::timeout(PROBE_TIMEOUT, read)
+        .await
+        .is_ok_and(|banner| banner.starts_with(b"SSH-"))
+}
@@ -7,11 +7,32 @@
 //!
 //! All configuration is loaded from environment variables, making the provisioner
 //! fully configurable without code changes.
+//!
+//! With `RUNPOD_DRY_RUN=true`, `create_pod()` sends nothing and returns
+//! `RunpodError::DryRun` describing the exact request instead.
+//!
+//! With `RUNPOD_CLOUD_TYPE_FALLBACK` set (e.g. "COMMUNITY"), a creation that
+//! fails for lack of capacity is retried in each fallback cloud type, in order.
+//!
+//! With `RUNPOD_BID_STRATEGY` set (e.g. "ondemand-minus:20"), the spot bid is
+//! computed from current GPU prices at creation instead of being hard-coded.
+//!
+//! `RUNPOD_STOP_AFTER` / `RUNPOD_TERMINATE_AFTER` ask `RunPod` itself to stop or
+//! terminate the pod at a deadline, so a forgotten pod stops billing even if
+//! the process that created it is gone.
 
-use std::{collections::HashMap, env, fmt, time::Duration};
+use std::{cmp::Ordering, collections::HashMap, env, fmt, sync::Arc, time::Duration};
 
 use serde::{Deserialize, Serialize};
 
+use crate::runpod_client::{GpuLowestPrice, RunpodClient, RunpodClientConfig, RunpodClientError};
+use crate::runpod_env::{self, EnvFileError, EnvTemplateError, EnvTemplateMode};
+use crate::runpod_http::{
+    self, ApiKey, ApiResource, ApiVersion, DryRunRequest, HttpOptions, RetryPolicy,
+};
+use crate::runpod_log::{ErrorChain, log_debug, log_info, log_warn};
+use crate::runpod_metrics::{self, MetricsSink};
+
 /// Configuration for provisioning a new `RunPod` pod.
 ///
 /// All fields can be configured via environment variables.
@@ -20,10 +41,14 @@ use serde::{Deserialize, Serialize};
 pub struct RunpodProvisionConfig {
     /// `RunPod` API key for authentication.
     /// Env: `RUNPOD_API_KEY` (required)
-    pub api_key: String,
+    pub api_key: ApiKey,
+
+    /// REST API version: URL default and request/response mapping.
+    /// Env: `RUNPOD_API_VERSION` (default: "v1")
+    pub api_version: ApiVersion,
 
     /// REST API URL for `RunPod`.
-    /// Env: `RUNPOD_REST_URL` (default: "<https://rest.runpod.io/v1>")
+    /// Env: `RUNPOD_REST_URL` (default: the `api_version` URL, "<https://rest.runpod.io/v1>")
     pub rest_url: String,
 
     /// Pod name.
@@ -34,6 +59,51 @@ pub struct RunpodProvisionConfig {
     /// Env: `RUNPOD_CLOUD_TYPE` (default: "SECURE")
     pub cloud_type: String,
 
+    /// Cloud types to try, in order, when `cloud_type` has no capacity.
+    /// Env: `RUNPOD_CLOUD_TYPE_FALLBACK` (default: none, comma-separated)
+    pub cloud_type_fallback: Vec<String>,
+
+    /// ISO 3166-1 alpha-2 country codes pods may be placed in (e.g. "FR,DE").
+    /// Applies to every cloud type tried, including community fallbacks.
+    /// Env: `RUNPOD_ALLOWED_COUNTRY_CODES` (default: none = anywhere, comma-separated)
+    pub allowed_country_codes: Vec<String>,
+
+    /// Create an interruptible (spot) pod instead of an on-demand one.
+    /// Spot pods are cheaper but can be stopped at any time by a higher bid.
+    /// Env: `RUNPOD_INTERRUPTIBLE` (default: false)
+    pub interruptible: bool,
+
+    /// Bid per GPU per hour in USD for interruptible pods.
+    /// Env: `RUNPOD_BID_PER_GPU` (optional; `RunPod` uses the current spot price if unset)
+    pub bid_per_gpu: Option<f64>,
+
+    /// Compute the bid from current GPU prices at creation (when `bid_per_gpu`
+    /// is unset).
+    /// Env: `RUNPOD_BID_STRATEGY` (optional; "ondemand-minus:<percent>" or
+    /// "spot-plus:<percent>", e.g. "ondemand-minus:20")
+    pub bid_strategy: Option<BidStrategy>,
+
+    /// Lowest bid per GPU per hour in USD the strategy may place.
+    /// Env: `RUNPOD_BID_FLOOR` (optional)
+    pub bid_floor: Option<f64>,
+
+    /// Highest bid per GPU per hour in USD the strategy may place.
+    /// Env: `RUNPOD_BID_CEILING` (optional)
+    pub bid_ceiling: Option<f64>,
+
+    /// GraphQL API URL, used for spot price lookups.
+    /// Env: `RUNPOD_GRAPHQL_URL` (default: "<https://api.runpod.io/graphql>")
+    pub graphql_url: String,
+
+    /// Stop the pod automatically at this deadline (enforced by `RunPod`).
+    /// Env: `RUNPOD_STOP_AFTER` (optional; duration like "90m", "2h", "1h30m",
+    /// or an RFC 3339 timestamp like "2025-01-31T18:00:00Z")
+    pub stop_after: Option<PodDeadline>,
+
+    /// Terminate the pod automatically at this deadline (enforced by `RunPod`).
+    /// Env: `RUNPOD_TERMINATE_AFTER` (optional; same format as `RUNPOD_STOP_AFTER`)
+    pub terminate_after: Option<PodDeadline>,
+
     /// Compute type ("GPU" | "CPU").
     /// Env: `RUNPOD_COMPUTE_TYPE` (default: "GPU")
     pub compute_type: String,
@@ -51,6 +121,12 @@ pub struct RunpodProvisionConfig {
     /// Examples: "NVIDIA A40", "NVIDIA `GeForce` RTX 4090", "NVIDIA RTX 5090"
     pub gpu_type_ids: Vec<String>,
 
+    /// Try `gpu_type_ids` one at a time, moving to the next on a stock-out:
+    /// "none" (a single request, `RunPod` picks), "ordered" (listed order) or
+    /// "price" (cheapest current `lowestPrice` first).
+    /// Env: `RUNPOD_GPU_FALLBACK` (default: "none")
+    pub gpu_fallback: GpuFallback,
+
     /// Container disk size in GB.
     /// Env: `RUNPOD_CONTAINER_DISK_GB` (default: 50)
     pub container_disk_gb: u32,
@@ -68,20 +144,108 @@ pub struct RunpodProvisionConfig {
     /// Format: "<port>/<protocol>" where protocol is "tcp" or "http"
     pub ports: Vec<String>,
 
+    /// Command run instead of the image's default `CMD`.
+    /// Env: `RUNPOD_DOCKER_START_CMD` (optional, JSON array, e.g. `["sh", "-c", "..."]`)
+    pub docker_start_cmd: Vec<String>,
+
     /// Optional network volume ID for shared persistent storage.
     /// Env: `RUNPOD_NETWORK_VOLUME_ID` (optional)
     pub network_volume_id: Option<String>,
 
-    /// HTTP request timeout in milliseconds.
+    /// Attach the pod to the global private network, reachable from other
+    /// pods as `<pod_id>.runpod.internal`.
+    /// Env: `RUNPOD_GLOBAL_NETWORKING` (default: false)
+    pub global_networking: bool,
+
+    /// Data centers the pod may be placed in (e.g. "EU-RO-1"); empty = any.
+    /// Env: `RUNPOD_DATA_CENTER_IDS` (optional, comma-separated)
+    pub data_center_ids: Vec<String>,
+
+    /// How `RunPod` picks among `gpu_type_ids`: "availability" (any with
+    /// capacity) or "custom" (in the listed order).
+    /// Env: `RUNPOD_GPU_TYPE_PRIORITY` (optional; `RunPod` default: "availability")
+    pub gpu_type_priority: Option<String>,
+
+    /// How `RunPod` picks among `data_center_ids`: "availability" or "custom".
+    /// Env: `RUNPOD_DATA_CENTER_PRIORITY` (optional; `RunPod` default: "availability")
+    pub data_center_priority: Option<String>,
+
+    /// Total HTTP request timeout in milliseconds (0 = none; see also
+    /// `HttpOptions::connect_timeout_ms` and `read_timeout_ms`).
     /// Env: `RUNPOD_HTTP_TIMEOUT_MS` (default: 15000)
     pub timeout_ms: u64,
 
-    /// Additional environment variables for the pod (JSON object string).
-    /// Env: `RUNPOD_POD_ENV` (optional, JSON format: {"KEY": "value"})
+    /// Additional environment variables for the pod.
+    /// Env: `RUNPOD_POD_ENV_FILE` (optional, env-file with `KEY=value` lines)
+    /// and `RUNPOD_POD_ENV` (optional, JSON format: {"KEY": "value"}), which
+    /// overrides the file
     pub pod_env: HashMap<String, String>,
+
+    /// How `${VAR}` references in `RUNPOD_POD_ENV` values are expanded
+    /// (already applied to `pod_env` by `from_env()`).
+    /// Env: `RUNPOD_POD_ENV_TEMPLATE` (default: "off")
+    /// Options: "off", "lenient" (unset = empty), "strict" (unset = error)
+    pub pod_env_template: EnvTemplateMode,
+
+    /// Describe requests instead of sending them.
+    /// Env: `RUNPOD_DRY_RUN` (default: false)
+    pub dry_run: bool,
+
+    /// Connection pool / keepalive / HTTP version tuning.
+    /// Env: `RUNPOD_HTTP_*` (see `HttpOptions`)
+    pub http: HttpOptions,
+
+    /// Retry policy for transient failures.
+    /// Env: `RUNPOD_HTTP_RETRY_*` (see `RetryPolicy`)
+    pub retry: RetryPolicy,
 }
 
 impl RunpodProvisionConfig {
+    /// Configuration with the `from_env()` defaults, without reading the
+    /// environment: build a pod spec in code by setting fields afterwards.
+    #[must_use]
+    pub fn new(api_key: impl Into<ApiKey>, image_name: impl Into<String>) -> Self {
+        let api_version = ApiVersion::default();
+        Self {
+            api_key: api_key.into(),
+            api_version,
+            rest_url: api_version.default_rest_url().to_string(),
+            name: "halldyll-pod".to_string(),
+            cloud_type: "SECURE".to_string(),
+            cloud_type_fallback: Vec::new(),
+            allowed_country_codes: Vec::new(),
+            interruptible: false,
+            bid_per_gpu: None,
+            bid_strategy: None,
+            bid_floor: None,
+            bid_ceiling: None,
+            graphql_url: "https://api.runpod.io/graphql".to_string(),
+            stop_after: None,
+            terminate_after: None,
+            compute_type: "GPU".to_string(),
+            image_name: image_name.into(),
+            gpu_count: 1,
+            gpu_type_ids: vec!["NVIDIA A40".to_string()],
+            gpu_fallback: GpuFallback::Off,
+            container_disk_gb: 50,
+            volume_gb: 20,
+            volume_mount_path: "/workspace".to_string(),
+            ports: vec!["22/tcp".to_string(), "8888/http".to_string()],
+            docker_start_cmd: Vec::new(),
+            network_volume_id: None,
+            global_networking: false,
+            data_center_ids: Vec::new(),
+            gpu_type_priority: None,
+            data_center_priority: None,
+            timeout_ms: 15_000,
+            pod_env: HashMap::new(),
+            pod_env_template: EnvTemplateMode::default(),
+            dry_run: false,
+            http: HttpOptions::default(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
     /// Load configuration from environment variables.
     ///
     /// Required environment variables:
@@ -92,40 +256,115 @@ impl RunpodProvisionConfig {
     /// - `RUNPOD_REST_URL`: REST API URL (default: "<https://rest.runpod.io/v1>")
     /// - `RUNPOD_POD_NAME`: Pod name (default: "halldyll-pod")
     /// - `RUNPOD_CLOUD_TYPE`: "SECURE" or "COMMUNITY" (default: "SECURE")
+    /// - `RUNPOD_CLOUD_TYPE_FALLBACK`: Cloud types to try when out of capacity (optional)
+    /// - `RUNPOD_ALLOWED_COUNTRY_CODES`: Countries pods may be placed in (optional)
+    /// - `RUNPOD_INTERRUPTIBLE`: Create a spot pod (default: false)
+    /// - `RUNPOD_BID_PER_GPU`: Spot bid per GPU per hour in USD (optional)
+    /// - `RUNPOD_BID_STRATEGY`: Spot bid computed from current prices (optional)
+    /// - `RUNPOD_BID_FLOOR` / `RUNPOD_BID_CEILING`: Bounds of the computed bid (optional)
+    /// - `RUNPOD_STOP_AFTER`: Auto-stop deadline, duration or RFC 3339 timestamp (optional)
+    /// - `RUNPOD_TERMINATE_AFTER`: Auto-terminate deadline, same format (optional)
     /// - `RUNPOD_COMPUTE_TYPE`: "GPU" or "CPU" (default: "GPU")
     /// - `RUNPOD_GPU_COUNT`: Number of GPUs (default: 1)
     /// - `RUNPOD_GPU_TYPE_IDS`: Comma-separated GPU types (default: "NVIDIA A40")
+    /// - `RUNPOD_GPU_FALLBACK`: One GPU type at a time on stock-outs (default: "none")
     /// - `RUNPOD_CONTAINER_DISK_GB`: Container disk size (default: 50)
     /// - `RUNPOD_VOLUME_GB`: Volume size (default: 20)
     /// - `RUNPOD_VOLUME_MOUNT_PATH`: Mount path (default: "/workspace")
     /// - `RUNPOD_PORTS`: Comma-separated ports (default: "22/tcp,8888/http")
     /// - `RUNPOD_NETWORK_VOLUME_ID`: Network volume ID (optional)
+    /// - `RUNPOD_DATA_CENTER_IDS`: Data centers the pod may be placed in (optional)
     /// - `RUNPOD_HTTP_TIMEOUT_MS`: HTTP timeout (default: 15000)
     /// - `RUNPOD_POD_ENV`: Additional pod env vars as JSON (optional)
+    /// - `RUNPOD_POD_ENV_FILE`: Pod env vars from an env-file (optional)
+    /// - `RUNPOD_POD_ENV_TEMPLATE`: `${VAR}` expansion in pod env values (default: "off")
+    /// - `RUNPOD_DRY_RUN`: Describe requests instead of sending them (default: false)
     ///
     /// # Errors
     ///
     /// Returns an error if required environment variables are missing or invalid.
     pub fn from_env() -> Result<Self, RunpodError> {
-        let _ = dotenvy::dotenv();
+        runpod_env::load_dotenv().map_err(RunpodError::EnvFile)?;
 
-        let pod_env = parse_json_env("RUNPOD_POD_ENV")?;
+        let pod_env_template = env::var("RUNPOD_POD_ENV_TEMPLATE")
+            .map_or(Some(EnvTemplateMode::Off), |v| EnvTemplateMode::parse(&v))
+            .ok_or(RunpodError::InvalidEnv {
+                key: "RUNPOD_POD_ENV_TEMPLATE",
+                reason: "expected off, lenient or strict",
+            })?;
+        // Inline JSON entries override the env-file.
+        let mut pod_env = runpod_env::pod_env_file_from_env().map_err(RunpodError::EnvFile)?;
+        pod_env.extend(parse_json_env("RUNPOD_POD_ENV")?);
+        let pod_env =
+            runpod_env::expand_pod_env(pod_env, pod_env_template).map_err(RunpodError::PodEnv)?;
+
+        let interruptible = parse_bool_env("RUNPOD_INTERRUPTIBLE", false)?;
+        let bid_per_gpu = parse_opt_f64_env("RUNPOD_BID_PER_GPU")?;
+        if bid_per_gpu.is_some() && !interruptible {
+            return Err(RunpodError::InvalidEnv {
+                key: "RUNPOD_BID_PER_GPU",
+                reason: "a bid requires RUNPOD_INTERRUPTIBLE=true",
+            });
+        }
+        let bid_strategy = env::var("RUNPOD_BID_STRATEGY")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| {
+                BidStrategy::parse(&v).ok_or(RunpodError::InvalidEnv {
+                    key: "RUNPOD_BID_STRATEGY",
+                    reason: "expected ondemand-minus:<percent> or spot-plus:<percent>",
+                })
+            })
+            .transpose()?;
+        if bid_strategy.is_some() && (bid_per_gpu.is_some() || !interruptible) {
+            return Err(RunpodError::InvalidEnv {
+                key: "RUNPOD_BID_STRATEGY",
+                reason: "requires RUNPOD_INTERRUPTIBLE=true and no RUNPOD_BID_PER_GPU",
+            });
+        }
+        let bid_floor = parse_opt_f64_env("RUNPOD_BID_FLOOR")?;
+        let bid_ceiling = parse_opt_f64_env("RUNPOD_BID_CEILING")?;
+        if let (Some(floor), Some(ceiling)) = (bid_floor, bid_ceiling)
+            && floor > ceiling
+        {
+            return Err(RunpodError::InvalidEnv {
+                key: "RUNPOD_BID_FLOOR",
+                reason: "must not exceed RUNPOD_BID_CEILING",
+            });
+        }
+
+        let api_version = ApiVersion::from_env().map_err(|e| RunpodError::InvalidEnv {
+            key: e.key,
+            reason: e.reason,
+        })?;
 
         Ok(Self {
-            api_key: must_env("RUNPOD_API_KEY")?,
-            rest_url: env::var("RUNPOD_REST_URL")
-                .unwrap_or_else(|_| "https://rest.runpod.io/v1".to_string()),
-
-            name: env::var("RUNPOD_POD_NAME")
-                .unwrap_or_else(|_| "halldyll-pod".to_string()),
-            cloud_type: env::var("RUNPOD_CLOUD_TYPE")
-                .unwrap_or_else(|_| "SECURE".to_string()),
-            compute_type: env::var("RUNPOD_COMPUTE_TYPE")
-                .unwrap_or_else(|_| "GPU".to_string()),
+            api_key: must_env("RUNPOD_API_KEY")?.into(),
+            api_version,
+            rest_url: api_version.rest_url_from_env(),
+
+            name: env::var("RUNPOD_POD_NAME").unwrap_or_else(|_| "halldyll-pod".to_string()),
+            cloud_type: env::var("RUNPOD_CLOUD_TYPE").unwrap_or_else(|_| "SECURE".to_string()),
+            cloud_type_fallback: split_csv_env("RUNPOD_CLOUD_TYPE_FALLBACK", "")
+                .into_iter()
+                .map(|c| c.to_uppercase())
+                .collect(),
+            allowed_country_codes: parse_country_codes_env("RUNPOD_ALLOWED_COUNTRY_CODES")?,
+            interruptible,
+            bid_per_gpu,
+            bid_strategy,
+            bid_floor,
+            bid_ceiling,
+            graphql_url: env::var("RUNPOD_GRAPHQL_URL")
+                .unwrap_or_else(|_| "https://api.runpod.io/graphql".to_string()),
+            stop_after: parse_deadline_env("RUNPOD_STOP_AFTER")?,
+            terminate_after: parse_deadline_env("RUNPOD_TERMINATE_AFTER")?,
+            compute_type: env::var("RUNPOD_COMPUTE_TYPE").unwrap_or_else(|_| "GPU".to_string()),
             image_name: must_env("RUNPOD_IMAGE_NAME")?,
 
             gpu_count: parse_u32_env("RUNPOD_GPU_COUNT", 1)?,
             gpu_type_ids: split_csv_env("RUNPOD_GPU_TYPE_IDS", "NVIDIA A40"),
+            gpu_fallback: parse_gpu_fallback_env("RUNPOD_GPU_FALLBACK")?,
 
             container_disk_gb: parse_u32_env("RUNPOD_CONTAINER_DISK_GB", 50)?,
             volume_gb: parse_u32_env("RUNPOD_VOLUME_GB", 20)?,
@@ -133,13 +372,31 @@ impl RunpodProvisionConfig {
                 .unwrap_or_else(|_| "/workspace".to_string()),
             ports: split_csv_env("RUNPOD_PORTS", "22/tcp,8888/http"),
 
+            docker_start_cmd: parse_json_list_env("RUNPOD_DOCKER_START_CMD")?,
+
             network_volume_id: env::var("RUNPOD_NETWORK_VOLUME_ID")
                 .ok()
                 .filter(|s| !s.trim().is_empty()),
+            global_networking: parse_bool_env("RUNPOD_GLOBAL_NETWORKING", false)?,
+            data_center_ids: split_csv_env("RUNPOD_DATA_CENTER_IDS", ""),
+            gpu_type_priority: parse_priority_env("RUNPOD_GPU_TYPE_PRIORITY")?,
+            data_center_priority: parse_priority_env("RUNPOD_DATA_CENTER_PRIORITY")?,
 
             timeout_ms: parse_u64_env("RUNPOD_HTTP_TIMEOUT_MS", 15_000)?,
 
             pod_env,
+            pod_env_template,
+
+            dry_run: parse_bool_env("RUNPOD_DRY_RUN", false)?,
+
+            http: HttpOptions::from_env().map_err(|e| RunpodError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?,
+            retry: RetryPolicy::from_env().map_err(|e| RunpodError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?,
         })
     }
 }
@@ -148,6 +405,9 @@ impl RunpodProvisionConfig {
 pub struct RunpodProvisioner {
     cfg: RunpodProvisionConfig,
     http: reqwest::Client,
+    /// GraphQL client for price queries, sharing `http`.
+    graphql: RunpodClient,
+    metrics: Arc<dyn MetricsSink>,
 }
 
 impl RunpodProvisioner {
@@ -155,14 +415,51 @@ impl RunpodProvisioner {
     ///
     /// # Errors
     ///
-    /// Returns an error if the HTTP client cannot be built.
+    /// Returns an error if the HTTP options are invalid or the HTTP client
+    /// cannot be built.
     pub fn new(cfg: RunpodProvisionConfig) -> Result<Self, RunpodError> {
-        let http = reqwest::Client::builder()
-            .timeout(Duration::from_millis(cfg.timeout_ms))
+        let http = cfg
+            .http
+            .client_builder(cfg.timeout_ms)
+            .map_err(|e| RunpodError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?
             .build()
             .map_err(RunpodError::Http)?;
 
-        Ok(Self { cfg, http })
+        Ok(Self::new_with_http(cfg, http))
+    }
+
+    /// Create a provisioner sending its requests through `http` (e.g. a
+    /// client shared with other components, see `HttpOptions::shared_client()`).
+    ///
+    /// `cfg.http` options are those of `http`; `cfg.timeout_ms` still applies
+    /// per request.
+    #[must_use]
+    pub fn new_with_http(cfg: RunpodProvisionConfig, http: reqwest::Client) -> Self {
+        let graphql_cfg = RunpodClientConfig {
+            api_key: cfg.api_key.clone(),
+            graphql_url: cfg.graphql_url.clone(),
+            timeout_ms: cfg.timeout_ms,
+            retry: cfg.retry.clone(),
+            http: cfg.http.clone(),
+        };
+        let graphql = RunpodClient::new_with_http(graphql_cfg, http.clone());
+        Self {
+            cfg,
+            http,
+            graphql,
+            metrics: runpod_metrics::noop(),
+        }
+    }
+
+    /// Report retry metrics (of creations and price queries) to `sink`.
+    #[must_use]
+    pub fn with_metrics(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.graphql = self.graphql.with_metrics(Arc::clone(&sink));
+        self.metrics = sink;
+        self
     }
 
     /// Create a new Pod and return its newly assigned podId.
@@ -170,58 +467,344 @@ impl RunpodProvisioner {
     /// Uses the configuration loaded from environment variables.
     /// The pod will be created with the specified GPU type, count, image, etc.
     ///
+    /// If the configured cloud type has no capacity, each cloud type of
+    /// `cloud_type_fallback` is tried in order; the one used is reported in
+    /// `CreatedPod::cloud_type`. With a `gpu_fallback`, every cloud type is
+    /// tried for one GPU type before moving to the next; the GPU types given
+    /// up on are reported in `CreatedPod::skipped_gpu_types`. A spot bid from
+    /// a `bid_strategy` is computed for each GPU type as it is tried; GPU
+    /// types without a spot price are skipped.
+    ///
     /// # Errors
     ///
-    /// Returns an error if the HTTP request fails or the API returns an error.
+    /// Returns an error if the HTTP request fails or the API returns an error
+    /// (for capacity errors, the one from the last cloud type tried).
     pub async fn create_pod(&self) -> Result<CreatedPod, RunpodError> {
-        let url = format!("{}/pods", self.cfg.rest_url.trim_end_matches('/'));
+        if self.cfg.dry_run {
+            return Err(RunpodError::DryRun(Box::new(self.preview_create_pod()?)));
+        }
 
-        let req_body = CreatePodRequest {
-            cloudType: self.cfg.cloud_type.clone(),
-            computeType: self.cfg.compute_type.clone(),
-            name: self.cfg.name.clone(),
-            imageName: self.cfg.image_name.clone(),
-            gpuCount: self.cfg.gpu_count,
-            gpuTypeIds: self.cfg.gpu_type_ids.clone(),
-            containerDiskInGb: self.cfg.container_disk_gb,
-            volumeInGb: self.cfg.volume_gb,
-            volumeMountPath: self.cfg.volume_mount_path.clone(),
-            ports: self.cfg.ports.clone(),
-            env: self.cfg.pod_env.clone(),
-            networkVolumeId: self.cfg.network_volume_id.clone(),
+        let mut skipped_gpu_types = Vec::new();
+        for gpu_type_id in self.gpu_attempts().await {
+            let bid_per_gpu = match self.resolve_bid_for(gpu_type_id.as_deref()).await {
+                Ok(bid) => bid,
+                Err(RunpodError::NoSpotPricing(_)) if gpu_type_id.is_some() => {
+                    let gpu_type_id = gpu_type_id.unwrap_or_default();
+                    log_info!("no spot price for {gpu_type_id}, trying the next GPU type");
+                    skipped_gpu_types.push(gpu_type_id);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let mut skipped_cloud_types = Vec::new();
+            for cloud_type in self.cloud_types() {
+                match self
+                    .create_pod_in(cloud_type, gpu_type_id.as_deref(), bid_per_gpu)
+                    .await
+                {
+                    Ok(mut created) => {
+                        created.skipped_cloud_types = skipped_cloud_types;
+                        created.skipped_gpu_types = skipped_gpu_types;
+                        created.gpu_type_id = created.gpu_type_id.or(gpu_type_id);
+                        return Ok(created);
+                    }
+                    Err(e) if e.is_capacity_error() => {
+                        skipped_cloud_types.push(cloud_type.to_string());
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            if let Some(gpu_type_id) = gpu_type_id {
+                log_info!("no capacity for {gpu_type_id}, trying the next GPU type");
+                skipped_gpu_types.push(gpu_type_id);
+            }
+        }
+
+        let cloud_types = self.cloud_types().into_iter().map(str::to_string).collect();
+        let gpu_types = if skipped_gpu_types.is_empty() {
+            self.cfg.gpu_type_ids.clone()
+        } else {
+            skipped_gpu_types
         };
+        Err(RunpodError::NoCapacity {
+            cloud_types,
+            gpu_types,
+        })
+    }
 
-        let resp = self
-            .http
-            .post(url)
-            .bearer_auth(&self.cfg.api_key)
-            .json(&req_body)
-            .send()
-            .await
-            .map_err(RunpodError::Http)?;
+    /// GPU types `create_pod()` requests one at a time, in order (a single
+    /// `None` when all of `gpu_type_ids` go in one request). When prices
+    /// cannot be fetched, `GpuFallback::Price` keeps the listed order.
+    async fn gpu_attempts(&self) -> Vec<Option<String>> {
+        if self.cfg.gpu_type_ids.len() < 2 {
+            return vec![None];
+        }
+        let order = match self.cfg.gpu_fallback {
+            GpuFallback::Off => return vec![None],
+            GpuFallback::Ordered => self.cfg.gpu_type_ids.clone(),
+            GpuFallback::Price => self.gpu_types_by_price().await.unwrap_or_else(|e| {
+                log_warn!(
+                    "GPU prices unavailable, keeping the listed order: {e}",
+                    e = ErrorChain(&e)
+                );
+                self.cfg.gpu_type_ids.clone()
+            }),
+        };
+        order.into_iter().map(Some).collect()
+    }
+
+    /// `gpu_type_ids` ordered from the cheapest current price per GPU (the
+    /// minimum spot bid for interruptible pods, the on-demand price
+    /// otherwise). GPU types without a price keep their order, last.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if prices cannot be fetched.
+    pub async fn gpu_types_by_price(&self) -> Result<Vec<String>, RunpodError> {
+        let client = &self.graphql;
+        let secure_cloud = !self.cfg.cloud_type.eq_ignore_ascii_case("COMMUNITY");
+
+        let mut priced = Vec::with_capacity(self.cfg.gpu_type_ids.len());
+        for gpu_type_id in &self.cfg.gpu_type_ids {
+            let price = client
+                .gpu_lowest_price(gpu_type_id, self.cfg.gpu_count, secure_cloud)
+                .await
+                .map_err(RunpodError::Pricing)?;
+            let usd = price.and_then(|p| {
+                if self.cfg.interruptible {
+                    p.minimumBidPrice
+                } else {
+                    p.uninterruptablePrice
+                }
+            });
+            log_debug!("{gpu_type_id}: {usd:?} USD/GPU/hour");
+            priced.push((gpu_type_id.clone(), usd));
+        }
+
+        // Stable: equal and unknown prices keep the configured order.
+        priced.sort_by(|(_, a), (_, b)| match (a, b) {
+            (Some(a), Some(b)) => a.total_cmp(b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        });
+        Ok(priced.into_iter().map(|(id, _)| id).collect())
+    }
 
-        let status = resp.status();
-        let body = resp.text().await.unwrap_or_default();
+    /// Cloud types to try, in order (configured one first, no duplicates).
+    fn cloud_types(&self) -> Vec<&str> {
+        let mut out = vec![self.cfg.cloud_type.as_str()];
+        for c in &self.cfg.cloud_type_fallback {
+            if !out.iter().any(|o| o.eq_ignore_ascii_case(c)) {
+                out.push(c);
+            }
+        }
+        out
+    }
 
-        if !status.is_success() {
-            return Err(RunpodError::Api { status, body });
+    /// Get the spot bid per GPU per hour a request for all of `gpu_type_ids`
+    /// at once would place (see `resolve_bid_for()`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if prices cannot be fetched or no acceptable GPU type
+    /// has the prices the strategy needs.
+    pub async fn resolve_bid(&self) -> Result<Option<f64>, RunpodError> {
+        self.resolve_bid_for(None).await
+    }
+
+    /// Get the spot bid per GPU per hour `create_pod()` places when it
+    /// requests `gpu_type_id` (`None`: all of `gpu_type_ids` at once).
+    ///
+    /// This is `bid_per_gpu` if set; otherwise, with a `bid_strategy`, the
+    /// strategy applied to the current prices of the requested GPU type (of
+    /// each one for `None`, keeping the highest result so any of them can be
+    /// won), clamped to `bid_floor` / `bid_ceiling`. `None` for on-demand
+    /// pods, or spot pods left at `RunPod`'s current spot price.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if prices cannot be fetched or the requested GPU
+    /// types lack the prices the strategy needs.
+    pub async fn resolve_bid_for(
+        &self,
+        gpu_type_id: Option<&str>,
+    ) -> Result<Option<f64>, RunpodError> {
+        if self.cfg.bid_per_gpu.is_some() {
+            return Ok(self.cfg.bid_per_gpu);
         }
+        let Some(strategy) = self.cfg.bid_strategy.filter(|_| self.cfg.interruptible) else {
+            return Ok(None);
+        };
 
-        let created: CreatePodResponse =
-            serde_json::from_str(&body).map_err(|e| RunpodError::Json { source: e, body })?;
+        let client = &self.graphql;
+        let secure_cloud = !self.cfg.cloud_type.eq_ignore_ascii_case("COMMUNITY");
+        let gpu_type_ids =
+            gpu_type_id.map_or_else(|| self.cfg.gpu_type_ids.clone(), |id| vec![id.to_string()]);
+
+        let mut bid: Option<f64> = None;
+        for requested in &gpu_type_ids {
+            let price = client
+                .gpu_lowest_price(requested, self.cfg.gpu_count, secure_cloud)
+                .await
+                .map_err(RunpodError::Pricing)?;
+            if let Some(candidate) = price.and_then(|p| strategy.bid(&p)) {
+                bid = Some(bid.map_or(candidate, |b| b.max(candidate)));
+            }
+        }
+
+        let bid = bid.ok_or(RunpodError::NoSpotPricing(gpu_type_ids))?;
+        Ok(Some(clamp_bid(
+            bid,
+            self.cfg.bid_floor,
+            self.cfg.bid_ceiling,
+        )))
+    }
+
+    /// Create a pod in one cloud type (with one GPU type, if given).
+    async fn create_pod_in(
+        &self,
+        cloud_type: &str,
+        gpu_type_id: Option<&str>,
+        bid_per_gpu: Option<f64>,
+    ) -> Result<CreatedPod, RunpodError> {
+        let url = self.create_url();
+        let mut req_body = self.create_request();
+        req_body.cloudType = cloud_type.to_string();
+        if let Some(gpu_type_id) = gpu_type_id {
+            req_body.gpuTypeIds = vec![gpu_type_id.to_string()];
+        }
+        req_body.bidPerGpu = bid_per_gpu;
+        let req_body = self
+            .cfg
+            .api_version
+            .encode(ApiResource::Pod, &req_body)
+            .map_err(|e| RunpodError::Json {
+                source: e,
+                body: String::new(),
+            })?;
+
+        let mut attempt: u32 = 0;
+        let body = loop {
+            attempt = attempt.saturating_add(1);
+
+            let req = self.http.post(&url).bearer_auth(self.cfg.api_key.expose());
+            let send_res = runpod_http::with_timeout(req, self.cfg.timeout_ms)
+                .json(&req_body)
+                .send()
+                .await;
+
+            match send_res {
+                Ok(resp) => {
+                    let status = resp.status();
+                    if status.is_success() {
+                        break resp.text().await.map_err(RunpodError::Http)?;
+                    }
+                    let body = resp.text().await.unwrap_or_default();
+
+                    // Out of capacity: fall back to the next cloud type rather than retrying.
+                    // Creating is not idempotent: only statuses proving no pod was
+                    // created are retried.
+                    let err = RunpodError::Api { status, body };
+                    if err.is_capacity_error()
+                        || !self.cfg.retry.retries_status(attempt, status, false)
+                    {
+                        return Err(err);
+                    }
+                    runpod_http::record_retry(self.metrics.as_ref(), "provisioner");
+                    tokio::time::sleep(self.cfg.retry.delay(attempt)).await;
+                }
+                Err(e) if self.cfg.retry.retries_error(attempt, &e, false) => {
+                    runpod_http::record_retry(self.metrics.as_ref(), "provisioner");
+                    tokio::time::sleep(self.cfg.retry.delay(attempt)).await;
+                }
+                Err(e) => return Err(RunpodError::Http(e)),
+            }
+        };
+
+        let created: CreatePodResponse = self
+            .cfg
+            .api_version
+            .decode(ApiResource::Pod, &body)
+            .map_err(|e| RunpodError::Json { source: e, body })?;
 
         Ok(CreatedPod {
             id: created.id,
             desired_status: created.desiredStatus,
             public_ip: created.publicIp,
+            cloud_type: Some(cloud_type.to_string()),
+            skipped_cloud_types: Vec::new(),
+            skipped_gpu_types: Vec::new(),
+            gpu_type_id: created.machine.as_ref().and_then(|m| m.gpuTypeId.clone()),
+            data_center_id: created.machine.and_then(|m| m.dataCenterId),
         })
     }
 
+    /// Describe the request `create_pod()` would send, without sending it.
+    ///
+    /// The API key and pod env values are redacted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request body cannot be serialized.
+    pub fn preview_create_pod(&self) -> Result<DryRunRequest, RunpodError> {
+        let body = self
+            .cfg
+            .api_version
+            .encode(ApiResource::Pod, &self.create_request())
+            .map_err(|e| RunpodError::Json {
+                source: e,
+                body: String::new(),
+            })?;
+        Ok(DryRunRequest::new(
+            &reqwest::Method::POST,
+            &self.create_url(),
+            Some(&body),
+        ))
+    }
+
     /// Get a reference to the current configuration.
     #[must_use]
     pub const fn config(&self) -> &RunpodProvisionConfig {
         &self.cfg
     }
+
+    fn create_url(&self) -> String {
+        format!("{}/pods", self.cfg.rest_url.trim_end_matches('/'))
+    }
+
+    fn create_request(&self) -> CreatePodRequest {
+        // Relative deadlines count from the moment the request is built.
+        let now_secs = crate::runpod_state::now_unix_ms() / 1000;
+        CreatePodRequest {
+            cloudType: self.cfg.cloud_type.clone(),
+            computeType: self.cfg.compute_type.clone(),
+            name: self.cfg.name.clone(),
+            imageName: self.cfg.image_name.clone(),
+            gpuCount: self.cfg.gpu_count,
+            gpuTypeIds: self.cfg.gpu_type_ids.clone(),
+            gpuTypePriority: self.cfg.gpu_type_priority.clone(),
+            containerDiskInGb: self.cfg.container_disk_gb,
+            volumeInGb: self.cfg.volume_gb,
+            volumeMountPath: self.cfg.volume_mount_path.clone(),
+            ports: self.cfg.ports.clone(),
+            env: self.cfg.pod_env.clone(),
+            dockerStartCmd: self.cfg.docker_start_cmd.clone(),
+            networkVolumeId: self.cfg.network_volume_id.clone(),
+            globalNetworking: self.cfg.global_networking,
+            dataCenterIds: self.cfg.data_center_ids.clone(),
+            dataCenterPriority: self.cfg.data_center_priority.clone(),
+            countryCodes: self.cfg.allowed_country_codes.clone(),
+            interruptible: self.cfg.interruptible,
+            bidPerGpu: self.cfg.bid_per_gpu,
+            stopAfter: self.cfg.stop_after.as_ref().map(|d| d.to_rfc3339(now_secs)),
+            terminateAfter: self
+                .cfg
+                .terminate_after
+                .as_ref()
+                .map(|d| d.to_rfc3339(now_secs)),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -233,13 +816,33 @@ struct CreatePodRequest {
     imageName: String,
     gpuCount: u32,
     gpuTypeIds: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gpuTypePriority: Option<String>,
     containerDiskInGb: u32,
     volumeInGb: u32,
     volumeMountPath: String,
     ports: Vec<String>,
     env: HashMap<String, String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dockerStartCmd: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     networkVolumeId: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    globalNetworking: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dataCenterIds: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dataCenterPriority: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    countryCodes: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    interruptible: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bidPerGpu: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stopAfter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    terminateAfter: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -250,6 +853,208 @@ struct CreatePodResponse {
     desiredStatus: Option<String>,
     #[serde(default)]
     publicIp: Option<String>,
+    #[serde(default)]
+    machine: Option<CreatedMachine>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct CreatedMachine {
+    #[serde(default)]
+    gpuTypeId: Option<String>,
+    #[serde(default)]
+    dataCenterId: Option<String>,
+}
+
+/// How `create_pod()` falls back across `gpu_type_ids` on stock-outs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GpuFallback {
+    /// Request every GPU type at once and let `RunPod` pick.
+    #[default]
+    Off,
+    /// Request one GPU type at a time, in the listed order.
+    Ordered,
+    /// Request one GPU type at a time, cheapest current price first.
+    Price,
+}
+
+impl GpuFallback {
+    /// Parse "none", "ordered" or "price" (case-insensitive).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "none" | "off" => Some(Self::Off),
+            "ordered" => Some(Self::Ordered),
+            "price" => Some(Self::Price),
+            _ => None,
+        }
+    }
+
+    /// Mode name (as parsed).
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "none",
+            Self::Ordered => "ordered",
+            Self::Price => "price",
+        }
+    }
+}
+
+/// How to compute a spot bid from current GPU prices.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BidStrategy {
+    /// The on-demand price minus `percent` percent.
+    OnDemandMinus {
+        /// Discount in percent (0-100).
+        percent: f64,
+    },
+    /// The current minimum spot bid plus `percent` percent.
+    SpotPlus {
+        /// Markup in percent.
+        percent: f64,
+    },
+}
+
+impl BidStrategy {
+    /// Parse "ondemand-minus:<percent>" or "spot-plus:<percent>".
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        let (kind, percent) = value.trim().split_once(':')?;
+        let percent = percent
+            .trim()
+            .trim_end_matches('%')
+            .parse::<f64>()
+            .ok()
+            .filter(|p| p.is_finite() && *p >= 0.0)?;
+        match kind.trim().to_lowercase().as_str() {
+            "ondemand-minus" | "on-demand-minus" if percent < 100.0 => {
+                Some(Self::OnDemandMinus { percent })
+            }
+            "spot-plus" => Some(Self::SpotPlus { percent }),
+            _ => None,
+        }
+    }
+
+    /// Compute the bid per GPU per hour from `price`, before floor/ceiling.
+    ///
+    /// Returns `None` if the price this strategy starts from is unknown.
+    #[must_use]
+    pub fn bid(&self, price: &GpuLowestPrice) -> Option<f64> {
+        match *self {
+            Self::OnDemandMinus { percent } => price
+                .uninterruptablePrice
+                .map(|p| p * (1.0 - percent / 100.0)),
+            Self::SpotPlus { percent } => {
+                price.minimumBidPrice.map(|p| p * (1.0 + percent / 100.0))
+            }
+        }
+    }
+}
+
+/// Round a computed bid to the cent, then bound it: rounding never takes it
+/// past `floor` or `ceiling`.
+fn clamp_bid(bid: f64, floor: Option<f64>, ceiling: Option<f64>) -> f64 {
+    let bid = (bid * 100.0).round() / 100.0;
+    let bid = floor.map_or(bid, |f| bid.max(f));
+    ceiling.map_or(bid, |c| bid.min(c))
+}
+
+/// Deadline for an automatic stop or termination.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PodDeadline {
+    /// Relative to the creation request.
+    After(Duration),
+    /// Absolute RFC 3339 timestamp, sent as-is.
+    At(String),
+}
+
+impl PodDeadline {
+    /// Parse a duration ("45s", "90m", "2h", "1d", "1h30m", bare seconds)
+    /// or an RFC 3339 timestamp.
+    ///
+    /// Returns `None` if the value is neither.
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if looks_like_rfc3339(value) {
+            return Some(Self::At(value.to_string()));
+        }
+        parse_duration(value).map(Self::After)
+    }
+
+    /// Resolve to an RFC 3339 timestamp, relative deadlines counting from
+    /// `now_unix_secs`.
+    #[must_use]
+    pub fn to_rfc3339(&self, now_unix_secs: u64) -> String {
+        match self {
+            Self::After(d) => format_rfc3339(now_unix_secs.saturating_add(d.as_secs())),
+            Self::At(ts) => ts.clone(),
+        }
+    }
+}
+
+fn parse_duration(value: &str) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let mut total: u64 = 0;
+    let mut digits = String::new();
+    for ch in value.chars() {
+        if ch.is_ascii_digit() {
+            digits.push(ch);
+            continue;
+        }
+        let unit = match ch.to_ascii_lowercase() {
+            's' => 1,
+            'm' => 60,
+            'h' => 3_600,
+            'd' => 86_400,
+            _ => return None,
+        };
+        let n: u64 = digits.parse().ok()?;
+        total = total.checked_add(n.checked_mul(unit)?)?;
+        digits.clear();
+    }
+
+    (digits.is_empty() && total > 0).then(|| Duration::from_secs(total))
+}
+
+/// `YYYY-MM-DDTHH:MM:SS` followed by `Z`, an offset or fractional seconds.
+fn looks_like_rfc3339(value: &str) -> bool {
+    let b = value.as_bytes();
+    b.len() >= 20
+        && b.iter().take(19).enumerate().all(|(i, c)| match i {
+            4 | 7 => *c == b'-',
+            10 => *c == b'T' || *c == b't',
+            13 | 16 => *c == b':',
+            _ => c.is_ascii_digit(),
+        })
+}
+
+/// Format UNIX seconds as an RFC 3339 UTC timestamp.
+pub(crate) fn format_rfc3339(unix_secs: u64) -> String {
+    let days = unix_secs / 86_400;
+    let rem = unix_secs % 86_400;
+
+    // Civil date from days since 1970-01-01 (H. Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
 }
 
 /// Represents a newly created pod.
@@ -261,6 +1066,16 @@ pub struct CreatedPod {
     pub desired_status: Option<String>,
     /// Public IP address (if available).
     pub public_ip: Option<String>,
+    /// Cloud type the pod was created in.
+    pub cloud_type: Option<String>,
+    /// Cloud types tried first that had no capacity (non-empty = fallback used).
+    pub skipped_cloud_types: Vec<String>,
+    /// GPU types tried first that had no capacity (with a `gpu_fallback`).
+    pub skipped_gpu_types: Vec<String>,
+    /// GPU type the pod got (if reported by `RunPod`).
+    pub gpu_type_id: Option<String>,
+    /// Data center the pod was placed in (if reported by `RunPod`).
+    pub data_center_id: Option<String>,
 }
 
 /// Error type for `RunPod` provisioning operations.
@@ -292,6 +1107,45 @@ pub enum RunpodError {
         /// Response body.
         body: String,
     },
+    /// Dry run: the request was described but not sent.
+    DryRun(Box<DryRunRequest>),
+    /// No capacity in any of the cloud types tried, for any of the GPU types.
+    NoCapacity {
+        /// Cloud types tried.
+        cloud_types: Vec<String>,
+        /// GPU types tried.
+        gpu_types: Vec<String>,
+    },
+    /// A pod env value could not be expanded.
+    PodEnv(EnvTemplateError),
+    /// The env file (`RUNPOD_ENV_FILE` / `RUNPOD_POD_ENV_FILE`) could not be loaded.
+    EnvFile(EnvFileError),
+    /// Current GPU prices could not be fetched for the spot bid.
+    Pricing(RunpodClientError),
+    /// None of these GPU types has a spot price to bid from.
+    NoSpotPricing(Vec<String>),
+}
+
+impl RunpodError {
+    /// Check whether the API rejected the creation for lack of capacity.
+    #[must_use]
+    pub fn is_capacity_error(&self) -> bool {
+        match self {
+            Self::Api { body, .. } => {
+                let body = body.to_lowercase();
+                [
+                    "no longer any instances available",
+                    "no instances available",
+                    "not enough capacity",
+                    "insufficient capacity",
+                ]
+                .iter()
+                .any(|needle| body.contains(needle))
+            }
+            Self::NoCapacity { .. } => true,
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for RunpodError {
@@ -299,43 +1153,62 @@ impl fmt::Display for RunpodError {
         match self {
             Self::MissingEnv(k) => write!(f, "missing required env var: {k}"),
             Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
-            Self::Http(e) => write!(f, "http error: {e}"),
-            Self::Json { source, .. } => write!(f, "json decode error: {source}"),
+            Self::Http(e) => crate::runpod_http::fmt_http_error(f, e),
+            Self::Json { .. } => write!(f, "json decode error"),
             Self::Api { status, body } => {
                 write!(f, "runpod api error: status={status}, body={body}")
             }
+            Self::DryRun(req) => write!(f, "dry run, request not sent: {} {}", req.method, req.url),
+            Self::NoCapacity {
+                cloud_types,
+                gpu_types,
+            } => write!(
+                f,
+                "no capacity for GPU types {} in cloud types: {}",
+                gpu_types.join(", "),
+                cloud_types.join(", ")
+            ),
+            Self::PodEnv(e) => write!(f, "{e}"),
+            Self::EnvFile(e) => write!(f, "{e}"),
+            Self::Pricing(_) => write!(f, "spot bid pricing error"),
+            Self::NoSpotPricing(gpu_type_ids) => {
+                write!(
+                    f,
+                    "no spot pricing for GPU types: {}",
+                    gpu_type_ids.join(", ")
+                )
+            }
         }
     }
 }
 
-impl std::error::Error for RunpodError {}
+impl std::error::Error for RunpodError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(e) => Some(e),
+            Self::Json { source, .. } => Some(source),
+            Self::PodEnv(e) => std::error::Error::source(e),
+            Self::EnvFile(e) => std::error::Error::source(e),
+            Self::Pricing(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 fn must_env(key: &'static str) -> Result<String, RunpodError> {
     env::var(key).map_err(|_| RunpodError::MissingEnv(key))
 }
 
 fn parse_u32_env(key: &'static str, default: u32) -> Result<u32, RunpodError> {
-    env::var(key).map_or_else(
-        |_| Ok(default),
-        |v| {
-            v.parse::<u32>().map_err(|_| RunpodError::InvalidEnv {
-                key,
-                reason: "expected an unsigned integer",
-            })
-        },
-    )
+    runpod_env::u32_var(key, default).map_err(|reason| RunpodError::InvalidEnv { key, reason })
 }
 
 fn parse_u64_env(key: &'static str, default: u64) -> Result<u64, RunpodError> {
-    env::var(key).map_or_else(
-        |_| Ok(default),
-        |v| {
-            v.parse::<u64>().map_err(|_| RunpodError::InvalidEnv {
-                key,
-                reason: "expected an unsigned integer",
-            })
-        },
-    )
+    runpod_env::u64_var(key, default).map_err(|reason| RunpodError::InvalidEnv { key, reason })
+}
+
+fn parse_bool_env(key: &'static str, default: bool) -> Result<bool, RunpodError> {
+    runpod_env::bool_var(key, default).map_err(|reason| RunpodError::InvalidEnv { key, reason })
 }
 
 fn split_csv_env(key: &'static str, default: &str) -> Vec<String> {
@@ -346,6 +1219,93 @@ fn split_csv_env(key: &'static str, default: &str) -> Vec<String> {
         .collect()
 }
 
+fn parse_deadline_env(key: &'static str) -> Result<Option<PodDeadline>, RunpodError> {
+    env::var(key)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map_or(Ok(None), |v| {
+            PodDeadline::parse(&v)
+                .map(Some)
+                .ok_or(RunpodError::InvalidEnv {
+                    key,
+                    reason: "expected a duration (e.g. 90m, 2h) or an RFC 3339 timestamp",
+                })
+        })
+}
+
+fn parse_opt_f64_env(key: &'static str) -> Result<Option<f64>, RunpodError> {
+    env::var(key)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map_or(Ok(None), |v| {
+            v.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|f| f.is_finite() && *f > 0.0)
+                .map(Some)
+                .ok_or(RunpodError::InvalidEnv {
+                    key,
+                    reason: "expected a positive number",
+                })
+        })
+}
+
+fn parse_country_codes_env(key: &'static str) -> Result<Vec<String>, RunpodError> {
+    let codes: Vec<String> = split_csv_env(key, "")
+        .into_iter()
+        .map(|c| c.to_uppercase())
+        .collect();
+    if codes
+        .iter()
+        .any(|c| c.len() != 2 || !c.chars().all(|ch| ch.is_ascii_alphabetic()))
+    {
+        return Err(RunpodError::InvalidEnv {
+            key,
+            reason: "expected two-letter country codes (e.g. FR,DE)",
+        });
+    }
+    Ok(codes)
+}
+
+fn parse_priority_env(key: &'static str) -> Result<Option<String>, RunpodError> {
+    env::var(key)
+        .ok()
+        .map(|v| v.trim().to_lowercase())
+        .filter(|v| !v.is_empty())
+        .map(|v| match v.as_str() {
+            "availability" | "custom" => Ok(v),
+            _ => Err(RunpodError::InvalidEnv {
+                key,
+                reason: "expected availability or custom",
+            }),
+        })
+        .transpose()
+}
+
+fn parse_gpu_fallback_env(key: &'static str) -> Result<GpuFallback, RunpodError> {
+    env::var(key)
+        .map_or(Some(GpuFallback::Off), |v| GpuFallback::parse(&v))
+        .ok_or(RunpodError::InvalidEnv {
+            key,
+            reason: "expected none, ordered or price",
+        })
+}
+
+fn parse_json_list_env(key: &'static str) -> Result<Vec<String>, RunpodError> {
+    env::var(key)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map_or_else(
+            || Ok(Vec::new()),
+            |v| {
+                serde_json::from_str(&v).map_err(|_| RunpodError::InvalidEnv {
+                    key,
+                    reason: "expected a JSON array of strings",
+                })
+            },
+        )
+}
+
 fn parse_json_env(key: &'static str) -> Result<HashMap<String, String>, RunpodError> {
     env::var(key)
         .ok()
@@ -0,0 +1,249 @@
+//! Background reconciliation.
+//!
+//! Unique responsibility: keep the configured pod at a target status
+//! (running, stopped or terminated) from a spawned task, until the handle is
+//! shut down.
+//!
+//! Each pass runs `RunpodOrchestrator::reconcile_to()`: a running target goes
+//! through `plan()` and only calls `ensure_ready_pod()` when the pod is not
+//! already reusable as is (in recreate mode: not already running), a stopped
+//! target stops the pod if it runs, and a terminated target terminates it.
+//! Passes run every interval, and at once when the target changes. Failed
+//! passes are logged and retried on the next one.
+//!
+//! A scheduled reconciler (`RunpodOrchestrator::run_schedule()`) also sets
+//! its target from a `Schedule` at every window boundary, waking up for it.
+//!
+//! ```ignore
+//! let orchestrator = Arc::new(RunpodOrchestrator::new(cfg)?);
+//! let reconciler = orchestrator.run_reconciler(Duration::from_secs(60));
+//!
+//! // Outside working hours:
+//! reconciler.set_target(TargetStatus::Exited);
+//! if let Some(pass) = reconciler.last_pass() {
+//!     println!("{:?}: {:?} {:?}", pass.target, pass.action, pass.error);
+//! }
+//!
+//! reconciler.shutdown().await; // lets the current pass finish
+//! ```
+
+use serde::Serialize;
+use std::{
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use tokio::{
+    sync::{oneshot, watch},
+    task::JoinHandle,
+};
+
+use crate::runpod_log::{ErrorChain, log_debug, log_warn};
+use crate::runpod_orchestrator::{LeaseAction, RunpodOrchestrator};
+use crate::runpod_schedule::Schedule;
+use crate::runpod_state::{TargetStatus, now_unix_ms};
+
+/// What one reconciliation pass did to the pod.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ReconcileAction {
+    /// The pod already matched the target.
+    Noop,
+    /// The pod was brought up (started, updated, replaced or created).
+    Ensured {
+        /// Pod ID.
+        pod_id: String,
+        /// How the pod was obtained.
+        how: LeaseAction,
+    },
+    /// The running pod was stopped.
+    Stopped {
+        /// Pod ID.
+        pod_id: String,
+    },
+    /// The pod was terminated.
+    Terminated {
+        /// Pod ID.
+        pod_id: String,
+    },
+}
+
+/// Outcome of one reconciliation pass.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReconcilePass {
+    /// Target the pass reconciled to.
+    pub target: TargetStatus,
+    /// What was done (`None` if the pass failed).
+    pub action: Option<ReconcileAction>,
+    /// Why the pass failed.
+    pub error: Option<String>,
+    /// When the pass ended (ms since UNIX epoch).
+    pub finished_ms: u64,
+}
+
+/// Handle of a background reconciliation loop
+/// (see `RunpodOrchestrator::run_reconciler()`).
+///
+/// The loop stops after its current pass when the handle is dropped or
+/// `shutdown()` is called.
+pub struct ReconcilerHandle {
+    target: watch::Sender<TargetStatus>,
+    last: Arc<Mutex<Option<ReconcilePass>>>,
+    stop: oneshot::Sender<()>,
+    task: JoinHandle<()>,
+}
+
+impl ReconcilerHandle {
+    /// Start reconciling `orchestrator`'s pod to `target` every `interval`.
+    ///
+    /// Must be called inside a Tokio runtime.
+    #[must_use]
+    pub fn start(
+        orchestrator: Arc<RunpodOrchestrator>,
+        interval: Duration,
+        target: TargetStatus,
+    ) -> Self {
+        Self::spawn(orchestrator, interval, target, None)
+    }
+
+    /// Start reconciling `orchestrator`'s pod every `interval`, to the
+    /// target of `schedule`.
+    ///
+    /// The target is set from the schedule at start and at every window
+    /// boundary; `set_target()` overrides it until the next boundary. Must
+    /// be called inside a Tokio runtime.
+    #[must_use]
+    pub fn start_scheduled(
+        orchestrator: Arc<RunpodOrchestrator>,
+        interval: Duration,
+        schedule: Schedule,
+    ) -> Self {
+        let target = schedule.target_at(now_unix_ms());
+        Self::spawn(orchestrator, interval, target, Some(schedule))
+    }
+
+    fn spawn(
+        orchestrator: Arc<RunpodOrchestrator>,
+        interval: Duration,
+        target: TargetStatus,
+        schedule: Option<Schedule>,
+    ) -> Self {
+        let (target_tx, target_rx) = watch::channel(target);
+        let (stop_tx, stop_rx) = oneshot::channel();
+        let last = Arc::new(Mutex::new(None));
+        let scheduler = schedule.map(|schedule| (schedule, target_tx.clone()));
+        let task = tokio::spawn(run(
+            orchestrator,
+            interval,
+            target_rx,
+            stop_rx,
+            Arc::clone(&last),
+            scheduler,
+        ));
+        Self {
+            target: target_tx,
+            last,
+            stop: stop_tx,
+            task,
+        }
+    }
+
+    /// Change the target; a pass starts at once.
+    pub fn set_target(&self, target: TargetStatus) {
+        self.target.send_replace(target);
+    }
+
+    /// Current target.
+    #[must_use]
+    pub fn target(&self) -> TargetStatus {
+        *self.target.borrow()
+    }
+
+    /// Outcome of the last finished pass (`None` before the first one).
+    #[must_use]
+    pub fn last_pass(&self) -> Option<ReconcilePass> {
+        self.last
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Whether the loop is still running.
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Stop the loop, waiting for its current pass to finish.
+    pub async fn shutdown(self) {
+        let _ = self.stop.send(());
+        let _ = self.task.await;
+    }
+}
+
+impl fmt::Debug for ReconcilerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconcilerHandle")
+            .field("target", &self.target())
+            .field("running", &self.is_running())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reconcile until `stop` fires (or its sender is dropped).
+async fn run(
+    orchestrator: Arc<RunpodOrchestrator>,
+    interval: Duration,
+    mut target: watch::Receiver<TargetStatus>,
+    mut stop: oneshot::Receiver<()>,
+    last: Arc<Mutex<Option<ReconcilePass>>>,
+    scheduler: Option<(Schedule, watch::Sender<TargetStatus>)>,
+) {
+    // Target of the current window: overrides last until it changes.
+    let mut window_target = None;
+    loop {
+        if let Some((schedule, target_tx)) = &scheduler {
+            let now_target = schedule.target_at(now_unix_ms());
+            if window_target.replace(now_target) != Some(now_target) {
+                target_tx.send_replace(now_target);
+            }
+        }
+
+        let wanted = *target.borrow_and_update();
+        let (action, error) = match orchestrator.reconcile_to(wanted).await {
+            Ok(action) => {
+                log_debug!("reconciled to {wanted:?}: {action:?}");
+                (Some(action), None)
+            }
+            Err(e) => {
+                log_warn!("reconciling to {wanted:?} failed: {e}", e = ErrorChain(&e));
+                (None, Some(e.to_string()))
+            }
+        };
+        *last.lock().unwrap_or_else(PoisonError::into_inner) = Some(ReconcilePass {
+            target: wanted,
+            action,
+            error,
+            finished_ms: now_unix_ms(),
+        });
+
+        // Wake up at the next window boundary if it comes first.
+        let wait = scheduler
+            .as_ref()
+            .and_then(|(schedule, _)| schedule.next_boundary(now_unix_ms()))
+            .map_or(interval, |at| {
+                interval.min(Duration::from_millis(at.saturating_sub(now_unix_ms())))
+            });
+        tokio::select! {
+            biased;
+            _ = &mut stop => return,
+            changed = target.changed() => {
+                if changed.is_err() {
+                    return;
+                }
+            }
+            () = tokio::time::sleep(wait) => {}
+        }
+    }
+}
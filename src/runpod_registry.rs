@@ -0,0 +1,339 @@
+//! Container registry pre-checks.
+//!
+//! Unique responsibility: check that a container image exists in its registry
+//! before a pod is created with it.
+//!
+//! A typo in `RUNPOD_IMAGE_NAME` otherwise costs a full pod creation and a
+//! readiness timeout before failing. The check uses the Docker Registry HTTP
+//! API v2 (Docker Hub, GHCR, and any compatible registry):
+//! - HEAD `https://<registry>/v2/<repository>/manifests/<tag-or-digest>`
+//! - On `401`, fetch a pull token from the realm advertised in `WWW-Authenticate`
+//!   (anonymously, or with `RegistryCredentials`) and retry once
+
+use std::{env, fmt};
+
+use reqwest::{StatusCode, header};
+use serde::Deserialize;
+
+/// Docker Hub registry host.
+pub const DOCKER_HUB_REGISTRY: &str = "registry-1.docker.io";
+
+/// Manifest media types accepted by the pre-check.
+const MANIFEST_ACCEPT: &str = "application/vnd.oci.image.index.v1+json, \
+    application/vnd.oci.image.manifest.v1+json, \
+    application/vnd.docker.distribution.manifest.list.v2+json, \
+    application/vnd.docker.distribution.manifest.v2+json";
+
+/// Parsed container image reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageRef {
+    /// Registry host (e.g. "registry-1.docker.io", "ghcr.io").
+    pub registry: String,
+    /// Repository path (e.g. "library/ubuntu", "org/app").
+    pub repository: String,
+    /// Tag or digest (e.g. "latest", "sha256:...").
+    pub reference: String,
+}
+
+impl ImageRef {
+    /// Parse an image name such as `ubuntu`, `runpod/pytorch:2.1`,
+    /// `ghcr.io/org/app:v1` or `repo@sha256:...`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is empty or malformed.
+    pub fn parse(image: &str) -> Result<Self, RegistryError> {
+        let image = image.trim();
+        if image.is_empty() {
+            return Err(RegistryError::InvalidImage(image.to_string()));
+        }
+
+        let (name, reference) = if let Some((name, digest)) = image.split_once('@') {
+            (name, digest.to_string())
+        } else {
+            // A ':' after the last '/' is a tag, before it a registry port.
+            let last_slash = image.rfind('/').map_or(0, |i| i + 1);
+            image[last_slash..].rfind(':').map_or_else(
+                || (image, "latest".to_string()),
+                |i| {
+                    (
+                        &image[..last_slash + i],
+                        image[last_slash + i + 1..].to_string(),
+                    )
+                },
+            )
+        };
+
+        let (registry, repository) = match name.split_once('/') {
+            Some((first, rest))
+                if first.contains('.') || first.contains(':') || first == "localhost" =>
+            {
+                (first.to_string(), rest.to_string())
+            }
+            Some(_) => (DOCKER_HUB_REGISTRY.to_string(), name.to_string()),
+            None => (DOCKER_HUB_REGISTRY.to_string(), format!("library/{name}")),
+        };
+
+        let registry = if registry == "docker.io" || registry == "index.docker.io" {
+            DOCKER_HUB_REGISTRY.to_string()
+        } else {
+            registry
+        };
+
+        if repository.is_empty() || reference.is_empty() {
+            return Err(RegistryError::InvalidImage(image.to_string()));
+        }
+
+        Ok(Self {
+            registry,
+            repository,
+            reference,
+        })
+    }
+
+    fn manifest_url(&self) -> String {
+        format!(
+            "https://{}/v2/{}/manifests/{}",
+            self.registry, self.repository, self.reference
+        )
+    }
+}
+
+impl fmt::Display for ImageRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sep = if self.reference.contains(':') {
+            '@'
+        } else {
+            ':'
+        };
+        write!(
+            f,
+            "{}/{}{sep}{}",
+            self.registry, self.repository, self.reference
+        )
+    }
+}
+
+/// Registry credentials for private images.
+#[derive(Clone)]
+pub struct RegistryCredentials {
+    /// Registry username.
+    /// Env: `RUNPOD_REGISTRY_USERNAME`
+    pub username: String,
+    /// Registry password or access token.
+    /// Env: `RUNPOD_REGISTRY_PASSWORD`
+    pub password: String,
+}
+
+impl RegistryCredentials {
+    /// Load credentials from `RUNPOD_REGISTRY_USERNAME` / `RUNPOD_REGISTRY_PASSWORD`.
+    ///
+    /// Returns `None` unless both are set and non-empty.
+    #[must_use]
+    pub fn from_env() -> Option<Self> {
+        let username = env::var("RUNPOD_REGISTRY_USERNAME")
+            .ok()
+            .filter(|s| !s.trim().is_empty())?;
+        let password = env::var("RUNPOD_REGISTRY_PASSWORD")
+            .ok()
+            .filter(|s| !s.trim().is_empty())?;
+        Some(Self { username, password })
+    }
+}
+
+impl fmt::Debug for RegistryCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegistryCredentials")
+            .field("username", &self.username)
+            .field("password", &crate::runpod_http::REDACTED)
+            .finish()
+    }
+}
+
+/// Check that `image` exists in its registry.
+///
+/// # Errors
+///
+/// Returns `RegistryError::ImageNotFound` if the registry reports the manifest
+/// as missing, and another error if the check itself could not be completed.
+pub async fn check_image_exists(
+    http: &reqwest::Client,
+    image: &str,
+    credentials: Option<&RegistryCredentials>,
+) -> Result<(), RegistryError> {
+    let image_ref = ImageRef::parse(image)?;
+    let url = image_ref.manifest_url();
+
+    let resp = head_manifest(http, &url, None).await?;
+    let resp = if resp.status() == StatusCode::UNAUTHORIZED {
+        let challenge = resp
+            .headers()
+            .get(header::WWW_AUTHENTICATE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let token = fetch_token(http, &challenge, &image_ref, credentials).await?;
+        head_manifest(http, &url, Some(&token)).await?
+    } else {
+        resp
+    };
+
+    match resp.status() {
+        s if s.is_success() => Ok(()),
+        StatusCode::NOT_FOUND => Err(RegistryError::ImageNotFound(image.to_string())),
+        // Registries answer 401/403 for missing repositories to avoid leaking names.
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN if credentials.is_none() => {
+            Err(RegistryError::ImageNotFound(image.to_string()))
+        }
+        status => Err(RegistryError::Api {
+            status,
+            body: resp.text().await.unwrap_or_default(),
+        }),
+    }
+}
+
+async fn head_manifest(
+    http: &reqwest::Client,
+    url: &str,
+    token: Option<&str>,
+) -> Result<reqwest::Response, RegistryError> {
+    let mut req = http.head(url).header(header::ACCEPT, MANIFEST_ACCEPT);
+    if let Some(token) = token {
+        req = req.bearer_auth(token);
+    }
+    req.send().await.map_err(RegistryError::Http)
+}
+
+/// Fetch a pull token from the realm advertised in a `WWW-Authenticate` challenge.
+async fn fetch_token(
+    http: &reqwest::Client,
+    challenge: &str,
+    image_ref: &ImageRef,
+    credentials: Option<&RegistryCredentials>,
+) -> Result<String, RegistryError> {
+    let params = parse_challenge(challenge);
+    let realm = params
+        .iter()
+        .find(|(k, _)| k == "realm")
+        .map(|(_, v)| v.clone())
+        .ok_or_else(|| RegistryError::Auth(format!("unsupported challenge: {challenge}")))?;
+
+    let mut query: Vec<(String, String)> = params
+        .into_iter()
+        .filter(|(k, _)| k == "service" || k == "scope")
+        .collect();
+    if !query.iter().any(|(k, _)| k == "scope") {
+        query.push((
+            "scope".to_string(),
+            format!("repository:{}:pull", image_ref.repository),
+        ));
+    }
+
+    let url = reqwest::Url::parse_with_params(&realm, &query)
+        .map_err(|e| RegistryError::Auth(format!("invalid realm {realm:?}: {e}")))?;
+
+    let mut req = http.get(url);
+    if let Some(c) = credentials {
+        req = req.basic_auth(&c.username, Some(&c.password));
+    }
+
+    let resp = req.send().await.map_err(RegistryError::Http)?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(RegistryError::Auth(format!(
+            "token request failed: status={status}"
+        )));
+    }
+    let body = resp.text().await.map_err(RegistryError::Http)?;
+
+    let token: TokenResponse =
+        serde_json::from_str(&body).map_err(|e| RegistryError::Auth(e.to_string()))?;
+
+    token
+        .token
+        .or(token.access_token)
+        .ok_or_else(|| RegistryError::Auth("token response without token".to_string()))
+}
+
+/// Parse `Bearer realm="...",service="...",scope="..."` into key/value pairs.
+fn parse_challenge(challenge: &str) -> Vec<(String, String)> {
+    let Some(params) = challenge
+        .trim()
+        .strip_prefix("Bearer ")
+        .or_else(|| challenge.trim().strip_prefix("bearer "))
+    else {
+        return Vec::new();
+    };
+
+    let mut out = Vec::new();
+    let mut rest = params.trim();
+    while let Some((key, after_key)) = rest.split_once('=') {
+        let key = key.trim().trim_start_matches(',').trim().to_lowercase();
+        let (value, remainder) = after_key.strip_prefix('"').map_or_else(
+            || after_key.split_once(',').unwrap_or((after_key, "")),
+            |quoted| quoted.split_once('"').unwrap_or((quoted, "")),
+        );
+        out.push((key, value.to_string()));
+        rest = remainder.trim_start_matches(',').trim();
+    }
+    out
+}
+
+// ============================================================================
+// Response types
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    access_token: Option<String>,
+}
+
+// ============================================================================
+// Error type
+// ============================================================================
+
+/// Error type for registry pre-checks.
+#[derive(Debug)]
+pub enum RegistryError {
+    /// The image name could not be parsed.
+    InvalidImage(String),
+    /// The image (repository or tag) does not exist in its registry.
+    ImageNotFound(String),
+    /// Registry authentication failed.
+    Auth(String),
+    /// HTTP client error.
+    Http(reqwest::Error),
+    /// Unexpected registry response.
+    Api {
+        /// HTTP status code.
+        status: StatusCode,
+        /// Response body.
+        body: String,
+    },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidImage(i) => write!(f, "invalid image name: {i:?}"),
+            Self::ImageNotFound(i) => write!(f, "image not found in registry: {i}"),
+            Self::Auth(e) => write!(f, "registry auth error: {e}"),
+            Self::Http(e) => crate::runpod_http::fmt_http_error(f, e),
+            Self::Api { status, body } => {
+                write!(f, "registry error: status={status}, body={body}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
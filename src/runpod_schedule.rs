@@ -0,0 +1,436 @@
+//! Scheduled start/stop windows.
+//!
+//! Unique responsibility: turn cron-style time windows into the status the
+//! configured pod should have at a given time (running inside a window,
+//! stopped or terminated outside).
+//!
+//! A window is a 5-field cron expression ("minute hour day-of-month month
+//! day-of-week", UTC) matching the minutes the pod should run: `* 9-18 * * 1-5`
+//! is weekdays 09:00–19:00. Fields take `*`, values, ranges (`9-18`), steps
+//! (`*/15`, `0-30/10`) and lists (`1,3,5`); day-of-week also takes `sun`..`sat`
+//! (0 and 7 are Sunday). As in cron, when both day fields are restricted a
+//! day matches either of them. Several windows are separated by `;`.
+//!
+//! The schedule is applied by a background reconciler
+//! (`RunpodOrchestrator::run_schedule()`), which switches its target at
+//! each window boundary.
+//!
+//! ```ignore
+//! // RUNPOD_SCHEDULE="* 9-18 * * mon-fri; * 10-13 * * sat"
+//! let schedule = Schedule::from_env()?;
+//! println!("now: {:?}", schedule.target_at(now_unix_ms()));
+//!
+//! let orchestrator = Arc::new(RunpodOrchestrator::new(cfg)?);
+//! let scheduler = orchestrator.run_schedule(schedule, Duration::from_secs(300));
+//! // Manual overrides hold until the next window boundary:
+//! scheduler.set_target(TargetStatus::Running);
+//! ```
+
+use std::{env, fmt};
+
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_state::TargetStatus;
+
+/// Milliseconds per minute.
+const MINUTE_MS: u64 = 60_000;
+
+/// How far ahead `Schedule::next_boundary()` looks, in minutes (8 days, so
+/// weekly schedules always find their next boundary).
+const BOUNDARY_HORIZON_MIN: u64 = 8 * 24 * 60;
+
+/// Day-of-week names accepted in cron expressions (index = day number).
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A 5-field cron expression ("minute hour day-of-month month day-of-week"),
+/// evaluated in UTC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronExpr {
+    /// Parse a cron expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ScheduleError::InvalidCron` if a field is missing, extra or
+    /// out of range.
+    pub fn parse(value: &str) -> Result<Self, ScheduleError> {
+        let invalid = || ScheduleError::InvalidCron(value.trim().to_string());
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return Err(invalid());
+        };
+
+        let weekdays = parse_field(weekday, 0, 7).ok_or_else(invalid)?;
+        Ok(Self {
+            source: fields.join(" "),
+            minutes: parse_field(minute, 0, 59).ok_or_else(invalid)?,
+            hours: parse_field(hour, 0, 23).ok_or_else(invalid)?,
+            days: parse_field(day, 1, 31).ok_or_else(invalid)?,
+            months: parse_field(month, 1, 12).ok_or_else(invalid)?,
+            // 7 is Sunday too.
+            weekdays: (weekdays | (weekdays >> 7)) & 0x7f,
+            any_day: *day == "*",
+            any_weekday: *weekday == "*",
+        })
+    }
+
+    /// Whether the minute containing `unix_ms` matches.
+    #[must_use]
+    pub const fn matches(&self, unix_ms: u64) -> bool {
+        let minutes = unix_ms / MINUTE_MS;
+        let days = minutes / (24 * 60);
+        let (month, day) = civil_month_day(days);
+        let weekday = (days + 4) % 7; // 1970-01-01 was a Thursday.
+
+        let day_ok = bit(self.days, day);
+        let weekday_ok = bit(self.weekdays, weekday);
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (false, false) => day_ok || weekday_ok,
+            _ => day_ok && weekday_ok,
+        };
+        bit(self.minutes, minutes % 60)
+            && bit(self.hours, (minutes / 60) % 24)
+            && bit(self.months, month)
+            && day_matches
+    }
+}
+
+impl fmt::Display for CronExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Time windows during which the pod should run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// Minutes the pod should run (any window matching).
+    /// Env: `RUNPOD_SCHEDULE` (required, `;`-separated cron expressions)
+    pub windows: Vec<CronExpr>,
+
+    /// Pod status outside the windows (`Exited` or `Terminated`).
+    /// Env: `RUNPOD_SCHEDULE_OUTSIDE` (default: "stopped")
+    /// Options: "stopped", "terminated"
+    pub outside: TargetStatus,
+}
+
+impl Schedule {
+    /// Parse `;`-separated windows, stopping the pod outside them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a window is not a valid cron expression, or if
+    /// there is none.
+    pub fn parse(value: &str) -> Result<Self, ScheduleError> {
+        let windows = value
+            .split(';')
+            .map(str::trim)
+            .filter(|w| !w.is_empty())
+            .map(CronExpr::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if windows.is_empty() {
+            return Err(ScheduleError::InvalidCron(value.trim().to_string()));
+        }
+        Ok(Self {
+            windows,
+            outside: TargetStatus::Exited,
+        })
+    }
+
+    /// Load the schedule from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `RUNPOD_SCHEDULE` is missing or a variable is invalid.
+    pub fn from_env() -> Result<Self, ScheduleError> {
+        runpod_env::load_dotenv().map_err(ScheduleError::EnvFile)?;
+
+        let value = env::var("RUNPOD_SCHEDULE")
+            .map_err(|_| ScheduleError::MissingEnv("RUNPOD_SCHEDULE"))?;
+        let outside =
+            env::var("RUNPOD_SCHEDULE_OUTSIDE").map_or(Ok(TargetStatus::Exited), |v| {
+                match v.trim().to_lowercase().as_str() {
+                    "stopped" | "exited" => Ok(TargetStatus::Exited),
+                    "terminated" => Ok(TargetStatus::Terminated),
+                    _ => Err(ScheduleError::InvalidEnv {
+                        key: "RUNPOD_SCHEDULE_OUTSIDE",
+                        reason: "expected stopped or terminated",
+                    }),
+                }
+            })?;
+
+        Ok(Self {
+            outside,
+            ..Self::parse(&value)?
+        })
+    }
+
+    /// Whether `unix_ms` falls inside a window.
+    #[must_use]
+    pub fn is_open(&self, unix_ms: u64) -> bool {
+        self.windows.iter().any(|w| w.matches(unix_ms))
+    }
+
+    /// Status the pod should have at `unix_ms`.
+    #[must_use]
+    pub fn target_at(&self, unix_ms: u64) -> TargetStatus {
+        if self.is_open(unix_ms) {
+            TargetStatus::Running
+        } else {
+            self.outside
+        }
+    }
+
+    /// Start of the next minute (after `unix_ms`) where the target changes.
+    ///
+    /// `None` if it does not change within the next 8 days.
+    #[must_use]
+    pub fn next_boundary(&self, unix_ms: u64) -> Option<u64> {
+        let open = self.is_open(unix_ms);
+        let minute = unix_ms / MINUTE_MS;
+        (1..=BOUNDARY_HORIZON_MIN)
+            .map(|i| (minute + i) * MINUTE_MS)
+            .find(|at| self.is_open(*at) != open)
+    }
+}
+
+// ============================================================================
+// Error type
+// ============================================================================
+
+/// Error type for schedules.
+#[derive(Debug)]
+pub enum ScheduleError {
+    /// Missing required environment variable.
+    MissingEnv(&'static str),
+    /// Invalid environment variable value.
+    InvalidEnv {
+        /// The environment variable key.
+        key: &'static str,
+        /// The reason for invalidity.
+        reason: &'static str,
+    },
+    /// Invalid cron expression.
+    InvalidCron(String),
+    /// The env file (`RUNPOD_ENV_FILE`) could not be loaded.
+    EnvFile(EnvFileError),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingEnv(k) => write!(f, "missing required env var: {k}"),
+            Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
+            Self::InvalidCron(expr) => write!(f, "invalid cron expression: {expr:?}"),
+            Self::EnvFile(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::EnvFile(e) => std::error::Error::source(e),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+const fn bit(set: u64, value: u64) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parse one cron field into a bit set of the values in `[min, max]`.
+fn parse_field(field: &str, min: u64, max: u64) -> Option<u64> {
+    let mut set = 0_u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u64>().ok().filter(|s| *s > 0)?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (parse_value(start, max)?, parse_value(end, max)?),
+                // "5/10" means from 5 to the end, every 10.
+                None if step > 1 => (parse_value(range, max)?, max),
+                None => {
+                    let value = parse_value(range, max)?;
+                    (value, value)
+                }
+            },
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+        for value in (start..=end).step_by(usize::try_from(step).ok()?) {
+            set |= 1 << value;
+        }
+    }
+    Some(set)
+}
+
+/// Parse a field value: a number, or a day-of-week name (day-of-week only,
+/// i.e. `max` = 7).
+fn parse_value(value: &str, max: u64) -> Option<u64> {
+    value.parse::<u64>().ok().or_else(|| {
+        let value = value.to_lowercase();
+        (max == 7)
+            .then(|| WEEKDAYS.iter().position(|d| *d == value))
+            .flatten()
+            .and_then(|i| u64::try_from(i).ok())
+    })
+}
+
+/// Month (1-12) and day of month (1-31) of a day count since 1970-01-01.
+const fn civil_month_day(days: u64) -> (u64, u64) {
+    // H. Hinnant's civil-from-days algorithm, without the year.
+    let doe = (days + 719_468) % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (month, day)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    const HOUR_MS: u64 = 60 * MINUTE_MS;
+    const DAY_MS: u64 = 24 * HOUR_MS;
+    /// 2024-01-01 00:00 UTC, a Monday.
+    const MONDAY: u64 = 1_704_067_200_000;
+
+    const fn utc(day: u64, hour: u64, minute: u64) -> u64 {
+        MONDAY + day * DAY_MS + hour * HOUR_MS + minute * MINUTE_MS
+    }
+
+    #[test]
+    fn next_boundary_opens_and_closes_the_window() {
+        let schedule = Schedule::parse("* 9-18 * * mon-fri").unwrap();
+
+        assert_eq!(schedule.target_at(utc(0, 8, 30)), TargetStatus::Exited);
+        assert_eq!(schedule.next_boundary(utc(0, 8, 30)), Some(utc(0, 9, 0)));
+        assert_eq!(schedule.target_at(utc(0, 10, 0)), TargetStatus::Running);
+        assert_eq!(schedule.next_boundary(utc(0, 10, 0)), Some(utc(0, 19, 0)));
+        // From Friday evening, the next boundary is Monday morning.
+        assert_eq!(schedule.next_boundary(utc(4, 19, 0)), Some(utc(7, 9, 0)));
+    }
+
+    #[test]
+    fn next_boundary_is_after_the_current_minute() {
+        let schedule = Schedule::parse("* 9-18 * * *").unwrap();
+
+        assert_eq!(
+            schedule.next_boundary(utc(0, 8, 59) + 30_000),
+            Some(utc(0, 9, 0))
+        );
+        assert_eq!(schedule.next_boundary(utc(0, 9, 0)), Some(utc(0, 19, 0)));
+    }
+
+    #[test]
+    fn next_boundary_is_none_when_the_target_never_changes() {
+        let always = Schedule::parse("* * * * *").unwrap();
+        assert_eq!(always.next_boundary(utc(0, 0, 0)), None);
+
+        // February 30th never comes.
+        let never = Schedule::parse("* * 30 2 *").unwrap();
+        assert_eq!(never.next_boundary(utc(0, 0, 0)), None);
+    }
+
+    #[test]
+    fn next_boundary_finds_a_weekly_window() {
+        let schedule = Schedule::parse("0-29 12 * * sun").unwrap();
+
+        assert_eq!(schedule.next_boundary(utc(0, 13, 0)), Some(utc(6, 12, 0)));
+        assert_eq!(schedule.next_boundary(utc(6, 12, 10)), Some(utc(6, 12, 30)));
+    }
+
+    #[test]
+    fn several_windows_are_merged() {
+        let schedule = Schedule::parse("* 9-11 * * *; * 12-13 * * *").unwrap();
+
+        assert!(schedule.is_open(utc(0, 12, 30)));
+        assert_eq!(schedule.next_boundary(utc(0, 9, 0)), Some(utc(0, 14, 0)));
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either() {
+        // Day 1 of the month or a Monday.
+        let cron = CronExpr::parse("0 12 1 * mon").unwrap();
+
+        assert!(cron.matches(utc(7, 12, 0))); // Monday 8th
+        assert!(cron.matches(utc(31, 12, 0))); // Thursday, February 1st
+        assert!(!cron.matches(utc(1, 12, 0))); // Tuesday 2nd
+    }
+
+    #[test]
+    fn an_unrestricted_day_field_requires_the_other() {
+        let monthly = CronExpr::parse("0 12 1 * *").unwrap();
+        assert!(!monthly.matches(utc(7, 12, 0)));
+        assert!(monthly.matches(utc(31, 12, 0)));
+
+        let weekly = CronExpr::parse("0 12 * * mon").unwrap();
+        assert!(weekly.matches(utc(7, 12, 0)));
+        assert!(!weekly.matches(utc(31, 12, 0)));
+    }
+
+    #[test]
+    fn sunday_is_0_or_7() {
+        let zero = CronExpr::parse("* * * * 0").unwrap();
+        let seven = CronExpr::parse("* * * * 7").unwrap();
+
+        assert!(zero.matches(utc(6, 12, 0)));
+        assert!(seven.matches(utc(6, 12, 0)));
+        assert!(!seven.matches(utc(5, 12, 0)));
+    }
+
+    #[test]
+    fn leap_days_are_in_february() {
+        let cron = CronExpr::parse("0 0 29 2 *").unwrap();
+
+        assert!(cron.matches(utc(59, 0, 0))); // 2024-02-29
+        assert!(!cron.matches(utc(60, 0, 0))); // 2024-03-01
+    }
+
+    #[test]
+    fn steps_and_lists() {
+        let cron = CronExpr::parse("*/15,50 * * * *").unwrap();
+        let matching: Vec<u64> = (0..60).filter(|m| cron.matches(utc(0, 0, *m))).collect();
+
+        assert_eq!(matching, [0, 15, 30, 45, 50]);
+    }
+
+    #[test]
+    fn invalid_expressions_are_rejected() {
+        for expr in [
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 9-25 * * *",
+            "*/0 * * * *",
+        ] {
+            assert!(
+                matches!(CronExpr::parse(expr), Err(ScheduleError::InvalidCron(_))),
+                "{expr}"
+            );
+        }
+        assert!(Schedule::parse(" ; ").is_err());
+    }
+}
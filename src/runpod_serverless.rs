@@ -0,0 +1,955 @@
+//! `RunPod` serverless endpoints.
+//!
+//! Unique responsibility: manage serverless endpoints through the `RunPod` REST API.
+//!
+//! REST endpoints:
+//! - GET <https://rest.runpod.io/v1/endpoints/{endpointId}>
+//! - PATCH <https://rest.runpod.io/v1/endpoints/{endpointId}>
+//!
+//! Job endpoints:
+//! - POST <https://api.runpod.ai/v2/{endpointId}/run>
+//! - GET <https://api.runpod.ai/v2/{endpointId}/status/{jobId}>
+//! - POST <https://api.runpod.ai/v2/{endpointId}/cancel/{jobId}>
+//! - POST <https://api.runpod.ai/v2/{endpointId}/purge-queue>
+//! - GET <https://api.runpod.ai/v2/{endpointId}/health>
+//!
+//! Scaling is converged rather than set blindly: `reconcile_scaling()` reads
+//! the endpoint, patches only the fields that differ from the desired
+//! `ScalingSpec`, and reports what changed.
+//!
+//! ```ignore
+//! let client = ServerlessClient::new(ServerlessConfig::from_env()?)?;
+//! let desired = ScalingSpec::from_env()?; // RUNPOD_SERVERLESS_WORKERS_MIN, ...
+//! let report = client.reconcile_scaling("abc123", &desired).await?;
+//! if !report.changed.is_empty() {
+//!     println!("scaled {}: {:?} -> {:?}", report.endpoint_id, report.before, report.after);
+//! }
+//! ```
+
+use std::{env, fmt, sync::Arc, time::Duration};
+
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_http::{self, ApiKey, ApiResource, ApiVersion, HttpOptions, RetryPolicy};
+use crate::runpod_log::log_debug;
+use crate::runpod_metrics::{self, MetricsSink};
+
+/// Configuration for the serverless client.
+#[derive(Debug, Clone)]
+pub struct ServerlessConfig {
+    /// `RunPod` API key for authentication.
+    /// Env: `RUNPOD_API_KEY` (required)
+    pub api_key: ApiKey,
+
+    /// REST API version: URL default and request/response mapping.
+    /// Env: `RUNPOD_API_VERSION` (default: "v1")
+    pub api_version: ApiVersion,
+
+    /// REST API URL for `RunPod`.
+    /// Env: `RUNPOD_REST_URL` (default: the `api_version` URL, "<https://rest.runpod.io/v1>")
+    pub rest_url: String,
+
+    /// Serverless job API URL.
+    /// Env: `RUNPOD_SERVERLESS_URL` (default: "<https://api.runpod.ai/v2>")
+    pub api_url: String,
+
+    /// Total HTTP request timeout in milliseconds (0 = none; see also
+    /// `HttpOptions::connect_timeout_ms` and `read_timeout_ms`).
+    /// Env: `RUNPOD_HTTP_TIMEOUT_MS` (default: 30000)
+    pub timeout_ms: u64,
+
+    /// Retry policy for transient failures.
+    /// Env: `RUNPOD_HTTP_RETRY_*` (see `RetryPolicy`)
+    pub retry: RetryPolicy,
+
+    /// Connection pool / keepalive / HTTP version tuning.
+    /// Env: `RUNPOD_HTTP_*` (see `HttpOptions`)
+    pub http: HttpOptions,
+}
+
+impl ServerlessConfig {
+    /// Load configuration from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if required environment variables are missing or invalid.
+    pub fn from_env() -> Result<Self, ServerlessError> {
+        runpod_env::load_dotenv().map_err(ServerlessError::EnvFile)?;
+
+        let api_version = ApiVersion::from_env().map_err(|e| ServerlessError::InvalidEnv {
+            key: e.key,
+            reason: e.reason,
+        })?;
+
+        Ok(Self {
+            api_key: must_env("RUNPOD_API_KEY")?.into(),
+            api_version,
+            rest_url: api_version.rest_url_from_env(),
+            api_url: env::var("RUNPOD_SERVERLESS_URL")
+                .unwrap_or_else(|_| "https://api.runpod.ai/v2".to_string()),
+            timeout_ms: parse_u64_env("RUNPOD_HTTP_TIMEOUT_MS", 30_000)?,
+            retry: RetryPolicy::from_env().map_err(|e| ServerlessError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?,
+            http: HttpOptions::from_env().map_err(|e| ServerlessError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?,
+        })
+    }
+}
+
+/// How an endpoint decides to add workers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ScalerType {
+    /// Scale when jobs wait longer than `scaler_value` seconds in the queue.
+    QueueDelay,
+    /// Scale to one worker per `scaler_value` queued requests.
+    RequestCount,
+}
+
+impl ScalerType {
+    /// Parse `QUEUE_DELAY` or `REQUEST_COUNT` (case-insensitive).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_uppercase().replace('-', "_").as_str() {
+            "QUEUE_DELAY" => Some(Self::QueueDelay),
+            "REQUEST_COUNT" => Some(Self::RequestCount),
+            _ => None,
+        }
+    }
+}
+
+/// Current scaling parameters of an endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndpointScaling {
+    /// Workers kept running even when idle.
+    pub workers_min: u32,
+    /// Maximum number of workers.
+    pub workers_max: u32,
+    /// Seconds an idle worker is kept before scaling down.
+    pub idle_timeout_s: u32,
+    /// Scaling strategy.
+    pub scaler_type: ScalerType,
+    /// Strategy threshold (seconds of queue delay, or requests per worker).
+    pub scaler_value: u32,
+}
+
+/// Desired scaling parameters; `None` fields are left as they are.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScalingSpec {
+    /// Workers kept running even when idle.
+    /// Env: `RUNPOD_SERVERLESS_WORKERS_MIN` (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workers_min: Option<u32>,
+
+    /// Maximum number of workers.
+    /// Env: `RUNPOD_SERVERLESS_WORKERS_MAX` (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workers_max: Option<u32>,
+
+    /// Seconds an idle worker is kept before scaling down.
+    /// Env: `RUNPOD_SERVERLESS_IDLE_TIMEOUT_S` (optional)
+    #[serde(rename = "idleTimeout", skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_s: Option<u32>,
+
+    /// Scaling strategy.
+    /// Env: `RUNPOD_SERVERLESS_SCALER_TYPE` (optional: `QUEUE_DELAY` | `REQUEST_COUNT`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scaler_type: Option<ScalerType>,
+
+    /// Strategy threshold.
+    /// Env: `RUNPOD_SERVERLESS_SCALER_VALUE` (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scaler_value: Option<u32>,
+}
+
+impl ScalingSpec {
+    /// Load a desired scaling spec from environment variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is set to an invalid value.
+    pub fn from_env() -> Result<Self, ServerlessError> {
+        runpod_env::load_dotenv().map_err(ServerlessError::EnvFile)?;
+
+        let scaler_type = env::var("RUNPOD_SERVERLESS_SCALER_TYPE")
+            .ok()
+            .filter(|v| !v.trim().is_empty())
+            .map(|v| {
+                ScalerType::parse(&v).ok_or(ServerlessError::InvalidEnv {
+                    key: "RUNPOD_SERVERLESS_SCALER_TYPE",
+                    reason: "expected QUEUE_DELAY or REQUEST_COUNT",
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            workers_min: parse_opt_u32_env("RUNPOD_SERVERLESS_WORKERS_MIN")?,
+            workers_max: parse_opt_u32_env("RUNPOD_SERVERLESS_WORKERS_MAX")?,
+            idle_timeout_s: parse_opt_u32_env("RUNPOD_SERVERLESS_IDLE_TIMEOUT_S")?,
+            scaler_type,
+            scaler_value: parse_opt_u32_env("RUNPOD_SERVERLESS_SCALER_VALUE")?,
+        })
+    }
+
+    /// Check whether no field is set.
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.workers_min.is_none()
+            && self.workers_max.is_none()
+            && self.idle_timeout_s.is_none()
+            && self.scaler_type.is_none()
+            && self.scaler_value.is_none()
+    }
+
+    /// The fields of `self` that differ from `current`.
+    #[must_use]
+    pub fn diff(&self, current: &EndpointScaling) -> Self {
+        Self {
+            workers_min: self.workers_min.filter(|v| *v != current.workers_min),
+            workers_max: self.workers_max.filter(|v| *v != current.workers_max),
+            idle_timeout_s: self.idle_timeout_s.filter(|v| *v != current.idle_timeout_s),
+            scaler_type: self.scaler_type.filter(|v| *v != current.scaler_type),
+            scaler_value: self.scaler_value.filter(|v| *v != current.scaler_value),
+        }
+    }
+
+    /// `current` with the set fields of `self` applied.
+    #[must_use]
+    pub fn applied_to(&self, current: &EndpointScaling) -> EndpointScaling {
+        EndpointScaling {
+            workers_min: self.workers_min.unwrap_or(current.workers_min),
+            workers_max: self.workers_max.unwrap_or(current.workers_max),
+            idle_timeout_s: self.idle_timeout_s.unwrap_or(current.idle_timeout_s),
+            scaler_type: self.scaler_type.unwrap_or(current.scaler_type),
+            scaler_value: self.scaler_value.unwrap_or(current.scaler_value),
+        }
+    }
+}
+
+/// A serverless endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    /// Endpoint ID.
+    pub id: String,
+    /// Endpoint name.
+    pub name: Option<String>,
+    /// Template the workers run.
+    pub template_id: Option<String>,
+    /// Acceptable GPU type IDs.
+    pub gpu_type_ids: Vec<String>,
+    /// Scaling parameters.
+    pub scaling: EndpointScaling,
+}
+
+/// Outcome of `ServerlessClient::reconcile_scaling()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScalingReconcile {
+    /// Endpoint ID.
+    pub endpoint_id: String,
+    /// Scaling before reconciliation.
+    pub before: EndpointScaling,
+    /// Scaling after reconciliation.
+    pub after: EndpointScaling,
+    /// Fields that were patched (empty = already converged).
+    pub changed: ScalingSpec,
+}
+
+/// Status of a serverless job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum JobStatus {
+    /// Waiting for a worker.
+    InQueue,
+    /// Running on a worker.
+    InProgress,
+    /// Finished successfully.
+    Completed,
+    /// Finished with an error.
+    Failed,
+    /// Cancelled before completion.
+    Cancelled,
+    /// Exceeded the endpoint's execution timeout.
+    TimedOut,
+    /// A status this crate does not know about.
+    #[serde(other)]
+    Unknown,
+}
+
+impl JobStatus {
+    /// Check whether the job has finished (successfully or not).
+    #[must_use]
+    pub const fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            Self::Completed | Self::Failed | Self::Cancelled | Self::TimedOut
+        )
+    }
+}
+
+/// A serverless job, as returned by `/run`, `/status` or a completion webhook.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct JobResult {
+    /// Job ID.
+    pub id: String,
+    /// Job status.
+    pub status: JobStatus,
+    /// Handler output (completed jobs).
+    #[serde(default)]
+    pub output: Option<serde_json::Value>,
+    /// Error message (failed jobs).
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Time spent in the queue, in milliseconds.
+    #[serde(default, rename = "delayTime")]
+    pub delay_time_ms: Option<u64>,
+    /// Execution time, in milliseconds.
+    #[serde(default, rename = "executionTime")]
+    pub execution_time_ms: Option<u64>,
+}
+
+/// Outcome of `ServerlessClient::cancel_job()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CancelResult {
+    /// Job ID.
+    pub job_id: String,
+    /// Job status after the call.
+    pub status: JobStatus,
+    /// Jobs removed by this call: 1 if the job was cancelled, 0 if it had
+    /// already finished.
+    pub removed: u32,
+}
+
+/// Outcome of `ServerlessClient::purge_queue()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PurgeResult {
+    /// Endpoint ID.
+    pub endpoint_id: String,
+    /// Queued jobs removed (running jobs are not affected).
+    pub removed: u32,
+}
+
+/// Job counters of an endpoint (`/health`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JobCounts {
+    /// Jobs completed (recent window kept by `RunPod`).
+    pub completed: u64,
+    /// Jobs failed.
+    pub failed: u64,
+    /// Jobs running on a worker.
+    pub in_progress: u64,
+    /// Jobs waiting for a worker.
+    pub in_queue: u64,
+    /// Jobs retried.
+    pub retried: u64,
+}
+
+/// Worker counters of an endpoint (`/health`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WorkerCounts {
+    /// Workers ready and without a job.
+    pub idle: u64,
+    /// Workers running a job.
+    pub running: u64,
+    /// Workers starting up.
+    pub initializing: u64,
+    /// Workers ready to take jobs.
+    pub ready: u64,
+    /// Workers waiting for GPU capacity.
+    pub throttled: u64,
+    /// Workers failing health checks.
+    pub unhealthy: u64,
+}
+
+/// Endpoint health snapshot (`/health`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct EndpointHealth {
+    /// Job counters.
+    pub jobs: JobCounts,
+    /// Worker counters.
+    pub workers: WorkerCounts,
+}
+
+impl EndpointHealth {
+    /// Jobs waiting per active (idle or running) worker; `None` with no worker.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)] // Counters stay far below 2^52.
+    pub fn queue_per_worker(&self) -> Option<f64> {
+        let workers = self.workers.idle + self.workers.running;
+        (workers > 0).then(|| self.jobs.in_queue as f64 / workers as f64)
+    }
+}
+
+/// Client for `RunPod` serverless endpoints.
+pub struct ServerlessClient {
+    cfg: ServerlessConfig,
+    http: reqwest::Client,
+    metrics: Arc<dyn MetricsSink>,
+}
+
+impl ServerlessClient {
+    /// Create a new serverless client from the given configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP options are invalid or the HTTP client
+    /// cannot be built.
+    pub fn new(cfg: ServerlessConfig) -> Result<Self, ServerlessError> {
+        let http = cfg
+            .http
+            .client_builder(cfg.timeout_ms)
+            .map_err(|e| ServerlessError::InvalidEnv {
+                key: e.key,
+                reason: e.reason,
+            })?
+            .build()
+            .map_err(ServerlessError::Http)?;
+
+        Ok(Self::new_with_http(cfg, http))
+    }
+
+    /// Create a serverless client sending its requests through `http` (e.g.
+    /// a client shared with other components, see
+    /// `HttpOptions::shared_client()`).
+    ///
+    /// `cfg.http` options are those of `http`; `cfg.timeout_ms` still applies
+    /// per request.
+    #[must_use]
+    pub fn new_with_http(cfg: ServerlessConfig, http: reqwest::Client) -> Self {
+        Self {
+            cfg,
+            http,
+            metrics: runpod_metrics::noop(),
+        }
+    }
+
+    /// Get a reference to the current configuration.
+    #[must_use]
+    pub const fn config(&self) -> &ServerlessConfig {
+        &self.cfg
+    }
+
+    /// Report HTTP request metrics to `sink`.
+    #[must_use]
+    pub fn with_metrics(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = sink;
+        self
+    }
+
+    /// Get an endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ServerlessError::NotFound` if the endpoint does not exist, and
+    /// another error if the request fails.
+    pub async fn get_endpoint(&self, endpoint_id: &str) -> Result<Endpoint, ServerlessError> {
+        let req = self
+            .http
+            .get(self.endpoint_url(endpoint_id))
+            .bearer_auth(self.cfg.api_key.expose());
+        let body = self.send(req, endpoint_id).await?;
+
+        let raw: EndpointResponse = self
+            .cfg
+            .api_version
+            .decode(ApiResource::Endpoint, &body)
+            .map_err(ServerlessError::Json)?;
+        Ok(raw.into())
+    }
+
+    /// Get an endpoint's scaling parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the endpoint cannot be read.
+    pub async fn scaling(&self, endpoint_id: &str) -> Result<EndpointScaling, ServerlessError> {
+        Ok(self.get_endpoint(endpoint_id).await?.scaling)
+    }
+
+    /// Patch the set fields of `spec` onto an endpoint.
+    ///
+    /// Returns the endpoint's scaling after the update (the requested one if
+    /// the API answers with an empty body).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ServerlessError::InvalidScaling` if the result would have
+    /// `workers_min > workers_max`, `ServerlessError::Json` if the updated
+    /// endpoint cannot be decoded, and another error if a request fails.
+    pub async fn set_scaling(
+        &self,
+        endpoint_id: &str,
+        spec: &ScalingSpec,
+    ) -> Result<EndpointScaling, ServerlessError> {
+        let current = self.scaling(endpoint_id).await?;
+        self.patch_scaling(endpoint_id, &current, spec).await
+    }
+
+    /// Converge an endpoint to `desired`, patching only the fields that differ.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the endpoint cannot be read or updated, or the
+    /// desired spec is invalid for it.
+    pub async fn reconcile_scaling(
+        &self,
+        endpoint_id: &str,
+        desired: &ScalingSpec,
+    ) -> Result<ScalingReconcile, ServerlessError> {
+        let before = self.scaling(endpoint_id).await?;
+        let changed = desired.diff(&before);
+
+        let after = if changed.is_empty() {
+            before
+        } else {
+            self.patch_scaling(endpoint_id, &before, &changed).await?
+        };
+
+        Ok(ScalingReconcile {
+            endpoint_id: endpoint_id.to_string(),
+            before,
+            after,
+            changed,
+        })
+    }
+
+    async fn patch_scaling(
+        &self,
+        endpoint_id: &str,
+        current: &EndpointScaling,
+        spec: &ScalingSpec,
+    ) -> Result<EndpointScaling, ServerlessError> {
+        let target = spec.applied_to(current);
+        if target.workers_min > target.workers_max {
+            return Err(ServerlessError::InvalidScaling(format!(
+                "workersMin ({}) > workersMax ({})",
+                target.workers_min, target.workers_max
+            )));
+        }
+        if spec.is_empty() {
+            return Ok(*current);
+        }
+
+        let payload = self
+            .cfg
+            .api_version
+            .encode(ApiResource::Endpoint, spec)
+            .map_err(ServerlessError::Json)?;
+        let req = self
+            .http
+            .patch(self.endpoint_url(endpoint_id))
+            .bearer_auth(self.cfg.api_key.expose())
+            .json(&payload);
+        let body = self.send(req, endpoint_id).await?;
+
+        // Some API versions answer with an empty body; fall back to the target.
+        let trimmed = body.trim();
+        if trimmed.is_empty() || trimmed == "null" {
+            return Ok(target);
+        }
+        let raw: EndpointResponse = self
+            .cfg
+            .api_version
+            .decode(ApiResource::Endpoint, &body)
+            .map_err(ServerlessError::Json)?;
+        Ok(Endpoint::from(raw).scaling)
+    }
+
+    /// Submit an asynchronous job.
+    ///
+    /// With `webhook` set, `RunPod` POSTs the finished job to that URL (see
+    /// the `runpod_webhook` module, feature `webhook`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the API returns an error.
+    pub async fn submit_job(
+        &self,
+        endpoint_id: &str,
+        input: &serde_json::Value,
+        webhook: Option<&str>,
+    ) -> Result<JobResult, ServerlessError> {
+        let mut payload = serde_json::json!({ "input": input });
+        if let Some(url) = webhook {
+            payload["webhook"] = serde_json::Value::String(url.to_string());
+        }
+
+        let req = self
+            .http
+            .post(self.job_url(endpoint_id, "run"))
+            .bearer_auth(self.cfg.api_key.expose())
+            .json(&payload);
+        let body = self.send(req, endpoint_id).await?;
+
+        serde_json::from_str(&body).map_err(ServerlessError::Json)
+    }
+
+    /// Get the status (and output, once finished) of a job.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ServerlessError::JobNotFound` if the job does not exist (or
+    /// expired), and another error if the request fails or the API returns
+    /// an error.
+    pub async fn job_status(
+        &self,
+        endpoint_id: &str,
+        job_id: &str,
+    ) -> Result<JobResult, ServerlessError> {
+        let req = self
+            .http
+            .get(self.job_url(endpoint_id, &format!("status/{job_id}")))
+            .bearer_auth(self.cfg.api_key.expose());
+        let body = self
+            .send(req, endpoint_id)
+            .await
+            .map_err(|e| job_not_found(e, job_id))?;
+
+        serde_json::from_str(&body).map_err(ServerlessError::Json)
+    }
+
+    /// Cancel a queued or running job.
+    ///
+    /// Cancelling a job that already finished is not an error; it reports
+    /// `removed: 0` with the job's final status.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ServerlessError::JobNotFound` if the job does not exist (or
+    /// expired), and another error if the request fails or the API returns
+    /// an error.
+    pub async fn cancel_job(
+        &self,
+        endpoint_id: &str,
+        job_id: &str,
+    ) -> Result<CancelResult, ServerlessError> {
+        let before = self.job_status(endpoint_id, job_id).await?;
+        if before.status.is_terminal() {
+            return Ok(CancelResult {
+                job_id: job_id.to_string(),
+                status: before.status,
+                removed: 0,
+            });
+        }
+
+        let req = self
+            .http
+            .post(self.job_url(endpoint_id, &format!("cancel/{job_id}")))
+            .bearer_auth(self.cfg.api_key.expose());
+        let body = self
+            .send(req, endpoint_id)
+            .await
+            .map_err(|e| job_not_found(e, job_id))?;
+
+        let job: JobResult = serde_json::from_str(&body).map_err(ServerlessError::Json)?;
+        Ok(CancelResult {
+            job_id: job_id.to_string(),
+            removed: u32::from(job.status == JobStatus::Cancelled),
+            status: job.status,
+        })
+    }
+
+    /// Remove every queued job of an endpoint.
+    ///
+    /// Jobs already running are left to finish (cancel them with `cancel_job()`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the API returns an error.
+    pub async fn purge_queue(&self, endpoint_id: &str) -> Result<PurgeResult, ServerlessError> {
+        let req = self
+            .http
+            .post(self.job_url(endpoint_id, "purge-queue"))
+            .bearer_auth(self.cfg.api_key.expose());
+        let body = self.send(req, endpoint_id).await?;
+
+        let purged: PurgeResponse = serde_json::from_str(&body).map_err(ServerlessError::Json)?;
+        Ok(PurgeResult {
+            endpoint_id: endpoint_id.to_string(),
+            removed: purged.removed,
+        })
+    }
+
+    /// Get an endpoint's worker and job counters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails or the API returns an error.
+    pub async fn health(&self, endpoint_id: &str) -> Result<EndpointHealth, ServerlessError> {
+        let req = self
+            .http
+            .get(self.job_url(endpoint_id, "health"))
+            .bearer_auth(self.cfg.api_key.expose());
+        let body = self.send(req, endpoint_id).await?;
+
+        serde_json::from_str(&body).map_err(ServerlessError::Json)
+    }
+
+    /// Poll an endpoint's health every `interval`, forever.
+    ///
+    /// The first snapshot is taken immediately. Failed polls are yielded as
+    /// errors and polling continues; stop by dropping the stream.
+    pub fn health_stream<'a>(
+        &'a self,
+        endpoint_id: &'a str,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<EndpointHealth, ServerlessError>> + 'a {
+        stream::unfold(true, move |first| async move {
+            if !first {
+                tokio::time::sleep(interval).await;
+            }
+            Some((self.health(endpoint_id).await, false))
+        })
+    }
+
+    fn job_url(&self, endpoint_id: &str, path: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            self.cfg.api_url.trim_end_matches('/'),
+            endpoint_id,
+            path
+        )
+    }
+
+    fn endpoint_url(&self, endpoint_id: &str) -> String {
+        format!(
+            "{}/endpoints/{}",
+            self.cfg.rest_url.trim_end_matches('/'),
+            endpoint_id
+        )
+    }
+
+    /// Send a request, recording metrics, and return the body of a success.
+    ///
+    /// Transient failures are retried per `retry`. Non-idempotent requests,
+    /// such as `POST /run`, are only retried when the server did not act on
+    /// them, so a lost response never submits a job twice.
+    async fn send(
+        &self,
+        req: reqwest::RequestBuilder,
+        endpoint_id: &str,
+    ) -> Result<String, ServerlessError> {
+        let (client, req) = runpod_http::with_timeout(req, self.cfg.timeout_ms).build_split();
+        let mut req = req.map_err(ServerlessError::Http)?;
+        let idempotent = runpod_http::is_idempotent(req.method());
+        let mut attempt: u32 = 0;
+        let res = loop {
+            attempt = attempt.saturating_add(1);
+            let retry_req = req.try_clone();
+
+            let sent_at = std::time::Instant::now();
+            let res = client.execute(req).await;
+
+            let status_tag = res
+                .as_ref()
+                .map_or_else(|_| "error".to_string(), |r| r.status().as_u16().to_string());
+            self.metrics.counter(
+                runpod_metrics::HTTP_REQUESTS,
+                1,
+                &[("component", "serverless"), ("status", &status_tag)],
+            );
+            self.metrics.histogram(
+                runpod_metrics::HTTP_REQUEST_MS,
+                runpod_metrics::elapsed_ms(sent_at),
+                &[("component", "serverless")],
+            );
+
+            let retryable = match &res {
+                Ok(resp) => self
+                    .cfg
+                    .retry
+                    .retries_status(attempt, resp.status(), idempotent),
+                Err(e) => self.cfg.retry.retries_error(attempt, e, idempotent),
+            };
+            match retry_req {
+                Some(next) if retryable => {
+                    log_debug!(
+                        "serverless request returned {status_tag}, retrying (attempt {attempt})"
+                    );
+                    runpod_http::record_retry(self.metrics.as_ref(), "serverless");
+                    tokio::time::sleep(self.cfg.retry.delay(attempt)).await;
+                    req = next;
+                }
+                _ => break res,
+            }
+        };
+
+        let resp = res.map_err(ServerlessError::Http)?;
+        let status = resp.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(ServerlessError::NotFound(endpoint_id.to_string()));
+        }
+        if !status.is_success() {
+            let body = resp.text().await.unwrap_or_default();
+            return Err(ServerlessError::Api { status, body });
+        }
+        resp.text().await.map_err(ServerlessError::Http)
+    }
+}
+
+// ============================================================================
+// Response types
+// ============================================================================
+
+#[derive(Debug, Deserialize)]
+#[allow(non_snake_case)]
+struct EndpointResponse {
+    id: String,
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    templateId: Option<String>,
+    #[serde(default)]
+    gpuTypeIds: Vec<String>,
+    #[serde(default)]
+    workersMin: u32,
+    #[serde(default)]
+    workersMax: u32,
+    #[serde(default)]
+    idleTimeout: u32,
+    #[serde(default)]
+    scalerType: Option<ScalerType>,
+    #[serde(default)]
+    scalerValue: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurgeResponse {
+    #[serde(default)]
+    removed: u32,
+}
+
+impl From<EndpointResponse> for Endpoint {
+    fn from(raw: EndpointResponse) -> Self {
+        Self {
+            id: raw.id,
+            name: raw.name,
+            template_id: raw.templateId,
+            gpu_type_ids: raw.gpuTypeIds,
+            scaling: EndpointScaling {
+                workers_min: raw.workersMin,
+                workers_max: raw.workersMax,
+                idle_timeout_s: raw.idleTimeout,
+                scaler_type: raw.scalerType.unwrap_or(ScalerType::QueueDelay),
+                scaler_value: raw.scalerValue,
+            },
+        }
+    }
+}
+
+// ============================================================================
+// Error type
+// ============================================================================
+
+/// Error type for serverless operations.
+#[derive(Debug)]
+pub enum ServerlessError {
+    /// Missing required environment variable.
+    MissingEnv(&'static str),
+    /// Invalid environment variable value.
+    InvalidEnv {
+        /// The environment variable key.
+        key: &'static str,
+        /// The reason for invalidity.
+        reason: &'static str,
+    },
+    /// HTTP client error.
+    Http(reqwest::Error),
+    /// JSON parsing error.
+    Json(serde_json::Error),
+    /// API error response.
+    Api {
+        /// HTTP status code.
+        status: reqwest::StatusCode,
+        /// Response body.
+        body: String,
+    },
+    /// The endpoint does not exist.
+    NotFound(String),
+    /// The job does not exist on the endpoint (unknown ID, or expired).
+    JobNotFound {
+        /// The endpoint ID.
+        endpoint_id: String,
+        /// The job ID.
+        job_id: String,
+    },
+    /// The requested scaling is inconsistent.
+    InvalidScaling(String),
+    /// The env file (`RUNPOD_ENV_FILE`) could not be loaded.
+    EnvFile(EnvFileError),
+}
+
+impl fmt::Display for ServerlessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingEnv(k) => write!(f, "missing required env var: {k}"),
+            Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
+            Self::Http(e) => crate::runpod_http::fmt_http_error(f, e),
+            Self::Json(_) => write!(f, "invalid json response"),
+            Self::Api { status, body } => {
+                write!(f, "runpod api error: status={status}, body={body}")
+            }
+            Self::NotFound(id) => write!(f, "endpoint not found: {id}"),
+            Self::JobNotFound {
+                endpoint_id,
+                job_id,
+            } => write!(f, "job {job_id} not found on endpoint {endpoint_id}"),
+            Self::InvalidScaling(e) => write!(f, "invalid scaling: {e}"),
+            Self::EnvFile(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for ServerlessError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(e) => Some(e),
+            Self::Json(e) => Some(e),
+            Self::EnvFile(e) => std::error::Error::source(e),
+            _ => None,
+        }
+    }
+}
+
+/// A 404 on a job URL means the job, not the endpoint, is gone.
+fn job_not_found(err: ServerlessError, job_id: &str) -> ServerlessError {
+    match err {
+        ServerlessError::NotFound(endpoint_id) => ServerlessError::JobNotFound {
+            endpoint_id,
+            job_id: job_id.to_string(),
+        },
+        other => other,
+    }
+}
+
+fn must_env(key: &'static str) -> Result<String, ServerlessError> {
+    env::var(key).map_err(|_| ServerlessError::MissingEnv(key))
+}
+
+fn parse_u64_env(key: &'static str, default: u64) -> Result<u64, ServerlessError> {
+    runpod_env::u64_var(key, default).map_err(|reason| ServerlessError::InvalidEnv { key, reason })
+}
+
+fn parse_opt_u32_env(key: &'static str) -> Result<Option<u32>, ServerlessError> {
+    env::var(key)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .map_or(Ok(None), |v| {
+            v.trim()
+                .parse::<u32>()
+                .map(Some)
+                .map_err(|_| ServerlessError::InvalidEnv {
+                    key,
+                    reason: "expected an unsigned integer",
+                })
+        })
+}
@@ -0,0 +1,269 @@
+//! Offline `RunPod` simulator.
+//!
+//! Unique responsibility: a deterministic in-memory pod model standing in for
+//! the `RunPod` API, so orchestration code can be developed and demoed without
+//! an account or network access.
+//!
+//! Enabled in the orchestrator with `RUNPOD_SIMULATE=true`. The model:
+//! - Pod IDs are `sim-pod-0001`, `sim-pod-0002`, ... in creation order
+//! - A running pod becomes ready after `ready_after_ticks` status reads
+//!   (`RUNPOD_SIMULATE_READY_TICKS`, default: 3)
+//! - Ready pods get a fake public IP (`10.0.0.<n>`) and port mappings
+//!   (`40000 + 100 * <n> + <i>` for the i-th exposed port)
+//! - Terminated pods disappear, as on `RunPod`
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, PoisonError},
+};
+
+use crate::runpod_orchestrator::{PodDetails, PodInfo, PodUpdate};
+use crate::runpod_provisioner::CreatedPod;
+
+/// Deterministic in-memory stand-in for the `RunPod` pod API.
+#[derive(Debug)]
+pub struct SimulatedCloud {
+    ready_after_ticks: u32,
+    inner: Mutex<SimInner>,
+}
+
+#[derive(Debug, Default)]
+struct SimInner {
+    pods: BTreeMap<String, SimPod>,
+    created: u32,
+}
+
+#[derive(Debug, Clone)]
+struct SimPod {
+    id: String,
+    index: u32,
+    name: String,
+    image_name: String,
+    ports: Vec<String>,
+    desired_status: String,
+    ticks_left: u32,
+    network_volume_id: Option<String>,
+    volume_mount_path: Option<String>,
+    interruptible: bool,
+    env: HashMap<String, String>,
+}
+
+impl SimPod {
+    fn is_ready(&self) -> bool {
+        self.desired_status == "RUNNING" && self.ticks_left == 0
+    }
+
+    fn info(&self) -> PodInfo {
+        PodInfo {
+            id: self.id.clone(),
+            name: Some(self.name.clone()),
+            desiredStatus: Some(self.desired_status.clone()),
+            imageName: Some(self.image_name.clone()),
+            machineId: Some("sim-machine".to_string()),
+            costPerHr: None,
+            env: (!self.env.is_empty()).then(|| self.env.clone()),
+            lastStartedAt: None,
+        }
+    }
+
+    fn details(&self) -> PodDetails {
+        let ready = self.is_ready();
+        let port_mappings = ready.then(|| {
+            self.ports
+                .iter()
+                .zip(0_u16..)
+                .filter_map(|(spec, i)| {
+                    let container = spec.split('/').next()?.parse::<u16>().ok()?;
+                    let base = u16::try_from(self.index % 250).ok()?.saturating_mul(100);
+                    Some((
+                        container.to_string(),
+                        40_000_u16.saturating_add(base).saturating_add(i),
+                    ))
+                })
+                .collect()
+        });
+
+        PodDetails {
+            id: self.id.clone(),
+            name: Some(self.name.clone()),
+            desiredStatus: Some(self.desired_status.clone()),
+            imageName: Some(self.image_name.clone()),
+            publicIp: ready.then(|| format!("10.0.0.{}", self.index % 254 + 1)),
+            portMappings: port_mappings,
+            ports: Some(self.ports.clone()),
+            networkVolumeId: self.network_volume_id.clone(),
+            volumeMountPath: self.volume_mount_path.clone(),
+            env: (!self.env.is_empty()).then(|| self.env.clone()),
+            gpu: None,
+            containerDiskInGb: None,
+            volumeInGb: None,
+            dockerStartCmd: None,
+            interruptible: Some(self.interruptible),
+            machine: None,
+            machineId: Some("sim-machine".to_string()),
+            costPerHr: None,
+            lastStartedAt: None,
+        }
+    }
+}
+
+impl SimulatedCloud {
+    /// Create an empty simulated cloud where pods become ready after
+    /// `ready_after_ticks` status reads.
+    #[must_use]
+    pub fn new(ready_after_ticks: u32) -> Self {
+        Self {
+            ready_after_ticks,
+            inner: Mutex::new(SimInner::default()),
+        }
+    }
+
+    /// List all (non-terminated) pods.
+    #[must_use]
+    pub fn list_pods(&self) -> Vec<PodInfo> {
+        self.lock().pods.values().map(SimPod::info).collect()
+    }
+
+    /// Every pod's details, without advancing readiness.
+    #[must_use]
+    pub fn list_pod_details(&self) -> Vec<PodDetails> {
+        self.lock().pods.values().map(SimPod::details).collect()
+    }
+
+    /// Read a pod, advancing its readiness by one tick.
+    #[must_use]
+    pub fn get_pod(&self, pod_id: &str) -> Option<PodDetails> {
+        self.with_pod(pod_id, |pod, _| {
+            if pod.desired_status == "RUNNING" {
+                pod.ticks_left = pod.ticks_left.saturating_sub(1);
+            }
+            pod.details()
+        })
+    }
+
+    /// Create a running pod (not ready until its ticks have elapsed).
+    #[must_use]
+    pub fn create_pod(
+        &self,
+        name: &str,
+        image_name: &str,
+        ports: &[String],
+        network_volume: Option<(&str, &str)>,
+    ) -> CreatedPod {
+        let mut inner = self.lock();
+        inner.created = inner.created.saturating_add(1);
+        let index = inner.created;
+        let id = format!("sim-pod-{index:04}");
+
+        inner.pods.insert(
+            id.clone(),
+            SimPod {
+                id: id.clone(),
+                index,
+                name: name.to_string(),
+                image_name: image_name.to_string(),
+                ports: ports.to_vec(),
+                desired_status: "RUNNING".to_string(),
+                ticks_left: self.ready_after_ticks,
+                network_volume_id: network_volume.map(|(volume_id, _)| volume_id.to_string()),
+                volume_mount_path: network_volume.map(|(_, path)| path.to_string()),
+                interruptible: false,
+                env: HashMap::new(),
+            },
+        );
+        drop(inner);
+
+        CreatedPod {
+            id,
+            desired_status: Some("RUNNING".to_string()),
+            public_ip: None,
+            cloud_type: Some("SIMULATED".to_string()),
+            skipped_cloud_types: Vec::new(),
+            skipped_gpu_types: Vec::new(),
+            gpu_type_id: None,
+            data_center_id: None,
+        }
+    }
+
+    /// Start a stopped pod. Returns `false` if the pod does not exist.
+    #[must_use]
+    pub fn start_pod(&self, pod_id: &str) -> bool {
+        self.with_pod(pod_id, |pod, ready_after| {
+            if pod.desired_status != "RUNNING" {
+                pod.desired_status = "RUNNING".to_string();
+                pod.ticks_left = ready_after;
+            }
+        })
+        .is_some()
+    }
+
+    /// Stop a running pod. Returns `false` if the pod does not exist.
+    #[must_use]
+    pub fn stop_pod(&self, pod_id: &str) -> bool {
+        self.with_pod(pod_id, |pod, _| pod.desired_status = "EXITED".to_string())
+            .is_some()
+    }
+
+    /// Preempt a pod as `RunPod` reclaims a spot pod: it is marked
+    /// interruptible and stopped. Returns `false` if the pod does not exist.
+    #[must_use]
+    pub fn preempt_pod(&self, pod_id: &str) -> bool {
+        self.with_pod(pod_id, |pod, _| {
+            pod.interruptible = true;
+            pod.desired_status = "EXITED".to_string();
+        })
+        .is_some()
+    }
+
+    /// Apply an in-place update (restarts the container).
+    ///
+    /// Returns `None` if the pod does not exist.
+    #[must_use]
+    pub fn update_pod(&self, pod_id: &str, changes: &PodUpdate) -> Option<PodDetails> {
+        self.with_pod(pod_id, |pod, ready_after| {
+            if let Some(name) = &changes.name {
+                pod.name.clone_from(name);
+            }
+            if let Some(image) = &changes.image_name {
+                pod.image_name.clone_from(image);
+            }
+            if let Some(ports) = &changes.ports {
+                pod.ports.clone_from(ports);
+            }
+            if let Some(env) = &changes.env {
+                pod.env.clone_from(env);
+            }
+            if pod.desired_status == "RUNNING" {
+                pod.ticks_left = ready_after;
+            }
+            pod.details()
+        })
+    }
+
+    /// Terminate (remove) a pod. Returns `false` if the pod does not exist.
+    #[must_use]
+    pub fn terminate_pod(&self, pod_id: &str) -> bool {
+        self.lock().pods.remove(pod_id).is_some()
+    }
+
+    /// Advance every running pod by one tick.
+    pub fn tick(&self) {
+        for pod in self.lock().pods.values_mut() {
+            if pod.desired_status == "RUNNING" {
+                pod.ticks_left = pod.ticks_left.saturating_sub(1);
+            }
+        }
+    }
+
+    fn with_pod<T>(&self, pod_id: &str, f: impl FnOnce(&mut SimPod, u32) -> T) -> Option<T> {
+        self.lock()
+            .pods
+            .get_mut(pod_id)
+            .map(|pod| f(pod, self.ready_after_ticks))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SimInner> {
+        // The model stays consistent even if a holder panicked.
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
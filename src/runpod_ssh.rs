@@ -0,0 +1,633 @@
+//! SSH host key management.
+//!
+//! Unique responsibility: pin the host keys of leased pods in a managed
+//! `known_hosts` file, so SSH connections keep host checking on.
+//!
+//! Pods get fresh host keys on every (re)creation, which is why SSH is so
+//! often run with `StrictHostKeyChecking no`. Instead, `KnownHosts` records
+//! the key the first time a pod is seen (trust on first use, via
+//! `ssh-keyscan`) and verifies it on every later connection:
+//! - `HostKeyPolicy::AcceptNew`: unknown hosts are recorded, changed keys are rejected
+//! - `HostKeyPolicy::Strict`: unknown hosts are rejected too (keys must be added beforehand)
+//!
+//! ```ignore
+//! let known_hosts = KnownHosts::from_env()?;
+//! let pod = orchestrator.ensure_ready_pod().await?;
+//! known_hosts.verify_lease(&pod).await?;
+//! // ssh <options> -p <port> root@<ip>, with host checking on
+//! let options = known_hosts.ssh_options();
+//! ```
+//!
+//! `SshLogin` runs commands on a leased pod with those options (user,
+//! identity file, host checking) and a timeout.
+
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::runpod_orchestrator::{PodLease, ssh_destination_args};
+
+/// What to do with a host that has no recorded key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HostKeyPolicy {
+    /// Reject unknown hosts and changed keys.
+    Strict,
+    /// Record the key of unknown hosts (trust on first use), reject changed keys.
+    #[default]
+    AcceptNew,
+}
+
+impl HostKeyPolicy {
+    /// Parse "strict" or "accept-new" (case-insensitive).
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "strict" | "yes" => Some(Self::Strict),
+            "accept-new" | "accept_new" | "tofu" => Some(Self::AcceptNew),
+            _ => None,
+        }
+    }
+
+    /// Matching OpenSSH `StrictHostKeyChecking` value.
+    #[must_use]
+    pub const fn ssh_option(self) -> &'static str {
+        match self {
+            Self::Strict => "yes",
+            Self::AcceptNew => "accept-new",
+        }
+    }
+}
+
+/// One host public key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HostKey {
+    /// Key type (e.g. `ssh-ed25519`).
+    pub key_type: String,
+    /// Base64-encoded public key.
+    pub key: String,
+}
+
+impl fmt::Display for HostKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.key_type, self.key)
+    }
+}
+
+/// Outcome of a successful host key verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostKeyStatus {
+    /// The host presented a recorded key.
+    Trusted,
+    /// The host was unknown; its keys were recorded (`AcceptNew` only).
+    Added(Vec<HostKey>),
+}
+
+/// Managed `known_hosts` file and host key policy.
+#[derive(Debug, Clone)]
+pub struct KnownHosts {
+    path: PathBuf,
+    policy: HostKeyPolicy,
+    scan_timeout: Duration,
+}
+
+impl KnownHosts {
+    /// Manage the `known_hosts` file at `path`, with the `AcceptNew` policy.
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            policy: HostKeyPolicy::AcceptNew,
+            scan_timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Load settings from environment variables.
+    ///
+    /// - `RUNPOD_SSH_KNOWN_HOSTS`: managed file (default: `~/.ssh/halldyll_known_hosts`)
+    /// - `RUNPOD_SSH_HOST_KEY_POLICY`: "strict" or "accept-new" (default: "accept-new")
+    /// - `RUNPOD_SSH_KEYSCAN_TIMEOUT_SECS`: `ssh-keyscan` timeout (default: 10)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is invalid, or the home directory cannot
+    /// be determined when `RUNPOD_SSH_KNOWN_HOSTS` is unset.
+    pub fn from_env() -> Result<Self, SshError> {
+        let path = match env::var_os("RUNPOD_SSH_KNOWN_HOSTS").filter(|p| !p.is_empty()) {
+            Some(path) => PathBuf::from(path),
+            None => default_known_hosts_path()?,
+        };
+        let policy = env::var("RUNPOD_SSH_HOST_KEY_POLICY")
+            .map_or(Some(HostKeyPolicy::AcceptNew), |v| HostKeyPolicy::parse(&v))
+            .ok_or(SshError::InvalidEnv {
+                key: "RUNPOD_SSH_HOST_KEY_POLICY",
+                reason: "expected strict or accept-new",
+            })?;
+        let timeout_secs = env::var("RUNPOD_SSH_KEYSCAN_TIMEOUT_SECS").map_or(Ok(10), |v| {
+            v.parse::<u64>().map_err(|_| SshError::InvalidEnv {
+                key: "RUNPOD_SSH_KEYSCAN_TIMEOUT_SECS",
+                reason: "expected an unsigned integer",
+            })
+        })?;
+
+        Ok(Self::new(path)
+            .with_policy(policy)
+            .with_scan_timeout(Duration::from_secs(timeout_secs)))
+    }
+
+    /// Manage `~/.ssh/halldyll_known_hosts` (`halldyll_known_hosts` in the
+    /// current directory without a home directory), with the `AcceptNew` policy.
+    #[must_use]
+    pub fn managed() -> Self {
+        Self::new(
+            default_known_hosts_path().unwrap_or_else(|_| PathBuf::from("halldyll_known_hosts")),
+        )
+    }
+
+    /// Set the host key policy.
+    #[must_use]
+    pub const fn with_policy(mut self, policy: HostKeyPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Set the `ssh-keyscan` timeout (rounded up to whole seconds).
+    #[must_use]
+    pub const fn with_scan_timeout(mut self, timeout: Duration) -> Self {
+        self.scan_timeout = timeout;
+        self
+    }
+
+    /// Get the managed `known_hosts` file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the host key policy.
+    #[must_use]
+    pub const fn policy(&self) -> HostKeyPolicy {
+        self.policy
+    }
+
+    /// `known_hosts` pattern for `host:port` (`host` alone on port 22).
+    #[must_use]
+    pub fn host_pattern(host: &str, port: u16) -> String {
+        if port == 22 {
+            host.to_string()
+        } else {
+            format!("[{host}]:{port}")
+        }
+    }
+
+    /// Keys recorded for `host:port`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read.
+    pub fn keys(&self, host: &str, port: u16) -> Result<Vec<HostKey>, SshError> {
+        let pattern = Self::host_pattern(host, port);
+        Ok(self
+            .read_lines()?
+            .iter()
+            .filter_map(|line| parse_line(line))
+            .filter(|(patterns, _)| patterns.iter().any(|p| *p == pattern))
+            .map(|(_, key)| key)
+            .collect())
+    }
+
+    /// Record `keys` for `host:port` (keys already recorded are skipped).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or written.
+    pub fn add(&self, host: &str, port: u16, keys: &[HostKey]) -> Result<(), SshError> {
+        let known = self.keys(host, port)?;
+        let pattern = Self::host_pattern(host, port);
+        let mut lines = self.read_lines()?;
+        lines.extend(
+            keys.iter()
+                .filter(|key| !known.contains(key))
+                .map(|key| format!("{pattern} {key}")),
+        );
+        self.write_lines(&lines)
+    }
+
+    /// Remove every key recorded for `host:port`, returning how many were removed.
+    ///
+    /// Call it when a pod is replaced: its successor may get the same IP and
+    /// port, with a different key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or written.
+    pub fn forget(&self, host: &str, port: u16) -> Result<usize, SshError> {
+        let pattern = Self::host_pattern(host, port);
+        let lines = self.read_lines()?;
+        let kept: Vec<String> = lines
+            .iter()
+            .filter(|line| {
+                parse_line(line).is_none_or(|(patterns, _)| !patterns.iter().any(|p| *p == pattern))
+            })
+            .cloned()
+            .collect();
+        let removed = lines.len() - kept.len();
+        if removed > 0 {
+            self.write_lines(&kept)?;
+        }
+        Ok(removed)
+    }
+
+    /// Fetch the keys `host:port` presents, with `ssh-keyscan`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `ssh-keyscan` cannot be run or returns no key.
+    pub async fn scan(&self, host: &str, port: u16) -> Result<Vec<HostKey>, SshError> {
+        let timeout_secs =
+            self.scan_timeout.as_secs().max(1) + u64::from(self.scan_timeout.subsec_nanos() > 0);
+        let output = Command::new("ssh-keyscan")
+            .arg("-T")
+            .arg(timeout_secs.to_string())
+            .arg("-p")
+            .arg(port.to_string())
+            .arg(host)
+            .output()
+            .await
+            .map_err(|e| SshError::Spawn {
+                program: "ssh-keyscan".to_string(),
+                source: e,
+            })?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let keys: Vec<HostKey> = stdout
+            .lines()
+            .filter_map(parse_line)
+            .map(|(_, key)| key)
+            .collect();
+        if keys.is_empty() {
+            return Err(SshError::Scan(format!("no host key from {host}:{port}")));
+        }
+        Ok(keys)
+    }
+
+    /// Verify the keys `host:port` presents against the recorded ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host presents none of its recorded keys, is
+    /// unknown under the `Strict` policy, or cannot be scanned.
+    pub async fn verify(&self, host: &str, port: u16) -> Result<HostKeyStatus, SshError> {
+        let presented = self.scan(host, port).await?;
+        let known = self.keys(host, port)?;
+
+        if known.is_empty() {
+            return match self.policy {
+                HostKeyPolicy::Strict => Err(SshError::UnknownHost(Self::host_pattern(host, port))),
+                HostKeyPolicy::AcceptNew => {
+                    self.add(host, port, &presented)?;
+                    Ok(HostKeyStatus::Added(presented))
+                }
+            };
+        }
+
+        if presented.iter().any(|key| known.contains(key)) {
+            Ok(HostKeyStatus::Trusted)
+        } else {
+            Err(SshError::HostKeyMismatch {
+                host: Self::host_pattern(host, port),
+                presented,
+            })
+        }
+    }
+
+    /// Verify the SSH endpoint (port 22 mapping) of a lease.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease has no SSH endpoint, or as `verify()`.
+    pub async fn verify_lease(&self, lease: &PodLease) -> Result<HostKeyStatus, SshError> {
+        let (host, port) = lease
+            .ssh_endpoint()
+            .ok_or_else(|| SshError::NoSshEndpoint(lease.id.clone()))?;
+        self.verify(host, port).await
+    }
+
+    /// `ssh` options using the managed file and policy
+    /// (`-o UserKnownHostsFile=... -o StrictHostKeyChecking=...`).
+    #[must_use]
+    pub fn ssh_options(&self) -> Vec<String> {
+        vec![
+            "-o".to_string(),
+            format!("UserKnownHostsFile={}", self.path.display()),
+            "-o".to_string(),
+            format!("StrictHostKeyChecking={}", self.policy.ssh_option()),
+        ]
+    }
+
+    fn read_lines(&self) -> Result<Vec<String>, SshError> {
+        match fs::read_to_string(&self.path) {
+            Ok(s) => Ok(s.lines().map(str::to_string).collect()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(SshError::Io(e)),
+        }
+    }
+
+    /// Replace the file atomically (write to a temp file, then rename).
+    fn write_lines(&self, lines: &[String]) -> Result<(), SshError> {
+        if let Some(parent) = self.path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent).map_err(SshError::Io)?;
+        }
+        let mut contents = lines.join("\n");
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        let tmp = self
+            .path
+            .with_extension(format!("{}.tmp", std::process::id()));
+        fs::write(&tmp, contents).map_err(SshError::Io)?;
+        fs::rename(&tmp, &self.path).map_err(SshError::Io)
+    }
+}
+
+// ============================================================================
+// Remote commands
+// ============================================================================
+
+/// How to log in to pods to run commands over SSH.
+#[derive(Debug, Clone)]
+pub struct SshLogin {
+    /// Remote user.
+    /// Env: `RUNPOD_SSH_USER` (default: "root")
+    pub user: String,
+    /// Private key to use (`ssh -i`).
+    /// Env: `RUNPOD_SSH_IDENTITY_FILE` (optional)
+    pub identity_file: Option<PathBuf>,
+    /// Managed `known_hosts` file and policy (default: `KnownHosts::managed()`).
+    /// `None` disables host checking: an explicit, insecure opt-in.
+    pub known_hosts: Option<KnownHosts>,
+    /// SSH client program.
+    pub program: String,
+}
+
+impl SshLogin {
+    /// Log in as `root` with the default key, with host checking through
+    /// `KnownHosts::managed()` (accept-new).
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            user: "root".to_string(),
+            identity_file: None,
+            known_hosts: Some(KnownHosts::managed()),
+            program: "ssh".to_string(),
+        }
+    }
+
+    /// Load the login from environment variables, with host checking through
+    /// `KnownHosts::from_env()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is invalid.
+    pub fn from_env() -> Result<Self, SshError> {
+        let mut login = Self::new();
+        if let Ok(user) = env::var("RUNPOD_SSH_USER")
+            && !user.is_empty()
+        {
+            login.user = user;
+        }
+        login.identity_file = env::var_os("RUNPOD_SSH_IDENTITY_FILE")
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from);
+        login.known_hosts = Some(KnownHosts::from_env()?);
+        Ok(login)
+    }
+
+    /// Build the `ssh` command running `remote_command` on `host:port`
+    /// (non-interactive, stdin closed, stdout/stderr piped).
+    #[must_use]
+    pub fn command(&self, host: &str, port: u16, remote_command: &str) -> Command {
+        let mut options = vec!["-o".to_string(), "BatchMode=yes".to_string()];
+        match &self.known_hosts {
+            Some(known_hosts) => options.extend(known_hosts.ssh_options()),
+            None => options.extend(
+                [
+                    "-o",
+                    "StrictHostKeyChecking=no",
+                    "-o",
+                    "UserKnownHostsFile=/dev/null",
+                ]
+                .map(String::from),
+            ),
+        }
+        let identity = self.identity_file.as_deref();
+        let mut cmd = Command::new(&self.program);
+        cmd.args(ssh_destination_args(
+            host, port, &self.user, identity, &options,
+        ))
+        .arg(remote_command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+        cmd
+    }
+
+    /// Run `remote_command` on `host:port`, killing it after `timeout`.
+    ///
+    /// A non-zero exit status is not an error: check `SshOutput::success()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `ssh` cannot be started or `timeout` elapses.
+    pub async fn exec(
+        &self,
+        host: &str,
+        port: u16,
+        remote_command: &str,
+        timeout: Duration,
+    ) -> Result<SshOutput, SshError> {
+        let output =
+            tokio::time::timeout(timeout, self.command(host, port, remote_command).output())
+                .await
+                .map_err(|_| SshError::CommandTimeout {
+                    command: remote_command.to_string(),
+                    timeout,
+                })?
+                .map_err(|e| SshError::Spawn {
+                    program: self.program.clone(),
+                    source: e,
+                })?;
+        Ok(SshOutput {
+            status: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+
+    /// Run `remote_command` on the SSH endpoint (port 22 mapping) of a lease.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease has no SSH endpoint, or as `exec()`.
+    pub async fn exec_lease(
+        &self,
+        lease: &PodLease,
+        remote_command: &str,
+        timeout: Duration,
+    ) -> Result<SshOutput, SshError> {
+        let (host, port) = lease
+            .ssh_endpoint()
+            .ok_or_else(|| SshError::NoSshEndpoint(lease.id.clone()))?;
+        self.exec(host, port, remote_command, timeout).await
+    }
+
+    /// Whether `host:port` accepts a login: runs `true` there.
+    ///
+    /// `ssh` exits with 255 both when the connection fails and when the
+    /// remote command does: probing tells the two apart.
+    pub async fn can_connect(&self, host: &str, port: u16, timeout: Duration) -> bool {
+        self.exec(host, port, "true", timeout)
+            .await
+            .is_ok_and(|output| output.success())
+    }
+}
+
+impl Default for SshLogin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Output of a command run with `SshLogin::exec()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshOutput {
+    /// Exit status (`None` if killed by a signal). `ssh` itself exits with 255
+    /// when the connection fails.
+    pub status: Option<i32>,
+    /// Standard output.
+    pub stdout: String,
+    /// Standard error.
+    pub stderr: String,
+}
+
+impl SshOutput {
+    /// Check whether the command exited with status 0.
+    #[must_use]
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
+/// Quote `value` for a POSIX shell (remote commands run through the login
+/// shell of the pod).
+#[must_use]
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Parse a `known_hosts` / `ssh-keyscan` line into its host patterns and key.
+///
+/// Comments, markers (`@revoked`, `@cert-authority`) and hashed hosts are skipped.
+fn parse_line(line: &str) -> Option<(Vec<&str>, HostKey)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with('@') || line.starts_with('|') {
+        return None;
+    }
+    let mut fields = line.split_whitespace();
+    let patterns = fields.next()?.split(',').collect();
+    let key_type = fields.next()?.to_string();
+    let key = fields.next()?.to_string();
+    Some((patterns, HostKey { key_type, key }))
+}
+
+fn default_known_hosts_path() -> Result<PathBuf, SshError> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| {
+            PathBuf::from(home)
+                .join(".ssh")
+                .join("halldyll_known_hosts")
+        })
+        .ok_or(SshError::NoHomeDir)
+}
+
+/// Error type for SSH host key management.
+#[derive(Debug)]
+pub enum SshError {
+    /// Invalid environment variable value.
+    InvalidEnv {
+        /// The environment variable key.
+        key: &'static str,
+        /// The reason for invalidity.
+        reason: &'static str,
+    },
+    /// The pod has no SSH port (22) mapped.
+    NoSshEndpoint(String),
+    /// The host has no recorded key (`Strict` policy).
+    UnknownHost(String),
+    /// The host presented none of its recorded keys.
+    HostKeyMismatch {
+        /// Host pattern (`[ip]:port`).
+        host: String,
+        /// Keys the host presented.
+        presented: Vec<HostKey>,
+    },
+    /// The host keys could not be fetched.
+    Scan(String),
+    /// The home directory (for the default file) could not be determined.
+    NoHomeDir,
+    /// File read/write error.
+    Io(io::Error),
+    /// A local program (`ssh`, `ssh-keyscan`) could not be started.
+    Spawn {
+        /// The program that failed to start.
+        program: String,
+        /// The underlying error.
+        source: io::Error,
+    },
+    /// A remote command did not finish in time (it was killed).
+    CommandTimeout {
+        /// The remote command.
+        command: String,
+        /// The timeout that elapsed.
+        timeout: Duration,
+    },
+}
+
+impl fmt::Display for SshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
+            Self::NoSshEndpoint(id) => write!(f, "pod {id} has no ssh endpoint (port 22)"),
+            Self::UnknownHost(host) => write!(f, "no recorded host key for {host}"),
+            Self::HostKeyMismatch { host, .. } => {
+                write!(f, "host key of {host} does not match the recorded one")
+            }
+            Self::Scan(e) => write!(f, "host key scan failed: {e}"),
+            Self::NoHomeDir => write!(f, "cannot determine home directory"),
+            Self::Io(_) => write!(f, "io error"),
+            Self::Spawn { program, .. } => write!(f, "cannot run {program}"),
+            Self::CommandTimeout { command, timeout } => {
+                write!(f, "ssh command timed out after {timeout:?}: {command}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SshError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) | Self::Spawn { source: e, .. } => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -5,38 +5,89 @@
 //! API endpoint used:
 //! - POST <https://rest.runpod.io/v1/pods/{podId}/start>
 //! - Header: Authorization: Bearer <token>
+//!
+//! `start_many()` starts several pods with bounded concurrency, reusing the
+//! same retry policy for every pod.
+//!
+//! Spot (interruptible) pods cannot be started with `/start`; they are
+//! bid-resumed through the GraphQL `podBidResume` mutation instead.
+//! `start_or_resume()` picks the right path from the pod's `interruptible` flag.
+//!
+//! `terminate()` deletes the pod; like the orchestrator and GraphQL client
+//! terminate paths, it treats a pod that is already gone as success.
+//!
+//! With `RUNPOD_DRY_RUN=true`, mutating requests are not sent; they fail with
+//! `RunpodError::DryRun` describing the exact request instead.
 
-use std::{env, fmt, time::Duration};
+use std::{env, fmt, sync::Arc};
+
+use futures_util::stream::{self, StreamExt};
+use serde::Deserialize;
+
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_http::{
+    self, ApiKey, ApiResource, ApiVersion, DryRunRequest, HttpOptions, RetryPolicy,
+};
+use crate::runpod_log::{ErrorChain, log_debug, log_warn};
+use crate::runpod_metrics::{self, MetricsSink};
+use crate::runpod_state::{PodId, TerminateOutcome, is_pod_gone_error};
 
 /// Configuration for starting/resuming a `RunPod` pod.
 pub struct RunpodStarterConfig {
     /// `RunPod` API key for authentication.
     /// Env: `RUNPOD_API_KEY` (required)
-    pub api_key: String,
+    pub api_key: ApiKey,
+
+    /// REST API version: URL default and request/response mapping.
+    /// Env: `RUNPOD_API_VERSION` (default: "v1")
+    pub api_version: ApiVersion,
 
     /// REST API URL for `RunPod`.
-    /// Env: `RUNPOD_REST_URL` (default: "<https://rest.runpod.io/v1>")
+    /// Env: `RUNPOD_REST_URL` (default: the `api_version` URL, "<https://rest.runpod.io/v1>")
     pub rest_url: String,
 
     /// Pod ID to start/resume.
     /// Env: `RUNPOD_POD_ID` (required)
     pub pod_id: String,
 
-    /// HTTP request timeout in milliseconds.
+    /// Total HTTP request timeout in milliseconds (0 = none; see also
+    /// `HttpOptions::connect_timeout_ms` and `read_timeout_ms`).
     /// Env: `RUNPOD_HTTP_TIMEOUT_MS` (default: 15000)
     pub timeout_ms: u64,
 
-    /// Maximum number of retry attempts.
-    /// Env: `RUNPOD_HTTP_RETRY_MAX` (default: 3)
-    pub retry_max: u32,
-
-    /// Backoff time between retries in milliseconds.
-    /// Env: `RUNPOD_HTTP_RETRY_BACKOFF_MS` (default: 250)
-    pub retry_backoff_ms: u64,
+    /// Retry policy for transient failures.
+    /// Env: `RUNPOD_HTTP_RETRY_*` (see `RetryPolicy`)
+    pub retry: RetryPolicy,
 
     /// User agent for HTTP requests.
     /// Env: `RUNPOD_USER_AGENT` (default: "halldyll-starter/1.0")
     pub user_agent: String,
+
+    /// Maximum number of pods started concurrently by `start_many()`.
+    /// Env: `RUNPOD_START_CONCURRENCY` (default: 4)
+    pub max_concurrency: u32,
+
+    /// GraphQL API URL (used to bid-resume spot pods).
+    /// Env: `RUNPOD_GRAPHQL_URL` (default: "<https://api.runpod.io/graphql>")
+    pub graphql_url: String,
+
+    /// Bid per GPU in USD/hour for resuming spot pods (the provisioner's
+    /// creation bid).
+    /// Env: `RUNPOD_BID_PER_GPU` (optional, required to resume spot pods;
+    /// `RUNPOD_SPOT_BID` is a deprecated alias)
+    pub bid_per_gpu: Option<f64>,
+
+    /// Number of GPUs requested when bid-resuming a spot pod.
+    /// Env: `RUNPOD_GPU_COUNT` (default: 1)
+    pub gpu_count: u32,
+
+    /// Describe mutating requests instead of sending them.
+    /// Env: `RUNPOD_DRY_RUN` (default: false)
+    pub dry_run: bool,
+
+    /// Connection pool / keepalive / HTTP version tuning.
+    /// Env: `RUNPOD_HTTP_*` (see `HttpOptions`)
+    pub http: HttpOptions,
 }
 
 impl RunpodStarterConfig {
@@ -49,41 +100,84 @@ impl RunpodStarterConfig {
     ///
     /// Returns an error if required environment variables are missing or invalid.
     pub fn from_env() -> Result<Self, RunpodError> {
-        let _ = dotenvy::dotenv();
-
-        let api_key = must_env("RUNPOD_API_KEY")?;
-        let rest_url = env::var("RUNPOD_REST_URL")
-            .unwrap_or_else(|_| "https://rest.runpod.io/v1".to_string());
+        runpod_env::load_dotenv().map_err(RunpodError::EnvFile)?;
+
+        let api_key = must_env("RUNPOD_API_KEY")?.into();
+        let api_version = ApiVersion::from_env().map_err(|e| RunpodError::InvalidEnv {
+            key: e.key,
+            value: e.value,
+            reason: e.reason,
+        })?;
+        let rest_url = api_version.rest_url_from_env();
         let pod_id = must_env("RUNPOD_POD_ID")?;
 
         let timeout_ms = parse_u64_env("RUNPOD_HTTP_TIMEOUT_MS", 15_000)?;
-        let retry_max = parse_u32_env("RUNPOD_HTTP_RETRY_MAX", 3)?;
-        let retry_backoff_ms = parse_u64_env("RUNPOD_HTTP_RETRY_BACKOFF_MS", 250)?;
+        let retry = RetryPolicy::from_env().map_err(|e| RunpodError::InvalidEnv {
+            key: e.key,
+            value: e.value,
+            reason: e.reason,
+        })?;
 
         let user_agent = env::var("RUNPOD_USER_AGENT")
             .unwrap_or_else(|_| "halldyll-starter/1.0".to_string());
+        let max_concurrency = parse_u32_env("RUNPOD_START_CONCURRENCY", 4)?;
+
+        let graphql_url = env::var("RUNPOD_GRAPHQL_URL")
+            .unwrap_or_else(|_| "https://api.runpod.io/graphql".to_string());
+        let mut bid_per_gpu = parse_f64_opt_env("RUNPOD_BID_PER_GPU")?;
+        if bid_per_gpu.is_none() {
+            bid_per_gpu = parse_f64_opt_env("RUNPOD_SPOT_BID")?;
+            if bid_per_gpu.is_some() {
+                log_warn!("RUNPOD_SPOT_BID is deprecated, use RUNPOD_BID_PER_GPU");
+            }
+        }
+        let gpu_count = parse_u32_env("RUNPOD_GPU_COUNT", 1)?;
+        let dry_run = parse_bool_env("RUNPOD_DRY_RUN", false)?;
+        let http = HttpOptions::from_env().map_err(|e| RunpodError::InvalidEnv {
+            key: e.key,
+            value: e.value,
+            reason: e.reason,
+        })?;
 
         Ok(Self {
             api_key,
+            api_version,
             rest_url,
             pod_id,
             timeout_ms,
-            retry_max,
-            retry_backoff_ms,
+            retry,
             user_agent,
+            max_concurrency,
+            graphql_url,
+            bid_per_gpu,
+            gpu_count,
+            dry_run,
+            http,
         })
     }
 
     /// Build the start URL for the configured pod.
     #[inline]
     fn start_url(&self) -> String {
+        self.start_url_for(&self.pod_id)
+    }
+
+    /// Build the start URL for an arbitrary pod.
+    #[inline]
+    fn start_url_for(&self, pod_id: &str) -> String {
         format!(
             "{}/pods/{}/start",
             self.rest_url.trim_end_matches('/'),
-            self.pod_id
+            pod_id
         )
     }
 
+    /// Build the details URL for an arbitrary pod.
+    #[inline]
+    fn pod_url_for(&self, pod_id: &str) -> String {
+        format!("{}/pods/{}", self.rest_url.trim_end_matches('/'), pod_id)
+    }
+
     /// Build the stop URL for the configured pod.
     #[inline]
     fn stop_url(&self) -> String {
@@ -99,6 +193,7 @@ impl RunpodStarterConfig {
 pub struct RunpodStarter {
     cfg: RunpodStarterConfig,
     http: reqwest::Client,
+    metrics: Arc<dyn MetricsSink>,
 }
 
 impl RunpodStarter {
@@ -106,30 +201,92 @@ impl RunpodStarter {
     ///
     /// # Errors
     ///
-    /// Returns an error if the HTTP client cannot be built.
+    /// Returns an error if the HTTP options are invalid or the HTTP client
+    /// cannot be built.
     pub fn new(cfg: RunpodStarterConfig) -> Result<Self, RunpodError> {
-        let http = reqwest::Client::builder()
-            .timeout(Duration::from_millis(cfg.timeout_ms))
+        let http = cfg
+            .http
+            .client_builder(cfg.timeout_ms)
+            .map_err(|e| RunpodError::InvalidEnv {
+                key: e.key,
+                value: e.value,
+                reason: e.reason,
+            })?
             .user_agent(cfg.user_agent.clone())
             .build()
             .map_err(RunpodError::Http)?;
 
-        Ok(Self { cfg, http })
+        Ok(Self::new_with_http(cfg, http))
+    }
+
+    /// Create a starter sending its requests through `http` (e.g. a client
+    /// shared with other components, see `HttpOptions::shared_client()`).
+    ///
+    /// `cfg.http` options are those of `http`; `cfg.timeout_ms` and
+    /// `cfg.user_agent` still apply per request.
+    #[must_use]
+    pub fn new_with_http(cfg: RunpodStarterConfig, http: reqwest::Client) -> Self {
+        Self {
+            cfg,
+            http,
+            metrics: runpod_metrics::noop(),
+        }
     }
 
     /// Start or resume the configured pod.
     ///
+    /// Looks up the pod's `interruptible` flag first: on-demand pods use
+    /// `/start`, spot pods are bid-resumed with `RUNPOD_BID_PER_GPU`.
+    ///
     /// Returns the raw response body on success.
     /// Implements retry logic with exponential backoff for transient failures.
     ///
     /// # Errors
     ///
-    /// Returns an error if the HTTP request fails or the API returns an error.
+    /// Returns an error if the HTTP request fails, the API returns an error,
+    /// or the pod is a spot pod and no bid is configured.
     pub async fn start_or_resume(&self) -> Result<String, RunpodError> {
+        self.start_auto(&self.cfg.pod_id).await
+    }
+
+    /// Start the configured pod with a plain `/start` call (on-demand pods).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API returns an error.
+    pub async fn start(&self) -> Result<String, RunpodError> {
         let url = self.cfg.start_url();
         self.post_with_retry(&url).await
     }
 
+    /// Bid-resume the configured spot pod (GraphQL `podBidResume`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no bid is configured, the request fails,
+    /// or the API returns an error.
+    pub async fn bid_resume(&self) -> Result<String, RunpodError> {
+        self.bid_resume_pod(&self.cfg.pod_id).await
+    }
+
+    /// Check whether a pod is interruptible (spot).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the response cannot be parsed.
+    pub async fn is_interruptible(&self, pod_id: &str) -> Result<bool, RunpodError> {
+        let url = self.cfg.pod_url_for(pod_id);
+        let body = self
+            .send_with_retry(reqwest::Method::GET, &url, None, true)
+            .await?;
+        let info: PodSpotInfo = self
+            .cfg
+            .api_version
+            .decode(ApiResource::Pod, &body)
+            .map_err(RunpodError::Json)?;
+        Ok(info.interruptible)
+    }
+
     /// Stop the configured pod.
     ///
     /// Returns the raw response body on success.
@@ -142,40 +299,201 @@ impl RunpodStarter {
         self.post_with_retry(&url).await
     }
 
+    /// Terminate the configured pod (removes it from `RunPod`).
+    ///
+    /// Idempotent: a pod that is already gone (404, or error code `NOT_FOUND`)
+    /// yields `TerminateOutcome::AlreadyGone`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP request fails or the API returns an error.
+    pub async fn terminate(&self) -> Result<TerminateOutcome, RunpodError> {
+        let url = self.cfg.pod_url_for(&self.cfg.pod_id);
+        match self
+            .send_with_retry(reqwest::Method::DELETE, &url, None, true)
+            .await
+        {
+            Ok(_) => Ok(TerminateOutcome::Terminated),
+            Err(RunpodError::Api { status, body }) if is_pod_gone_error(status.as_u16(), &body) => {
+                Ok(TerminateOutcome::AlreadyGone)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Start several pods with bounded concurrency.
+    ///
+    /// At most `max_concurrency` starts are in flight at once; each pod uses the
+    /// same retry policy as `start_or_resume()`. A failing pod does not abort
+    /// the others. Results are returned in input order.
+    pub async fn start_many(&self, pod_ids: &[PodId]) -> Vec<StartResult> {
+        let limit = usize::try_from(self.cfg.max_concurrency.max(1)).unwrap_or(1);
+
+        stream::iter(pod_ids)
+            .map(|pod_id| async move {
+                StartResult {
+                    pod_id: pod_id.clone(),
+                    result: self.start_auto(pod_id.as_str()).await,
+                }
+            })
+            .buffered(limit)
+            .collect()
+            .await
+    }
+
     /// Get a reference to the current configuration.
     #[must_use]
     pub const fn config(&self) -> &RunpodStarterConfig {
         &self.cfg
     }
 
-    /// Internal method to POST with retry logic.
+    /// Report HTTP request and retry metrics to `sink`.
+    #[must_use]
+    pub fn with_metrics(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.metrics = sink;
+        self
+    }
+
+    /// Start a pod through the path matching its deploy mode.
+    async fn start_auto(&self, pod_id: &str) -> Result<String, RunpodError> {
+        if self.is_interruptible(pod_id).await? {
+            self.bid_resume_pod(pod_id).await
+        } else {
+            let url = self.cfg.start_url_for(pod_id);
+            self.post_with_retry(&url).await
+        }
+    }
+
+    /// Bid-resume a spot pod via GraphQL.
+    async fn bid_resume_pod(&self, pod_id: &str) -> Result<String, RunpodError> {
+        let bid = self
+            .cfg
+            .bid_per_gpu
+            .ok_or(RunpodError::MissingEnv("RUNPOD_BID_PER_GPU"))?;
+
+        let payload = serde_json::json!({
+            "query": r"
+                mutation podBidResume($input: PodBidResumeInput!) {
+                    podBidResume(input: $input) {
+                        id
+                        desiredStatus
+                    }
+                }
+            ",
+            "variables": {
+                "input": {
+                    "podId": pod_id,
+                    "bidPerGpu": bid,
+                    "gpuCount": self.cfg.gpu_count
+                }
+            }
+        });
+
+        let body = self
+            .send_with_retry(
+                reqwest::Method::POST,
+                &self.cfg.graphql_url,
+                Some(&payload),
+                false,
+            )
+            .await?;
+
+        let resp: GraphQLErrors = serde_json::from_str(&body).map_err(RunpodError::Json)?;
+        if let Some(errors) = resp.errors
+            && !errors.is_empty()
+        {
+            let msg = errors
+                .iter()
+                .map(|e| e.message.as_str())
+                .collect::<Vec<_>>()
+                .join("; ");
+            return Err(RunpodError::GraphQL(msg));
+        }
+
+        Ok(body)
+    }
+
+    /// Describe the request `start()` would send, without sending it.
+    #[must_use]
+    pub fn preview_start(&self) -> DryRunRequest {
+        DryRunRequest::new(&reqwest::Method::POST, &self.cfg.start_url(), None)
+    }
+
+    /// Describe the request `stop()` would send, without sending it.
+    #[must_use]
+    pub fn preview_stop(&self) -> DryRunRequest {
+        DryRunRequest::new(&reqwest::Method::POST, &self.cfg.stop_url(), None)
+    }
+
+    /// Describe the request `terminate()` would send, without sending it.
+    #[must_use]
+    pub fn preview_terminate(&self) -> DryRunRequest {
+        DryRunRequest::new(
+            &reqwest::Method::DELETE,
+            &self.cfg.pod_url_for(&self.cfg.pod_id),
+            None,
+        )
+    }
+
+    /// Internal method to POST a start/stop call with retry logic.
+    ///
+    /// Both move the pod to a fixed state, so repeating them is safe.
     async fn post_with_retry(&self, url: &str) -> Result<String, RunpodError> {
+        self.send_with_retry(reqwest::Method::POST, url, None, true)
+            .await
+    }
+
+    /// Internal method to send a request with retry logic.
+    ///
+    /// A non-`idempotent` request is only retried when the server did not act on it.
+    async fn send_with_retry(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        json: Option<&serde_json::Value>,
+        idempotent: bool,
+    ) -> Result<String, RunpodError> {
+        // Reads are harmless; only mutating calls are suppressed in dry-run mode.
+        if self.cfg.dry_run && method != reqwest::Method::GET {
+            return Err(RunpodError::DryRun(Box::new(DryRunRequest::new(
+                &method, url, json,
+            ))));
+        }
+
         let mut attempt: u32 = 0;
-        let mut backoff = Duration::from_millis(self.cfg.retry_backoff_ms);
 
         loop {
             attempt = attempt.saturating_add(1);
 
-            let send_res = self
-                .http
-                .post(url)
-                .bearer_auth(&self.cfg.api_key)
-                .send()
-                .await;
+            let req = self.http.request(method.clone(), url);
+            let mut req = runpod_http::with_timeout(req, self.cfg.timeout_ms)
+                .header(reqwest::header::USER_AGENT, &self.cfg.user_agent)
+                .bearer_auth(self.cfg.api_key.expose());
+            if let Some(body) = json {
+                req = req.json(body);
+            }
+            let sent_at = std::time::Instant::now();
+            let send_res = req.send().await;
+            self.record_request(
+                sent_at,
+                send_res.as_ref().ok().map(reqwest::Response::status),
+            );
 
             match send_res {
                 Ok(resp) => {
                     let status = resp.status();
-                    let body = resp.text().await.unwrap_or_default();
-
                     if status.is_success() {
-                        return Ok(body);
+                        return resp.text().await.map_err(RunpodError::Http);
                     }
+                    let body = resp.text().await.unwrap_or_default();
 
                     // Retry on typical transient statuses.
-                    if attempt <= self.cfg.retry_max && is_retryable_status(status) {
-                        tokio::time::sleep(backoff).await;
-                        backoff = next_backoff(backoff);
+                    if self.cfg.retry.retries_status(attempt, status, idempotent) {
+                        log_debug!(
+                            "starter request returned {status}, retrying (attempt {attempt})"
+                        );
+                        runpod_http::record_retry(self.metrics.as_ref(), "starter");
+                        tokio::time::sleep(self.cfg.retry.delay(attempt)).await;
                         continue;
                     }
 
@@ -183,9 +501,13 @@ impl RunpodStarter {
                 }
                 Err(e) => {
                     // Retry on connection/timeout errors (transient).
-                    if attempt <= self.cfg.retry_max && is_retryable_reqwest(&e) {
-                        tokio::time::sleep(backoff).await;
-                        backoff = next_backoff(backoff);
+                    if self.cfg.retry.retries_error(attempt, &e, idempotent) {
+                        log_debug!(
+                            "starter request failed: {e}, retrying (attempt {attempt})",
+                            e = ErrorChain(&e)
+                        );
+                        runpod_http::record_retry(self.metrics.as_ref(), "starter");
+                        tokio::time::sleep(self.cfg.retry.delay(attempt)).await;
                         continue;
                     }
 
@@ -194,6 +516,45 @@ impl RunpodStarter {
             }
         }
     }
+
+    fn record_request(&self, sent_at: std::time::Instant, status: Option<reqwest::StatusCode>) {
+        let status = status.map_or_else(|| "error".to_string(), |s| s.as_u16().to_string());
+        self.metrics.counter(
+            runpod_metrics::HTTP_REQUESTS,
+            1,
+            &[("component", "starter"), ("status", &status)],
+        );
+        self.metrics.histogram(
+            runpod_metrics::HTTP_REQUEST_MS,
+            runpod_metrics::elapsed_ms(sent_at),
+            &[("component", "starter")],
+        );
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PodSpotInfo {
+    #[serde(default)]
+    interruptible: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLErrors {
+    errors: Option<Vec<GraphQLError>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphQLError {
+    message: String,
+}
+
+/// Per-pod result of `RunpodStarter::start_many()`.
+#[derive(Debug)]
+pub struct StartResult {
+    /// Pod that was started.
+    pub pod_id: PodId,
+    /// Raw response body on success, or the error for this pod.
+    pub result: Result<String, RunpodError>,
 }
 
 /// Error type for `RunPod` starter operations.
@@ -212,6 +573,10 @@ pub enum RunpodError {
     },
     /// HTTP client error.
     Http(reqwest::Error),
+    /// JSON parsing error.
+    Json(serde_json::Error),
+    /// GraphQL error from server.
+    GraphQL(String),
     /// API error response.
     Api {
         /// HTTP status code.
@@ -219,6 +584,10 @@ pub enum RunpodError {
         /// Response body.
         body: String,
     },
+    /// Dry run: the request was described but not sent.
+    DryRun(Box<DryRunRequest>),
+    /// The env file (`RUNPOD_ENV_FILE` / `RUNPOD_POD_ENV_FILE`) could not be loaded.
+    EnvFile(EnvFileError),
 }
 
 impl fmt::Display for RunpodError {
@@ -228,15 +597,28 @@ impl fmt::Display for RunpodError {
             Self::InvalidEnv { key, value, reason } => {
                 write!(f, "invalid env var {key}={value:?}: {reason}")
             }
-            Self::Http(e) => write!(f, "http error: {e}"),
+            Self::Http(e) => crate::runpod_http::fmt_http_error(f, e),
+            Self::Json(_) => write!(f, "invalid json response"),
+            Self::GraphQL(e) => write!(f, "graphql error: {e}"),
             Self::Api { status, body } => {
                 write!(f, "runpod api error: status={status}, body={body}")
             }
+            Self::DryRun(req) => write!(f, "dry run, request not sent: {} {}", req.method, req.url),
+            Self::EnvFile(e) => write!(f, "{e}"),
         }
     }
 }
 
-impl std::error::Error for RunpodError {}
+impl std::error::Error for RunpodError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Http(e) => Some(e),
+            Self::Json(e) => Some(e),
+            Self::EnvFile(e) => std::error::Error::source(e),
+            _ => None,
+        }
+    }
+}
 
 #[inline]
 fn must_env(key: &'static str) -> Result<String, RunpodError> {
@@ -245,48 +627,44 @@ fn must_env(key: &'static str) -> Result<String, RunpodError> {
 
 #[inline]
 fn parse_u64_env(key: &'static str, default: u64) -> Result<u64, RunpodError> {
-    env::var(key).map_or_else(
-        |_| Ok(default),
-        |v| {
-            v.parse::<u64>().map_err(|_| RunpodError::InvalidEnv {
-                key,
-                value: v,
-                reason: "expected an unsigned integer",
-            })
-        },
-    )
+    runpod_env::u64_var(key, default).map_err(|reason| RunpodError::InvalidEnv {
+        key,
+        value: env::var(key).unwrap_or_default(),
+        reason,
+    })
 }
 
 #[inline]
 fn parse_u32_env(key: &'static str, default: u32) -> Result<u32, RunpodError> {
-    env::var(key).map_or_else(
-        |_| Ok(default),
-        |v| {
-            v.parse::<u32>().map_err(|_| RunpodError::InvalidEnv {
-                key,
-                value: v,
-                reason: "expected an unsigned integer",
-            })
-        },
-    )
+    runpod_env::u32_var(key, default).map_err(|reason| RunpodError::InvalidEnv {
+        key,
+        value: env::var(key).unwrap_or_default(),
+        reason,
+    })
 }
 
 #[inline]
-const fn is_retryable_status(status: reqwest::StatusCode) -> bool {
-    matches!(
-        status.as_u16(),
-        408 | 409 | 425 | 429 | 500 | 502 | 503 | 504
-    )
+fn parse_bool_env(key: &'static str, default: bool) -> Result<bool, RunpodError> {
+    runpod_env::bool_var(key, default).map_err(|reason| RunpodError::InvalidEnv {
+        key,
+        value: env::var(key).unwrap_or_default(),
+        reason,
+    })
 }
 
 #[inline]
-fn is_retryable_reqwest(e: &reqwest::Error) -> bool {
-    e.is_timeout() || e.is_connect() || e.is_request()
-}
-
-#[inline]
-fn next_backoff(current: Duration) -> Duration {
-    // Exponential backoff capped at 5 seconds.
-    let next = current.saturating_mul(2);
-    next.min(Duration::from_secs(5))
+fn parse_f64_opt_env(key: &'static str) -> Result<Option<f64>, RunpodError> {
+    match env::var(key) {
+        Ok(v) if !v.trim().is_empty() => {
+            v.trim()
+                .parse::<f64>()
+                .map(Some)
+                .map_err(|_| RunpodError::InvalidEnv {
+                    key,
+                    value: v,
+                    reason: "expected a decimal number",
+                })
+        }
+        _ => Ok(None),
+    }
 }
@@ -1,7 +1,7 @@
 //! `RunPod` state management.
 //!
 //! Unique responsibility: centralize and persist the management state of a `RunPod` Pod,
-//! then produce an idempotent action plan (Create/Start/Stop/Terminate/Noop)
+//! then produce an idempotent action plan (Create/Start/Resume/Stop/Terminate/Noop)
 //! based on remote observations (desiredStatus) and local target.
 //!
 //! Non-goals:
@@ -22,14 +22,25 @@
 //! 3) state.reconcile(observation, now_ms) => `PlannedAction`
 //! 4) Execute action in runpod_* (starter/provisioner)
 //! 5) state.apply_result(...) then save
+//!
+//! `StateTransaction` wraps steps 1-5 with rollback on execution failure.
 
 #![forbid(unsafe_code)]
 
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::fs;
+use std::future::Future;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::runpod_lock::{self, DistributedLock, FileLock, LockError};
+use crate::runpod_network::PrivateEndpoint;
+#[cfg(any(feature = "sqlite", feature = "redis", feature = "s3"))]
+use crate::runpod_store;
 
 /// State file format version.
 const STATE_FORMAT_VERSION: u32 = 1;
@@ -54,6 +65,40 @@ impl PodDesiredStatus {
     }
 }
 
+/// Outcome of an idempotent terminate call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerminateOutcome {
+    /// The pod existed and this call terminated it.
+    Terminated,
+    /// The pod was already gone (404, or error code `NOT_FOUND`).
+    AlreadyGone,
+}
+
+impl TerminateOutcome {
+    /// Check whether this call actually terminated the pod.
+    #[must_use]
+    pub const fn was_terminated(self) -> bool {
+        matches!(self, Self::Terminated)
+    }
+}
+
+/// Error code of the `RunPod` API for a resource that does not exist.
+pub(crate) const NOT_FOUND_CODE: &str = "NOT_FOUND";
+
+/// Check whether a failed REST call says the pod no longer exists: a 404,
+/// or an error body whose `code` is exactly `NOT_FOUND`. Messages are not
+/// matched: their wording is not part of the API.
+pub(crate) fn is_pod_gone_error(status: u16, body: &str) -> bool {
+    #[derive(Deserialize)]
+    struct ErrorBody {
+        code: Option<String>,
+    }
+    status == 404
+        || serde_json::from_str::<ErrorBody>(body)
+            .is_ok_and(|e| e.code.as_deref() == Some(NOT_FOUND_CODE))
+}
+
 /// Local target: what your orchestrator wants to achieve on `RunPod`.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -121,8 +166,23 @@ pub enum RemoteObservation {
     Unknown,
 }
 
+/// How the pod was deployed (determines how an EXITED pod is brought back).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeployMode {
+    /// On-demand pod, started with a plain start call.
+    #[default]
+    OnDemand,
+    /// Spot (interruptible) pod, resumed with a bid.
+    Spot {
+        /// Bid per GPU in USD/hour.
+        bid_per_gpu: f64,
+    },
+}
+
 /// Planned actions to take on a pod.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum PlannedAction {
     /// No operation needed.
     Noop,
@@ -136,6 +196,13 @@ pub enum PlannedAction {
         /// Pod ID.
         id: PodId,
     },
+    /// Resume a spot Pod with a bid.
+    ResumePod {
+        /// Pod ID.
+        id: PodId,
+        /// Bid per GPU in USD/hour.
+        bid_per_gpu: f64,
+    },
     /// Stop a Pod.
     StopPod {
         /// Pod ID.
@@ -148,6 +215,65 @@ pub enum PlannedAction {
     },
 }
 
+/// Why `reconcile()` chose an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PlanReason {
+    /// Remote status already matches the target.
+    AlreadyConverged,
+    /// Target needs a pod but none exists remotely (not found or terminated).
+    PodMissing,
+    /// A remote pod was observed but its `PodId` is unknown locally.
+    PodIdUnknown,
+    /// Pod is EXITED and the policy allows reusing it.
+    ResumeExited,
+    /// Pod is EXITED but the policy forbids reusing it.
+    ReuseDisabled,
+    /// Target is Exited while the pod is running.
+    TargetExited,
+    /// Target is Terminated while the pod still exists.
+    TargetTerminated,
+    /// `auto_terminate_after_exited_ms` elapsed while the pod was EXITED.
+    AutoTerminatePolicy,
+    /// The business-hours window is open.
+    WithinSchedule,
+    /// The business-hours window is closed.
+    OutsideSchedule,
+    /// Accumulated spend reached `max_total_spend_usd`.
+    BudgetExceeded,
+    /// The pod was chosen as a victim by `FleetState::scale_down()`.
+    ScaledDown,
+}
+
+impl fmt::Display for PlanReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            Self::AlreadyConverged => "remote status matches target",
+            Self::PodMissing => "pod does not exist remotely",
+            Self::PodIdUnknown => "pod id unknown locally",
+            Self::ResumeExited => "exited pod can be reused",
+            Self::ReuseDisabled => "policy forbids reusing exited pods",
+            Self::TargetExited => "target is exited",
+            Self::TargetTerminated => "target is terminated",
+            Self::AutoTerminatePolicy => "exited longer than auto-terminate policy",
+            Self::WithinSchedule => "inside business hours",
+            Self::OutsideSchedule => "outside business hours",
+            Self::BudgetExceeded => "spend ceiling reached",
+            Self::ScaledDown => "removed by scale-down",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// A planned action together with its rationale.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Plan {
+    /// Action to execute.
+    pub action: PlannedAction,
+    /// Why this action was chosen.
+    pub reason: PlanReason,
+}
+
 /// Local policy for state management.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatePolicy {
@@ -156,6 +282,18 @@ pub struct StatePolicy {
     /// If set: if Pod remains EXITED beyond this duration, plan `TerminatePod`.
     /// Useful to limit storage costs if you forget to clean up.
     pub auto_terminate_after_exited_ms: Option<u64>,
+    /// If set: the pod should run only inside this weekly window.
+    /// Outside it the target becomes Exited, inside it Running
+    /// (a Terminated target is never overridden).
+    #[serde(default)]
+    pub schedule: Option<BusinessHours>,
+    /// Estimated hourly cost of the pod in USD, used for spend accounting.
+    #[serde(default)]
+    pub hourly_cost_usd: Option<f64>,
+    /// If set: once accumulated spend reaches this ceiling (USD),
+    /// the target is forced to Terminated.
+    #[serde(default)]
+    pub max_total_spend_usd: Option<f64>,
 }
 
 impl Default for StatePolicy {
@@ -163,6 +301,61 @@ impl Default for StatePolicy {
         Self {
             reuse_exited_pod: true,
             auto_terminate_after_exited_ms: None,
+            schedule: None,
+            hourly_cost_usd: None,
+            max_total_spend_usd: None,
+        }
+    }
+}
+
+/// Weekly working-hours window.
+///
+/// The timezone is a fixed UTC offset: daylight-saving changes must be
+/// reflected by updating `utc_offset_minutes`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BusinessHours {
+    /// Active days (0 = Monday ... 6 = Sunday).
+    pub days: Vec<u8>,
+    /// Window start, in minutes since local midnight.
+    pub start_minute: u16,
+    /// Window end (exclusive), in minutes since local midnight.
+    /// If lower than `start_minute`, the window spans midnight.
+    pub end_minute: u16,
+    /// Local timezone as an offset from UTC in minutes (e.g. 60 for UTC+1).
+    pub utc_offset_minutes: i32,
+}
+
+impl BusinessHours {
+    /// Monday to Friday, 09:00 to 19:00 at the given UTC offset.
+    #[must_use]
+    pub fn weekdays(utc_offset_minutes: i32) -> Self {
+        Self {
+            days: vec![0, 1, 2, 3, 4],
+            start_minute: 9 * 60,
+            end_minute: 19 * 60,
+            utc_offset_minutes,
+        }
+    }
+
+    /// Check whether `now_ms` (ms since UNIX epoch) falls inside the window.
+    #[must_use]
+    pub fn contains(&self, now_ms: u64) -> bool {
+        const DAY_MS: i64 = 86_400_000;
+
+        let local_ms = i64::try_from(now_ms)
+            .unwrap_or(i64::MAX)
+            .saturating_add(i64::from(self.utc_offset_minutes).saturating_mul(60_000));
+        let days = local_ms.div_euclid(DAY_MS);
+        // 1970-01-01 was a Thursday (index 3 with Monday = 0).
+        let weekday = u8::try_from(days.saturating_add(3).rem_euclid(7)).unwrap_or(0);
+        let previous_day = (weekday + 6) % 7;
+        let minute = u16::try_from(local_ms.rem_euclid(DAY_MS) / 60_000).unwrap_or(0);
+
+        if self.start_minute <= self.end_minute {
+            self.days.contains(&weekday) && minute >= self.start_minute && minute < self.end_minute
+        } else {
+            (self.days.contains(&weekday) && minute >= self.start_minute)
+                || (self.days.contains(&previous_day) && minute < self.end_minute)
         }
     }
 }
@@ -184,6 +377,56 @@ pub struct RunPodState {
     pub last_updated_ms: u64,
     /// Local policy.
     pub policy: StatePolicy,
+    /// Monotonic revision, bumped by every successful save.
+    ///
+    /// Stores use it for compare-and-swap: a save is rejected unless this
+    /// matches the revision currently persisted.
+    #[serde(default)]
+    pub revision: u64,
+    /// Timestamp (ms) of the last `reconcile()` call.
+    #[serde(default)]
+    pub last_reconciled_ms: Option<u64>,
+    /// Last non-Noop action planned by `reconcile()`.
+    #[serde(default)]
+    pub last_action: Option<PlannedAction>,
+    /// Timestamp (ms) at which `last_action` was planned.
+    #[serde(default)]
+    pub last_action_ms: Option<u64>,
+    /// Rationale of `last_action`.
+    #[serde(default)]
+    pub last_reason: Option<PlanReason>,
+    /// How the current pod was deployed.
+    #[serde(default)]
+    pub deploy_mode: DeployMode,
+    /// Estimated spend (USD) accumulated while the pod was observed RUNNING.
+    #[serde(default)]
+    pub accumulated_spend_usd: f64,
+    /// Recent non-Noop decisions, oldest first (bounded to `HISTORY_LIMIT`).
+    #[serde(default)]
+    pub history: Vec<HistoryEntry>,
+    /// Holder of the current pool checkout (see `PodPool::checkout()`).
+    #[serde(default)]
+    pub checked_out_by: Option<String>,
+    /// Timestamp (ms) of the current pool checkout.
+    #[serde(default)]
+    pub checked_out_ms: Option<u64>,
+    /// Timestamp (ms) of the last pool checkin.
+    #[serde(default)]
+    pub last_used_ms: Option<u64>,
+}
+
+/// Maximum number of entries kept in `RunPodState::history`.
+pub const HISTORY_LIMIT: usize = 100;
+
+/// One recorded planner decision.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Timestamp (ms) of the decision.
+    pub at_ms: u64,
+    /// Planned action.
+    pub action: PlannedAction,
+    /// Why it was planned.
+    pub reason: PlanReason,
 }
 
 impl RunPodState {
@@ -198,6 +441,17 @@ impl RunPodState {
             last_remote: None,
             last_updated_ms: now_ms,
             policy: StatePolicy::default(),
+            revision: 0,
+            last_reconciled_ms: None,
+            last_action: None,
+            last_action_ms: None,
+            last_reason: None,
+            deploy_mode: DeployMode::default(),
+            accumulated_spend_usd: 0.0,
+            history: Vec::new(),
+            checked_out_by: None,
+            checked_out_ms: None,
+            last_used_ms: None,
         }
     }
 
@@ -207,6 +461,12 @@ impl RunPodState {
         self.last_updated_ms = now_ms;
     }
 
+    /// Record how the current pod is deployed (on-demand or spot).
+    pub const fn set_deploy_mode(&mut self, mode: DeployMode, now_ms: u64) {
+        self.deploy_mode = mode;
+        self.last_updated_ms = now_ms;
+    }
+
     /// Get the current `PodId` (if known).
     #[must_use]
     pub const fn pod_id(&self) -> Option<&PodId> {
@@ -223,17 +483,25 @@ impl RunPodState {
     /// - `NotFound` is treated as absence: if you want Running/Exited, you must recreate.
     /// - The decision of *how* to create is delegated to the provisioner.
     pub fn reconcile(&mut self, observation: RemoteObservation, now_ms: u64) -> PlannedAction {
+        self.reconcile_explained(observation, now_ms).action
+    }
+
+    /// Same as `reconcile()`, but also returns why the action was chosen.
+    pub fn reconcile_explained(&mut self, observation: RemoteObservation, now_ms: u64) -> Plan {
         self.last_updated_ms = now_ms;
+        self.last_reconciled_ms = Some(now_ms);
 
-        // 1) Assimilate remote observation
+        // 1) Assimilate remote observation (accruing spend for the elapsed running time)
         let remote_status_opt: Option<PodDesiredStatus> = match observation {
             RemoteObservation::Found(snapshot) => {
+                self.accrue_spend(snapshot.observed_at_ms);
                 self.pod_id = Some(snapshot.id.clone());
                 self.last_remote = Some(snapshot.clone());
                 Some(snapshot.desired_status)
             }
             RemoteObservation::NotFound => {
                 // Pod likely deleted/terminated on RunPod side.
+                self.accrue_spend(now_ms);
                 self.last_remote = None;
                 None
             }
@@ -244,7 +512,31 @@ impl RunPodState {
             }
         };
 
-        // 2) Apply policy (e.g., auto-terminate if EXITED too long)
+        // 2) Apply policy (business hours, then auto-terminate if EXITED too long)
+        let schedule_open = self
+            .policy
+            .schedule
+            .as_ref()
+            .filter(|_| self.target != TargetStatus::Terminated)
+            .map(|schedule| schedule.contains(now_ms));
+        if let Some(open) = schedule_open {
+            self.target = if open {
+                TargetStatus::Running
+            } else {
+                TargetStatus::Exited
+            };
+        }
+
+        let budget_forced = self
+            .policy
+            .max_total_spend_usd
+            .is_some_and(|ceiling| self.accumulated_spend_usd >= ceiling);
+        if budget_forced {
+            // Budget overrides everything else, including the schedule.
+            self.target = TargetStatus::Terminated;
+        }
+
+        let mut policy_forced = false;
         if let (Some(policy_ms), Some(remote)) =
             (self.policy.auto_terminate_after_exited_ms, self.last_remote.as_ref())
             && remote.desired_status == PodDesiredStatus::Exited
@@ -253,46 +545,156 @@ impl RunPodState {
             if elapsed >= policy_ms {
                 // Policy overrides target: force Terminated to cut costs.
                 self.target = TargetStatus::Terminated;
+                policy_forced = true;
             }
         }
 
         // 3) Decide action
+        let (action, mut reason) = self.decide(remote_status_opt);
+        if budget_forced && matches!(action, PlannedAction::TerminatePod { .. }) {
+            reason = PlanReason::BudgetExceeded;
+        } else if policy_forced && matches!(action, PlannedAction::TerminatePod { .. }) {
+            reason = PlanReason::AutoTerminatePolicy;
+        } else if let Some(open) = schedule_open {
+            match action {
+                PlannedAction::StopPod { .. } if !open => reason = PlanReason::OutsideSchedule,
+                PlannedAction::StartPod { .. } | PlannedAction::ResumePod { .. } if open => {
+                    reason = PlanReason::WithinSchedule;
+                }
+                _ => {}
+            }
+        }
+        if action != PlannedAction::Noop {
+            self.last_action = Some(action.clone());
+            self.last_action_ms = Some(now_ms);
+            self.last_reason = Some(reason);
+            self.record_history(HistoryEntry {
+                at_ms: now_ms,
+                action: action.clone(),
+                reason,
+            });
+        }
+        Plan { action, reason }
+    }
+
+    /// Add the cost of the running interval since the last observation.
+    fn accrue_spend(&mut self, until_ms: u64) {
+        let (Some(rate), Some(prev)) = (self.policy.hourly_cost_usd, self.last_remote.as_ref())
+        else {
+            return;
+        };
+        if prev.desired_status != PodDesiredStatus::Running {
+            return;
+        }
+        let elapsed =
+            std::time::Duration::from_millis(until_ms.saturating_sub(prev.observed_at_ms));
+        self.accumulated_spend_usd += rate * elapsed.as_secs_f64() / 3600.0;
+    }
+
+    fn record_history(&mut self, entry: HistoryEntry) {
+        self.history.push(entry);
+        if self.history.len() > HISTORY_LIMIT {
+            let excess = self.history.len() - HISTORY_LIMIT;
+            self.history.drain(..excess);
+        }
+    }
+
+    /// Pure decision table: target × remote status × known `PodId`.
+    fn decide(&self, remote_status_opt: Option<PodDesiredStatus>) -> (PlannedAction, PlanReason) {
         match (self.target, remote_status_opt, self.pod_id.clone()) {
             // --- Cases: Noop ---
             (TargetStatus::Terminated, None | Some(PodDesiredStatus::Terminated), _)
             | (TargetStatus::Running, Some(PodDesiredStatus::Running), _)
-            | (TargetStatus::Exited, Some(PodDesiredStatus::Exited), _) => PlannedAction::Noop,
+            | (TargetStatus::Exited, Some(PodDesiredStatus::Exited), _) => {
+                (PlannedAction::Noop, PlanReason::AlreadyConverged)
+            }
 
             // --- Cases: CreatePod ---
-            (TargetStatus::Running | TargetStatus::Exited, None | Some(PodDesiredStatus::Terminated), _)
-            | (_, Some(_), None) => PlannedAction::CreatePod {
-                name: self.pod_name.clone(),
-            },
+            (
+                TargetStatus::Running | TargetStatus::Exited,
+                None | Some(PodDesiredStatus::Terminated),
+                _,
+            ) => (
+                PlannedAction::CreatePod {
+                    name: self.pod_name.clone(),
+                },
+                PlanReason::PodMissing,
+            ),
+            (_, Some(_), None) => (
+                PlannedAction::CreatePod {
+                    name: self.pod_name.clone(),
+                },
+                PlanReason::PodIdUnknown,
+            ),
 
-            // --- Cases: StartPod or CreatePod ---
+            // --- Cases: StartPod/ResumePod or CreatePod ---
             (TargetStatus::Running, Some(PodDesiredStatus::Exited), Some(id)) => {
                 if self.policy.reuse_exited_pod {
-                    PlannedAction::StartPod { id }
+                    let action = match self.deploy_mode {
+                        DeployMode::OnDemand => PlannedAction::StartPod { id },
+                        DeployMode::Spot { bid_per_gpu } => {
+                            PlannedAction::ResumePod { id, bid_per_gpu }
+                        }
+                    };
+                    (action, PlanReason::ResumeExited)
                 } else {
-                    PlannedAction::CreatePod {
-                        name: self.pod_name.clone(),
-                    }
+                    (
+                        PlannedAction::CreatePod {
+                            name: self.pod_name.clone(),
+                        },
+                        PlanReason::ReuseDisabled,
+                    )
                 }
             }
 
             // --- Cases: StopPod ---
             (TargetStatus::Exited, Some(PodDesiredStatus::Running), Some(id)) => {
-                PlannedAction::StopPod { id }
+                (PlannedAction::StopPod { id }, PlanReason::TargetExited)
             }
 
             // --- Cases: TerminatePod ---
-            (TargetStatus::Terminated,
-             Some(PodDesiredStatus::Running | PodDesiredStatus::Exited), Some(id)) => {
-                PlannedAction::TerminatePod { id }
-            }
+            (
+                TargetStatus::Terminated,
+                Some(PodDesiredStatus::Running | PodDesiredStatus::Exited),
+                Some(id),
+            ) => (
+                PlannedAction::TerminatePod { id },
+                PlanReason::TargetTerminated,
+            ),
         }
     }
 
+    /// Summarize the state for display (CLIs, dashboards, logs).
+    #[must_use]
+    pub fn report(&self, now_ms: u64) -> StateReport {
+        StateReport {
+            pod_name: self.pod_name.clone(),
+            pod_id: self.pod_id.clone(),
+            target: self.target,
+            last_remote_status: self.last_remote.as_ref().map(|r| r.desired_status),
+            last_observation_age_ms: self
+                .last_remote
+                .as_ref()
+                .map(|r| now_ms.saturating_sub(r.observed_at_ms)),
+            last_reconciled_age_ms: self.last_reconciled_ms.map(|t| now_ms.saturating_sub(t)),
+            last_action: self.last_action.clone(),
+            last_reason: self.last_reason,
+            last_action_age_ms: self.last_action_ms.map(|t| now_ms.saturating_sub(t)),
+            policy: self.policy.clone(),
+            accumulated_spend_usd: self.accumulated_spend_usd,
+            revision: self.revision,
+        }
+    }
+
+    /// Check whether the state has not been reconciled within `max_age_ms`.
+    ///
+    /// A state that was never reconciled is always stale.
+    #[must_use]
+    pub fn is_stale(&self, max_age_ms: u64, now_ms: u64) -> bool {
+        self.last_reconciled_ms
+            .is_none_or(|t| now_ms.saturating_sub(t) > max_age_ms)
+    }
+
     /// Call after a successful creation.
     pub fn apply_created(&mut self, id: PodId, now_ms: u64) {
         self.pod_id = Some(id);
@@ -306,6 +708,546 @@ impl RunPodState {
         self.last_remote = None;
         self.last_updated_ms = now_ms;
     }
+
+    /// Retire the pod after a scale-down: set the target and plan the action.
+    ///
+    /// Unlike `reconcile()`, this does not need a fresh observation: a pod
+    /// with a known ID is stopped or terminated directly (a pod last seen
+    /// EXITED is not stopped again), a pod never created gets a `Noop`.
+    fn retire(&mut self, action: ScaleDownAction, now_ms: u64) -> Plan {
+        self.target = match action {
+            ScaleDownAction::Stop => TargetStatus::Exited,
+            ScaleDownAction::Terminate => TargetStatus::Terminated,
+        };
+        self.last_updated_ms = now_ms;
+
+        let already_exited = self
+            .last_remote
+            .as_ref()
+            .is_some_and(|r| r.desired_status == PodDesiredStatus::Exited);
+        let action = match (action, self.pod_id.clone()) {
+            (_, None) => PlannedAction::Noop,
+            (ScaleDownAction::Stop, Some(_)) if already_exited => PlannedAction::Noop,
+            (ScaleDownAction::Stop, Some(id)) => PlannedAction::StopPod { id },
+            (ScaleDownAction::Terminate, Some(id)) => PlannedAction::TerminatePod { id },
+        };
+        if action == PlannedAction::Noop {
+            return Plan {
+                action,
+                reason: PlanReason::AlreadyConverged,
+            };
+        }
+
+        let reason = PlanReason::ScaledDown;
+        self.last_action = Some(action.clone());
+        self.last_action_ms = Some(now_ms);
+        self.last_reason = Some(reason);
+        self.record_history(HistoryEntry {
+            at_ms: now_ms,
+            action: action.clone(),
+            reason,
+        });
+        Plan { action, reason }
+    }
+
+    /// Mark the pod as checked out by `holder`.
+    ///
+    /// Returns `false` (and changes nothing) if it is already checked out.
+    pub fn check_out(&mut self, holder: &str, now_ms: u64) -> bool {
+        if self.checked_out_by.is_some() {
+            return false;
+        }
+        self.checked_out_by = Some(holder.to_string());
+        self.checked_out_ms = Some(now_ms);
+        self.last_updated_ms = now_ms;
+        true
+    }
+
+    /// Drop a checkout taken `ttl_ms` or more before `now_ms` (its holder
+    /// neither renewed nor returned it). Returns the reclaimed holder.
+    pub fn reclaim_checkout(&mut self, ttl_ms: u64, now_ms: u64) -> Option<String> {
+        let since = self.checked_out_ms.unwrap_or(0);
+        if self.checked_out_by.is_none() || now_ms.saturating_sub(since) < ttl_ms {
+            return None;
+        }
+        self.checked_out_ms = None;
+        self.last_updated_ms = now_ms;
+        self.checked_out_by.take()
+    }
+
+    /// Restart the checkout TTL of `holder` at `now_ms`.
+    ///
+    /// Returns `false` (and changes nothing) if `holder` does not hold it.
+    pub fn renew_checkout(&mut self, holder: &str, now_ms: u64) -> bool {
+        if self.checked_out_by.as_deref() != Some(holder) {
+            return false;
+        }
+        self.checked_out_ms = Some(now_ms);
+        self.last_updated_ms = now_ms;
+        true
+    }
+
+    /// Release the checkout of `holder` and record the pod's last use.
+    ///
+    /// Returns `false` (and changes nothing) if `holder` does not hold it.
+    pub fn check_in(&mut self, holder: &str, now_ms: u64) -> bool {
+        if self.checked_out_by.as_deref() != Some(holder) {
+            return false;
+        }
+        self.checked_out_by = None;
+        self.checked_out_ms = None;
+        self.last_used_ms = Some(now_ms);
+        self.last_updated_ms = now_ms;
+        true
+    }
+
+    /// Usage signals of the pod for scale-down ranking (no GPU utilization).
+    #[must_use]
+    pub const fn usage(&self) -> PodUsage {
+        PodUsage {
+            in_use: self.checked_out_by.is_some(),
+            gpu_util_percent: None,
+            last_used_ms: self.last_used_ms,
+        }
+    }
+
+    /// Whether the pod was last observed RUNNING.
+    fn is_running(&self) -> bool {
+        self.last_remote
+            .as_ref()
+            .is_some_and(|r| r.desired_status == PodDesiredStatus::Running)
+    }
+}
+
+/// What happens to the pods removed from a group by `FleetState::scale_down()`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleDownAction {
+    /// Stop them: GPU billing ends, the disk is kept, and `scale_up()`
+    /// restarts them before creating new pods.
+    Stop,
+    /// Terminate them: nothing is billed anymore, but the disk is lost.
+    #[default]
+    Terminate,
+}
+
+/// Usage signals of one pod, used to rank scale-down victims.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PodUsage {
+    /// The pod is currently leased to a worker.
+    pub in_use: bool,
+    /// Current GPU utilization (0-100), if known.
+    pub gpu_util_percent: Option<f32>,
+    /// Timestamp (ms) of the last time the pod was used, if known.
+    pub last_used_ms: Option<u64>,
+}
+
+/// Group of pods sharing one target (e.g. "trainers", "dev-boxes").
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PodGroup {
+    /// Target given to the group's new pods, and to its pods without a
+    /// target of their own when set with `FleetState::set_group_target()`.
+    pub target: TargetStatus,
+    /// Names of the pods with a target of their own
+    /// (`FleetState::set_pod_target()`).
+    #[serde(default)]
+    pub overrides: BTreeSet<String>,
+    /// Per-pod state.
+    pub pods: Vec<RunPodState>,
+    /// How `FleetState::scale_down()` disposes of the pods it removes.
+    #[serde(default)]
+    pub scale_down: ScaleDownAction,
+    /// Pods removed by `scale_down()`, each keeping its own target (Exited
+    /// or Terminated). Terminated pods are dropped once their ID is
+    /// forgotten; stopped pods wait here for `scale_up()`.
+    #[serde(default)]
+    pub standby: Vec<RunPodState>,
+}
+
+impl PodGroup {
+    fn new(target: TargetStatus) -> Self {
+        Self {
+            target,
+            overrides: BTreeSet::new(),
+            pods: Vec::new(),
+            scale_down: ScaleDownAction::default(),
+            standby: Vec::new(),
+        }
+    }
+
+    /// Find a pod by name, active or on standby.
+    #[must_use]
+    pub fn pod(&self, pod_name: &str) -> Option<&RunPodState> {
+        self.pods
+            .iter()
+            .chain(&self.standby)
+            .find(|p| p.pod_name == pod_name)
+    }
+
+    /// Find a pod by name, active or on standby (mutable).
+    pub fn pod_mut(&mut self, pod_name: &str) -> Option<&mut RunPodState> {
+        self.pods
+            .iter_mut()
+            .chain(&mut self.standby)
+            .find(|p| p.pod_name == pod_name)
+    }
+}
+
+/// Multi-pod state: named groups, each with an independent target.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetState {
+    /// Format version for state serialization.
+    pub format_version: u32,
+    /// Groups by name.
+    pub groups: BTreeMap<String, PodGroup>,
+}
+
+/// Plan for one pod of a fleet group.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FleetPlan {
+    /// Group name.
+    pub group: String,
+    /// Logical pod name.
+    pub pod_name: String,
+    /// Planned action and rationale.
+    pub plan: Plan,
+}
+
+impl Default for FleetState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FleetState {
+    /// Create an empty fleet.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            format_version: STATE_FORMAT_VERSION,
+            groups: BTreeMap::new(),
+        }
+    }
+
+    /// Add a pod to a group, creating the group (target Running) if needed.
+    ///
+    /// Does nothing if a pod with the same name already exists in the group.
+    pub fn add_pod(&mut self, group: &str, pod_name: &str, now_ms: u64) {
+        let entry = self
+            .groups
+            .entry(group.to_string())
+            .or_insert_with(|| PodGroup::new(TargetStatus::default()));
+        if entry.pod(pod_name).is_none() {
+            let mut pod = RunPodState::new(pod_name, now_ms);
+            pod.target = entry.target;
+            entry.pods.push(pod);
+        }
+    }
+
+    /// Set the target of a group, and of its pods without a target of their
+    /// own. Returns `false` if the group does not exist.
+    pub fn set_group_target(&mut self, group: &str, target: TargetStatus, now_ms: u64) -> bool {
+        let Some(entry) = self.groups.get_mut(group) else {
+            return false;
+        };
+        entry.target = target;
+        for pod in &mut entry.pods {
+            if !entry.overrides.contains(&pod.pod_name) {
+                pod.set_target(target, now_ms);
+            }
+        }
+        true
+    }
+
+    /// Give one pod of a group a target of its own, kept when the group
+    /// target changes. Returns `false` if the group or pod does not exist.
+    pub fn set_pod_target(
+        &mut self,
+        group: &str,
+        pod_name: &str,
+        target: TargetStatus,
+        now_ms: u64,
+    ) -> bool {
+        let Some(entry) = self.groups.get_mut(group) else {
+            return false;
+        };
+        let Some(pod) = entry.pods.iter_mut().find(|p| p.pod_name == pod_name) else {
+            return false;
+        };
+        pod.set_target(target, now_ms);
+        entry.overrides.insert(pod_name.to_string());
+        true
+    }
+
+    /// Make a pod follow its group target again. Returns `false` if the
+    /// group or pod does not exist.
+    pub fn clear_pod_target(&mut self, group: &str, pod_name: &str, now_ms: u64) -> bool {
+        let Some(entry) = self.groups.get_mut(group) else {
+            return false;
+        };
+        let Some(pod) = entry.pods.iter_mut().find(|p| p.pod_name == pod_name) else {
+            return false;
+        };
+        pod.set_target(entry.target, now_ms);
+        entry.overrides.remove(pod_name);
+        true
+    }
+
+    /// Set how a group scales down. Returns `false` if the group does not exist.
+    pub fn set_group_scale_down(&mut self, group: &str, action: ScaleDownAction) -> bool {
+        let Some(entry) = self.groups.get_mut(group) else {
+            return false;
+        };
+        entry.scale_down = action;
+        true
+    }
+
+    /// Get a group by name.
+    #[must_use]
+    pub fn group(&self, name: &str) -> Option<&PodGroup> {
+        self.groups.get(name)
+    }
+
+    /// Shrink a group to `size` active pods and plan the victims' retirement.
+    ///
+    /// Victims are chosen in this order: pods never created, pods not
+    /// running, pods not in use, lowest GPU utilization, then least recently
+    /// used (unknown utilization counts as idle, unknown last use as oldest).
+    /// `usage` is keyed by logical pod name; missing entries mean "no
+    /// signal". Victims move to `standby` and are stopped or terminated per
+    /// the group's `scale_down` action.
+    ///
+    /// Returns one plan per victim (`Noop` when there is nothing to do
+    /// remotely), ready for `apply_fleet()`.
+    pub fn scale_down(
+        &mut self,
+        group: &str,
+        size: usize,
+        usage: &HashMap<String, PodUsage>,
+        now_ms: u64,
+    ) -> Vec<FleetPlan> {
+        let Some(entry) = self.groups.get_mut(group) else {
+            return Vec::new();
+        };
+        if entry.pods.len() <= size {
+            return Vec::new();
+        }
+
+        let no_usage = PodUsage::default();
+        let mut ranked: Vec<(usize, &RunPodState)> = entry.pods.iter().enumerate().collect();
+        ranked.sort_by(|(_, a), (_, b)| {
+            let (ua, ub) = (
+                usage.get(&a.pod_name).unwrap_or(&no_usage),
+                usage.get(&b.pod_name).unwrap_or(&no_usage),
+            );
+            a.pod_id
+                .is_some()
+                .cmp(&b.pod_id.is_some())
+                .then(a.is_running().cmp(&b.is_running()))
+                .then(ua.in_use.cmp(&ub.in_use))
+                .then(
+                    ua.gpu_util_percent
+                        .unwrap_or(0.0)
+                        .total_cmp(&ub.gpu_util_percent.unwrap_or(0.0)),
+                )
+                .then(ua.last_used_ms.cmp(&ub.last_used_ms))
+        });
+        let mut victims: Vec<usize> = ranked
+            .iter()
+            .take(entry.pods.len() - size)
+            .map(|(i, _)| *i)
+            .collect();
+        // Remove from the back so the remaining indices stay valid.
+        victims.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut plans = Vec::with_capacity(victims.len());
+        for index in victims {
+            let mut pod = entry.pods.remove(index);
+            entry.overrides.remove(&pod.pod_name);
+            plans.push(FleetPlan {
+                group: group.to_string(),
+                pod_name: pod.pod_name.clone(),
+                plan: pod.retire(entry.scale_down, now_ms),
+            });
+            entry.standby.push(pod);
+        }
+        plans.reverse();
+        plans
+    }
+
+    /// Grow a group to `size` active pods; returns the names of the pods added.
+    ///
+    /// Stopped pods on standby are brought back first (they keep their
+    /// disk); new pods are then named `"{group}-{n}"` with the lowest free
+    /// `n`. Creates the group (target Running) if needed. Run `reconcile()`
+    /// afterwards to plan the starts and creations.
+    pub fn scale_up(&mut self, group: &str, size: usize, now_ms: u64) -> Vec<String> {
+        let entry = self
+            .groups
+            .entry(group.to_string())
+            .or_insert_with(|| PodGroup::new(TargetStatus::default()));
+
+        let mut added = Vec::new();
+        while entry.pods.len() < size {
+            let revived = entry
+                .standby
+                .iter()
+                .position(|p| p.target == TargetStatus::Exited && p.pod_id.is_some());
+            let pod = if let Some(index) = revived {
+                let mut pod = entry.standby.remove(index);
+                pod.set_target(entry.target, now_ms);
+                pod
+            } else {
+                // n ranges over one more value than there are pods: one is free.
+                let name = (0..=entry.pods.len() + entry.standby.len())
+                    .map(|n| format!("{group}-{n}"))
+                    .find(|name| entry.pod(name).is_none())
+                    .unwrap_or_default();
+                let mut pod = RunPodState::new(name, now_ms);
+                pod.target = entry.target;
+                pod
+            };
+            added.push(pod.pod_name.clone());
+            entry.pods.push(pod);
+        }
+        added
+    }
+
+    /// Private endpoints of a group's pods on the global network.
+    ///
+    /// Pods without a known ID (never created, or terminated) are skipped.
+    #[must_use]
+    pub fn private_endpoints(&self, group: &str, container_port: u16) -> Vec<PrivateEndpoint> {
+        self.group(group).map_or_else(Vec::new, |g| {
+            g.pods
+                .iter()
+                .filter_map(|p| p.pod_id.as_ref())
+                .map(|id| PrivateEndpoint::global(id.as_str(), container_port))
+                .collect()
+        })
+    }
+
+    /// Reconcile every pod against its target.
+    ///
+    /// Pods carry their target: the group target reaches them when they are
+    /// added and through `set_group_target()`, so per-pod targets and the
+    /// ones forced by policy (schedule, budget, auto-terminate) are kept.
+    /// `observations` is keyed by logical pod name; pods without an
+    /// observation are reconciled with `RemoteObservation::Unknown`.
+    /// Standby pods (see `scale_down()`) are reconciled the same way.
+    /// Returns one plan per pod, including `Noop` plans.
+    pub fn reconcile(
+        &mut self,
+        observations: &HashMap<String, RemoteObservation>,
+        now_ms: u64,
+    ) -> Vec<FleetPlan> {
+        let mut plans = Vec::new();
+        for (group_name, group) in &mut self.groups {
+            // Standby pods keep their own target and are never recreated:
+            // once gone remotely (or forgotten) they are dropped.
+            for pod in &mut group.standby {
+                if matches!(
+                    observations.get(&pod.pod_name),
+                    Some(RemoteObservation::NotFound)
+                ) {
+                    pod.apply_terminated(now_ms);
+                }
+            }
+            group.standby.retain(|p| p.pod_id.is_some());
+
+            for pod in group.pods.iter_mut().chain(&mut group.standby) {
+                let observation = observations
+                    .get(&pod.pod_name)
+                    .cloned()
+                    .unwrap_or(RemoteObservation::Unknown);
+                plans.push(FleetPlan {
+                    group: group_name.clone(),
+                    pod_name: pod.pod_name.clone(),
+                    plan: pod.reconcile_explained(observation, now_ms),
+                });
+            }
+        }
+        plans
+    }
+}
+
+/// Typed summary of a `RunPodState`, produced by `RunPodState::report()`.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateReport {
+    /// Logical pod name.
+    pub pod_name: String,
+    /// Current `PodId` (if known).
+    pub pod_id: Option<PodId>,
+    /// Desired target state.
+    pub target: TargetStatus,
+    /// Status from the last remote observation.
+    pub last_remote_status: Option<PodDesiredStatus>,
+    /// Age (ms) of the last remote observation.
+    pub last_observation_age_ms: Option<u64>,
+    /// Age (ms) of the last reconciliation.
+    pub last_reconciled_age_ms: Option<u64>,
+    /// Last non-Noop planned action.
+    pub last_action: Option<PlannedAction>,
+    /// Rationale of the last planned action.
+    pub last_reason: Option<PlanReason>,
+    /// Age (ms) of the last planned action.
+    pub last_action_age_ms: Option<u64>,
+    /// Local policy.
+    pub policy: StatePolicy,
+    /// Estimated spend (USD) accumulated so far.
+    pub accumulated_spend_usd: f64,
+    /// Persisted revision.
+    pub revision: u64,
+}
+
+impl StateReport {
+    /// Check whether the last reconciliation is older than `max_age_ms`
+    /// (or never happened).
+    #[must_use]
+    pub fn is_stale(&self, max_age_ms: u64) -> bool {
+        self.last_reconciled_age_ms
+            .is_none_or(|age| age > max_age_ms)
+    }
+}
+
+impl fmt::Display for StateReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pod {}", self.pod_name)?;
+        if let Some(id) = &self.pod_id {
+            write!(f, " ({id})")?;
+        }
+        write!(f, ", target {:?}", self.target)?;
+        if let Some(status) = self.last_remote_status {
+            write!(f, ", remote {status:?}")?;
+        }
+        match self.last_reconciled_age_ms {
+            Some(age) => write!(f, ", last reconciled {} ago", format_age_ms(age)),
+            None => write!(f, ", never reconciled"),
+        }
+    }
+}
+
+/// Format a duration in milliseconds as a coarse human age ("3 days", "5 minutes").
+#[must_use]
+pub fn format_age_ms(age_ms: u64) -> String {
+    const UNITS: [(u64, &str); 4] = [
+        (86_400_000, "day"),
+        (3_600_000, "hour"),
+        (60_000, "minute"),
+        (1_000, "second"),
+    ];
+
+    for (unit_ms, name) in UNITS {
+        let n = age_ms / unit_ms;
+        if n > 0 {
+            return if n == 1 {
+                format!("1 {name}")
+            } else {
+                format!("{n} {name}s")
+            };
+        }
+    }
+    "0 seconds".to_string()
 }
 
 /// Errors for state store operations.
@@ -317,19 +1259,56 @@ pub enum StateStoreError {
     Serde(serde_json::Error),
     /// Invalid state.
     InvalidState(&'static str),
+    /// Unknown state backend.
+    UnsupportedBackend(String),
+    /// Missing required environment variable of the selected backend.
+    MissingEnv(&'static str),
+    /// Backend-specific error (database, Redis or S3 request).
+    Backend(String),
+    /// Revision mismatch: the stored state changed since it was loaded.
+    Conflict {
+        /// Revision carried by the state being saved.
+        expected: u64,
+        /// Revision currently persisted.
+        found: u64,
+    },
+    /// The lock file guarding a file could not be taken or released.
+    Lock(LockError),
+    /// Another writer held the lock of this file for too long.
+    LockTimeout(PathBuf),
 }
 
 impl fmt::Display for StateStoreError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Io(e) => write!(f, "io error: {e}"),
-            Self::Serde(e) => write!(f, "serde error: {e}"),
+            Self::Io(_) => write!(f, "io error"),
+            Self::Serde(_) => write!(f, "serde error"),
             Self::InvalidState(msg) => write!(f, "invalid state: {msg}"),
+            Self::UnsupportedBackend(name) => write!(f, "unsupported state backend: {name}"),
+            Self::MissingEnv(k) => write!(f, "missing required env var: {k}"),
+            Self::Backend(e) => write!(f, "state backend error: {e}"),
+            Self::Conflict { expected, found } => write!(
+                f,
+                "state revision conflict: expected {expected}, found {found}"
+            ),
+            Self::Lock(_) => write!(f, "state file lock failed"),
+            Self::LockTimeout(path) => {
+                write!(f, "timeout waiting for the lock of {}", path.display())
+            }
         }
     }
 }
 
-impl std::error::Error for StateStoreError {}
+impl std::error::Error for StateStoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Serde(e) => Some(e),
+            Self::Lock(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl From<io::Error> for StateStoreError {
     fn from(value: io::Error) -> Self {
@@ -351,12 +1330,32 @@ pub trait StateStore {
     ///
     /// Returns an error if loading fails (I/O, parsing, or validation).
     fn load(&self) -> Result<Option<RunPodState>, StateStoreError>;
-    /// Save the state to storage.
+    /// Save the state to storage (compare-and-swap on `revision`).
+    ///
+    /// The save only succeeds if `state.revision` matches the persisted
+    /// revision (0 when nothing is stored yet). On success, `state.revision`
+    /// is bumped to the newly persisted value.
     ///
     /// # Errors
     ///
-    /// Returns an error if saving fails (I/O, serialization, or validation).
-    fn save(&self, state: &RunPodState) -> Result<(), StateStoreError>;
+    /// Returns `StateStoreError::Conflict` if another writer saved in between,
+    /// or an error if saving fails (I/O, serialization, or validation).
+    fn save(&self, state: &mut RunPodState) -> Result<(), StateStoreError>;
+}
+
+/// Run a (blocking) store call on Tokio's blocking thread pool.
+///
+/// # Errors
+///
+/// Returns the call's error, or `StateStoreError::Io` if the call panicked.
+pub(crate) async fn run_blocking<T: Send + 'static>(
+    store: &Arc<dyn StateStore + Send + Sync>,
+    call: impl FnOnce(&dyn StateStore) -> Result<T, StateStoreError> + Send + 'static,
+) -> Result<T, StateStoreError> {
+    let store = Arc::clone(store);
+    tokio::task::spawn_blocking(move || call(store.as_ref()))
+        .await
+        .map_err(|e| StateStoreError::Io(io::Error::other(format!("store call panicked: {e}"))))?
 }
 
 /// File-based JSON state store with safe atomic writes.
@@ -381,21 +1380,53 @@ impl JsonFileStateStore {
     /// Get the default path from environment or fallback.
     ///
     /// Env: `RUNPOD_STATE_PATH` (default: `.runpod_state.json`)
+    /// Env: `RUNPOD_STATE_PROFILE` (optional): namespaces the file per project,
+    /// e.g. `projA` resolves to `.runpod_state.projA.json`.
     #[must_use]
     pub fn default_path() -> PathBuf {
-        if let Some(p) = std::env::var_os("RUNPOD_STATE_PATH") {
-            return PathBuf::from(p);
+        let base = Self::base_path();
+
+        match state_profile_from_env() {
+            Some(profile) => Self::profile_path(&base, &profile),
+            None => base,
         }
-        PathBuf::from(".runpod_state.json")
     }
 
-    fn ensure_parent_dir(&self) -> Result<(), io::Error> {
-        if let Some(parent) = self.path.parent()
-            && !parent.as_os_str().is_empty()
-        {
-            fs::create_dir_all(parent)?;
+    /// Create a store for a named profile next to the default state path.
+    #[must_use]
+    pub fn for_profile(profile: &str) -> Self {
+        Self::new(Self::profile_path(&Self::base_path(), profile))
+    }
+
+    /// Derive the state file path of a profile from a base path.
+    ///
+    /// The profile is inserted before the extension (`state.json` → `state.<profile>.json`).
+    /// Characters outside `[A-Za-z0-9_-]` are replaced with `_` so a profile can
+    /// never escape the state directory.
+    #[must_use]
+    pub fn profile_path(base: &Path, profile: &str) -> PathBuf {
+        let profile = sanitize_profile(profile);
+        if profile.is_empty() {
+            return base.to_path_buf();
         }
-        Ok(())
+
+        let stem = base
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("runpod_state");
+        let file_name = base.extension().and_then(|e| e.to_str()).map_or_else(
+            || format!("{stem}.{profile}"),
+            |ext| format!("{stem}.{profile}.{ext}"),
+        );
+
+        let mut path = base.to_path_buf();
+        path.set_file_name(file_name);
+        path
+    }
+
+    fn base_path() -> PathBuf {
+        std::env::var_os("RUNPOD_STATE_PATH")
+            .map_or_else(|| PathBuf::from(".runpod_state.json"), PathBuf::from)
     }
 }
 
@@ -404,59 +1435,416 @@ impl StateStore for JsonFileStateStore {
         if !self.path.exists() {
             return Ok(None);
         }
-        let bytes = fs::read(&self.path)?;
-        let state: RunPodState = serde_json::from_slice(&bytes)?;
-        if state.format_version != STATE_FORMAT_VERSION {
-            return Err(StateStoreError::InvalidState(
-                "unsupported state format version",
-            ));
-        }
-        if state.pod_name.trim().is_empty() {
-            return Err(StateStoreError::InvalidState("pod_name is empty"));
-        }
-        Ok(Some(state))
+        parse_stored_state(&fs::read(&self.path)?).map(Some)
     }
 
-    fn save(&self, state: &RunPodState) -> Result<(), StateStoreError> {
-        if state.format_version != STATE_FORMAT_VERSION {
-            return Err(StateStoreError::InvalidState("wrong state format version"));
+    fn save(&self, state: &mut RunPodState) -> Result<(), StateStoreError> {
+        check_state_to_save(state)?;
+
+        // The revision check and the write run under the file's lock, so
+        // two writers holding the same revision never both succeed.
+        with_file_lock(&self.path, || {
+            let found = self.load()?.map_or(0, |stored| stored.revision);
+            if found != state.revision {
+                return Err(StateStoreError::Conflict {
+                    expected: state.revision,
+                    found,
+                });
+            }
+
+            let mut next = state.clone();
+            next.revision = state.revision.saturating_add(1);
+            write_atomic(&self.path, &serde_json::to_vec_pretty(&next)?)?;
+
+            state.revision = next.revision;
+            Ok(())
+        })
+    }
+}
+
+/// Parse and validate a persisted state document.
+///
+/// # Errors
+///
+/// Returns an error if the bytes are not a state of a supported format.
+pub(crate) fn parse_stored_state(bytes: &[u8]) -> Result<RunPodState, StateStoreError> {
+    let state: RunPodState = serde_json::from_slice(bytes)?;
+    if state.format_version != STATE_FORMAT_VERSION {
+        return Err(StateStoreError::InvalidState(
+            "unsupported state format version",
+        ));
+    }
+    if state.pod_name.trim().is_empty() {
+        return Err(StateStoreError::InvalidState("pod_name is empty"));
+    }
+    Ok(state)
+}
+
+/// Validate a state before a store persists it.
+///
+/// # Errors
+///
+/// Returns an error if the state has a wrong format version or no pod name.
+pub(crate) fn check_state_to_save(state: &RunPodState) -> Result<(), StateStoreError> {
+    if state.format_version != STATE_FORMAT_VERSION {
+        return Err(StateStoreError::InvalidState("wrong state format version"));
+    }
+    if state.pod_name.trim().is_empty() {
+        return Err(StateStoreError::InvalidState("pod_name is empty"));
+    }
+    Ok(())
+}
+
+/// How long a file store waits for another writer's lock.
+const FILE_LOCK_WAIT: Duration = Duration::from_secs(10);
+
+/// Lifetime of a file store's lock: a crashed writer's lock expires after it.
+const FILE_LOCK_TTL: Duration = Duration::from_secs(30);
+
+/// Run `f` holding the lock of `path` (a `FileLock` next to it), so the
+/// read-compare-write sequences of several processes never interleave.
+///
+/// # Errors
+///
+/// Returns `f`'s error, or an error if the lock cannot be taken within
+/// `FILE_LOCK_WAIT` or released.
+pub(crate) fn with_file_lock<T>(
+    path: &Path,
+    f: impl FnOnce() -> Result<T, StateStoreError>,
+) -> Result<T, StateStoreError> {
+    let dir = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let key = path
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("runpod_state");
+    let lock = FileLock::new(dir);
+    let owner = runpod_lock::acquisition_token();
+
+    let started = Instant::now();
+    while !lock
+        .try_acquire(key, &owner, FILE_LOCK_TTL)
+        .map_err(StateStoreError::Lock)?
+    {
+        if started.elapsed() >= FILE_LOCK_WAIT {
+            return Err(StateStoreError::LockTimeout(path.to_path_buf()));
         }
-        if state.pod_name.trim().is_empty() {
-            return Err(StateStoreError::InvalidState("pod_name is empty"));
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    let result = f();
+    let released = lock.release(key, &owner).map_err(StateStoreError::Lock);
+    let value = result?;
+    released?;
+    Ok(value)
+}
+
+/// Write `bytes` to `path` atomically.
+///
+/// The bytes go to a uniquely named temporary file next to `path`
+/// (created with `O_EXCL`), are synced, then renamed over `path`. The
+/// rename replaces the target in one step (on Windows too), so readers see
+/// the old or the new content and never a missing file.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written.
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut tmp = path.to_path_buf();
+    tmp.set_file_name(format!(
+        ".{}.{}.tmp",
+        path.file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("runpod_state"),
+        runpod_lock::acquisition_token()
+    ));
+    let written = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp)
+        .and_then(|mut f| {
+            f.write_all(bytes)?;
+            f.sync_all()
+        })
+        .and_then(|()| fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    written
+}
+
+/// Outcome of executing a `PlannedAction`, fed back into the state on commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionOutcome {
+    /// The action succeeded and needs no ID bookkeeping (Noop/Start/Resume/Stop).
+    Applied,
+    /// A pod was created with the given ID.
+    Created(PodId),
+    /// The pod was terminated.
+    Terminated,
+}
+
+/// Load → plan → execute → commit, with rollback on failure.
+///
+/// The transaction works on a copy of the loaded state. If execution fails,
+/// the working copy is reset to what was loaded, so the persisted state never
+/// claims an action happened that didn't. Nothing is written until `commit()`.
+pub struct StateTransaction<'a, S: StateStore + ?Sized> {
+    store: &'a S,
+    original: RunPodState,
+    working: RunPodState,
+    planned: Option<PlannedAction>,
+}
+
+impl<'a, S: StateStore + Sync + ?Sized> StateTransaction<'a, S> {
+    /// Begin a transaction by loading the current state.
+    ///
+    /// If nothing is stored yet, starts from `RunPodState::new(pod_name, now_ms)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state cannot be loaded.
+    pub fn begin(
+        store: &'a S,
+        pod_name: impl Into<String>,
+        now_ms: u64,
+    ) -> Result<Self, StateStoreError> {
+        let original = store
+            .load()?
+            .unwrap_or_else(|| RunPodState::new(pod_name, now_ms));
+        Ok(Self {
+            store,
+            working: original.clone(),
+            original,
+            planned: None,
+        })
+    }
+
+    /// Get the working copy of the state.
+    #[must_use]
+    pub const fn state(&self) -> &RunPodState {
+        &self.working
+    }
+
+    /// Get mutable access to the working copy (e.g. to change the target).
+    pub const fn state_mut(&mut self) -> &mut RunPodState {
+        &mut self.working
+    }
+
+    /// Reconcile the working copy against a remote observation.
+    pub fn plan(&mut self, observation: RemoteObservation, now_ms: u64) -> PlannedAction {
+        let action = self.working.reconcile(observation, now_ms);
+        self.planned = Some(action.clone());
+        action
+    }
+
+    /// Execute the planned action (`Noop` if `plan()` was not called).
+    ///
+    /// On success, the outcome is applied to the working copy.
+    /// On failure, the working copy is rolled back to the loaded state.
+    ///
+    /// # Errors
+    ///
+    /// Returns the executor's error unchanged.
+    pub async fn execute<F, Fut, E>(&mut self, now_ms: u64, exec: F) -> Result<ActionOutcome, E>
+    where
+        F: FnOnce(PlannedAction) -> Fut + Send,
+        Fut: Future<Output = Result<ActionOutcome, E>> + Send,
+    {
+        let action = self.planned.take().unwrap_or(PlannedAction::Noop);
+
+        match exec(action).await {
+            Ok(outcome) => {
+                match &outcome {
+                    ActionOutcome::Applied => {}
+                    ActionOutcome::Created(id) => self.working.apply_created(id.clone(), now_ms),
+                    ActionOutcome::Terminated => self.working.apply_terminated(now_ms),
+                }
+                Ok(outcome)
+            }
+            Err(e) => {
+                self.working = self.original.clone();
+                Err(e)
+            }
         }
+    }
+
+    /// Persist the working copy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if saving fails (including revision conflicts).
+    pub fn commit(mut self) -> Result<RunPodState, StateStoreError> {
+        self.store.save(&mut self.working)?;
+        Ok(self.working)
+    }
 
-        self.ensure_parent_dir()?;
+    /// Discard the transaction and return the state as it was loaded.
+    #[must_use]
+    pub fn rollback(self) -> RunPodState {
+        self.original
+    }
+}
 
-        // Write to temp file in same directory for atomic rename.
-        let mut tmp = self.path.clone();
-        let tmp_name = format!(
-            ".{}.tmp",
-            self.path
-                .file_name()
-                .and_then(|s| s.to_str())
-                .unwrap_or("runpod_state")
-        );
-        tmp.set_file_name(tmp_name);
+/// In-memory state store.
+///
+/// State lives only as long as the store itself. Useful for tests and
+/// short-lived processes that do not need persistence across restarts.
+#[derive(Debug, Default)]
+pub struct MemoryStateStore {
+    state: Mutex<Option<RunPodState>>,
+}
 
-        let json = serde_json::to_vec_pretty(state)?;
+impl MemoryStateStore {
+    /// Create a new, empty in-memory state store.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
 
-        {
-            let mut f = fs::File::create(&tmp)?;
-            f.write_all(&json)?;
-            f.sync_all()?;
+impl StateStore for MemoryStateStore {
+    fn load(&self) -> Result<Option<RunPodState>, StateStoreError> {
+        Ok(self
+            .state
+            .lock()
+            .map_err(|_| StateStoreError::InvalidState("memory store lock poisoned"))?
+            .clone())
+    }
+
+    fn save(&self, state: &mut RunPodState) -> Result<(), StateStoreError> {
+        if state.pod_name.trim().is_empty() {
+            return Err(StateStoreError::InvalidState("pod_name is empty"));
         }
+        let mut guard = self
+            .state
+            .lock()
+            .map_err(|_| StateStoreError::InvalidState("memory store lock poisoned"))?;
 
-        // Best-effort atomic replace (cross-platform pragmatic).
-        if self.path.exists() {
-            // On Windows, rename over existing can fail; remove first.
-            let _ = fs::remove_file(&self.path);
+        let found = guard.as_ref().map_or(0, |stored| stored.revision);
+        if found != state.revision {
+            return Err(StateStoreError::Conflict {
+                expected: state.revision,
+                found,
+            });
         }
-        fs::rename(&tmp, &self.path)?;
 
+        state.revision = state.revision.saturating_add(1);
+        *guard = Some(state.clone());
+        drop(guard);
         Ok(())
     }
 }
 
+/// Get the active state profile.
+///
+/// Env: `RUNPOD_STATE_PROFILE` (optional, unset or empty means no profile)
+#[must_use]
+pub fn state_profile_from_env() -> Option<String> {
+    std::env::var("RUNPOD_STATE_PROFILE")
+        .ok()
+        .map(|p| sanitize_profile(&p))
+        .filter(|p| !p.is_empty())
+}
+
+pub(crate) fn sanitize_profile(raw: &str) -> String {
+    raw.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Build the state store selected by configuration.
+///
+/// Env: `RUNPOD_STATE_BACKEND` (default: "json")
+/// Options:
+/// - "json": `JsonFileStateStore` at `RUNPOD_STATE_PATH`, namespaced by
+///   `RUNPOD_STATE_PROFILE` (see `JsonFileStateStore::default_path()`)
+/// - "sqlite" (feature `sqlite`): `SqliteStateStore::from_env()`
+/// - "redis" (feature `redis`): `RedisStateStore::from_env()`
+/// - "s3" (feature `s3`): `S3StateStore::from_env()`
+/// - "memory": `MemoryStateStore` (no persistence)
+///
+/// Other stores implement `StateStore` and are installed with
+/// `RunpodOrchestrator::with_state_store()`.
+///
+/// # Errors
+///
+/// Returns `StateStoreError::UnsupportedBackend` if the backend is unknown or
+/// its feature is disabled, or an error if the backend settings are invalid.
+pub fn state_store_from_env() -> Result<Box<dyn StateStore + Send + Sync>, StateStoreError> {
+    backend_from_env(None)
+}
+
+/// Build the state store of a named profile, on the backend selected by
+/// configuration (see `state_store_from_env()`).
+///
+/// With the "json" backend each profile gets its own file
+/// (`JsonFileStateStore::for_profile()`), with "sqlite" its own row, with
+/// "redis" its own key and with "s3" its own object; "memory" returns a
+/// fresh store.
+///
+/// # Errors
+///
+/// Returns `StateStoreError::UnsupportedBackend` if the backend is unknown or
+/// its feature is disabled, or an error if the backend settings are invalid.
+pub fn profile_state_store_from_env(
+    profile: &str,
+) -> Result<Box<dyn StateStore + Send + Sync>, StateStoreError> {
+    backend_from_env(Some(profile))
+}
+
+/// Build the store of the `RUNPOD_STATE_BACKEND` backend for `profile`
+/// (`None`: the `RUNPOD_STATE_PROFILE` one).
+fn backend_from_env(
+    profile: Option<&str>,
+) -> Result<Box<dyn StateStore + Send + Sync>, StateStoreError> {
+    crate::runpod_env::load_dotenv().map_err(|e| StateStoreError::Io(io::Error::other(e)))?;
+
+    let backend = std::env::var("RUNPOD_STATE_BACKEND").unwrap_or_else(|_| "json".to_string());
+
+    match backend.trim().to_lowercase().as_str() {
+        "" | "json" => Ok(Box::new(profile.map_or_else(
+            || JsonFileStateStore::new(JsonFileStateStore::default_path()),
+            JsonFileStateStore::for_profile,
+        ))),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Ok(Box::new(runpod_store::SqliteStateStore::from_env(profile)?)),
+        #[cfg(feature = "redis")]
+        "redis" => Ok(Box::new(runpod_store::RedisStateStore::from_env(profile)?)),
+        #[cfg(feature = "s3")]
+        "s3" => Ok(Box::new(runpod_store::S3StateStore::from_env(profile)?)),
+        #[cfg(not(feature = "sqlite"))]
+        "sqlite" => Err(backend_disabled("sqlite")),
+        #[cfg(not(feature = "redis"))]
+        "redis" => Err(backend_disabled("redis")),
+        #[cfg(not(feature = "s3"))]
+        "s3" => Err(backend_disabled("s3")),
+        "memory" => Ok(Box::new(MemoryStateStore::new())),
+        other => Err(StateStoreError::UnsupportedBackend(other.to_string())),
+    }
+}
+
+/// A known backend whose cargo feature (of the same name) is disabled.
+#[cfg(not(all(feature = "sqlite", feature = "redis", feature = "s3")))]
+fn backend_disabled(name: &str) -> StateStoreError {
+    StateStoreError::UnsupportedBackend(format!("{name} (built without the `{name}` feature)"))
+}
+
 /// Utility: current timestamp in milliseconds since UNIX epoch.
 #[must_use]
 pub fn now_unix_ms() -> u64 {
@@ -0,0 +1,672 @@
+//! External state store backends.
+//!
+//! Unique responsibility: persist `RunPodState` outside the local filesystem,
+//! so orchestrators on several hosts can share one state.
+//!
+//! Backends (each behind the cargo feature of the same name):
+//! - `SqliteStateStore` (`sqlite`): one row per profile in an `SQLite` database
+//! - `RedisStateStore` (`redis`): one hash per profile in Redis
+//! - `S3StateStore` (`s3`): one JSON object per profile in an S3 bucket (AWS,
+//!   or any S3-compatible service supporting conditional writes)
+//!
+//! Like `JsonFileStateStore`, every save is a compare-and-swap on
+//! `revision`. The stores block: the orchestrator calls them on Tokio's
+//! blocking thread pool. `state_store_from_env()` builds them from
+//! `RUNPOD_STATE_BACKEND`.
+//!
+//! ```ignore
+//! // RUNPOD_STATE_BACKEND=redis RUNPOD_STATE_REDIS_URL=redis://state-host:6379
+//! let orchestrator = RunpodOrchestrator::new(cfg)?
+//!     .with_state_store(Arc::from(state_store_from_env()?));
+//! ```
+
+use std::{env, fmt, time::Duration};
+#[cfg(feature = "sqlite")]
+use std::{path::Path, sync::Mutex};
+
+#[cfg(feature = "s3")]
+use std::{fmt::Write as _, sync::OnceLock};
+
+#[cfg(feature = "s3")]
+use crate::runpod_http::ApiKey;
+use crate::runpod_state::{
+    RunPodState, StateStore, StateStoreError, check_state_to_save, parse_stored_state,
+    sanitize_profile, state_profile_from_env,
+};
+
+/// How long a backend call may take (connection, lock wait or request).
+const BACKEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Profile a store built from env uses: `profile`, else `RUNPOD_STATE_PROFILE`
+/// ("" = no profile).
+fn resolve_profile(profile: Option<&str>) -> String {
+    profile.map_or_else(
+        || state_profile_from_env().unwrap_or_default(),
+        sanitize_profile,
+    )
+}
+
+#[cfg(any(feature = "redis", feature = "s3"))]
+fn must_env(key: &'static str) -> Result<String, StateStoreError> {
+    env::var(key)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .ok_or(StateStoreError::MissingEnv(key))
+}
+
+fn opt_env(key: &str) -> Option<String> {
+    env::var(key)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn backend_error(backend: &str, err: impl fmt::Display) -> StateStoreError {
+    StateStoreError::Backend(format!("{backend}: {err}"))
+}
+
+// ============================================================================
+// SQLite backend
+// ============================================================================
+
+/// State in an `SQLite` database: one row per profile in table `runpod_state`.
+///
+/// The revision check and the write run in one `IMMEDIATE` transaction, so
+/// processes sharing the database file never both win a save.
+#[cfg(feature = "sqlite")]
+#[derive(Debug)]
+pub struct SqliteStateStore {
+    conn: Mutex<rusqlite::Connection>,
+    profile: String,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteStateStore {
+    /// Open (creating it if needed) the database at `path`, for the state of
+    /// `profile` ("" = no profile).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or initialized.
+    pub fn open(path: impl AsRef<Path>, profile: &str) -> Result<Self, StateStoreError> {
+        let conn = rusqlite::Connection::open(path).map_err(sqlite_error)?;
+        conn.busy_timeout(BACKEND_TIMEOUT).map_err(sqlite_error)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS runpod_state (
+                profile TEXT PRIMARY KEY,
+                revision INTEGER NOT NULL,
+                state TEXT NOT NULL
+            )",
+        )
+        .map_err(sqlite_error)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            profile: sanitize_profile(profile),
+        })
+    }
+
+    /// Open the database configured by environment variables, for `profile`
+    /// (`None`: the `RUNPOD_STATE_PROFILE` one).
+    ///
+    /// Env: `RUNPOD_STATE_SQLITE_PATH` (default: `.runpod_state.sqlite`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or initialized.
+    pub fn from_env(profile: Option<&str>) -> Result<Self, StateStoreError> {
+        let path = opt_env("RUNPOD_STATE_SQLITE_PATH")
+            .unwrap_or_else(|| ".runpod_state.sqlite".to_string());
+        Self::open(path, &resolve_profile(profile))
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, rusqlite::Connection>, StateStoreError> {
+        self.conn
+            .lock()
+            .map_err(|_| StateStoreError::InvalidState("sqlite connection lock poisoned"))
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl StateStore for SqliteStateStore {
+    fn load(&self) -> Result<Option<RunPodState>, StateStoreError> {
+        use rusqlite::OptionalExtension as _;
+
+        let stored: Option<String> = self
+            .conn()?
+            .query_row(
+                "SELECT state FROM runpod_state WHERE profile = ?1",
+                [&self.profile],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(sqlite_error)?;
+        stored
+            .map(|json| parse_stored_state(json.as_bytes()))
+            .transpose()
+    }
+
+    fn save(&self, state: &mut RunPodState) -> Result<(), StateStoreError> {
+        use rusqlite::OptionalExtension as _;
+
+        check_state_to_save(state)?;
+
+        let mut conn = self.conn()?;
+        let tx = conn
+            .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+            .map_err(sqlite_error)?;
+        // `SQLite` integers are signed: revisions are stored as `i64`.
+        let found: i64 = tx
+            .query_row(
+                "SELECT revision FROM runpod_state WHERE profile = ?1",
+                [&self.profile],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(sqlite_error)?
+            .unwrap_or(0);
+        let found = u64::try_from(found)
+            .map_err(|_| StateStoreError::InvalidState("stored revision is negative"))?;
+        if found != state.revision {
+            return Err(StateStoreError::Conflict {
+                expected: state.revision,
+                found,
+            });
+        }
+
+        let mut next = state.clone();
+        next.revision = state.revision.saturating_add(1);
+        let revision = i64::try_from(next.revision)
+            .map_err(|_| StateStoreError::InvalidState("revision out of range"))?;
+        tx.execute(
+            "INSERT INTO runpod_state (profile, revision, state) VALUES (?1, ?2, ?3)
+             ON CONFLICT (profile) DO UPDATE SET revision = excluded.revision, state = excluded.state",
+            rusqlite::params![self.profile, revision, serde_json::to_string(&next)?],
+        )
+        .map_err(sqlite_error)?;
+        tx.commit().map_err(sqlite_error)?;
+        drop(conn);
+
+        state.revision = next.revision;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite_error(err: rusqlite::Error) -> StateStoreError {
+    backend_error("sqlite", err)
+}
+
+// ============================================================================
+// Redis backend
+// ============================================================================
+
+/// Compare-and-swap of a state hash: writes `ARGV[2]` (revision) and
+/// `ARGV[3]` (state) if the stored revision is `ARGV[1]`, otherwise returns
+/// the stored revision.
+#[cfg(feature = "redis")]
+const REDIS_SAVE_SCRIPT: &str = r"
+local found = redis.call('HGET', KEYS[1], 'revision') or '0'
+if found ~= ARGV[1] then
+    return found
+end
+redis.call('HSET', KEYS[1], 'revision', ARGV[2], 'state', ARGV[3])
+return false
+";
+
+/// State in Redis: a hash (`revision`, `state`) per profile.
+///
+/// Saves run as one Lua script, so the revision check and the write are
+/// atomic on the server. `rediss://` URLs connect over TLS.
+#[cfg(feature = "redis")]
+pub struct RedisStateStore {
+    client: redis::Client,
+    key: String,
+}
+
+#[cfg(feature = "redis")]
+impl fmt::Debug for RedisStateStore {
+    // The client holds the URL, which may carry a password.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisStateStore")
+            .field("key", &self.key)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "redis")]
+impl RedisStateStore {
+    /// Create a store keeping the state in hash `key` of the server at `url`
+    /// (`redis://[:password@]host[:port][/db]`). No connection is made yet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid.
+    pub fn open(url: &str, key: impl Into<String>) -> Result<Self, StateStoreError> {
+        Ok(Self {
+            client: redis::Client::open(url).map_err(redis_error)?,
+            key: key.into(),
+        })
+    }
+
+    /// Create the store configured by environment variables, for `profile`
+    /// (`None`: the `RUNPOD_STATE_PROFILE` one), stored at `<key>:<profile>`.
+    ///
+    /// Env: `RUNPOD_STATE_REDIS_URL` (required)
+    /// Env: `RUNPOD_STATE_REDIS_KEY` (default: "runpod:state")
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is missing or invalid.
+    pub fn from_env(profile: Option<&str>) -> Result<Self, StateStoreError> {
+        let url = must_env("RUNPOD_STATE_REDIS_URL")?;
+        let base = opt_env("RUNPOD_STATE_REDIS_KEY").unwrap_or_else(|| "runpod:state".to_string());
+        let profile = resolve_profile(profile);
+        let key = if profile.is_empty() {
+            base
+        } else {
+            format!("{base}:{profile}")
+        };
+        Self::open(url.trim(), key)
+    }
+
+    fn connection(&self) -> Result<redis::Connection, StateStoreError> {
+        let conn = self
+            .client
+            .get_connection_with_timeout(BACKEND_TIMEOUT)
+            .map_err(redis_error)?;
+        conn.set_read_timeout(Some(BACKEND_TIMEOUT))
+            .and_then(|()| conn.set_write_timeout(Some(BACKEND_TIMEOUT)))
+            .map_err(redis_error)?;
+        Ok(conn)
+    }
+}
+
+#[cfg(feature = "redis")]
+impl StateStore for RedisStateStore {
+    fn load(&self) -> Result<Option<RunPodState>, StateStoreError> {
+        let stored: Option<String> = redis::cmd("HGET")
+            .arg(&self.key)
+            .arg("state")
+            .query(&mut self.connection()?)
+            .map_err(redis_error)?;
+        stored
+            .map(|json| parse_stored_state(json.as_bytes()))
+            .transpose()
+    }
+
+    fn save(&self, state: &mut RunPodState) -> Result<(), StateStoreError> {
+        check_state_to_save(state)?;
+
+        let mut next = state.clone();
+        next.revision = state.revision.saturating_add(1);
+        let conflict: Option<String> = redis::cmd("EVAL")
+            .arg(REDIS_SAVE_SCRIPT)
+            .arg(1)
+            .arg(&self.key)
+            .arg(state.revision.to_string())
+            .arg(next.revision.to_string())
+            .arg(serde_json::to_string(&next)?)
+            .query(&mut self.connection()?)
+            .map_err(redis_error)?;
+        if let Some(found) = conflict {
+            return Err(StateStoreError::Conflict {
+                expected: state.revision,
+                found: found.parse().map_err(|_| {
+                    StateStoreError::InvalidState("stored revision is not a number")
+                })?,
+            });
+        }
+
+        state.revision = next.revision;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "redis")]
+fn redis_error(err: redis::RedisError) -> StateStoreError {
+    backend_error("redis", err)
+}
+
+// ============================================================================
+// S3 backend
+// ============================================================================
+
+/// State as a JSON object in an S3 bucket.
+///
+/// Requests are signed with AWS Signature Version 4. A save checks the
+/// stored revision, then writes with `If-Match` on the `ETag` it read (or
+/// `If-None-Match: *` for the first save), so a concurrent writer makes the
+/// service reject it instead of being overwritten.
+#[cfg(feature = "s3")]
+#[derive(Debug)]
+pub struct S3StateStore {
+    endpoint: Option<reqwest::Url>,
+    bucket: String,
+    key: String,
+    region: String,
+    credentials: S3Credentials,
+    http: OnceLock<reqwest::blocking::Client>,
+}
+
+/// AWS credentials of an `S3StateStore` (secrets redacted in `Debug`).
+#[cfg(feature = "s3")]
+#[derive(Debug, Clone)]
+pub struct S3Credentials {
+    /// Access key ID.
+    pub access_key_id: String,
+    /// Secret access key.
+    pub secret_access_key: ApiKey,
+    /// Session token of temporary credentials.
+    pub session_token: Option<ApiKey>,
+}
+
+#[cfg(feature = "s3")]
+impl S3StateStore {
+    /// Create a store keeping the state in object `key` of `bucket`, on AWS
+    /// in `region`.
+    #[must_use]
+    pub fn new(
+        bucket: impl Into<String>,
+        key: impl Into<String>,
+        region: impl Into<String>,
+        credentials: S3Credentials,
+    ) -> Self {
+        Self {
+            endpoint: None,
+            bucket: bucket.into(),
+            key: key.into(),
+            region: region.into(),
+            credentials,
+            http: OnceLock::new(),
+        }
+    }
+
+    /// Send requests to an S3-compatible service at `endpoint` (e.g.
+    /// `http://localhost:9000`) instead of AWS, with path-style URLs.
+    #[must_use]
+    pub fn with_endpoint(mut self, endpoint: reqwest::Url) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    /// Create the store configured by environment variables, for `profile`
+    /// (`None`: the `RUNPOD_STATE_PROFILE` one). A profile is inserted before
+    /// the key's extension (`runpod_state.json` → `runpod_state.<profile>.json`).
+    ///
+    /// Env: `RUNPOD_STATE_S3_BUCKET` (required)
+    /// Env: `RUNPOD_STATE_S3_KEY` (default: `runpod_state.json`)
+    /// Env: `RUNPOD_STATE_S3_REGION` (default: `AWS_REGION`, else "us-east-1")
+    /// Env: `RUNPOD_STATE_S3_ENDPOINT` (optional, S3-compatible service)
+    /// Env: `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` (required),
+    /// `AWS_SESSION_TOKEN` (optional)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required variable is missing or the endpoint is
+    /// not a URL.
+    pub fn from_env(profile: Option<&str>) -> Result<Self, StateStoreError> {
+        let bucket = must_env("RUNPOD_STATE_S3_BUCKET")?;
+        let key = opt_env("RUNPOD_STATE_S3_KEY").unwrap_or_else(|| "runpod_state.json".to_string());
+        let region = opt_env("RUNPOD_STATE_S3_REGION")
+            .or_else(|| opt_env("AWS_REGION"))
+            .unwrap_or_else(|| "us-east-1".to_string());
+        let credentials = S3Credentials {
+            access_key_id: must_env("AWS_ACCESS_KEY_ID")?.trim().to_string(),
+            secret_access_key: ApiKey::new(must_env("AWS_SECRET_ACCESS_KEY")?.trim().to_string()),
+            session_token: opt_env("AWS_SESSION_TOKEN").map(ApiKey::new),
+        };
+
+        let store = Self::new(
+            bucket.trim(),
+            profile_key(&key, &resolve_profile(profile)),
+            region,
+            credentials,
+        );
+        match opt_env("RUNPOD_STATE_S3_ENDPOINT") {
+            Some(raw) => {
+                let endpoint = reqwest::Url::parse(&raw).map_err(|_| {
+                    StateStoreError::InvalidState("RUNPOD_STATE_S3_ENDPOINT is not a URL")
+                })?;
+                Ok(store.with_endpoint(endpoint))
+            }
+            None => Ok(store),
+        }
+    }
+
+    /// Object URL, and its path as signed (the canonical URI).
+    fn object_url(&self) -> Result<(reqwest::Url, String), StateStoreError> {
+        let key = encode_key(&self.key);
+        let (base, path) = self.endpoint.as_ref().map_or_else(
+            || {
+                (
+                    format!("https://{}.s3.{}.amazonaws.com", self.bucket, self.region),
+                    format!("/{key}"),
+                )
+            },
+            |endpoint| {
+                (
+                    endpoint.as_str().trim_end_matches('/').to_string(),
+                    format!("/{}/{key}", self.bucket),
+                )
+            },
+        );
+        let url = reqwest::Url::parse(&format!("{base}{path}"))
+            .map_err(|e| backend_error("s3", format!("invalid object URL: {e}")))?;
+        Ok((url, path))
+    }
+
+    /// Send a signed request for the object.
+    fn send(
+        &self,
+        method: reqwest::Method,
+        body: Vec<u8>,
+        headers: &[(&'static str, String)],
+    ) -> Result<reqwest::blocking::Response, StateStoreError> {
+        let (url, path) = self.object_url()?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(backend_error("s3", "endpoint has no host")),
+        };
+        let secs = crate::runpod_state::now_unix_ms() / 1_000;
+        let amz_date: String = crate::runpod_provisioner::format_rfc3339(secs)
+            .chars()
+            .filter(|c| !matches!(c, '-' | ':'))
+            .collect();
+        let date = &amz_date[..8];
+        let payload_hash = hex(sha256(&body).as_ref());
+
+        // Signed headers: sorted by name, every `x-amz-*` included.
+        let mut signed = vec![
+            ("host", host),
+            ("x-amz-content-sha256", payload_hash.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.credentials.session_token {
+            signed.push(("x-amz-security-token", token.expose().to_string()));
+        }
+        signed.sort_by_key(|(name, _)| *name);
+        let signed_names = signed
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers = signed.iter().fold(String::new(), |mut out, (name, value)| {
+            let _ = writeln!(out, "{name}:{}", value.trim());
+            out
+        });
+
+        let canonical_request =
+            format!("{method}\n{path}\n\n{canonical_headers}\n{signed_names}\n{payload_hash}");
+        let scope = format!("{date}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex(sha256(canonical_request.as_bytes()).as_ref())
+        );
+        let signing_key = [self.region.as_str(), "s3", "aws4_request"].iter().fold(
+            hmac_sha256(
+                format!("AWS4{}", self.credentials.secret_access_key.expose()).as_bytes(),
+                date.as_bytes(),
+            ),
+            |key, part| hmac_sha256(&key, part.as_bytes()),
+        );
+        let signature = hex(&hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_names}, Signature={signature}",
+            self.credentials.access_key_id
+        );
+
+        let mut req = self.http()?.request(method, url);
+        for (name, value) in signed.into_iter().filter(|(name, _)| *name != "host") {
+            req = req.header(name, value);
+        }
+        for (name, value) in headers {
+            req = req.header(*name, value);
+        }
+        let mut authorization = reqwest::header::HeaderValue::from_str(&authorization)
+            .map_err(|e| backend_error("s3", e))?;
+        authorization.set_sensitive(true);
+        req.header(reqwest::header::AUTHORIZATION, authorization)
+            .body(body)
+            .send()
+            .map_err(|e| backend_error("s3", e))
+    }
+
+    // Built on first use, on the (blocking) thread of a store call: the
+    // blocking client must not be created or dropped inside an async task.
+    fn http(&self) -> Result<&reqwest::blocking::Client, StateStoreError> {
+        if let Some(http) = self.http.get() {
+            return Ok(http);
+        }
+        let http = reqwest::blocking::Client::builder()
+            .timeout(BACKEND_TIMEOUT)
+            .build()
+            .map_err(|e| backend_error("s3", e))?;
+        Ok(self.http.get_or_init(|| http))
+    }
+
+    /// Stored state and its `ETag` (`None` if the object does not exist).
+    fn fetch(&self) -> Result<Option<(RunPodState, String)>, StateStoreError> {
+        let resp = self.send(reqwest::Method::GET, Vec::new(), &[])?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let resp = check_status(resp)?;
+        let etag = resp
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string)
+            .ok_or_else(|| backend_error("s3", "GET response has no ETag"))?;
+        let bytes = resp.bytes().map_err(|e| backend_error("s3", e))?;
+        Ok(Some((parse_stored_state(&bytes)?, etag)))
+    }
+}
+
+#[cfg(feature = "s3")]
+impl StateStore for S3StateStore {
+    fn load(&self) -> Result<Option<RunPodState>, StateStoreError> {
+        Ok(self.fetch()?.map(|(state, _)| state))
+    }
+
+    fn save(&self, state: &mut RunPodState) -> Result<(), StateStoreError> {
+        check_state_to_save(state)?;
+
+        let stored = self.fetch()?;
+        let found = stored.as_ref().map_or(0, |(stored, _)| stored.revision);
+        if found != state.revision {
+            return Err(StateStoreError::Conflict {
+                expected: state.revision,
+                found,
+            });
+        }
+
+        let mut next = state.clone();
+        next.revision = state.revision.saturating_add(1);
+        let condition = match stored {
+            Some((_, etag)) => ("if-match", etag),
+            None => ("if-none-match", "*".to_string()),
+        };
+        let resp = self.send(
+            reqwest::Method::PUT,
+            serde_json::to_vec_pretty(&next)?,
+            &[condition, ("content-type", "application/json".to_string())],
+        )?;
+        // 412: the object changed since it was read; 409: a concurrent
+        // conditional write is in progress.
+        if matches!(
+            resp.status(),
+            reqwest::StatusCode::PRECONDITION_FAILED | reqwest::StatusCode::CONFLICT
+        ) {
+            return Err(StateStoreError::Conflict {
+                expected: state.revision,
+                found: self.fetch()?.map_or(0, |(current, _)| current.revision),
+            });
+        }
+        check_status(resp)?;
+
+        state.revision = next.revision;
+        Ok(())
+    }
+}
+
+/// Insert a profile before the extension of an object key
+/// (`dir/state.json` → `dir/state.<profile>.json`).
+#[cfg(feature = "s3")]
+fn profile_key(key: &str, profile: &str) -> String {
+    if profile.is_empty() {
+        return key.to_string();
+    }
+    let name_start = key.rfind('/').map_or(0, |i| i + 1);
+    match key[name_start..].rfind('.') {
+        Some(dot) if dot > 0 => {
+            let (stem, ext) = key.split_at(name_start + dot);
+            format!("{stem}.{profile}{ext}")
+        }
+        _ => format!("{key}.{profile}"),
+    }
+}
+
+/// Percent-encode an object key (segments separated by `/` are kept).
+#[cfg(feature = "s3")]
+fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(feature = "s3")]
+fn check_status(
+    resp: reqwest::blocking::Response,
+) -> Result<reqwest::blocking::Response, StateStoreError> {
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let body = resp.text().unwrap_or_default();
+    Err(backend_error("s3", format!("status={status}, body={body}")))
+}
+
+#[cfg(feature = "s3")]
+fn sha256(bytes: &[u8]) -> aws_lc_rs::digest::Digest {
+    aws_lc_rs::digest::digest(&aws_lc_rs::digest::SHA256, bytes)
+}
+
+#[cfg(feature = "s3")]
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let key = aws_lc_rs::hmac::Key::new(aws_lc_rs::hmac::HMAC_SHA256, key);
+    aws_lc_rs::hmac::sign(&key, message).as_ref().to_vec()
+}
+
+#[cfg(feature = "s3")]
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, b| {
+        let _ = fmt::Write::write_fmt(&mut hex, format_args!("{b:02x}"));
+        hex
+    })
+}
@@ -0,0 +1,700 @@
+//! Incremental directory synchronization to a pod.
+//!
+//! Unique responsibility: bring a pod directory up to date with a local
+//! directory over SSH, sending only what changed.
+//!
+//! Works like `rsync` at file granularity, with nothing but `ssh` locally and
+//! `find` + `tar` (GNU) on the pod:
+//! 1. List the remote files (size + mtime, or SHA-256 with `SyncCompare::Checksum`)
+//! 2. Compare with the local files, honoring include/exclude patterns
+//! 3. Stream the new and changed files as one tar archive (mtimes preserved,
+//!    so the next comparison sees them as unchanged)
+//! 4. Optionally delete remote files that no longer exist locally
+//!
+//! With `dry_run`, steps 3 and 4 are skipped and the report says what would happen.
+//!
+//! ```ignore
+//! let mut options = SyncOptions::from_env()?; // RUNPOD_SYNC_*
+//! options.exclude = vec![".git".into(), "target".into()];
+//! let report = runpod_sync::sync_dir(&pod, "./my-repo", &options).await?;
+//! println!("{} files sent, {} bytes", report.uploaded.len(), report.bytes);
+//! ```
+
+use std::{
+    collections::HashMap,
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::UNIX_EPOCH,
+};
+
+use serde::Serialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::runpod_env;
+use crate::runpod_orchestrator::PodLease;
+use crate::runpod_ssh::{SshError, SshLogin, shell_quote};
+
+/// Read size when hashing local files.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// How files are compared with their remote copy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncCompare {
+    /// Same size and modification time (whole seconds) = unchanged. Fast.
+    #[default]
+    SizeMtime,
+    /// Same SHA-256 = unchanged. Reads every file on both sides.
+    Checksum,
+}
+
+/// Options for `sync_dir()`.
+#[derive(Debug, Clone)]
+pub struct SyncOptions {
+    /// Pod directory to synchronize into (created if missing).
+    /// Env: `RUNPOD_SYNC_REMOTE_DIR` (default: "/workspace")
+    pub remote_dir: String,
+    /// Only sync paths matching one of these patterns (empty = everything).
+    /// Env: `RUNPOD_SYNC_INCLUDE` (optional, comma-separated)
+    pub include: Vec<String>,
+    /// Never sync (nor delete) paths matching one of these patterns.
+    /// Env: `RUNPOD_SYNC_EXCLUDE` (optional, comma-separated, e.g. ".git,target")
+    ///
+    /// Patterns use `*`, `**` and `?`. A pattern without `/` matches any path
+    /// component (`target` excludes every `target` directory); one with `/`
+    /// matches from the synchronized root (`data/*.csv`).
+    pub exclude: Vec<String>,
+    /// How files are compared.
+    /// Env: `RUNPOD_SYNC_CHECKSUM` (default: false = size + mtime)
+    pub compare: SyncCompare,
+    /// Delete remote files that do not exist locally (excluded files are kept).
+    /// Env: `RUNPOD_SYNC_DELETE` (default: false)
+    pub delete: bool,
+    /// Report what would change without changing anything.
+    /// Env: `RUNPOD_SYNC_DRY_RUN` (default: false)
+    pub dry_run: bool,
+    /// How to log in to the pod: user, identity file, host checking and
+    /// SSH client program (default: `SshLogin::new()`).
+    /// Env: see `SshLogin::from_env()`
+    pub login: SshLogin,
+}
+
+impl SyncOptions {
+    /// Create options syncing into `remote_dir`, comparing size + mtime,
+    /// without deletion, logging in with `SshLogin::new()` (host checking
+    /// through `KnownHosts::managed()`).
+    #[must_use]
+    pub fn new(remote_dir: impl Into<String>) -> Self {
+        Self {
+            remote_dir: remote_dir.into(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            compare: SyncCompare::SizeMtime,
+            delete: false,
+            dry_run: false,
+            login: SshLogin::new(),
+        }
+    }
+
+    /// Load options from environment variables, logging in with
+    /// `SshLogin::from_env()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a variable is invalid.
+    pub fn from_env() -> Result<Self, SyncError> {
+        let mut options = Self::new(
+            env::var("RUNPOD_SYNC_REMOTE_DIR").unwrap_or_else(|_| "/workspace".to_string()),
+        );
+        options.include = split_csv_env("RUNPOD_SYNC_INCLUDE");
+        options.exclude = split_csv_env("RUNPOD_SYNC_EXCLUDE");
+        if parse_bool_env("RUNPOD_SYNC_CHECKSUM", false)? {
+            options.compare = SyncCompare::Checksum;
+        }
+        options.delete = parse_bool_env("RUNPOD_SYNC_DELETE", false)?;
+        options.dry_run = parse_bool_env("RUNPOD_SYNC_DRY_RUN", false)?;
+        options.login = SshLogin::from_env().map_err(SyncError::Ssh)?;
+        Ok(options)
+    }
+
+    /// Whether `path` (relative, `/`-separated) is synchronized.
+    #[must_use]
+    pub fn is_selected(&self, path: &str) -> bool {
+        let excluded = self
+            .exclude
+            .iter()
+            .any(|pattern| pattern_matches(pattern, path));
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| pattern_matches(pattern, path));
+        included && !excluded
+    }
+}
+
+/// Why a file is sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncReason {
+    /// The file does not exist on the pod.
+    New,
+    /// The file differs from its remote copy.
+    Changed,
+}
+
+/// One file sent (or to be sent, in dry-run mode).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncEntry {
+    /// Path relative to the synchronized root.
+    pub path: String,
+    /// Size in bytes.
+    pub size: u64,
+    /// Why it is sent.
+    pub reason: SyncReason,
+}
+
+/// What a synchronization did (or would do, in dry-run mode).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SyncReport {
+    /// Nothing was changed on the pod.
+    pub dry_run: bool,
+    /// Files sent, in path order.
+    pub uploaded: Vec<SyncEntry>,
+    /// Remote files deleted (with `delete`), in path order.
+    pub deleted: Vec<String>,
+    /// Selected files already up to date.
+    pub unchanged: usize,
+    /// Bytes of file content sent.
+    pub bytes: u64,
+}
+
+impl SyncReport {
+    /// Whether the pod was (or would be) changed.
+    #[must_use]
+    pub const fn has_changes(&self) -> bool {
+        !self.uploaded.is_empty() || !self.deleted.is_empty()
+    }
+}
+
+/// Bring `options.remote_dir` on the pod up to date with `local_dir`.
+///
+/// Symbolic links are skipped. Local files are listed on a blocking thread
+/// and streamed to the pod, never read whole into memory.
+///
+/// # Errors
+///
+/// Returns an error if the lease has no SSH endpoint, the local directory
+/// cannot be read, or a remote command fails.
+pub async fn sync_dir(
+    lease: &PodLease,
+    local_dir: impl AsRef<Path>,
+    options: &SyncOptions,
+) -> Result<SyncReport, SyncError> {
+    let (host, port) = lease
+        .ssh_endpoint()
+        .ok_or_else(|| SyncError::NoSshEndpoint(lease.id.clone()))?;
+    let ssh = SshTarget {
+        host,
+        port,
+        options,
+    };
+    let local_dir = local_dir.as_ref();
+
+    let (root, walk_options) = (local_dir.to_path_buf(), options.clone());
+    let mut local = tokio::task::spawn_blocking(move || {
+        let mut files = Vec::new();
+        walk(&root, "", &walk_options, &mut files).map(|()| files)
+    })
+    .await
+    .map_err(|e| SyncError::Io(io::Error::other(e)))??;
+    local.sort_by(|a, b| a.path.cmp(&b.path));
+    let remote = ssh.remote_manifest().await?;
+
+    let mut report = SyncReport {
+        dry_run: options.dry_run,
+        ..SyncReport::default()
+    };
+    for file in &local {
+        let reason = match remote.get(&file.path) {
+            None => SyncReason::New,
+            Some(remote_file)
+                if file
+                    .differs(remote_file, local_dir, options.compare)
+                    .await? =>
+            {
+                SyncReason::Changed
+            }
+            Some(_) => {
+                report.unchanged += 1;
+                continue;
+            }
+        };
+        report.bytes += file.size;
+        report.uploaded.push(SyncEntry {
+            path: file.path.clone(),
+            size: file.size,
+            reason,
+        });
+    }
+    if options.delete {
+        let local_paths: Vec<&str> = local.iter().map(|f| f.path.as_str()).collect();
+        report.deleted = remote
+            .keys()
+            .filter(|path| {
+                options.is_selected(path) && local_paths.binary_search(&path.as_str()).is_err()
+            })
+            .cloned()
+            .collect();
+        report.deleted.sort();
+    }
+
+    if !options.dry_run {
+        let to_send: Vec<&LocalFile> = local
+            .iter()
+            .filter(|f| report.uploaded.iter().any(|e| e.path == f.path))
+            .collect();
+        if !to_send.is_empty() {
+            ssh.upload(local_dir, &to_send).await?;
+        }
+        if !report.deleted.is_empty() {
+            ssh.delete(&report.deleted).await?;
+        }
+    }
+
+    Ok(report)
+}
+
+// ============================================================================
+// Local side
+// ============================================================================
+
+struct LocalFile {
+    path: String,
+    size: u64,
+    mtime: u64,
+    mode: u32,
+}
+
+/// File as listed on the pod.
+struct RemoteFile {
+    size: u64,
+    mtime: u64,
+    sha256: Option<String>,
+}
+
+impl LocalFile {
+    async fn differs(
+        &self,
+        remote: &RemoteFile,
+        root: &Path,
+        compare: SyncCompare,
+    ) -> Result<bool, SyncError> {
+        Ok(match compare {
+            SyncCompare::SizeMtime => self.size != remote.size || self.mtime != remote.mtime,
+            SyncCompare::Checksum => {
+                let local = sha256_file(&root.join(&self.path)).await?;
+                remote.sha256.as_deref() != Some(local.as_str())
+            }
+        })
+    }
+}
+
+/// Collect the selected regular files under `dir` (`prefix` = its relative path).
+fn walk(
+    dir: &Path,
+    prefix: &str,
+    options: &SyncOptions,
+    files: &mut Vec<LocalFile>,
+) -> Result<(), SyncError> {
+    let entries = fs::read_dir(dir).map_err(|e| SyncError::Dir {
+        path: dir.to_path_buf(),
+        source: e,
+    })?;
+    for entry in entries {
+        let entry = entry.map_err(SyncError::Io)?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{prefix}/{name}")
+        };
+        let meta = entry.metadata().map_err(SyncError::Io)?;
+
+        if meta.is_dir() {
+            // Excluded directories are not descended into.
+            if !options
+                .exclude
+                .iter()
+                .any(|pattern| pattern_matches(pattern, &path))
+            {
+                walk(&entry.path(), &path, options, files)?;
+            }
+        } else if meta.is_file() && options.is_selected(&path) {
+            let mtime = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs());
+            files.push(LocalFile {
+                path,
+                size: meta.len(),
+                mtime,
+                mode: file_mode(&meta),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn file_mode(meta: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+const fn file_mode(_meta: &fs::Metadata) -> u32 {
+    0o644
+}
+
+/// SHA-256 of a file, read in chunks.
+async fn sha256_file(path: &Path) -> Result<String, SyncError> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut context = aws_lc_rs::digest::Context::new(&aws_lc_rs::digest::SHA256);
+    let mut chunk = vec![0_u8; CHUNK_BYTES];
+    loop {
+        let n = file.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        context.update(&chunk[..n]);
+    }
+    Ok(context
+        .finish()
+        .as_ref()
+        .iter()
+        .fold(String::new(), |mut hex, b| {
+            let _ = fmt::Write::write_fmt(&mut hex, format_args!("{b:02x}"));
+            hex
+        }))
+}
+
+/// Match `path` against a sync pattern (see `SyncOptions::exclude`).
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let pattern = pattern.trim_end_matches('/');
+    if let Some(anchored) = pattern.strip_prefix('/') {
+        return prefixes(path).any(|p| glob_matches(anchored.as_bytes(), p.as_bytes()));
+    }
+    if pattern.contains('/') {
+        prefixes(path).any(|p| glob_matches(pattern.as_bytes(), p.as_bytes()))
+    } else {
+        path.split('/')
+            .any(|component| glob_matches(pattern.as_bytes(), component.as_bytes()))
+    }
+}
+
+/// `a`, `a/b`, `a/b/c` for `a/b/c` (a directory match covers what is below it).
+fn prefixes(path: &str) -> impl Iterator<Item = &str> {
+    path.match_indices('/')
+        .map(|(i, _)| &path[..i])
+        .chain(std::iter::once(path))
+}
+
+/// Glob match: `**` spans `/`, `*` and `?` do not.
+fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', rest @ ..] => {
+            let rest = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len()).any(|i| glob_matches(rest, &text[i..]))
+        }
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob_matches(rest, &text[i..])),
+        [b'?', rest @ ..] => {
+            text.first().is_some_and(|c| *c != b'/') && glob_matches(rest, &text[1..])
+        }
+        [c, rest @ ..] => text.first() == Some(c) && glob_matches(rest, &text[1..]),
+    }
+}
+
+// ============================================================================
+// Remote side
+// ============================================================================
+
+struct SshTarget<'a> {
+    host: &'a str,
+    port: u16,
+    options: &'a SyncOptions,
+}
+
+impl SshTarget<'_> {
+    fn spawn_error(&self, e: io::Error) -> SyncError {
+        SyncError::Ssh(SshError::Spawn {
+            program: self.options.login.program.clone(),
+            source: e,
+        })
+    }
+
+    async fn run(&self, remote_command: &str) -> Result<String, SyncError> {
+        let output = self
+            .options
+            .login
+            .command(self.host, self.port, remote_command)
+            .output()
+            .await
+            .map_err(|e| self.spawn_error(e))?;
+        if !output.status.success() {
+            return Err(SyncError::Remote {
+                command: remote_command.to_string(),
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Files under the remote directory (none if it does not exist yet).
+    async fn remote_manifest(&self) -> Result<HashMap<String, RemoteFile>, SyncError> {
+        let dir = shell_quote(&self.options.remote_dir);
+        let listing = self
+            .run(&format!(
+                "cd {dir} 2>/dev/null || exit 0; find . -type f -printf '%s %T@ %P\\n'"
+            ))
+            .await?;
+        let mut files: HashMap<String, RemoteFile> = listing
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, ' ');
+                let size = fields.next()?.parse().ok()?;
+                let mtime = fields.next()?.split('.').next()?.parse().ok()?;
+                let path = fields.next()?.to_string();
+                Some((
+                    path,
+                    RemoteFile {
+                        size,
+                        mtime,
+                        sha256: None,
+                    },
+                ))
+            })
+            .collect();
+
+        if self.options.compare == SyncCompare::Checksum && !files.is_empty() {
+            let sums = self
+                .run(&format!(
+                    "cd {dir} && find . -type f -exec sha256sum {{}} +"
+                ))
+                .await?;
+            for line in sums.lines() {
+                if let Some((hash, path)) = line.split_once("  ")
+                    && let Some(file) = files.get_mut(path.trim_start_matches("./"))
+                {
+                    file.sha256 = Some(hash.to_string());
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Stream `files` as a tar archive into the remote directory.
+    async fn upload(&self, root: &Path, files: &[&LocalFile]) -> Result<(), SyncError> {
+        let dir = shell_quote(&self.options.remote_dir);
+        let remote_command = format!("mkdir -p {dir} && tar -x -f - -C {dir}");
+        let mut child = self
+            .options
+            .login
+            .command(self.host, self.port, &remote_command)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| self.spawn_error(e))?;
+
+        let mut stdin = child.stdin.take().ok_or_else(|| {
+            SyncError::Io(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "ssh stdin unavailable",
+            ))
+        })?;
+        let written = async {
+            for file in files {
+                let local = tokio::fs::File::open(root.join(&file.path)).await?;
+                let size = local.metadata().await?.len();
+                stdin.write_all(&tar_header(file, size)).await?;
+                // A file shrinking meanwhile is zero-filled to its announced size.
+                let sent = tokio::io::copy(&mut local.take(size), &mut stdin).await?;
+                let missing = usize::try_from(size - sent).unwrap_or(0) + padding(size);
+                stdin.write_all(&vec![0_u8; missing]).await?;
+            }
+            // End of archive: two zero blocks.
+            stdin.write_all(&[0_u8; 1024]).await?;
+            stdin.shutdown().await?;
+            Ok::<(), SyncError>(())
+        }
+        .await;
+        drop(stdin);
+
+        let output = child.wait_with_output().await.map_err(SyncError::Io)?;
+        if !output.status.success() {
+            return Err(SyncError::Remote {
+                command: remote_command,
+                stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            });
+        }
+        written
+    }
+
+    async fn delete(&self, paths: &[String]) -> Result<(), SyncError> {
+        let dir = shell_quote(&self.options.remote_dir);
+        for chunk in paths.chunks(200) {
+            let quoted: Vec<String> = chunk.iter().map(|p| shell_quote(p)).collect();
+            self.run(&format!("cd {dir} && rm -f -- {}", quoted.join(" ")))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Tar (ustar, with GNU long names)
+// ============================================================================
+
+/// Header block(s) of a regular file entry.
+fn tar_header(file: &LocalFile, size: u64) -> Vec<u8> {
+    let name = file.path.as_bytes();
+    let mut out = Vec::with_capacity(1536);
+    if name.len() > 100 {
+        // GNU long name: a `././@LongLink` entry whose data is the name.
+        let mut long = header_block(b"././@LongLink", 0o644, name.len() as u64 + 1, 0, b'L');
+        out.append(&mut long);
+        out.extend_from_slice(name);
+        out.push(0);
+        out.extend(std::iter::repeat_n(0, padding(name.len() as u64 + 1)));
+    }
+    out.append(&mut header_block(
+        &name[..name.len().min(100)],
+        file.mode,
+        size,
+        file.mtime,
+        b'0',
+    ));
+    out
+}
+
+fn header_block(name: &[u8], mode: u32, size: u64, mtime: u64, kind: u8) -> Vec<u8> {
+    let mut block = vec![0_u8; 512];
+    block[..name.len()].copy_from_slice(name);
+    write_octal(&mut block[100..108], u64::from(mode));
+    write_octal(&mut block[108..116], 0);
+    write_octal(&mut block[116..124], 0);
+    write_octal(&mut block[124..136], size);
+    write_octal(&mut block[136..148], mtime);
+    block[156] = kind;
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+
+    // Checksum: computed with its own field filled with spaces.
+    block[148..156].copy_from_slice(b"        ");
+    let sum: u64 = block.iter().map(|b| u64::from(*b)).sum();
+    write_octal(&mut block[148..155], sum);
+    block
+}
+
+/// Zero-padded octal, NUL-terminated, filling `field`.
+fn write_octal(field: &mut [u8], value: u64) {
+    let width = field.len() - 1;
+    let digits = format!("{value:0width$o}");
+    let digits = digits.as_bytes();
+    let start = digits.len().saturating_sub(width);
+    field[..width].copy_from_slice(&digits[start..]);
+    field[width] = 0;
+}
+
+/// Zero bytes completing `len` bytes to a 512-byte block.
+fn padding(len: u64) -> usize {
+    usize::try_from((512 - len % 512) % 512).unwrap_or(0)
+}
+
+// ============================================================================
+// Error type
+// ============================================================================
+
+/// Error type for directory synchronization.
+#[derive(Debug)]
+pub enum SyncError {
+    /// Invalid environment variable value.
+    InvalidEnv {
+        /// The environment variable key.
+        key: &'static str,
+        /// The reason for invalidity.
+        reason: &'static str,
+    },
+    /// The pod has no SSH port (22) mapped.
+    NoSshEndpoint(String),
+    /// Local file error.
+    Io(io::Error),
+    /// A local directory could not be listed.
+    Dir {
+        /// The directory.
+        path: PathBuf,
+        /// The underlying error.
+        source: io::Error,
+    },
+    /// SSH setup error.
+    Ssh(SshError),
+    /// A remote command failed.
+    Remote {
+        /// The remote command.
+        command: String,
+        /// Its standard error.
+        stderr: String,
+    },
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
+            Self::NoSshEndpoint(id) => write!(f, "pod {id} has no ssh endpoint (port 22)"),
+            Self::Io(_) => write!(f, "io error"),
+            Self::Dir { path, .. } => write!(f, "cannot list {}", path.display()),
+            Self::Ssh(e) => write!(f, "{e}"),
+            Self::Remote { command, stderr } => {
+                write!(f, "remote command failed ({command}): {stderr}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SyncError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) | Self::Dir { source: e, .. } => Some(e),
+            Self::Ssh(e) => std::error::Error::source(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SyncError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+// ============================================================================
+// Helper functions
+// ============================================================================
+
+fn parse_bool_env(key: &'static str, default: bool) -> Result<bool, SyncError> {
+    runpod_env::bool_var(key, default).map_err(|reason| SyncError::InvalidEnv { key, reason })
+}
+
+fn split_csv_env(key: &'static str) -> Vec<String> {
+    env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
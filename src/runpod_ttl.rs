@@ -0,0 +1,546 @@
+//! Lease expiry.
+//!
+//! Unique responsibility: record until when each leased pod is wanted, let
+//! the client renew that deadline (heartbeat), and list the pods whose
+//! deadline passed so they can be terminated.
+//!
+//! A lease granted with a TTL expires unless renewed within the TTL. The
+//! registry lives in a file next to the pod state file (in memory with
+//! `RUNPOD_STATE_BACKEND=memory`), so a companion process sharing the state
+//! directory can reap pods of a client that died without cleaning up. The
+//! `sqlite`, `redis` and `s3` backends only hold the pod state: the registry
+//! then stays in the file.
+//!
+//! ```ignore
+//! let registry = Arc::new(LeaseRegistry::from_env()?);
+//! let orchestrator = RunpodOrchestrator::new(cfg)?.with_lease_registry(registry);
+//!
+//! let pod = orchestrator.ensure_ready_pod().await?; // granted, expires_ms set
+//! let _heartbeat = orchestrator.start_heartbeat(&pod);
+//!
+//! // In the companion process:
+//! orchestrator.run_lease_reaper(Duration::from_secs(60)).await;
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use tokio::task::JoinHandle;
+
+use crate::runpod_env::{self, EnvFileError};
+use crate::runpod_log::{ErrorChain, log_debug, log_warn};
+use crate::runpod_state::{self, JsonFileStateStore, StateStoreError, now_unix_ms};
+
+/// Lease file format version.
+const LEASE_FORMAT_VERSION: u32 = 1;
+
+/// Default lease TTL (five minutes).
+pub const DEFAULT_LEASE_TTL_MS: u64 = 5 * 60 * 1000;
+
+/// One granted lease.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaseRecord {
+    /// Leased pod ID.
+    pub pod_id: String,
+    /// Leased pod name.
+    pub pod_name: String,
+    /// Who holds the lease (process ID and grant time).
+    pub holder: String,
+    /// TTL the lease is renewed by.
+    pub ttl_ms: u64,
+    /// Last grant or renewal (ms since UNIX epoch).
+    pub renewed_ms: u64,
+    /// Deadline after which the pod may be terminated (ms since UNIX epoch).
+    pub expires_ms: u64,
+}
+
+impl LeaseRecord {
+    /// Whether the deadline passed at `now_ms`.
+    #[must_use]
+    pub const fn is_expired(&self, now_ms: u64) -> bool {
+        now_ms >= self.expires_ms
+    }
+}
+
+/// Every granted lease, as persisted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaseBook {
+    /// File format version.
+    pub format_version: u32,
+    /// Leases by pod.
+    pub leases: Vec<LeaseRecord>,
+}
+
+impl Default for LeaseBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LeaseBook {
+    /// Create an empty book.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            format_version: LEASE_FORMAT_VERSION,
+            leases: Vec::new(),
+        }
+    }
+
+    /// The lease of `pod_id`, if granted.
+    #[must_use]
+    pub fn get(&self, pod_id: &str) -> Option<&LeaseRecord> {
+        self.leases.iter().find(|l| l.pod_id == pod_id)
+    }
+
+    /// Grant (or re-grant) a lease on `pod_id` expiring `ttl_ms` after `now_ms`.
+    pub fn grant(&mut self, pod_id: &str, pod_name: &str, holder: &str, ttl_ms: u64, now_ms: u64) {
+        let record = LeaseRecord {
+            pod_id: pod_id.to_string(),
+            pod_name: pod_name.to_string(),
+            holder: holder.to_string(),
+            ttl_ms,
+            renewed_ms: now_ms,
+            expires_ms: now_ms.saturating_add(ttl_ms),
+        };
+        match self.leases.iter_mut().find(|l| l.pod_id == pod_id) {
+            Some(existing) => *existing = record,
+            None => self.leases.push(record),
+        }
+    }
+
+    /// Push the deadline of `pod_id` to `ttl_ms` after `now_ms`.
+    ///
+    /// Returns the new deadline, or `None` if the pod has no lease (it was
+    /// released or reaped).
+    pub fn renew(&mut self, pod_id: &str, now_ms: u64) -> Option<u64> {
+        let lease = self.leases.iter_mut().find(|l| l.pod_id == pod_id)?;
+        lease.renewed_ms = now_ms;
+        lease.expires_ms = now_ms.saturating_add(lease.ttl_ms);
+        Some(lease.expires_ms)
+    }
+
+    /// Drop the lease of `pod_id`. Returns whether there was one.
+    pub fn release(&mut self, pod_id: &str) -> bool {
+        let before = self.leases.len();
+        self.leases.retain(|l| l.pod_id != pod_id);
+        self.leases.len() != before
+    }
+
+    /// Drop the lease `expired` if it is still the same expired lease at
+    /// `now_ms` (not renewed nor granted again). Returns whether it was
+    /// dropped: the pod may then be terminated.
+    pub fn claim_expired(&mut self, expired: &LeaseRecord, now_ms: u64) -> bool {
+        let claimed = self
+            .get(&expired.pod_id)
+            .is_some_and(|lease| lease == expired && lease.is_expired(now_ms));
+        if claimed {
+            self.release(&expired.pod_id);
+        }
+        claimed
+    }
+
+    /// Put back a claimed lease, unless its pod was leased again since.
+    pub fn restore(&mut self, record: LeaseRecord) {
+        if self.get(&record.pod_id).is_none() {
+            self.leases.push(record);
+        }
+    }
+
+    /// Leases whose deadline passed at `now_ms`.
+    #[must_use]
+    pub fn expired(&self, now_ms: u64) -> Vec<LeaseRecord> {
+        self.leases
+            .iter()
+            .filter(|l| l.is_expired(now_ms))
+            .cloned()
+            .collect()
+    }
+
+    /// Load a lease file (`None` if it does not exist).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, parsed, or has an
+    /// unsupported format version.
+    pub fn load(path: &Path) -> Result<Option<Self>, StateStoreError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let book: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if book.format_version != LEASE_FORMAT_VERSION {
+            return Err(StateStoreError::InvalidState(
+                "unsupported lease format version",
+            ));
+        }
+        Ok(Some(book))
+    }
+
+    /// Save the book to `path` (written to a temporary file, then renamed
+    /// over it).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self, path: &Path) -> Result<(), StateStoreError> {
+        runpod_state::write_atomic(path, &serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+/// Shared, persisted lease book.
+///
+/// Every change reloads the file, applies itself and saves the book back
+/// under the file's lock, so a client and a reaper process sharing the state
+/// directory see one book and never lose each other's changes.
+#[derive(Debug)]
+pub struct LeaseRegistry {
+    book: Mutex<LeaseBook>,
+    path: Option<PathBuf>,
+    ttl_ms: u64,
+}
+
+impl LeaseRegistry {
+    /// Create a registry that keeps its book in memory only.
+    #[must_use]
+    pub const fn in_memory(ttl_ms: u64) -> Self {
+        Self {
+            book: Mutex::new(LeaseBook::new()),
+            path: None,
+            ttl_ms,
+        }
+    }
+
+    /// Open (or start) the lease file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an existing file cannot be loaded.
+    pub fn open(path: impl Into<PathBuf>, ttl_ms: u64) -> Result<Self, StateStoreError> {
+        let path = path.into();
+        let book = LeaseBook::load(&path)?.unwrap_or_else(LeaseBook::new);
+        Ok(Self {
+            book: Mutex::new(book),
+            path: Some(path),
+            ttl_ms,
+        })
+    }
+
+    /// Create a registry on the state backend selected by configuration.
+    ///
+    /// Env: `RUNPOD_STATE_BACKEND` (default: "json"): "json" stores the
+    /// book at `default_path()`, "memory" keeps it in memory. "sqlite",
+    /// "redis" and "s3" also store it at `default_path()`, with a warning.
+    /// Env: `RUNPOD_LEASE_TTL_MS` (default: 300000)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the env-file or a variable is invalid, the
+    /// backend is unsupported, or the lease file cannot be loaded.
+    pub fn from_env() -> Result<Self, LeaseTtlError> {
+        runpod_env::load_dotenv().map_err(LeaseTtlError::EnvFile)?;
+
+        let ttl_ms =
+            env::var("RUNPOD_LEASE_TTL_MS").map_or(Ok(DEFAULT_LEASE_TTL_MS), |v| {
+                match v.trim().parse::<u64>() {
+                    Ok(ttl) if ttl > 0 => Ok(ttl),
+                    _ => Err(LeaseTtlError::InvalidEnv {
+                        key: "RUNPOD_LEASE_TTL_MS",
+                        reason: "expected a positive integer",
+                    }),
+                }
+            })?;
+
+        let backend = env::var("RUNPOD_STATE_BACKEND").unwrap_or_else(|_| "json".to_string());
+        match backend.trim().to_lowercase().as_str() {
+            "" | "json" => Ok(Self::open(Self::default_path(), ttl_ms)?),
+            "memory" => Ok(Self::in_memory(ttl_ms)),
+            other @ ("sqlite" | "redis" | "s3") => {
+                let path = Self::default_path();
+                log_warn!(
+                    "state backend {other} does not hold leases: using {}",
+                    path.display()
+                );
+                Ok(Self::open(path, ttl_ms)?)
+            }
+            other => Err(StateStoreError::UnsupportedBackend(other.to_string()).into()),
+        }
+    }
+
+    /// Lease file next to the pod state file.
+    ///
+    /// `.runpod_state.json` gives `.runpod_state.leases.json`; the state
+    /// profile (`RUNPOD_STATE_PROFILE`) is kept in the name.
+    #[must_use]
+    pub fn default_path() -> PathBuf {
+        JsonFileStateStore::profile_path(&JsonFileStateStore::default_path(), "leases")
+    }
+
+    /// Path of the lease file (`None` in memory).
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// TTL of the leases granted by this registry.
+    #[must_use]
+    pub const fn ttl_ms(&self) -> u64 {
+        self.ttl_ms
+    }
+
+    /// Grant a lease on a pod; returns its deadline.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease file cannot be reloaded or saved.
+    pub fn grant(&self, pod_id: &str, pod_name: &str, now_ms: u64) -> Result<u64, StateStoreError> {
+        let holder = format!("{}-{now_ms}", std::process::id());
+        self.update(|book| {
+            book.grant(pod_id, pod_name, &holder, self.ttl_ms, now_ms);
+            now_ms.saturating_add(self.ttl_ms)
+        })
+    }
+
+    /// Renew the lease of a pod; returns its new deadline, or `None` if the
+    /// pod has no lease anymore.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease file cannot be reloaded or saved.
+    pub fn renew(&self, pod_id: &str, now_ms: u64) -> Result<Option<u64>, StateStoreError> {
+        self.update(|book| book.renew(pod_id, now_ms))
+    }
+
+    /// Drop the lease of a pod (it will not be reaped). Returns whether there
+    /// was one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease file cannot be reloaded or saved.
+    pub fn release(&self, pod_id: &str) -> Result<bool, StateStoreError> {
+        self.update(|book| book.release(pod_id))
+    }
+
+    /// Drop the lease `expired` if it is still the same expired lease (see
+    /// `LeaseBook::claim_expired()`). Returns whether it was dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease file cannot be reloaded or saved.
+    pub fn claim_expired(
+        &self,
+        expired: &LeaseRecord,
+        now_ms: u64,
+    ) -> Result<bool, StateStoreError> {
+        self.update(|book| book.claim_expired(expired, now_ms))
+    }
+
+    /// Put back a claimed lease, unless its pod was leased again since.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease file cannot be reloaded or saved.
+    pub fn restore(&self, record: LeaseRecord) -> Result<(), StateStoreError> {
+        self.update(|book| book.restore(record))
+    }
+
+    /// Leases whose deadline passed at `now_ms` (reloaded from the file).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease file cannot be reloaded.
+    pub fn expired(&self, now_ms: u64) -> Result<Vec<LeaseRecord>, StateStoreError> {
+        Ok(self.reload()?.expired(now_ms))
+    }
+
+    /// Leases still running at `now_ms` (reloaded from the file).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease file cannot be reloaded.
+    pub fn active(&self, now_ms: u64) -> Result<Vec<LeaseRecord>, StateStoreError> {
+        let mut book = self.reload()?;
+        book.leases.retain(|lease| !lease.is_expired(now_ms));
+        Ok(book.leases)
+    }
+
+    /// Copy of the current book.
+    #[must_use]
+    pub fn snapshot(&self) -> LeaseBook {
+        self.lock().clone()
+    }
+
+    /// Reload the book from the file and return a copy of it.
+    fn reload(&self) -> Result<LeaseBook, StateStoreError> {
+        let mut book = self.lock();
+        if let Some(path) = &self.path
+            && let Some(stored) = LeaseBook::load(path)?
+        {
+            *book = stored;
+        }
+        Ok(book.clone())
+    }
+
+    /// Reload the book, apply `change` and save it back, holding the lock of
+    /// the file throughout.
+    fn update<T>(&self, change: impl FnOnce(&mut LeaseBook) -> T) -> Result<T, StateStoreError> {
+        let mut book = self.lock();
+        let Some(path) = &self.path else {
+            return Ok(change(&mut book));
+        };
+        runpod_state::with_file_lock(path, || {
+            if let Some(stored) = LeaseBook::load(path)? {
+                *book = stored;
+            }
+            let result = change(&mut book);
+            book.save(path)?;
+            Ok(result)
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LeaseBook> {
+        self.book.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Run a (blocking) registry call on Tokio's blocking thread pool.
+///
+/// # Errors
+///
+/// Returns the call's error, or `StateStoreError::Io` if the call panicked.
+pub(crate) async fn run_blocking<T: Send + 'static>(
+    registry: &Arc<LeaseRegistry>,
+    call: impl FnOnce(&LeaseRegistry) -> Result<T, StateStoreError> + Send + 'static,
+) -> Result<T, StateStoreError> {
+    let registry = Arc::clone(registry);
+    tokio::task::spawn_blocking(move || call(&registry))
+        .await
+        .map_err(|e| StateStoreError::Io(io::Error::other(format!("lease call panicked: {e}"))))?
+}
+
+// ============================================================================
+// Heartbeat
+// ============================================================================
+
+/// Background renewal of one lease, every third of its TTL.
+///
+/// The renewals stop when the heartbeat is dropped (or `stop()` is called),
+/// and on their own once the lease is gone (released or reaped).
+pub struct LeaseHeartbeat {
+    pod_id: String,
+    task: JoinHandle<()>,
+}
+
+impl LeaseHeartbeat {
+    /// Start renewing the lease of `pod_id` in `registry`.
+    ///
+    /// Must be called inside a Tokio runtime.
+    #[must_use]
+    pub fn start(registry: Arc<LeaseRegistry>, pod_id: &str) -> Self {
+        let interval = Duration::from_millis((registry.ttl_ms() / 3).max(1));
+        let id = pod_id.to_string();
+        let task = tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let renewed = id.clone();
+                match run_blocking(&registry, move |r| r.renew(&renewed, now_unix_ms())).await {
+                    Ok(Some(expires_ms)) => {
+                        log_debug!("lease of pod {id} renewed until {expires_ms}");
+                    }
+                    Ok(None) => {
+                        log_warn!("lease of pod {id} is gone, heartbeat stopped");
+                        return;
+                    }
+                    Err(e) => {
+                        log_warn!("renewing lease of pod {id} failed: {e}", e = ErrorChain(&e));
+                    }
+                }
+            }
+        });
+        Self {
+            pod_id: pod_id.to_string(),
+            task,
+        }
+    }
+
+    /// Whether renewals are still running.
+    #[must_use]
+    pub fn is_running(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    /// Stop renewing (the lease then expires after its TTL).
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for LeaseHeartbeat {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl fmt::Debug for LeaseHeartbeat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeaseHeartbeat")
+            .field("pod_id", &self.pod_id)
+            .field("running", &self.is_running())
+            .finish_non_exhaustive()
+    }
+}
+
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// Errors for lease expiry configuration.
+#[derive(Debug)]
+pub enum LeaseTtlError {
+    /// Invalid environment variable value.
+    InvalidEnv {
+        /// Variable name.
+        key: &'static str,
+        /// Reason it is invalid.
+        reason: &'static str,
+    },
+    /// The env-file could not be loaded.
+    EnvFile(EnvFileError),
+    /// The lease file could not be loaded.
+    State(StateStoreError),
+}
+
+impl fmt::Display for LeaseTtlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEnv { key, reason } => write!(f, "invalid env var {key}: {reason}"),
+            Self::EnvFile(e) => write!(f, "{e}"),
+            Self::State(_) => write!(f, "lease registry error"),
+        }
+    }
+}
+
+impl std::error::Error for LeaseTtlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::EnvFile(e) => std::error::Error::source(e),
+            Self::State(e) => Some(e),
+            Self::InvalidEnv { .. } => None,
+        }
+    }
+}
+
+impl From<StateStoreError> for LeaseTtlError {
+    fn from(value: StateStoreError) -> Self {
+        Self::State(value)
+    }
+}
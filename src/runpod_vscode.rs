@@ -0,0 +1,243 @@
+//! VS Code Remote-SSH export.
+//!
+//! Unique responsibility: write the files VS Code needs to attach to a leased pod.
+//!
+//! Artifacts:
+//! - An `~/.ssh/config` host entry for the pod, kept in a marked block that is
+//!   replaced (not duplicated) on every export
+//! - Optionally, a `.devcontainer/devcontainer.json` whose Docker host is the
+//!   same SSH alias
+//!
+//! ```ignore
+//! let pod = orchestrator.ensure_ready_pod().await?;
+//! let artifacts = VsCodeExport::new("my-pod").write(&pod)?;
+//! // Then: code --remote ssh-remote+my-pod /workspace
+//! println!("{}", artifacts.open_command);
+//! ```
+
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::runpod_orchestrator::PodLease;
+use crate::runpod_ssh::KnownHosts;
+
+/// Options for exporting a lease to VS Code.
+#[derive(Debug, Clone)]
+pub struct VsCodeExport {
+    /// SSH host alias (`Host` line, and `ssh-remote+<alias>` in VS Code).
+    pub host_alias: String,
+    /// Remote user.
+    pub user: String,
+    /// Private key to use (`IdentityFile`), if any.
+    pub identity_file: Option<PathBuf>,
+    /// SSH config file to update (default: `~/.ssh/config`).
+    pub ssh_config_path: Option<PathBuf>,
+    /// Managed `known_hosts` file and policy for host checking (default:
+    /// `KnownHosts::managed()`). `None` disables host checking: an explicit,
+    /// insecure opt-in.
+    pub known_hosts: Option<KnownHosts>,
+    /// Folder to open on the pod.
+    pub remote_folder: String,
+    /// Directory in which to write `devcontainer.json` (e.g. `.devcontainer`).
+    /// `None` skips the devcontainer snippet.
+    pub devcontainer_dir: Option<PathBuf>,
+}
+
+/// Files written (and command to run) by `VsCodeExport::write()`.
+#[derive(Debug, Clone)]
+pub struct VsCodeArtifacts {
+    /// SSH config file that was updated.
+    pub ssh_config_path: PathBuf,
+    /// `devcontainer.json` that was written, if requested.
+    pub devcontainer_path: Option<PathBuf>,
+    /// Command opening the remote folder in VS Code.
+    pub open_command: String,
+}
+
+impl VsCodeExport {
+    /// Create export options with defaults (`root`, `/workspace`, `~/.ssh/config`,
+    /// host checking through `KnownHosts::managed()`).
+    #[must_use]
+    pub fn new(host_alias: impl Into<String>) -> Self {
+        Self {
+            host_alias: host_alias.into(),
+            user: "root".to_string(),
+            identity_file: None,
+            ssh_config_path: None,
+            known_hosts: Some(KnownHosts::managed()),
+            remote_folder: "/workspace".to_string(),
+            devcontainer_dir: None,
+        }
+    }
+
+    /// Render the SSH config host entry for `lease`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease has no SSH port (22) mapped.
+    pub fn ssh_config_entry(&self, lease: &PodLease) -> Result<String, VsCodeError> {
+        let (host, port) = lease
+            .ssh_endpoint()
+            .ok_or_else(|| VsCodeError::NoSshEndpoint(lease.id.clone()))?;
+
+        let identity = self
+            .identity_file
+            .as_ref()
+            .map(|key| format!("    IdentityFile {}\n", key.display()))
+            .unwrap_or_default();
+
+        // Pod IPs and host keys change on every (re)creation: host checking
+        // is only off when the caller opted out of a managed known_hosts file.
+        let host_checking = self.known_hosts.as_ref().map_or_else(
+            || "StrictHostKeyChecking no\n    UserKnownHostsFile /dev/null\n".to_string(),
+            |known_hosts| {
+                format!(
+                    "StrictHostKeyChecking {}\n    UserKnownHostsFile {}\n",
+                    known_hosts.policy().ssh_option(),
+                    known_hosts.path().display()
+                )
+            },
+        );
+        Ok(format!(
+            "Host {}\n    HostName {host}\n    Port {port}\n    User {}\n{identity}    {host_checking}",
+            self.host_alias, self.user
+        ))
+    }
+
+    /// Render a `devcontainer.json` that uses the pod as its Docker host.
+    #[must_use]
+    pub fn devcontainer_json(&self, lease: &PodLease) -> serde_json::Value {
+        serde_json::json!({
+            "name": lease.name,
+            "workspaceFolder": self.remote_folder,
+            "remoteUser": self.user,
+            "customizations": {
+                "vscode": {
+                    "settings": {
+                        "docker.host": format!("ssh://{}", self.host_alias)
+                    }
+                }
+            }
+        })
+    }
+
+    /// Command opening the remote folder in VS Code.
+    #[must_use]
+    pub fn open_command(&self) -> String {
+        format!(
+            "code --remote ssh-remote+{} {}",
+            self.host_alias, self.remote_folder
+        )
+    }
+
+    /// Write the SSH config entry (and the devcontainer snippet, if requested).
+    ///
+    /// Re-exporting the same alias replaces its previous entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lease has no SSH endpoint, the home directory
+    /// cannot be determined, or a file cannot be read or written.
+    pub fn write(&self, lease: &PodLease) -> Result<VsCodeArtifacts, VsCodeError> {
+        let entry = self.ssh_config_entry(lease)?;
+        let ssh_config_path = match &self.ssh_config_path {
+            Some(p) => p.clone(),
+            None => default_ssh_config_path()?,
+        };
+
+        let existing = match fs::read_to_string(&ssh_config_path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(VsCodeError::Io(e)),
+        };
+        let updated = upsert_block(&existing, &self.host_alias, &entry);
+        write_file(&ssh_config_path, &updated)?;
+
+        let devcontainer_path = match &self.devcontainer_dir {
+            Some(dir) => {
+                let path = dir.join("devcontainer.json");
+                let json = serde_json::to_string_pretty(&self.devcontainer_json(lease))
+                    .map_err(|e| VsCodeError::Io(e.into()))?;
+                write_file(&path, &json)?;
+                Some(path)
+            }
+            None => None,
+        };
+
+        Ok(VsCodeArtifacts {
+            ssh_config_path,
+            devcontainer_path,
+            open_command: self.open_command(),
+        })
+    }
+}
+
+/// Replace (or append) the marked block for `alias` in an SSH config.
+fn upsert_block(config: &str, alias: &str, entry: &str) -> String {
+    let begin = format!("# >>> halldyll {alias} >>>");
+    let end = format!("# <<< halldyll {alias} <<<");
+    let block = format!("{begin}\n{entry}{end}\n");
+
+    if let (Some(start), Some(stop)) = (config.find(&begin), config.find(&end))
+        && start < stop
+    {
+        let after = config[stop + end.len()..].trim_start_matches('\n');
+        return format!("{}{block}{after}", &config[..start]);
+    }
+
+    if config.is_empty() {
+        block
+    } else if config.ends_with('\n') {
+        format!("{config}\n{block}")
+    } else {
+        format!("{config}\n\n{block}")
+    }
+}
+
+fn default_ssh_config_path() -> Result<PathBuf, VsCodeError> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".ssh").join("config"))
+        .ok_or(VsCodeError::NoHomeDir)
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), VsCodeError> {
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent).map_err(VsCodeError::Io)?;
+    }
+    fs::write(path, contents).map_err(VsCodeError::Io)
+}
+
+/// Error type for VS Code export.
+#[derive(Debug)]
+pub enum VsCodeError {
+    /// The pod has no SSH port (22) mapped.
+    NoSshEndpoint(String),
+    /// The home directory (for `~/.ssh/config`) could not be determined.
+    NoHomeDir,
+    /// File read/write error.
+    Io(io::Error),
+}
+
+impl fmt::Display for VsCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSshEndpoint(id) => write!(f, "pod {id} has no ssh endpoint (port 22)"),
+            Self::NoHomeDir => write!(f, "cannot determine home directory"),
+            Self::Io(_) => write!(f, "io error"),
+        }
+    }
+}
+
+impl std::error::Error for VsCodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! Serverless job-completion webhooks (feature `webhook`).
+//!
+//! Unique responsibility: receive `RunPod` job-completion webhooks and turn
+//! them into a channel of typed `JobResult`s.
+//!
+//! `RunPod` POSTs the finished job (the same JSON as `/status/{jobId}`) to the
+//! URL passed at submission. Webhooks are not signed, so the receiver embeds
+//! a shared secret in the URL it hands out and rejects calls without it.
+//!
+//! The receiver is transport-agnostic: `WebhookReceiver::handle()` validates
+//! one request, and `serve()` is a minimal HTTP/1.1 listener for setups
+//! without a web framework. Behind axum, hyper or any other server, call
+//! `handle()` from the route instead.
+//!
+//! ```ignore
+//! let (receiver, mut results) = WebhookReceiver::new(Some(secret), 64);
+//! let url = receiver.webhook_url("https://hooks.example.com/runpod");
+//! tokio::spawn(runpod_webhook::serve(TcpListener::bind("0.0.0.0:8080").await?, receiver));
+//!
+//! client.submit_job("abc123", &input, Some(&url)).await?;
+//! while let Some(job) = results.recv().await {
+//!     println!("{} finished: {:?}", job.id, job.status);
+//! }
+//! ```
+
+use std::{fmt, sync::Arc, time::Duration};
+
+use reqwest::Url;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+};
+
+use crate::runpod_log::{ErrorChain, log_warn};
+use crate::runpod_serverless::JobResult;
+
+/// Query parameter carrying the shared secret.
+const TOKEN_PARAM: &str = "token";
+
+/// Largest request head accepted by `serve()`, in bytes.
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// Largest webhook body accepted by `serve()`, in bytes.
+const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+/// Time `serve()` gives a client to send its request head.
+const HEAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Time `serve()` gives a client to send its whole request, body included.
+const REQUEST_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// Pause after a failed `accept()` (e.g. out of file descriptors).
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Validates webhook calls and forwards job results to a channel.
+#[derive(Clone)]
+pub struct WebhookReceiver {
+    secret: Option<Arc<str>>,
+    tx: mpsc::Sender<JobResult>,
+}
+
+impl WebhookReceiver {
+    /// Create a receiver and the channel its job results are delivered on.
+    ///
+    /// With `secret` set, calls must carry `?token=<secret>` (see `webhook_url()`).
+    /// `buffer` bounds the results waiting to be read; when full, webhook calls
+    /// wait, and `RunPod` retries them if they time out.
+    #[must_use]
+    pub fn new(secret: Option<String>, buffer: usize) -> (Self, mpsc::Receiver<JobResult>) {
+        let (tx, rx) = mpsc::channel(buffer.max(1));
+        let receiver = Self {
+            secret: secret.map(Arc::from),
+            tx,
+        };
+        (receiver, rx)
+    }
+
+    /// The URL to pass to `submit_job()`, with the secret appended
+    /// (percent-encoded).
+    #[must_use]
+    pub fn webhook_url(&self, base: &str) -> String {
+        let Some(secret) = &self.secret else {
+            return base.to_string();
+        };
+        Url::parse(base).map_or_else(
+            |_| {
+                let sep = if base.contains('?') { '&' } else { '?' };
+                format!("{base}{sep}{TOKEN_PARAM}={secret}")
+            },
+            |mut url| {
+                url.query_pairs_mut().append_pair(TOKEN_PARAM, secret);
+                url.into()
+            },
+        )
+    }
+
+    /// Validate one webhook call and forward its job result.
+    ///
+    /// `path_and_query` is the request target (e.g. `/runpod?token=...`);
+    /// the token is percent-decoded before it is compared.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the secret is missing or wrong, the body is not a
+    /// job result, or the result channel has been dropped.
+    pub async fn handle(
+        &self,
+        path_and_query: &str,
+        body: &[u8],
+    ) -> Result<JobResult, WebhookError> {
+        if let Some(secret) = &self.secret {
+            let token = query_param(path_and_query, TOKEN_PARAM);
+            if !token.is_some_and(|t| constant_time_eq(t.as_bytes(), secret.as_bytes())) {
+                return Err(WebhookError::Unauthorized);
+            }
+        }
+
+        let job: JobResult = serde_json::from_slice(body).map_err(WebhookError::InvalidBody)?;
+        if job.id.trim().is_empty() {
+            return Err(WebhookError::MissingJobId);
+        }
+
+        self.tx
+            .send(job.clone())
+            .await
+            .map_err(|_| WebhookError::Closed)?;
+        Ok(job)
+    }
+}
+
+impl fmt::Debug for WebhookReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookReceiver")
+            .field(
+                "secret",
+                &self.secret.as_ref().map(|_| crate::runpod_http::REDACTED),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+/// Serve webhooks on `listener` until the result channel is dropped.
+///
+/// A minimal HTTP/1.1 server: one `POST` per connection, answered `200` on
+/// success, `401` for a bad secret, `400` for a bad body and `405`/`413` for
+/// other methods or oversized requests. A client gets 10 s to send its
+/// request head and 60 s for the whole request (`408` after that). Failed
+/// accepts are logged and retried. Put it behind a TLS-terminating proxy.
+///
+/// # Errors
+///
+/// Currently never fails; the `Result` is kept for listener errors that
+/// cannot be retried.
+pub async fn serve(listener: TcpListener, receiver: WebhookReceiver) -> Result<(), WebhookError> {
+    loop {
+        if receiver.tx.is_closed() {
+            return Ok(());
+        }
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                log_warn!(
+                    "webhook listener: accept failed, retrying: {e}",
+                    e = ErrorChain(&e)
+                );
+                tokio::time::sleep(ACCEPT_BACKOFF).await;
+                continue;
+            }
+        };
+        let receiver = receiver.clone();
+        tokio::spawn(async move {
+            // A broken connection only loses that call; RunPod retries it.
+            let _ = serve_connection(stream, &receiver).await;
+        });
+    }
+}
+
+async fn serve_connection(
+    mut stream: TcpStream,
+    receiver: &WebhookReceiver,
+) -> std::io::Result<()> {
+    let request = tokio::time::timeout(REQUEST_TIMEOUT, read_request(&mut stream))
+        .await
+        .unwrap_or(Ok(Err("408 Request Timeout")))?;
+    let status = match request {
+        Ok((target, body)) => match receiver.handle(&target, &body).await {
+            Ok(_) => "200 OK",
+            Err(WebhookError::Unauthorized) => "401 Unauthorized",
+            Err(WebhookError::InvalidBody(_) | WebhookError::MissingJobId) => "400 Bad Request",
+            Err(_) => "503 Service Unavailable",
+        },
+        Err(status) => status,
+    };
+
+    let response = format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Read one request; `Err(status)` for requests rejected before the body.
+async fn read_request(
+    stream: &mut TcpStream,
+) -> std::io::Result<Result<(String, Vec<u8>), &'static str>> {
+    let head_deadline = tokio::time::Instant::now() + HEAD_TIMEOUT;
+    let mut buf = Vec::with_capacity(4096);
+    let head_end = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        if buf.len() > MAX_HEAD_BYTES {
+            return Ok(Err("413 Payload Too Large"));
+        }
+        let mut chunk = [0_u8; 4096];
+        let Ok(read) = tokio::time::timeout_at(head_deadline, stream.read(&mut chunk)).await else {
+            return Ok(Err("408 Request Timeout"));
+        };
+        let n = read?;
+        if n == 0 {
+            return Ok(Err("400 Bad Request"));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (method, target) = (request_line.next(), request_line.next());
+    let Some(target) = target.filter(|_| method == Some("POST")) else {
+        return Ok(Err("405 Method Not Allowed"));
+    };
+
+    let content_length = lines
+        .filter_map(|l| l.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.trim().parse::<usize>().ok());
+    let Some(content_length) = content_length else {
+        return Ok(Err("411 Length Required"));
+    };
+    if content_length > MAX_BODY_BYTES {
+        return Ok(Err("413 Payload Too Large"));
+    }
+
+    let mut body = buf.split_off(head_end);
+    while body.len() < content_length {
+        let mut chunk = [0_u8; 8192];
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(Err("400 Bad Request"));
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(content_length);
+
+    Ok(Ok((target.to_string(), body)))
+}
+
+/// Percent-decoded value of query parameter `name` in `path_and_query`.
+fn query_param(path_and_query: &str, name: &str) -> Option<String> {
+    let url = Url::parse("http://localhost/")
+        .ok()?
+        .join(path_and_query)
+        .ok()?;
+    url.query_pairs()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.into_owned())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0_u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Error type for webhook handling.
+#[derive(Debug)]
+pub enum WebhookError {
+    /// The call did not carry the expected secret.
+    Unauthorized,
+    /// The body is not a job result.
+    InvalidBody(serde_json::Error),
+    /// The job result has an empty job id.
+    MissingJobId,
+    /// The result channel has been dropped.
+    Closed,
+    /// Listener I/O error.
+    Io(std::io::Error),
+}
+
+impl fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unauthorized => write!(f, "webhook call without a valid token"),
+            Self::InvalidBody(_) => write!(f, "invalid webhook body"),
+            Self::MissingJobId => write!(f, "invalid webhook body: empty job id"),
+            Self::Closed => write!(f, "webhook result channel closed"),
+            Self::Io(_) => write!(f, "io error"),
+        }
+    }
+}
+
+impl std::error::Error for WebhookError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidBody(e) => Some(e),
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}